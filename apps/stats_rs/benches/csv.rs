@@ -0,0 +1,35 @@
+//! CSV parsing throughput (`cargo bench --bench csv`).
+
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use stats_rs::ingest::{CsvOptions, read_csv};
+
+/// `rows` data rows of an id, two floats, an integer and a short label.
+fn payload(rows: usize) -> Vec<u8> {
+    let mut s = String::from("id,x,y,count,label\n");
+    for i in 0..rows {
+        let x = (i as f64 * 0.618_034).fract();
+        s.push_str(&format!(
+            "{i},{x:.6},{:.4},{},g{}\n",
+            x * 1e3 - 250.0,
+            i % 97,
+            i % 5
+        ));
+    }
+    s.into_bytes()
+}
+
+fn read(c: &mut Criterion) {
+    let mut g = c.benchmark_group("csv");
+    let opts = CsvOptions::default();
+    for rows in [1_000, 10_000, 100_000] {
+        let bytes = payload(rows);
+        g.throughput(Throughput::Bytes(bytes.len() as u64));
+        g.bench_with_input(BenchmarkId::new("read_csv", rows), &bytes, |b, bytes| {
+            b.iter(|| read_csv(black_box(bytes), &opts).unwrap())
+        });
+    }
+    g.finish();
+}
+
+criterion_group!(benches, read);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+//! Throughput of the statistical kernels at several input sizes.
+//!
+//! ```bash
+//! cargo bench --bench kernels                  # everything
+//! cargo bench --bench kernels -- kendall       # one group
+//! cargo bench --bench kernels -- --save-baseline main   # then --baseline main
+//! ```
+
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use stats_rs::stats::prelude::*;
+
+/// Deterministic pseudo-random values in `[0, 1)` (xorshift64).
+fn values(n: usize, seed: u64) -> Vec<f64> {
+    let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    (0..n)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        })
+        .collect()
+}
+
+fn points(n: usize, dim: usize) -> Vec<Vec<f64>> {
+    values(n * dim, 3)
+        .chunks(dim)
+        .map(<[f64]>::to_vec)
+        .collect()
+}
+
+fn quantiles(c: &mut Criterion) {
+    let mut g = c.benchmark_group("quantiles");
+    for n in [1_000, 100_000, 1_000_000] {
+        let xs = values(n, 1);
+        g.throughput(Throughput::Elements(n as u64));
+        g.bench_with_input(BenchmarkId::new("quartiles", n), &xs, |b, xs| {
+            b.iter(|| quartiles(black_box(xs)))
+        });
+        g.bench_with_input(BenchmarkId::new("median", n), &xs, |b, xs| {
+            b.iter(|| median(black_box(xs)))
+        });
+        g.bench_with_input(BenchmarkId::new("sketch", n), &xs, |b, xs| {
+            b.iter(|| {
+                let s = QuantileSketch::new(black_box(xs), SKETCH_SIZE, 0);
+                quartiles_sorted(s.sorted())
+            })
+        });
+    }
+    g.finish();
+}
+
+fn correlations(c: &mut Criterion) {
+    let mut g = c.benchmark_group("correlations");
+    for n in [1_000, 100_000] {
+        let (x, y) = (values(n, 1), values(n, 2));
+        g.throughput(Throughput::Elements(n as u64));
+        g.bench_function(BenchmarkId::new("pearson", n), |b| {
+            b.iter(|| pearson_correlation(black_box(&x), black_box(&y)))
+        });
+        g.bench_function(BenchmarkId::new("spearman", n), |b| {
+            b.iter(|| spearman_rho(black_box(&x), black_box(&y)))
+        });
+    }
+    g.finish();
+}
+
+fn kendall(c: &mut Criterion) {
+    let mut g = c.benchmark_group("kendall");
+    g.sample_size(10);
+    for n in [100, 1_000, 5_000] {
+        let (x, y) = (values(n, 1), values(n, 2));
+        g.bench_function(BenchmarkId::new("tau_b", n), |b| {
+            b.iter(|| kendall_tau_b(black_box(&x), black_box(&y)))
+        });
+    }
+    g.finish();
+}
+
+fn psi(c: &mut Criterion) {
+    let mut g = c.benchmark_group("psi");
+    for n in [1_000, 100_000] {
+        let (expected, actual) = (values(n, 1), values(n, 2));
+        g.throughput(Throughput::Elements(2 * n as u64));
+        g.bench_function(BenchmarkId::new("quantile_bins", n), |b| {
+            b.iter(|| psi_quantile_bins(black_box(&expected), black_box(&actual), 10))
+        });
+    }
+    g.finish();
+}
+
+fn cosine(c: &mut Criterion) {
+    let mut g = c.benchmark_group("cosine");
+    for dim in [384, 1536] {
+        let (a, b64) = (values(dim, 1), values(dim, 2));
+        let (a32, b32): (Vec<f32>, Vec<f32>) = (
+            a.iter().map(|&x| x as f32).collect(),
+            b64.iter().map(|&x| x as f32).collect(),
+        );
+        g.bench_function(BenchmarkId::new("similarity_f64", dim), |b| {
+            b.iter(|| cosine_similarity(black_box(&a), black_box(&b64)))
+        });
+        g.bench_function(BenchmarkId::new("similarity_f32", dim), |b| {
+            b.iter(|| cosine_similarity_f32(black_box(&a32), black_box(&b32)))
+        });
+    }
+    g.sample_size(10);
+    for n in [100, 1_000] {
+        let pts = points(n, 384);
+        g.throughput(Throughput::Elements((n * (n - 1) / 2) as u64));
+        g.bench_with_input(BenchmarkId::new("pairwise_stats", n), &pts, |b, pts| {
+            b.iter(|| pairwise_cosine_stats(black_box(pts)))
+        });
+        g.bench_with_input(BenchmarkId::new("near_duplicates", n), &pts, |b, pts| {
+            b.iter(|| near_duplicate_pairs(black_box(pts), 0.95))
+        });
+    }
+    g.finish();
+}
+
+criterion_group!(benches, quantiles, correlations, kendall, psi, cosine);
+criterion_main!(benches);
@@ -0,0 +1,17 @@
+//! Build script: compiles `proto/stats.proto` for the `grpc` feature, using a
+//! vendored `protoc` so builds need no system install, and sets the Node.js
+//! addon link flags for the `napi` feature.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "grpc")]
+    {
+        let mut config = tonic_prost_build::Config::new();
+        config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+        tonic_prost_build::configure()
+            .build_transport(false)
+            .compile_with_config(config, &["proto/stats.proto"], &["proto"])?;
+    }
+    #[cfg(feature = "napi")]
+    napi_build::setup();
+    Ok(())
+}
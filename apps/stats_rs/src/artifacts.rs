@@ -0,0 +1,317 @@
+//! # Result artifacts
+//!
+//! Large outputs — job results over `artifacts.inline_max_bytes`, reports
+//! requested with `?store=true` — are kept under an artifact id (`art_…`)
+//! and served by `GET /artifacts/{id}` with their original media type, so
+//! job status responses and webhook payloads stay small.
+//!
+//! The store is picked by [`ArtifactConfig`]:
+//!
+//! - **memory** (default): lost on restart; the oldest artifacts are dropped
+//!   beyond `artifacts.max_bytes`
+//! - **disk** (`artifacts.dir`): `{tenant}/{id}` holds the bytes and
+//!   `{tenant}/{id}.json` the [`ArtifactOut`] metadata
+//! - **S3** (`artifacts.s3_uri`, feature `s3`): the same two objects under
+//!   the URI's prefix, with the `STATS_S3_*` credentials
+//!
+//! Artifacts are filed under the [`Tenant`] that stored them and are not
+//! found by other tenants.
+//!
+//! Disk and S3 artifacts are never deleted by the service; expire them with
+//! the storage's own lifecycle rules.
+
+use crate::{
+    config::{ArtifactConfig, S3Config},
+    error::ServiceError,
+    jobs::now_secs,
+    tenant::Tenant,
+    types::ArtifactOut,
+};
+use axum::body::Bytes;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt, io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// A stored artifact and its bytes.
+#[derive(Clone, Debug)]
+pub struct Artifact {
+    pub meta: ArtifactOut,
+    pub bytes: Bytes,
+}
+
+#[derive(Default)]
+struct Memory {
+    /// Insertion order, oldest first
+    order: VecDeque<(Tenant, String)>,
+    entries: HashMap<(Tenant, String), Artifact>,
+    bytes: usize,
+}
+
+enum Backend {
+    Memory {
+        max_bytes: usize,
+        state: Mutex<Memory>,
+    },
+    Disk(PathBuf),
+    #[cfg(feature = "s3")]
+    S3 {
+        store: Box<dyn object_store::ObjectStore>,
+        prefix: object_store::path::Path,
+    },
+}
+
+/// Shared, cheaply clonable handle to the configured artifact store.
+#[derive(Clone)]
+pub struct ArtifactStore(Arc<Backend>);
+
+impl fmt::Debug for ArtifactStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let backend = match &*self.0 {
+            Backend::Memory { .. } => "memory".to_string(),
+            Backend::Disk(dir) => dir.display().to_string(),
+            #[cfg(feature = "s3")]
+            Backend::S3 { prefix, .. } => format!("s3 {prefix}"),
+        };
+        f.debug_tuple("ArtifactStore").field(&backend).finish()
+    }
+}
+
+impl Default for ArtifactStore {
+    /// In memory, with the default budget.
+    fn default() -> Self {
+        Self::memory(ArtifactConfig::default().max_bytes)
+    }
+}
+
+/// Whether `id` has the shape of an id this store hands out, so it can be
+/// used as a file or object name.
+fn is_artifact_id(id: &str) -> bool {
+    id.strip_prefix("art_")
+        .is_some_and(|hex| hex.len() == 32 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+impl ArtifactStore {
+    /// Keep artifacts in memory, dropping the oldest beyond `max_bytes`.
+    pub fn memory(max_bytes: usize) -> Self {
+        Self(Arc::new(Backend::Memory {
+            max_bytes,
+            state: Mutex::default(),
+        }))
+    }
+
+    /// Write artifacts to `dir`, creating it if needed.
+    pub async fn disk(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        tokio::fs::create_dir_all(&dir).await?;
+        Ok(Self(Arc::new(Backend::Disk(dir))))
+    }
+
+    /// Open the configured store.
+    pub async fn open(cfg: &ArtifactConfig, s3: &S3Config) -> io::Result<Self> {
+        if let Some(dir) = &cfg.dir {
+            return Self::disk(dir).await;
+        }
+        #[cfg(feature = "s3")]
+        if let Some(uri) = &cfg.s3_uri {
+            let url = reqwest::Url::parse(uri)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let bucket = url.host_str().unwrap_or_default();
+            let store = crate::ingest::s3::bucket_store(s3, bucket).map_err(io::Error::other)?;
+            let prefix = object_store::path::Path::from_url_path(url.path())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            return Ok(Self(Arc::new(Backend::S3 {
+                store: Box::new(store),
+                prefix,
+            })));
+        }
+        #[cfg(not(feature = "s3"))]
+        if cfg.s3_uri.is_some() {
+            let _ = s3;
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "artifacts.s3_uri needs the `s3` feature",
+            ));
+        }
+        Ok(Self::memory(cfg.max_bytes))
+    }
+
+    /// Store `bytes` for `tenant`, served later as `content_type`.
+    pub async fn put(
+        &self,
+        tenant: &Tenant,
+        content_type: &str,
+        bytes: Bytes,
+    ) -> Result<ArtifactOut, ServiceError> {
+        let meta = ArtifactOut {
+            id: format!("art_{}", uuid::Uuid::new_v4().simple()),
+            content_type: content_type.to_string(),
+            size: bytes.len() as u64,
+            created_at: now_secs(),
+        };
+        let meta_json =
+            serde_json::to_vec(&meta).map_err(|e| ServiceError::Internal(e.to_string()))?;
+        match &*self.0 {
+            Backend::Memory { max_bytes, state } => {
+                if bytes.len() > *max_bytes {
+                    return Err(ServiceError::TooLarge(format!(
+                        "artifact is {} bytes, the store holds {max_bytes}",
+                        bytes.len()
+                    )));
+                }
+                let mut m = state.lock().unwrap();
+                while m.bytes + bytes.len() > *max_bytes {
+                    let Some(old) = m.order.pop_front() else {
+                        break;
+                    };
+                    if let Some(a) = m.entries.remove(&old) {
+                        m.bytes -= a.bytes.len();
+                    }
+                }
+                m.bytes += bytes.len();
+                let key = (tenant.clone(), meta.id.clone());
+                m.order.push_back(key.clone());
+                let artifact = Artifact {
+                    meta: meta.clone(),
+                    bytes,
+                };
+                m.entries.insert(key, artifact);
+            }
+            Backend::Disk(dir) => {
+                let io = |e: io::Error| ServiceError::Internal(format!("artifact store: {e}"));
+                let dir = dir.join(tenant.as_str());
+                tokio::fs::create_dir_all(&dir).await.map_err(io)?;
+                tokio::fs::write(dir.join(&meta.id), &bytes)
+                    .await
+                    .map_err(io)?;
+                // Metadata last: an artifact is visible once both are written
+                tokio::fs::write(dir.join(format!("{}.json", meta.id)), meta_json)
+                    .await
+                    .map_err(io)?;
+            }
+            #[cfg(feature = "s3")]
+            Backend::S3 { store, prefix } => {
+                let upstream = |e: object_store::Error| ServiceError::Upstream(e.to_string());
+                let prefix = prefix.child(tenant.as_str());
+                store
+                    .put(&prefix.child(meta.id.as_str()), bytes.into())
+                    .await
+                    .map_err(upstream)?;
+                store
+                    .put(
+                        &prefix.child(format!("{}.json", meta.id)),
+                        Bytes::from(meta_json).into(),
+                    )
+                    .await
+                    .map_err(upstream)?;
+            }
+        }
+        Ok(meta)
+    }
+
+    /// The artifact `tenant` stored as `id`, if any.
+    pub async fn get(&self, tenant: &Tenant, id: &str) -> Result<Option<Artifact>, ServiceError> {
+        if !is_artifact_id(id) {
+            return Ok(None);
+        }
+        let parse = |meta: &[u8]| {
+            serde_json::from_slice::<ArtifactOut>(meta)
+                .map_err(|e| ServiceError::Internal(format!("artifact {id} metadata: {e}")))
+        };
+        match &*self.0 {
+            Backend::Memory { state, .. } => {
+                let key = (tenant.clone(), id.to_string());
+                Ok(state.lock().unwrap().entries.get(&key).cloned())
+            }
+            Backend::Disk(dir) => {
+                let io = |e: io::Error| ServiceError::Internal(format!("artifact store: {e}"));
+                let dir = dir.join(tenant.as_str());
+                let meta = match tokio::fs::read(dir.join(format!("{id}.json"))).await {
+                    Ok(m) => parse(&m)?,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+                    Err(e) => return Err(io(e)),
+                };
+                let bytes = tokio::fs::read(dir.join(id)).await.map_err(io)?;
+                Ok(Some(Artifact {
+                    meta,
+                    bytes: bytes.into(),
+                }))
+            }
+            #[cfg(feature = "s3")]
+            Backend::S3 { store, prefix } => {
+                let upstream = |e: object_store::Error| ServiceError::Upstream(e.to_string());
+                let prefix = prefix.child(tenant.as_str());
+                let meta = match store.get(&prefix.child(format!("{id}.json"))).await {
+                    Ok(m) => parse(&m.bytes().await.map_err(upstream)?)?,
+                    Err(object_store::Error::NotFound { .. }) => return Ok(None),
+                    Err(e) => return Err(upstream(e)),
+                };
+                let bytes = store
+                    .get(&prefix.child(id))
+                    .await
+                    .map_err(upstream)?
+                    .bytes()
+                    .await
+                    .map_err(upstream)?;
+                Ok(Some(Artifact { meta, bytes }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn memory_store_drops_the_oldest_beyond_its_budget() {
+        let (store, t) = (ArtifactStore::memory(10), Tenant::default());
+        let a = store
+            .put(&t, "text/plain", Bytes::from("abcdef"))
+            .await
+            .unwrap();
+        assert!(is_artifact_id(&a.id));
+        assert_eq!(a.size, 6);
+        let b = store
+            .put(&t, "text/plain", Bytes::from("ghijk"))
+            .await
+            .unwrap();
+        assert!(store.get(&t, &a.id).await.unwrap().is_none());
+        let got = store.get(&t, &b.id).await.unwrap().unwrap();
+        assert_eq!((got.bytes.as_ref(), got.meta), (&b"ghijk"[..], b));
+        assert!(matches!(
+            store
+                .put(&t, "text/plain", Bytes::from("x".repeat(11)))
+                .await,
+            Err(ServiceError::TooLarge(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn disk_store_round_trips_and_rejects_odd_ids() {
+        let dir = std::env::temp_dir().join(format!("stats-artifacts-{}", std::process::id()));
+        let store = ArtifactStore::disk(&dir).await.unwrap();
+        let t = Tenant::default();
+        let meta = store
+            .put(&t, "application/json", Bytes::from(r#"{"x":1}"#))
+            .await
+            .unwrap();
+        let got = store.get(&t, &meta.id).await.unwrap().unwrap();
+        assert_eq!(got.meta.content_type, "application/json");
+        assert_eq!(got.bytes.as_ref(), br#"{"x":1}"#);
+
+        assert!(store.get(&t, "../etc/passwd").await.unwrap().is_none());
+        let other = Tenant::new("team-b").unwrap();
+        assert!(store.get(&other, &meta.id).await.unwrap().is_none());
+        assert!(
+            store
+                .get(&t, "art_00000000000000000000000000000000")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
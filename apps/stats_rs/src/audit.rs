@@ -0,0 +1,545 @@
+//! # Audit trail
+//!
+//! With `audit.file` or `audit.postgres_url` set (see
+//! [`AuditConfig`](crate::config::AuditConfig)), every API request that does
+//! work — any method but `GET`, `HEAD` and `OPTIONS` — leaves an
+//! [`AuditRecord`]: who sent it (the `audit.user_header` header), the path,
+//! the dataset it names, its parameters, the status and how long it took.
+//! `GET /admin/audit` queries the trail.
+//!
+//! Parameters are the query string plus the request's JSON body with every
+//! array replaced by `"[n items]"`, so options are kept but uploaded data is
+//! not. Arrays are counted while the body is parsed, without building their
+//! elements, so a large upload costs a scan of its bytes rather than a
+//! second copy as a JSON tree. The record is written before the response leaves, so every answered
+//! request is on file; a failed write is logged and does not fail the request.
+//!
+//! - **file**: one JSON object per line, appended; queries scan the file
+//! - **Postgres** (feature `postgres`): rows in `stats_audit`, created at startup
+//!
+//! gRPC calls and WebSocket sessions are not audited.
+
+use crate::{
+    config::AuditConfig,
+    types::{AuditQuery, AuditRecord},
+};
+use axum::{
+    body::{Body, to_bytes},
+    extract::{OriginalUri, Query, Request, State},
+    http::{HeaderName, Method, Uri, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{
+    Deserialize, Deserializer,
+    de::{IgnoredAny, MapAccess, SeqAccess, Visitor},
+};
+use serde_json::{Map, Number, Value};
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    path::PathBuf,
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    sync::Mutex,
+};
+
+/// Most records one `GET /admin/audit` returns.
+pub const MAX_QUERY_LIMIT: usize = 1000;
+
+/// Longest string parameter kept verbatim; longer ones are cut.
+const MAX_PARAM_CHARS: usize = 256;
+
+#[cfg(feature = "postgres")]
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS stats_audit (
+    id          BIGSERIAL PRIMARY KEY,
+    at_ms       BIGINT NOT NULL,
+    request_id  TEXT,
+    user_id     TEXT,
+    method      TEXT NOT NULL,
+    path        TEXT NOT NULL,
+    dataset     TEXT,
+    params      JSONB NOT NULL,
+    status      INTEGER NOT NULL,
+    duration_ms DOUBLE PRECISION NOT NULL
+)";
+
+enum Store {
+    File {
+        path: PathBuf,
+        file: Mutex<File>,
+    },
+    #[cfg(feature = "postgres")]
+    Postgres(tokio_postgres::Client),
+}
+
+/// Where audit records go and how callers are identified.
+pub struct AuditLog {
+    store: Store,
+    user_header: HeaderName,
+}
+
+impl fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let store = match &self.store {
+            Store::File { path, .. } => path.display().to_string(),
+            #[cfg(feature = "postgres")]
+            Store::Postgres(_) => "postgres".into(),
+        };
+        f.debug_struct("AuditLog")
+            .field("store", &store)
+            .field("user_header", &self.user_header)
+            .finish()
+    }
+}
+
+impl AuditLog {
+    /// Open the configured store; `Ok(None)` means auditing is off.
+    pub async fn open(cfg: &AuditConfig) -> io::Result<Option<Self>> {
+        let user_header = HeaderName::try_from(cfg.user_header.as_str())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        if let Some(path) = &cfg.file {
+            return Ok(Some(Self::file(path, user_header).await?));
+        }
+        #[cfg(feature = "postgres")]
+        if let Some(url) = &cfg.postgres_url {
+            let (client, conn) = tokio_postgres::connect(url, tokio_postgres::NoTls)
+                .await
+                .map_err(io::Error::other)?;
+            tokio::spawn(async move {
+                if let Err(e) = conn.await {
+                    tracing::error!("audit database connection lost: {e}");
+                }
+            });
+            client
+                .batch_execute(CREATE_TABLE)
+                .await
+                .map_err(io::Error::other)?;
+            return Ok(Some(Self {
+                store: Store::Postgres(client),
+                user_header,
+            }));
+        }
+        #[cfg(not(feature = "postgres"))]
+        if cfg.postgres_url.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "audit.postgres_url needs the `postgres` feature",
+            ));
+        }
+        Ok(None)
+    }
+
+    /// Append records to the JSON Lines file at `path`.
+    pub async fn file(path: impl Into<PathBuf>, user_header: HeaderName) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        Ok(Self {
+            store: Store::File {
+                path,
+                file: Mutex::new(file),
+            },
+            user_header,
+        })
+    }
+
+    /// Store one record.
+    pub async fn write(&self, r: &AuditRecord) -> io::Result<()> {
+        match &self.store {
+            Store::File { file, .. } => {
+                let mut line = serde_json::to_vec(r)?;
+                line.push(b'\n');
+                let mut file = file.lock().await;
+                file.write_all(&line).await?;
+                file.flush().await
+            }
+            #[cfg(feature = "postgres")]
+            Store::Postgres(client) => client
+                .execute(
+                    "INSERT INTO stats_audit (at_ms, request_id, user_id, method, path, \
+                     dataset, params, status, duration_ms) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                    &[
+                        &(r.at_ms as i64),
+                        &r.request_id,
+                        &r.user,
+                        &r.method,
+                        &r.path,
+                        &r.dataset,
+                        &r.params,
+                        &i32::from(r.status),
+                        &r.duration_ms,
+                    ],
+                )
+                .await
+                .map(drop)
+                .map_err(io::Error::other),
+        }
+    }
+
+    /// Records matching `q`, newest first, at most `q.limit` (default 100,
+    /// capped at [`MAX_QUERY_LIMIT`]).
+    pub async fn query(&self, q: &AuditQuery) -> io::Result<Vec<AuditRecord>> {
+        let limit = q.limit.unwrap_or(100).min(MAX_QUERY_LIMIT);
+        match &self.store {
+            Store::File { path, .. } => {
+                let mut lines = BufReader::new(File::open(path).await?).lines();
+                let mut last = VecDeque::with_capacity(limit);
+                while let Some(line) = lines.next_line().await? {
+                    let Ok(r) = serde_json::from_str::<AuditRecord>(&line) else {
+                        continue;
+                    };
+                    if limit > 0 && matches(q, &r) {
+                        if last.len() == limit {
+                            last.pop_front();
+                        }
+                        last.push_back(r);
+                    }
+                }
+                Ok(last.into_iter().rev().collect())
+            }
+            #[cfg(feature = "postgres")]
+            Store::Postgres(client) => {
+                let rows = client
+                    .query(
+                        "SELECT at_ms, request_id, user_id, method, path, dataset, params, \
+                         status, duration_ms FROM stats_audit \
+                         WHERE ($1::text IS NULL OR user_id = $1) \
+                           AND ($2::text IS NULL OR starts_with(path, $2)) \
+                           AND ($3::text IS NULL OR dataset = $3) \
+                           AND ($4::bigint IS NULL OR at_ms >= $4) \
+                           AND ($5::bigint IS NULL OR at_ms < $5) \
+                         ORDER BY id DESC LIMIT $6",
+                        &[
+                            &q.user,
+                            &q.path,
+                            &q.dataset,
+                            &q.since_ms.map(|t| t as i64),
+                            &q.until_ms.map(|t| t as i64),
+                            &(limit as i64),
+                        ],
+                    )
+                    .await
+                    .map_err(io::Error::other)?;
+                Ok(rows
+                    .iter()
+                    .map(|row| AuditRecord {
+                        at_ms: row.get::<_, i64>(0) as u64,
+                        request_id: row.get(1),
+                        user: row.get(2),
+                        method: row.get(3),
+                        path: row.get(4),
+                        dataset: row.get(5),
+                        params: row.get(6),
+                        status: row.get::<_, i32>(7) as u16,
+                        duration_ms: row.get(8),
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+fn matches(q: &AuditQuery, r: &AuditRecord) -> bool {
+    q.user.as_ref().is_none_or(|u| r.user.as_ref() == Some(u))
+        && q.path
+            .as_ref()
+            .is_none_or(|p| r.path.starts_with(p.as_str()))
+        && q.dataset
+            .as_ref()
+            .is_none_or(|d| r.dataset.as_ref() == Some(d))
+        && q.since_ms.is_none_or(|t| r.at_ms >= t)
+        && q.until_ms.is_none_or(|t| r.at_ms < t)
+}
+
+/// `value` with arrays reduced to their length and long strings cut.
+pub fn redact_data(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::String(format!("[{} items]", items.len())),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k, redact_data(v)))
+                .collect(),
+        ),
+        Value::String(s) if s.chars().count() > MAX_PARAM_CHARS => Value::String(cut(&s)),
+        other => other,
+    }
+}
+
+/// The first [`MAX_PARAM_CHARS`] characters of `s`, marked as cut.
+fn cut(s: &str) -> String {
+    s.chars().take(MAX_PARAM_CHARS).chain(['…']).collect()
+}
+
+/// A JSON document deserialized straight into its [`redact_data`] form.
+struct Redacted(Value);
+
+impl<'de> Deserialize<'de> for Redacted {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        d.deserialize_any(RedactedVisitor).map(Redacted)
+    }
+}
+
+struct RedactedVisitor;
+
+impl<'de> Visitor<'de> for RedactedVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Value, E> {
+        Ok(n.into())
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Value, E> {
+        Ok(n.into())
+    }
+
+    fn visit_f64<E>(self, x: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(x).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::String(match s.chars().count() > MAX_PARAM_CHARS {
+            true => cut(s),
+            false => s.to_string(),
+        }))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        // Elements are skipped, never materialized
+        let mut n = 0usize;
+        while seq.next_element::<IgnoredAny>()?.is_some() {
+            n += 1;
+        }
+        Ok(Value::String(format!("[{n} items]")))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut out = Map::new();
+        while let Some((k, Redacted(v))) = map.next_entry::<String, Redacted>()? {
+            out.insert(k, v);
+        }
+        Ok(Value::Object(out))
+    }
+}
+
+/// The decoded query pairs merged with the (redacted) JSON body; body
+/// fields win. A non-object body is kept under `"body"`.
+fn params(uri: &Uri, body: Option<Value>) -> Value {
+    let mut out = Map::new();
+    let Query(pairs) = Query::<Vec<(String, String)>>::try_from_uri(uri).unwrap_or_default();
+    for (k, v) in pairs {
+        out.insert(k, redact_data(Value::String(v)));
+    }
+    match body.map(redact_data) {
+        Some(Value::Object(fields)) => out.extend(fields),
+        Some(other) => {
+            out.insert("body".into(), other);
+        }
+        None => {}
+    }
+    Value::Object(out)
+}
+
+/// The dataset a request names: the id in `/datasets/{id}/…`, else a
+/// `dataset_id` (or `dataset`) parameter.
+fn dataset(path: &str, params: &Value) -> Option<String> {
+    let mut segments = path.split('/');
+    if segments.any(|s| s == "datasets")
+        && let Some(id) = segments.next().filter(|s| !s.is_empty())
+    {
+        return Some(id.to_string());
+    }
+    ["dataset_id", "dataset"]
+        .iter()
+        .find_map(|k| match params.get(k)? {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+}
+
+fn header(req: &Request, name: &HeaderName) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+fn is_json(req: &Request) -> bool {
+    req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("json"))
+}
+
+/// Axum middleware recording each non-`GET` request's outcome.
+///
+/// JSON bodies are buffered (up to `limit` bytes, the buffered routes' own
+/// limit) to read their parameters; other bodies pass through untouched.
+pub async fn middleware(
+    State((log, limit)): State<(Arc<AuditLog>, usize)>,
+    OriginalUri(uri): OriginalUri,
+    req: Request,
+    next: Next,
+) -> Response {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(req).await;
+    }
+    let at_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    let started = Instant::now();
+    let user = header(&req, &log.user_header);
+    let request_id = header(&req, &crate::request_id::X_REQUEST_ID);
+    let method = req.method().to_string();
+    let path = uri.path().to_string();
+
+    let (req, body) = if is_json(&req) {
+        let (parts, body) = req.into_parts();
+        let bytes = match to_bytes(body, limit).await {
+            Ok(b) => b,
+            Err(_) => {
+                return crate::error::ServiceError::TooLarge(
+                    "request body exceeds the size limit".into(),
+                )
+                .into_response();
+            }
+        };
+        let json = serde_json::from_slice::<Redacted>(&bytes).ok().map(|r| r.0);
+        (Request::from_parts(parts, Body::from(bytes)), json)
+    } else {
+        (req, None)
+    };
+
+    let res = next.run(req).await;
+    let params = params(&uri, body);
+    let record = AuditRecord {
+        at_ms,
+        request_id,
+        user,
+        method,
+        dataset: dataset(&path, &params),
+        path,
+        params,
+        status: res.status().as_u16(),
+        duration_ms: started.elapsed().as_secs_f64() * 1e3,
+    };
+    if let Err(e) = log.write(&record).await {
+        tracing::error!(path = %record.path, "audit record not written: {e}");
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn params_keep_options_but_not_data() {
+        let p = params(
+            &Uri::from_static("/api/v1/stats/summary?fields=mean&dataset_id=a%20c"),
+            Some(json!({"values": [1, 2, 3], "options": {"bins": 10, "edges": [0, 1]}})),
+        );
+        assert_eq!(
+            p,
+            json!({
+                "fields": "mean",
+                "dataset_id": "a c",
+                "values": "[3 items]",
+                "options": {"bins": 10, "edges": "[2 items]"}
+            })
+        );
+        let bare = Uri::from_static("/api/v1/describe");
+        assert_eq!(
+            params(&bare, Some(json!([1, 2]))),
+            json!({"body": "[2 items]"})
+        );
+        assert_eq!(dataset("/api/v1/stats/summary", &p).as_deref(), Some("a c"));
+        assert_eq!(
+            dataset("/api/v1/datasets/ds-1", &json!({})).as_deref(),
+            Some("ds-1")
+        );
+        let long = redact_data(Value::String("x".repeat(1000)));
+        assert_eq!(long.as_str().unwrap().chars().count(), MAX_PARAM_CHARS + 1);
+    }
+
+    #[test]
+    fn bodies_parse_straight_into_their_redacted_form() {
+        let body = json!({
+            "values": [1.5, null, [2, 3], {"a": 1}],
+            "options": {"bins": 10, "edges": [0, 1], "label": "x".repeat(300)},
+            "seed": -3,
+            "approx": true,
+            "big": 18446744073709551615u64,
+            "none": null
+        });
+        let bytes = serde_json::to_vec(&body).unwrap();
+        let Redacted(shallow) = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(shallow, redact_data(body));
+        let Redacted(top) = serde_json::from_slice(b"[1, 2, 3]").unwrap();
+        assert_eq!(top, json!("[3 items]"));
+    }
+
+    #[tokio::test]
+    async fn file_store_queries_newest_first() {
+        let path = std::env::temp_dir().join(format!("stats_audit_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let log = AuditLog::file(&path, HeaderName::from_static("x-user-id"))
+            .await
+            .unwrap();
+        for (i, user) in ["ana", "bo", "ana"].into_iter().enumerate() {
+            let r = AuditRecord {
+                at_ms: 1000 + i as u64,
+                request_id: None,
+                user: Some(user.into()),
+                method: "POST".into(),
+                path: "/api/v1/stats/summary".into(),
+                dataset: None,
+                params: json!({}),
+                status: 200,
+                duration_ms: 1.5,
+            };
+            log.write(&r).await.unwrap();
+        }
+        let q = |user: Option<&str>, limit| AuditQuery {
+            user: user.map(str::to_string),
+            limit,
+            ..Default::default()
+        };
+        let ana = log.query(&q(Some("ana"), None)).await.unwrap();
+        assert_eq!(
+            ana.iter().map(|r| r.at_ms).collect::<Vec<_>>(),
+            [1002, 1000]
+        );
+        assert_eq!(log.query(&q(None, Some(1))).await.unwrap()[0].at_ms, 1002);
+        let later = AuditQuery {
+            since_ms: Some(1001),
+            path: Some("/api/v1/stats/".into()),
+            ..Default::default()
+        };
+        assert_eq!(log.query(&later).await.unwrap().len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
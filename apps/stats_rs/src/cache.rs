@@ -0,0 +1,391 @@
+//! # Result cache
+//!
+//! [`ResultCache`] keeps parsed datasets and expensive intermediates (sorted
+//! copies, rank vectors, correlation matrices) in memory, keyed by a hash of
+//! the content they were computed from, so repeated analyses of the same
+//! upload skip the work. Entries expire after the configured TTL and the least
+//! recently used ones are evicted once the memory budget
+//! ([`CacheConfig`](crate::config::CacheConfig)) is exceeded.
+//!
+//! Artifacts derived from a registered dataset (a column's values, their
+//! sorted copy, histograms) are keyed by dataset id, column and parameters
+//! instead ([`CacheKey::of_column`]), which skips hashing the data itself.
+//!
+//! Sizes are estimates ([`Weigh`]), good enough to keep the cache near its
+//! budget rather than an exact accounting.
+
+use crate::{
+    config::CacheConfig,
+    frame::{ColumnData, Frame},
+    ingest::CsvTable,
+    stats::prelude::*,
+    types::CorrMatrixOut,
+};
+use std::{
+    any::Any,
+    collections::{BTreeMap, HashMap},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Content hash identifying a cached value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey(u64);
+
+impl CacheKey {
+    /// Key for the `kind` of value derived from `content` (e.g. a CSV body
+    /// together with its parsing options).
+    pub fn new(kind: &str, content: &impl Hash) -> Self {
+        let mut h = DefaultHasher::new();
+        kind.hash(&mut h);
+        content.hash(&mut h);
+        Self(h.finish())
+    }
+
+    /// Key for the `kind` of value derived from numeric series.
+    pub fn of_series<'a>(kind: &str, series: impl IntoIterator<Item = &'a [f64]>) -> Self {
+        let mut h = DefaultHasher::new();
+        kind.hash(&mut h);
+        for s in series {
+            s.len().hash(&mut h);
+            s.iter().for_each(|x| x.to_bits().hash(&mut h));
+        }
+        Self(h.finish())
+    }
+
+    /// Key for the `kind` of value derived from `column` of the registered
+    /// dataset `dataset` with `params` (e.g. a bin count). Dataset ids are
+    /// never reused, so entries of a removed dataset just age out.
+    pub fn of_column(kind: &str, dataset: &str, column: &str, params: &impl Hash) -> Self {
+        Self::new(kind, &(dataset, column, params))
+    }
+}
+
+/// Lowercase hex, as used in `ETag`s.
+impl std::fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Approximate heap size of a cached value, in bytes.
+pub trait Weigh {
+    fn weight(&self) -> usize;
+}
+
+impl Weigh for Vec<f64> {
+    fn weight(&self) -> usize {
+        self.len() * size_of::<f64>()
+    }
+}
+
+impl Weigh for Vec<usize> {
+    fn weight(&self) -> usize {
+        self.len() * size_of::<usize>()
+    }
+}
+
+impl<A: Weigh, B: Weigh> Weigh for (A, B) {
+    fn weight(&self) -> usize {
+        self.0.weight() + self.1.weight()
+    }
+}
+
+impl Weigh for CsvTable {
+    fn weight(&self) -> usize {
+        self.columns
+            .iter()
+            .flat_map(|c| &c.cells)
+            .map(|s| s.len() + size_of::<String>())
+            .sum()
+    }
+}
+
+impl Weigh for Frame {
+    fn weight(&self) -> usize {
+        self.columns()
+            .iter()
+            .map(|c| match &c.data {
+                ColumnData::Numeric(v) => v.len() * size_of::<Option<f64>>(),
+                ColumnData::Text(v) => v.iter().map(|s| s.len() + size_of::<String>()).sum(),
+            })
+            .sum()
+    }
+}
+
+impl Weigh for CorrMatrixOut {
+    fn weight(&self) -> usize {
+        self.matrix.weight() + self.names.iter().flatten().map(String::len).sum::<usize>()
+    }
+}
+
+/// Hit/miss counters and current occupancy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+struct Entry {
+    value: Arc<dyn Any + Send + Sync>,
+    weight: usize,
+    expires: Instant,
+    /// Position in `Inner::lru`
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<CacheKey, Entry>,
+    /// Access tick → key, oldest first
+    lru: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl Inner {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(e) = self.entries.remove(key) {
+            self.lru.remove(&e.tick);
+            self.stats.bytes -= e.weight;
+        }
+    }
+
+    fn touch(&mut self, key: CacheKey) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(e) = self.entries.get_mut(&key) {
+            self.lru.remove(&e.tick);
+            e.tick = tick;
+            self.lru.insert(tick, key);
+        }
+    }
+}
+
+/// Shared, cheaply clonable TTL + LRU cache of computed values.
+#[derive(Clone)]
+pub struct ResultCache {
+    inner: Arc<Mutex<Inner>>,
+    max_bytes: usize,
+    ttl: Duration,
+}
+
+impl std::fmt::Debug for ResultCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResultCache")
+            .field("max_bytes", &self.max_bytes)
+            .field("ttl", &self.ttl)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        Self::new(&CacheConfig::default())
+    }
+}
+
+impl ResultCache {
+    pub fn new(cfg: &CacheConfig) -> Self {
+        Self {
+            inner: Arc::default(),
+            max_bytes: cfg.max_bytes,
+            ttl: Duration::from_secs(cfg.ttl_secs),
+        }
+    }
+
+    /// The live value stored under `key`, if it has type `T`.
+    pub fn get<T: Send + Sync + 'static>(&self, key: CacheKey) -> Option<Arc<T>> {
+        let mut inner = self.inner.lock().unwrap();
+        let hit = match inner.entries.get(&key) {
+            Some(e) if e.expires <= Instant::now() => {
+                inner.remove(&key);
+                None
+            }
+            Some(e) => e.value.clone().downcast::<T>().ok(),
+            None => None,
+        };
+        if hit.is_some() {
+            inner.stats.hits += 1;
+            inner.touch(key);
+        } else {
+            inner.stats.misses += 1;
+        }
+        hit
+    }
+
+    /// Store `value` under `key`, evicting least recently used entries to
+    /// stay within the budget. Values larger than the whole budget are
+    /// returned without being stored.
+    pub fn insert<T: Weigh + Send + Sync + 'static>(&self, key: CacheKey, value: T) -> Arc<T> {
+        let weight = value.weight();
+        let value = Arc::new(value);
+        if weight > self.max_bytes || self.ttl.is_zero() {
+            return value;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.remove(&key);
+        while inner.stats.bytes + weight > self.max_bytes {
+            let Some((_, oldest)) = inner.lru.pop_first() else {
+                break;
+            };
+            inner.remove(&oldest);
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.entries.insert(
+            key,
+            Entry {
+                value: value.clone(),
+                weight,
+                expires: Instant::now() + self.ttl,
+                tick,
+            },
+        );
+        inner.lru.insert(tick, key);
+        inner.stats.bytes += weight;
+        value
+    }
+
+    /// Cached value for `key`, or the result of `make` (stored on success).
+    pub fn get_or_try_insert_with<T, E>(
+        &self,
+        key: CacheKey,
+        make: impl FnOnce() -> Result<T, E>,
+    ) -> Result<Arc<T>, E>
+    where
+        T: Weigh + Send + Sync + 'static,
+    {
+        match self.get(key) {
+            Some(v) => Ok(v),
+            None => Ok(self.insert(key, make()?)),
+        }
+    }
+
+    /// Cached value for `key`, or the result of `make`.
+    pub fn get_or_insert_with<T>(&self, key: CacheKey, make: impl FnOnce() -> T) -> Arc<T>
+    where
+        T: Weigh + Send + Sync + 'static,
+    {
+        match self.get(key) {
+            Some(v) => v,
+            None => self.insert(key, make()),
+        }
+    }
+
+    /// Ascending copy of `xs` (NaN-free input).
+    pub fn sorted(&self, xs: &[f64]) -> Arc<Vec<f64>> {
+        self.get_or_insert_with(CacheKey::of_series("sorted", [xs]), || {
+            let mut v = xs.to_vec();
+            v.sort_by(f64::total_cmp);
+            v
+        })
+    }
+
+    /// Average ranks of `xs` (NaN-free input), aligned with `xs`.
+    pub fn ranks(&self, xs: &[f64]) -> Arc<Vec<f64>> {
+        self.get_or_insert_with(CacheKey::of_series("ranks", [xs]), || average_ranks(xs))
+    }
+
+    /// Drop every entry, returning the occupancy that was freed; hit and
+    /// miss counters are kept.
+    pub fn clear(&self) -> CacheStats {
+        let mut inner = self.inner.lock().unwrap();
+        let freed = CacheStats {
+            entries: inner.entries.len(),
+            bytes: inner.stats.bytes,
+            hits: 0,
+            misses: 0,
+        };
+        inner.entries.clear();
+        inner.lru.clear();
+        inner.stats.bytes = 0;
+        freed
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock().unwrap();
+        CacheStats {
+            entries: inner.entries.len(),
+            ..inner.stats
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_bytes: usize, ttl_secs: u64) -> ResultCache {
+        ResultCache::new(&CacheConfig {
+            max_bytes,
+            ttl_secs,
+        })
+    }
+
+    #[test]
+    fn hits_share_the_stored_value() {
+        let c = cache(1 << 20, 60);
+        let k = CacheKey::new("body", &"a,b\n1,2\n");
+        assert!(c.get::<Vec<f64>>(k).is_none());
+        let v = c.insert(k, vec![1.0, 2.0]);
+        assert!(Arc::ptr_eq(&v, &c.get::<Vec<f64>>(k).unwrap()));
+        // Same key, other type: a miss rather than a bad downcast
+        assert!(c.get::<CsvTable>(k).is_none());
+        assert_eq!(
+            c.stats(),
+            CacheStats {
+                entries: 1,
+                bytes: 16,
+                hits: 1,
+                misses: 2
+            }
+        );
+
+        assert_ne!(k, CacheKey::new("other", &"a,b\n1,2\n"));
+        assert_eq!(*c.sorted(&[3.0, 1.0, 2.0]), vec![1.0, 2.0, 3.0]);
+        assert_eq!(*c.ranks(&[3.0, 1.0, 1.0]), vec![3.0, 1.5, 1.5]);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_past_budget() {
+        let c = cache(64, 60);
+        let key = |i: u64| CacheKey::new("n", &i);
+        c.insert(key(1), vec![0.0; 3]);
+        c.insert(key(2), vec![0.0; 3]);
+        c.get::<Vec<f64>>(key(1)); // 2 is now the oldest
+        c.insert(key(3), vec![0.0; 3]);
+        assert!(c.get::<Vec<f64>>(key(1)).is_some());
+        assert!(c.get::<Vec<f64>>(key(2)).is_none());
+        assert!(c.get::<Vec<f64>>(key(3)).is_some());
+        assert_eq!(c.stats().bytes, 48);
+
+        // Larger than the budget: computed but not kept
+        c.insert(key(4), vec![0.0; 9]);
+        assert!(c.get::<Vec<f64>>(key(4)).is_none());
+        assert_eq!(c.stats().entries, 2);
+
+        let freed = c.clear();
+        assert_eq!((freed.entries, freed.bytes), (2, 48));
+        assert_eq!((c.stats().entries, c.stats().bytes), (0, 0));
+        assert!(c.get::<Vec<f64>>(key(1)).is_none());
+    }
+
+    #[test]
+    fn zero_ttl_disables_caching() {
+        let c = cache(1 << 20, 0);
+        let k = CacheKey::new("n", &1);
+        let mut calls = 0;
+        for _ in 0..2 {
+            c.get_or_insert_with(k, || {
+                calls += 1;
+                vec![1.0]
+            });
+        }
+        assert_eq!(calls, 2);
+    }
+}
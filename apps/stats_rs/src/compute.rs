@@ -0,0 +1,180 @@
+//! # Offloading CPU-bound work
+//!
+//! Handlers run on the async runtime's worker threads, so a long correlation
+//! matrix or neighbour search computed inline stalls every other request on
+//! that thread, health checks included. [`ComputePool::run`] moves such work
+//! to Tokio's blocking pool once the input is larger than
+//! [`ComputeConfig::offload_above`], with a semaphore bounding how many
+//! offloaded computations run at once (the rest wait their turn without
+//! holding a thread). The permit travels with the work onto the blocking
+//! pool, so a computation abandoned by its caller still counts against the
+//! limit until it actually returns. Small inputs still run inline, where a thread hop would
+//! cost more than the work.
+//!
+//! Offloaded work is handed a [`CancelFlag`] that is set when the handler's
+//! future is dropped — which is what Axum does when the client disconnects —
+//! so kernels that poll it stop instead of finishing a result nobody reads.
+
+use crate::{config::ComputeConfig, error::ServiceError, stats::CancelFlag};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Shared, cheaply clonable gate in front of the blocking pool.
+#[derive(Clone, Debug)]
+pub struct ComputePool {
+    permits: Arc<Semaphore>,
+    offload_above: usize,
+}
+
+impl Default for ComputePool {
+    fn default() -> Self {
+        Self::new(&ComputeConfig::default())
+    }
+}
+
+impl ComputePool {
+    pub fn new(cfg: &ComputeConfig) -> Self {
+        let workers = match cfg.workers {
+            0 => std::thread::available_parallelism().map_or(1, usize::from),
+            n => n,
+        };
+        Self {
+            permits: Arc::new(Semaphore::new(workers)),
+            offload_above: cfg.offload_above,
+        }
+    }
+
+    /// Whether work on `size` inputs leaves the async runtime.
+    pub fn offloads(&self, size: usize) -> bool {
+        size > self.offload_above
+    }
+
+    /// Run `work` on `size` inputs: inline when small, otherwise on the
+    /// blocking pool once a permit is free. The flag passed to `work` is set
+    /// if the returned future is dropped before the work finishes.
+    pub async fn run<T, F>(&self, size: usize, work: F) -> Result<T, ServiceError>
+    where
+        F: FnOnce(&CancelFlag) -> Result<T, ServiceError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let cancel = CancelFlag::default();
+        if !self.offloads(size) {
+            // Nothing can drop the future while inline work runs
+            return work(&cancel);
+        }
+        let guard = CancelOnDrop(cancel.clone());
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| ServiceError::Internal(format!("compute pool closed: {e}")))?;
+        let out = tokio::task::spawn_blocking(move || {
+            // Released when the work returns, not when the caller goes away
+            let _permit = permit;
+            work(&cancel)
+        })
+        .await
+        .map_err(|e| ServiceError::Internal(format!("computation aborted: {e}")))?;
+        std::mem::forget(guard);
+        out
+    }
+}
+
+/// Sets its flag when dropped, i.e. when the awaiting future is abandoned.
+struct CancelOnDrop(CancelFlag);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn large_inputs_leave_the_runtime_thread() {
+        let pool = ComputePool::new(&ComputeConfig {
+            offload_above: 10,
+            workers: 1,
+        });
+        let caller = std::thread::current().id();
+        let on = |size| pool.run(size, move |_| Ok(std::thread::current().id() == caller));
+        assert!(on(10).await.unwrap(), "small work runs inline");
+        assert!(!on(11).await.unwrap(), "large work is offloaded");
+        let err = pool
+            .run(11, |_| Err::<(), _>(ServiceError::Empty))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::Empty));
+    }
+
+    #[tokio::test]
+    async fn dropping_the_future_cancels_offloaded_work() {
+        let pool = ComputePool::new(&ComputeConfig {
+            offload_above: 0,
+            workers: 1,
+        });
+        let (started, ready) = tokio::sync::oneshot::channel();
+        let (stopped, done) = tokio::sync::oneshot::channel();
+        let run = pool.run(1, move |cancel| {
+            let _ = started.send(());
+            while !cancel.is_cancelled() {
+                std::thread::yield_now();
+            }
+            let _ = stopped.send(());
+            Ok(())
+        });
+        tokio::select! {
+            _ = run => panic!("work only ends once cancelled"),
+            _ = ready => {}
+        }
+        // `run` was dropped by `select!` once the work had started
+        tokio::time::timeout(std::time::Duration::from_secs(5), done)
+            .await
+            .expect("work observed the cancellation")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn abandoned_work_keeps_its_permit_until_it_returns() {
+        let pool = ComputePool::new(&ComputeConfig {
+            offload_above: 0,
+            workers: 2,
+        });
+        let (started, ready) = tokio::sync::oneshot::channel();
+        let (release, gate) = std::sync::mpsc::channel::<()>();
+        let (stopped, done) = tokio::sync::oneshot::channel();
+        let run = pool.run(1, move |_| {
+            let _ = started.send(());
+            // Ignores cancellation, like a kernel between checkpoints
+            let _ = gate.recv();
+            let _ = stopped.send(());
+            Ok(())
+        });
+        tokio::select! {
+            _ = run => panic!("work only ends once released"),
+            _ = ready => {}
+        }
+        assert_eq!(
+            pool.permits.available_permits(),
+            1,
+            "still held after the drop"
+        );
+        release.send(()).unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(5), done)
+            .await
+            .expect("work returned")
+            .unwrap();
+        // The permit is dropped just after the closure's last statement
+        for _ in 0..500 {
+            if pool.permits.available_permits() == 2 {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("permit was not released after the work returned");
+    }
+}
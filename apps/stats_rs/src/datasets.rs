@@ -0,0 +1,165 @@
+//! # Dataset registry
+//!
+//! In-memory store of ingested tables, addressed by server-assigned ids
+//! (`ds_1`, `ds_2`, …). Lives in [`AppState`](crate::state::AppState) so any
+//! handler can register or look up a dataset; contents are lost on restart.
+//!
+//! Each dataset can also hold a [`DatasetCorr`]: a correlation matrix over its
+//! numeric columns that later requests grow by whole series or observations
+//! without recomputing it. It is dropped together with the dataset.
+//!
+//! Datasets belong to the [`Tenant`] that registered them: lookups by
+//! another tenant find nothing. [`all`](DatasetRegistry::all) and
+//! [`evict`](DatasetRegistry::evict) ignore tenants, for the admin routes.
+
+use crate::{ingest::CsvTable, stats::OnlineCorrMatrix, tenant::Tenant, types::DatasetFormat};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Whether a request's `query` names a registered dataset (`?dataset=`).
+///
+/// Such responses depend on registry state, not just on the request, so the
+/// response caches leave them alone: ids are per process and reused after a
+/// restart, and the dataset may be dropped while an entry lives.
+pub fn named_in_query(query: Option<&str>) -> bool {
+    query.is_some_and(|q| {
+        q.split('&')
+            .any(|p| p.split_once('=').map_or(p, |(k, _)| k) == "dataset")
+    })
+}
+
+/// One registered dataset.
+#[derive(Clone, Debug)]
+pub struct Dataset {
+    pub id: String,
+    /// Owner; other tenants cannot see the dataset
+    pub tenant: Tenant,
+    /// Caller-supplied label (defaults to the source's file name)
+    pub name: String,
+    /// Where the data came from (e.g. the fetched URL)
+    pub source: String,
+    pub format: DatasetFormat,
+    /// Size of the original payload in bytes
+    pub bytes: usize,
+    /// Registration time, seconds since the Unix epoch
+    pub created_at: u64,
+    pub table: CsvTable,
+}
+
+/// Correlation matrix stored for a dataset, with one name per series.
+#[derive(Clone, Debug, Default)]
+pub struct DatasetCorr {
+    pub names: Vec<String>,
+    pub moments: OnlineCorrMatrix,
+}
+
+/// Shared, cheaply clonable handle to the registered datasets.
+#[derive(Clone, Debug, Default)]
+pub struct DatasetRegistry {
+    inner: Arc<RwLock<HashMap<String, Arc<Dataset>>>>,
+    corr: Arc<RwLock<HashMap<String, Arc<Mutex<DatasetCorr>>>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl DatasetRegistry {
+    /// Store a table under a fresh id and return the registered entry.
+    pub fn insert(
+        &self,
+        tenant: &Tenant,
+        name: String,
+        source: String,
+        format: DatasetFormat,
+        bytes: usize,
+        table: CsvTable,
+    ) -> Arc<Dataset> {
+        let id = format!("ds_{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let ds = Arc::new(Dataset {
+            id: id.clone(),
+            tenant: tenant.clone(),
+            name,
+            source,
+            format,
+            bytes,
+            created_at,
+            table,
+        });
+        self.inner.write().unwrap().insert(id, ds.clone());
+        ds
+    }
+
+    /// Dataset `id`, if `tenant` registered it.
+    pub fn get(&self, tenant: &Tenant, id: &str) -> Option<Arc<Dataset>> {
+        self.inner
+            .read()
+            .unwrap()
+            .get(id)
+            .filter(|d| d.tenant == *tenant)
+            .cloned()
+    }
+
+    /// Drop dataset `id`, if `tenant` registered it.
+    pub fn remove(&self, tenant: &Tenant, id: &str) -> Option<Arc<Dataset>> {
+        self.get(tenant, id)?;
+        self.evict(id)
+    }
+
+    /// Drop dataset `id`, whoever registered it.
+    pub fn evict(&self, id: &str) -> Option<Arc<Dataset>> {
+        self.corr.write().unwrap().remove(id);
+        self.inner.write().unwrap().remove(id)
+    }
+
+    /// Drop every dataset, returning how many there were.
+    pub fn clear(&self) -> usize {
+        self.corr.write().unwrap().clear();
+        let mut inner = self.inner.write().unwrap();
+        let n = inner.len();
+        inner.clear();
+        n
+    }
+
+    /// The correlation matrix stored for dataset `id`, if any.
+    pub fn corr(&self, id: &str) -> Option<Arc<Mutex<DatasetCorr>>> {
+        self.corr.read().unwrap().get(id).cloned()
+    }
+
+    /// Store (or replace) the correlation matrix of dataset `id`.
+    pub fn set_corr(&self, id: &str, corr: DatasetCorr) -> Arc<Mutex<DatasetCorr>> {
+        let corr = Arc::new(Mutex::new(corr));
+        self.corr.write().unwrap().insert(id.into(), corr.clone());
+        corr
+    }
+
+    /// `tenant`'s datasets in registration order.
+    pub fn list(&self, tenant: &Tenant) -> Vec<Arc<Dataset>> {
+        let mut v = self.all();
+        v.retain(|d| d.tenant == *tenant);
+        v
+    }
+
+    /// Every tenant's datasets in registration order.
+    pub fn all(&self) -> Vec<Arc<Dataset>> {
+        let mut v: Vec<_> = self.inner.read().unwrap().values().cloned().collect();
+        v.sort_by_key(|d| d.id[3..].parse::<u64>().unwrap_or(0));
+        v
+    }
+
+    /// How many datasets `tenant` holds, and their payload bytes together.
+    pub fn usage(&self, tenant: &Tenant) -> (usize, usize) {
+        self.inner
+            .read()
+            .unwrap()
+            .values()
+            .filter(|d| d.tenant == *tenant)
+            .fold((0, 0), |(n, bytes), d| (n + 1, bytes + d.bytes))
+    }
+}
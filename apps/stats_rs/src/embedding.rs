@@ -0,0 +1,255 @@
+//! # Embedding wire formats
+//!
+//! Vector and RAG endpoints accept each embedding either as a JSON number array
+//! or as a base64 string holding little-endian `f32` values (standard alphabet,
+//! padding optional). The base64 form is roughly 4–5× smaller than a JSON array
+//! of the same floats, which matters for 1k+-dimensional embeddings.
+//!
+//! Fields opt in with `#[serde(deserialize_with = "crate::embedding::vectors")]`
+//! (or [`vector`] for a single embedding); both forms may be mixed in one list.
+//!
+//! Point sets whose request carries a `dtype` are typed [`Embeddings`]
+//! instead: rows stay at their wire precision until the handler asks for
+//! `f64` or `f32`, so an `f32` request never widens its base64 payload.
+
+use base64::{
+    Engine, alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+};
+use schemars::JsonSchema;
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeSeq,
+};
+use std::fmt;
+
+const B64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Schema-only mirror of the accepted embedding encodings.
+#[derive(JsonSchema, utoipa::ToSchema)]
+#[serde(untagged)]
+pub enum EmbeddingWire {
+    /// Plain JSON array of numbers
+    Array(Vec<f64>),
+    /// Base64 of little-endian `f32` values
+    Base64(String),
+}
+
+/// Decode a base64 string of little-endian `f32` values.
+///
+/// Fails on invalid base64, a byte length that is not a multiple of 4, or
+/// non-finite values (JSON arrays cannot carry those either).
+pub fn decode_f32_base64(s: &str) -> Result<Vec<f64>, String> {
+    decode_f32_base64_native(s).map(|v| v.into_iter().map(f64::from).collect())
+}
+
+/// [`decode_f32_base64`] without widening to `f64`.
+pub fn decode_f32_base64_native(s: &str) -> Result<Vec<f32>, String> {
+    let bytes = B64
+        .decode(s.trim())
+        .map_err(|e| format!("invalid base64 embedding: {e}"))?;
+    if bytes.len() % 4 != 0 {
+        return Err(format!(
+            "base64 embedding has {} bytes, not a multiple of 4 (f32)",
+            bytes.len()
+        ));
+    }
+    bytes
+        .chunks_exact(4)
+        .enumerate()
+        .map(|(i, c)| {
+            let x = f32::from_le_bytes([c[0], c[1], c[2], c[3]]);
+            if x.is_finite() {
+                Ok(x)
+            } else {
+                Err(format!(
+                    "base64 embedding has non-finite value at index {i}"
+                ))
+            }
+        })
+        .collect()
+}
+
+/// Encode values as base64 little-endian `f32` (lossy for `f64` inputs).
+pub fn encode_f32_base64(xs: &[f64]) -> String {
+    let bytes: Vec<u8> = xs.iter().flat_map(|&x| (x as f32).to_le_bytes()).collect();
+    B64.encode(bytes)
+}
+
+/// One embedding in either wire format, at the precision it arrived in.
+#[derive(Debug, Clone, PartialEq)]
+enum Embedding {
+    /// Decoded from base64
+    F32(Vec<f32>),
+    /// Parsed from a JSON array
+    F64(Vec<f64>),
+}
+
+impl Embedding {
+    fn len(&self) -> usize {
+        match self {
+            Self::F32(v) => v.len(),
+            Self::F64(v) => v.len(),
+        }
+    }
+
+    fn into_f64(self) -> Vec<f64> {
+        match self {
+            Self::F32(v) => v.into_iter().map(f64::from).collect(),
+            Self::F64(v) => v,
+        }
+    }
+
+    fn into_f32(self) -> Vec<f32> {
+        match self {
+            Self::F32(v) => v,
+            Self::F64(v) => v.into_iter().map(|x| x as f32).collect(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Embedding {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = Embedding;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array of numbers or a base64 string of little-endian f32")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Embedding, E> {
+                decode_f32_base64_native(s)
+                    .map(Embedding::F32)
+                    .map_err(E::custom)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Embedding, A::Error> {
+                let mut v = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(x) = seq.next_element::<f64>()? {
+                    v.push(x);
+                }
+                Ok(Embedding::F64(v))
+            }
+        }
+
+        d.deserialize_any(V)
+    }
+}
+
+/// `deserialize_with` helper for a single embedding.
+pub fn vector<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<f64>, D::Error> {
+    Embedding::deserialize(d).map(Embedding::into_f64)
+}
+
+/// `deserialize_with` helper for a list of embeddings.
+pub fn vectors<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Vec<f64>>, D::Error> {
+    Vec::<Embedding>::deserialize(d).map(|v| v.into_iter().map(Embedding::into_f64).collect())
+}
+
+/// A list of embeddings in either wire format, converted to one precision
+/// only when the handler knows which it wants. Serializes as number arrays.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Embeddings(Vec<Embedding>);
+
+impl Embeddings {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Numbers over all embeddings.
+    pub fn values(&self) -> usize {
+        self.0.iter().map(Embedding::len).sum()
+    }
+
+    pub fn into_f64(self) -> Vec<Vec<f64>> {
+        self.0.into_iter().map(Embedding::into_f64).collect()
+    }
+
+    /// Narrow to `f32`; base64 rows are moved as they are.
+    pub fn into_f32(self) -> Vec<Vec<f32>> {
+        self.0.into_iter().map(Embedding::into_f32).collect()
+    }
+}
+
+impl From<Vec<Vec<f64>>> for Embeddings {
+    fn from(rows: Vec<Vec<f64>>) -> Self {
+        Self(rows.into_iter().map(Embedding::F64).collect())
+    }
+}
+
+impl Serialize for Embeddings {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut seq = s.serialize_seq(Some(self.0.len()))?;
+        for row in &self.0 {
+            match row {
+                Embedding::F32(v) => seq.serialize_element(v)?,
+                Embedding::F64(v) => seq.serialize_element(v)?,
+            }
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_roundtrip_and_errors() {
+        let xs = [1.0, -0.5, 0.25, 3.0];
+        let s = encode_f32_base64(&xs);
+        assert_eq!(decode_f32_base64(&s).unwrap(), xs);
+        // padding is optional
+        assert_eq!(decode_f32_base64(s.trim_end_matches('=')).unwrap(), xs);
+
+        assert!(decode_f32_base64("not base64!").is_err());
+        assert!(decode_f32_base64(&B64.encode([0u8; 3])).is_err());
+        assert!(decode_f32_base64(&B64.encode(f32::NAN.to_le_bytes())).is_err());
+    }
+
+    #[test]
+    fn vectors_accept_mixed_forms() {
+        #[derive(Deserialize)]
+        struct T {
+            #[serde(deserialize_with = "vectors")]
+            v: Vec<Vec<f64>>,
+        }
+        let b = encode_f32_base64(&[0.5, 2.0]);
+        let t: T = serde_json::from_value(serde_json::json!({"v": [[1.0, 2.0], b]})).unwrap();
+        assert_eq!(t.v, vec![vec![1.0, 2.0], vec![0.5, 2.0]]);
+
+        let bad = serde_json::from_value::<T>(serde_json::json!({"v": [true]}));
+        assert!(bad.is_err());
+    }
+
+    #[test]
+    fn embeddings_keep_wire_precision_until_asked() {
+        let b = encode_f32_base64(&[0.1, 2.0]);
+        let e: Embeddings = serde_json::from_value(serde_json::json!([[0.1, 3.0], b])).unwrap();
+        assert_eq!(e.len(), 2);
+        assert_eq!(
+            e.clone().into_f32(),
+            vec![vec![0.1_f32, 3.0], vec![0.1_f32, 2.0]]
+        );
+        // the base64 row widens exactly; the array row was never narrowed
+        assert_eq!(
+            e.clone().into_f64(),
+            vec![vec![0.1, 3.0], vec![f64::from(0.1_f32), 2.0]]
+        );
+        assert_eq!(
+            serde_json::to_value(&e).unwrap()[0],
+            serde_json::json!([0.1, 3.0])
+        );
+    }
+}
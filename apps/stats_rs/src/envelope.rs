@@ -0,0 +1,277 @@
+//! # `/api/v2` response envelope
+//!
+//! `/api/v2` serves the same routes as `/api/v1`, but every JSON or
+//! plain-text response is wrapped as an [`Envelope`]:
+//! `{ "data": …, "meta": { "n_used", "n_dropped", "elapsed_ms", "warnings" } }`.
+//! Failures carry `"data": null` and an `"error"` in the [`ErrorResponse`]
+//! shape, including the ones v1 answers with plain text or an empty body
+//! (extractor rejections, `408` timeouts, `413` body limits).
+//!
+//! The envelope is built from the finished v1 response, so new routes get it
+//! without changes. `meta.n_used` is what the handler reported with an
+//! [`NUsed`] response extension (`null` when it reported none); [`meta_for`]
+//! reads the rest off the result's common fields. A JSON response that does
+//! not parse is a bug, answered `500` rather than wrapped as `data: null`.
+//! CSV/TSV renderings and `204 No Content` pass through unchanged.
+
+use crate::{
+    error::ServiceError,
+    types::{Envelope, ErrorResponse, Meta},
+};
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::{HeaderValue, StatusCode, header, response::Parts},
+    middleware::Next,
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+};
+use serde_json::Value;
+use std::{convert::Infallible, time::Instant};
+
+/// Values or rows a result was computed from, returned by a handler next to
+/// its body (`Ok((NUsed(n), Json(out)))`) and reported as `meta.n_used`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NUsed(pub usize);
+
+impl IntoResponseParts for NUsed {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Infallible> {
+        res.extensions_mut().insert(self);
+        Ok(res)
+    }
+}
+
+/// Counts and caveats for a v1 result:
+///
+/// - `n_used`: as reported by the handler
+/// - `n_dropped`: missing values removed by the `drop` policy
+///   (`missing.count`) plus unusable rows (`skipped_rows`)
+/// - `warnings`: imputed missing values, row sampling (`sample`), and the
+///   result's own `warnings` (e.g. `/profile` findings)
+pub fn meta_for(data: &Value, n_used: Option<NUsed>) -> Meta {
+    let count = |v: &Value, k: &str| v.get(k).and_then(Value::as_u64);
+    let mut meta = Meta {
+        n_used: n_used.map(|n| n.0 as u64),
+        n_dropped: count(data, "skipped_rows").unwrap_or(0),
+        ..Default::default()
+    };
+
+    if let Some(missing) = data.get("missing") {
+        let n = count(missing, "count").unwrap_or(0);
+        match missing.get("policy").and_then(Value::as_str) {
+            Some("drop") => meta.n_dropped += n,
+            Some(policy) if n > 0 => meta
+                .warnings
+                .push(format!("{n} missing value(s) replaced ({policy})")),
+            _ => {}
+        }
+    }
+    if let Some(s) = data.get("sample") {
+        let (seen, sampled) = (count(s, "rows_seen"), count(s, "rows_sampled"));
+        if let (Some(seen), Some(sampled)) = (seen, sampled)
+            && sampled < seen
+        {
+            meta.warnings.push(format!(
+                "analyzed a random sample of {sampled} of {seen} rows (seed {})",
+                count(s, "seed").unwrap_or(0)
+            ));
+        }
+    }
+    for w in data
+        .get("warnings")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        match w
+            .as_str()
+            .or_else(|| w.get("message").and_then(Value::as_str))
+        {
+            Some(m) => meta.warnings.push(m.to_string()),
+            None => meta.warnings.push(w.to_string()),
+        }
+    }
+    meta
+}
+
+/// [`ErrorResponse`] for a failed v1 response; bodies in another shape
+/// become its message under a code named after the status.
+pub fn error_for(status: StatusCode, body: &[u8]) -> ErrorResponse {
+    if let Ok(e) = serde_json::from_slice::<ErrorResponse>(body) {
+        return e;
+    }
+    let text = String::from_utf8_lossy(body).trim().to_string();
+    let reason = status.canonical_reason().unwrap_or("Error");
+    ErrorResponse {
+        code: match status {
+            StatusCode::BAD_REQUEST => "invalid_input".into(),
+            StatusCode::REQUEST_TIMEOUT => "timeout".into(),
+            StatusCode::UNPROCESSABLE_ENTITY => "validation_failed".into(),
+            StatusCode::INTERNAL_SERVER_ERROR => "internal_error".into(),
+            _ => reason.to_ascii_lowercase().replace([' ', '-'], "_"),
+        },
+        message: if text.is_empty() { reason.into() } else { text },
+        details: None,
+        request_id: None,
+    }
+}
+
+fn is(parts: &Parts, mime: &str) -> bool {
+    parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with(mime))
+}
+
+/// Axum middleware wrapping responses in an [`Envelope`].
+pub async fn middleware(req: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let (mut parts, body) = next.run(req).await.into_parts();
+    let failed = parts.status.is_client_error() || parts.status.is_server_error();
+    let json = is(&parts, "application/json");
+    if parts.status == StatusCode::NO_CONTENT || !(failed || json || is(&parts, "text/plain")) {
+        return Response::from_parts(parts, body);
+    }
+
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(b) => b,
+        Err(e) => return ServiceError::Internal(format!("response body: {e}")).into_response(),
+    };
+    let elapsed_ms = started.elapsed().as_secs_f64() * 1e3;
+    let failure = |error| Envelope {
+        data: Value::Null,
+        meta: Meta {
+            elapsed_ms,
+            ..Default::default()
+        },
+        error: Some(error),
+    };
+    let envelope = if failed {
+        failure(error_for(parts.status, &bytes))
+    } else {
+        let data = match json {
+            true => serde_json::from_slice(&bytes),
+            false => Ok(Value::String(String::from_utf8_lossy(&bytes).into_owned())),
+        };
+        match data {
+            Ok(data) => Envelope {
+                meta: Meta {
+                    elapsed_ms,
+                    ..meta_for(&data, parts.extensions.get::<NUsed>().copied())
+                },
+                data,
+                error: None,
+            },
+            Err(e) => {
+                tracing::error!(status = %parts.status, "v1 response is not valid JSON: {e}");
+                parts.status = StatusCode::INTERNAL_SERVER_ERROR;
+                failure(
+                    ServiceError::Internal(format!("response is not valid JSON: {e}"))
+                        .to_response(),
+                )
+            }
+        }
+    };
+
+    let body = match serde_json::to_vec(&envelope) {
+        Ok(b) => b,
+        Err(e) => return ServiceError::Internal(format!("envelope: {e}")).into_response(),
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn meta_reads_counts_and_caveats() {
+        let m = meta_for(
+            &json!({
+            "count": 8,
+            "missing": {"policy": "drop", "count": 2},
+            "sample": {"seed": 3, "rows_seen": 100, "rows_sampled": 10, "fraction": 0.1}
+            }),
+            Some(NUsed(8)),
+        );
+        assert_eq!(m.n_used, Some(8));
+        assert_eq!(m.n_dropped, 2);
+        assert_eq!(
+            m.warnings,
+            ["analyzed a random sample of 10 of 100 rows (seed 3)"]
+        );
+
+        // Counts are never guessed from field names
+        let m = meta_for(
+            &json!({
+            "n_rows": 4,
+            "missing": {"policy": "impute_mean", "count": 1},
+            "warnings": [{"column": "a", "kind": "constant", "message": "a is constant"}]
+            }),
+            None,
+        );
+        assert_eq!(m.n_used, None);
+        assert_eq!(m.n_dropped, 0);
+        assert_eq!(
+            m.warnings,
+            ["1 missing value(s) replaced (impute_mean)", "a is constant"]
+        );
+
+        assert_eq!(meta_for(&json!([1, 2]), None), Meta::default());
+    }
+
+    #[tokio::test]
+    async fn unparseable_json_is_a_server_error() {
+        use tower::ServiceExt;
+        let app = axum::Router::new()
+            .route(
+                "/",
+                axum::routing::get(|| async {
+                    (
+                        [(header::CONTENT_TYPE, "application/json")],
+                        "{\"count\": 1,",
+                    )
+                }),
+            )
+            .layer(axum::middleware::from_fn(middleware));
+        let res = app
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let v: Value = serde_json::from_slice(&body).unwrap();
+        assert!(v["data"].is_null());
+        assert_eq!(v["error"]["code"], "internal_error");
+    }
+
+    #[test]
+    fn errors_share_one_shape() {
+        let e = error_for(
+            StatusCode::NOT_FOUND,
+            br#"{"code":"not_found","message":"job 'x'"}"#,
+        );
+        assert_eq!(
+            (e.code.as_str(), e.message.as_str()),
+            ("not_found", "job 'x'")
+        );
+
+        let e = error_for(StatusCode::UNSUPPORTED_MEDIA_TYPE, b"Expected JSON");
+        assert_eq!(e.code, "unsupported_media_type");
+        assert_eq!(e.message, "Expected JSON");
+
+        let e = error_for(StatusCode::REQUEST_TIMEOUT, b"");
+        assert_eq!(
+            (e.code.as_str(), e.message.as_str()),
+            ("timeout", "Request Timeout")
+        );
+    }
+}
@@ -4,12 +4,15 @@
 //! microservice to represent high-level service and data processing errors.
 //!
 //! Each variant corresponds to a common failure mode during CSV ingestion,
-//! numeric analysis, or data validation. The enum also implements [`IntoResponse`]
-//! so it can be returned directly from Axum handlers as structured JSON
-//! error responses.
+//! numeric analysis, or data validation. With the `server` feature the enum
+//! also implements `IntoResponse` so it can be returned directly from Axum
+//! handlers as an [`ErrorResponse`]: a machine-readable `code`, the
+//! human-readable `message` and, where known, `details` locating the problem.
 
-use axum::{Json, http::StatusCode, response::IntoResponse};
-use serde_json::json;
+use crate::types::{ErrorDetails, ErrorResponse};
+#[cfg(feature = "server")]
+use axum::{Json, response::IntoResponse};
+use http::StatusCode;
 
 /// Represents errors that may occur while processing statistical requests.
 ///
@@ -51,11 +54,19 @@ pub enum ServiceError {
 
     /// The uploaded CSV could not be parsed into valid records.
     ///
-    /// This often indicates malformed delimiters, inconsistent headers,
-    /// or corrupted encodings. In the future, this error may include
-    /// more detailed location context (e.g., row and column).
-    #[error("failed to parse CSV")]
-    CsvParse,
+    /// This often indicates malformed quoting or corrupted encodings.
+    /// `line` (1-based, of the raw input) and `column` (1-based field) locate
+    /// the failure when the parser reports them.
+    #[error("failed to parse CSV{}: {message}", at_line(*line))]
+    CsvParse {
+        line: Option<u64>,
+        column: Option<u64>,
+        message: String,
+    },
+
+    /// The uploaded spreadsheet (`.xlsx`) could not be opened or read.
+    #[error("failed to parse spreadsheet")]
+    SpreadsheetParse,
 
     /// The CSV was parsed successfully but contained no numeric columns.
     ///
@@ -63,45 +74,286 @@ pub enum ServiceError {
     /// this is returned when all fields are strings, booleans, or empty.
     #[error("no numeric data found in CSV")]
     NoNumeric,
+
+    /// The request was well-formed JSON but its contents are unusable
+    /// (e.g. mismatched vector dimensions or an out-of-range parameter).
+    ///
+    /// The message names the offending field.
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    /// A request field failed validation. `field` is a JSON pointer into the
+    /// request body (e.g. `/values` or `/series/1`).
+    #[error("invalid field {field}: {message}")]
+    Validation { field: String, message: String },
+
+    /// A referenced resource (e.g. a dataset id) does not exist.
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    /// The request lacks valid credentials (e.g. the admin bearer token).
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The request targets something the server is configured to refuse,
+    /// such as a URL outside the ingestion allowlist.
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+
+    /// The resource exists but is not in a state that allows the request
+    /// (e.g. the result of a job that has not finished).
+    #[error("conflict: {0}")]
+    Conflict(String),
+
+    /// A payload exceeded a configured size limit.
+    #[error("payload too large: {0}")]
+    TooLarge(String),
+
+    /// The caller's tenant is at one of its configured quotas (datasets,
+    /// stored bytes or active jobs).
+    #[error("quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    /// An upstream fetch (e.g. `/ingest/url`) failed or timed out.
+    #[error("upstream error: {0}")]
+    Upstream(String),
+
+    /// A bug or resource failure on the server side; never the client's fault.
+    #[error("internal error: {0}")]
+    Internal(String),
+
+    /// The computation was abandoned because the client stopped waiting for
+    /// it (e.g. the connection closed). Nobody reads the response; the status
+    /// exists for logs and metrics.
+    #[error("request cancelled by the client")]
+    Cancelled,
+}
+
+fn at_line(line: Option<u64>) -> String {
+    line.map(|l| format!(" at line {l}")).unwrap_or_default()
+}
+
+impl ServiceError {
+    /// Stable, machine-readable identifier of the error kind.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ServiceError::Empty => "empty_dataset",
+            ServiceError::NaN => "nan_value",
+            ServiceError::CsvParse { .. } => "csv_parse",
+            ServiceError::SpreadsheetParse => "spreadsheet_parse",
+            ServiceError::NoNumeric => "no_numeric_data",
+            ServiceError::InvalidInput(_) => "invalid_input",
+            ServiceError::Validation { .. } => "validation_failed",
+            ServiceError::NotFound(_) => "not_found",
+            ServiceError::Unauthorized(_) => "unauthorized",
+            ServiceError::Forbidden(_) => "forbidden",
+            ServiceError::Conflict(_) => "conflict",
+            ServiceError::TooLarge(_) => "payload_too_large",
+            ServiceError::QuotaExceeded(_) => "quota_exceeded",
+            ServiceError::Upstream(_) => "upstream_error",
+            ServiceError::Internal(_) => "internal_error",
+            ServiceError::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ServiceError::Empty
+            | ServiceError::NaN
+            | ServiceError::CsvParse { .. }
+            | ServiceError::SpreadsheetParse
+            | ServiceError::NoNumeric
+            | ServiceError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            ServiceError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::NotFound(_) => StatusCode::NOT_FOUND,
+            ServiceError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ServiceError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ServiceError::Conflict(_) => StatusCode::CONFLICT,
+            ServiceError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ServiceError::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+            ServiceError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ServiceError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            // Non-standard "499 Client Closed Request", as logged by nginx
+            ServiceError::Cancelled => {
+                StatusCode::from_u16(499).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+
+    /// Location of the problem, for CSV and validation errors.
+    pub fn details(&self) -> Option<ErrorDetails> {
+        match self {
+            ServiceError::CsvParse { line, column, .. } if line.is_some() || column.is_some() => {
+                Some(ErrorDetails {
+                    line: *line,
+                    column: *column,
+                    field: None,
+                })
+            }
+            ServiceError::Validation { field, .. } => Some(ErrorDetails {
+                field: Some(field.clone()),
+                ..Default::default()
+            }),
+            _ => None,
+        }
+    }
+
+    /// The JSON body sent to clients.
+    pub fn to_response(&self) -> ErrorResponse {
+        ErrorResponse {
+            code: self.code().into(),
+            message: self.to_string(),
+            details: self.details(),
+            request_id: None,
+        }
+    }
 }
 
+#[cfg(feature = "server")]
 impl IntoResponse for ServiceError {
     /// Converts a [`ServiceError`] into an Axum `Response`.
     ///
-    /// The implementation currently maps all variants to HTTP `400 Bad Request`,
-    /// but can be extended in the future for more granular codes:
+    /// Input problems map to HTTP `400 Bad Request`, failed field validation
+    /// to `422`; lookup, policy, size, upstream and server failures get their
+    /// own codes:
     ///
-    /// | Variant | Status Code | Typical Meaning |
-    /// |----------|--------------|----------------|
-    /// | `Empty` | `400` | User provided an empty dataset |
-    /// | `NaN` | `400` | Dataset contained invalid numeric values |
-    /// | `CsvParse` | `400` | CSV could not be parsed |
-    /// | `NoNumeric` | `400` | CSV contained no numeric data |
+    /// | Variant | Status Code | `code` | Typical Meaning |
+    /// |----------|--------------|--------|----------------|
+    /// | `Empty` | `400` | `empty_dataset` | User provided an empty dataset |
+    /// | `NaN` | `400` | `nan_value` | Dataset contained invalid numeric values |
+    /// | `CsvParse` | `400` | `csv_parse` | CSV could not be parsed |
+    /// | `SpreadsheetParse` | `400` | `spreadsheet_parse` | Spreadsheet could not be parsed |
+    /// | `NoNumeric` | `400` | `no_numeric_data` | CSV contained no numeric data |
+    /// | `InvalidInput` | `400` | `invalid_input` | Parameters or shapes are invalid |
+    /// | `Validation` | `422` | `validation_failed` | A field broke a documented constraint |
+    /// | `NotFound` | `404` | `not_found` | Unknown dataset or resource |
+    /// | `Unauthorized` | `401` | `unauthorized` | Missing or wrong credentials (admin token, tenant API key) |
+    /// | `Forbidden` | `403` | `forbidden` | Target refused by configuration (e.g. URL allowlist) |
+    /// | `Conflict` | `409` | `conflict` | Resource not ready (e.g. unfinished job) |
+    /// | `TooLarge` | `413` | `payload_too_large` | Payload exceeded a size limit |
+    /// | `QuotaExceeded` | `429` | `quota_exceeded` | The tenant holds as many datasets or jobs as it may |
+    /// | `Upstream` | `502` | `upstream_error` | Remote fetch failed |
+    /// | `Internal` | `500` | `internal_error` | Server-side failure |
+    /// | `Cancelled` | `499` | `cancelled` | Client disconnected before the result was ready |
     ///
-    /// The response body is JSON with a single `"error"` key, e.g.:
+    /// The body is an [`ErrorResponse`], e.g.:
     ///
     /// ```json
-    /// { "error": "empty dataset" }
+    /// {
+    ///   "code": "csv_parse",
+    ///   "message": "failed to parse CSV at line 3: invalid UTF-8 in field 2",
+    ///   "details": { "line": 3, "column": 2 }
+    /// }
     /// ```
     ///
     /// # Example
     ///
     /// ```rust,ignore
     /// async fn handler() -> Result<Json<Value>, ServiceError> {
-    ///     Err(ServiceError::CsvParse)
+    ///     Err(ServiceError::NoNumeric)
     /// }
     /// ```
     fn into_response(self) -> axum::response::Response {
-        // For now, all are mapped to HTTP 400; specialized status codes can be added later.
-        let status = match self {
-            ServiceError::Empty
-            | ServiceError::NaN
-            | ServiceError::CsvParse
-            | ServiceError::NoNumeric => StatusCode::BAD_REQUEST,
+        if let ServiceError::Internal(e) = &self {
+            tracing::error!("internal error: {e}");
+        }
+        (self.status(), Json(self.to_response())).into_response()
+    }
+}
+
+/// CSV reader errors, located by the line and field the reader reports.
+#[cfg(feature = "server")]
+impl From<csv::Error> for ServiceError {
+    fn from(e: csv::Error) -> Self {
+        let (line, column) = match e.kind() {
+            csv::ErrorKind::Utf8 { pos, err } => {
+                (pos.as_ref().map(|p| p.line()), Some(err.field() as u64 + 1))
+            }
+            csv::ErrorKind::UnequalLengths { pos, .. } => (pos.as_ref().map(|p| p.line()), None),
+            _ => (e.position().map(|p| p.line()), None),
         };
+        let message = match e.kind() {
+            csv::ErrorKind::Utf8 { err, .. } => {
+                format!("invalid UTF-8 in field {}", err.field() + 1)
+            }
+            csv::ErrorKind::Io(io) => io.to_string(),
+            _ => e.to_string(),
+        };
+        ServiceError::CsvParse {
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+/// Errors raised while reading a streamed request body.
+///
+/// Hitting the wire body limit mid-stream maps to `TooLarge` (`413`);
+/// anything else (client disconnect, bad compression) is `InvalidInput`.
+#[cfg(feature = "server")]
+impl From<axum::Error> for ServiceError {
+    fn from(e: axum::Error) -> Self {
+        let mut src: Option<&(dyn std::error::Error + 'static)> = Some(&e);
+        while let Some(s) = src {
+            if s.is::<http_body_util::LengthLimitError>() {
+                return ServiceError::TooLarge("request body exceeds the size limit".into());
+            }
+            src = s.source();
+        }
+        ServiceError::InvalidInput(format!("body: {e}"))
+    }
+}
 
-        let body = json!({ "error": self.to_string() });
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        (status, Json(body)).into_response()
+    #[cfg(feature = "server")]
+    #[test]
+    fn csv_errors_carry_their_location() {
+        let mut rdr = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(&b"a,b\n1,2\n3,\xff\n"[..]);
+        let err = rdr
+            .records()
+            .find_map(Result::err)
+            .map(ServiceError::from)
+            .unwrap();
+        assert_eq!(err.code(), "csv_parse");
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            err.details(),
+            Some(ErrorDetails {
+                line: Some(3),
+                column: Some(2),
+                field: None
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            "failed to parse CSV at line 3: invalid UTF-8 in field 2"
+        );
+    }
+
+    #[test]
+    fn validation_errors_name_the_field() {
+        let err = ServiceError::Validation {
+            field: "/bins".into(),
+            message: "must be at least 2".into(),
+        };
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = serde_json::to_value(err.to_response()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "code": "validation_failed",
+                "message": "invalid field /bins: must be at least 2",
+                "details": {"field": "/bins"}
+            })
+        );
+        let plain = serde_json::to_value(ServiceError::Empty.to_response()).unwrap();
+        assert_eq!(plain["code"], "empty_dataset");
+        assert!(plain.get("details").is_none());
     }
 }
@@ -0,0 +1,224 @@
+//! # Conditional requests for deterministic analyses
+//!
+//! The analysis endpoints (`/stats/*`, `/describe`, `/profile`,
+//! `/schema/infer`) are deterministic: the same request always gets the same
+//! answer, since stochastic methods fall back to the configured seed. Their
+//! `200` responses carry a strong `ETag` computed from the request (method,
+//! path and query, tenant, `Accept`, `Content-Type` and body; the tenant so
+//! that one tenant's cached answers never reach another), so:
+//!
+//! - a request whose `If-None-Match` lists that tag is answered
+//!   `304 Not Modified` before any work is done (`*` is not honoured: it
+//!   would skip a computation whose result the client never saw), and
+//! - the response body is kept in the [`ResultCache`](crate::cache::ResultCache),
+//!   so a client without the tag still skips the computation while the entry
+//!   lives.
+//!
+//! Ingestion and job submission have side effects and are left alone, as are
+//! requests reading a registered dataset (`?dataset=`): their answer changes
+//! when the dataset is dropped.
+
+use crate::{
+    cache::{CacheKey, Weigh},
+    datasets,
+    envelope::NUsed,
+    error::ServiceError,
+    state::AppState,
+    tenant::Tenant,
+};
+use axum::{
+    body::{Body, Bytes, HttpBody, to_bytes},
+    extract::{OriginalUri, Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+/// Route prefixes (below the API version) whose responses are deterministic.
+const DETERMINISTIC_PREFIXES: [&str; 4] = ["/stats/", "/describe", "/profile", "/schema/infer"];
+
+/// A stored `200 OK` response.
+struct CachedResponse {
+    content_type: Option<HeaderValue>,
+    /// Reported by the handler, for the `/api/v2` envelope
+    n_used: Option<NUsed>,
+    body: Bytes,
+}
+
+impl Weigh for CachedResponse {
+    fn weight(&self) -> usize {
+        self.body.len()
+    }
+}
+
+/// Cache key of a request; the crate version keeps tags from outliving a
+/// change of output format.
+pub fn request_key(
+    method: &Method,
+    uri: &str,
+    tenant: &Tenant,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> CacheKey {
+    let header = |name| headers.get(name).map_or(&b""[..], HeaderValue::as_bytes);
+    CacheKey::new(
+        "response",
+        &(
+            method.as_str(),
+            uri,
+            tenant.as_str(),
+            header(header::ACCEPT),
+            header(header::CONTENT_TYPE),
+            body,
+            env!("CARGO_PKG_VERSION"),
+        ),
+    )
+}
+
+/// Whether `If-None-Match` lists `etag` (weak comparison, as RFC 9110
+/// prescribes for this header). `*` matches nothing: a `POST` is not a
+/// representation the client could already hold.
+pub fn matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|t| t.trim())
+        .any(|t| t.trim_start_matches("W/") == etag)
+}
+
+fn with_etag(mut res: Response, etag: &HeaderValue) -> Response {
+    res.headers_mut().insert(header::ETAG, etag.clone());
+    res
+}
+
+/// Axum middleware answering conditional requests and caching responses.
+pub async fn middleware(
+    State(state): State<Arc<AppState>>,
+    OriginalUri(uri): OriginalUri,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path();
+    if req.method() != Method::POST
+        || !DETERMINISTIC_PREFIXES.iter().any(|p| path.starts_with(p))
+        || datasets::named_in_query(req.uri().query())
+    {
+        return next.run(req).await;
+    }
+
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes(body, state.config.max_decompressed_body_bytes).await {
+        Ok(b) => b,
+        Err(_) => {
+            return ServiceError::TooLarge("request body exceeds the size limit".into())
+                .into_response();
+        }
+    };
+    let tenant = parts
+        .extensions
+        .get::<Tenant>()
+        .cloned()
+        .unwrap_or_default();
+    let key = request_key(
+        &parts.method,
+        &uri.to_string(),
+        &tenant,
+        &parts.headers,
+        &body,
+    );
+    let tag = format!("\"{key}\"");
+    let etag = HeaderValue::from_str(&tag).expect("hex digits are a valid header value");
+
+    if matches(&parts.headers, &tag) {
+        return with_etag(StatusCode::NOT_MODIFIED.into_response(), &etag);
+    }
+    if let Some(hit) = state.cache.get::<CachedResponse>(key) {
+        let mut res = hit.body.clone().into_response();
+        match &hit.content_type {
+            Some(ct) => res.headers_mut().insert(header::CONTENT_TYPE, ct.clone()),
+            None => res.headers_mut().remove(header::CONTENT_TYPE),
+        };
+        if let Some(n) = hit.n_used {
+            res.extensions_mut().insert(n);
+        }
+        return with_etag(res, &etag);
+    }
+
+    let res = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if res.status() != StatusCode::OK {
+        return res;
+    }
+    // Only buffer bodies of known size within the cache budget
+    let storable = res
+        .body()
+        .size_hint()
+        .exact()
+        .is_some_and(|n| n <= state.config.cache.max_bytes as u64);
+    if !storable {
+        return with_etag(res, &etag);
+    }
+
+    let (parts, body) = res.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(b) => b,
+        Err(e) => return ServiceError::Internal(format!("response body: {e}")).into_response(),
+    };
+    state.cache.insert(
+        key,
+        CachedResponse {
+            content_type: parts.headers.get(header::CONTENT_TYPE).cloned(),
+            n_used: parts.extensions.get::<NUsed>().copied(),
+            body: bytes.clone(),
+        },
+    );
+    with_etag(Response::from_parts(parts, Body::from(bytes)), &etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_covers_route_tenant_format_and_body() {
+        let json = HeaderMap::new();
+        let mut csv = HeaderMap::new();
+        csv.insert(header::ACCEPT, HeaderValue::from_static("text/csv"));
+        let t = Tenant::default();
+        let k = |uri, h: &HeaderMap, body: &[u8]| request_key(&Method::POST, uri, &t, h, body);
+
+        let base = k("/api/v1/stats/summary", &json, b"[1,2]");
+        assert_eq!(base, k("/api/v1/stats/summary", &json, b"[1,2]"));
+        assert_ne!(base, k("/api/v2/stats/summary", &json, b"[1,2]"));
+        assert_ne!(base, k("/api/v1/stats/summary", &csv, b"[1,2]"));
+        assert_ne!(base, k("/api/v1/stats/summary", &json, b"[1,3]"));
+        let other = Tenant::new("team-b").unwrap();
+        assert_ne!(
+            base,
+            request_key(
+                &Method::POST,
+                "/api/v1/stats/summary",
+                &other,
+                &json,
+                b"[1,2]"
+            )
+        );
+    }
+
+    #[test]
+    fn if_none_match_lists_tags_but_not_wildcards() {
+        let tag = "\"00ff\"";
+        let with = |v: &'static str| {
+            let mut h = HeaderMap::new();
+            h.insert(header::IF_NONE_MATCH, HeaderValue::from_static(v));
+            h
+        };
+        assert!(matches(&with("\"00ff\""), tag));
+        assert!(matches(&with("\"abcd\", W/\"00ff\""), tag));
+        assert!(!matches(&with("*"), tag));
+        assert!(!matches(&with("\"abcd\""), tag));
+        assert!(!matches(&HeaderMap::new(), tag));
+    }
+}
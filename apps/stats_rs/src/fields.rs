@@ -0,0 +1,125 @@
+//! # Response field selection
+//!
+//! Endpoints returning a set of independent metrics (`/stats/summary`,
+//! `/stats/pairwise`) take `?fields=a,b`: only the named metrics are computed
+//! and the others are left out of the response. Skipping a metric skips its
+//! work, e.g. `?fields=pearson` avoids Kendall's O(n²) pass. Bookkeeping
+//! fields (`count`, `missing`) are always returned.
+
+use crate::{error::ServiceError, routes::export::Table};
+use serde::{Deserialize, Serialize, Serializer, ser::Error as _};
+use utoipa::IntoParams;
+
+/// `?fields=` for endpoints with selectable metrics.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FieldsQuery {
+    /// Comma-separated metrics to compute and return (default: all)
+    pub fields: Option<String>,
+}
+
+/// The metrics a request asked for out of an endpoint's `known` ones.
+#[derive(Debug, Clone, Default)]
+pub struct Fields {
+    known: &'static [&'static str],
+    /// `None` = all
+    selected: Option<Vec<&'static str>>,
+}
+
+impl Fields {
+    /// Every metric.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Parse a comma-separated list; absent or blank selects everything.
+    pub fn parse(list: Option<&str>, known: &'static [&'static str]) -> Result<Self, ServiceError> {
+        let Some(list) = list.filter(|l| !l.trim().is_empty()) else {
+            return Ok(Self {
+                known,
+                selected: None,
+            });
+        };
+        let selected = list
+            .split(',')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(|f| {
+                known.iter().copied().find(|k| *k == f).ok_or_else(|| {
+                    ServiceError::InvalidInput(format!(
+                        "fields: unknown field '{f}' (expected {})",
+                        known.join(", ")
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            known,
+            selected: Some(selected),
+        })
+    }
+
+    /// Whether the metric `name` should be computed.
+    pub fn wants(&self, name: &str) -> bool {
+        self.selected.as_ref().is_none_or(|s| s.contains(&name))
+    }
+
+    /// Whether a response key survives: bookkeeping or a wanted metric.
+    fn keeps(&self, key: &str) -> bool {
+        !self.known.contains(&key) || self.wants(key)
+    }
+}
+
+/// A result reduced to the selected [`Fields`]: JSON objects lose the
+/// unselected metric keys, and [`Table`] rows whose first cell names one are
+/// dropped.
+pub struct Selected<T>(pub Fields, pub T);
+
+impl<T: Serialize> Serialize for Selected<T> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut v = serde_json::to_value(&self.1).map_err(S::Error::custom)?;
+        if let Some(obj) = v.as_object_mut() {
+            obj.retain(|k, _| self.0.keeps(k));
+        }
+        v.serialize(s)
+    }
+}
+
+impl<T: Table> Table for Selected<T> {
+    fn header(&self) -> Vec<String> {
+        self.1.header()
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let mut rows = self.1.rows();
+        rows.retain(|r| r.first().is_none_or(|k| self.0.keeps(k)));
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const KNOWN: &[&str] = &["mean", "std"];
+
+    #[test]
+    fn parses_and_rejects_unknown_fields() {
+        let f = Fields::parse(Some(" std ,"), KNOWN).unwrap();
+        assert!(f.wants("std") && !f.wants("mean"));
+        assert!(Fields::parse(Some(""), KNOWN).unwrap().wants("mean"));
+        assert!(Fields::parse(None, KNOWN).unwrap().wants("mean"));
+        assert!(Fields::parse(Some("mean,kendall"), KNOWN).is_err());
+    }
+
+    #[test]
+    fn drops_unselected_metrics_only() {
+        let f = Fields::parse(Some("std"), KNOWN).unwrap();
+        let v = json!({"count": 3, "mean": null, "std": 1.0});
+        assert_eq!(
+            serde_json::to_value(Selected(f, v)).unwrap(),
+            json!({"count": 3, "std": 1.0})
+        );
+    }
+}
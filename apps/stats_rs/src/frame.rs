@@ -0,0 +1,313 @@
+//! # Columnar frames
+//!
+//! [`Frame`] is the typed, column-major view multi-column endpoints work on:
+//! numeric columns are parsed once into `Option<f64>` cells (`None` = empty or
+//! unparseable), text columns keep their trimmed strings. It is built from an
+//! ingested [`CsvTable`] and offers the operations those endpoints share, such
+//! as pairwise-complete correlation matrices.
+//!
+//! With the `polars` feature, frames convert to and from a polars `DataFrame`
+//! (see [`Frame::to_polars`]) for lazy or parallel execution.
+
+use crate::{
+    ingest::CsvTable,
+    stats::prelude::*,
+    types::{ColumnSchema, ColumnType, CorrMethod},
+};
+
+/// Cell storage of one [`FrameColumn`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnData {
+    Numeric(Vec<Option<f64>>),
+    /// Empty strings are missing cells
+    Text(Vec<String>),
+}
+
+/// One named, typed column.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameColumn {
+    pub name: String,
+    /// 0-based position in the source file
+    pub index: usize,
+    pub dtype: ColumnType,
+    pub data: ColumnData,
+}
+
+impl FrameColumn {
+    pub fn len(&self) -> usize {
+        match &self.data {
+            ColumnData::Numeric(v) => v.len(),
+            ColumnData::Text(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self.data, ColumnData::Numeric(_))
+    }
+
+    /// Non-missing cells.
+    pub fn non_empty(&self) -> usize {
+        match &self.data {
+            ColumnData::Numeric(v) => v.iter().flatten().count(),
+            ColumnData::Text(v) => v.iter().filter(|s| !s.is_empty()).count(),
+        }
+    }
+
+    pub fn schema(&self) -> ColumnSchema {
+        let non_empty = self.non_empty();
+        ColumnSchema {
+            name: self.name.clone(),
+            index: self.index,
+            dtype: self.dtype,
+            non_empty,
+            missing: self.len() - non_empty,
+        }
+    }
+
+    /// The present values of a numeric column, in row order (empty for text).
+    pub fn values(&self) -> Vec<f64> {
+        match &self.data {
+            ColumnData::Numeric(v) => v.iter().flatten().copied().collect(),
+            ColumnData::Text(_) => Vec::new(),
+        }
+    }
+
+    /// Cell `i` as text (numbers are formatted), `""` when missing.
+    pub fn text(&self, i: usize) -> String {
+        match &self.data {
+            ColumnData::Numeric(v) => v[i].map(|x| x.to_string()).unwrap_or_default(),
+            ColumnData::Text(v) => v[i].clone(),
+        }
+    }
+}
+
+/// Column-major table with a common row count.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    columns: Vec<FrameColumn>,
+    n_rows: usize,
+}
+
+impl Frame {
+    /// Assemble a frame; every column must have `n_rows` cells.
+    pub fn new(columns: Vec<FrameColumn>) -> Self {
+        let n_rows = columns.first().map_or(0, FrameColumn::len);
+        assert!(
+            columns.iter().all(|c| c.len() == n_rows),
+            "frame columns differ in length"
+        );
+        Self { columns, n_rows }
+    }
+
+    /// Type an ingested table: integer/float columns become numeric (cells
+    /// parsed under the table's inference settings), the rest stay text.
+    pub fn from_table(table: &CsvTable) -> Self {
+        let columns = table
+            .columns
+            .iter()
+            .map(|c| FrameColumn {
+                name: c.name.clone(),
+                index: c.index,
+                dtype: c.dtype,
+                data: match c.dtype {
+                    ColumnType::Integer | ColumnType::Float => {
+                        ColumnData::Numeric(c.cells.iter().map(|s| table.infer.number(s)).collect())
+                    }
+                    _ => ColumnData::Text(c.cells.clone()),
+                },
+            })
+            .collect();
+        Self {
+            columns,
+            n_rows: table.n_rows,
+        }
+    }
+
+    pub fn n_rows(&self) -> usize {
+        self.n_rows
+    }
+
+    pub fn columns(&self) -> &[FrameColumn] {
+        &self.columns
+    }
+
+    pub fn column(&self, name: &str) -> Option<&FrameColumn> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+
+    pub fn numeric_columns(&self) -> impl Iterator<Item = &FrameColumn> {
+        self.columns.iter().filter(|c| c.is_numeric())
+    }
+
+    /// Empty cells over all cells.
+    pub fn missing_rate(&self) -> f64 {
+        let cells = self.n_rows * self.columns.len();
+        if cells == 0 {
+            return 0.0;
+        }
+        let missing: usize = self.columns.iter().map(|c| c.len() - c.non_empty()).sum();
+        missing as f64 / cells as f64
+    }
+
+    /// Row-major `m×m` correlation matrix over the numeric columns, each pair
+    /// using the rows where both are present. Undefined pairs are `0.0`.
+    pub fn corr_matrix(&self, method: CorrMethod) -> (Vec<String>, Vec<f64>) {
+        let cols: Vec<(&str, &[Option<f64>])> = self
+            .columns
+            .iter()
+            .filter_map(|c| match &c.data {
+                ColumnData::Numeric(v) => Some((c.name.as_str(), v.as_slice())),
+                ColumnData::Text(_) => None,
+            })
+            .collect();
+        let m = cols.len();
+        let mut matrix = vec![0.0; m * m];
+        for i in 0..m {
+            matrix[i * m + i] = 1.0;
+            for j in (i + 1)..m {
+                let (xs, ys): (Vec<f64>, Vec<f64>) = cols[i]
+                    .1
+                    .iter()
+                    .zip(cols[j].1)
+                    .filter_map(|(a, b)| Some(((*a)?, (*b)?)))
+                    .unzip();
+                let r = match method {
+                    CorrMethod::Pearson => pearson_correlation(&xs, &ys),
+                    CorrMethod::Spearman => spearman_rho(&xs, &ys),
+                    CorrMethod::Kendall => kendall_tau_b(&xs, &ys),
+                };
+                let r = if r.is_nan() { 0.0 } else { r };
+                matrix[i * m + j] = r;
+                matrix[j * m + i] = r;
+            }
+        }
+        (cols.iter().map(|(n, _)| n.to_string()).collect(), matrix)
+    }
+}
+
+#[cfg(feature = "polars")]
+mod polars_interop {
+    use super::{ColumnData, Frame, FrameColumn};
+    use crate::{error::ServiceError, types::ColumnType};
+    use polars::prelude::{Column, DataFrame, DataType, NamedFrom, PlSmallStr, Series};
+
+    impl Frame {
+        /// Copy into a polars `DataFrame` (numeric → `Float64`, text → `String`
+        /// with missing cells as nulls).
+        pub fn to_polars(&self) -> Result<DataFrame, ServiceError> {
+            let columns: Vec<Column> = self
+                .columns
+                .iter()
+                .map(|c| {
+                    let name = PlSmallStr::from(c.name.as_str());
+                    let s = match &c.data {
+                        ColumnData::Numeric(v) => Series::new(name, v.as_slice()),
+                        ColumnData::Text(v) => {
+                            let cells: Vec<Option<&str>> = v
+                                .iter()
+                                .map(|s| (!s.is_empty()).then_some(s.as_str()))
+                                .collect();
+                            Series::new(name, cells)
+                        }
+                    };
+                    s.into()
+                })
+                .collect();
+            DataFrame::new(columns).map_err(|e| ServiceError::InvalidInput(format!("polars: {e}")))
+        }
+
+        /// Copy back from polars: numeric dtypes become `Float` columns, strings
+        /// stay text, anything else is rejected.
+        pub fn from_polars(df: &DataFrame) -> Result<Self, ServiceError> {
+            let err =
+                |e: polars::error::PolarsError| ServiceError::InvalidInput(format!("polars: {e}"));
+            let columns = df
+                .get_columns()
+                .iter()
+                .enumerate()
+                .map(|(index, col)| {
+                    let name = col.name().to_string();
+                    let (dtype, data) = if col.dtype().is_primitive_numeric() {
+                        let f = col.cast(&DataType::Float64).map_err(err)?;
+                        let v = f.f64().map_err(err)?.into_iter().collect();
+                        (ColumnType::Float, ColumnData::Numeric(v))
+                    } else if col.dtype() == &DataType::String {
+                        let v = col
+                            .str()
+                            .map_err(err)?
+                            .into_iter()
+                            .map(|s| s.unwrap_or_default().to_string())
+                            .collect();
+                        (ColumnType::String, ColumnData::Text(v))
+                    } else {
+                        return Err(ServiceError::InvalidInput(format!(
+                            "polars column '{name}' has unsupported type {}",
+                            col.dtype()
+                        )));
+                    };
+                    Ok(FrameColumn {
+                        name,
+                        index,
+                        dtype,
+                        data,
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(Frame::new(columns))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn round_trips_through_polars() {
+            let f = Frame::new(vec![
+                FrameColumn {
+                    name: "x".into(),
+                    index: 0,
+                    dtype: ColumnType::Float,
+                    data: ColumnData::Numeric(vec![Some(1.5), None]),
+                },
+                FrameColumn {
+                    name: "tag".into(),
+                    index: 1,
+                    dtype: ColumnType::String,
+                    data: ColumnData::Text(vec!["a".into(), String::new()]),
+                },
+            ]);
+            let df = f.to_polars().unwrap();
+            assert_eq!(df.shape(), (2, 2));
+            assert_eq!(Frame::from_polars(&df).unwrap(), f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::{CsvOptions, read_csv};
+
+    #[test]
+    fn types_table_and_pairs_complete_rows() {
+        let csv = "a,b,label\n1,2,x\n2,,y\n3,6,\n4,8,z\n";
+        let f = Frame::from_table(&read_csv(csv.as_bytes(), &CsvOptions::default()).unwrap());
+        assert_eq!(f.n_rows(), 4);
+        assert_eq!(f.numeric_columns().count(), 2);
+        assert_eq!(f.column("b").unwrap().values(), vec![2.0, 6.0, 8.0]);
+        assert_eq!(f.column("label").unwrap().schema().missing, 1);
+        assert_eq!(f.column("b").unwrap().text(1), "");
+        assert!((f.missing_rate() - 2.0 / 12.0).abs() < 1e-12);
+
+        let (names, m) = f.corr_matrix(CorrMethod::Pearson);
+        assert_eq!(names, ["a", "b"]);
+        // rows 0, 2, 3 are complete and exactly linear
+        assert!((m[1] - 1.0).abs() < 1e-12);
+        assert_eq!(m[1], m[2]);
+    }
+}
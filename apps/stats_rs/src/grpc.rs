@@ -0,0 +1,525 @@
+//! # gRPC service (feature `grpc`)
+//!
+//! Serves `stats.v1.Stats` (`proto/stats.proto`) on the HTTP port for
+//! internal callers that prefer binary RPC: summary, distribution, pairwise,
+//! corr-matrix, normalize and, with the `rag` feature, RAG metrics. Each call
+//! converts its message to the matching route's DTO, runs the same
+//! validation and computation — on the [`ComputePool`](crate::compute::ComputePool),
+//! with its concurrency limit and cancellation — and converts the result
+//! back, so the two surfaces answer alike.
+//!
+//! gRPC clients connect with HTTP/2 prior knowledge (`h2c`), which the
+//! server accepts next to HTTP/1.1. Repeated `double` fields mark missing
+//! values with NaN, settled by the request's `missing` policy. Failures carry
+//! the status code nearest the HTTP one (`INVALID_ARGUMENT` for `400`/`422`)
+//! with the [`ErrorResponse`](crate::types::ErrorResponse) JSON as details.
+//!
+//! Calls are assigned a tenant by the same rules as HTTP requests
+//! ([`tenant::resolve`] on the call's metadata): with API keys configured, a
+//! missing or unknown `x-api-key` is `UNAUTHENTICATED`.
+
+use crate::{
+    error::ServiceError,
+    fields::Fields,
+    missing::resolve,
+    routes::{
+        stats_corr_matrix::corr_matrix, stats_distribution::distribution,
+        stats_normalize::normalize, stats_pairwise::pairwise, stats_summary::summarize,
+    },
+    state::AppState,
+    tenant::{self, Tenant},
+    types::{
+        CorrMatrixIn, CorrMatrixOut, CorrMethod, CorrTest, DistIn, DistOut, MissingPolicy,
+        MissingReport, NormMethod, NormalizeIn, NormalizeOut, PairIn, PairOut, SummaryIn,
+        SummaryOut,
+    },
+    validate::Validate,
+};
+use std::sync::Arc;
+use tonic::{
+    Code, Request, Response, Status, server::NamedService, service::interceptor::InterceptedService,
+};
+
+/// Code generated from `proto/stats.proto`, including a `StatsClient`.
+pub mod proto {
+    tonic::include_proto!("stats.v1");
+}
+
+use proto::stats_server::{Stats, StatsServer};
+
+/// Router serving `stats.v1.Stats`, to be merged into the HTTP app. Only the
+/// service's own paths are claimed, so other paths keep the app's `404`.
+pub fn router(state: Arc<AppState>) -> axum::Router {
+    let limit = state.config.max_decompressed_body_bytes;
+    let svc = StatsServer::new(StatsService {
+        state: state.clone(),
+    })
+    .max_decoding_message_size(limit);
+    let svc = InterceptedService::new(svc, move |req| authenticate(&state, req));
+    let path = format!("/{}/{{*rpc}}", StatsServer::<StatsService>::NAME);
+    axum::Router::new().route_service(&path, svc)
+}
+
+/// Implementation of `stats.v1.Stats` over the shared [`AppState`].
+#[derive(Debug, Clone)]
+pub struct StatsService {
+    state: Arc<AppState>,
+}
+
+/// gRPC status for a [`ServiceError`].
+pub fn status(e: ServiceError) -> Status {
+    let code = match e {
+        ServiceError::NotFound(_) => Code::NotFound,
+        ServiceError::Unauthorized(_) => Code::Unauthenticated,
+        ServiceError::Forbidden(_) => Code::PermissionDenied,
+        ServiceError::Conflict(_) => Code::FailedPrecondition,
+        ServiceError::TooLarge(_) | ServiceError::QuotaExceeded(_) => Code::ResourceExhausted,
+        ServiceError::Upstream(_) => Code::Unavailable,
+        ServiceError::Internal(_) => Code::Internal,
+        ServiceError::Cancelled => Code::Cancelled,
+        _ => Code::InvalidArgument,
+    };
+    let details = serde_json::to_vec(&e.to_response()).unwrap_or_default();
+    Status::with_details(code, e.to_string(), details.into())
+}
+
+/// Attach the call's [`Tenant`], or refuse the call as the HTTP tenant
+/// middleware would refuse the request.
+fn authenticate(state: &AppState, mut req: Request<()>) -> Result<Request<()>, Status> {
+    let headers = req.metadata().clone().into_headers();
+    let tenant: Tenant = tenant::resolve(state, &headers).map_err(status)?;
+    req.extensions_mut().insert(tenant);
+    Ok(req)
+}
+
+impl StatsService {
+    /// Validate a converted request against the service limits.
+    fn check<T: Validate>(&self, inp: T) -> Result<T, Status> {
+        inp.validate(&self.state.config).map_err(status)?;
+        Ok(inp)
+    }
+}
+
+#[tonic::async_trait]
+impl Stats for StatsService {
+    async fn summary(
+        &self,
+        req: Request<proto::SummaryRequest>,
+    ) -> Result<Response<proto::SummaryReply>, Status> {
+        let inp = self.check(SummaryIn::from(req.into_inner()))?;
+        let out = self
+            .state
+            .compute
+            .run(inp.values.len(), move |_| {
+                let r = resolve(inp.values, inp.missing.unwrap_or_default())?;
+                let mut out = summarize(&r.values, &Fields::all(), inp.percentiles.as_deref());
+                out.missing = Some(r.report);
+                Ok(out)
+            })
+            .await
+            .map_err(status)?;
+        Ok(Response::new(out.into()))
+    }
+
+    async fn distribution(
+        &self,
+        req: Request<proto::DistributionRequest>,
+    ) -> Result<Response<proto::DistributionReply>, Status> {
+        let inp = self.check(DistIn::from(req.into_inner()))?;
+        let seed = self.state.config.seed;
+        let out = self
+            .state
+            .compute
+            .run(inp.values.len(), move |cancel| {
+                distribution(inp, seed, cancel)
+            })
+            .await
+            .map_err(status)?;
+        Ok(Response::new(out.into()))
+    }
+
+    async fn pairwise(
+        &self,
+        req: Request<proto::PairwiseRequest>,
+    ) -> Result<Response<proto::PairwiseReply>, Status> {
+        let inp = self.check(PairIn::from(req.into_inner()))?;
+        let st = self.state.clone();
+        let out = self
+            .state
+            .compute
+            .run(inp.x.len() + inp.y.len(), move |_| {
+                pairwise(&st, inp, &Fields::all())
+            })
+            .await
+            .map_err(status)?;
+        Ok(Response::new(out.into()))
+    }
+
+    async fn corr_matrix(
+        &self,
+        req: Request<proto::CorrMatrixRequest>,
+    ) -> Result<Response<proto::CorrMatrixReply>, Status> {
+        let inp = self.check(CorrMatrixIn::from(req.into_inner()))?;
+        let size = inp.series.iter().map(Vec::len).sum();
+        let st = self.state.clone();
+        let out = self
+            .state
+            .compute
+            .run(size, move |cancel| corr_matrix(&st, inp, cancel))
+            .await
+            .map_err(status)?;
+        Ok(Response::new(out.into()))
+    }
+
+    async fn normalize(
+        &self,
+        req: Request<proto::NormalizeRequest>,
+    ) -> Result<Response<proto::NormalizeReply>, Status> {
+        let inp = self.check(NormalizeIn::from(req.into_inner()))?;
+        let out = self
+            .state
+            .compute
+            .run(inp.values.len(), move |_| normalize(inp))
+            .await
+            .map_err(status)?;
+        Ok(Response::new(out.into()))
+    }
+
+    #[cfg(feature = "rag")]
+    async fn rag_metrics(
+        &self,
+        req: Request<proto::RagMetricsRequest>,
+    ) -> Result<Response<proto::RagMetricsReply>, Status> {
+        if !self.state.config.features.rag {
+            return Err(Status::unimplemented("RAG metrics are disabled"));
+        }
+        let inp = crate::types::RagMetricsIn::from(req.into_inner());
+        let out = crate::routes::stats_rag::rag_metrics(inp).map_err(status)?;
+        Ok(Response::new(out.into()))
+    }
+
+    #[cfg(not(feature = "rag"))]
+    async fn rag_metrics(
+        &self,
+        _: Request<proto::RagMetricsRequest>,
+    ) -> Result<Response<proto::RagMetricsReply>, Status> {
+        Err(Status::unimplemented("RAG metrics need the `rag` feature"))
+    }
+}
+
+// ---- message <-> DTO conversions ----
+
+fn policy(p: proto::MissingPolicy) -> Option<MissingPolicy> {
+    use proto::MissingPolicy as P;
+    match p {
+        P::Unspecified => None,
+        P::Error => Some(MissingPolicy::Error),
+        P::Drop => Some(MissingPolicy::Drop),
+        P::ImputeMean => Some(MissingPolicy::ImputeMean),
+        P::ImputeMedian => Some(MissingPolicy::ImputeMedian),
+        P::ImputeZero => Some(MissingPolicy::ImputeZero),
+        P::Pairwise => Some(MissingPolicy::Pairwise),
+    }
+}
+
+fn report(r: Option<MissingReport>) -> Option<proto::MissingReport> {
+    use proto::MissingPolicy as P;
+    r.map(|r| proto::MissingReport {
+        policy: match r.policy {
+            MissingPolicy::Error => P::Error,
+            MissingPolicy::Drop => P::Drop,
+            MissingPolicy::ImputeMean => P::ImputeMean,
+            MissingPolicy::ImputeMedian => P::ImputeMedian,
+            MissingPolicy::ImputeZero => P::ImputeZero,
+            MissingPolicy::Pairwise => P::Pairwise,
+        } as i32,
+        count: r.count as u64,
+    })
+}
+
+impl From<proto::SummaryRequest> for SummaryIn {
+    fn from(m: proto::SummaryRequest) -> Self {
+        Self {
+            missing: policy(m.missing()),
+            values: m.values,
+            approx: None,
+            seed: None,
+            percentiles: (!m.percentiles.is_empty()).then_some(m.percentiles),
+            quantile_method: None,
+        }
+    }
+}
+
+impl From<SummaryOut> for proto::SummaryReply {
+    fn from(o: SummaryOut) -> Self {
+        Self {
+            count: o.count as u64,
+            mean: o.mean,
+            median: o.median,
+            std: o.std,
+            min: o.min,
+            max: o.max,
+            iqr: o.iqr,
+            mad: o.mad,
+            missing: report(o.missing),
+            cv: o.cv,
+            sem: o.sem,
+            skewness: o.skewness,
+            excess_kurtosis: o.excess_kurtosis,
+            percentiles: o
+                .percentiles
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(p, value)| proto::Quantile { p, value })
+                .collect(),
+        }
+    }
+}
+
+impl From<proto::DistributionRequest> for DistIn {
+    fn from(m: proto::DistributionRequest) -> Self {
+        Self {
+            missing: policy(m.missing()),
+            values: m.values,
+            weights: None,
+            bins: m.bins.map(|b| b as usize),
+            edges: None,
+            scale: None,
+            quantiles: (!m.quantiles.is_empty()).then_some(m.quantiles),
+            quantile_method: None,
+            approx: None,
+            seed: None,
+            shape_se: None,
+            resamples: None,
+            density: None,
+            kde: None,
+            bandwidth: None,
+        }
+    }
+}
+
+impl From<DistOut> for proto::DistributionReply {
+    fn from(o: DistOut) -> Self {
+        Self {
+            counts: o.counts.into_iter().map(|c| c as u64).collect(),
+            edges: o.edges,
+            quantiles: o
+                .quantiles
+                .into_iter()
+                .map(|(p, value)| proto::Quantile { p, value })
+                .collect(),
+            skewness: o.skewness,
+            excess_kurtosis: o.excess_kurtosis,
+            entropy_bits: o.entropy_bits,
+            missing: report(o.missing),
+        }
+    }
+}
+
+impl From<proto::PairwiseRequest> for PairIn {
+    fn from(m: proto::PairwiseRequest) -> Self {
+        Self {
+            missing: policy(m.missing()),
+            x: m.x,
+            y: m.y,
+            confidence: m.confidence,
+        }
+    }
+}
+
+impl From<CorrTest> for proto::CorrTest {
+    fn from(t: CorrTest) -> Self {
+        Self {
+            p_value: t.p_value,
+            lower: t.lower,
+            upper: t.upper,
+        }
+    }
+}
+
+impl From<PairOut> for proto::PairwiseReply {
+    fn from(o: PairOut) -> Self {
+        Self {
+            covariance: o.covariance,
+            pearson: o.pearson,
+            spearman: o.spearman,
+            kendall: o.kendall,
+            missing: report(o.missing),
+            n: o.n as u64,
+            confidence: o.confidence,
+            pearson_test: o.pearson_test.map(Into::into),
+            spearman_test: o.spearman_test.map(Into::into),
+            kendall_test: o.kendall_test.map(Into::into),
+        }
+    }
+}
+
+impl From<proto::CorrMatrixRequest> for CorrMatrixIn {
+    fn from(m: proto::CorrMatrixRequest) -> Self {
+        use proto::CorrMethod as P;
+        Self {
+            missing: policy(m.missing()),
+            method: match m.method() {
+                P::Unspecified => None,
+                P::Pearson => Some(CorrMethod::Pearson),
+                P::Spearman => Some(CorrMethod::Spearman),
+                P::Kendall => Some(CorrMethod::Kendall),
+            },
+            series: m.series.into_iter().map(|s| s.values).collect(),
+            names: (!m.names.is_empty()).then_some(m.names),
+            p_values: m.p_values.then_some(true),
+            cluster: None,
+            covariance: None,
+            partial: None,
+        }
+    }
+}
+
+impl From<CorrMatrixOut> for proto::CorrMatrixReply {
+    fn from(o: CorrMatrixOut) -> Self {
+        Self {
+            size: o.size as u64,
+            names: o.names.unwrap_or_default(),
+            matrix: o.matrix,
+            missing: report(o.missing),
+            p_values: o.p_values.unwrap_or_default(),
+            n_used: o
+                .n_used
+                .unwrap_or_default()
+                .into_iter()
+                .map(|n| n as u64)
+                .collect(),
+        }
+    }
+}
+
+impl From<proto::NormalizeRequest> for NormalizeIn {
+    fn from(m: proto::NormalizeRequest) -> Self {
+        use proto::NormMethod as P;
+        Self {
+            missing: policy(m.missing()),
+            method: match m.method() {
+                P::Unspecified => None,
+                P::Zscore => Some(NormMethod::Zscore),
+                P::Minmax => Some(NormMethod::Minmax),
+            },
+            range: m.range.map(|r| (r.lower, r.upper)),
+            window: None,
+            groups: None,
+            values: m.values,
+        }
+    }
+}
+
+impl From<NormalizeOut> for proto::NormalizeReply {
+    fn from(o: NormalizeOut) -> Self {
+        Self {
+            values: o.values,
+            missing: report(o.missing),
+        }
+    }
+}
+
+#[cfg(feature = "rag")]
+mod rag {
+    use super::proto;
+    use crate::types::{Aggregation, RagMetricsIn, RagMetricsOut, RagQuery};
+
+    fn ids(xs: Vec<u64>) -> Vec<usize> {
+        xs.into_iter().map(|x| x as usize).collect()
+    }
+
+    fn nan_for_none(xs: Vec<Option<f64>>) -> Vec<f64> {
+        xs.into_iter().map(|x| x.unwrap_or(f64::NAN)).collect()
+    }
+
+    impl From<proto::RagMetricsRequest> for RagMetricsIn {
+        fn from(m: proto::RagMetricsRequest) -> Self {
+            use proto::Aggregation as P;
+            Self {
+                aggregation: match m.aggregation() {
+                    P::Unspecified => None,
+                    P::Mean => Some(Aggregation::Mean),
+                    P::Median => Some(Aggregation::Median),
+                    P::Percentile => Some(Aggregation::Percentile),
+                },
+                queries: m
+                    .queries
+                    .into_iter()
+                    .map(|q| RagQuery {
+                        retrieved: ids(q.retrieved),
+                        relevant: ids(q.relevant),
+                    })
+                    .collect(),
+                k: m.k.map(|k| k as usize),
+                percentile: m.percentile,
+            }
+        }
+    }
+
+    impl From<RagMetricsOut> for proto::RagMetricsReply {
+        fn from(o: RagMetricsOut) -> Self {
+            use proto::Aggregation as P;
+            let q = o.per_query;
+            Self {
+                n_queries: o.n_queries as u64,
+                k: o.k as u64,
+                aggregation: match o.aggregation {
+                    Aggregation::Mean => P::Mean,
+                    Aggregation::Median => P::Median,
+                    Aggregation::Percentile => P::Percentile,
+                } as i32,
+                precision_at_k: o.precision_at_k,
+                recall_at_k: o.recall_at_k,
+                mrr: o.mrr,
+                ndcg_at_k: o.ndcg_at_k,
+                map: o.map,
+                per_query: Some(proto::RagPerQuery {
+                    precision_at_k: nan_for_none(q.precision_at_k),
+                    recall_at_k: nan_for_none(q.recall_at_k),
+                    reciprocal_rank: nan_for_none(q.reciprocal_rank),
+                    ndcg_at_k: nan_for_none(q.ndcg_at_k),
+                    average_precision: nan_for_none(q.average_precision),
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unspecified_enums_keep_the_json_defaults() {
+        let inp = CorrMatrixIn::from(proto::CorrMatrixRequest {
+            series: vec![proto::Series {
+                values: vec![1.0, f64::NAN],
+            }],
+            ..Default::default()
+        });
+        assert!(inp.method.is_none() && inp.missing.is_none() && inp.names.is_none());
+        assert!(inp.series[0][1].is_nan());
+
+        let inp = NormalizeIn::from(proto::NormalizeRequest {
+            method: proto::NormMethod::Minmax as i32,
+            missing: proto::MissingPolicy::ImputeZero as i32,
+            ..Default::default()
+        });
+        assert!(matches!(inp.method, Some(NormMethod::Minmax)));
+        assert_eq!(inp.missing, Some(MissingPolicy::ImputeZero));
+    }
+
+    #[test]
+    fn errors_map_to_grpc_codes() {
+        let s = status(crate::validate::invalid("/y", "must not be empty"));
+        assert_eq!(s.code(), Code::InvalidArgument);
+        let body: serde_json::Value = serde_json::from_slice(s.details()).unwrap();
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(body["details"]["field"], "/y");
+        assert_eq!(
+            status(ServiceError::Internal("x".into())).code(),
+            Code::Internal
+        );
+    }
+}
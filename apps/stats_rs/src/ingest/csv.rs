@@ -0,0 +1,708 @@
+//! CSV ingestion: dialects, column selection, row skipping and per-column type inference.
+
+use super::{
+    datetime::{DayFirst, parse_datetime},
+    sample::{Reservoir, RowSample},
+};
+use crate::{
+    error::ServiceError,
+    types::{ColumnSchema, ColumnType, CsvQuery, SampleOut},
+};
+use std::borrow::Cow;
+
+/// Types the inference pass may assign; anything else is read as `String`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InferTypes {
+    pub integers: bool,
+    pub floats: bool,
+    pub booleans: bool,
+    pub dates: bool,
+}
+
+impl Default for InferTypes {
+    fn default() -> Self {
+        Self {
+            integers: true,
+            floats: true,
+            booleans: true,
+            dates: true,
+        }
+    }
+}
+
+impl InferTypes {
+    /// Read every column as string.
+    pub fn none() -> Self {
+        Self {
+            integers: false,
+            floats: false,
+            booleans: false,
+            dates: false,
+        }
+    }
+
+    /// Parse a comma-separated list such as `int,float` (or `none`).
+    pub fn parse(s: &str) -> Result<Self, ServiceError> {
+        let mut t = Self::none();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.to_ascii_lowercase().as_str() {
+                "int" | "integer" | "integers" => t.integers = true,
+                "float" | "floats" => t.floats = true,
+                "bool" | "boolean" | "booleans" => t.booleans = true,
+                "date" | "dates" => t.dates = true,
+                "none" => {}
+                other => {
+                    return Err(ServiceError::InvalidInput(format!(
+                        "unknown infer type '{other}' (expected int, float, bool, date or none)"
+                    )));
+                }
+            }
+        }
+        Ok(t)
+    }
+
+    /// Parse a cell as a number under the enabled numeric types.
+    pub fn number(&self, cell: &str) -> Option<f64> {
+        if self.floats {
+            cell.parse::<f64>().ok().filter(|x| x.is_finite())
+        } else if self.integers {
+            cell.parse::<i64>().ok().map(|x| x as f64)
+        } else {
+            None
+        }
+    }
+}
+
+/// Column selector: header name or 0-based index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColumnRef {
+    Name(String),
+    Index(usize),
+}
+
+/// Field separator, quoting and number locale of a delimited file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CsvDialect {
+    pub delimiter: u8,
+    /// Quote character; `None` disables quoting
+    pub quote: Option<u8>,
+    /// Numbers use `,` as the decimal mark and `.` as the thousands separator
+    pub decimal_comma: bool,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: Some(b'"'),
+            decimal_comma: false,
+        }
+    }
+}
+
+/// Single ASCII character option such as `delimiter=;`.
+fn ascii_char(field: &str, s: &str) -> Result<u8, ServiceError> {
+    match s.as_bytes() {
+        [b] if b.is_ascii() && !b.is_ascii_alphanumeric() => Ok(*b),
+        _ => Err(ServiceError::InvalidInput(format!(
+            "{field} must be a single punctuation character, got '{s}'"
+        ))),
+    }
+}
+
+impl CsvDialect {
+    /// Build from the `delimiter`, `quote` and `decimal` query options.
+    pub fn parse(
+        delimiter: Option<&str>,
+        quote: Option<&str>,
+        decimal: Option<&str>,
+    ) -> Result<Self, ServiceError> {
+        let d = Self::default();
+        let delimiter = match delimiter {
+            None => d.delimiter,
+            Some(s) => match s.to_ascii_lowercase().as_str() {
+                "comma" => b',',
+                "semicolon" => b';',
+                "tab" | "\t" | "\\t" => b'\t',
+                "pipe" => b'|',
+                _ => ascii_char("delimiter", s)?,
+            },
+        };
+        let quote = match quote {
+            None => d.quote,
+            Some(s) if s.eq_ignore_ascii_case("none") => None,
+            Some(s) => Some(ascii_char("quote", s)?),
+        };
+        let decimal_comma = match decimal.map(str::to_ascii_lowercase).as_deref() {
+            None | Some("." | "dot" | "point") => false,
+            Some("," | "comma") => true,
+            Some(other) => {
+                return Err(ServiceError::InvalidInput(format!(
+                    "decimal must be '.' or ',', got '{other}'"
+                )));
+            }
+        };
+        if quote == Some(delimiter) {
+            return Err(ServiceError::InvalidInput(
+                "delimiter and quote must differ".into(),
+            ));
+        }
+        Ok(Self {
+            delimiter,
+            quote,
+            decimal_comma,
+        })
+    }
+
+    /// Trim a raw cell and, for decimal-comma files, rewrite numbers such as
+    /// `1.234,5` to `1234.5` so inference and stats see ordinary floats.
+    pub fn cell<'a>(&self, raw: &'a str) -> Cow<'a, str> {
+        let s = raw.trim();
+        match self.decimal_comma.then(|| from_decimal_comma(s)).flatten() {
+            Some(n) => Cow::Owned(n),
+            None => Cow::Borrowed(s),
+        }
+    }
+}
+
+/// `3,14` → `3.14`, `-1.234,5` → `-1234.5`; `None` unless the result is a
+/// number. `.` only counts as a separator between groups of three digits.
+fn from_decimal_comma(s: &str) -> Option<String> {
+    let (int, frac) = match s.split_once(',') {
+        Some((i, f)) => (i, Some(f)),
+        None => (s, None),
+    };
+    let mut groups = int.split('.');
+    let mut out = groups.next()?.to_string();
+    for g in groups {
+        if g.len() != 3 || !g.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        out.push_str(g);
+    }
+    if let Some(f) = frac {
+        out.push('.');
+        out.push_str(f);
+    }
+    (out != s && out.parse::<f64>().is_ok()).then_some(out)
+}
+
+/// Options controlling [`read_csv`].
+#[derive(Clone, Debug, Default)]
+pub struct CsvOptions {
+    /// Columns to keep, in order (all when `None`)
+    pub columns: Option<Vec<ColumnRef>>,
+    /// Leading rows to drop before the header/data
+    pub skip_rows: usize,
+    /// Header presence; auto-detected when `None`
+    pub has_header: Option<bool>,
+    pub infer: InferTypes,
+    pub dialect: CsvDialect,
+    /// Keep at most this many data rows (all when `None`)
+    pub max_rows: Option<usize>,
+    /// Analyze a seeded uniform sample of the data rows instead of all of them
+    pub sample: Option<RowSample>,
+    /// Seed of samples drawn without being asked for, such as the values
+    /// [`summarize_csv_stream`](super::summarize_csv_stream) keeps for the
+    /// median of a very large body
+    pub seed: u64,
+}
+
+impl CsvOptions {
+    /// Options of a request's [`CsvQuery`]; `seed` draws `?sample=` rows when
+    /// the query names none (handlers pass [`ServiceConfig::seed`]).
+    ///
+    /// [`ServiceConfig::seed`]: crate::config::ServiceConfig::seed
+    pub fn from_query(q: &CsvQuery, seed: u64) -> Result<Self, ServiceError> {
+        let columns = q.columns.as_deref().map(|s| {
+            s.split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(|c| match c.parse::<usize>() {
+                    Ok(i) => ColumnRef::Index(i),
+                    Err(_) => ColumnRef::Name(c.to_string()),
+                })
+                .collect()
+        });
+        Ok(Self {
+            columns,
+            skip_rows: q.skip_rows.unwrap_or(0),
+            has_header: q.has_header,
+            infer: q
+                .infer
+                .as_deref()
+                .map_or(Ok(InferTypes::default()), InferTypes::parse)?,
+            dialect: CsvDialect::parse(
+                q.delimiter.as_deref(),
+                q.quote.as_deref(),
+                q.decimal.as_deref(),
+            )?,
+            max_rows: None,
+            sample: q.sample.map(|size| RowSample {
+                size,
+                seed: q.seed.unwrap_or(seed),
+            }),
+            seed: q.seed.unwrap_or(seed),
+        })
+    }
+}
+
+/// One ingested column: trimmed raw cells plus the inferred type.
+#[derive(Clone, Debug)]
+pub struct CsvColumn {
+    pub name: String,
+    /// 0-based position in the source file
+    pub index: usize,
+    pub dtype: ColumnType,
+    /// One cell per data row (empty string when missing)
+    pub cells: Vec<String>,
+}
+
+impl CsvColumn {
+    pub fn schema(&self) -> ColumnSchema {
+        let non_empty = self.cells.iter().filter(|c| !c.is_empty()).count();
+        ColumnSchema {
+            name: self.name.clone(),
+            index: self.index,
+            dtype: self.dtype,
+            non_empty,
+            missing: self.cells.len() - non_empty,
+        }
+    }
+}
+
+/// Parsed CSV restricted to the selected columns.
+#[derive(Clone, Debug)]
+pub struct CsvTable {
+    pub columns: Vec<CsvColumn>,
+    pub n_rows: usize,
+    pub infer: InferTypes,
+    /// Data rows beyond [`CsvOptions::max_rows`] were dropped
+    pub truncated: bool,
+    /// Set when the rows are a [`CsvOptions::sample`] of the payload
+    pub sample: Option<SampleOut>,
+}
+
+impl CsvTable {
+    pub fn schema(&self) -> Vec<ColumnSchema> {
+        self.columns.iter().map(CsvColumn::schema).collect()
+    }
+
+    /// Every cell that parses as a number under the enabled numeric types,
+    /// column by column (non-numeric cells are skipped, not rejected).
+    pub fn numeric_cells(&self) -> Vec<f64> {
+        self.columns
+            .iter()
+            .flat_map(|c| c.cells.iter().filter_map(|s| self.infer.number(s)))
+            .collect()
+    }
+}
+
+fn is_bool(s: &str) -> bool {
+    s.eq_ignore_ascii_case("true") || s.eq_ignore_ascii_case("false")
+}
+
+/// Running type inference for one column, fed a cell at a time.
+///
+/// Tracks which candidate types still fit every non-empty cell seen so far,
+/// so streaming readers reach the same verdict as [`infer_column_type`].
+#[derive(Clone, Copy, Debug)]
+pub struct ColumnTypeAcc {
+    non_empty: usize,
+    boolean: bool,
+    integer: bool,
+    float: bool,
+    date: bool,
+}
+
+impl Default for ColumnTypeAcc {
+    fn default() -> Self {
+        Self {
+            non_empty: 0,
+            boolean: true,
+            integer: true,
+            float: true,
+            date: true,
+        }
+    }
+}
+
+impl ColumnTypeAcc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one trimmed cell; empty cells only count as missing.
+    pub fn push(&mut self, cell: &str) {
+        if cell.is_empty() {
+            return;
+        }
+        self.non_empty += 1;
+        self.boolean = self.boolean && is_bool(cell);
+        self.integer = self.integer && cell.parse::<i64>().is_ok();
+        self.float = self.float && cell.parse::<f64>().is_ok();
+        self.date = self.date && parse_datetime(cell, DayFirst::Auto).is_some();
+    }
+
+    pub fn non_empty(&self) -> usize {
+        self.non_empty
+    }
+
+    /// Narrowest enabled type that fits every non-empty cell.
+    pub fn dtype(&self, infer: &InferTypes) -> ColumnType {
+        if self.non_empty == 0 {
+            ColumnType::Empty
+        } else if infer.booleans && self.boolean {
+            ColumnType::Boolean
+        } else if infer.integers && self.integer {
+            ColumnType::Integer
+        } else if infer.floats && self.float {
+            ColumnType::Float
+        } else if infer.dates && self.date {
+            ColumnType::Date
+        } else {
+            ColumnType::String
+        }
+    }
+}
+
+/// Narrowest enabled type that fits every non-empty cell.
+pub fn infer_column_type(cells: &[String], infer: &InferTypes) -> ColumnType {
+    let mut acc = ColumnTypeAcc::new();
+    cells.iter().for_each(|c| acc.push(c));
+    acc.dtype(infer)
+}
+
+/// Reader settings shared by the buffered and streaming CSV paths.
+pub(crate) fn reader_builder(dialect: &CsvDialect) -> ::csv::ReaderBuilder {
+    let mut b = ::csv::ReaderBuilder::new();
+    b.has_headers(false)
+        .flexible(true)
+        .delimiter(dialect.delimiter)
+        .quoting(dialect.quote.is_some())
+        .quote(dialect.quote.unwrap_or(b'"'));
+    b
+}
+
+/// Parse a CSV payload into typed columns (see [`table_from_rows`]).
+///
+/// With [`CsvOptions::sample`], records are drawn into a [`Reservoir`] while
+/// reading, so only the kept rows are ever split into owned cells.
+pub fn read_csv(bytes: &[u8], opts: &CsvOptions) -> Result<CsvTable, ServiceError> {
+    let mut rdr = reader_builder(&opts.dialect).from_reader(bytes);
+    if let Some(spec) = opts.sample {
+        return read_csv_sampled(&mut rdr, opts, spec);
+    }
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    // header + max_rows + one extra row to tell whether anything was cut
+    let take = opts.max_rows.map_or(usize::MAX, |n| n.saturating_add(2));
+    for rec in rdr.records().skip(opts.skip_rows).take(take) {
+        let rec = rec?;
+        rows.push(
+            rec.iter()
+                .map(|c| opts.dialect.cell(c).into_owned())
+                .collect(),
+        );
+    }
+    table_from_rows(rows, opts)
+}
+
+fn read_csv_sampled<R: std::io::Read>(
+    rdr: &mut ::csv::Reader<R>,
+    opts: &CsvOptions,
+    spec: RowSample,
+) -> Result<CsvTable, ServiceError> {
+    let to_row = |rec: &::csv::StringRecord| -> Vec<String> {
+        rec.iter()
+            .map(|c| opts.dialect.cell(c).into_owned())
+            .collect()
+    };
+    let mut rec = ::csv::StringRecord::new();
+    let mut next = |rec: &mut ::csv::StringRecord| rdr.read_record(rec).map_err(ServiceError::from);
+    for _ in 0..opts.skip_rows {
+        if !next(&mut rec)? {
+            break;
+        }
+    }
+
+    let size = opts.max_rows.map_or(spec.size, |m| m.min(spec.size));
+    let mut reservoir = Reservoir::new(RowSample { size, ..spec });
+    let mut header = None;
+    if next(&mut rec)? {
+        let first = to_row(&rec);
+        if opts.has_header.unwrap_or_else(|| looks_like_header(&first)) {
+            header = Some(first);
+        } else {
+            reservoir.offer_with(|| first);
+        }
+    }
+    while next(&mut rec)? {
+        reservoir.offer_with(|| to_row(&rec));
+    }
+
+    let summary = reservoir.summary();
+    let has_header = header.is_some();
+    let rows = header.into_iter().chain(reservoir.into_ordered()).collect();
+    let mut table = table_from_rows(
+        rows,
+        &CsvOptions {
+            has_header: Some(has_header),
+            sample: None,
+            ..opts.clone()
+        },
+    )?;
+    table.sample = Some(summary);
+    Ok(table)
+}
+
+/// Header auto-detection: a first row with any non-empty, non-numeric cell.
+pub(crate) fn looks_like_header<S: AsRef<str>>(row: &[S]) -> bool {
+    row.iter()
+        .map(AsRef::as_ref)
+        .any(|c| !c.is_empty() && c.parse::<f64>().is_err())
+}
+
+/// Build typed columns from already-split text rows (after `skip_rows`).
+///
+/// Without an explicit `has_header`, the first row is a header unless all of
+/// its non-empty cells are numeric. Ragged rows are padded with empty cells;
+/// a `sample` draws from the data rows, and rows past `max_rows` are then
+/// dropped and flagged as `truncated`.
+pub fn table_from_rows(
+    mut rows: Vec<Vec<String>>,
+    opts: &CsvOptions,
+) -> Result<CsvTable, ServiceError> {
+    let has_header = opts
+        .has_header
+        .unwrap_or_else(|| rows.first().is_some_and(|r| looks_like_header(r)));
+    let header = if has_header && !rows.is_empty() {
+        Some(rows.remove(0))
+    } else {
+        None
+    };
+    let sample = opts.sample.map(|spec| {
+        let mut reservoir = Reservoir::new(spec);
+        std::mem::take(&mut rows)
+            .into_iter()
+            .for_each(|r| reservoir.offer_with(|| r));
+        let summary = reservoir.summary();
+        rows = reservoir.into_ordered();
+        summary
+    });
+    let truncated = opts.max_rows.is_some_and(|m| rows.len() > m);
+    if let Some(m) = opts.max_rows {
+        rows.truncate(m);
+    }
+    let width = rows
+        .iter()
+        .map(Vec::len)
+        .chain(header.as_ref().map(Vec::len))
+        .max()
+        .unwrap_or(0);
+    let names: Vec<String> = (0..width)
+        .map(|i| match header.as_ref().and_then(|h| h.get(i)) {
+            Some(n) if !n.is_empty() => n.clone(),
+            _ => format!("column_{i}"),
+        })
+        .collect();
+
+    let selected: Vec<usize> = match &opts.columns {
+        None => (0..width).collect(),
+        Some(refs) => refs
+            .iter()
+            .map(|r| match r {
+                ColumnRef::Name(n) => names
+                    .iter()
+                    .position(|x| x == n)
+                    .ok_or_else(|| ServiceError::InvalidInput(format!("unknown column '{n}'"))),
+                ColumnRef::Index(i) if *i < width => Ok(*i),
+                ColumnRef::Index(i) => Err(ServiceError::InvalidInput(format!(
+                    "column index {i} out of range (file has {width} columns)"
+                ))),
+            })
+            .collect::<Result<_, _>>()?,
+    };
+
+    let columns = selected
+        .into_iter()
+        .map(|i| {
+            let cells: Vec<String> = rows
+                .iter()
+                .map(|r| r.get(i).cloned().unwrap_or_default())
+                .collect();
+            CsvColumn {
+                name: names[i].clone(),
+                index: i,
+                dtype: infer_column_type(&cells, &opts.infer),
+                cells,
+            }
+        })
+        .collect();
+
+    Ok(CsvTable {
+        columns,
+        n_rows: rows.len(),
+        infer: opts.infer,
+        truncated,
+        sample,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(xs: &[&str]) -> Vec<String> {
+        xs.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn infers_narrowest_enabled_type() {
+        let all = InferTypes::default();
+        assert_eq!(
+            infer_column_type(&cells(&["1", "", "-3"]), &all),
+            ColumnType::Integer
+        );
+        assert_eq!(
+            infer_column_type(&cells(&["1", "2.5"]), &all),
+            ColumnType::Float
+        );
+        assert_eq!(
+            infer_column_type(&cells(&["TRUE", "false"]), &all),
+            ColumnType::Boolean
+        );
+        assert_eq!(
+            infer_column_type(&cells(&["2024-02-01", "2024-03-01T10:00:00Z"]), &all),
+            ColumnType::Date
+        );
+        assert_eq!(
+            infer_column_type(&cells(&["2024-13-01"]), &all),
+            ColumnType::String
+        );
+        assert_eq!(
+            infer_column_type(&cells(&["", ""]), &all),
+            ColumnType::Empty
+        );
+
+        let floats_only = InferTypes::parse("float").unwrap();
+        assert_eq!(
+            infer_column_type(&cells(&["1", "2"]), &floats_only),
+            ColumnType::Float
+        );
+        assert_eq!(
+            infer_column_type(&cells(&["1", "2"]), &InferTypes::none()),
+            ColumnType::String
+        );
+        assert!(InferTypes::parse("int,complex").is_err());
+    }
+
+    #[test]
+    fn selects_columns_skips_rows_and_detects_header() {
+        let csv = b"# exported 2024\nid,price,ok\n1,9.5,true\n2,,false\n3,7\n";
+        let q = CsvQuery {
+            columns: Some("price,0".into()),
+            skip_rows: Some(1),
+            ..Default::default()
+        };
+        let t = read_csv(csv, &CsvOptions::from_query(&q, 0).unwrap()).unwrap();
+        assert_eq!(t.n_rows, 3);
+        let s = t.schema();
+        assert_eq!(
+            (s[0].name.as_str(), s[0].dtype),
+            ("price", ColumnType::Float)
+        );
+        assert_eq!((s[0].non_empty, s[0].missing), (2, 1));
+        assert_eq!((s[1].name.as_str(), s[1].index), ("id", 0));
+        assert_eq!(t.numeric_cells(), vec![9.5, 7.0, 1.0, 2.0, 3.0]);
+
+        // headerless numeric input keeps its first row
+        let t = read_csv(b"1,2\n3,4\n", &CsvOptions::default()).unwrap();
+        assert_eq!(t.columns[0].name, "column_0");
+        assert_eq!(t.n_rows, 2);
+
+        // max_rows counts data rows only and flags the cut
+        for (m, truncated) in [(2, true), (3, false)] {
+            let opts = CsvOptions {
+                skip_rows: 1,
+                max_rows: Some(m),
+                ..Default::default()
+            };
+            let t = read_csv(csv, &opts).unwrap();
+            assert_eq!((t.n_rows, t.truncated), (m.min(3), truncated));
+        }
+
+        let bad = CsvOptions {
+            columns: Some(vec![ColumnRef::Name("nope".into())]),
+            ..Default::default()
+        };
+        assert!(read_csv(csv, &bad).is_err());
+    }
+
+    #[test]
+    fn sampled_read_matches_in_memory_sample() {
+        let mut text = String::from("# export\nid,x\n");
+        for i in 0..500 {
+            text.push_str(&format!("{i},{}\n", i * 2));
+        }
+        let opts = CsvOptions {
+            skip_rows: 1,
+            sample: Some(RowSample { size: 40, seed: 9 }),
+            ..Default::default()
+        };
+        let t = read_csv(text.as_bytes(), &opts).unwrap();
+        assert_eq!(t.n_rows, 40);
+        assert_eq!(t.columns[0].name, "id");
+        let s = t.sample.unwrap();
+        assert_eq!((s.rows_seen, s.rows_sampled, s.seed), (500, 40, 9));
+        assert!((s.fraction - 0.08).abs() < 1e-12);
+
+        // Same draw as sampling already-split rows (the xlsx/parquet path)
+        let rows: Vec<Vec<String>> = text
+            .lines()
+            .skip(1)
+            .map(|l| l.split(',').map(String::from).collect())
+            .collect();
+        let u = table_from_rows(rows, &opts).unwrap();
+        assert_eq!(u.columns[0].cells, t.columns[0].cells);
+        assert_eq!(u.sample, t.sample);
+
+        // max_rows caps the sample size
+        let capped = CsvOptions {
+            max_rows: Some(10),
+            ..opts
+        };
+        let t = read_csv(text.as_bytes(), &capped).unwrap();
+        assert_eq!((t.n_rows, t.truncated), (10, false));
+    }
+
+    #[test]
+    fn european_dialect_and_decimal_comma() {
+        let q = CsvQuery {
+            delimiter: Some("semicolon".into()),
+            decimal: Some(",".into()),
+            ..Default::default()
+        };
+        let opts = CsvOptions::from_query(&q, 0).unwrap();
+        let csv = "name;price;qty\n\"a;b\";2,75;1.200\nc;-1.234,5;7\nd;n/a;\n";
+        let t = read_csv(csv.as_bytes(), &opts).unwrap();
+        assert_eq!(t.columns[0].cells[0], "a;b");
+        assert_eq!(t.columns[1].cells[..2], ["2.75", "-1234.5"]);
+        assert_eq!(t.columns[2].dtype, ColumnType::Integer);
+        assert_eq!(t.numeric_cells(), vec![2.75, -1234.5, 1200.0, 7.0]);
+
+        let d = CsvDialect::default();
+        assert_eq!(d.cell(" 3,14 "), "3,14");
+        let dc = CsvDialect {
+            decimal_comma: true,
+            ..d
+        };
+        assert_eq!(dc.cell("1.5"), "1.5");
+        assert_eq!(dc.cell("x,y"), "x,y");
+
+        let tab = CsvDialect::parse(Some("tab"), Some("none"), None).unwrap();
+        assert_eq!((tab.delimiter, tab.quote), (b'\t', None));
+        assert!(CsvDialect::parse(Some("ab"), None, None).is_err());
+        assert!(CsvDialect::parse(Some("'"), Some("'"), None).is_err());
+        assert!(CsvDialect::parse(None, None, Some("x")).is_err());
+    }
+}
@@ -0,0 +1,411 @@
+//! Streaming CSV summaries for bodies too large to buffer.
+//!
+//! The request body is handed chunk by chunk to a blocking `csv` reader
+//! through a small bounded channel, so memory holds a few chunks plus the
+//! per-column accumulators and at most [`MAX_KEPT_VALUES`] parsed numbers for
+//! the median — never the raw text or per-cell strings of
+//! [`CsvTable`](super::CsvTable). Past that many numbers the kept ones are a
+//! seeded uniform sample, and the median read off it is approximate.
+//! With [`CsvOptions::sample`] the rows are first drawn into a bounded
+//! [`Reservoir`] and only the sample is summarized.
+
+use super::{
+    csv::{ColumnRef, ColumnTypeAcc, CsvOptions, looks_like_header, reader_builder},
+    sample::{Reservoir, RowSample},
+};
+use crate::{
+    error::ServiceError,
+    stats::OnlineMeanVar,
+    types::{ColumnSchema, SampleOut},
+};
+use axum::body::Bytes;
+use futures_util::{Stream, StreamExt};
+use std::io::{self, Read};
+use tokio::sync::mpsc;
+
+/// Body chunks buffered between the network and the parser.
+const CHANNEL_CHUNKS: usize = 8;
+
+/// Numeric cells kept for order statistics (8 MiB of values); beyond this a
+/// uniform sample of this size is kept (rank error ≈ 0.0016 at 99%).
+pub const MAX_KEPT_VALUES: usize = 1 << 20;
+
+/// Result of a streaming pass: schema plus the numeric cells' moments.
+#[derive(Clone, Debug)]
+pub struct CsvSummary {
+    pub schema: Vec<ColumnSchema>,
+    pub n_rows: usize,
+    /// Running mean/variance over every numeric cell of the selected columns
+    pub moments: OnlineMeanVar,
+    /// The numeric cells themselves (row by row) for order statistics, or a
+    /// uniform sample of [`MAX_KEPT_VALUES`] of them when there are more;
+    /// `moments.count()` is the full count
+    pub values: Vec<f64>,
+    /// Set when only a row sample was summarized
+    pub sample: Option<SampleOut>,
+}
+
+/// Incremental counterpart of [`read_csv`](super::read_csv) that keeps only
+/// per-column type state and numeric values.
+///
+/// Records are fed after `skip_rows`; header detection, column selection and
+/// type inference follow [`table_from_rows`](super::table_from_rows).
+#[derive(Debug)]
+pub struct CsvStreamSummary {
+    opts: CsvOptions,
+    started: bool,
+    header: Vec<String>,
+    /// Source indices of the selected columns; `None` keeps every column
+    selected: Option<Vec<usize>>,
+    /// One accumulator per selected column (per source column when `selected` is `None`)
+    types: Vec<ColumnTypeAcc>,
+    width: usize,
+    n_rows: usize,
+    moments: OnlineMeanVar,
+    values: Reservoir<f64>,
+    reservoir: Option<Reservoir<Vec<String>>>,
+}
+
+impl CsvStreamSummary {
+    pub fn new(opts: CsvOptions) -> Self {
+        Self {
+            reservoir: opts.sample.map(Reservoir::new),
+            values: Reservoir::new(RowSample {
+                size: MAX_KEPT_VALUES,
+                seed: opts.seed,
+            }),
+            opts,
+            started: false,
+            header: Vec::new(),
+            selected: None,
+            types: Vec::new(),
+            width: 0,
+            n_rows: 0,
+            moments: OnlineMeanVar::new(),
+        }
+    }
+
+    fn name(&self, i: usize) -> String {
+        match self.header.get(i) {
+            Some(n) if !n.is_empty() => n.clone(),
+            _ => format!("column_{i}"),
+        }
+    }
+
+    /// Resolve a selector against the header; `column_N` names and indices
+    /// are range-checked in [`finish`](Self::finish) once the width is known.
+    fn resolve(&self, r: &ColumnRef) -> Result<usize, ServiceError> {
+        match r {
+            ColumnRef::Index(i) => Ok(*i),
+            ColumnRef::Name(n) => (0..self.width)
+                .find(|&i| self.name(i) == *n)
+                .or_else(|| n.strip_prefix("column_").and_then(|i| i.parse().ok()))
+                .ok_or_else(|| ServiceError::InvalidInput(format!("unknown column '{n}'"))),
+        }
+    }
+
+    fn start<S: AsRef<str>>(&mut self, first: &[S]) -> Result<bool, ServiceError> {
+        self.started = true;
+        let is_header = self
+            .opts
+            .has_header
+            .unwrap_or_else(|| looks_like_header(first));
+        self.width = first.len();
+        if is_header {
+            self.header = first.iter().map(|c| c.as_ref().to_string()).collect();
+        }
+        if let Some(refs) = &self.opts.columns {
+            let sel: Vec<usize> = refs
+                .iter()
+                .map(|r| self.resolve(r))
+                .collect::<Result<_, _>>()?;
+            self.types = vec![ColumnTypeAcc::new(); sel.len()];
+            self.selected = Some(sel);
+        }
+        Ok(is_header)
+    }
+
+    /// Feed one record of cells already passed through [`CsvDialect::cell`](super::CsvDialect::cell).
+    pub fn push<S: AsRef<str>>(&mut self, row: &[S]) -> Result<(), ServiceError> {
+        if !self.started && self.start(row)? {
+            return Ok(());
+        }
+        match &mut self.reservoir {
+            Some(r) => {
+                r.offer_with(|| row.iter().map(|c| c.as_ref().to_string()).collect());
+                Ok(())
+            }
+            None => self.record(row),
+        }
+    }
+
+    fn record<S: AsRef<str>>(&mut self, row: &[S]) -> Result<(), ServiceError> {
+        self.width = self.width.max(row.len());
+        self.n_rows += 1;
+
+        let Self {
+            opts,
+            selected,
+            types,
+            moments,
+            values,
+            ..
+        } = self;
+        let mut observe = |acc: &mut ColumnTypeAcc, cell: &str| {
+            acc.push(cell);
+            if let Some(x) = opts.infer.number(cell) {
+                moments.push(x);
+                values.offer_with(|| x);
+            }
+        };
+        match selected {
+            Some(sel) => {
+                for (acc, &i) in types.iter_mut().zip(sel.iter()) {
+                    observe(acc, row.get(i).map_or("", AsRef::as_ref));
+                }
+            }
+            None => {
+                if types.len() < row.len() {
+                    types.resize(row.len(), ColumnTypeAcc::new());
+                }
+                for (acc, cell) in types.iter_mut().zip(row) {
+                    observe(acc, cell.as_ref());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate the selection against the final width and build the summary.
+    pub fn finish(mut self) -> Result<CsvSummary, ServiceError> {
+        if !self.started && self.opts.columns.is_some() {
+            self.start::<&str>(&[])?;
+        }
+        let sample = match self.reservoir.take() {
+            Some(r) => {
+                let summary = r.summary();
+                for row in r.into_ordered() {
+                    self.record(&row)?;
+                }
+                Some(summary)
+            }
+            None => None,
+        };
+        let indices: Vec<usize> = match &self.selected {
+            Some(sel) => sel.clone(),
+            None => {
+                self.types.resize(self.width, ColumnTypeAcc::new());
+                (0..self.width).collect()
+            }
+        };
+        if let Some(&i) = indices.iter().find(|&&i| i >= self.width) {
+            return Err(ServiceError::InvalidInput(format!(
+                "column index {i} out of range (file has {} columns)",
+                self.width
+            )));
+        }
+
+        let schema = indices
+            .iter()
+            .zip(&self.types)
+            .map(|(&i, acc)| ColumnSchema {
+                name: self.name(i),
+                index: i,
+                dtype: acc.dtype(&self.opts.infer),
+                non_empty: acc.non_empty(),
+                missing: self.n_rows - acc.non_empty(),
+            })
+            .collect();
+        Ok(CsvSummary {
+            schema,
+            n_rows: self.n_rows,
+            moments: self.moments,
+            values: self.values.into_ordered(),
+            sample,
+        })
+    }
+}
+
+/// Blocking [`Read`] over chunks forwarded from the async body stream.
+struct ChannelReader {
+    rx: mpsc::Receiver<Bytes>,
+    cur: Bytes,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.cur.is_empty() {
+            match self.rx.blocking_recv() {
+                Some(chunk) => self.cur = chunk,
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.cur.len());
+        buf[..n].copy_from_slice(&self.cur.split_to(n));
+        Ok(n)
+    }
+}
+
+/// Parse a CSV byte stream on the blocking pool while it is still arriving.
+///
+/// Backpressure comes from the bounded channel: the body is only polled as
+/// fast as the parser drains it. A parse error stops reading the body early.
+pub async fn summarize_csv_stream<S, E>(
+    mut stream: S,
+    opts: CsvOptions,
+) -> Result<CsvSummary, ServiceError>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    ServiceError: From<E>,
+{
+    let (tx, rx) = mpsc::channel(CHANNEL_CHUNKS);
+    let parser = tokio::task::spawn_blocking(move || {
+        let skip = opts.skip_rows;
+        let dialect = opts.dialect;
+        let mut acc = CsvStreamSummary::new(opts);
+        let mut rdr = reader_builder(&dialect).from_reader(ChannelReader {
+            rx,
+            cur: Bytes::new(),
+        });
+        let mut rec = ::csv::StringRecord::new();
+        let mut seen = 0usize;
+        while rdr.read_record(&mut rec)? {
+            seen += 1;
+            if seen > skip {
+                let row: Vec<_> = rec.iter().map(|c| dialect.cell(c)).collect();
+                acc.push(&row)?;
+            }
+        }
+        acc.finish()
+    });
+
+    while let Some(chunk) = stream.next().await {
+        if tx.send(chunk?).await.is_err() {
+            break; // parser already stopped with an error
+        }
+    }
+    drop(tx);
+    match parser.await {
+        Ok(summary) => summary,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => Err(ServiceError::Internal(format!("CSV parser task: {e}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ingest::read_csv,
+        types::{ColumnType, CsvQuery},
+    };
+
+    fn feed(csv: &str, opts: CsvOptions) -> Result<CsvSummary, ServiceError> {
+        let mut rdr = reader_builder(&opts.dialect).from_reader(csv.as_bytes());
+        let mut acc = CsvStreamSummary::new(opts.clone());
+        for rec in rdr.records().skip(opts.skip_rows) {
+            let rec = rec.unwrap();
+            acc.push(&rec.iter().map(|c| opts.dialect.cell(c)).collect::<Vec<_>>())?;
+        }
+        acc.finish()
+    }
+
+    #[test]
+    fn matches_buffered_reader() {
+        let csv = "# exported 2024\nid,price,ok\n1,9.5,true\n2,,false\n3,7\n";
+        for columns in [None, Some("price,0"), Some("ok,1")] {
+            let q = CsvQuery {
+                columns: columns.map(Into::into),
+                skip_rows: Some(1),
+                ..Default::default()
+            };
+            let opts = CsvOptions::from_query(&q, 0).unwrap();
+            let table = read_csv(csv.as_bytes(), &opts).unwrap();
+            let s = feed(csv, opts).unwrap();
+            assert_eq!(s.schema, table.schema());
+            assert_eq!(s.n_rows, table.n_rows);
+
+            let mut a = s.values.clone();
+            let mut b = table.numeric_cells();
+            a.sort_by(f64::total_cmp);
+            b.sort_by(f64::total_cmp);
+            assert_eq!(a, b);
+            assert_eq!(s.moments.count() as usize, b.len());
+        }
+    }
+
+    #[test]
+    fn headerless_ragged_rows_and_bad_selection() {
+        let s = feed("1,2\n3,4,5\n", CsvOptions::default()).unwrap();
+        assert_eq!(s.schema.len(), 3);
+        assert_eq!(s.schema[2].name, "column_2");
+        assert_eq!((s.schema[2].non_empty, s.schema[2].missing), (1, 1));
+        assert_eq!(s.schema[0].dtype, ColumnType::Integer);
+
+        let idx = CsvOptions {
+            columns: Some(vec![ColumnRef::Index(7)]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            feed("1,2\n", idx),
+            Err(ServiceError::InvalidInput(_))
+        ));
+        let name = CsvOptions {
+            columns: Some(vec![ColumnRef::Name("nope".into())]),
+            ..Default::default()
+        };
+        assert!(feed("a,b\n1,2\n", name.clone()).is_err());
+        assert!(feed("", name).is_err());
+    }
+
+    #[test]
+    fn sample_matches_buffered_sample() {
+        let mut csv = String::from("v\n");
+        for i in 0..1000 {
+            csv.push_str(&format!("{i}\n"));
+        }
+        let opts = CsvOptions {
+            sample: Some(crate::ingest::RowSample { size: 25, seed: 1 }),
+            ..Default::default()
+        };
+        let table = read_csv(csv.as_bytes(), &opts).unwrap();
+        let s = feed(&csv, opts).unwrap();
+        assert_eq!(s.values, table.numeric_cells());
+        assert_eq!(s.n_rows, 25);
+        assert_eq!(s.sample, table.sample);
+        assert_eq!(s.schema, table.schema());
+    }
+
+    #[test]
+    fn values_past_the_cap_are_a_uniform_sample() {
+        let n = MAX_KEPT_VALUES + MAX_KEPT_VALUES / 4;
+        let mut acc = CsvStreamSummary::new(CsvOptions {
+            has_header: Some(false),
+            seed: 3,
+            ..Default::default()
+        });
+        for i in 0..n {
+            acc.push(&[((i * 7919) % n).to_string()]).unwrap();
+        }
+        let s = acc.finish().unwrap();
+        assert_eq!(s.moments.count() as usize, n);
+        assert_eq!(s.values.len(), MAX_KEPT_VALUES);
+        let sketch = crate::stats::QuantileSketch::from_sample(s.values, n, 3);
+        let m = crate::stats::median_sorted(sketch.sorted());
+        let rank = m / n as f64;
+        assert!((rank - 0.5).abs() < sketch.rank_error(0.99), "{rank}");
+    }
+
+    #[tokio::test]
+    async fn parses_records_split_across_chunks() {
+        let chunks = ["val", "ue,note\n1,\"a\n", "b\"\n2,c\n3", ",d\n"]
+            .map(|c| Ok::<_, ServiceError>(Bytes::from_static(c.as_bytes())));
+        let s = summarize_csv_stream(futures_util::stream::iter(chunks), CsvOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(s.n_rows, 3);
+        assert_eq!(s.schema[0].name, "value");
+        assert_eq!(s.schema[1].dtype, ColumnType::String);
+        assert_eq!(s.values, vec![1.0, 2.0, 3.0]);
+    }
+}
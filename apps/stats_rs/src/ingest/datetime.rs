@@ -0,0 +1,383 @@
+//! Datetime cells: parsing common text formats to UTC epoch milliseconds and
+//! the calendar arithmetic used for time-indexed statistics.
+//!
+//! Accepted forms (date, optionally followed by `T` or a space and a time):
+//!
+//! | Date part | Example |
+//! |---|---|
+//! | ISO `YYYY-MM-DD` / `YYYY/MM/DD` | `2024-03-05`, `2024/03/05 14:30` |
+//! | Slashed `MM/DD/YYYY` (or `DD/MM/YYYY`) | `03/05/2024` |
+//! | Dotted `DD.MM.YYYY` | `05.03.2024` |
+//! | Month names | `5 Mar 2024`, `March 5, 2024` |
+//!
+//! Times are `HH:MM[:SS[.fff]]`, optionally with `AM`/`PM`, and a `Z` or
+//! `±HH[:MM]` offset; values without an offset are taken as UTC.
+
+const MS_PER_DAY: i64 = 86_400_000;
+
+/// How to read ambiguous `NN/NN/YYYY` dates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DayFirst {
+    /// Month first unless the first number cannot be a month
+    #[default]
+    Auto,
+    /// `DD/MM/YYYY`
+    Yes,
+    /// `MM/DD/YYYY`
+    No,
+}
+
+/// Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+pub fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+/// Days since 1970-01-01 for a civil date (inverse of [`civil_from_days`]).
+pub fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = i64::from((m + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(d) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn days_in_month(y: i64, m: u32) -> u32 {
+    match m {
+        4 | 6 | 9 | 11 => 30,
+        2 if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// `YYYY-MM-DDTHH:MM:SS` (UTC, with `.mmm` when sub-second).
+pub fn timestamp_text(ms: i64) -> String {
+    let (y, m, d) = civil_from_days(ms.div_euclid(MS_PER_DAY));
+    let in_day = ms.rem_euclid(MS_PER_DAY);
+    let s = in_day / 1000;
+    let frac = in_day % 1000;
+    let mut out = format!(
+        "{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}",
+        s / 3600,
+        s / 60 % 60,
+        s % 60
+    );
+    if frac != 0 {
+        out.push_str(&format!(".{frac:03}"));
+    }
+    out
+}
+
+/// Day of week, Monday = 0 … Sunday = 6.
+pub fn weekday(ms: i64) -> u32 {
+    // 1970-01-01 was a Thursday
+    (ms.div_euclid(MS_PER_DAY) + 3).rem_euclid(7) as u32
+}
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// `Mar`, `mar.` or `March` → 3 (at least three letters).
+fn month_name(s: &str) -> Option<u32> {
+    let s = s.trim_end_matches('.').to_ascii_lowercase();
+    if s.len() < 3 {
+        return None;
+    }
+    let i = MONTHS
+        .iter()
+        .position(|m| m.starts_with(s.as_str()))
+        .or_else(|| (s == "sept").then_some(8))?;
+    Some(i as u32 + 1)
+}
+
+fn num(s: &str, digits: std::ops::RangeInclusive<usize>) -> Option<u32> {
+    (digits.contains(&s.len()) && s.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| s.parse().ok())
+        .flatten()
+}
+
+/// Split `date[T| ]time` into its parts; month-name dates keep their spaces.
+fn split_date_time(s: &str) -> (&str, &str) {
+    if let Some(i) = s.find('T').filter(|&i| i >= 8) {
+        return (&s[..i], &s[i + 1..]);
+    }
+    // the time starts at the first token containing ':'
+    match s.find(':') {
+        Some(c) => match s[..c].rfind(' ') {
+            Some(sp) => (s[..sp].trim_end(), &s[sp + 1..]),
+            None => (s, ""),
+        },
+        None => (s, ""),
+    }
+}
+
+fn parse_date(s: &str, day_first: DayFirst) -> Option<(i64, u32, u32)> {
+    let s = s.trim().trim_end_matches(',');
+    let (y, m, d) = if let Some(parts) = split3(s, &['-', '/']).filter(|p| p[0].len() == 4) {
+        (
+            num(parts[0], 4..=4)?,
+            num(parts[1], 1..=2)?,
+            num(parts[2], 1..=2)?,
+        )
+    } else if let Some(parts) = split3(s, &['/']) {
+        let (a, b, y) = (
+            num(parts[0], 1..=2)?,
+            num(parts[1], 1..=2)?,
+            num(parts[2], 4..=4)?,
+        );
+        let dayfirst = match day_first {
+            DayFirst::Yes => true,
+            DayFirst::No => false,
+            DayFirst::Auto => a > 12,
+        };
+        if dayfirst { (y, b, a) } else { (y, a, b) }
+    } else if let Some(parts) = split3(s, &['.']) {
+        (
+            num(parts[2], 4..=4)?,
+            num(parts[1], 1..=2)?,
+            num(parts[0], 1..=2)?,
+        )
+    } else {
+        // "5 Mar 2024" or "March 5, 2024"
+        let toks: Vec<&str> = s.split([' ', ',']).filter(|t| !t.is_empty()).collect();
+        let [a, b, y] = toks[..] else { return None };
+        let y = num(y, 4..=4)?;
+        match (month_name(a), month_name(b)) {
+            (Some(m), None) => (y, m, num(b, 1..=2)?),
+            (None, Some(m)) => (y, m, num(a, 1..=2)?),
+            _ => return None,
+        }
+    };
+    let y = i64::from(y);
+    ((1..=12).contains(&m) && d >= 1 && d <= days_in_month(y, m)).then_some((y, m, d))
+}
+
+fn split3<'a>(s: &'a str, seps: &[char]) -> Option<[&'a str; 3]> {
+    let sep = seps.iter().find(|&&c| s.contains(c))?;
+    let mut it = s.split(*sep);
+    let parts = [it.next()?, it.next()?, it.next()?];
+    it.next().is_none().then_some(parts)
+}
+
+/// Milliseconds since midnight, minus the UTC offset in milliseconds.
+fn parse_time(s: &str) -> Option<(i64, i64)> {
+    let mut s = s.trim();
+    let mut offset_ms = 0i64;
+    let mut pm = None;
+    for (suffix, is_pm) in [("am", false), ("pm", true)] {
+        if let Some(rest) = s
+            .len()
+            .checked_sub(2)
+            .filter(|&i| i > 0 && s.get(i..).is_some_and(|t| t.eq_ignore_ascii_case(suffix)))
+            .map(|i| s[..i].trim_end())
+        {
+            s = rest;
+            pm = Some(is_pm);
+        }
+    }
+    if let Some(rest) = s.strip_suffix('Z').or_else(|| s.strip_suffix('z')) {
+        s = rest;
+    } else if let Some(i) = s.rfind(['+', '-']).filter(|&i| i >= 4) {
+        let off = s[i + 1..].replace(':', "");
+        let (h, m) = match off.len() {
+            2 => (num(&off, 2..=2)?, 0),
+            4 => (num(&off[..2], 2..=2)?, num(&off[2..], 2..=2)?),
+            _ => return None,
+        };
+        let sign = if &s[i..=i] == "-" { -1 } else { 1 };
+        offset_ms = sign * i64::from(h * 60 + m) * 60_000;
+        s = s[..i].trim_end();
+    }
+
+    let mut hms = s.split(':');
+    let mut h = num(hms.next()?, 1..=2)?;
+    let m = num(hms.next()?, 2..=2)?;
+    let (sec, ms) = match hms.next() {
+        None => (0, 0),
+        Some(sec) => {
+            let (whole, frac) = sec.split_once('.').unwrap_or((sec, ""));
+            if !frac.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            // milliseconds from the first three fractional digits
+            let ms = format!("{:0<3}", &frac[..frac.len().min(3)]).parse().ok()?;
+            (num(whole, 2..=2)?, ms)
+        }
+    };
+    if hms.next().is_some() {
+        return None;
+    }
+    if let Some(is_pm) = pm {
+        if !(1..=12).contains(&h) {
+            return None;
+        }
+        h = h % 12 + if is_pm { 12 } else { 0 };
+    }
+    (h < 24 && m < 60 && sec < 61)
+        .then_some((i64::from(((h * 60 + m) * 60 + sec) * 1000 + ms), offset_ms))
+}
+
+/// Parse a datetime cell to UTC epoch milliseconds; `None` if it is not one.
+pub fn parse_datetime(s: &str, day_first: DayFirst) -> Option<i64> {
+    let s = s.trim();
+    if s.len() < 8 || !s.bytes().any(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (date, time) = split_date_time(s);
+    let (y, m, d) = parse_date(date, day_first)?;
+    let (in_day, offset) = if time.is_empty() {
+        (0, 0)
+    } else {
+        parse_time(time)?
+    };
+    Some(days_from_civil(y, m, d) * MS_PER_DAY + in_day - offset)
+}
+
+/// Calendar bucket for resampling a time index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Period {
+    Hour,
+    Day,
+    /// ISO weeks, starting Monday
+    Week,
+    Month,
+}
+
+impl Period {
+    /// Ordinal of the bucket containing `ms` (consecutive buckets differ by 1).
+    pub fn bucket(self, ms: i64) -> i64 {
+        match self {
+            Period::Hour => ms.div_euclid(3_600_000),
+            Period::Day => ms.div_euclid(MS_PER_DAY),
+            Period::Week => (ms.div_euclid(MS_PER_DAY) + 3).div_euclid(7),
+            Period::Month => {
+                let (y, m, _) = civil_from_days(ms.div_euclid(MS_PER_DAY));
+                y * 12 + i64::from(m) - 1
+            }
+        }
+    }
+
+    /// Start of bucket `b` in epoch milliseconds.
+    pub fn start(self, b: i64) -> i64 {
+        match self {
+            Period::Hour => b * 3_600_000,
+            Period::Day => b * MS_PER_DAY,
+            Period::Week => (b * 7 - 3) * MS_PER_DAY,
+            Period::Month => {
+                days_from_civil(b.div_euclid(12), b.rem_euclid(12) as u32 + 1, 1) * MS_PER_DAY
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i64, m: u32, d: u32, h: i64, min: i64, s: i64) -> i64 {
+        days_from_civil(y, m, d) * MS_PER_DAY + ((h * 60 + min) * 60 + s) * 1000
+    }
+
+    #[test]
+    fn civil_round_trip() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        for z in [-800_000, -1, 0, 59, 19_782, 2_932_896] {
+            let (y, m, d) = civil_from_days(z);
+            assert_eq!(days_from_civil(y, m, d), z);
+        }
+        assert_eq!(
+            timestamp_text(86_400_000 + 3_661_000),
+            "1970-01-02T01:01:01"
+        );
+        assert_eq!(timestamp_text(1_500), "1970-01-01T00:00:01.500");
+        assert_eq!(weekday(at(2024, 3, 4, 12, 0, 0)), 0); // a Monday
+    }
+
+    #[test]
+    fn parses_common_formats() {
+        let p = |s| parse_datetime(s, DayFirst::Auto);
+        let noon = at(2024, 3, 5, 12, 0, 0);
+        for s in [
+            "2024-03-05T12:00:00Z",
+            "2024-03-05 12:00",
+            "2024-03-05T13:00:00+01:00",
+            "2024-03-05T07:00:00-0500",
+            "2024/03/05 12:00:00",
+            "03/05/2024 12:00 PM",
+            "05.03.2024 12:00",
+            "5 Mar 2024 12:00",
+            "March 5, 2024 12:00:00",
+        ] {
+            assert_eq!(p(s), Some(noon), "{s}");
+        }
+        assert_eq!(p("2024-03-05"), Some(at(2024, 3, 5, 0, 0, 0)));
+        assert_eq!(
+            p("2024-03-05T00:00:00.25Z"),
+            Some(at(2024, 3, 5, 0, 0, 0) + 250)
+        );
+        assert_eq!(p("25/03/2024"), Some(at(2024, 3, 25, 0, 0, 0)));
+        assert_eq!(
+            parse_datetime("03/05/2024", DayFirst::Yes),
+            Some(at(2024, 5, 3, 0, 0, 0))
+        );
+        for bad in [
+            "2024-02-30",
+            "2024-13-01",
+            "12345678",
+            "hello world",
+            "2024-03-05T25:00",
+        ] {
+            assert_eq!(p(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn period_buckets() {
+        let t = at(2024, 3, 6, 15, 30, 0); // Wednesday
+        assert_eq!(
+            Period::Day.start(Period::Day.bucket(t)),
+            at(2024, 3, 6, 0, 0, 0)
+        );
+        assert_eq!(
+            Period::Hour.start(Period::Hour.bucket(t)),
+            at(2024, 3, 6, 15, 0, 0)
+        );
+        assert_eq!(
+            Period::Week.start(Period::Week.bucket(t)),
+            at(2024, 3, 4, 0, 0, 0)
+        );
+        assert_eq!(
+            Period::Month.start(Period::Month.bucket(t)),
+            at(2024, 3, 1, 0, 0, 0)
+        );
+        assert_eq!(
+            Period::Month.bucket(at(2024, 1, 1, 0, 0, 0))
+                - Period::Month.bucket(at(2023, 12, 31, 0, 0, 0)),
+            1
+        );
+    }
+}
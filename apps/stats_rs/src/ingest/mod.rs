@@ -0,0 +1,74 @@
+//! # Data ingestion
+//!
+//! Parsers that turn uploaded payloads into typed columns for the stats routes.
+//!
+//! - [`csv`] — delimited text with column selection, row skipping and dtype inference.
+//! - [`csv_stream`] — the same CSV rules applied to a body stream with bounded memory.
+//! - [`datetime`] — datetime cell parsing and calendar buckets for time indexes.
+//! - [`ndjson`] — newline-delimited JSON records decoded incrementally from a stream.
+//! - [`sample`] — seeded reservoir sampling of data rows (`?sample=N`).
+//! - `xlsx` — spreadsheet worksheets through the CSV column pipeline (feature `xlsx`).
+//! - `parquet` — Parquet files through the same pipeline (feature `parquet`).
+//! - `url` — allowlisted HTTP(S) downloads (feature `fetch`).
+//! - `s3` — `s3://` objects from S3-compatible storage (feature `s3`).
+
+pub mod csv;
+pub mod csv_stream;
+pub mod datetime;
+pub mod ndjson;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "s3")]
+pub mod s3;
+pub mod sample;
+#[cfg(feature = "fetch")]
+pub mod url;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+
+pub use self::csv::*;
+#[cfg(feature = "parquet")]
+pub use self::parquet::*;
+#[cfg(feature = "s3")]
+pub use self::s3::*;
+#[cfg(feature = "fetch")]
+pub use self::url::*;
+pub use csv_stream::*;
+pub use ndjson::*;
+pub use sample::*;
+#[cfg(feature = "xlsx")]
+pub use xlsx::*;
+
+use crate::{error::ServiceError, types::DatasetFormat};
+use axum::body::Bytes;
+
+/// Guess a payload's format from its path extension, then its `Content-Type`
+/// (CSV when neither says Parquet).
+pub fn detect_format(path: &str, content_type: Option<&str>) -> DatasetFormat {
+    let path = path.to_ascii_lowercase();
+    if path.ends_with(".parquet")
+        || path.ends_with(".pq")
+        || content_type.is_some_and(|c| c.contains("parquet"))
+    {
+        DatasetFormat::Parquet
+    } else {
+        DatasetFormat::Csv
+    }
+}
+
+/// Parse a payload of the given format into typed columns.
+pub fn read_table(
+    format: DatasetFormat,
+    body: Bytes,
+    opts: &CsvOptions,
+) -> Result<CsvTable, ServiceError> {
+    match format {
+        DatasetFormat::Csv => read_csv(&body, opts),
+        #[cfg(feature = "parquet")]
+        DatasetFormat::Parquet => read_parquet(body, opts),
+        #[cfg(not(feature = "parquet"))]
+        DatasetFormat::Parquet => Err(ServiceError::InvalidInput(
+            "parquet support is not enabled in this build (feature `parquet`)".into(),
+        )),
+    }
+}
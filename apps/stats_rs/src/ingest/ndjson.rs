@@ -0,0 +1,223 @@
+//! NDJSON ingestion: incremental line decoding and per-field running summaries.
+
+use crate::{
+    error::ServiceError,
+    stats::OnlineMeanVar,
+    types::{ColumnType, NdjsonFieldOut},
+};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Longest accepted record line; guards the buffer against input without newlines.
+pub const MAX_LINE_BYTES: usize = 8 * 1024 * 1024;
+
+/// Splits a chunked byte stream into JSON-object records, one per line.
+///
+/// Blank lines and a trailing `\r` are ignored; a final line without a
+/// newline is handled by [`NdjsonDecoder::finish`].
+#[derive(Debug, Default)]
+pub struct NdjsonDecoder {
+    buf: Vec<u8>,
+    line: usize,
+}
+
+impl NdjsonDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one chunk, calling `f` for every complete record.
+    pub fn push<F>(&mut self, chunk: &[u8], mut f: F) -> Result<(), ServiceError>
+    where
+        F: FnMut(Map<String, Value>),
+    {
+        self.buf.extend_from_slice(chunk);
+        let mut start = 0;
+        while let Some(off) = self.buf[start..].iter().position(|&b| b == b'\n') {
+            let end = start + off;
+            self.line += 1;
+            if let Some(rec) = parse_line(&self.buf[start..end], self.line)? {
+                f(rec);
+            }
+            start = end + 1;
+        }
+        self.buf.drain(..start);
+        if self.buf.len() > MAX_LINE_BYTES {
+            return Err(ServiceError::InvalidInput(format!(
+                "line {} exceeds {MAX_LINE_BYTES} bytes",
+                self.line + 1
+            )));
+        }
+        Ok(())
+    }
+
+    /// Flush the last line if the stream did not end with a newline.
+    pub fn finish<F>(mut self, mut f: F) -> Result<(), ServiceError>
+    where
+        F: FnMut(Map<String, Value>),
+    {
+        if let Some(rec) = parse_line(&self.buf, self.line + 1)? {
+            f(rec);
+        }
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+fn parse_line(raw: &[u8], line: usize) -> Result<Option<Map<String, Value>>, ServiceError> {
+    let raw = raw.strip_suffix(b"\r").unwrap_or(raw);
+    if raw.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    match serde_json::from_slice::<Value>(raw) {
+        Ok(Value::Object(m)) => Ok(Some(m)),
+        Ok(_) => Err(ServiceError::InvalidInput(format!(
+            "line {line}: expected a JSON object"
+        ))),
+        Err(e) => Err(ServiceError::InvalidInput(format!("line {line}: {e}"))),
+    }
+}
+
+/// Running per-field summary of one record field.
+#[derive(Clone, Debug, Default)]
+struct FieldAcc {
+    present: u64,
+    nulls: u64,
+    numbers: OnlineMeanVar,
+    all_integers: bool,
+    min: f64,
+    max: f64,
+    booleans: u64,
+    strings: u64,
+    other: u64,
+}
+
+impl FieldAcc {
+    fn push(&mut self, v: &Value) {
+        self.present += 1;
+        match v {
+            Value::Null => self.nulls += 1,
+            Value::Number(n) => {
+                let x = n.as_f64().unwrap_or(f64::NAN);
+                if self.numbers.count() == 0 {
+                    self.all_integers = true;
+                    (self.min, self.max) = (x, x);
+                }
+                self.all_integers &= n.is_i64() || n.is_u64();
+                self.min = self.min.min(x);
+                self.max = self.max.max(x);
+                self.numbers.push(x);
+            }
+            Value::Bool(_) => self.booleans += 1,
+            Value::String(_) => self.strings += 1,
+            Value::Array(_) | Value::Object(_) => self.other += 1,
+        }
+    }
+
+    fn dtype(&self) -> ColumnType {
+        let n = self.numbers.count();
+        let kinds = [n, self.booleans, self.strings, self.other]
+            .iter()
+            .filter(|&&c| c > 0)
+            .count();
+        match kinds {
+            0 => ColumnType::Empty,
+            1 if n > 0 && self.all_integers => ColumnType::Integer,
+            1 if n > 0 => ColumnType::Float,
+            1 if self.booleans > 0 => ColumnType::Boolean,
+            _ => ColumnType::String,
+        }
+    }
+}
+
+/// Streaming summary over NDJSON records: memory grows with the number of
+/// distinct field names, not with the number of records.
+#[derive(Clone, Debug, Default)]
+pub struct RecordSummary {
+    records: u64,
+    fields: BTreeMap<String, FieldAcc>,
+}
+
+impl RecordSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, rec: &Map<String, Value>) {
+        self.records += 1;
+        for (k, v) in rec {
+            self.fields.entry(k.clone()).or_default().push(v);
+        }
+    }
+
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Per-field summaries sorted by field name.
+    pub fn fields(&self) -> Vec<NdjsonFieldOut> {
+        #[inline]
+        fn o(x: f64) -> Option<f64> {
+            if x.is_nan() { None } else { Some(x) }
+        }
+        self.fields
+            .iter()
+            .map(|(name, a)| {
+                let has_num = a.numbers.count() > 0;
+                NdjsonFieldOut {
+                    name: name.clone(),
+                    dtype: a.dtype(),
+                    present: a.present,
+                    missing: self.records - a.present + a.nulls,
+                    numeric_count: a.numbers.count(),
+                    mean: has_num.then(|| a.numbers.mean()),
+                    std_dev: o(a.numbers.sample_std()),
+                    min: has_num.then_some(a.min),
+                    max: has_num.then_some(a.max),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx;
+
+    #[test]
+    fn decoder_handles_split_chunks_and_trailing_line() {
+        let mut dec = NdjsonDecoder::new();
+        let mut got = Vec::new();
+        for chunk in [&b"{\"a\":1}\n{\"a\""[..], b":2}\r\n\n", b"{\"a\":3}"] {
+            dec.push(chunk, |r| got.push(r["a"].as_i64().unwrap()))
+                .unwrap();
+        }
+        dec.finish(|r| got.push(r["a"].as_i64().unwrap())).unwrap();
+        assert_eq!(got, vec![1, 2, 3]);
+
+        let mut dec = NdjsonDecoder::new();
+        assert!(dec.push(b"{\"a\":1}\n[1,2]\n", |_| {}).is_err());
+    }
+
+    #[test]
+    fn summary_types_and_moments() {
+        let mut s = RecordSummary::new();
+        let mut dec = NdjsonDecoder::new();
+        dec.push(
+            b"{\"x\":1,\"y\":\"a\",\"z\":true}\n{\"x\":2.5,\"y\":null}\n{\"x\":4}\n",
+            |r| s.push(&r),
+        )
+        .unwrap();
+        assert_eq!(s.records(), 3);
+
+        let f = s.fields();
+        let (x, y, z) = (&f[0], &f[1], &f[2]);
+        assert_eq!(x.dtype, ColumnType::Float);
+        approx!(x.mean.unwrap(), 2.5, 1e-12);
+        assert_eq!((x.min, x.max), (Some(1.0), Some(4.0)));
+        assert_eq!((y.dtype, y.present, y.missing), (ColumnType::String, 2, 2));
+        assert_eq!((z.dtype, z.missing), (ColumnType::Boolean, 2));
+        assert_eq!(z.mean, None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Apache Parquet ingestion (feature `parquet`).
+//!
+//! Top-level fields become columns and are rendered to text, then go through
+//! the same selection and type inference as CSV.
+
+use super::{
+    csv::{CsvOptions, CsvTable, table_from_rows},
+    datetime::{civil_from_days, timestamp_text},
+};
+use crate::error::ServiceError;
+use axum::body::Bytes;
+use parquet::{
+    file::reader::{FileReader, SerializedFileReader},
+    record::Field,
+};
+
+/// Render one value as the text CSV ingestion would have seen.
+fn field_text(f: &Field) -> String {
+    match f {
+        Field::Null => String::new(),
+        Field::Str(s) => s.trim().to_string(),
+        Field::Float(x) => x.to_string(),
+        Field::Double(x) => x.to_string(),
+        Field::Date(days) => {
+            let (y, m, d) = civil_from_days(i64::from(*days));
+            format!("{y:04}-{m:02}-{d:02}")
+        }
+        Field::TimestampMillis(ms) => timestamp_text(*ms),
+        Field::TimestampMicros(us) => timestamp_text(us.div_euclid(1000)),
+        other => other.to_string(),
+    }
+}
+
+/// Parse a Parquet payload into typed columns.
+pub fn read_parquet(bytes: Bytes, opts: &CsvOptions) -> Result<CsvTable, ServiceError> {
+    let reader = SerializedFileReader::new(bytes)
+        .map_err(|e| ServiceError::InvalidInput(format!("invalid parquet: {e}")))?;
+    let header: Vec<String> = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .map(|f| f.name().to_string())
+        .collect();
+    let mut rows = vec![header];
+    let iter = reader
+        .get_row_iter(None)
+        .map_err(|e| ServiceError::InvalidInput(format!("invalid parquet: {e}")))?;
+    for row in iter.skip(opts.skip_rows) {
+        let row = row.map_err(|e| ServiceError::InvalidInput(format!("invalid parquet: {e}")))?;
+        rows.push(row.get_column_iter().map(|(_, f)| field_text(f)).collect());
+    }
+    let opts = CsvOptions {
+        has_header: Some(true),
+        ..opts.clone()
+    };
+    table_from_rows(rows, &opts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_columns_with_nulls() {
+        use parquet::{
+            data_type::{ByteArray, ByteArrayType, DoubleType},
+            file::{properties::WriterProperties, writer::SerializedFileWriter},
+            schema::parser::parse_message_type,
+        };
+        use std::sync::Arc;
+
+        let schema = Arc::new(
+            parse_message_type("message t { required binary city (UTF8); optional double temp; }")
+                .unwrap(),
+        );
+        let mut buf = Vec::new();
+        let mut w =
+            SerializedFileWriter::new(&mut buf, schema, Arc::new(WriterProperties::default()))
+                .unwrap();
+        let mut rg = w.next_row_group().unwrap();
+        let mut c = rg.next_column().unwrap().unwrap();
+        let cities: Vec<ByteArray> = ["Oslo", "Lima", "Pune"].map(ByteArray::from).to_vec();
+        c.typed::<ByteArrayType>()
+            .write_batch(&cities, None, None)
+            .unwrap();
+        c.close().unwrap();
+        let mut c = rg.next_column().unwrap().unwrap();
+        c.typed::<DoubleType>()
+            .write_batch(&[4.5, 31.0], Some(&[1, 0, 1]), None)
+            .unwrap();
+        c.close().unwrap();
+        rg.close().unwrap();
+        w.close().unwrap();
+
+        let t = read_parquet(Bytes::from(buf), &CsvOptions::default()).unwrap();
+        let s = t.schema();
+        assert_eq!(t.n_rows, 3);
+        assert_eq!(
+            (s[0].name.as_str(), s[0].dtype),
+            ("city", crate::types::ColumnType::String)
+        );
+        assert_eq!((s[1].name.as_str(), s[1].missing), ("temp", 1));
+        assert_eq!(t.numeric_cells(), vec![4.5, 31.0]);
+    }
+}
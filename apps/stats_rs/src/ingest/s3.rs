@@ -0,0 +1,127 @@
+//! `s3://bucket/key` downloads from S3-compatible object storage (feature `s3`).
+//!
+//! Credentials and endpoint come from [`S3Config`](crate::config::S3Config)
+//! (the `[s3]` settings);
+//! the URI must still match the ingestion allowlist (bucket as host).
+
+use super::url::Fetched;
+use crate::{
+    config::{IngestConfig, S3Config},
+    error::ServiceError,
+};
+use object_store::{
+    ObjectStore,
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path,
+};
+use reqwest::Url;
+
+/// Parse and authorize an `s3://bucket/key` URI; returns `(url, bucket, key)`.
+pub fn check_s3_uri(cfg: &IngestConfig, raw: &str) -> Result<(Url, String, Path), ServiceError> {
+    let url = Url::parse(raw).map_err(|e| ServiceError::InvalidInput(format!("url: {e}")))?;
+    let bucket = url
+        .host_str()
+        .filter(|b| url.scheme() == "s3" && !b.is_empty())
+        .ok_or_else(|| ServiceError::InvalidInput("expected s3://bucket/key".into()))?
+        .to_string();
+    let key = Path::from_url_path(url.path())
+        .map_err(|e| ServiceError::InvalidInput(format!("s3 key: {e}")))?;
+    if key.as_ref().is_empty() {
+        return Err(ServiceError::InvalidInput(
+            "s3 URI is missing an object key".into(),
+        ));
+    }
+    if !cfg.url_allowed(url.as_str(), &bucket) {
+        return Err(ServiceError::Forbidden(format!(
+            "bucket {bucket} is not in STATS_URL_ALLOWLIST"
+        )));
+    }
+    Ok((url, bucket, key))
+}
+
+/// Client for `bucket` with the configured region, endpoint and credentials.
+pub fn bucket_store(s3: &S3Config, bucket: &str) -> Result<AmazonS3, ServiceError> {
+    let mut b = AmazonS3Builder::new()
+        .with_bucket_name(bucket)
+        .with_region(s3.region.as_deref().unwrap_or("us-east-1"))
+        .with_allow_http(s3.allow_http);
+    if let Some(e) = &s3.endpoint {
+        b = b.with_endpoint(e);
+    }
+    if let Some(k) = &s3.access_key_id {
+        b = b.with_access_key_id(k);
+    }
+    if let Some(k) = &s3.secret_access_key {
+        b = b.with_secret_access_key(k);
+    }
+    if let Some(t) = &s3.session_token {
+        b = b.with_token(t);
+    }
+    b.build()
+        .map_err(|e| ServiceError::InvalidInput(format!("s3 config: {e}")))
+}
+
+/// Download one object within the configured size and timeout limits.
+pub async fn fetch_s3(
+    cfg: &IngestConfig,
+    s3: &S3Config,
+    raw: &str,
+) -> Result<Fetched, ServiceError> {
+    let (url, bucket, key) = check_s3_uri(cfg, raw)?;
+    let store = bucket_store(s3, &bucket)?;
+
+    let map_err = |e: object_store::Error| match e {
+        object_store::Error::NotFound { .. } => ServiceError::NotFound(url.to_string()),
+        e => ServiceError::Upstream(e.to_string()),
+    };
+    let get = async {
+        let meta = store.head(&key).await.map_err(map_err)?;
+        if meta.size > cfg.max_fetch_bytes as u64 {
+            return Err(ServiceError::TooLarge(format!(
+                "object is {} bytes, limit is {}",
+                meta.size, cfg.max_fetch_bytes
+            )));
+        }
+        store
+            .get(&key)
+            .await
+            .map_err(map_err)?
+            .bytes()
+            .await
+            .map_err(map_err)
+    };
+    let body = tokio::time::timeout(cfg.fetch_timeout(), get)
+        .await
+        .map_err(|_| ServiceError::Upstream(format!("timed out fetching {url}")))??;
+
+    Ok(Fetched {
+        url,
+        content_type: None,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bucket_and_key() {
+        let cfg = IngestConfig {
+            url_allowlist: vec!["lake".into()],
+            ..Default::default()
+        };
+        let (_, bucket, key) = check_s3_uri(&cfg, "s3://lake/a/b%20c.csv").unwrap();
+        assert_eq!(bucket, "lake");
+        assert_eq!(key.as_ref(), "a/b c.csv");
+
+        assert!(matches!(
+            check_s3_uri(&cfg, "s3://lake/"),
+            Err(ServiceError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            check_s3_uri(&cfg, "s3://other/x.csv"),
+            Err(ServiceError::Forbidden(_))
+        ));
+    }
+}
@@ -0,0 +1,115 @@
+//! Seeded uniform row sampling.
+//!
+//! [`Reservoir`] keeps a uniform sample of at most `size` rows from a stream of
+//! unknown length (Vitter's Algorithm R). Rows are only materialized when they
+//! enter the reservoir, so a 5M-row file sampled down to 10k rows allocates
+//! cells for roughly `size · ln(n / size)` rows instead of all of them.
+
+use crate::types::SampleOut;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+/// Requested row sample: at most `size` data rows drawn with `seed`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowSample {
+    pub size: usize,
+    pub seed: u64,
+}
+
+/// Uniform reservoir over offered items, tagged with their arrival order.
+#[derive(Debug)]
+pub struct Reservoir<T> {
+    spec: RowSample,
+    rng: StdRng,
+    seen: usize,
+    items: Vec<(usize, T)>,
+}
+
+impl<T> Reservoir<T> {
+    pub fn new(spec: RowSample) -> Self {
+        Self {
+            spec,
+            rng: StdRng::seed_from_u64(spec.seed),
+            seen: 0,
+            items: Vec::with_capacity(spec.size.min(1 << 16)),
+        }
+    }
+
+    /// Offer the next item, building it only if it is kept.
+    pub fn offer_with(&mut self, make: impl FnOnce() -> T) {
+        let i = self.seen;
+        self.seen += 1;
+        if self.items.len() < self.spec.size {
+            self.items.push((i, make()));
+        } else if self.spec.size > 0 {
+            let j = self.rng.random_range(0..=i);
+            if j < self.spec.size {
+                self.items[j] = (i, make());
+            }
+        }
+    }
+
+    /// Items offered so far.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// What was drawn, for echoing back to the caller.
+    pub fn summary(&self) -> SampleOut {
+        SampleOut {
+            seed: self.spec.seed,
+            rows_seen: self.seen,
+            rows_sampled: self.items.len(),
+            fraction: if self.seen == 0 {
+                1.0
+            } else {
+                self.items.len() as f64 / self.seen as f64
+            },
+        }
+    }
+
+    /// The kept items in their original order.
+    pub fn into_ordered(mut self) -> Vec<T> {
+        self.items.sort_unstable_by_key(|(i, _)| *i);
+        self.items.into_iter().map(|(_, t)| t).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(size: usize, seed: u64, n: usize) -> (Vec<usize>, SampleOut) {
+        let mut r = Reservoir::new(RowSample { size, seed });
+        (0..n).for_each(|i| r.offer_with(|| i));
+        let s = r.summary();
+        (r.into_ordered(), s)
+    }
+
+    #[test]
+    fn seeded_ordered_and_bounded() {
+        let (a, s) = draw(100, 7, 10_000);
+        assert_eq!(a.len(), 100);
+        assert!(a.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!((s.rows_seen, s.rows_sampled), (10_000, 100));
+        assert!((s.fraction - 0.01).abs() < 1e-12);
+
+        assert_eq!(draw(100, 7, 10_000).0, a);
+        assert_ne!(draw(100, 8, 10_000).0, a);
+
+        // Fewer rows than the sample size keeps everything
+        assert_eq!(draw(100, 7, 5).0, vec![0, 1, 2, 3, 4]);
+        assert!(draw(0, 7, 5).0.is_empty());
+    }
+
+    #[test]
+    fn roughly_uniform() {
+        // Each of 10 deciles should get ~1/10 of a 2000-row sample
+        let (a, _) = draw(2_000, 42, 100_000);
+        let mut deciles = [0usize; 10];
+        a.iter().for_each(|&i| deciles[i / 10_000] += 1);
+        assert!(
+            deciles.iter().all(|&c| (150..250).contains(&c)),
+            "{deciles:?}"
+        );
+    }
+}
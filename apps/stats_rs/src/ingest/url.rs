@@ -0,0 +1,115 @@
+//! HTTP(S) dataset download (feature `fetch`).
+//!
+//! Only URLs matching [`IngestConfig::url_allowed`] are fetched, including
+//! every redirect hop, and downloads are capped in size and time.
+
+use crate::{
+    config::{IngestConfig, ServiceConfig},
+    error::ServiceError,
+};
+use axum::body::Bytes;
+use reqwest::{Url, header::CONTENT_TYPE, redirect};
+
+/// Maximum redirect hops followed.
+const MAX_REDIRECTS: usize = 5;
+
+/// A completed download.
+#[derive(Clone, Debug)]
+pub struct Fetched {
+    /// Final URL after redirects
+    pub url: Url,
+    pub content_type: Option<String>,
+    pub body: Bytes,
+}
+
+fn allowed(cfg: &IngestConfig, url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "https")
+        && url
+            .host_str()
+            .is_some_and(|h| cfg.url_allowed(url.as_str(), h))
+}
+
+/// Parse `raw` and check it against the scheme rules and the allowlist.
+pub fn check_url(cfg: &IngestConfig, raw: &str) -> Result<Url, ServiceError> {
+    let url = Url::parse(raw).map_err(|e| ServiceError::InvalidInput(format!("url: {e}")))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ServiceError::InvalidInput(
+            "url must use http or https".into(),
+        ));
+    }
+    if !allowed(cfg, &url) {
+        return Err(ServiceError::Forbidden(format!(
+            "{} is not in STATS_URL_ALLOWLIST",
+            url.host_str().unwrap_or_default()
+        )));
+    }
+    Ok(url)
+}
+
+/// Download `raw` within the configured size and timeout limits.
+pub async fn fetch_url(cfg: &IngestConfig, raw: &str) -> Result<Fetched, ServiceError> {
+    let url = check_url(cfg, raw)?;
+    let upstream = |e: reqwest::Error| ServiceError::Upstream(e.to_string());
+
+    let policy_cfg = cfg.clone();
+    let client = reqwest::Client::builder()
+        .timeout(cfg.fetch_timeout())
+        .redirect(redirect::Policy::custom(move |a| {
+            if a.previous().len() >= MAX_REDIRECTS {
+                a.error("too many redirects")
+            } else if allowed(&policy_cfg, a.url()) {
+                a.follow()
+            } else {
+                a.error("redirect target is not allowlisted")
+            }
+        }))
+        .build()
+        .map_err(upstream)?;
+
+    let mut resp = client.get(url).send().await.map_err(upstream)?;
+    if !resp.status().is_success() {
+        return Err(ServiceError::Upstream(format!(
+            "{} returned {}",
+            resp.url(),
+            resp.status()
+        )));
+    }
+    let too_large =
+        || ServiceError::TooLarge(format!("download exceeds {} bytes", cfg.max_fetch_bytes));
+    if resp
+        .content_length()
+        .is_some_and(|n| n > cfg.max_fetch_bytes as u64)
+    {
+        return Err(too_large());
+    }
+
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let final_url = resp.url().clone();
+    let mut buf = Vec::new();
+    while let Some(chunk) = resp.chunk().await.map_err(upstream)? {
+        if buf.len() + chunk.len() > cfg.max_fetch_bytes {
+            return Err(too_large());
+        }
+        buf.extend_from_slice(&chunk);
+    }
+
+    Ok(Fetched {
+        url: final_url,
+        content_type,
+        body: Bytes::from(buf),
+    })
+}
+
+/// Fetch an `http(s)://` URL, or an `s3://` URI (with `cfg.s3`'s
+/// credentials) when built with feature `s3`.
+pub async fn fetch_any(cfg: &ServiceConfig, raw: &str) -> Result<Fetched, ServiceError> {
+    #[cfg(feature = "s3")]
+    if raw.starts_with("s3://") {
+        return super::s3::fetch_s3(&cfg.ingest, &cfg.s3, raw).await;
+    }
+    fetch_url(&cfg.ingest, raw).await
+}
@@ -0,0 +1,65 @@
+//! Spreadsheet (`.xlsx`) ingestion via `calamine` (feature `xlsx`).
+//!
+//! Cells are rendered to text and fed through the same column selection and
+//! type inference as CSV, so both formats echo identical schemas.
+
+use super::csv::{CsvOptions, CsvTable, table_from_rows};
+use crate::error::ServiceError;
+use calamine::{Data, Reader, Xlsx};
+use std::io::Cursor;
+
+/// Render one cell as the text CSV ingestion would have seen.
+fn cell_text(d: &Data) -> String {
+    match d {
+        Data::Int(i) => i.to_string(),
+        Data::Float(f) => f.to_string(),
+        Data::String(s) => s.trim().to_string(),
+        Data::Bool(b) => b.to_string(),
+        Data::DateTime(dt) if dt.is_datetime() => {
+            let (y, mo, d, h, mi, s, _) = dt.to_ymd_hms_milli();
+            if (h, mi, s) == (0, 0, 0) {
+                format!("{y:04}-{mo:02}-{d:02}")
+            } else {
+                format!("{y:04}-{mo:02}-{d:02}T{h:02}:{mi:02}:{s:02}")
+            }
+        }
+        Data::DateTime(dt) => dt.as_f64().to_string(),
+        Data::DateTimeIso(s) | Data::DurationIso(s) => s.clone(),
+        Data::Error(_) | Data::Empty => String::new(),
+    }
+}
+
+/// Parse one worksheet of an `.xlsx` payload into typed columns.
+///
+/// `sheet` defaults to the first worksheet; returns the sheet name used.
+pub fn read_xlsx(
+    bytes: &[u8],
+    sheet: Option<&str>,
+    opts: &CsvOptions,
+) -> Result<(String, CsvTable), ServiceError> {
+    let mut wb: Xlsx<_> =
+        Xlsx::new(Cursor::new(bytes)).map_err(|_| ServiceError::SpreadsheetParse)?;
+    let names = wb.sheet_names();
+    let name = match sheet {
+        Some(s) if names.iter().any(|n| n == s) => s.to_string(),
+        Some(s) => {
+            return Err(ServiceError::InvalidInput(format!(
+                "unknown sheet '{s}' (available: {})",
+                names.join(", ")
+            )));
+        }
+        None => names
+            .first()
+            .cloned()
+            .ok_or(ServiceError::SpreadsheetParse)?,
+    };
+    let range = wb
+        .worksheet_range(&name)
+        .map_err(|_| ServiceError::SpreadsheetParse)?;
+    let rows: Vec<Vec<String>> = range
+        .rows()
+        .skip(opts.skip_rows)
+        .map(|r| r.iter().map(cell_text).collect())
+        .collect();
+    Ok((name, table_from_rows(rows, opts)?))
+}
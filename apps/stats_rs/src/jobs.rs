@@ -0,0 +1,574 @@
+//! # Background jobs
+//!
+//! Registry of long-running analyses submitted through `POST /jobs`
+//! (`job_1`, `job_2`, …). Each job runs on Tokio's blocking pool, detached
+//! from the request that created it, so it is not subject to the request
+//! timeout. At most `workers` jobs run at once; the rest wait as `queued`.
+//! Like [`DatasetRegistry`](crate::datasets::DatasetRegistry) it lives in
+//! [`AppState`](crate::state::AppState) and is lost on restart; only the most
+//! recent [`MAX_FINISHED_JOBS`] finished jobs are kept.
+//!
+//! [`JobRegistry::submit_idempotent`] remembers the `Idempotency-Key` a job
+//! was submitted under for as long as the job itself is kept, so a client
+//! retrying after a lost response gets the original job instead of a second
+//! computation.
+//!
+//! With an [`ArtifactStore`] attached ([`JobRegistry::with_artifacts`]),
+//! results serializing to more than `artifacts.inline_max_bytes` are moved
+//! there once the job succeeds and the job keeps only the artifact id.
+//!
+//! Jobs, their idempotency keys and offloaded results belong to the
+//! [`Tenant`] that submitted them; [`JobRegistry::get`] does not find another
+//! tenant's jobs.
+//!
+//! [`Job::finished`] resolves once a job succeeds, fails or is cancelled, for
+//! callers that react to completion (job webhooks) instead of polling.
+
+use crate::{
+    artifacts::ArtifactStore,
+    cache::CacheKey,
+    tenant::Tenant,
+    types::{JobKind, JobOut, JobStatus},
+};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::{Notify, Semaphore};
+
+/// Finished jobs retained for status/result lookups.
+pub const MAX_FINISHED_JOBS: usize = 1000;
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[derive(Debug)]
+enum JobState {
+    Queued,
+    Running,
+    Succeeded { output: Output, finished_at: u64 },
+    Failed { error: String, finished_at: u64 },
+    Cancelled { finished_at: u64 },
+}
+
+/// Where a succeeded job's result is kept.
+#[derive(Debug)]
+enum Output {
+    Inline(Value),
+    /// Id in the registry's artifact store
+    Artifact(String),
+}
+
+/// One submitted job.
+#[derive(Debug)]
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    pub tenant: Tenant,
+    /// Submission time, seconds since the Unix epoch
+    pub created_at: u64,
+    state: Mutex<JobState>,
+    /// `f64` bits of the completed fraction
+    progress: AtomicU64,
+    /// Woken when the job reaches a final state
+    done: Notify,
+}
+
+impl Job {
+    fn set_state(&self, s: JobState) {
+        *self.state.lock().unwrap() = s;
+    }
+
+    pub fn status(&self) -> JobOut {
+        let state = self.state.lock().unwrap();
+        let (status, finished_at, error) = match &*state {
+            JobState::Queued => (JobStatus::Queued, None, None),
+            JobState::Running => (JobStatus::Running, None, None),
+            JobState::Succeeded { finished_at, .. } => {
+                (JobStatus::Succeeded, Some(*finished_at), None)
+            }
+            JobState::Failed { error, finished_at } => {
+                (JobStatus::Failed, Some(*finished_at), Some(error.clone()))
+            }
+            JobState::Cancelled { finished_at } => (JobStatus::Cancelled, Some(*finished_at), None),
+        };
+        JobOut {
+            id: self.id.clone(),
+            kind: self.kind,
+            status,
+            progress: f64::from_bits(self.progress.load(Ordering::Relaxed)),
+            created_at: self.created_at,
+            finished_at,
+            error,
+            artifact: match &*state {
+                JobState::Succeeded {
+                    output: Output::Artifact(id),
+                    ..
+                } => Some(id.clone()),
+                _ => None,
+            },
+        }
+    }
+
+    /// The output of a succeeded job, unless it was moved to an artifact.
+    pub fn result(&self) -> Option<Value> {
+        match &*self.state.lock().unwrap() {
+            JobState::Succeeded {
+                output: Output::Inline(result),
+                ..
+            } => Some(result.clone()),
+            _ => None,
+        }
+    }
+
+    /// Id of the artifact a succeeded job's output was moved to.
+    pub fn artifact(&self) -> Option<String> {
+        match &*self.state.lock().unwrap() {
+            JobState::Succeeded {
+                output: Output::Artifact(id),
+                ..
+            } => Some(id.clone()),
+            _ => None,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        matches!(
+            *self.state.lock().unwrap(),
+            JobState::Queued | JobState::Running
+        )
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(
+            *self.state.lock().unwrap(),
+            JobState::Succeeded { .. } | JobState::Failed { .. } | JobState::Cancelled { .. }
+        )
+    }
+
+    /// Final status, once the job has succeeded, failed or been cancelled.
+    pub async fn finished(&self) -> JobOut {
+        loop {
+            // Registered before the check, so a finish in between still wakes it
+            let woken = self.done.notified();
+            if self.is_finished() {
+                return self.status();
+            }
+            woken.await;
+        }
+    }
+}
+
+/// Progress reporter handed to a running job.
+#[derive(Clone, Debug)]
+pub struct Progress(Arc<Job>);
+
+impl Progress {
+    /// Record that `done` of `total` units of work are complete.
+    pub fn set(&self, done: usize, total: usize) {
+        let f = if total == 0 {
+            1.0
+        } else {
+            (done as f64 / total as f64).clamp(0.0, 1.0)
+        };
+        self.0.progress.store(f.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Outcome of [`JobRegistry::submit_idempotent`].
+#[derive(Debug)]
+pub enum Submission {
+    /// A new job was queued.
+    Created(Arc<Job>),
+    /// The key was already used for the same request; this is its job.
+    Replayed(Arc<Job>),
+    /// The key was already used for a different request.
+    KeyReused,
+}
+
+/// An `Idempotency-Key` as seen by one tenant.
+type IdempotencyKey = (Tenant, String);
+
+/// Shared, cheaply clonable handle to submitted jobs.
+#[derive(Clone, Debug)]
+pub struct JobRegistry {
+    inner: Arc<RwLock<HashMap<String, Arc<Job>>>>,
+    /// (tenant, idempotency key) → (request fingerprint, job id)
+    keys: Arc<Mutex<HashMap<IdempotencyKey, (CacheKey, String)>>>,
+    next_id: Arc<AtomicU64>,
+    workers: Arc<Semaphore>,
+    capacity: usize,
+    /// Store for large results, and the size above which they go there
+    artifacts: Option<(ArtifactStore, usize)>,
+}
+
+impl Default for JobRegistry {
+    /// One worker per available CPU.
+    fn default() -> Self {
+        Self::new(std::thread::available_parallelism().map_or(1, usize::from))
+    }
+}
+
+impl JobRegistry {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            inner: Arc::default(),
+            keys: Arc::default(),
+            next_id: Arc::default(),
+            workers: Arc::new(Semaphore::new(workers.max(1))),
+            capacity: workers.max(1),
+            artifacts: None,
+        }
+    }
+
+    /// Move results serializing to more than `inline_max_bytes` to `store`.
+    pub fn with_artifacts(mut self, store: ArtifactStore, inline_max_bytes: usize) -> Self {
+        self.artifacts = Some((store, inline_max_bytes));
+        self
+    }
+
+    /// Register a job of `tenant` and start `work` once a worker is free.
+    /// Must be called from within a Tokio runtime.
+    pub fn submit<F>(&self, tenant: &Tenant, kind: JobKind, work: F) -> Arc<Job>
+    where
+        F: FnOnce(&Progress) -> Result<Value, String> + Send + 'static,
+    {
+        let id = format!("job_{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let job = Arc::new(Job {
+            id: id.clone(),
+            kind,
+            tenant: tenant.clone(),
+            created_at: now_secs(),
+            state: Mutex::new(JobState::Queued),
+            progress: AtomicU64::new(0.0f64.to_bits()),
+            done: Notify::new(),
+        });
+        {
+            let mut jobs = self.inner.write().unwrap();
+            jobs.insert(id, job.clone());
+            prune_finished(&mut jobs);
+        }
+
+        let workers = self.workers.clone();
+        let artifacts = self.artifacts.clone();
+        let running = job.clone();
+        tokio::spawn(async move {
+            let Ok(_permit) = workers.acquire_owned().await else {
+                return;
+            };
+            {
+                // A drained job never starts
+                let mut state = running.state.lock().unwrap();
+                if !matches!(*state, JobState::Queued) {
+                    return;
+                }
+                *state = JobState::Running;
+            }
+            let progress = Progress(running.clone());
+            let outcome = tokio::task::spawn_blocking(move || work(&progress)).await;
+            let finished_at = now_secs();
+            running.set_state(match outcome {
+                Ok(Ok(result)) => {
+                    Progress(running.clone()).set(1, 1);
+                    let output = match &artifacts {
+                        Some((store, max)) => offload(&running, store, *max, result).await,
+                        None => Output::Inline(result),
+                    };
+                    JobState::Succeeded {
+                        output,
+                        finished_at,
+                    }
+                }
+                Ok(Err(error)) => JobState::Failed { error, finished_at },
+                Err(e) => JobState::Failed {
+                    error: format!("job aborted: {e}"),
+                    finished_at,
+                },
+            });
+            running.done.notify_waiters();
+        });
+        job
+    }
+
+    /// [`submit`](Self::submit) under an idempotency key: the first request
+    /// with `key` queues `work`; later ones with the same `fingerprint` get
+    /// that job back while it is kept. Keys expire with their jobs and are
+    /// scoped to `tenant`.
+    pub fn submit_idempotent<F>(
+        &self,
+        tenant: &Tenant,
+        key: &str,
+        fingerprint: CacheKey,
+        kind: JobKind,
+        work: F,
+    ) -> Submission
+    where
+        F: FnOnce(&Progress) -> Result<Value, String> + Send + 'static,
+    {
+        // Held across the submit so concurrent retries queue one job
+        let mut keys = self.keys.lock().unwrap();
+        let key = (tenant.clone(), key.to_string());
+        if let Some((seen, id)) = keys.get(&key)
+            && let Some(job) = self.get(tenant, id)
+        {
+            return if *seen == fingerprint {
+                Submission::Replayed(job)
+            } else {
+                Submission::KeyReused
+            };
+        }
+        let job = self.submit(tenant, kind, work);
+        {
+            let jobs = self.inner.read().unwrap();
+            keys.retain(|_, (_, id)| jobs.contains_key(id));
+        }
+        keys.insert(key, (fingerprint, job.id.clone()));
+        Submission::Created(job)
+    }
+
+    /// The job `tenant` submitted as `id`, while it is kept.
+    pub fn get(&self, tenant: &Tenant, id: &str) -> Option<Arc<Job>> {
+        let jobs = self.inner.read().unwrap();
+        jobs.get(id).filter(|job| job.tenant == *tenant).cloned()
+    }
+
+    /// How many of `tenant`'s jobs are queued or running.
+    pub fn active(&self, tenant: &Tenant) -> usize {
+        self.inner
+            .read()
+            .unwrap()
+            .values()
+            .filter(|job| job.tenant == *tenant && job.is_active())
+            .count()
+    }
+
+    /// Cancel every job still waiting for a worker; running jobs finish.
+    /// Returns how many were cancelled.
+    pub fn drain(&self) -> usize {
+        let finished_at = now_secs();
+        self.inner
+            .read()
+            .unwrap()
+            .values()
+            .filter(|job| {
+                let mut state = job.state.lock().unwrap();
+                let queued = matches!(*state, JobState::Queued);
+                if queued {
+                    *state = JobState::Cancelled { finished_at };
+                    job.done.notify_waiters();
+                }
+                queued
+            })
+            .count()
+    }
+
+    /// Resolves once no job is running. Queued jobs still start first, so
+    /// [`drain`](Self::drain) before waiting on shutdown.
+    pub async fn idle(&self) {
+        let _ = self.workers.acquire_many(self.capacity as u32).await;
+    }
+}
+
+/// `result` of `job` stored as an artifact of its tenant if it serializes to
+/// more than `inline_max` bytes. A failed store is logged and keeps it inline.
+async fn offload(job: &Job, store: &ArtifactStore, inline_max: usize, result: Value) -> Output {
+    let bytes = match serde_json::to_vec(&result) {
+        Ok(b) if b.len() > inline_max => b,
+        _ => return Output::Inline(result),
+    };
+    match store
+        .put(&job.tenant, "application/json", bytes.into())
+        .await
+    {
+        Ok(meta) => Output::Artifact(meta.id),
+        Err(e) => {
+            tracing::warn!(
+                job = job.id,
+                "result kept inline, artifact store failed: {e}"
+            );
+            Output::Inline(result)
+        }
+    }
+}
+
+/// Drop the oldest finished jobs beyond [`MAX_FINISHED_JOBS`].
+fn prune_finished(jobs: &mut HashMap<String, Arc<Job>>) {
+    let mut finished: Vec<(u64, String)> = jobs
+        .values()
+        .filter(|j| j.is_finished())
+        .map(|j| (j.id[4..].parse().unwrap_or(0), j.id.clone()))
+        .collect();
+    if finished.len() <= MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort_unstable();
+    let excess = finished.len() - MAX_FINISHED_JOBS;
+    for (_, id) in finished.into_iter().take(excess) {
+        jobs.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn wait(job: &Job) -> JobOut {
+        for _ in 0..200 {
+            let s = job.status();
+            if matches!(
+                s.status,
+                JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled
+            ) {
+                return s;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("job {} did not finish", job.id);
+    }
+
+    #[tokio::test]
+    async fn runs_jobs_and_records_outcomes() {
+        let (reg, t) = (JobRegistry::new(1), Tenant::default());
+        let ok = reg.submit(&t, JobKind::Bootstrap, |p| {
+            p.set(1, 2);
+            Ok(serde_json::json!({"answer": 42}))
+        });
+        let bad = reg.submit(&t, JobKind::Permutation, |_| Err("boom".into()));
+        assert_eq!((ok.id.as_str(), bad.id.as_str()), ("job_1", "job_2"));
+
+        let s = wait(&ok).await;
+        assert_eq!(s.status, JobStatus::Succeeded);
+        assert_eq!(s.progress, 1.0);
+        assert!(s.finished_at.is_some());
+        assert_eq!(ok.result().unwrap()["answer"], 42);
+
+        let s = wait(&bad).await;
+        assert_eq!(s.status, JobStatus::Failed);
+        assert_eq!(s.error.as_deref(), Some("boom"));
+        assert!(bad.result().is_none());
+        assert!(reg.get(&t, "job_2").is_some() && reg.get(&t, "job_3").is_none());
+        let other = Tenant::new("team-b").unwrap();
+        assert!(
+            reg.get(&other, "job_2").is_none(),
+            "other tenants' jobs are hidden"
+        );
+    }
+
+    #[tokio::test]
+    async fn large_results_move_to_artifacts() {
+        let store = ArtifactStore::default();
+        let reg = JobRegistry::new(1).with_artifacts(store.clone(), 16);
+        let t = Tenant::new("team-a").unwrap();
+        let small = reg.submit(&t, JobKind::Bootstrap, |_| Ok(serde_json::json!({"x": 1})));
+        let large = reg.submit(&t, JobKind::Bootstrap, |_| {
+            Ok(serde_json::json!(vec![0.5; 100]))
+        });
+
+        assert!(wait(&small).await.artifact.is_none());
+        assert_eq!(small.result().unwrap()["x"], 1);
+        let id = wait(&large).await.artifact.expect("stored as an artifact");
+        assert!(large.result().is_none());
+        let stored = store.get(&t, &id).await.unwrap().unwrap();
+        assert_eq!(stored.meta.content_type, "application/json");
+        let back: Value = serde_json::from_slice(&stored.bytes).unwrap();
+        assert_eq!(back.as_array().unwrap().len(), 100);
+    }
+
+    #[tokio::test]
+    async fn finished_resolves_on_completion_and_cancellation() {
+        let (reg, t) = (JobRegistry::new(1), Tenant::default());
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let first = reg.submit(&t, JobKind::CorrMatrix, move |_| {
+            rx.recv().ok();
+            Ok(Value::Null)
+        });
+        let second = reg.submit(&t, JobKind::CorrMatrix, |_| Ok(Value::Null));
+        let waiting = tokio::spawn({
+            let first = first.clone();
+            async move { first.finished().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        reg.drain();
+        assert_eq!(second.finished().await.status, JobStatus::Cancelled);
+        tx.send(()).unwrap();
+        assert_eq!(waiting.await.unwrap().status, JobStatus::Succeeded);
+        // already final: resolves at once
+        assert_eq!(first.finished().await.status, JobStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn idempotency_keys_replay_their_job() {
+        let (reg, t) = (JobRegistry::new(1), Tenant::default());
+        let (a, b) = (CacheKey::new("job", &1), CacheKey::new("job", &2));
+        let submit =
+            |key, fp| reg.submit_idempotent(&t, key, fp, JobKind::Bootstrap, |_| Ok(Value::Null));
+
+        let Submission::Created(first) = submit("k1", a) else {
+            panic!("expected a new job");
+        };
+        let Submission::Replayed(again) = submit("k1", a) else {
+            panic!("expected a replay");
+        };
+        assert_eq!(again.id, first.id);
+        assert!(matches!(submit("k1", b), Submission::KeyReused));
+        assert!(matches!(submit("k2", a), Submission::Created(j) if j.id == "job_2"));
+        let other = Tenant::new("team-b").unwrap();
+        let fresh = reg.submit_idempotent(&other, "k1", a, JobKind::Bootstrap, |_| Ok(Value::Null));
+        assert!(matches!(fresh, Submission::Created(j) if j.id == "job_3"));
+    }
+
+    #[tokio::test]
+    async fn jobs_wait_for_a_free_worker() {
+        let (reg, t) = (JobRegistry::new(1), Tenant::default());
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let first = reg.submit(&t, JobKind::CorrMatrix, move |_| {
+            rx.recv().ok();
+            Ok(Value::Null)
+        });
+        let second = reg.submit(&t, JobKind::CorrMatrix, |_| Ok(Value::Null));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(first.status().status, JobStatus::Running);
+        assert_eq!(second.status().status, JobStatus::Queued);
+        tx.send(()).unwrap();
+        assert_eq!(wait(&second).await.status, JobStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn draining_cancels_queued_jobs_only() {
+        let (reg, t) = (JobRegistry::new(1), Tenant::default());
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let first = reg.submit(&t, JobKind::CorrMatrix, move |_| {
+            rx.recv().ok();
+            Ok(Value::Null)
+        });
+        let second = reg.submit(&t, JobKind::CorrMatrix, |_| Ok(Value::Null));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(reg.drain(), 1);
+        assert_eq!(second.status().status, JobStatus::Cancelled);
+        assert!(second.status().finished_at.is_some());
+
+        let idle = tokio::spawn({
+            let reg = reg.clone();
+            async move { reg.idle().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!idle.is_finished(), "the first job is still running");
+        tx.send(()).unwrap();
+        idle.await.unwrap();
+        assert_eq!(first.status().status, JobStatus::Succeeded);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(second.status().status, JobStatus::Cancelled);
+        assert_eq!(reg.drain(), 0);
+    }
+}
@@ -8,35 +8,370 @@
 //!
 //! The library exports modular components organized as follows:
 //!
+//! - [`artifacts`] — Stored large results (memory, disk or S3) served at `/artifacts/{id}`.
+//! - [`audit`] — Audit trail of API requests (file or Postgres), queried at `/admin/audit`.
+//! - [`cache`] — TTL/LRU cache of parsed datasets and intermediate results.
+//! - [`compute`] — Moving large computations off the async runtime onto the blocking pool.
+//! - [`config`] — Runtime settings from env/TOML (body limits, timeouts, CORS, toggles, ingestion allowlist).
+//! - [`datasets`] — In-memory registry of ingested datasets.
+//! - [`embedding`] — Embedding wire formats (JSON arrays or base64 `f32`).
+//! - [`envelope`] — `/api/v2` response envelope (`data` + `meta`).
 //! - [`error`] — Standardized error types for API and computation failures.
+//! - [`etag`] — `ETag`s, `304 Not Modified` and cached responses for deterministic analyses.
+//! - [`fields`] — `?fields=` selection of the metrics an endpoint computes.
+//! - [`frame`] — Typed columnar frames shared by multi-column endpoints.
+//! - `grpc` — `stats.v1.Stats` gRPC service on the HTTP port (feature `grpc`).
+//! - [`ingest`] — Payload parsers (CSV column selection and type inference).
+//! - [`jobs`] — Background execution of long-running analyses.
+//! - [`logging`] — Tracing subscriber setup and the runtime-adjustable log filter.
+//! - [`missing`] — `null` handling for numeric arrays (drop, impute or reject).
+//! - `node` — napi-rs exports of cosine matrices, retrieval metrics and quantiles (feature `napi`).
+//! - [`precision`] — `?precision=` rounding of returned floats and non-finite CSV cells.
+//! - [`request_id`] — `X-Request-Id` on responses, tracing spans and error bodies.
 //! - [`routes`] — HTTP route handlers for each statistical endpoint.
+//! - `shared_cache` — Redis-backed response cache shared by replicas (feature `redis`).
+//! - [`shutdown`] — SIGTERM handling: failing `/ready`, then draining requests and jobs.
 //! - [`state`] — Global [`AppState`] shared across handlers.
 //! - [`stats`] — Core statistical algorithms (mean, variance, correlation, etc.).
+//! - [`tenant`] — Tenant resolution (header or API key) and per-tenant quotas.
+//! - `tls` — rustls HTTPS serving and the HTTP→HTTPS redirect (feature `tls`).
 //! - [`types`] — Shared request/response DTOs and Zod-compatible schemas.
+//! - [`validate`] — Constraint checks on stats requests (`422` with field paths).
+//! - `wasm` — `wasm-bindgen` exports of summary, histogram and correlation (feature `wasm`).
+//! - `webhooks` — Signed callbacks when background jobs finish (feature `webhooks`).
+//! - [`window`] — Paging and LTTB/uniform downsampling of long output arrays.
 //!
 //! The central entry point is [`build_app`], which assembles the Axum router
 //! with all endpoints, middleware, and feature-conditional routes.
+//!
+//! ## Library use
+//!
+//! Everything except [`embedding`], [`error`], [`missing`], [`stats`] and
+//! [`types`] belongs to the HTTP service and sits behind the default `server`
+//! feature. Other Rust services can take just the kernels and DTOs, without
+//! axum, tokio or tower:
+//!
+//! ```toml
+//! stats_rs = { path = "../stats_rs", default-features = false, features = ["rag"] }
+//! ```
+//!
+//! The same core compiles to `wasm32-unknown-unknown`; the `wasm` feature adds
+//! browser bindings on top (see `wasm`), and `napi` a Node.js addon for the
+//! TypeScript backend (see `node`).
 
+#[cfg(feature = "server")]
+pub mod artifacts;
+#[cfg(feature = "server")]
+pub mod audit;
+#[cfg(feature = "server")]
+pub mod cache;
+#[cfg(feature = "server")]
+pub mod compute;
+#[cfg(feature = "server")]
+pub mod config;
+#[cfg(feature = "server")]
+pub mod datasets;
+pub mod embedding;
+#[cfg(feature = "server")]
+pub mod envelope;
 pub mod error;
+#[cfg(feature = "server")]
+pub mod etag;
+#[cfg(feature = "server")]
+pub mod fields;
+#[cfg(feature = "server")]
+pub mod frame;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "server")]
+pub mod ingest;
+#[cfg(feature = "server")]
+pub mod jobs;
+#[cfg(feature = "server")]
+pub mod logging;
+pub mod missing;
+#[cfg(feature = "napi")]
+pub mod node;
+#[cfg(feature = "server")]
+pub mod precision;
+#[cfg(feature = "server")]
+pub mod request_id;
+#[cfg(feature = "server")]
 pub mod routes;
+#[cfg(feature = "redis")]
+pub mod shared_cache;
+#[cfg(feature = "server")]
+pub mod shutdown;
+#[cfg(feature = "server")]
 pub mod state;
 pub mod stats;
+#[cfg(feature = "server")]
+pub mod tenant;
+#[cfg(feature = "tls")]
+pub mod tls;
 pub mod types;
+#[cfg(feature = "server")]
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
+#[cfg(feature = "server")]
+pub mod window;
 
+#[cfg(feature = "server")]
 use axum::extract::DefaultBodyLimit;
-use axum::{
-    Router, http,
-    routing::{get, post},
-};
+#[cfg(any(feature = "docs", feature = "metrics"))]
+use axum::routing::get;
+#[cfg(feature = "server")]
+use axum::{Router, http};
+#[cfg(feature = "server")]
 use state::AppState;
+#[cfg(feature = "server")]
 use std::{sync::Arc, time::Duration};
+#[cfg(feature = "server")]
 use tower_http::{
     compression::CompressionLayer,
-    cors::{Any, CorsLayer},
+    cors::{AllowOrigin, Any, CorsLayer},
+    decompression::RequestDecompressionLayer,
+    limit::RequestBodyLimitLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
     timeout::TimeoutLayer,
     trace::TraceLayer,
 };
+#[cfg(feature = "server")]
+use utoipa::OpenApi;
+#[cfg(feature = "server")]
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+#[cfg(feature = "server")]
+/// Largest request body accepted on the wire (after any `Content-Encoding`).
+pub const MAX_BODY_BYTES: usize = 25 * 1024 * 1024;
+
+#[cfg(feature = "server")]
+/// Largest body handlers will buffer once decompressed (10× the wire limit).
+pub const MAX_DECOMPRESSED_BODY_BYTES: usize = 10 * MAX_BODY_BYTES;
+
+#[cfg(feature = "server")]
+/// Wire limit for routes that parse their body as a stream (`/describe-csv`).
+pub const MAX_STREAM_BODY_BYTES: usize = 1024 * 1024 * 1024;
+
+#[cfg(feature = "server")]
+/// The routes served under each API version, with their per-group
+/// timeouts and body limits.
+fn api_routes(state: &Arc<AppState>) -> OpenApiRouter {
+    let cfg = &state.config;
+
+    let timeout =
+        |d: Duration| TimeoutLayer::with_status_code(http::StatusCode::REQUEST_TIMEOUT, d);
+
+    // Routes are mounted with `routes!` so their `#[utoipa::path]` docs land
+    // in `/openapi.json`; the document lists exactly what is served.
+    // Quick routes: lookups and small summaries
+    let quick = OpenApiRouter::new()
+        // Health and readiness endpoints
+        .routes(routes!(routes::health::health))
+        .routes(routes!(routes::health::ready))
+        // "Describe" endpoint: summarize a numeric array
+        .routes(routes!(routes::describe::describe))
+        .routes(routes!(routes::datasets::list_datasets))
+        .routes(routes!(
+            routes::datasets::get_dataset,
+            routes::datasets::delete_dataset
+        ))
+        // Job status and results; the work itself runs outside any timeout
+        .routes(routes!(routes::jobs::get_job))
+        .routes(routes!(routes::jobs::get_job_result))
+        // JSON schema reflection for input/output
+        .routes(routes!(routes::schemas::schema_describe_input))
+        .routes(routes!(routes::schemas::schema_describe_output))
+        .with_state(state.clone());
+
+    // Feature: live running statistics; the socket outlives the upgrade
+    // request, so no timeout applies to it
+    #[cfg(feature = "ws")]
+    let quick = if cfg.features.ws {
+        quick.merge(
+            OpenApiRouter::new()
+                .routes(routes!(routes::ws::ws_stats))
+                .with_state(state.clone()),
+        )
+    } else {
+        quick
+    };
+
+    // Standard routes: linear or n·log n work on the request body
+    let standard = OpenApiRouter::new()
+        .routes(routes!(routes::schema_infer::schema_infer))
+        // Core statistics endpoints
+        .routes(routes!(routes::stats_summary::stats_summary))
+        .routes(routes!(routes::stats_distribution::stats_distribution))
+        .routes(routes!(routes::stats_pairwise::stats_pairwise))
+        // Extended statistics
+        .routes(routes!(routes::stats_ecdf::stats_ecdf))
+        .routes(routes!(routes::stats_qq::stats_qq_normal))
+        .routes(routes!(routes::stats_outliers::stats_outliers))
+        .routes(routes!(routes::stats_normalize::stats_normalize))
+        .routes(routes!(routes::stats_normalize::stats_normalize_apply))
+        .routes(routes!(routes::stats_normalize::stats_normalize_inverse))
+        .routes(routes!(routes::stats_binrule::stats_binrule))
+        .routes(routes!(routes::stats_entropy::stats_entropy))
+        .routes(routes!(routes::stats_contingency::stats_contingency))
+        .routes(routes!(routes::stats_anomaly::stats_anomaly_score))
+        .routes(routes!(routes::stats_compare::stats_compare))
+        .routes(routes!(routes::stats_drift::stats_drift))
+        .routes(routes!(routes::stats_generate::stats_generate))
+        .routes(routes!(routes::stats_simulate::stats_simulate))
+        .routes(routes!(routes::stats_tests::stats_tests))
+        .routes(routes!(routes::stats_tests::stats_tests_recommend))
+        .routes(routes!(routes::stats_ttest::stats_ttest))
+        .routes(routes!(routes::stats_seasonality::stats_seasonality))
+        // Cached derived artifacts of registered datasets
+        .routes(routes!(routes::datasets::column_distribution))
+        // Stored large results, possibly read back from disk or S3
+        .routes(routes!(routes::artifacts::get_artifact))
+        .with_state(state.clone());
+
+    // Heavy routes: whole-table parses, quadratic work and large uploads
+    let heavy = OpenApiRouter::new()
+        .routes(routes!(routes::ingest::ingest_ndjson))
+        .routes(routes!(routes::profile::profile))
+        .routes(routes!(routes::report::report))
+        .routes(routes!(routes::stats_corr_matrix::stats_corr_matrix))
+        .routes(routes!(routes::stats_corr_matrix::stats_corr_matrix_csv))
+        // Stored, incrementally updated correlation matrices of datasets
+        .routes(routes!(
+            routes::datasets::get_corr_matrix,
+            routes::datasets::put_corr_matrix
+        ))
+        .routes(routes!(routes::datasets::append_corr_series))
+        .routes(routes!(routes::datasets::append_corr_rows))
+        // Vega-Lite specs over the stats computations
+        .routes(routes!(routes::plots::plot_spec))
+        .routes(routes!(routes::stats_resample::stats_resample))
+        // Background jobs: inputs are validated (and NaNs resolved) here
+        .routes(routes!(routes::jobs::submit_job))
+        .with_state(state.clone());
+
+    // Embedding / vector-set diagnostics
+    let heavy = if cfg.features.vector {
+        heavy.merge(
+            OpenApiRouter::new()
+                .routes(routes!(routes::stats_vector::stats_knn_distances))
+                .routes(routes!(routes::stats_vector::stats_intrinsic_dim))
+                .routes(routes!(routes::stats_vector::stats_near_duplicates))
+                .routes(routes!(routes::stats_vector::stats_similarity))
+                .with_state(state.clone()),
+        )
+    } else {
+        heavy
+    };
+
+    // Feature: download datasets by URL
+    #[cfg(feature = "fetch")]
+    let heavy = if cfg.features.url_ingest {
+        heavy.merge(
+            OpenApiRouter::new()
+                .routes(routes!(routes::ingest::ingest_url))
+                .with_state(state.clone()),
+        )
+    } else {
+        heavy
+    };
+
+    // Feature: spreadsheet uploads
+    #[cfg(feature = "xlsx")]
+    let heavy = if cfg.features.xlsx {
+        heavy.merge(
+            OpenApiRouter::new()
+                .routes(routes!(routes::xlsx::describe_xlsx))
+                .routes(routes!(routes::xlsx::stats_summary_xlsx))
+                .with_state(state.clone()),
+        )
+    } else {
+        heavy
+    };
+
+    // Feature: retrieval-augmented metrics (RAG)
+    #[cfg(feature = "rag")]
+    let standard = if cfg.features.rag {
+        standard
+            .routes(routes!(routes::stats_rag::stats_rag_metrics))
+            .routes(routes!(routes::stats_rag::stats_rag_mmr))
+            .routes(routes!(routes::stats_rag::stats_rag_text_metrics))
+            .routes(routes!(routes::stats_rag::stats_rag_groundedness))
+    } else {
+        standard
+    };
+
+    // Deterministic analyses: `ETag` / `If-None-Match` and cached responses,
+    // inside each group's timeout so that buffering a stalled body times out
+    let etag = axum::middleware::from_fn_with_state(state.clone(), etag::middleware);
+    let v1 = quick
+        .layer(etag.clone())
+        .layer(timeout(cfg.quick_timeout()))
+        .merge(
+            standard
+                .layer(etag.clone())
+                .layer(timeout(cfg.request_timeout())),
+        )
+        .merge(heavy.layer(etag).layer(timeout(cfg.heavy_timeout())));
+
+    // `?precision=` rounding, outside the caches so they keep full digits
+    let precision = axum::middleware::from_fn_with_state(state.clone(), precision::middleware);
+    let v1 = v1.layer(precision.clone());
 
+    // Feature: response cache shared across replicas
+    #[cfg(feature = "redis")]
+    let v1 = match state.shared_cache.clone() {
+        Some(c) => v1.layer(axum::middleware::from_fn_with_state(
+            c,
+            shared_cache::middleware,
+        )),
+        None => v1,
+    };
+
+    // Audit trail: outside the caches, so answers from them are recorded too
+    let audit = state.audit.clone().map(|log| {
+        axum::middleware::from_fn_with_state(
+            (log, cfg.max_decompressed_body_bytes),
+            audit::middleware,
+        )
+    });
+    let v1 = match audit.clone() {
+        Some(layer) => v1.layer(layer),
+        None => v1,
+    };
+
+    // Buffered routes: extractors read the whole (decompressed) body
+    let v1 = v1
+        .layer(DefaultBodyLimit::max(cfg.max_decompressed_body_bytes)) // large CSVs once inflated
+        .layer(RequestDecompressionLayer::new())
+        .layer(RequestBodyLimitLayer::new(cfg.max_body_bytes));
+
+    // Streaming routes: parsed incrementally, so only the wire size is capped
+    let streaming = OpenApiRouter::new()
+        .routes(routes!(routes::describe::describe_csv))
+        .with_state(state.clone())
+        .layer(precision)
+        .layer(timeout(cfg.heavy_timeout()));
+    let streaming = match audit {
+        Some(layer) => streaming.layer(layer),
+        None => streaming,
+    };
+    let streaming = streaming
+        .layer(RequestDecompressionLayer::new())
+        .layer(RequestBodyLimitLayer::new(cfg.max_stream_body_bytes));
+
+    // Outermost: buffered and streaming routes alike get their tenant
+    v1.merge(streaming)
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            tenant::middleware,
+        ))
+}
+
+#[cfg(feature = "server")]
 /// Builds and configures the top-level Axum [`Router`] for the `stats_rs` microservice.
 ///
 /// This function wires up all routes, middleware layers, and optional feature-based
@@ -45,8 +380,9 @@ use tower_http::{
 ///
 /// # Parameters
 ///
-/// - `state`: Shared [`AppState`] reference wrapped in [`Arc`].
-///   Contains service-wide configuration, database handles, or cached models.
+/// - `state`: Shared [`AppState`] reference wrapped in [`Arc`]. Its
+///   [`ServiceConfig`](config::ServiceConfig) supplies the body limits,
+///   timeouts, CORS origins and route toggles used below.
 ///
 /// # Routes
 ///
@@ -56,30 +392,105 @@ use tower_http::{
 /// |-----------|------|---------|-------------|
 /// | Health    | `/health`, `/ready` | `GET` | Liveness and readiness checks |
 /// | Describe  | `/describe`, `/describe-csv` | `POST` | Statistical summaries for JSON or CSV input |
+/// | Ingest    | `/ingest/ndjson` | `POST` | Streamed NDJSON records summarized per field |
+/// | Profile   | `/profile` | `POST` | Per-column summaries, histograms, top values, correlations and warnings for a CSV |
+/// | Report    | `/report` | `POST` | Self-contained HTML or Markdown analysis report for a CSV or dataset |
+/// | Datasets  | `/datasets`, `/datasets/{id}` | `GET`, `DELETE` | Registered dataset metadata |
+/// | Datasets  | `/datasets/{id}/columns/{column}/distribution` | `GET` | Cached distribution of one column |
+/// | Datasets  | `/datasets/{id}/corr-matrix` | `GET`, `PUT` | Stored correlation matrix of the numeric columns |
+/// | Datasets  | `/datasets/{id}/corr-matrix/series`, `/datasets/{id}/corr-matrix/rows` | `POST` | Grow the stored matrix by a series or observations |
+/// | Jobs      | `/jobs`, `/jobs/{id}`, `/jobs/{id}/result` | `POST`, `GET` | Bootstrap, permutation and large correlation jobs run in the background |
+/// | Artifacts | `/artifacts/{id}` | `GET` | Stored large results (job outputs, reports saved with `?store=true`) |
 /// | Schemas   | `/schema/*` | `GET` | Returns JSON schemas for input/output payloads |
+/// | Schemas   | `/schema/infer` | `POST` | Column types, null rates, examples and ranges from a CSV sample |
 /// | Core Stats | `/stats/summary`, `/stats/distribution`, `/stats/pairwise` | `POST` | Core analytic endpoints |
-/// | Extended Stats | `/stats/ecdf`, `/stats/qq-normal`, `/stats/corr-matrix`, `/stats/outliers`, `/stats/normalize`, `/stats/binrule` | `POST` | Advanced statistical and normalization routines |
+/// | Extended Stats | `/stats/ecdf`, `/stats/qq-normal`, `/stats/corr-matrix`, `/stats/corr-matrix-csv`, `/stats/outliers`, `/stats/normalize`, `/stats/normalize/apply`, `/stats/normalize/inverse`, `/stats/binrule`, `/stats/entropy`, `/stats/contingency`, `/stats/anomaly/score`, `/stats/compare`, `/stats/drift`, `/stats/generate`, `/stats/simulate`, `/stats/tests/recommend`, `/stats/ttest` | `POST` | Advanced statistical and normalization routines |
+/// | Extended Stats | `/stats/tests` | `GET` | Catalog of hypothesis tests with their assumptions and inputs |
+/// | Plots | `/plots/spec` | `POST` | Vega-Lite histogram, ECDF, box plot, QQ or correlation heatmap with embedded data |
+/// | Time series | `/stats/resample`, `/stats/seasonality` | `POST` | CSV columns aggregated into hour/day/week/month buckets of a datetime column; dominant period and per-season summaries |
+/// | Vectors | `/stats/vector/knn-distances`, `/stats/vector/intrinsic-dim`, `/stats/vector/near-duplicates`, `/stats/vector/similarity` | `POST` | Embedding-set diagnostics |
+///
+/// With an admin token configured (`STATS_ADMIN_TOKEN`), [`routes::admin`]
+/// adds bearer-authenticated `/admin/*` routes to flush the cache, list and
+/// evict datasets, read or change the log filter, view the (redacted) config,
+/// drain the job queue and query the audit trail.
+///
+/// The same routes are served under `/api/v2`, where every JSON or plain-text
+/// response is wrapped as `{ data, meta: { n_used, n_dropped, elapsed_ms,
+/// warnings } }` and every failure carries an `error` in the
+/// [`ErrorResponse`](types::ErrorResponse) shape (see [`envelope`]); `/api/v1`
+/// responses are unchanged.
 ///
-/// Feature-based optional routes:
+/// Feature-based optional routes (each can also be switched off at runtime
+/// with [`FeatureToggles`](config::FeatureToggles), as can the vector routes):
 ///
-/// - `rag` → `/stats/rag/metrics` for retrieval-augmented generation metrics
+/// - `rag` → `/stats/rag/metrics` for retrieval-augmented generation metrics,
+///   `/stats/rag/mmr` for MMR re-ranking of candidate embeddings,
+///   `/stats/rag/text-metrics` for ROUGE/BLEU/token-F1 answer overlap,
+///   `/stats/rag/groundedness` for embedding-based answer support
+/// - `fetch` → `/ingest/url` to download and register allowlisted CSV/Parquet files
+/// - `s3` → `s3://` URIs for `/ingest/url` (implies `fetch`)
+/// - `parquet` → Parquet support for dataset ingestion
+/// - `polars` → conversion between [`frame::Frame`] and polars `DataFrame`s
+/// - `redis` → analysis responses cached in Redis across replicas, with an
+///   `x-cache: hit|miss|bypass` header (when `AppState::shared_cache` is set)
+/// - `xlsx` (default) → `/describe-xlsx` and `/stats/summary-xlsx` for spreadsheet uploads
 /// - `docs` → `/docs` for Swagger/ReDoc UI
 /// - `metrics` → `/metrics` for Prometheus scraping
+/// - `ws` → `/ws/stats`, a WebSocket streaming running statistics of pushed chunks
+/// - `tls` → HTTPS served by `main.rs` from `STATS_TLS_CERT`/`STATS_TLS_KEY`,
+///   with an optional HTTP→HTTPS redirect port (see `tls`)
+/// - `grpc` → the `stats.v1.Stats` gRPC service (summary, distribution,
+///   pairwise, corr-matrix, normalize, RAG metrics) on the same port, for
+///   HTTP/2 clients, under the same tenant rules (see [`grpc`])
+///
+/// Every `/api/v1` route is mounted through `utoipa_axum`, so `/openapi.json`
+/// is generated from the handlers' `#[utoipa::path]` annotations and lists
+/// only the routes this instance actually serves.
 ///
 /// # Middleware
 ///
 /// The following layers are attached to the root router:
 ///
-/// - [`TraceLayer`] for structured HTTP logging
+/// - [`SetRequestIdLayer`] / [`PropagateRequestIdLayer`]: every request gets
+///   an `X-Request-Id` (the client's, or a new UUID) echoed on the response
+/// - [`TraceLayer`] for structured HTTP logging, with the request id on the span
+/// - [`request_id::middleware`] adding `request_id` to JSON error bodies
+/// - [`tenant::middleware`] on the API routes, assigning each request its
+///   tenant (`401` for a missing or unknown API key when keys are configured)
+/// - [`audit::middleware`] on the API routes when an audit store is
+///   configured, recording each non-`GET` request
 /// - [`CompressionLayer`] for gzip/br encoding
-/// - [`CorsLayer`] permitting any origin and standard methods
-/// - [`DefaultBodyLimit`] increased to 25 MB (large CSVs)
-/// - [`TimeoutLayer`] limiting request duration to 30 s
+/// - [`etag::middleware`] on the buffered analysis routes: strong `ETag`s
+///   derived from the request, `304` for a matching `If-None-Match`, and
+///   responses kept in the result cache
+/// - [`CorsLayer`] permitting the configured origins (none unless listed; `*` for any)
+///   and the methods the routes use (`GET`, `POST`, `PUT`, `DELETE`, plus `OPTIONS`)
+/// - [`RequestBodyLimitLayer`] capping the wire body (default [`MAX_BODY_BYTES`], 25 MB),
+///   or the streaming limit (default [`MAX_STREAM_BODY_BYTES`], 1 GiB) on streaming routes
+/// - [`RequestDecompressionLayer`] accepting `gzip`, `deflate`, `br` and `zstd` uploads
+/// - [`DefaultBodyLimit`] capping decompressed bodies (default
+///   [`MAX_DECOMPRESSED_BODY_BYTES`]) on buffered routes
+/// - [`TimeoutLayer`] per route group, so heavy endpoints get more time
+///   without raising everyone's:
+///   - *quick* (default 5 s): health, schemas, dataset and job lookups,
+///     `/describe`, `/openapi.json`, `/docs`, `/metrics`, `/admin/*`
+///   - *standard* (default 30 s): `/schema/infer`, the remaining `/stats/*`
+///     routes (including `rag`), `/datasets/{id}/columns/{column}/distribution`
+///     and `/artifacts/{id}`
+///   - *heavy* (default 300 s): `/profile`, `/report`, `/plots/spec`,
+///     `/ingest/ndjson`, `/ingest/url`, `/describe-csv`, `/describe-xlsx`,
+///     `/stats/summary-xlsx`, `/stats/corr-matrix`, `/stats/corr-matrix-csv`,
+///     `/datasets/{id}/corr-matrix` (with its `/series` and `/rows` appends),
+///     `/stats/resample`, `/stats/vector/*` and `POST /jobs`
 ///
 /// # Example
 ///
 /// ```rust,ignore
-/// let app = build_app(Arc::new(AppState::default()));
+/// let app = build_app(Arc::new(AppState {
+///     config: ServiceConfig::load()?,
+///     ..Default::default()
+/// }));
 /// tracing::info!("Server ready on :9000");
 /// axum::serve(listener, app).await?;
 /// ```
@@ -88,65 +499,81 @@ use tower_http::{
 ///
 /// An Axum [`Router`] instance ready to be served by a Tokio runtime.
 pub fn build_app(state: Arc<AppState>) -> Router {
-    // --- v1 API ---
-    let v1 = Router::new()
-        // Health and readiness endpoints
-        .route("/health", get(routes::health))
-        .route("/ready", get(routes::ready))
-        // "Describe" endpoints: summarize numeric arrays or CSV files
-        .route("/describe", post(routes::describe))
-        .route("/describe-csv", post(routes::describe_csv))
-        // JSON schema reflection for input/output
-        .route("/schema/describe-input", get(routes::schema_describe_input))
-        .route(
-            "/schema/describe-output",
-            get(routes::schema_describe_output),
-        )
-        // Core statistics endpoints
-        .route("/stats/summary", post(routes::stats_summary))
-        .route("/stats/distribution", post(routes::stats_distribution))
-        .route("/stats/pairwise", post(routes::stats_pairwise))
-        // Extended statistics
-        .route("/stats/ecdf", post(routes::stats_ecdf))
-        .route("/stats/qq-normal", post(routes::stats_qq_normal))
-        .route("/stats/corr-matrix", post(routes::stats_corr_matrix))
-        .route("/stats/outliers", post(routes::stats_outliers))
-        .route("/stats/normalize", post(routes::stats_normalize))
-        .route("/stats/binrule", post(routes::stats_binrule))
-        .with_state(state.clone());
+    let cfg = &state.config;
 
-    // Feature: retrieval-augmented metrics (RAG)
-    #[cfg(feature = "rag")]
-    let v1 = v1.route("/stats/rag/metrics", post(routes::stats_rag_metrics));
+    let timeout =
+        |d: Duration| TimeoutLayer::with_status_code(http::StatusCode::REQUEST_TIMEOUT, d);
+
+    let (v1, doc) = OpenApiRouter::with_openapi(routes::ApiDoc::openapi())
+        .nest("/api/v1", api_routes(&state))
+        .split_for_parts();
+
+    // v2: the same routes with every response wrapped in an `Envelope`
+    let (v2, _) = api_routes(&state).split_for_parts();
+    let v2 = v2.layer(axum::middleware::from_fn(envelope::middleware));
+
+    let origins = if cfg.cors_any_origin() {
+        AllowOrigin::any()
+    } else {
+        let allowed = state.clone();
+        AllowOrigin::predicate(move |origin, _| {
+            origin.to_str().is_ok_and(|o| allowed.config.cors_allows(o))
+        })
+    };
 
     // --- root router ---
-    let root = Router::new()
-        .nest("/api/v1", v1)
-        // Always expose raw OpenAPI JSON (generated by backend or contracts)
-        .route("/openapi.json", get(routes::openapi))
-        // Middleware layers
-        .layer(TraceLayer::new_for_http())
-        .layer(CompressionLayer::new())
-        .layer(
-            CorsLayer::new()
-                .allow_methods([http::Method::GET, http::Method::POST, http::Method::OPTIONS])
-                .allow_origin(Any)
-                .allow_headers(Any),
-        )
-        .layer(DefaultBodyLimit::max(25 * 1024 * 1024)) // allow large CSV uploads
-        .layer(TimeoutLayer::new(Duration::from_secs(30)));
+    // Always expose the OpenAPI document generated above
+    let root = Router::new().route("/openapi.json", routes::openapi(doc));
 
     // Feature: documentation UI
     #[cfg(feature = "docs")]
-    {
-        root = root.route("/docs", get(routes::docs_ui));
-    }
+    let root = root.route("/docs", get(routes::docs_ui));
 
     // Feature: Prometheus metrics
     #[cfg(feature = "metrics")]
-    {
-        root = root.route("/metrics", get(routes::prom_metrics));
-    }
+    let root = root.route("/metrics", get(routes::prom_metrics));
 
-    root
+    let app = root
+        .layer(timeout(cfg.quick_timeout()))
+        .merge(v1)
+        .nest("/api/v2", v2);
+
+    // Operator routes, only with a token to guard them
+    let app = if cfg.admin.token.is_some() {
+        app.merge(routes::admin::router(state.clone()).layer(timeout(cfg.quick_timeout())))
+    } else {
+        app
+    };
+
+    // Feature: gRPC; its failures are statuses, so no HTTP timeout wraps it
+    #[cfg(feature = "grpc")]
+    let app = if cfg.features.grpc {
+        app.merge(grpc::router(state.clone()))
+    } else {
+        app
+    };
+
+    app
+        // Middleware layers
+        .layer(axum::middleware::from_fn(request_id::middleware))
+        .layer(TraceLayer::new_for_http().make_span_with(request_id::span))
+        .layer(CompressionLayer::new())
+        .layer(
+            CorsLayer::new()
+                .allow_methods([
+                    http::Method::GET,
+                    http::Method::POST,
+                    http::Method::PUT,
+                    http::Method::DELETE,
+                    http::Method::OPTIONS,
+                ])
+                .allow_origin(origins)
+                .allow_headers(Any)
+                .expose_headers([request_id::X_REQUEST_ID]),
+        )
+        .layer(PropagateRequestIdLayer::new(request_id::X_REQUEST_ID))
+        .layer(SetRequestIdLayer::new(
+            request_id::X_REQUEST_ID,
+            MakeRequestUuid,
+        ))
 }
@@ -0,0 +1,96 @@
+//! # Log filter
+//!
+//! `main.rs` installs the tracing subscriber through [`init`], which keeps a
+//! reload handle on its `EnvFilter`. The returned [`LogControl`] lives in
+//! [`AppState`](crate::state::AppState), so `PUT /admin/log-level` can turn
+//! verbosity up or down without restarting the pod.
+
+use crate::error::ServiceError;
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+use tracing_subscriber::{EnvFilter, fmt as layer, prelude::*, reload};
+
+/// Filter used when `RUST_LOG` is unset or unparseable.
+pub const DEFAULT_FILTER: &str = "info,axum=info,tower_http=info,hyper=warn";
+
+type Reload = dyn Fn(EnvFilter) -> Result<(), String> + Send + Sync;
+
+/// Handle on the installed log filter.
+#[derive(Clone)]
+pub struct LogControl {
+    spec: Arc<Mutex<String>>,
+    reload: Arc<Reload>,
+}
+
+impl fmt::Debug for LogControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogControl")
+            .field("filter", &self.filter())
+            .finish()
+    }
+}
+
+impl LogControl {
+    /// A control starting at `spec` that hands new filters to `reload`.
+    pub fn new(
+        spec: &str,
+        reload: impl Fn(EnvFilter) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            spec: Arc::new(Mutex::new(spec.to_string())),
+            reload: Arc::new(reload),
+        }
+    }
+
+    /// The active filter directives (`RUST_LOG` syntax).
+    pub fn filter(&self) -> String {
+        self.spec.lock().unwrap().clone()
+    }
+
+    /// Replace the filter, e.g. with `"debug"` or `"info,stats_rs=trace"`.
+    pub fn set(&self, spec: &str) -> Result<(), ServiceError> {
+        let filter = EnvFilter::try_new(spec)
+            .map_err(|e| ServiceError::InvalidInput(format!("log filter '{spec}': {e}")))?;
+        let mut current = self.spec.lock().unwrap();
+        (self.reload)(filter).map_err(ServiceError::Internal)?;
+        *current = spec.to_string();
+        Ok(())
+    }
+}
+
+/// Install the global subscriber (compact output, no targets) filtered by
+/// `RUST_LOG`, or [`DEFAULT_FILTER`].
+pub fn init() -> LogControl {
+    let spec = std::env::var("RUST_LOG")
+        .ok()
+        .filter(|s| EnvFilter::try_new(s).is_ok())
+        .unwrap_or_else(|| DEFAULT_FILTER.to_string());
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(&spec));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(layer::layer().with_target(false).compact())
+        .init();
+    LogControl::new(&spec, move |f| handle.reload(f).map_err(|e| e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_validates_before_reloading() {
+        let seen = Arc::new(Mutex::new(0));
+        let count = seen.clone();
+        let log = LogControl::new("info", move |_| {
+            *count.lock().unwrap() += 1;
+            Ok(())
+        });
+        log.set("debug,hyper=warn").unwrap();
+        assert_eq!(log.filter(), "debug,hyper=warn");
+        assert!(log.set("stats_rs=loud").is_err());
+        assert_eq!(log.filter(), "debug,hyper=warn");
+        assert_eq!(*seen.lock().unwrap(), 1);
+    }
+}
@@ -6,10 +6,10 @@
 //!
 //! ## Responsibilities
 //!
-//! - Initialize structured tracing via [`tracing_subscriber`]
+//! - Initialize structured tracing via [`logging::init`]
 //! - Load environment configuration (optionally from `.env`)
 //! - Build the Axum router with [`build_app`] and shared [`AppState`]
-//! - Report active compile-time features (`rag`, `docs`, `metrics`)
+//! - Report active compile-time features (`rag`, `docs`, `metrics`, `xlsx`, ...)
 //! - Serve incoming HTTP traffic on the configured address
 //! - Handle termination gracefully (SIGTERM, Ctrl+C)
 //!
@@ -19,13 +19,19 @@
 //! |-----------|----------|-------------|
 //! | `HOST` | `0.0.0.0` | Network interface to bind |
 //! | `PORT` | `9000` | TCP port for the HTTP server |
-//! | `RUST_LOG` | `info,axum=info,tower_http=info,hyper=warn` | Logging filter spec |
+//! | `LISTEN_UDS` | *(none)* | Unix socket path to serve on instead of `HOST`/`PORT` (Unix only) |
+//! | `LISTEN_UDS_MODE` | *(umask)* | Octal permissions for the socket, e.g. `660` so an nginx group can connect |
+//! | `RUST_LOG` | `info,axum=info,tower_http=info,hyper=warn` | Logging filter spec (changeable at runtime via `PUT /admin/log-level`) |
+//! | `STATS_CONFIG` | *(none)* | TOML file with service settings; see [`stats_rs::config`] for it and the `STATS_*` overrides |
+//! | `STATS_TLS_CERT`, `STATS_TLS_KEY` | *(none)* | PEM certificate chain and key: serve HTTPS on `PORT` (feature `tls`) |
+//! | `STATS_TLS_REDIRECT_PORT` | *(none)* | Also listen for plain HTTP here, redirecting to HTTPS |
 //!
 //! Example `.env` file:
 //! ```env
 //! HOST=0.0.0.0
 //! PORT=9000
 //! RUST_LOG=stats_rs=debug,axum=info
+//! STATS_CORS_ORIGINS=http://localhost:5173
 //! ```
 //!
 //! ## Running
@@ -34,17 +40,45 @@
 //! cargo run --release
 //! ```
 //!
+//! ## Unix socket
+//!
+//! With `LISTEN_UDS=/run/stats/stats.sock` the server accepts plain HTTP on
+//! that socket only, for same-host deployments behind nginx
+//! (`proxy_pass http://unix:/run/stats/stats.sock;`). A stale socket file
+//! left by a previous run is replaced, and the file is removed on shutdown.
+//! TLS is the proxy's job in this setup, so `STATS_TLS_CERT` is rejected.
+//!
+//! ## HTTPS
+//!
+//! Built with `--features tls`, the service terminates TLS itself when
+//! `STATS_TLS_CERT` and `STATS_TLS_KEY` are set (HTTP/2 and HTTP/1.1 via
+//! ALPN), so simple deployments need no sidecar. Without the feature those
+//! settings are a startup error rather than a silent plain-HTTP listener.
+//!
 //! ## Graceful Shutdown
 //!
 //! The server listens for `SIGTERM` and `Ctrl+C` (Unix or Windows).
-//! Upon receiving either signal, it stops accepting new requests,
-//! waits for in-flight requests to complete, and then exits cleanly.
+//! Upon receiving either signal, `/ready` starts answering `503` and queued
+//! jobs are cancelled. After `STATS_SHUTDOWN_READY_GRACE_SECS` (default 0)
+//! it stops accepting new requests, then waits for in-flight requests and
+//! running jobs for up to `STATS_SHUTDOWN_DRAIN_SECS` (default 30) before
+//! exiting (see [`stats_rs::shutdown`]).
 
-use stats_rs::{build_app, state::AppState};
-use std::{env, net::SocketAddr, sync::Arc};
+use axum::Router;
+use stats_rs::{
+    artifacts::ArtifactStore,
+    build_app,
+    cache::ResultCache,
+    compute::ComputePool,
+    config::{ServiceConfig, TlsConfig},
+    jobs::JobRegistry,
+    logging,
+    shutdown::Shutdown,
+    state::AppState,
+};
+use std::{env, future::IntoFuture, net::SocketAddr, sync::Arc};
 use tokio::net::TcpListener;
 use tracing::{info, warn};
-use tracing_subscriber::{EnvFilter, fmt};
 
 /// Application entrypoint for the `stats_rs` microservice.
 ///
@@ -79,15 +113,9 @@ use tracing_subscriber::{EnvFilter, fmt};
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // --- Logging Setup -------------------------------------------------------
-    // Default filter: info-level logs for core and framework crates.
-    let filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info,axum=info,tower_http=info,hyper=warn"));
-
-    fmt()
-        .with_env_filter(filter)
-        .with_target(false)
-        .compact()
-        .init();
+    // Default filter: info-level logs for core and framework crates; the
+    // handle lets `/admin/log-level` change it later.
+    let log = logging::init();
 
     // --- Environment Setup ---------------------------------------------------
     // Load `.env` file if available (no error if missing)
@@ -100,14 +128,57 @@ async fn main() -> anyhow::Result<()> {
         .and_then(|s| s.parse().ok())
         .unwrap_or(9000);
     let addr: SocketAddr = format!("{host}:{port}").parse()?;
+    let uds = env::var("LISTEN_UDS").ok().filter(|p| !p.trim().is_empty());
 
     // --- Application State + Router ------------------------------------------
-    let state = Arc::new(AppState);
-    let app = build_app(state);
+    let config = ServiceConfig::load()?;
+    if config.cors_any_origin() {
+        warn!("STATS_CORS_ORIGINS=*: any website may call this service from a browser");
+    }
+    let tls = config.tls.clone();
+    if uds.is_some() && tls.enabled() {
+        anyhow::bail!(
+            "LISTEN_UDS serves plain HTTP; unset STATS_TLS_CERT or terminate TLS in the proxy"
+        );
+    }
+    #[cfg(feature = "redis")]
+    let shared_cache = stats_rs::shared_cache::SharedCache::connect(
+        &config.redis,
+        config.max_decompressed_body_bytes,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        warn!("redis unavailable, running without the shared cache: {e}");
+        None
+    });
+    // Auditing is a compliance requirement where configured: no store, no start
+    let audit = stats_rs::audit::AuditLog::open(&config.audit)
+        .await
+        .map_err(|e| anyhow::anyhow!("audit store: {e}"))?
+        .map(Arc::new);
+    let artifacts = ArtifactStore::open(&config.artifacts, &config.s3)
+        .await
+        .map_err(|e| anyhow::anyhow!("artifact store: {e}"))?;
+    let state = Arc::new(AppState {
+        jobs: JobRegistry::default()
+            .with_artifacts(artifacts.clone(), config.artifacts.inline_max_bytes),
+        artifacts,
+        cache: ResultCache::new(&config.cache),
+        compute: ComputePool::new(&config.compute),
+        audit,
+        #[cfg(feature = "redis")]
+        shared_cache,
+        config,
+        log: Some(log),
+        ..Default::default()
+    });
+    let app = build_app(state.clone());
+    let shutdown = Shutdown::on(shutdown_signal(), state);
 
     // --- Feature Flag Detection ----------------------------------------------
     // Uses compile-time flags (Cargo features) to log enabled modules.
-    let features = String::new();
+    #[allow(unused_mut)]
+    let mut features = String::new();
     #[cfg(feature = "rag")]
     {
         features.push_str("rag, ");
@@ -120,6 +191,42 @@ async fn main() -> anyhow::Result<()> {
     {
         features.push_str("metrics, ");
     }
+    #[cfg(feature = "xlsx")]
+    {
+        features.push_str("xlsx, ");
+    }
+    #[cfg(feature = "parquet")]
+    {
+        features.push_str("parquet, ");
+    }
+    #[cfg(feature = "fetch")]
+    {
+        features.push_str("fetch, ");
+    }
+    #[cfg(feature = "s3")]
+    {
+        features.push_str("s3, ");
+    }
+    #[cfg(feature = "redis")]
+    {
+        features.push_str("redis, ");
+    }
+    #[cfg(feature = "ws")]
+    {
+        features.push_str("ws, ");
+    }
+    #[cfg(feature = "grpc")]
+    {
+        features.push_str("grpc, ");
+    }
+    #[cfg(feature = "tls")]
+    {
+        features.push_str("tls, ");
+    }
+    #[cfg(feature = "postgres")]
+    {
+        features.push_str("postgres, ");
+    }
     let features = if features.is_empty() {
         "none".to_string()
     } else {
@@ -127,38 +234,136 @@ async fn main() -> anyhow::Result<()> {
     };
 
     // --- Startup Log ---------------------------------------------------------
+    let endpoint = match &uds {
+        Some(path) => format!("unix:{path}"),
+        None if tls.enabled() => format!("https://{addr}"),
+        None => format!("http://{addr}"),
+    };
     info!(
         "stats_rs v{} listening on {} (features: {})",
         env!("CARGO_PKG_VERSION"),
-        addr,
+        endpoint,
         features
     );
 
     // --- Server Startup ------------------------------------------------------
-    let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    if let Some(path) = uds {
+        serve_uds(&path, app, &shutdown).await?;
+    } else if tls.enabled() {
+        serve_tls(addr, app, &tls, &shutdown).await?;
+    } else {
+        let listener = TcpListener::bind(addr).await?;
+        let server = axum::serve(listener, app).with_graceful_shutdown(stopped(&shutdown));
+        shutdown.drain(server.into_future()).await?;
+    }
 
     info!("server shut down cleanly");
     Ok(())
 }
 
+/// Serves `app` on the Unix socket at `path`, removing the file afterwards.
+#[cfg(unix)]
+async fn serve_uds(path: &str, app: Router, shutdown: &Shutdown) -> anyhow::Result<()> {
+    use std::{
+        fs,
+        os::unix::fs::{FileTypeExt, PermissionsExt},
+    };
+
+    // A socket left behind by a crash would make bind fail; anything else
+    // at the path is not ours to delete
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => anyhow::bail!("LISTEN_UDS={path} exists and is not a socket"),
+        Err(_) => {}
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    if let Ok(mode) = env::var("LISTEN_UDS_MODE") {
+        let mode = u32::from_str_radix(mode.trim(), 8)
+            .map_err(|_| anyhow::anyhow!("LISTEN_UDS_MODE={mode:?} is not an octal mode"))?;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+
+    let server = axum::serve(listener, app).with_graceful_shutdown(stopped(shutdown));
+    let served = shutdown.drain(server.into_future()).await;
+    let _ = fs::remove_file(path);
+    Ok(served?)
+}
+
+#[cfg(not(unix))]
+async fn serve_uds(_: &str, _: Router, _: &Shutdown) -> anyhow::Result<()> {
+    anyhow::bail!("LISTEN_UDS needs a Unix platform")
+}
+
+/// Serves `app` over HTTPS on `addr`, plus the redirect listener when
+/// `tls.redirect_port` is set.
+#[cfg(feature = "tls")]
+async fn serve_tls(
+    addr: SocketAddr,
+    app: Router,
+    tls: &TlsConfig,
+    shutdown: &Shutdown,
+) -> anyhow::Result<()> {
+    let rustls = stats_rs::tls::rustls_config(tls)?;
+
+    let redirect = match tls.redirect_port {
+        Some(port) => {
+            let listener = TcpListener::bind(SocketAddr::new(addr.ip(), port)).await?;
+            info!("redirecting http://{} to https", listener.local_addr()?);
+            let app = stats_rs::tls::redirect_app(addr.port());
+            Some(tokio::spawn(
+                async move { axum::serve(listener, app).await },
+            ))
+        }
+        None => None,
+    };
+
+    let handle = axum_server::Handle::new();
+    tokio::spawn({
+        let (handle, stop) = (handle.clone(), stopped(shutdown));
+        async move {
+            stop.await;
+            handle.graceful_shutdown(None);
+        }
+    });
+    let server = axum_server::bind_rustls(addr, rustls)
+        .handle(handle)
+        .serve(app.into_make_service());
+    shutdown.drain(server).await?;
+
+    // Redirects are single responses; nothing worth draining
+    if let Some(task) = redirect {
+        task.abort();
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "tls"))]
+async fn serve_tls(_: SocketAddr, _: Router, _: &TlsConfig, _: &Shutdown) -> anyhow::Result<()> {
+    anyhow::bail!("STATS_TLS_CERT is set, but this build lacks the `tls` feature")
+}
+
+/// Resolves when listeners should close (see [`Shutdown::triggered`]).
+fn stopped(shutdown: &Shutdown) -> impl Future<Output = ()> + Send + 'static {
+    let shutdown = shutdown.clone();
+    async move { shutdown.triggered().await }
+}
+
 /// Waits for OS signals to trigger a graceful shutdown.
 ///
 /// The handler supports:
 /// - `Ctrl+C` (SIGINT)
 /// - `SIGTERM` (on Unix)
 ///
-/// Once a signal is received, the function returns,
-/// allowing [`axum::serve`] to finish active requests.
+/// Once a signal is received, the function returns and [`Shutdown`] takes
+/// over: `/ready` fails, and after the configured grace period listeners
+/// close and in-flight requests and jobs get up to the drain timeout.
 ///
 /// # Example
 ///
 /// ```rust,ignore
-/// axum::serve(listener, app)
-///     .with_graceful_shutdown(shutdown_signal())
-///     .await?;
+/// let shutdown = Shutdown::on(shutdown_signal(), state);
+/// let server = axum::serve(listener, app).with_graceful_shutdown(stopped(&shutdown));
+/// shutdown.drain(server.into_future()).await?;
 /// ```
 async fn shutdown_signal() {
     #[cfg(unix)]
@@ -0,0 +1,455 @@
+//! # Missing values in numeric payloads
+//!
+//! Array endpoints accept `null` entries as missing values. Fields opt in with
+//! `#[serde(deserialize_with = "crate::missing::values")]` (or [`series`] for a
+//! list of arrays), which decodes `null` as `NaN`; handlers then settle those
+//! with the request's [`MissingPolicy`] via [`resolve`] or [`resolve_series`]
+//! and echo a [`MissingReport`] so callers can see how many values it touched.
+//!
+//! Both decoders stream numbers straight into the output `Vec<f64>` (no
+//! intermediate `Vec<Option<f64>>`, half the memory of a large body) and, when
+//! run under [`with_value_limit`], stop at the first number past the limit
+//! instead of materializing the whole oversized array.
+
+use crate::{
+    error::ServiceError,
+    stats::prelude::*,
+    types::{MissingPolicy, MissingReport},
+};
+use serde::{
+    Deserializer,
+    de::{self, SeqAccess, Visitor},
+};
+use std::{cell::Cell, fmt};
+
+thread_local! {
+    static LIMIT: Cell<usize> = const { Cell::new(usize::MAX) };
+    static OVERFLOWED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` (a synchronous deserialization) with [`values`] arrays capped at
+/// `limit` numbers each and [`series`] lists at `limit` numbers in total.
+///
+/// Returns `f`'s result and whether a decoder hit the cap, so callers can tell
+/// that failure apart from malformed JSON.
+pub fn with_value_limit<R>(limit: usize, f: impl FnOnce() -> R) -> (R, bool) {
+    let prev = LIMIT.replace(limit);
+    OVERFLOWED.set(false);
+    let out = f();
+    LIMIT.set(prev);
+    (out, OVERFLOWED.replace(false))
+}
+
+/// Numbers of one array pushed into an empty buffer, `null` as `NaN`, at
+/// most `budget` of them.
+struct Numbers<'a> {
+    out: &'a mut Vec<f64>,
+    budget: usize,
+}
+
+fn too_many<E: de::Error>(limit: usize) -> E {
+    OVERFLOWED.set(true);
+    E::custom(format!("more than {limit} values"))
+}
+
+impl<'de> Visitor<'de> for Numbers<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of numbers or nulls")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let hint = seq.size_hint().unwrap_or(0).min(self.budget);
+        self.out.reserve(hint);
+        while let Some(x) = seq.next_element::<Option<f64>>()? {
+            if self.out.len() == self.budget {
+                return Err(too_many(LIMIT.get()));
+            }
+            self.out.push(x.unwrap_or(f64::NAN));
+        }
+        Ok(())
+    }
+}
+
+/// Deserialize a number array whose `null` entries become `NaN`.
+pub fn values<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<f64>, D::Error> {
+    let mut out = Vec::new();
+    d.deserialize_seq(Numbers {
+        out: &mut out,
+        budget: LIMIT.get(),
+    })?;
+    Ok(out)
+}
+
+/// Arrays of a list decoded with [`Numbers`], sharing one budget.
+struct Series;
+
+impl<'de> Visitor<'de> for Series {
+    type Value = Vec<Vec<f64>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of arrays of numbers or nulls")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut budget = LIMIT.get();
+        let mut out = Vec::new();
+        while let Some(xs) = seq.next_element_seed(Seed(budget))? {
+            budget -= xs.len();
+            out.push(xs);
+        }
+        Ok(out)
+    }
+}
+
+/// One array of [`Series`] with what is left of the budget.
+struct Seed(usize);
+
+impl<'de> de::DeserializeSeed<'de> for Seed {
+    type Value = Vec<f64>;
+
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<Vec<f64>, D::Error> {
+        let mut out = Vec::new();
+        d.deserialize_seq(Numbers {
+            out: &mut out,
+            budget: self.0,
+        })?;
+        Ok(out)
+    }
+}
+
+/// [`values`] for an optional field.
+pub fn optional_values<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Vec<f64>>, D::Error> {
+    use serde::Deserialize;
+
+    struct Wrap(Vec<f64>);
+    impl<'de> Deserialize<'de> for Wrap {
+        fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+            values(d).map(Wrap)
+        }
+    }
+    Ok(Option::<Wrap>::deserialize(d)?.map(|w| w.0))
+}
+
+/// Deserialize a list of number arrays whose `null` entries become `NaN`.
+pub fn series<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Vec<f64>>, D::Error> {
+    d.deserialize_seq(Series)
+}
+
+/// Deserialize a label array: strings as they are, numbers and booleans as
+/// their JSON text, `null` as `None`.
+pub fn labels<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Option<String>>, D::Error> {
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Label {
+        Text(String),
+        Number(serde_json::Number),
+        Flag(bool),
+    }
+    let labels = Vec::<Option<Label>>::deserialize(d)?;
+    Ok(labels
+        .into_iter()
+        .map(|l| {
+            l.map(|l| match l {
+                Label::Text(s) => s,
+                Label::Number(n) => n.to_string(),
+                Label::Flag(b) => b.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// [`labels`] for an optional field.
+pub fn optional_labels<'de, D: Deserializer<'de>>(
+    d: D,
+) -> Result<Option<Vec<Option<String>>>, D::Error> {
+    use serde::Deserialize;
+
+    struct Wrap(Vec<Option<String>>);
+    impl<'de> Deserialize<'de> for Wrap {
+        fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+            labels(d).map(Wrap)
+        }
+    }
+    Ok(Option::<Wrap>::deserialize(d)?.map(|w| w.0))
+}
+
+fn is_missing(x: f64) -> bool {
+    !x.is_finite()
+}
+
+/// A series after its missing values were settled.
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    pub values: Vec<f64>,
+    /// Input position of each entry of `values` (identity unless dropped)
+    pub origin: Vec<usize>,
+    pub report: MissingReport,
+}
+
+/// Fill value for the imputing policies; `None` when nothing was observed.
+fn fill_value(policy: MissingPolicy, observed: &[f64]) -> Option<f64> {
+    match policy {
+        MissingPolicy::ImputeZero => Some(0.0),
+        _ if observed.is_empty() => None,
+        MissingPolicy::ImputeMean => Some(mean(observed)),
+        MissingPolicy::ImputeMedian => Some(median(observed)),
+        MissingPolicy::Error | MissingPolicy::Drop | MissingPolicy::Pairwise => None,
+    }
+}
+
+/// Apply `policy` to one series.
+///
+/// Imputation uses the observed values' mean/median; a series with nothing
+/// observed has nothing to impute from and comes back empty.
+pub fn resolve(xs: Vec<f64>, policy: MissingPolicy) -> Result<Resolved, ServiceError> {
+    let count = xs.iter().filter(|&&x| is_missing(x)).count();
+    let report = MissingReport { policy, count };
+    if count == 0 {
+        let origin = (0..xs.len()).collect();
+        return Ok(Resolved {
+            values: xs,
+            origin,
+            report,
+        });
+    }
+    if policy == MissingPolicy::Error {
+        return Err(ServiceError::NaN);
+    }
+
+    let observed: Vec<f64> = xs.iter().copied().filter(|&x| !is_missing(x)).collect();
+    let (values, origin) = match fill_value(policy, &observed) {
+        Some(fill) => (
+            xs.iter()
+                .map(|&x| if is_missing(x) { fill } else { x })
+                .collect(),
+            (0..xs.len()).collect(),
+        ),
+        None => {
+            let origin = (0..xs.len()).filter(|&i| !is_missing(xs[i])).collect();
+            (observed, origin)
+        }
+    };
+    Ok(Resolved {
+        values,
+        origin,
+        report,
+    })
+}
+
+/// Number of values of `xs` left after [`resolve`] applies `policy`, counted
+/// without resolving (e.g. for `meta.n_used` of a handler that hands `xs` on).
+pub fn kept(xs: &[f64], policy: MissingPolicy) -> usize {
+    let observed = xs.iter().filter(|&&x| !is_missing(x)).count();
+    match policy {
+        MissingPolicy::ImputeZero => xs.len(),
+        MissingPolicy::ImputeMean | MissingPolicy::ImputeMedian if observed > 0 => xs.len(),
+        _ => observed,
+    }
+}
+
+/// Positions of aligned `series` that `policy` uses: under `drop` those
+/// observed in every series, under `pairwise` those observed in at least two
+/// (so in some pair), and all of them when imputing.
+pub fn rows_kept(series: &[Vec<f64>], policy: MissingPolicy) -> usize {
+    let len = series.iter().map(Vec::len).max().unwrap_or(0);
+    let observed = |i: usize| {
+        series
+            .iter()
+            .filter(|s| s.get(i).is_some_and(|&x| !is_missing(x)))
+            .count()
+    };
+    let needed = match policy {
+        MissingPolicy::Drop | MissingPolicy::Error => series.len(),
+        MissingPolicy::Pairwise => series.len().min(2),
+        _ => return len,
+    };
+    (0..len).filter(|&i| observed(i) >= needed).count()
+}
+
+/// Report for aligned series left as they are under the `pairwise` policy,
+/// whose missing positions are skipped pair by pair.
+pub fn pairwise_report(series: &[Vec<f64>]) -> MissingReport {
+    MissingReport {
+        policy: MissingPolicy::Pairwise,
+        count: series.iter().flatten().filter(|&&x| is_missing(x)).count(),
+    }
+}
+
+/// Apply `policy` to aligned series (e.g. the `x`/`y` of a pair).
+///
+/// `drop` is listwise: a position missing in any series is removed from all
+/// of them, so the series stay aligned; so is `pairwise` here, as only a
+/// caller comparing many series can keep different positions per pair.
+/// Imputation works per series.
+pub fn resolve_series(
+    series: Vec<Vec<f64>>,
+    policy: MissingPolicy,
+) -> Result<(Vec<Vec<f64>>, MissingReport), ServiceError> {
+    let count = series.iter().flatten().filter(|&&x| is_missing(x)).count();
+    let report = MissingReport { policy, count };
+    if count == 0 {
+        return Ok((series, report));
+    }
+    match policy {
+        MissingPolicy::Error => Err(ServiceError::NaN),
+        MissingPolicy::Drop | MissingPolicy::Pairwise => {
+            let len = series.iter().map(Vec::len).max().unwrap_or(0);
+            let keep: Vec<bool> = (0..len)
+                .map(|i| {
+                    !series
+                        .iter()
+                        .any(|s| s.get(i).is_some_and(|&x| is_missing(x)))
+                })
+                .collect();
+            let out = series
+                .into_iter()
+                .map(|s| {
+                    s.into_iter()
+                        .zip(&keep)
+                        .filter(|(_, k)| **k)
+                        .map(|(x, _)| x)
+                        .collect()
+                })
+                .collect();
+            Ok((out, report))
+        }
+        _ => {
+            let out = series
+                .into_iter()
+                .map(|s| resolve(s, policy).map(|r| r.values))
+                .collect::<Result<_, _>>()?;
+            Ok((out, report))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    const NAN: f64 = f64::NAN;
+
+    #[test]
+    fn nulls_decode_as_nan() {
+        #[derive(Deserialize)]
+        struct In {
+            #[serde(deserialize_with = "values")]
+            values: Vec<f64>,
+            #[serde(deserialize_with = "series")]
+            series: Vec<Vec<f64>>,
+        }
+        let v: In =
+            serde_json::from_str(r#"{"values":[1,null,2.5],"series":[[null],[3]]}"#).unwrap();
+        assert_eq!(v.values[0], 1.0);
+        assert!(v.values[1].is_nan());
+        assert!(v.series[0][0].is_nan());
+        assert_eq!(v.series[1], vec![3.0]);
+    }
+
+    #[test]
+    fn value_limit_stops_oversized_arrays() {
+        #[derive(Debug, Deserialize)]
+        struct Values {
+            #[serde(deserialize_with = "values")]
+            _values: Vec<f64>,
+        }
+        #[derive(Debug, Deserialize)]
+        struct Series {
+            #[serde(deserialize_with = "series")]
+            _series: Vec<Vec<f64>>,
+        }
+        let parse =
+            |limit, body| with_value_limit(limit, || serde_json::from_str::<Values>(body).is_ok());
+        assert_eq!(parse(3, r#"{"_values":[1,null,3]}"#), (true, false));
+        assert_eq!(parse(2, r#"{"_values":[1,null,3]}"#), (false, true));
+        assert_eq!(parse(2, r#"{"_values":[1,"x"]}"#), (false, false));
+
+        let (ok, over) = with_value_limit(3, || {
+            serde_json::from_str::<Series>(r#"{"_series":[[1,2],[3,4]]}"#).is_ok()
+        });
+        assert!(!ok && over);
+        // no limit outside `with_value_limit`
+        assert!(serde_json::from_str::<Series>(r#"{"_series":[[1,2],[3,4]]}"#).is_ok());
+    }
+
+    #[test]
+    fn policies_on_one_series() {
+        let xs = || vec![4.0, NAN, 1.0, NAN, 7.0];
+
+        let r = resolve(xs(), MissingPolicy::Drop).unwrap();
+        assert_eq!(r.values, vec![4.0, 1.0, 7.0]);
+        assert_eq!(r.origin, vec![0, 2, 4]);
+        assert_eq!(r.report.count, 2);
+
+        let r = resolve(xs(), MissingPolicy::ImputeMean).unwrap();
+        assert_eq!(r.values, vec![4.0, 4.0, 1.0, 4.0, 7.0]);
+        assert_eq!(r.origin, (0..5).collect::<Vec<_>>());
+        assert_eq!(
+            resolve(xs(), MissingPolicy::ImputeMedian).unwrap().values[1],
+            4.0
+        );
+        assert_eq!(
+            resolve(xs(), MissingPolicy::ImputeZero).unwrap().values[3],
+            0.0
+        );
+        assert!(matches!(
+            resolve(xs(), MissingPolicy::Error),
+            Err(ServiceError::NaN)
+        ));
+
+        // Nothing to impute from
+        let r = resolve(vec![NAN, NAN], MissingPolicy::ImputeMean).unwrap();
+        assert!(r.values.is_empty());
+        assert_eq!(r.report.count, 2);
+
+        // Complete input passes through untouched, even with `error`
+        let r = resolve(vec![1.0, 2.0], MissingPolicy::Error).unwrap();
+        assert_eq!(r.report.count, 0);
+    }
+
+    #[test]
+    fn drop_is_listwise_across_series() {
+        let s = vec![vec![1.0, NAN, 3.0, 4.0], vec![5.0, 6.0, 7.0, NAN]];
+        let (out, rep) = resolve_series(s.clone(), MissingPolicy::Drop).unwrap();
+        assert_eq!(out, vec![vec![1.0, 3.0], vec![5.0, 7.0]]);
+        assert_eq!(rep.count, 2);
+
+        let (out, _) = resolve_series(s, MissingPolicy::ImputeZero).unwrap();
+        assert_eq!(
+            out,
+            vec![vec![1.0, 0.0, 3.0, 4.0], vec![5.0, 6.0, 7.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn kept_counts_what_resolving_leaves() {
+        let xs = [1.0, NAN, 7.0, NAN];
+        for policy in [
+            MissingPolicy::Drop,
+            MissingPolicy::Pairwise,
+            MissingPolicy::ImputeMean,
+            MissingPolicy::ImputeMedian,
+            MissingPolicy::ImputeZero,
+        ] {
+            for xs in [&xs[..], &[NAN, NAN], &[2.0]] {
+                let want = resolve(xs.to_vec(), policy).unwrap().values.len();
+                assert_eq!(kept(xs, policy), want, "{policy:?} {xs:?}");
+            }
+        }
+
+        let s = vec![
+            vec![1.0, NAN, 3.0, NAN, 5.0],
+            vec![5.0, 6.0, NAN, NAN, 7.0],
+            vec![5.0, 6.0, 7.0, NAN, 8.0],
+        ];
+        let (out, _) = resolve_series(s.clone(), MissingPolicy::Drop).unwrap();
+        assert_eq!(rows_kept(&s, MissingPolicy::Drop), out[0].len());
+        assert_eq!(rows_kept(&s, MissingPolicy::Pairwise), 4);
+        assert_eq!(rows_kept(&s, MissingPolicy::ImputeMean), 5);
+    }
+}
@@ -0,0 +1,210 @@
+//! # Node.js bindings (feature `napi`)
+//!
+//! napi-rs exports of the hot kernels so the TypeScript backend can score
+//! small payloads in-process and keep HTTP for large or long-running jobs.
+//! Numbers match the service: [`quantiles`] is the R-7 quantile used by
+//! `/stats/distribution`, and [`retrieval_metrics`] computes the same
+//! per-query values as `POST /stats/rag/metrics`.
+//!
+//! Build the addon without the server:
+//!
+//! ```bash
+//! cargo rustc --lib --release --crate-type cdylib --no-default-features --features napi
+//! cp target/release/libstats_rs.so stats_rs.node   # .dylib on macOS, .dll on Windows
+//! ```
+//!
+//! ```js
+//! const { cosineMatrix, quantiles } = require("./stats_rs.node");
+//! quantiles([3, 1, 2, 4], [0.5, 0.9]); // [2.5, 3.7]
+//! ```
+
+use crate::{error::ServiceError, stats::prelude::*};
+use napi_derive::napi;
+use std::collections::HashSet;
+
+/// One query's ranked result ids and its relevant ids.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct RetrievalQuery {
+    pub retrieved: Vec<u32>,
+    pub relevant: Vec<u32>,
+}
+
+/// Mean metrics over the queries plus the per-query values (`null` where a
+/// metric is undefined, e.g. recall without relevant ids).
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct RetrievalMetrics {
+    pub n_queries: u32,
+    pub k: u32,
+    pub precision_at_k: Option<f64>,
+    pub recall_at_k: Option<f64>,
+    pub mrr: Option<f64>,
+    pub ndcg_at_k: Option<f64>,
+    pub map: Option<f64>,
+    pub per_query: Vec<RetrievalPerQuery>,
+}
+
+/// Metrics of a single query.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct RetrievalPerQuery {
+    pub precision_at_k: Option<f64>,
+    pub recall_at_k: Option<f64>,
+    pub reciprocal_rank: Option<f64>,
+    pub ndcg_at_k: Option<f64>,
+    pub average_precision: Option<f64>,
+}
+
+fn o(x: f64) -> Option<f64> {
+    if x.is_nan() { None } else { Some(x) }
+}
+
+/// Cosine similarity of every row of `a` to every row of `b` (`a` itself
+/// when `b` is absent); zero vectors give `NaN`.
+pub fn cosine_matrix_of(
+    a: &[Vec<f64>],
+    b: Option<&[Vec<f64>]>,
+) -> Result<Vec<Vec<f64>>, ServiceError> {
+    let b = b.unwrap_or(a);
+    let dim = a.first().or(b.first()).map_or(0, Vec::len);
+    if let Some(i) = a.iter().chain(b).position(|r| r.len() != dim) {
+        return Err(ServiceError::InvalidInput(format!(
+            "row {i} has {} dimensions, expected {dim}",
+            a.iter().chain(b).nth(i).map_or(0, Vec::len)
+        )));
+    }
+    #[cfg(feature = "ndarray")]
+    return Ok(crate::stats::linalg::cosine_matrix(a, b));
+    #[cfg(not(feature = "ndarray"))]
+    Ok(a.iter()
+        .map(|x| b.iter().map(|y| cosine_similarity(x, y)).collect())
+        .collect())
+}
+
+/// P@k, R@k, MRR, nDCG@k and AP per query (`k` defaults to 10), with their means.
+pub fn retrieval_metrics_of(queries: &[RetrievalQuery], k: Option<u32>) -> RetrievalMetrics {
+    let k = k.unwrap_or(10);
+    let ids = |v: &[u32]| v.iter().map(|&i| i as usize).collect::<Vec<_>>();
+    let per_query: Vec<[f64; 5]> = queries
+        .iter()
+        .map(|q| {
+            let (retrieved, relevant) = (ids(&q.retrieved), ids(&q.relevant));
+            let rel: HashSet<usize> = relevant.iter().copied().collect();
+            let k = k as usize;
+            [
+                precision_at_k(&retrieved, &relevant, k),
+                recall_at_k(&retrieved, &relevant, k),
+                mrr(&retrieved, &relevant),
+                ndcg_at_k(&retrieved, &relevant, k),
+                average_precision(&retrieved, &rel),
+            ]
+        })
+        .collect();
+    let mean_of = |m: usize| {
+        let v: Vec<f64> = per_query
+            .iter()
+            .map(|q| q[m])
+            .filter(|x| !x.is_nan())
+            .collect();
+        (!v.is_empty()).then(|| mean(&v))
+    };
+    RetrievalMetrics {
+        n_queries: queries.len() as u32,
+        k,
+        precision_at_k: mean_of(0),
+        recall_at_k: mean_of(1),
+        mrr: mean_of(2),
+        ndcg_at_k: mean_of(3),
+        map: mean_of(4),
+        per_query: per_query
+            .iter()
+            .map(|&[p, r, rr, nd, ap]| RetrievalPerQuery {
+                precision_at_k: o(p),
+                recall_at_k: o(r),
+                reciprocal_rank: o(rr),
+                ndcg_at_k: o(nd),
+                average_precision: o(ap),
+            })
+            .collect(),
+    }
+}
+
+/// R-7 quantiles of `values` at each of `probs` (each in `[0, 1]`); `NaN`
+/// values are ignored and an empty input gives `NaN`s.
+pub fn quantiles_of(values: &[f64], probs: &[f64]) -> Result<Vec<f64>, ServiceError> {
+    if let Some(p) = probs.iter().find(|p| !(0.0..=1.0).contains(*p)) {
+        return Err(ServiceError::InvalidInput(format!(
+            "probs: {p} is not a probability in [0, 1]"
+        )));
+    }
+    let xs: Vec<f64> = values.iter().copied().filter(|x| !x.is_nan()).collect();
+    let xs = sorted(&xs);
+    Ok(probs.iter().map(|&p| quantile_sorted(&xs, p)).collect())
+}
+
+fn js<T>(out: Result<T, ServiceError>) -> napi::Result<T> {
+    out.map_err(|e| napi::Error::new(napi::Status::InvalidArg, e.to_string()))
+}
+
+/// `cosineMatrix(a: number[][], b?: number[][]): number[][]`
+#[napi]
+pub fn cosine_matrix(a: Vec<Vec<f64>>, b: Option<Vec<Vec<f64>>>) -> napi::Result<Vec<Vec<f64>>> {
+    js(cosine_matrix_of(&a, b.as_deref()))
+}
+
+/// `retrievalMetrics(queries: RetrievalQuery[], k?: number): RetrievalMetrics`
+#[napi]
+pub fn retrieval_metrics(queries: Vec<RetrievalQuery>, k: Option<u32>) -> RetrievalMetrics {
+    retrieval_metrics_of(&queries, k)
+}
+
+/// `quantiles(values: number[], probs: number[]): number[]`
+#[napi]
+pub fn quantiles(values: Vec<f64>, probs: Vec<f64>) -> napi::Result<Vec<f64>> {
+    js(quantiles_of(&values, &probs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cosine_matrix_is_square_or_cross() {
+        let a = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]];
+        let m = cosine_matrix_of(&a, None).unwrap();
+        assert_eq!((m.len(), m[0].len()), (3, 3));
+        assert!((m[0][2] - 1.0 / 2f64.sqrt()).abs() < 1e-12);
+        let m = cosine_matrix_of(&a, Some(&[vec![2.0, 0.0]])).unwrap();
+        assert_eq!(m, vec![vec![1.0], vec![0.0], vec![1.0 / 2f64.sqrt()]]);
+        assert!(cosine_matrix_of(&a, Some(&[vec![1.0]])).is_err());
+    }
+
+    #[test]
+    fn retrieval_metrics_match_the_route() {
+        let queries = [
+            RetrievalQuery {
+                retrieved: vec![3, 1, 2],
+                relevant: vec![1],
+            },
+            RetrievalQuery {
+                retrieved: vec![5],
+                relevant: vec![],
+            },
+        ];
+        let m = retrieval_metrics_of(&queries, Some(2));
+        assert_eq!((m.n_queries, m.k), (2, 2));
+        assert_eq!(m.per_query[0].reciprocal_rank, Some(0.5));
+        assert_eq!(m.per_query[1].recall_at_k, None);
+        assert_eq!(m.mrr, Some(0.25));
+        assert_eq!(m.recall_at_k, Some(1.0));
+    }
+
+    #[test]
+    fn quantiles_skip_nan_and_check_probs() {
+        let q = quantiles_of(&[3.0, f64::NAN, 1.0, 2.0, 4.0], &[0.0, 0.5, 1.0]).unwrap();
+        assert_eq!(q, vec![1.0, 2.5, 4.0]);
+        assert!(quantiles_of(&[1.0], &[1.5]).is_err());
+        assert!(quantiles_of(&[], &[0.5]).unwrap()[0].is_nan());
+    }
+}
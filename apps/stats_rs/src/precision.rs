@@ -0,0 +1,231 @@
+//! # Output precision
+//!
+//! Returned floats carry every digit of their shortest round-trip form by
+//! default (`0.30000000000000004`). `?precision=N` on any API request, or
+//! `STATS_PRECISION` for requests without it, rounds them to `N` significant
+//! digits (1–17) in JSON bodies and CSV/TSV tables alike. Integers such as
+//! counts and indices are left alone, and so are the keys and strings of
+//! JSON bodies.
+//!
+//! Non-finite results are written the same way everywhere: JSON has no NaN
+//! or infinity, so they are `null`, and CSV/TSV cells holding `NaN`, `inf` or
+//! `-inf` are emptied, with a `Warning` header counting them.
+
+use crate::{error::ServiceError, state::AppState, validate::invalid};
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Query, Request, State},
+    http::{HeaderValue, Uri, header, response::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::{ops::RangeInclusive, sync::Arc};
+
+/// Significant digits a request or the config may ask for.
+pub const DIGITS: RangeInclusive<u32> = 1..=17;
+
+#[derive(Deserialize)]
+struct PrecisionQuery {
+    precision: Option<String>,
+}
+
+/// `?precision=` of `uri`, if given.
+fn requested(uri: &Uri) -> Result<Option<u32>, ServiceError> {
+    let Ok(Query(q)) = Query::<PrecisionQuery>::try_from_uri(uri) else {
+        return Ok(None);
+    };
+    let Some(raw) = q.precision else {
+        return Ok(None);
+    };
+    match raw.trim().parse() {
+        Ok(d) if DIGITS.contains(&d) => Ok(Some(d)),
+        _ => Err(invalid(
+            "/precision",
+            format!("must be a whole number in 1..=17, got '{raw}'"),
+        )),
+    }
+}
+
+/// `x` rounded to `digits` significant digits, in decimal: `0.1 + 0.2`
+/// becomes exactly the double nearest `0.3` at any `digits` below 17.
+pub fn round_significant(x: f64, digits: u32) -> f64 {
+    if !x.is_finite() || x == 0.0 {
+        return x;
+    }
+    let d = digits.clamp(*DIGITS.start(), *DIGITS.end()) as usize;
+    format!("{:.*e}", d - 1, x).parse().unwrap_or(x)
+}
+
+/// `token` rewritten at `digits`, if it is a float literal (it has a
+/// fraction or an exponent); integers come back as `None`.
+fn round_literal(token: &str, digits: u32) -> Option<String> {
+    if !token.contains(['.', 'e', 'E']) {
+        return None;
+    }
+    let x: f64 = token.parse().ok()?;
+    serde_json::Number::from_f64(round_significant(x, digits)).map(|n| n.to_string())
+}
+
+/// `json` with each float literal rounded to `digits`; everything else,
+/// including key order and whitespace, is copied as is.
+pub fn round_json(json: &[u8], digits: u32) -> Vec<u8> {
+    let mut out = Vec::with_capacity(json.len());
+    let mut i = 0;
+    while i < json.len() {
+        match json[i] {
+            b'"' => {
+                // copy the string through its closing quote
+                let start = i;
+                i += 1;
+                while i < json.len() && json[i] != b'"' {
+                    i += if json[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(json.len());
+                out.extend_from_slice(&json[start..i]);
+            }
+            b'-' | b'0'..=b'9' => {
+                let start = i;
+                while i < json.len()
+                    && matches!(json[i], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                {
+                    i += 1;
+                }
+                let token = std::str::from_utf8(&json[start..i]).unwrap_or_default();
+                match round_literal(token, digits) {
+                    Some(r) => out.extend_from_slice(r.as_bytes()),
+                    None => out.extend_from_slice(&json[start..i]),
+                }
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// A delimited table with float cells rounded to `digits` (when given) and
+/// non-finite cells emptied, and the number of cells emptied. The header
+/// row is copied as is.
+pub fn round_delimited(text: &[u8], delimiter: u8, digits: Option<u32>) -> (Vec<u8>, usize) {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text);
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_writer(Vec::with_capacity(text.len()));
+    let mut blanked = 0;
+    for (row, record) in reader.records().enumerate() {
+        let Ok(record) = record else {
+            // not a table after all: leave it alone
+            return (text.to_vec(), 0);
+        };
+        let cells = record.iter().map(|cell| {
+            if row == 0 {
+                return cell.to_string();
+            }
+            match cell {
+                "NaN" | "inf" | "-inf" => {
+                    blanked += 1;
+                    String::new()
+                }
+                _ => digits
+                    .and_then(|d| round_literal(cell, d))
+                    .unwrap_or_else(|| cell.to_string()),
+            }
+        });
+        let cells: Vec<String> = cells.collect();
+        // Writing into a Vec cannot fail
+        writer.write_record(&cells).expect("in-memory write");
+    }
+    (writer.into_inner().expect("in-memory write"), blanked)
+}
+
+fn content_type(parts: &Parts) -> &str {
+    parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+}
+
+/// Axum middleware applying the requested precision to successful JSON,
+/// CSV and TSV responses, and emptying non-finite CSV/TSV cells.
+pub async fn middleware(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let digits = match requested(req.uri()) {
+        Ok(d) => d.or(state.config.precision),
+        Err(e) => return e.into_response(),
+    };
+    let (mut parts, body) = next.run(req).await.into_parts();
+    let ct = content_type(&parts);
+    let delimiter = match ct {
+        _ if !parts.status.is_success() => return Response::from_parts(parts, body),
+        ct if ct.starts_with("application/json") && digits.is_some() => None,
+        ct if ct.starts_with("text/csv") => Some(b','),
+        ct if ct.starts_with("text/tab-separated-values") => Some(b'\t'),
+        _ => return Response::from_parts(parts, body),
+    };
+
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(b) => b,
+        Err(e) => return ServiceError::Internal(format!("response body: {e}")).into_response(),
+    };
+    let out = match (delimiter, digits) {
+        (None, Some(d)) => round_json(&bytes, d),
+        (Some(delimiter), digits) => {
+            let (out, blanked) = round_delimited(&bytes, delimiter, digits);
+            if blanked > 0 {
+                let warning =
+                    format!("199 - \"{blanked} non-finite value(s) written as empty cells\"");
+                if let Ok(v) = HeaderValue::from_str(&warning) {
+                    parts.headers.insert(header::WARNING, v);
+                }
+            }
+            out
+        }
+        (None, None) => bytes.to_vec(),
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_to_significant_digits() {
+        assert_eq!(round_significant(0.1 + 0.2, 15), 0.3);
+        assert_eq!(round_significant(123_456.789, 4), 123_500.0);
+        assert_eq!(round_significant(-0.000_123_456, 2), -0.000_12);
+        assert_eq!(round_significant(2.6e-300, 1), 3e-300);
+        assert!(round_significant(f64::NAN, 3).is_nan());
+        assert_eq!(round_significant(0.0, 3), 0.0);
+    }
+
+    #[test]
+    fn json_floats_round_but_integers_and_strings_do_not() {
+        let body = br#"{"count":12345,"mean":3.14159265,"name":"v 1.23456","xs":[1e-7,-2.7182818,0.5],"esc":"a\"1.2345"}"#;
+        let out = String::from_utf8(round_json(body, 3)).unwrap();
+        assert_eq!(
+            out,
+            r#"{"count":12345,"mean":3.14,"name":"v 1.23456","xs":[1e-7,-2.72,0.5],"esc":"a\"1.2345"}"#
+        );
+    }
+
+    #[test]
+    fn tables_round_and_blank_non_finite_cells() {
+        let (out, blanked) =
+            round_delimited(b"x,p\n1.23456,0.5\nNaN,inf\n7,0.333333\n", b',', Some(2));
+        assert_eq!(String::from_utf8(out).unwrap(), "x,p\n1.2,0.5\n,\n7,0.33\n");
+        assert_eq!(blanked, 2);
+        let (out, blanked) = round_delimited(b"a\tb\n0.123456\t-inf\n", b'\t', None);
+        assert_eq!(String::from_utf8(out).unwrap(), "a\tb\n0.123456\t\n");
+        assert_eq!(blanked, 1);
+    }
+}
@@ -0,0 +1,122 @@
+//! # Request ids
+//!
+//! Every request carries an `X-Request-Id`: the client's own, or a UUID
+//! generated on arrival (`SetRequestIdLayer` in [`build_app`](crate::build_app)).
+//! The id is
+//!
+//! - echoed in the `X-Request-Id` response header,
+//! - recorded on the request's tracing span ([`span`]), so log lines for a
+//!   request can be found by it, and
+//! - added as `request_id` to JSON error bodies ([`middleware`]), including
+//!   the `error` of an `/api/v2` envelope,
+//!
+//! so a user reporting a wrong result or a failure can quote it.
+
+use crate::error::ServiceError;
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::{self, HeaderName, HeaderValue, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+use tracing::Span;
+
+/// Header carrying the id, in both directions.
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The request's id, if set.
+pub fn of<B>(req: &http::Request<B>) -> Option<&str> {
+    req.headers()
+        .get(X_REQUEST_ID)
+        .and_then(|v| v.to_str().ok())
+}
+
+/// Tracing span for a request, as `TraceLayer`'s default plus `request_id`.
+pub fn span<B>(req: &http::Request<B>) -> Span {
+    tracing::info_span!(
+        "request",
+        method = %req.method(),
+        uri = %req.uri(),
+        version = ?req.version(),
+        request_id = of(req).unwrap_or_default(),
+    )
+}
+
+/// Set `request_id` on an error body: the envelope's `error`, or the
+/// top-level [`ErrorResponse`](crate::types::ErrorResponse). Other JSON is
+/// left alone.
+pub fn annotate(body: &mut Value, id: &str) -> bool {
+    let target = if matches!(body.get("error"), Some(Value::Object(_))) {
+        body.get_mut("error")
+    } else if body.get("code").is_some_and(Value::is_string) {
+        Some(body)
+    } else {
+        None
+    };
+    match target.and_then(Value::as_object_mut) {
+        Some(obj) => {
+            obj.insert("request_id".into(), id.into());
+            true
+        }
+        None => false,
+    }
+}
+
+/// Axum middleware adding the request id to JSON error bodies.
+pub async fn middleware(req: Request, next: Next) -> Response {
+    let Some(id) = of(&req).map(str::to_owned) else {
+        return next.run(req).await;
+    };
+    let res = next.run(req).await;
+    let failed = res.status().is_client_error() || res.status().is_server_error();
+    let json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    if !(failed && json) {
+        return res;
+    }
+
+    let (mut parts, body) = res.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(b) => b,
+        Err(e) => return ServiceError::Internal(format!("response body: {e}")).into_response(),
+    };
+    let mut value = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(v) => v,
+        Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+    };
+    if !annotate(&mut value, &id) {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn annotates_plain_and_enveloped_errors() {
+        let mut v1 = json!({"code": "not_found", "message": "job 'x'"});
+        assert!(annotate(&mut v1, "r1"));
+        assert_eq!(v1["request_id"], "r1");
+
+        let mut v2 = json!({"data": null, "meta": {}, "error": {"code": "timeout", "message": ""}});
+        assert!(annotate(&mut v2, "r2"));
+        assert_eq!(v2["error"]["request_id"], "r2");
+        assert!(v2.get("request_id").is_none());
+
+        let mut other = json!({"detail": "x"});
+        assert!(!annotate(&mut other, "r3"));
+    }
+}
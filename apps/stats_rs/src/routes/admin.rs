@@ -0,0 +1,193 @@
+//! /admin/* — runtime management for operators
+//!
+//! Mounted by [`build_app`](crate::build_app) only when an admin token is
+//! configured (`STATS_ADMIN_TOKEN`), and every request must carry it as
+//! `Authorization: Bearer <token>`. The routes act on this process only (a
+//! Redis shared cache is left alone), see every tenant's datasets and are not
+//! listed in `/openapi.json`.
+
+use crate::{
+    config::ServiceConfig,
+    error::ServiceError,
+    state::AppState,
+    types::{
+        AuditOut, AuditQuery, CacheFlushOut, DatasetOut, DatasetsClearedOut, JobsDrainedOut,
+        LogLevelIo,
+    },
+};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+};
+use std::sync::Arc;
+
+/// The admin routes, behind [`require_token`].
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/admin/config", get(get_config))
+        .route("/admin/cache/flush", post(flush_cache))
+        .route("/admin/datasets", get(list_datasets).delete(clear_datasets))
+        .route("/admin/datasets/{id}", delete(delete_dataset))
+        .route("/admin/log-level", get(get_log_level).put(set_log_level))
+        .route("/admin/jobs/drain", post(drain_jobs))
+        .route("/admin/audit", get(get_audit))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+/// Reject requests without the configured bearer token (`401`).
+pub async fn require_token(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let expected = state.config.admin.token.as_deref().unwrap_or_default();
+    let given = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if !expected.is_empty() && same(given.as_bytes(), expected.as_bytes()) {
+        return next.run(req).await;
+    }
+    let mut res =
+        ServiceError::Unauthorized("admin routes need a valid bearer token".into()).into_response();
+    res.headers_mut()
+        .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    res
+}
+
+/// Byte equality that takes the same time wherever the first difference is.
+pub(crate) fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// The effective settings, with secrets shown as `***`.
+async fn get_config(State(state): State<Arc<AppState>>) -> Json<ServiceConfig> {
+    Json(state.config.clone())
+}
+
+/// Empty the in-process result cache; hit/miss counters are kept.
+async fn flush_cache(State(state): State<Arc<AppState>>) -> Json<CacheFlushOut> {
+    let freed = state.cache.clear();
+    tracing::info!(
+        entries = freed.entries,
+        bytes = freed.bytes,
+        "cache flushed"
+    );
+    Json(CacheFlushOut {
+        entries: freed.entries,
+        bytes: freed.bytes,
+    })
+}
+
+/// Every tenant's datasets in registration order, each naming its tenant.
+async fn list_datasets(State(state): State<Arc<AppState>>) -> Json<Vec<DatasetOut>> {
+    let all = state.datasets.all();
+    Json(
+        all.iter()
+            .map(|d| DatasetOut {
+                tenant: Some(d.tenant.to_string()),
+                ..d.as_ref().into()
+            })
+            .collect(),
+    )
+}
+
+/// Drop one dataset, whichever tenant registered it.
+///
+/// - **Response**: `204 No Content`; `404` for an unknown id
+async fn delete_dataset(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ServiceError> {
+    let d = state
+        .datasets
+        .evict(&id)
+        .ok_or_else(|| ServiceError::NotFound(format!("dataset '{id}'")))?;
+    tracing::info!(id, tenant = %d.tenant, "dataset evicted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Drop every registered dataset.
+async fn clear_datasets(State(state): State<Arc<AppState>>) -> Json<DatasetsClearedOut> {
+    let removed = state.datasets.clear();
+    tracing::info!(removed, "datasets cleared");
+    Json(DatasetsClearedOut { removed })
+}
+
+/// The active log filter.
+///
+/// - **Errors**: `Conflict` (`409`) when the process has no reloadable filter
+async fn get_log_level(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LogLevelIo>, ServiceError> {
+    let log = log_control(&state)?;
+    Ok(Json(LogLevelIo {
+        filter: log.filter(),
+    }))
+}
+
+/// Replace the log filter, e.g. `{"filter": "info,stats_rs=debug"}`.
+///
+/// - **Errors**: `InvalidInput` (`400`) for an unparseable filter; `Conflict`
+///   (`409`) when the process has no reloadable filter
+async fn set_log_level(
+    State(state): State<Arc<AppState>>,
+    Json(inp): Json<LogLevelIo>,
+) -> Result<Json<LogLevelIo>, ServiceError> {
+    let log = log_control(&state)?;
+    log.set(&inp.filter)?;
+    tracing::info!(filter = %inp.filter, "log filter changed");
+    Ok(Json(inp))
+}
+
+fn log_control(state: &AppState) -> Result<&crate::logging::LogControl, ServiceError> {
+    state.log.as_ref().ok_or_else(|| {
+        ServiceError::Conflict("log filter is not adjustable in this process".into())
+    })
+}
+
+/// Cancel every queued job; running jobs finish normally.
+async fn drain_jobs(State(state): State<Arc<AppState>>) -> Json<JobsDrainedOut> {
+    let cancelled = state.jobs.drain();
+    tracing::info!(cancelled, "job queue drained");
+    Json(JobsDrainedOut { cancelled })
+}
+
+/// Audit records matching the query's filters, newest first, e.g.
+/// `?user=ana&path=/api/v1/stats/&since_ms=1760000000000&limit=50`.
+///
+/// - **Errors**: `Conflict` (`409`) when no audit store is configured;
+///   `Internal` (`500`) when the store cannot be read
+async fn get_audit(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<AuditQuery>,
+) -> Result<Json<AuditOut>, ServiceError> {
+    let log = state
+        .audit
+        .as_ref()
+        .ok_or_else(|| ServiceError::Conflict("audit trail is not enabled".into()))?;
+    let records = log
+        .query(&q)
+        .await
+        .map_err(|e| ServiceError::Internal(format!("audit store: {e}")))?;
+    Ok(Json(AuditOut { records }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_tokens_exactly() {
+        assert!(same(b"s3cret", b"s3cret"));
+        assert!(!same(b"s3cret", b"s3creT"));
+        assert!(!same(b"s3cre", b"s3cret"));
+    }
+}
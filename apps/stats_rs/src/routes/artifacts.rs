@@ -0,0 +1,88 @@
+//! /artifacts/*
+
+use crate::{error::ServiceError, state::AppState, tenant::Tenant, types::ErrorResponse};
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+/// The bytes `tenant` stored as `id`, served with their media type.
+pub(crate) async fn serve(
+    state: &AppState,
+    tenant: &Tenant,
+    id: &str,
+) -> Result<Response, ServiceError> {
+    let artifact = state
+        .artifacts
+        .get(tenant, id)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound(format!("artifact '{id}'")))?;
+    let content_type = HeaderValue::from_str(&artifact.meta.content_type)
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            // An id never names other bytes
+            (
+                header::CACHE_CONTROL,
+                HeaderValue::from_static("private, max-age=86400, immutable"),
+            ),
+        ],
+        artifact.bytes,
+    )
+        .into_response())
+}
+
+/// Store `bytes` and answer `201 Created` with their [`ArtifactOut`] and
+/// `Location: /api/v1/artifacts/{id}`.
+///
+/// [`ArtifactOut`]: crate::types::ArtifactOut
+pub(crate) async fn created(
+    state: &AppState,
+    tenant: &Tenant,
+    content_type: &str,
+    bytes: Bytes,
+) -> Result<Response, ServiceError> {
+    let meta = state.artifacts.put(tenant, content_type, bytes).await?;
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, format!("/api/v1/artifacts/{}", meta.id))],
+        Json(meta),
+    )
+        .into_response())
+}
+
+/// Download a stored result.
+///
+/// Artifacts hold job results too large to keep with the job (see
+/// `JobOut.artifact`) and documents stored with `?store=true`.
+///
+/// - **Response**: the stored bytes, with the media type they were stored as
+/// - **Errors**: `NotFound` (`404`) for an unknown or evicted id, or one
+///   stored by another tenant
+#[utoipa::path(
+    get,
+    path = "/artifacts/{id}",
+    tag = "jobs",
+    summary = "Download a stored result",
+    params(("id" = String, Path, description = "Artifact id (`art_…`)")),
+    responses(
+        (status = 200, description = "The stored bytes", content(
+            (String = "application/json"),
+            (String = "text/html"),
+            (String = "text/markdown")
+        )),
+        (status = 404, description = "Not Found", body = ErrorResponse)
+    )
+)]
+pub async fn get_artifact(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Path(id): Path<String>,
+) -> Result<Response, ServiceError> {
+    serve(&state, &tenant, &id).await
+}
@@ -0,0 +1,426 @@
+//! /datasets/*
+
+use crate::{
+    cache::CacheKey,
+    datasets::{Dataset, DatasetCorr},
+    envelope::NUsed,
+    error::ServiceError,
+    frame::{ColumnData, Frame},
+    missing::resolve_series,
+    routes::stats_distribution::{assemble, empty},
+    state::AppState,
+    stats::prelude::*,
+    tenant::Tenant,
+    types::{
+        ColumnDistQuery, ColumnType, CorrMatrixOut, CorrRowsIn, CorrSeriesIn, DatasetOut, DistOut,
+        ErrorResponse, MissingPolicy, MissingReport,
+    },
+    validate::{Valid, Validate, invalid, probability_list},
+};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use std::sync::{Arc, Mutex};
+
+impl From<&Dataset> for DatasetOut {
+    fn from(d: &Dataset) -> Self {
+        DatasetOut {
+            id: d.id.clone(),
+            name: d.name.clone(),
+            source: d.source.clone(),
+            format: d.format,
+            bytes: d.bytes,
+            rows: d.table.n_rows,
+            created_at: d.created_at,
+            columns: d.table.schema(),
+            sample: d.table.sample,
+            tenant: None,
+        }
+    }
+}
+
+/// List registered datasets in registration order.
+#[utoipa::path(
+    get,
+    path = "/datasets",
+    tag = "datasets",
+    summary = "List registered datasets",
+    responses(
+        (status = 200, description = "OK", body = Vec<DatasetOut>)
+    )
+)]
+pub async fn list_datasets(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+) -> Json<Vec<DatasetOut>> {
+    Json(
+        state
+            .datasets
+            .list(&tenant)
+            .iter()
+            .map(|d| d.as_ref().into())
+            .collect(),
+    )
+}
+
+/// Metadata and inferred schema of one dataset.
+///
+/// - **Errors**: `NotFound` (`404`) for an unknown id
+#[utoipa::path(
+    get,
+    path = "/datasets/{id}",
+    tag = "datasets",
+    summary = "Dataset metadata and inferred schema",
+    responses(
+        (status = 200, description = "OK", body = DatasetOut),
+        (status = 404, description = "Not Found", body = ErrorResponse)
+    )
+)]
+pub async fn get_dataset(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Path(id): Path<String>,
+) -> Result<(NUsed, Json<DatasetOut>), ServiceError> {
+    let ds = dataset(&state, &tenant, &id)?;
+    Ok((NUsed(ds.table.n_rows), Json(ds.as_ref().into())))
+}
+
+/// Drop a dataset from the registry.
+///
+/// - **Response**: `204 No Content`; `404` for an unknown id
+#[utoipa::path(
+    delete,
+    path = "/datasets/{id}",
+    tag = "datasets",
+    summary = "Remove a dataset",
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "Not Found", body = ErrorResponse)
+    )
+)]
+pub async fn delete_dataset(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ServiceError> {
+    state
+        .datasets
+        .remove(&tenant, &id)
+        .map(|_| StatusCode::NO_CONTENT)
+        .ok_or_else(|| ServiceError::NotFound(format!("dataset '{id}'")))
+}
+
+/// Histogram, quantiles and shape statistics of one numeric column of a
+/// dataset, as `/stats/distribution` computes them for its values.
+///
+/// - **Missing**: empty and unparseable cells are dropped (`missing` reports
+///   how many)
+/// - **Caching**: the column's values, their sorted copy and each histogram
+///   are cached per dataset, column and bin count, so redrawing a chart of
+///   the same column skips the parse, the sort and the binning
+/// - **Errors**: `404` for an unknown dataset or column, `400` for a text
+///   column, `422` naming `/bins` or `/quantiles/i`
+#[utoipa::path(
+    get,
+    path = "/datasets/{id}/columns/{column}/distribution",
+    tag = "datasets",
+    summary = "Distribution of one dataset column",
+    params(
+        ("id" = String, Path, description = "Dataset id"),
+        ("column" = String, Path, description = "Column name"),
+        ColumnDistQuery
+    ),
+    responses(
+        (status = 200, description = "OK", body = DistOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the parameter", body = ErrorResponse)
+    )
+)]
+pub async fn column_distribution(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Path((id, column)): Path<(String, String)>,
+    Query(q): Query<ColumnDistQuery>,
+) -> Result<(NUsed, Json<DistOut>), ServiceError> {
+    q.validate(&state.config)?;
+    let ds = dataset(&state, &tenant, &id)?;
+    let col = ds
+        .table
+        .columns
+        .iter()
+        .find(|c| c.name == column)
+        .ok_or_else(|| ServiceError::NotFound(format!("column '{column}' in dataset '{id}'")))?;
+    if !matches!(col.dtype, ColumnType::Integer | ColumnType::Float) {
+        return Err(ServiceError::InvalidInput(format!(
+            "column '{column}' is not numeric"
+        )));
+    }
+
+    let cache = &state.cache;
+    let key = |kind, bins: Option<usize>| CacheKey::of_column(kind, &id, &column, &bins);
+    let values = cache.get_or_insert_with(key("column_values", None), || {
+        col.cells
+            .iter()
+            .filter_map(|s| ds.table.infer.number(s))
+            .filter(|x| x.is_finite())
+            .collect::<Vec<f64>>()
+    });
+    let missing = Some(MissingReport {
+        policy: MissingPolicy::Drop,
+        count: col.cells.len() - values.len(),
+    });
+    if values.is_empty() {
+        return Ok((NUsed(0), Json(DistOut { missing, ..empty() })));
+    }
+
+    let bins = q.bins.unwrap_or(10);
+    let ascending = cache.get_or_insert_with(key("column_sorted", None), || sorted(&values));
+    let hist = cache.get_or_insert_with(key("column_histogram", Some(bins)), || {
+        histogram(&values, bins)
+    });
+    let qs = match &q.quantiles {
+        Some(s) => probability_list("/quantiles", s)?,
+        None => vec![0.25, 0.5, 0.75],
+    };
+    Ok((
+        NUsed(values.len()),
+        Json(DistOut {
+            missing,
+            ..assemble(
+                &values,
+                &ascending,
+                (*hist).clone(),
+                qs,
+                q.quantile_method.unwrap_or_default().into(),
+            )
+        }),
+    ))
+}
+
+/// Dataset `id` of `tenant`; `404` also for another tenant's dataset.
+pub(crate) fn dataset(
+    state: &AppState,
+    tenant: &Tenant,
+    id: &str,
+) -> Result<Arc<Dataset>, ServiceError> {
+    state
+        .datasets
+        .get(tenant, id)
+        .ok_or_else(|| ServiceError::NotFound(format!("dataset '{id}'")))
+}
+
+fn stored_corr(
+    state: &AppState,
+    tenant: &Tenant,
+    id: &str,
+) -> Result<Arc<Mutex<DatasetCorr>>, ServiceError> {
+    dataset(state, tenant, id)?;
+    state
+        .datasets
+        .corr(id)
+        .ok_or_else(|| ServiceError::NotFound(format!("correlation matrix of dataset '{id}'")))
+}
+
+/// Response for a stored matrix, with the observations it was computed from.
+fn corr_out(corr: &DatasetCorr, missing: Option<MissingReport>) -> (NUsed, Json<CorrMatrixOut>) {
+    let out = CorrMatrixOut {
+        size: corr.moments.size(),
+        names: Some(corr.names.clone()),
+        matrix: corr.moments.matrix(),
+        p_values: None,
+        n_used: None,
+        covariance: None,
+        partial: None,
+        order: None,
+        linkage: None,
+        missing,
+    };
+    (NUsed(corr.moments.len()), Json(out))
+}
+
+/// Compute and store the Pearson correlation matrix of a dataset's numeric
+/// columns, replacing any matrix stored before.
+///
+/// - **Missing**: rows with an empty or unparseable cell in any numeric
+///   column are dropped (`missing` reports how many)
+/// - **Errors**: `404` for an unknown dataset, `400` when it has no numeric
+///   column
+#[utoipa::path(
+    put,
+    path = "/datasets/{id}/corr-matrix",
+    tag = "datasets",
+    summary = "Store the correlation matrix of a dataset",
+    params(("id" = String, Path, description = "Dataset id")),
+    responses(
+        (status = 200, description = "OK", body = CorrMatrixOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse)
+    )
+)]
+pub async fn put_corr_matrix(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Path(id): Path<String>,
+) -> Result<(NUsed, Json<CorrMatrixOut>), ServiceError> {
+    let ds = dataset(&state, &tenant, &id)?;
+    let size = ds.table.n_rows * ds.table.columns.len();
+    let st = state.clone();
+    let out = state
+        .compute
+        .run(size, move |_| {
+            let frame = Frame::from_table(&ds.table);
+            let (names, series): (Vec<String>, Vec<Vec<f64>>) = frame
+                .columns()
+                .iter()
+                .filter_map(|c| match &c.data {
+                    ColumnData::Numeric(v) => Some((
+                        c.name.clone(),
+                        v.iter().map(|x| x.unwrap_or(f64::NAN)).collect(),
+                    )),
+                    ColumnData::Text(_) => None,
+                })
+                .unzip();
+            if series.is_empty() {
+                return Err(ServiceError::NoNumeric);
+            }
+            let (series, report) = resolve_series(series, MissingPolicy::Drop)?;
+            let mut moments = OnlineCorrMatrix::new();
+            series.into_iter().for_each(|s| moments.push_series(s));
+            let corr = DatasetCorr { names, moments };
+            let out = corr_out(&corr, Some(report));
+            st.datasets.set_corr(&id, corr);
+            Ok(out)
+        })
+        .await?;
+    Ok(out)
+}
+
+/// The correlation matrix stored for a dataset, including appended series
+/// and observations.
+///
+/// - **Errors**: `404` for an unknown dataset or one without a stored matrix
+#[utoipa::path(
+    get,
+    path = "/datasets/{id}/corr-matrix",
+    tag = "datasets",
+    summary = "Stored correlation matrix of a dataset",
+    params(("id" = String, Path, description = "Dataset id")),
+    responses(
+        (status = 200, description = "OK", body = CorrMatrixOut),
+        (status = 404, description = "Not Found", body = ErrorResponse)
+    )
+)]
+pub async fn get_corr_matrix(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Path(id): Path<String>,
+) -> Result<(NUsed, Json<CorrMatrixOut>), ServiceError> {
+    let corr = stored_corr(&state, &tenant, &id)?;
+    Ok(corr_out(&corr.lock().unwrap(), None))
+}
+
+/// Append a series to a dataset's stored correlation matrix as a new last
+/// row and column.
+///
+/// Only the new row/column is computed (`O(m·n)` for `m` series of `n`
+/// observations); the rest of the matrix is reused. The series lives in the
+/// stored matrix only, not in the dataset's table.
+///
+/// - **Errors**: `404` for an unknown dataset or one without a stored matrix,
+///   `422` naming `/name` (empty or already used) or `/values` (`null`s, or
+///   not one value per observation)
+#[utoipa::path(
+    post,
+    path = "/datasets/{id}/corr-matrix/series",
+    tag = "datasets",
+    summary = "Append a series to a stored correlation matrix",
+    params(("id" = String, Path, description = "Dataset id")),
+    request_body = CorrSeriesIn,
+    responses(
+        (status = 200, description = "OK", body = CorrMatrixOut),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn append_corr_series(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Path(id): Path<String>,
+    Valid(inp): Valid<CorrSeriesIn>,
+) -> Result<(NUsed, Json<CorrMatrixOut>), ServiceError> {
+    let corr = stored_corr(&state, &tenant, &id)?;
+    let size = inp.values.len() * corr.lock().unwrap().moments.size();
+    let out = state
+        .compute
+        .run(size, move |_| {
+            let mut corr = corr.lock().unwrap();
+            if corr.names.contains(&inp.name) {
+                return Err(invalid("/name", "already names a series of the matrix"));
+            }
+            let n = corr.moments.len();
+            if !corr.moments.is_empty() && inp.values.len() != n {
+                return Err(invalid(
+                    "/values",
+                    format!(
+                        "has {} values but the matrix has {n} observations",
+                        inp.values.len()
+                    ),
+                ));
+            }
+            corr.moments.push_series(inp.values);
+            corr.names.push(inp.name);
+            Ok(corr_out(&corr, None))
+        })
+        .await?;
+    Ok(out)
+}
+
+/// Append observations to every series of a dataset's stored correlation
+/// matrix.
+///
+/// Each row updates the stored co-moments in place (`O(m²)` per row for `m`
+/// series) instead of recomputing the matrix over all observations.
+///
+/// - **Errors**: `404` for an unknown dataset or one without a stored matrix,
+///   `422` naming `/rows/i` (`null`s, or not one value per series)
+#[utoipa::path(
+    post,
+    path = "/datasets/{id}/corr-matrix/rows",
+    tag = "datasets",
+    summary = "Append observations to a stored correlation matrix",
+    params(("id" = String, Path, description = "Dataset id")),
+    request_body = CorrRowsIn,
+    responses(
+        (status = 200, description = "OK", body = CorrMatrixOut),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn append_corr_rows(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Path(id): Path<String>,
+    Valid(inp): Valid<CorrRowsIn>,
+) -> Result<(NUsed, Json<CorrMatrixOut>), ServiceError> {
+    let corr = stored_corr(&state, &tenant, &id)?;
+    let m = corr.lock().unwrap().moments.size();
+    let out = state
+        .compute
+        .run(inp.rows.len() * m * m, move |_| {
+            let mut corr = corr.lock().unwrap();
+            let m = corr.moments.size();
+            if let Some(i) = inp.rows.iter().position(|r| r.len() != m) {
+                return Err(invalid(
+                    format!("/rows/{i}"),
+                    format!("has {} values for {m} series", inp.rows[i].len()),
+                ));
+            }
+            inp.rows.iter().for_each(|r| corr.moments.push_row(r));
+            Ok(corr_out(&corr, None))
+        })
+        .await?;
+    Ok(out)
+}
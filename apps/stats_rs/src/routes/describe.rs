@@ -1,96 +1,200 @@
 // ---------------- Describe (JSON & CSV) ----------------
 
 use crate::{
+    envelope::NUsed,
     error::ServiceError,
+    ingest::{CsvOptions, summarize_csv_stream},
+    missing::resolve,
     state::AppState,
     stats::prelude::*,
-    types::{DescribeInput, DescribeOutput},
+    types::{ApproxOut, CsvQuery, DescribeInput, DescribeOutput, DescribeQuery, ErrorResponse},
+    validate::invalid,
+};
+use axum::{
+    Json,
+    body::Body,
+    extract::{Query, State},
 };
-use axum::{Json, body::Bytes, extract::State};
 use std::sync::Arc;
 
+/// Modes listed by `robust=true` at most.
+const MAX_MODES: usize = 10;
+
 /// Compute simple descriptive stats for a JSON array of numbers.
 ///
-/// Validates input for emptiness and `NaN`/non-finite values.
-/// Returns `400 Bad Request` via [`ServiceError`] on invalid input.
+/// `null` entries are settled by the `missing` query option (default `drop`;
+/// `error` rejects them). Returns `400 Bad Request` via [`ServiceError`] when
+/// no values remain.
+///
+/// `robust=true` adds `min`, `max`, `quartiles`, `modes`, `mad` and a
+/// `trimmed_mean` (`trim` from each tail, default 0.1) for summary cards; the
+/// default response keeps just the four basic fields.
 ///
-/// - **Request**: [`DescribeInput`] (`application/json`)
-/// - **Response**: [`DescribeOutput`] (`200 OK`) or error (`400`)
+/// - **Request**: [`DescribeInput`] (`application/json`); query [`DescribeQuery`]
+/// - **Response**: [`DescribeOutput`] with `missing` (`200 OK`) or error (`400`;
+///   `422` naming `/trim` outside `[0, 0.5)`)
+#[utoipa::path(
+    post,
+    path = "/describe",
+    tag = "describe",
+    summary = "Compute stats for JSON array of numbers",
+    params(DescribeQuery),
+    responses(
+        (status = 200, description = "OK", body = DescribeOutput),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
 pub async fn describe(
     State(_state): State<Arc<AppState>>,
+    Query(q): Query<DescribeQuery>,
     Json(input): Json<DescribeInput>,
-) -> Result<Json<DescribeOutput>, ServiceError> {
-    let nums = input.0;
+) -> Result<(NUsed, Json<DescribeOutput>), ServiceError> {
+    let trim = q.trim.unwrap_or(0.1);
+    if !(0.0..0.5).contains(&trim) {
+        return Err(invalid("/trim", format!("must be in [0, 0.5), got {trim}")));
+    }
+    let r = resolve(input.0, q.missing.unwrap_or_default())?;
+    let nums = r.values;
     if nums.is_empty() {
         return Err(ServiceError::Empty);
     }
-    if nums.iter().any(|v| v.is_nan() || !v.is_finite()) {
-        return Err(ServiceError::NaN);
+
+    let mut out = basic(&nums);
+    if q.robust.unwrap_or(false) {
+        add_robust(&mut out, &nums, trim);
     }
+    out.missing = Some(r.report);
+    Ok((NUsed(out.count), Json(out)))
+}
 
-    let count = nums.len();
-    let mean = mean(&nums);
-    let median = median(&nums);
-    let std_dev = sample_std_dev(&nums, mean);
-    Ok(Json(DescribeOutput {
-        count,
+/// `count`, `mean`, `median` and `std_dev` of non-empty `nums`.
+fn basic(nums: &[f64]) -> DescribeOutput {
+    let mean = mean(nums);
+    DescribeOutput {
+        count: nums.len(),
         mean,
-        median,
-        std_dev,
-    }))
+        median: median(nums),
+        std_dev: sample_std_dev(nums, mean),
+        min: None,
+        max: None,
+        quartiles: None,
+        modes: None,
+        mad: None,
+        trimmed_mean: None,
+        schema: None,
+        missing: None,
+        sample: None,
+        approx: None,
+    }
+}
+
+/// Fill the `robust=true` fields of `out` from non-empty `nums`.
+fn add_robust(out: &mut DescribeOutput, nums: &[f64], trim: f64) {
+    let ascending = sorted(nums);
+    let (q1, q2, q3) = quartiles_sorted(&ascending);
+    let mut modes = mode(nums);
+    if modes.len() == nums.len() && nums.len() > 1 {
+        // every value is unique: no mode
+        modes.clear();
+    }
+    modes.truncate(MAX_MODES);
+    out.min = Some(ascending[0]);
+    out.max = Some(ascending[ascending.len() - 1]);
+    out.quartiles = Some([q1, q2, q3]);
+    out.modes = Some(modes);
+    out.mad = Some(mad_sorted(&ascending));
+    out.trimmed_mean = Some(trimmed_mean(nums, 1.0 - 2.0 * trim));
 }
 
 /// Compute descriptive stats from a raw CSV payload (`text/csv`).
 ///
-/// The parser scans all fields in all rows, collecting cells that parse as `f64`.
-/// Tries first with `has_headers=true`, then falls back to `false`.
+/// Collects every numeric cell of the selected columns (all by default) and
+/// echoes the inferred per-column schema so callers can verify parsing.
+///
+/// The body is parsed as it streams in (see [`summarize_csv_stream`]), so
+/// uploads may exceed the 25 MB buffered limit up to
+/// [`MAX_STREAM_BODY_BYTES`](crate::MAX_STREAM_BODY_BYTES). Count, mean and
+/// standard deviation are exact; the median is exact up to
+/// [`MAX_KEPT_VALUES`](crate::ingest::MAX_KEPT_VALUES) numeric cells and beyond that read from a seeded
+/// uniform sample of that many (`seed`), reported as `approx`. `sample=N`
+/// (with `seed`) limits the summary to a uniform sample of `N` rows and
+/// echoes it as `sample`.
 ///
-/// - **Request**: body `text/csv`
-/// - **Response**: [`DescribeOutput`] (`200 OK`)
-/// - **Errors**: `CsvParse` (malformed CSV), `NoNumeric` (no numeric cells)
+/// - **Request**: body `text/csv`; query [`CsvQuery`] (`columns`, `skip_rows`,
+///   `has_header`, `infer`, `delimiter`, `quote`, `decimal`, `sample`, `seed`)
+/// - **Response**: [`DescribeOutput`] with `schema` (`200 OK`)
+/// - **Errors**: `CsvParse` (malformed CSV), `NoNumeric` (no numeric cells),
+///   `InvalidInput` (unknown column or infer type), `TooLarge` (`413`)
+#[utoipa::path(
+    post,
+    path = "/describe-csv",
+    tag = "describe",
+    summary = "Compute stats for CSV body (text/csv), parsed as a stream (up to 1 GiB)",
+    request_body(content = String, content_type = "text/csv", description = "CSV upload"),
+    params(CsvQuery),
+    responses(
+        (status = 200, description = "OK", body = DescribeOutput),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 413, description = "Body exceeds the streaming limit", body = ErrorResponse)
+    )
+)]
 pub async fn describe_csv(
-    State(_state): State<Arc<AppState>>,
-    body: Bytes,
-) -> Result<Json<DescribeOutput>, ServiceError> {
-    let nums = parse_csv_numbers(&body).map_err(|_| ServiceError::CsvParse)?;
-    if nums.is_empty() {
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<CsvQuery>,
+    body: Body,
+) -> Result<(NUsed, Json<DescribeOutput>), ServiceError> {
+    let opts = CsvOptions::from_query(&q, state.config.seed)?;
+    let seed = opts.seed;
+    let s = summarize_csv_stream(body.into_data_stream(), opts).await?;
+    if s.values.is_empty() {
         return Err(ServiceError::NoNumeric);
     }
 
-    let count = nums.len();
-    let mean = mean(&nums);
-    let median = median(&nums);
-    let std_dev = sample_std_dev(&nums, mean);
-    Ok(Json(DescribeOutput {
-        count,
-        mean,
-        median,
-        std_dev,
-    }))
+    let count = s.moments.count() as usize;
+    let values = s.values;
+    let (median, approx) = state
+        .compute
+        .run(values.len(), move |_| {
+            let sketch = QuantileSketch::from_sample(values, count, seed);
+            let approx = (!sketch.is_exact()).then(|| ApproxOut::of(&sketch));
+            Ok((median_sorted(sketch.sorted()), approx))
+        })
+        .await?;
+    Ok((
+        NUsed(count),
+        Json(DescribeOutput {
+            count,
+            mean: s.moments.mean(),
+            median,
+            std_dev: s.moments.sample_std(),
+            min: None,
+            max: None,
+            quartiles: None,
+            modes: None,
+            mad: None,
+            trimmed_mean: None,
+            schema: Some(s.schema),
+            missing: None,
+            sample: s.sample,
+            approx,
+        }),
+    ))
 }
 
-/// Parse all numeric cells from a CSV byte buffer.
-fn parse_csv_numbers(bytes: &Bytes) -> Result<Vec<f64>, csv::Error> {
-    let try_once = |has_headers: bool| -> Result<Vec<f64>, csv::Error> {
-        let mut rdr = csv::ReaderBuilder::new()
-            .has_headers(has_headers)
-            .flexible(true)
-            .from_reader(bytes.as_ref());
-        let mut v = Vec::new();
-        for result in rdr.records() {
-            let rec = result?;
-            for field in rec.iter() {
-                if let Ok(x) = field.trim().parse::<f64>() {
-                    v.push(x);
-                }
-            }
-        }
-        Ok(v)
-    };
-
-    let mut out = try_once(true)?;
-    if out.is_empty() {
-        out = try_once(false)?;
+/// Describe the numeric cells of an ingested table, echoing its schema.
+#[cfg(feature = "xlsx")]
+pub(crate) fn describe_table(
+    table: &crate::ingest::CsvTable,
+) -> Result<DescribeOutput, ServiceError> {
+    let nums = table.numeric_cells();
+    if nums.is_empty() {
+        return Err(ServiceError::NoNumeric);
     }
-    Ok(out)
+
+    Ok(DescribeOutput {
+        schema: Some(table.schema()),
+        sample: table.sample,
+        ..basic(&nums)
+    })
 }
@@ -0,0 +1,274 @@
+//! CSV/TSV renderings of tabular results.
+//!
+//! Endpoints whose output is a table (summary, histogram, ECDF, correlation
+//! matrix) take an [`OutputFormat`] extractor and return [`Tabular`]; JSON
+//! stays the default. The format comes from `?format=json|csv|tsv` or, when
+//! that is absent, from the first of `text/csv`, `text/tab-separated-values`
+//! or `application/json` listed in `Accept`.
+
+use crate::{
+    error::ServiceError,
+    types::{CorrMatrixOut, DistOut, EcdfOut, SummaryOut},
+};
+use axum::{
+    Json,
+    extract::{FromRequestParts, Query},
+    http::{HeaderValue, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+/// Response encoding negotiated for a tabular endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Csv,
+    Tsv,
+}
+
+/// `?format=` override for tabular endpoints.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FormatQuery {
+    /// Response format: `json`, `csv` or `tsv` (or send `Accept: text/csv`)
+    #[param(pattern = "^(json|csv|tsv)$")]
+    pub format: Option<String>,
+}
+
+impl OutputFormat {
+    fn parse(s: &str) -> Result<Self, ServiceError> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            other => Err(ServiceError::InvalidInput(format!(
+                "unknown format '{other}' (expected json, csv or tsv)"
+            ))),
+        }
+    }
+
+    fn from_accept(accept: &str) -> Self {
+        let types = accept
+            .split(',')
+            .map(|t| t.split(';').next().unwrap_or("").trim());
+        for t in types {
+            match t {
+                "text/csv" => return Self::Csv,
+                "text/tab-separated-values" => return Self::Tsv,
+                "application/json" => return Self::Json,
+                _ => {}
+            }
+        }
+        Self::Json
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for OutputFormat {
+    type Rejection = ServiceError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, ServiceError> {
+        let q = Query::<FormatQuery>::try_from_uri(&parts.uri)
+            .map_err(|e| ServiceError::InvalidInput(e.body_text()))?;
+        if let Some(f) = q.0.format {
+            return Self::parse(&f);
+        }
+        Ok(parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .map_or(Self::Json, Self::from_accept))
+    }
+}
+
+/// A result that can be laid out as rows under a header.
+pub trait Table {
+    fn header(&self) -> Vec<String>;
+    fn rows(&self) -> Vec<Vec<String>>;
+}
+
+fn cell(x: Option<f64>) -> String {
+    x.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Two columns, `stat,value`; undefined metrics are empty cells and
+/// requested percentiles follow as `p5`, `p95`, ...
+impl Table for SummaryOut {
+    fn header(&self) -> Vec<String> {
+        vec!["stat".into(), "value".into()]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        [
+            ("count", Some(self.count as f64)),
+            ("mean", self.mean),
+            ("median", self.median),
+            ("std", self.std),
+            ("min", self.min),
+            ("max", self.max),
+            ("iqr", self.iqr),
+            ("mad", self.mad),
+            ("cv", self.cv),
+            ("sem", self.sem),
+            ("skewness", self.skewness),
+            ("excess_kurtosis", self.excess_kurtosis),
+        ]
+        .into_iter()
+        .map(|(k, v)| vec![k.to_string(), cell(v)])
+        .chain(
+            self.percentiles
+                .iter()
+                .flatten()
+                .map(|&(p, v)| vec![format!("p{p}"), cell(Some(v).filter(|v| !v.is_nan()))]),
+        )
+        .collect()
+    }
+}
+
+/// One row per histogram bin: `lower,upper,count`.
+impl Table for DistOut {
+    fn header(&self) -> Vec<String> {
+        let mut h = vec!["lower".into(), "upper".into(), "count".into()];
+        if self.weights.is_some() {
+            h.push("weight".into());
+        }
+        h
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.counts
+            .iter()
+            .zip(self.edges.windows(2))
+            .enumerate()
+            .map(|(i, (c, e))| {
+                let mut row = vec![e[0].to_string(), e[1].to_string(), c.to_string()];
+                if let Some(ws) = &self.weights {
+                    row.push(ws[i].to_string());
+                }
+                row
+            })
+            .collect()
+    }
+}
+
+impl Table for EcdfOut {
+    fn header(&self) -> Vec<String> {
+        vec!["x".into(), "p".into()]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.xs
+            .iter()
+            .zip(&self.ps)
+            .map(|(x, p)| vec![x.to_string(), p.to_string()])
+            .collect()
+    }
+}
+
+/// Square matrix with names down the first column and across the header
+/// (`series_{i}` when unnamed).
+impl Table for CorrMatrixOut {
+    fn header(&self) -> Vec<String> {
+        std::iter::once(String::new())
+            .chain(self.labels())
+            .collect()
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.labels()
+            .zip(self.matrix.chunks(self.size.max(1)))
+            .map(|(name, row)| {
+                std::iter::once(name)
+                    .chain(row.iter().map(f64::to_string))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl CorrMatrixOut {
+    fn labels(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.size).map(|i| {
+            self.names
+                .as_ref()
+                .and_then(|n| n.get(i).cloned())
+                .unwrap_or_else(|| format!("series_{i}"))
+        })
+    }
+}
+
+/// Delimited text for `t` (`b','` or `b'\t'`).
+pub fn to_delimited<T: Table>(t: &T, delimiter: u8) -> String {
+    let mut w = ::csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    // Writing into a Vec cannot fail
+    for row in std::iter::once(t.header()).chain(t.rows()) {
+        w.write_record(row).expect("in-memory write");
+    }
+    let bytes = w.into_inner().expect("in-memory write");
+    String::from_utf8(bytes).expect("CSV of UTF-8 cells is UTF-8")
+}
+
+/// Handler output rendered as JSON, CSV or TSV per [`OutputFormat`].
+pub struct Tabular<T>(pub OutputFormat, pub T);
+
+impl<T: Table + Serialize> IntoResponse for Tabular<T> {
+    fn into_response(self) -> Response {
+        let (delimiter, mime) = match self.0 {
+            OutputFormat::Json => return Json(self.1).into_response(),
+            OutputFormat::Csv => (b',', "text/csv; charset=utf-8"),
+            OutputFormat::Tsv => (b'\t', "text/tab-separated-values; charset=utf-8"),
+        };
+        (
+            [(header::CONTENT_TYPE, HeaderValue::from_static(mime))],
+            to_delimited(&self.1, delimiter),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_header_negotiation() {
+        assert_eq!(
+            OutputFormat::from_accept("text/csv, application/json"),
+            OutputFormat::Csv
+        );
+        assert_eq!(
+            OutputFormat::from_accept("text/tab-separated-values"),
+            OutputFormat::Tsv
+        );
+        assert_eq!(OutputFormat::from_accept("*/*"), OutputFormat::Json);
+        assert_eq!(OutputFormat::parse("TSV").unwrap(), OutputFormat::Tsv);
+        assert!(OutputFormat::parse("xml").is_err());
+    }
+
+    #[test]
+    fn corr_matrix_layout() {
+        let m = CorrMatrixOut {
+            size: 2,
+            names: Some(vec!["a,b".into()]),
+            matrix: vec![1.0, 0.5, 0.5, 1.0],
+            p_values: None,
+            n_used: None,
+            covariance: None,
+            partial: None,
+            order: None,
+            linkage: None,
+            missing: None,
+        };
+        assert_eq!(
+            to_delimited(&m, b','),
+            ",\"a,b\",series_1\n\"a,b\",1,0.5\nseries_1,0.5,1\n"
+        );
+        assert_eq!(
+            to_delimited(&m, b'\t').lines().nth(2),
+            Some("series_1\t0.5\t1")
+        );
+    }
+}
@@ -1,20 +1,45 @@
 // ---------------- Health / Ready ----------------
 
 use crate::state::AppState;
-use axum::extract::State;
-use std::sync::Arc;
+use axum::{extract::State, http::StatusCode};
+use std::sync::{Arc, atomic::Ordering};
 
 /// Liveness probe.
 ///
 /// Returns a static `"ok"` string. Useful for container health checks.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    summary = "Liveness probe",
+    responses(
+        (status = 200, description = "OK", body = String)
+    )
+)]
 pub async fn health() -> &'static str {
     "ok"
 }
 
 /// Readiness probe.
 ///
-/// Returns `"ready"` once the service is able to handle requests.
+/// Returns `"ready"` once the service is able to handle requests, and
+/// `503 "draining"` from the moment shutdown begins, so load balancers stop
+/// sending traffic while in-flight work finishes.
 /// In the future, this may check shared resources in [`AppState`].
-pub async fn ready(State(_state): State<Arc<AppState>>) -> &'static str {
-    "ready"
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    summary = "Readiness probe",
+    responses(
+        (status = 200, description = "OK", body = String),
+        (status = 503, description = "Shutting down", body = String)
+    )
+)]
+pub async fn ready(State(state): State<Arc<AppState>>) -> (StatusCode, &'static str) {
+    if state.draining.load(Ordering::Relaxed) {
+        (StatusCode::SERVICE_UNAVAILABLE, "draining")
+    } else {
+        (StatusCode::OK, "ready")
+    }
 }
@@ -0,0 +1,122 @@
+//! /ingest/*
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    ingest::{NdjsonDecoder, RecordSummary},
+    types::{ErrorResponse, NdjsonIngestOut},
+};
+#[cfg(feature = "fetch")]
+use crate::{
+    ingest::{CsvOptions, detect_format, fetch_any, read_table},
+    state::AppState,
+    tenant::{Tenant, check_dataset_quota},
+    types::{DatasetOut, IngestUrlIn},
+};
+use axum::{Json, body::Body};
+#[cfg(feature = "fetch")]
+use axum::{extract::State, http::StatusCode};
+use futures_util::StreamExt;
+#[cfg(feature = "fetch")]
+use std::sync::Arc;
+
+/// Ingest newline-delimited JSON records and summarize each field.
+///
+/// The body is decoded chunk by chunk as it arrives (and may be compressed),
+/// so clients can pipe long record streams up to the wire body limit; only
+/// per-field running statistics are kept, never the records themselves.
+///
+/// - **Request**: body `application/x-ndjson`, one JSON object per line
+/// - **Response**: [`NdjsonIngestOut`] (`200 OK`)
+/// - **Errors**: `InvalidInput` with the offending line number
+#[utoipa::path(
+    post,
+    path = "/ingest/ndjson",
+    tag = "ingest",
+    summary = "Stream newline-delimited JSON records and summarize each field",
+    request_body(content = String, content_type = "application/x-ndjson", description = "One JSON object per line"),
+    responses(
+        (status = 200, description = "OK", body = NdjsonIngestOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn ingest_ndjson(body: Body) -> Result<(NUsed, Json<NdjsonIngestOut>), ServiceError> {
+    let mut dec = NdjsonDecoder::new();
+    let mut summary = RecordSummary::new();
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        dec.push(&chunk?, |r| summary.push(&r))?;
+    }
+    dec.finish(|r| summary.push(&r))?;
+
+    let records = summary.records();
+    Ok((
+        NUsed(records as usize),
+        Json(NdjsonIngestOut {
+            records,
+            fields: summary.fields(),
+        }),
+    ))
+}
+
+/// Download a CSV/Parquet file from an allowlisted URL and register it as a dataset.
+///
+/// Avoids a second upload when the data already sits in object storage or on
+/// an internal file server. With feature `s3`, `s3://bucket/key` URIs are read
+/// using the configured object-storage credentials. The URL (and every redirect) must match
+/// `STATS_URL_ALLOWLIST`; size and time are capped by `STATS_URL_MAX_BYTES`
+/// and `STATS_URL_TIMEOUT_SECS`.
+///
+/// - **Request**: [`IngestUrlIn`]
+/// - **Response**: [`DatasetOut`] (`201 Created`)
+/// - **Errors**: `Forbidden` (`403`), `TooLarge` (`413`), `Upstream` (`502`),
+///   `CsvParse`/`InvalidInput` (`400`), `QuotaExceeded` (`429`) when the
+///   tenant holds as many datasets or bytes as it may
+#[cfg(feature = "fetch")]
+#[utoipa::path(
+    post,
+    path = "/ingest/url",
+    tag = "ingest",
+    summary = "Download an allowlisted CSV/Parquet URL and register it as a dataset",
+    responses(
+        (status = 201, description = "Created", body = DatasetOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 403, description = "URL not allowlisted", body = ErrorResponse),
+        (status = 413, description = "Download too large", body = ErrorResponse),
+        (status = 429, description = "Tenant dataset quota reached", body = ErrorResponse),
+        (status = 502, description = "Upstream fetch failed", body = ErrorResponse)
+    )
+)]
+pub async fn ingest_url(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Json(inp): Json<IngestUrlIn>,
+) -> Result<(StatusCode, NUsed, Json<DatasetOut>), ServiceError> {
+    let opts = CsvOptions::from_query(&inp.options, state.config.seed)?;
+    check_dataset_quota(&state, &tenant, 0)?;
+    let fetched = fetch_any(&state.config, &inp.url).await?;
+    let format = inp
+        .format
+        .unwrap_or_else(|| detect_format(fetched.url.path(), fetched.content_type.as_deref()));
+    let bytes = fetched.body.len();
+    check_dataset_quota(&state, &tenant, bytes)?;
+    let table = read_table(format, fetched.body, &opts)?;
+
+    let name = inp.name.unwrap_or_else(|| {
+        fetched
+            .url
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .filter(|s| !s.is_empty())
+            .unwrap_or(fetched.url.as_str())
+            .to_string()
+    });
+    let ds = state
+        .datasets
+        .insert(&tenant, name, fetched.url.to_string(), format, bytes, table);
+    Ok((
+        StatusCode::CREATED,
+        NUsed(ds.table.n_rows),
+        Json(ds.as_ref().into()),
+    ))
+}
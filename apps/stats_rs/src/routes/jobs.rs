@@ -0,0 +1,376 @@
+//! /jobs/*
+
+use crate::{
+    cache::CacheKey,
+    error::ServiceError,
+    jobs::{Job, Progress, Submission},
+    missing::{pairwise_report, resolve, resolve_series},
+    routes::artifacts,
+    routes::stats_corr_matrix::{
+        cluster_variables, correlation_matrix, covariance_matrix_pairwise, p_value_matrix,
+        pairwise_complete_matrix,
+    },
+    state::AppState,
+    stats::prelude::*,
+    tenant::{self, Tenant},
+    types::{
+        BootstrapIn, BootstrapOut, BootstrapStatistic, CorrMatrixIn, CorrMatrixOut, CorrMethod,
+        ErrorResponse, JobIn, JobOut, JobResult, JobStatus, MissingPolicy, MissingReport,
+        PermutationIn, PermutationOut,
+    },
+    validate::Validate,
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use rand::{SeedableRng, rngs::StdRng};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Upper bound on `resamples` / `permutations`.
+const MAX_DRAWS: usize = 1_000_000;
+
+/// Longest accepted `Idempotency-Key`.
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Request header naming a retry-safe submission.
+const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Response header set when a submission returns an existing job.
+const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+type Work = Box<dyn FnOnce(&Progress) -> Result<Value, String> + Send>;
+
+fn to_json<T: Serialize>(out: T) -> Result<Value, String> {
+    serde_json::to_value(out).map_err(|e| e.to_string())
+}
+
+fn nan_none(x: f64) -> Option<f64> {
+    (!x.is_nan()).then_some(x)
+}
+
+fn draws(n: Option<usize>, field: &str) -> Result<usize, ServiceError> {
+    match n.unwrap_or(1000) {
+        n @ 1..=MAX_DRAWS => Ok(n),
+        n => Err(ServiceError::InvalidInput(format!(
+            "{field} must be in 1..={MAX_DRAWS}, got {n}"
+        ))),
+    }
+}
+
+/// The `Idempotency-Key` header, if sent: 1 to
+/// [`MAX_IDEMPOTENCY_KEY_LEN`] visible ASCII characters.
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, ServiceError> {
+    let Some(v) = headers.get(IDEMPOTENCY_KEY) else {
+        return Ok(None);
+    };
+    match v.to_str() {
+        Ok(k)
+            if (1..=MAX_IDEMPOTENCY_KEY_LEN).contains(&k.len())
+                && k.bytes().all(|b| b.is_ascii_graphic()) =>
+        {
+            Ok(Some(k.to_string()))
+        }
+        _ => Err(ServiceError::InvalidInput(format!(
+            "Idempotency-Key must be 1 to {MAX_IDEMPOTENCY_KEY_LEN} visible ASCII characters"
+        ))),
+    }
+}
+
+fn std_dev(xs: &[f64]) -> f64 {
+    sample_std_dev(xs, mean(xs))
+}
+
+impl BootstrapStatistic {
+    /// The kernel computing this statistic of a sample.
+    pub fn kernel(self) -> fn(&[f64]) -> f64 {
+        match self {
+            BootstrapStatistic::Mean => mean,
+            BootstrapStatistic::Median => median,
+            BootstrapStatistic::Std => std_dev,
+        }
+    }
+}
+
+fn bootstrap(inp: BootstrapIn, seed: u64) -> Result<Work, ServiceError> {
+    let r = resolve(inp.values, inp.missing.unwrap_or_default())?;
+    let resamples = draws(inp.resamples, "resamples")?;
+    let confidence = inp.confidence.unwrap_or(0.95);
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err(ServiceError::InvalidInput(
+            "confidence must be in (0, 1)".into(),
+        ));
+    }
+    let statistic = inp.statistic.unwrap_or_default();
+    Ok(Box::new(move |p| {
+        let stat = statistic.kernel();
+        let xs = r.values;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (lower, upper) = bootstrap_ci(&xs, stat, resamples, confidence, &mut rng, |b| {
+            p.set(b, resamples)
+        });
+        to_json(BootstrapOut {
+            statistic,
+            estimate: (!xs.is_empty()).then(|| stat(&xs)).and_then(nan_none),
+            lower: nan_none(lower),
+            upper: nan_none(upper),
+            confidence,
+            resamples,
+            seed,
+            missing: Some(r.report),
+        })
+    }))
+}
+
+fn permutation(inp: PermutationIn, seed: u64) -> Result<Work, ServiceError> {
+    let policy = inp.missing.unwrap_or_default();
+    let (x, y) = (resolve(inp.x, policy)?, resolve(inp.y, policy)?);
+    let permutations = draws(inp.permutations, "permutations")?;
+    let missing = MissingReport {
+        policy,
+        count: x.report.count + y.report.count,
+    };
+    Ok(Box::new(move |p| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (diff, p_value) =
+            permutation_test_mean_diff(&x.values, &y.values, permutations, &mut rng, |b| {
+                p.set(b, permutations)
+            });
+        to_json(PermutationOut {
+            mean_diff: nan_none(diff),
+            p_value: nan_none(p_value),
+            permutations,
+            seed,
+            missing: Some(missing),
+        })
+    }))
+}
+
+fn corr_matrix(inp: CorrMatrixIn) -> Result<Work, ServiceError> {
+    let policy = inp.missing.unwrap_or_default();
+    let pairwise = policy == MissingPolicy::Pairwise;
+    let (series, report) = match pairwise {
+        true => {
+            let report = pairwise_report(&inp.series);
+            (inp.series, report)
+        }
+        false => resolve_series(inp.series, policy)?,
+    };
+    let method = inp.method.unwrap_or(CorrMethod::Pearson);
+    let p_values = inp.p_values.unwrap_or(false);
+    let cluster = inp.cluster;
+    let (covariance, partial) = (inp.covariance, inp.partial);
+    let names = inp.names;
+    Ok(Box::new(move |p| {
+        let m = series.len();
+        let (matrix, n_used) = match pairwise {
+            true => {
+                let (matrix, n_used) = pairwise_complete_matrix(&series, method, |i| p.set(i, m));
+                (matrix, Some(n_used))
+            }
+            false => (correlation_matrix(&series, method, |i| p.set(i, m)), None),
+        };
+        let n_used = match m {
+            0 => None,
+            _ => n_used.or_else(|| p_values.then(|| vec![series[0].len(); m * m])),
+        };
+        let (order, linkage) = match cluster.filter(|_| m > 0) {
+            Some(linkage) => {
+                let (order, steps) = cluster_variables(&matrix, m, linkage);
+                (Some(order), Some(steps))
+            }
+            None => (None, None),
+        };
+        to_json(CorrMatrixOut {
+            size: m,
+            names: if m == 0 { None } else { names },
+            p_values: n_used
+                .as_deref()
+                .filter(|_| p_values)
+                .map(|n| p_value_matrix(method, &matrix, n)),
+            covariance: covariance
+                .filter(|_| m > 0)
+                .unwrap_or(false)
+                .then(|| covariance_matrix_pairwise(&series)),
+            partial: partial
+                .filter(|_| m > 0)
+                .unwrap_or(false)
+                .then(|| partial_correlation_matrix(&matrix, m)),
+            matrix,
+            n_used,
+            order,
+            linkage,
+            missing: Some(report),
+        })
+    }))
+}
+
+/// Submit a long-running analysis for background execution.
+///
+/// - **Request**: [`JobIn`], tagged by `kind` (`bootstrap`, `permutation`,
+///   `corr_matrix`)
+/// - **Response**: [`JobOut`] (`202 Accepted`) with `Location: /api/v1/jobs/{id}`
+/// - **Retries**: with an `Idempotency-Key` header, resubmitting the same
+///   request returns the original job (marked `Idempotent-Replayed: true`)
+///   instead of queueing another, for as long as the job is kept
+/// - **Webhooks**: with a `Webhook-Url` header (feature `webhooks`), the
+///   final status and result are POSTed there, signed, once the job finishes
+/// - **Errors**: `InvalidInput` for bad parameters, `Validation` (`422`) for
+///   `corr_matrix` series breaking the `/stats/corr-matrix` constraints, and
+///   `NaN` (with `missing=error`) are reported here, before the job is queued;
+///   `Conflict` (`409`) when the key was used for a different request;
+///   `Forbidden` (`403`) for a `Webhook-Url` outside `STATS_WEBHOOK_ALLOWLIST`;
+///   `QuotaExceeded` (`429`) when the tenant already has `tenants.max_jobs`
+///   jobs queued or running
+#[utoipa::path(
+    post,
+    path = "/jobs",
+    tag = "jobs",
+    summary = "Submit a bootstrap, permutation or correlation-matrix job",
+    params(
+        ("Idempotency-Key" = Option<String>, Header,
+         description = "Client-chosen key making retries return the original job"),
+        ("Webhook-Url" = Option<String>, Header,
+         description = "URL notified with the signed final status (feature `webhooks`)")
+    ),
+    responses(
+        (status = 202, description = "Accepted; Location names the job", body = JobOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 403, description = "Webhook-Url not allowlisted", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key reused for a different request", body = ErrorResponse),
+        (status = 422, description = "Invalid corr_matrix series", body = ErrorResponse),
+        (status = 429, description = "Tenant job quota reached", body = ErrorResponse)
+    )
+)]
+pub async fn submit_job(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    headers: HeaderMap,
+    Json(inp): Json<JobIn>,
+) -> Result<Response, ServiceError> {
+    let key = idempotency_key(&headers)?;
+    #[cfg(feature = "webhooks")]
+    let webhook = crate::webhooks::requested(&headers, &state.config.webhooks)?;
+    let fingerprint = match &key {
+        Some(_) => Some(CacheKey::new(
+            "job",
+            &serde_json::to_string(&inp).map_err(|e| ServiceError::Internal(e.to_string()))?,
+        )),
+        None => None,
+    };
+    let kind = inp.kind();
+    let work = match inp {
+        JobIn::Bootstrap(b) => {
+            let seed = state.config.seed_for(b.seed);
+            bootstrap(b, seed)?
+        }
+        JobIn::Permutation(p) => {
+            let seed = state.config.seed_for(p.seed);
+            permutation(p, seed)?
+        }
+        JobIn::CorrMatrix(c) => {
+            c.validate(&state.config)?;
+            corr_matrix(c)?
+        }
+    };
+    tenant::check_job_quota(&state, &tenant)?;
+    let (job, replayed) = match key.zip(fingerprint) {
+        None => (state.jobs.submit(&tenant, kind, work), false),
+        Some((key, fp)) => match state.jobs.submit_idempotent(&tenant, &key, fp, kind, work) {
+            Submission::Created(job) => (job, false),
+            Submission::Replayed(job) => (job, true),
+            Submission::KeyReused => {
+                return Err(ServiceError::Conflict(format!(
+                    "Idempotency-Key '{key}' was already used for a different request"
+                )));
+            }
+        },
+    };
+    #[cfg(feature = "webhooks")]
+    if let Some(url) = webhook.filter(|_| !replayed) {
+        crate::webhooks::watch(job.clone(), url, state.config.webhooks.clone());
+    }
+    let mut res = (
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, format!("/api/v1/jobs/{}", job.id))],
+        Json(job.status()),
+    )
+        .into_response();
+    if replayed {
+        res.headers_mut()
+            .insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    }
+    Ok(res)
+}
+
+fn find(state: &AppState, tenant: &Tenant, id: &str) -> Result<Arc<Job>, ServiceError> {
+    state
+        .jobs
+        .get(tenant, id)
+        .ok_or_else(|| ServiceError::NotFound(format!("job '{id}'")))
+}
+
+/// Status and progress of a job.
+///
+/// - **Errors**: `NotFound` (`404`) for an unknown id or another tenant's job
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    tag = "jobs",
+    summary = "Job status and progress",
+    responses(
+        (status = 200, description = "OK", body = JobOut),
+        (status = 404, description = "Not Found", body = ErrorResponse)
+    )
+)]
+pub async fn get_job(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Path(id): Path<String>,
+) -> Result<Json<JobOut>, ServiceError> {
+    Ok(Json(find(&state, &tenant, &id)?.status()))
+}
+
+/// Output of a succeeded job: [`BootstrapOut`], [`PermutationOut`] or
+/// [`CorrMatrixOut`] according to its kind.
+///
+/// Results moved to an artifact (`JobOut.artifact`) are read back from the
+/// artifact store, so this works for every succeeded job while its artifact
+/// is kept.
+///
+/// - **Errors**: `NotFound` (`404`) for an unknown id; `Conflict` (`409`) while
+///   the job is queued or running, or when it failed or was cancelled
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}/result",
+    tag = "jobs",
+    summary = "Output of a succeeded job",
+    responses(
+        (status = 200, description = "OK", body = JobResult),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 409, description = "Job queued, running, failed or cancelled", body = ErrorResponse)
+    )
+)]
+pub async fn get_job_result(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Path(id): Path<String>,
+) -> Result<Response, ServiceError> {
+    let job = find(&state, &tenant, &id)?;
+    if let Some(result) = job.result() {
+        return Ok(Json(result).into_response());
+    }
+    if let Some(artifact) = job.artifact() {
+        return artifacts::serve(&state, &tenant, &artifact).await;
+    }
+    let s = job.status();
+    Err(ServiceError::Conflict(match s.status {
+        JobStatus::Failed => format!("job '{id}' failed: {}", s.error.unwrap_or_default()),
+        JobStatus::Cancelled => format!("job '{id}' was cancelled"),
+        _ => format!("job '{id}' has not finished"),
+    }))
+}
@@ -1,33 +1,96 @@
 //! Route module aggregator: re-exports to preserve `routes::*` API.
 
+pub mod admin;
+pub mod artifacts;
+pub mod datasets;
 pub mod describe;
 pub mod docs;
+pub mod export;
 pub mod health;
+pub mod ingest;
+pub mod jobs;
+pub mod plots;
+pub mod profile;
 pub mod prom;
+pub mod report;
+pub mod schema_infer;
 pub mod schemas;
+pub mod stats_anomaly;
 pub mod stats_binrule;
+pub mod stats_compare;
+pub mod stats_contingency;
 pub mod stats_corr_matrix;
 pub mod stats_distribution;
+pub mod stats_drift;
 pub mod stats_ecdf;
+pub mod stats_entropy;
+pub mod stats_generate;
 pub mod stats_normalize;
 pub mod stats_outliers;
 pub mod stats_pairwise;
 pub mod stats_qq;
+#[cfg(feature = "rag")]
+pub mod stats_rag;
+pub mod stats_resample;
+pub mod stats_seasonality;
+pub mod stats_simulate;
 pub mod stats_summary;
+pub mod stats_tests;
+pub mod stats_ttest;
+pub mod stats_vector;
+#[cfg(feature = "ws")]
+pub mod ws;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
 
 // Re-exports (public surface preserved)
+pub use artifacts::get_artifact;
+pub use datasets::{
+    append_corr_rows, append_corr_series, column_distribution, delete_dataset, get_corr_matrix,
+    get_dataset, list_datasets, put_corr_matrix,
+};
 pub use describe::{describe, describe_csv};
 pub use docs::{docs_ui, swagger_ui};
 pub use health::{health, ready};
+pub use ingest::ingest_ndjson;
+#[cfg(feature = "fetch")]
+pub use ingest::ingest_url;
+pub use jobs::{get_job, get_job_result, submit_job};
+pub use plots::plot_spec;
+pub use profile::profile;
 pub use prom::prom_metrics;
-pub use schemas::{openapi, schema_describe_input, schema_describe_output};
+pub use report::report;
+pub use schema_infer::schema_infer;
+pub use schemas::{ApiDoc, openapi, schema_describe_input, schema_describe_output};
 
+pub use stats_anomaly::stats_anomaly_score;
 pub use stats_binrule::stats_binrule;
+pub use stats_compare::stats_compare;
+pub use stats_contingency::stats_contingency;
 pub use stats_corr_matrix::stats_corr_matrix;
 pub use stats_distribution::stats_distribution;
+pub use stats_drift::stats_drift;
 pub use stats_ecdf::stats_ecdf;
-pub use stats_normalize::stats_normalize;
+pub use stats_entropy::stats_entropy;
+pub use stats_generate::stats_generate;
+pub use stats_normalize::{stats_normalize, stats_normalize_apply, stats_normalize_inverse};
 pub use stats_outliers::stats_outliers;
 pub use stats_pairwise::stats_pairwise;
 pub use stats_qq::stats_qq_normal;
+#[cfg(feature = "rag")]
+pub use stats_rag::{
+    stats_rag_groundedness, stats_rag_metrics, stats_rag_mmr, stats_rag_text_metrics,
+};
+pub use stats_resample::stats_resample;
+pub use stats_seasonality::stats_seasonality;
+pub use stats_simulate::stats_simulate;
 pub use stats_summary::stats_summary;
+pub use stats_tests::{stats_tests, stats_tests_recommend};
+pub use stats_ttest::stats_ttest;
+pub use stats_vector::{
+    stats_intrinsic_dim, stats_knn_distances, stats_near_duplicates, stats_similarity,
+};
+#[cfg(feature = "ws")]
+pub use ws::ws_stats;
+#[cfg(feature = "xlsx")]
+pub use xlsx::{describe_xlsx, stats_summary_xlsx};
@@ -0,0 +1,300 @@
+//! /plots/spec
+//!
+//! Vega-Lite v5 specifications built from the same computations as the
+//! `/stats/*` routes, with the results embedded as inline `data.values`, so a
+//! client only hands the spec to a renderer. Every spec uses the service's
+//! field names (`count`, `x`, `p`, `r`, …) and fills its container width.
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::{kept, resolve, rows_kept},
+    routes::{
+        stats_corr_matrix::corr_matrix, stats_distribution::distribution, stats_ecdf::ecdf,
+        stats_outliers::outliers, stats_qq::qq_normal,
+    },
+    state::AppState,
+    stats::prelude::*,
+    types::{
+        CorrMatrixIn, CorrMatrixOut, DistOut, EcdfOut, ErrorResponse, MissingReport, OutliersIn,
+        PlotSpecIn, PlotSpecOut, QqOut,
+    },
+    validate::Valid,
+};
+use axum::{Json, extract::State};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const SCHEMA: &str = "https://vega.github.io/schema/vega-lite/v5.json";
+
+/// Common top level of every spec.
+fn spec(body: Value) -> Value {
+    let mut spec = json!({ "$schema": SCHEMA, "width": "container" });
+    if let (Some(spec), Value::Object(body)) = (spec.as_object_mut(), body) {
+        spec.extend(body);
+    }
+    spec
+}
+
+fn histogram(d: &DistOut) -> Value {
+    let heights: Vec<Value> = match &d.weights {
+        Some(ws) => ws.iter().map(|&w| json!(w)).collect(),
+        None => d.counts.iter().map(|&c| json!(c)).collect(),
+    };
+    let bars: Vec<Value> = heights
+        .iter()
+        .zip(d.edges.windows(2))
+        .map(|(c, e)| json!({ "bin_start": e[0], "bin_end": e[1], "count": c }))
+        .collect();
+    spec(json!({
+        "data": { "values": bars },
+        "mark": "bar",
+        "encoding": {
+            "x": { "field": "bin_start", "bin": { "binned": true }, "type": "quantitative", "title": "value" },
+            "x2": { "field": "bin_end" },
+            "y": { "field": "count", "type": "quantitative" }
+        }
+    }))
+}
+
+fn ecdf_line(e: &EcdfOut) -> Value {
+    let points: Vec<Value> =
+        e.xs.iter()
+            .zip(&e.ps)
+            .map(|(x, p)| json!({ "x": x, "p": p }))
+            .collect();
+    spec(json!({
+        "data": { "values": points },
+        "mark": { "type": "line", "interpolate": "step-after" },
+        "encoding": {
+            "x": { "field": "x", "type": "quantitative", "title": "value" },
+            "y": { "field": "p", "type": "quantitative", "title": "F(x)", "scale": { "domain": [0, 1] } }
+        }
+    }))
+}
+
+/// Horizontal box plot: Tukey whiskers (the most extreme values within 1.5
+/// IQR of the quartiles) and the fliers `/stats/outliers` reports for `inp`.
+fn boxplot(inp: OutliersIn) -> Result<(Value, Option<MissingReport>), ServiceError> {
+    let r = resolve(inp.values.clone(), inp.missing.unwrap_or_default())?;
+    let fliers = outliers(inp)?;
+    let boxes = match r.values.is_empty() {
+        true => vec![],
+        false => {
+            let (q1, median, q3) = quartiles(&r.values);
+            let fence = 1.5 * (q3 - q1);
+            let inside = r
+                .values
+                .iter()
+                .copied()
+                .filter(|x| (q1 - fence..=q3 + fence).contains(x));
+            let (lower, upper) = inside.fold((q1, q3), |(lo, hi), x| (lo.min(x), hi.max(x)));
+            vec![json!({ "lower": lower, "q1": q1, "median": median, "q3": q3, "upper": upper })]
+        }
+    };
+    let points: Vec<Value> = fliers
+        .indices
+        .iter()
+        .zip(&fliers.values)
+        .map(|(i, x)| json!({ "index": i, "value": x }))
+        .collect();
+    let x = |field: &str| json!({ "field": field, "type": "quantitative", "title": "value" });
+    let spec = spec(json!({
+        "layer": [
+            {
+                "data": { "values": boxes },
+                "layer": [
+                    { "mark": "rule", "encoding": { "x": x("lower"), "x2": { "field": "upper" } } },
+                    { "mark": { "type": "bar", "size": 20 }, "encoding": { "x": x("q1"), "x2": { "field": "q3" } } },
+                    { "mark": { "type": "tick", "color": "white", "size": 20 }, "encoding": { "x": x("median") } }
+                ]
+            },
+            {
+                "data": { "values": points },
+                "mark": "point",
+                "encoding": { "x": x("value"), "tooltip": [{ "field": "index" }, { "field": "value" }] }
+            }
+        ]
+    }));
+    Ok((spec, fliers.missing))
+}
+
+fn qq_points(q: &QqOut) -> Value {
+    let points: Vec<Value> = q
+        .theoretical_quantiles
+        .iter()
+        .zip(&q.sample_quantiles)
+        .map(|(t, s)| json!({ "theoretical": t, "sample": s }))
+        .collect();
+    let ends = [
+        q.theoretical_quantiles.first(),
+        q.theoretical_quantiles.last(),
+    ];
+    let line: Vec<Value> = ends
+        .into_iter()
+        .flatten()
+        .map(|t| json!({ "theoretical": t, "sample": t }))
+        .collect();
+    let enc = json!({
+        "x": { "field": "theoretical", "type": "quantitative", "title": "normal quantile" },
+        "y": { "field": "sample", "type": "quantitative", "title": "sample quantile" }
+    });
+    spec(json!({
+        "layer": [
+            { "data": { "values": points }, "mark": "point", "encoding": enc },
+            { "data": { "values": line }, "mark": { "type": "line", "color": "gray" }, "encoding": enc }
+        ]
+    }))
+}
+
+fn heatmap(c: &CorrMatrixOut) -> Value {
+    let m = c.size;
+    let names: Vec<String> = match &c.names {
+        Some(names) => names.clone(),
+        None => (0..m).map(|i| format!("s{i}")).collect(),
+    };
+    let cells: Vec<Value> = (0..m * m)
+        .map(|k| json!({ "row": names[k / m], "col": names[k % m], "r": c.matrix[k] }))
+        .collect();
+    let order = match &c.order {
+        Some(order) => json!(order.iter().map(|&i| &names[i]).collect::<Vec<_>>()),
+        None => json!(names),
+    };
+    spec(json!({
+        "data": { "values": cells },
+        "encoding": {
+            "x": { "field": "col", "type": "nominal", "sort": order, "title": null },
+            "y": { "field": "row", "type": "nominal", "sort": order, "title": null }
+        },
+        "layer": [
+            {
+                "mark": "rect",
+                "encoding": {
+                    "color": {
+                        "field": "r",
+                        "type": "quantitative",
+                        "scale": { "domain": [-1, 1], "scheme": "redblue" }
+                    }
+                }
+            },
+            {
+                "mark": "text",
+                "encoding": { "text": { "field": "r", "type": "quantitative", "format": ".2f" } }
+            }
+        ]
+    }))
+}
+
+/// Ready-to-render Vega-Lite spec for a histogram, ECDF, box plot, QQ plot or
+/// correlation heatmap.
+///
+/// - The body is the input of the matching `/stats/*` route plus a `kind`
+///   (`histogram` → distribution, `ecdf`, `boxplot` → outliers, `qq` →
+///   qq-normal, `corr_heatmap` → corr-matrix), validated the same way
+/// - The computed values are embedded in the spec, so use `window` /
+///   `max_points` to keep ECDF and QQ specs small
+/// - Box plots draw Tukey whiskers; the fliers follow the request's outlier
+///   `method`, `threshold` and `top_k`
+/// - Heatmap axes follow the clustering order when `cluster` is set
+#[utoipa::path(
+    post,
+    path = "/plots/spec",
+    tag = "plots",
+    summary = "Vega-Lite spec with embedded data",
+    request_body = PlotSpecIn,
+    responses(
+        (status = 200, description = "OK", body = PlotSpecOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn plot_spec(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<PlotSpecIn>,
+) -> Result<(NUsed, Json<PlotSpecOut>), ServiceError> {
+    let kind = inp.kind();
+    let n = match &inp {
+        PlotSpecIn::Histogram(d) => kept(&d.values, d.missing.unwrap_or_default()),
+        PlotSpecIn::Ecdf(e) => kept(&e.values, e.missing.unwrap_or_default()),
+        PlotSpecIn::Boxplot(o) => kept(&o.values, o.missing.unwrap_or_default()),
+        PlotSpecIn::Qq(q) => kept(&q.values, q.missing.unwrap_or_default()),
+        PlotSpecIn::CorrHeatmap(c) => rows_kept(&c.series, c.missing.unwrap_or_default()),
+    };
+    let size = match &inp {
+        PlotSpecIn::Histogram(d) => d.values.len(),
+        PlotSpecIn::Ecdf(e) => e.values.len(),
+        PlotSpecIn::Boxplot(o) => o.values.len(),
+        PlotSpecIn::Qq(q) => q.values.len(),
+        PlotSpecIn::CorrHeatmap(CorrMatrixIn { series, .. }) => series.iter().map(Vec::len).sum(),
+    };
+    let st = state.clone();
+    let (spec, missing) = state
+        .compute
+        .run(size, move |cancel| match inp {
+            PlotSpecIn::Histogram(d) => {
+                let seed = st.config.seed_for(d.seed);
+                distribution(d, seed, cancel).map(|d| (histogram(&d), d.missing))
+            }
+            PlotSpecIn::Ecdf(e) => ecdf(&st, e).map(|e| (ecdf_line(&e), e.missing)),
+            PlotSpecIn::Boxplot(o) => boxplot(o),
+            PlotSpecIn::Qq(q) => qq_normal(&st, q).map(|q| (qq_points(&q), q.missing)),
+            PlotSpecIn::CorrHeatmap(c) => {
+                corr_matrix(&st, c, cancel).map(|c| (heatmap(&c), c.missing))
+            }
+        })
+        .await?;
+    Ok((
+        NUsed(n),
+        Json(PlotSpecOut {
+            kind,
+            spec,
+            missing,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heatmap_cells_follow_the_matrix() {
+        let spec = heatmap(&CorrMatrixOut {
+            size: 2,
+            names: Some(vec!["a".into(), "b".into()]),
+            matrix: vec![1.0, -0.5, -0.5, 1.0],
+            p_values: None,
+            n_used: None,
+            covariance: None,
+            partial: None,
+            order: None,
+            linkage: None,
+            missing: None,
+        });
+        assert_eq!(spec["$schema"], SCHEMA);
+        let cells = spec["data"]["values"].as_array().unwrap();
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells[1], json!({ "row": "a", "col": "b", "r": -0.5 }));
+        assert_eq!(spec["encoding"]["x"]["sort"], json!(["a", "b"]));
+    }
+
+    #[test]
+    fn boxplot_whiskers_stop_at_the_fences() {
+        let (spec, _) = boxplot(OutliersIn {
+            values: vec![1.0, 2.0, 3.0, 4.0, 5.0, 100.0],
+            method: None,
+            threshold: None,
+            missing: None,
+            top_k: None,
+            point_scores: None,
+            clean: None,
+            groups: None,
+        })
+        .unwrap();
+        let b = &spec["layer"][0]["data"]["values"][0];
+        assert_eq!(b["lower"], 1.0);
+        assert_eq!(b["upper"], 5.0);
+        let fliers = &spec["layer"][1]["data"]["values"];
+        assert_eq!(fliers, &json!([{ "index": 5, "value": 100.0 }]));
+    }
+}
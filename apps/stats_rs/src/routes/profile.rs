@@ -0,0 +1,211 @@
+//! /profile
+
+use crate::{
+    cache::CacheKey,
+    envelope::NUsed,
+    error::ServiceError,
+    frame::{Frame, FrameColumn},
+    ingest::{CsvOptions, read_csv},
+    state::AppState,
+    stats::prelude::*,
+    types::{
+        ColumnProfile, ColumnType, CorrMatrixOut, CorrMethod, CsvQuery, ErrorResponse,
+        NumericProfile, ProfileOut, ProfileQuery, ProfileWarning, ProfileWarningKind, ValueCount,
+    },
+};
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Query, State},
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// `|skewness|` above which a numeric column is flagged.
+const SKEW_THRESHOLD: f64 = 2.0;
+/// Text columns with more distinct values than this (and mostly unique) are flagged.
+const HIGH_CARDINALITY_MIN: usize = 50;
+
+fn nan_none(x: f64) -> Option<f64> {
+    (!x.is_nan()).then_some(x)
+}
+
+fn numeric_profile(xs: &[f64], bins: usize) -> NumericProfile {
+    let m = mean(xs);
+    let (q1, median, q3) = quartiles(xs);
+    let (counts, edges) = histogram(xs, bins);
+    NumericProfile {
+        mean: m,
+        std_dev: nan_none(sample_std_dev(xs, m)),
+        min: min(xs),
+        q1,
+        median,
+        q3,
+        max: max(xs),
+        skewness: nan_none(skewness(xs)),
+        excess_kurtosis: nan_none(excess_kurtosis(xs)),
+        zeros: xs.iter().filter(|&&x| x == 0.0).count(),
+        counts,
+        edges,
+    }
+}
+
+/// Top values are counted on the typed cells, so `1.5` and `1.50` are one
+/// value in a numeric column.
+fn profile_column(col: &FrameColumn, bins: usize, top: usize) -> ColumnProfile {
+    let schema = col.schema();
+    let mut freq: HashMap<String, usize> = HashMap::new();
+    for c in (0..col.len())
+        .map(|i| col.text(i))
+        .filter(|c| !c.is_empty())
+    {
+        *freq.entry(c).or_default() += 1;
+    }
+    let distinct = freq.len();
+    let mut ranked: Vec<(String, usize)> = freq.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let numeric = col
+        .is_numeric()
+        .then(|| numeric_profile(&col.values(), bins));
+    let rows = col.len();
+    ColumnProfile {
+        null_rate: if rows == 0 {
+            0.0
+        } else {
+            schema.missing as f64 / rows as f64
+        },
+        schema,
+        distinct,
+        top: ranked
+            .into_iter()
+            .take(top)
+            .map(|(value, count)| ValueCount { value, count })
+            .collect(),
+        numeric,
+    }
+}
+
+fn warnings(p: &ColumnProfile) -> Vec<ProfileWarning> {
+    let s = &p.schema;
+    let warn = |kind, message: String| ProfileWarning {
+        column: s.name.clone(),
+        kind,
+        message,
+    };
+    let mut out = Vec::new();
+    if s.non_empty == 0 {
+        out.push(warn(
+            ProfileWarningKind::AllMissing,
+            "every value is missing".into(),
+        ));
+    } else if p.distinct == 1 {
+        out.push(warn(
+            ProfileWarningKind::Constant,
+            format!("constant value '{}'", p.top[0].value),
+        ));
+    }
+    if s.dtype == ColumnType::String
+        && p.distinct > HIGH_CARDINALITY_MIN
+        && p.distinct * 2 > s.non_empty
+    {
+        out.push(warn(
+            ProfileWarningKind::HighCardinality,
+            format!("{} distinct values in {} rows", p.distinct, s.non_empty),
+        ));
+    }
+    if let Some(sk) = p.numeric.as_ref().and_then(|n| n.skewness)
+        && sk.abs() > SKEW_THRESHOLD
+    {
+        out.push(warn(
+            ProfileWarningKind::HighSkew,
+            format!("skewness {sk:.2}"),
+        ));
+    }
+    out
+}
+
+/// Pearson matrix over numeric columns, each pair using the rows where both
+/// are numeric; undefined pairs are reported as `0.0`.
+fn correlations(frame: &Frame) -> CorrMatrixOut {
+    let (names, matrix) = frame.corr_matrix(CorrMethod::Pearson);
+    CorrMatrixOut {
+        size: names.len(),
+        names: Some(names),
+        matrix,
+        p_values: None,
+        n_used: None,
+        covariance: None,
+        partial: None,
+        order: None,
+        linkage: None,
+        missing: None,
+    }
+}
+
+/// Profile an uploaded CSV: per-column summaries, histograms, top values,
+/// missingness, numeric correlations and data-quality warnings.
+///
+/// Warnings flag columns that are entirely missing, constant, high-cardinality
+/// text (over 50 distinct values, mostly unique) or heavily skewed (`|skew| > 2`).
+///
+/// - **Request**: body `text/csv`; query [`CsvQuery`] plus [`ProfileQuery`]
+///   (`bins`, `top`)
+/// - **Response**: [`ProfileOut`] (`200 OK`)
+/// - **Errors**: `CsvParse`, `InvalidInput` (unknown column, bad option)
+///
+/// The parsed table and correlation matrix are cached under a hash of the body
+/// and CSV options, so re-profiling an upload (e.g. with other `bins`) only
+/// recomputes the per-column summaries.
+#[utoipa::path(
+    post,
+    path = "/profile",
+    tag = "tables",
+    summary = "Profile a CSV: column summaries, histograms, top values, missingness, correlations and warnings",
+    request_body(content = String, content_type = "text/csv", description = "CSV upload"),
+    params(CsvQuery, ProfileQuery),
+    responses(
+        (status = 200, description = "OK", body = ProfileOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn profile(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<CsvQuery>,
+    Query(p): Query<ProfileQuery>,
+    body: Bytes,
+) -> Result<(NUsed, Json<ProfileOut>), ServiceError> {
+    let content = (&body[..], &q);
+    let table = state
+        .cache
+        .get_or_try_insert_with(CacheKey::new("csv_table", &content), || {
+            read_csv(&body, &CsvOptions::from_query(&q, state.config.seed)?)
+        })?;
+    let frame = Frame::from_table(&table);
+    let bins = p.bins.unwrap_or(10).max(2);
+    let top = p.top.unwrap_or(5);
+
+    let columns: Vec<ColumnProfile> = frame
+        .columns()
+        .iter()
+        .map(|c| profile_column(c, bins, top))
+        .collect();
+
+    Ok((
+        NUsed(frame.n_rows()),
+        Json(ProfileOut {
+            n_rows: frame.n_rows(),
+            n_columns: columns.len(),
+            missing_rate: frame.missing_rate(),
+            correlations: CorrMatrixOut::clone(
+                &state
+                    .cache
+                    .get_or_insert_with(CacheKey::new("profile_corr", &content), || {
+                        correlations(&frame)
+                    }),
+            ),
+            warnings: columns.iter().flat_map(warnings).collect(),
+            columns,
+            sample: table.sample,
+        }),
+    ))
+}
@@ -0,0 +1,511 @@
+//! /report
+//!
+//! One-shot, shareable analysis document for a table: overview, summary
+//! statistics, Jarque–Bera normality verdicts, IQR outliers, histogram and
+//! ECDF data and the Pearson correlation heatmap. The content is assembled
+//! once as a list of [`Block`]s and rendered either as a self-contained HTML
+//! page (inline CSS, no scripts or external assets) or as Markdown.
+
+use crate::{
+    cache::CacheKey,
+    datasets::Dataset,
+    error::ServiceError,
+    frame::{ColumnData, Frame, FrameColumn},
+    ingest::{CsvOptions, CsvTable, read_csv},
+    routes::{artifacts, datasets},
+    state::AppState,
+    stats::prelude::*,
+    tenant::Tenant,
+    types::{ArtifactOut, CorrMethod, CsvQuery, ErrorResponse, ReportFormat, ReportQuery},
+    validate::Validate,
+};
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use std::{fmt::Write, sync::Arc};
+
+/// Significance level of the normality verdicts.
+const ALPHA: f64 = 0.05;
+/// Below this many values the normality test is not reported.
+const MIN_NORMALITY_N: usize = 8;
+/// Outlying rows listed per column.
+const MAX_LISTED_OUTLIERS: usize = 10;
+/// Probabilities at which the ECDF is tabulated.
+const ECDF_PROBS: [f64; 11] = [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0];
+
+/// Table a report is built from.
+enum Source {
+    Dataset(Arc<Dataset>),
+    Upload(Arc<CsvTable>),
+}
+
+impl Source {
+    fn table(&self) -> &CsvTable {
+        match self {
+            Source::Dataset(ds) => &ds.table,
+            Source::Upload(table) => table,
+        }
+    }
+}
+
+/// Renderer-neutral piece of a report.
+enum Block {
+    Heading(u8, String),
+    Text(String),
+    /// Header, rows and an optional per-cell shade in `[-1, 1]` (HTML only:
+    /// blue for positive, red for negative, stronger further from 0)
+    Table {
+        head: Vec<String>,
+        rows: Vec<Vec<String>>,
+        shade: Option<Vec<Vec<f64>>>,
+    },
+}
+
+fn table(head: &[&str], rows: Vec<Vec<String>>) -> Block {
+    Block::Table {
+        head: head.iter().map(|h| h.to_string()).collect(),
+        rows,
+        shade: None,
+    }
+}
+
+/// Compact number: at most 4 decimals, no trailing zeros, `—` for NaN.
+fn num(x: f64) -> String {
+    if x.is_nan() {
+        return "—".into();
+    }
+    let s = format!("{x:.4}");
+    match s.contains('.') {
+        true => s.trim_end_matches('0').trim_end_matches('.').into(),
+        false => s,
+    }
+}
+
+/// Rows of a numeric column with their 1-based row numbers.
+fn present(col: &FrameColumn) -> Vec<(usize, f64)> {
+    match &col.data {
+        ColumnData::Numeric(v) => v
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| x.map(|x| (i + 1, x)))
+            .collect(),
+        ColumnData::Text(_) => vec![],
+    }
+}
+
+fn build(title: String, frame: &Frame, bins: usize) -> (String, Vec<Block>) {
+    let mut out = vec![Block::Heading(2, "Overview".into())];
+    out.push(Block::Text(format!(
+        "{} rows × {} columns; {}% of cells are missing.",
+        frame.n_rows(),
+        frame.columns().len(),
+        num(100.0 * frame.missing_rate())
+    )));
+    out.push(table(
+        &["column", "type", "missing"],
+        frame
+            .columns()
+            .iter()
+            .map(|c| {
+                let s = c.schema();
+                vec![
+                    s.name,
+                    format!("{:?}", s.dtype).to_lowercase(),
+                    s.missing.to_string(),
+                ]
+            })
+            .collect(),
+    ));
+
+    let numeric: Vec<(&FrameColumn, Vec<(usize, f64)>)> = frame
+        .numeric_columns()
+        .map(|c| (c, present(c)))
+        .filter(|(_, rows)| !rows.is_empty())
+        .collect();
+    if numeric.is_empty() {
+        out.push(Block::Text("No numeric columns to analyse.".into()));
+        return (title, out);
+    }
+    let values: Vec<Vec<f64>> = numeric
+        .iter()
+        .map(|(_, rows)| rows.iter().map(|&(_, x)| x).collect())
+        .collect();
+    let names = || numeric.iter().map(|(c, _)| c.name.clone());
+
+    out.push(Block::Heading(2, "Summary statistics".into()));
+    out.push(table(
+        &[
+            "column",
+            "n",
+            "mean",
+            "std",
+            "min",
+            "q1",
+            "median",
+            "q3",
+            "max",
+            "skewness",
+            "excess kurtosis",
+        ],
+        names()
+            .zip(&values)
+            .map(|(name, xs)| {
+                let m = mean(xs);
+                let (q1, med, q3) = quartiles(xs);
+                let mut row = vec![name, xs.len().to_string()];
+                row.extend(
+                    [
+                        m,
+                        sample_std_dev(xs, m),
+                        min(xs),
+                        q1,
+                        med,
+                        q3,
+                        max(xs),
+                        skewness(xs),
+                        excess_kurtosis(xs),
+                    ]
+                    .map(num),
+                );
+                row
+            })
+            .collect(),
+    ));
+
+    out.push(Block::Heading(2, "Normality (Jarque–Bera)".into()));
+    out.push(table(
+        &["column", "JB", "p", "verdict"],
+        names()
+            .zip(&values)
+            .map(|(name, xs)| {
+                let (jb, p) = jarque_bera(xs);
+                let verdict = match p {
+                    _ if xs.len() < MIN_NORMALITY_N => "too few values",
+                    p if p.is_nan() => "constant",
+                    p if p < ALPHA => "not normal",
+                    _ => "consistent with normal",
+                };
+                vec![name, num(jb), num(p), verdict.into()]
+            })
+            .collect(),
+    ));
+
+    out.push(Block::Heading(2, "Outliers (1.5 × IQR)".into()));
+    out.push(table(
+        &["column", "count", "rows (row: value)"],
+        numeric
+            .iter()
+            .zip(&values)
+            .map(|((c, rows), xs)| {
+                let (q1, _, q3) = quartiles(xs);
+                let fence = 1.5 * (q3 - q1);
+                let flagged: Vec<&(usize, f64)> = rows
+                    .iter()
+                    .filter(|(_, x)| *x < q1 - fence || *x > q3 + fence)
+                    .collect();
+                let mut listed = flagged
+                    .iter()
+                    .take(MAX_LISTED_OUTLIERS)
+                    .map(|(row, x)| format!("{row}: {}", num(*x)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if flagged.len() > MAX_LISTED_OUTLIERS {
+                    listed.push_str(", …");
+                }
+                vec![c.name.clone(), flagged.len().to_string(), listed]
+            })
+            .collect(),
+    ));
+
+    out.push(Block::Heading(2, "Histograms".into()));
+    for (name, xs) in names().zip(&values) {
+        let (counts, edges) = histogram(xs, bins);
+        let top = counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+        out.push(Block::Heading(3, name));
+        out.push(Block::Table {
+            head: vec!["from".into(), "to".into(), "count".into()],
+            rows: counts
+                .iter()
+                .zip(edges.windows(2))
+                .map(|(c, e)| vec![num(e[0]), num(e[1]), c.to_string()])
+                .collect(),
+            shade: Some(
+                counts
+                    .iter()
+                    .map(|&c| vec![0.0, 0.0, c as f64 / top])
+                    .collect(),
+            ),
+        });
+    }
+
+    out.push(Block::Heading(2, "ECDF".into()));
+    out.push(Block::Text(
+        "Value below which each fraction of the column lies.".into(),
+    ));
+    let mut head = vec!["column".to_string()];
+    head.extend(ECDF_PROBS.iter().map(|p| format!("{}%", num(100.0 * p))));
+    out.push(Block::Table {
+        head,
+        rows: names()
+            .zip(&values)
+            .map(|(name, xs)| {
+                let ascending = sorted(xs);
+                let mut row = vec![name];
+                row.extend(
+                    ECDF_PROBS
+                        .iter()
+                        .map(|&p| num(quantile_sorted(&ascending, p))),
+                );
+                row
+            })
+            .collect(),
+        shade: None,
+    });
+
+    out.push(Block::Heading(2, "Correlations (Pearson)".into()));
+    let (names, matrix) = frame.corr_matrix(CorrMethod::Pearson);
+    let m = names.len();
+    let mut head = vec![String::new()];
+    head.extend(names.iter().cloned());
+    out.push(Block::Table {
+        head,
+        rows: names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let mut row = vec![name.clone()];
+                row.extend(
+                    matrix[i * m..(i + 1) * m]
+                        .iter()
+                        .map(|&r| format!("{r:.2}")),
+                );
+                row
+            })
+            .collect(),
+        shade: Some(
+            (0..m)
+                .map(|i| {
+                    let mut row = vec![0.0];
+                    row.extend_from_slice(&matrix[i * m..(i + 1) * m]);
+                    row
+                })
+                .collect(),
+        ),
+    });
+    (title, out)
+}
+
+fn markdown(title: &str, blocks: &[Block]) -> String {
+    let cell = |s: &str| s.replace('|', "\\|");
+    let mut md = format!("# {}\n", cell(title));
+    for b in blocks {
+        match b {
+            Block::Heading(level, text) => {
+                let _ = write!(md, "\n{} {}\n", "#".repeat(*level as usize), cell(text));
+            }
+            Block::Text(text) => {
+                let _ = write!(md, "\n{text}\n");
+            }
+            Block::Table { head, rows, .. } => {
+                let line = |cells: &[String]| {
+                    let cells: Vec<String> = cells.iter().map(|c| cell(c)).collect();
+                    format!("| {} |\n", cells.join(" | "))
+                };
+                md.push('\n');
+                md.push_str(&line(head));
+                md.push_str(&line(&vec!["---".into(); head.len()]));
+                rows.iter().for_each(|r| md.push_str(&line(r)));
+            }
+        }
+    }
+    md
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html(title: &str, blocks: &[Block]) -> String {
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+         <style>body{{font-family:system-ui,sans-serif;margin:2rem;color:#222}}\
+         table{{border-collapse:collapse;margin:.5rem 0 1.5rem}}\
+         th,td{{border:1px solid #ccc;padding:.25rem .5rem;text-align:right}}\
+         th:first-child,td:first-child{{text-align:left}}th{{background:#f4f4f4}}</style>\n\
+         </head>\n<body>\n<h1>{0}</h1>\n",
+        escape(title)
+    );
+    for b in blocks {
+        match b {
+            Block::Heading(level, text) => {
+                let _ = writeln!(page, "<h{level}>{}</h{level}>", escape(text));
+            }
+            Block::Text(text) => {
+                let _ = writeln!(page, "<p>{}</p>", escape(text));
+            }
+            Block::Table { head, rows, shade } => {
+                page.push_str("<table>\n<tr>");
+                head.iter()
+                    .for_each(|h| page.push_str(&format!("<th>{}</th>", escape(h))));
+                page.push_str("</tr>\n");
+                for (i, row) in rows.iter().enumerate() {
+                    page.push_str("<tr>");
+                    for (j, c) in row.iter().enumerate() {
+                        let v = shade.as_ref().map_or(0.0, |s| s[i][j]);
+                        match v {
+                            0.0 => page.push_str("<td>"),
+                            v => {
+                                let rgb = if v > 0.0 { "33,102,172" } else { "178,24,43" };
+                                let _ = write!(
+                                    page,
+                                    "<td style=\"background:rgba({rgb},{:.2})\">",
+                                    0.6 * v.abs().min(1.0)
+                                );
+                            }
+                        }
+                        let _ = write!(page, "{}</td>", escape(c));
+                    }
+                    page.push_str("</tr>\n");
+                }
+                page.push_str("</table>\n");
+            }
+        }
+    }
+    page.push_str("</body>\n</html>\n");
+    page
+}
+
+/// Shareable HTML or Markdown report on a CSV or a registered dataset.
+///
+/// Sections: overview (rows, columns, missingness), summary statistics,
+/// Jarque–Bera normality verdicts at the 5% level, outlying rows by the
+/// 1.5 × IQR rule, per-column histograms, ECDF deciles and the Pearson
+/// correlation heatmap (shaded cells in HTML).
+///
+/// - **Request**: body `text/csv` with [`CsvQuery`] options, or
+///   `?dataset=<id>` for a registered dataset (the body is then ignored);
+///   [`ReportQuery`] picks the `format`, `bins` and `title`
+/// - **Response**: `text/html` (default) or `text/markdown`; with
+///   `?store=true`, `201 Created` with the [`ArtifactOut`] the document was
+///   stored as and `Location: /api/v1/artifacts/{id}`, for sharing a link
+/// - **Errors**: `CsvParse`, `404` for an unknown dataset, `422` naming `/bins`
+#[utoipa::path(
+    post,
+    path = "/report",
+    tag = "tables",
+    summary = "HTML or Markdown analysis report for a dataset",
+    request_body(content = String, content_type = "text/csv", description = "CSV upload (omit with ?dataset=)"),
+    params(CsvQuery, ReportQuery),
+    responses(
+        (status = 200, description = "OK", content(
+            (String = "text/html"),
+            (String = "text/markdown")
+        )),
+        (status = 201, description = "Stored (`?store=true`); Location names the artifact", body = ArtifactOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the parameter", body = ErrorResponse)
+    )
+)]
+pub async fn report(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    Query(q): Query<CsvQuery>,
+    Query(r): Query<ReportQuery>,
+    body: Bytes,
+) -> Result<Response, ServiceError> {
+    r.validate(&state.config)?;
+    let source = match &r.dataset {
+        Some(id) => Source::Dataset(datasets::dataset(&state, &tenant, id)?),
+        None => {
+            let content = (&body[..], &q);
+            let table = state
+                .cache
+                .get_or_try_insert_with(CacheKey::new("csv_table", &content), || {
+                    read_csv(&body, &CsvOptions::from_query(&q, state.config.seed)?)
+                })?;
+            Source::Upload(table)
+        }
+    };
+    let title = match (r.title, &source) {
+        (Some(title), _) => title,
+        (None, Source::Dataset(ds)) => ds.name.clone(),
+        (None, Source::Upload(_)) => "Dataset report".into(),
+    };
+    let bins = r.bins.unwrap_or(10);
+    let format = r.format.unwrap_or_default();
+    let size = source.table().n_rows * source.table().columns.len();
+    let doc = state
+        .compute
+        .run(size, move |_| {
+            let (title, blocks) = build(title, &Frame::from_table(source.table()), bins);
+            Ok(match format {
+                ReportFormat::Html => html(&title, &blocks),
+                ReportFormat::Markdown => markdown(&title, &blocks),
+            })
+        })
+        .await?;
+    let mime = match format {
+        ReportFormat::Html => "text/html; charset=utf-8",
+        ReportFormat::Markdown => "text/markdown; charset=utf-8",
+    };
+    if r.store == Some(true) {
+        return artifacts::created(&state, &tenant, mime, doc.into()).await;
+    }
+    Ok((
+        [(header::CONTENT_TYPE, HeaderValue::from_static(mime))],
+        doc,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(csv: &str) -> Frame {
+        Frame::from_table(&read_csv(csv.as_bytes(), &CsvOptions::default()).unwrap())
+    }
+
+    #[test]
+    fn markdown_report_has_every_section() {
+        let csv =
+            "x,y,label\n1,2,a\n2,4,b\n3,5,c\n4,9,d\n5,10,e\n6,11,f\n7,14,g\n8,15,h\n100,16,i\n";
+        let (title, blocks) = build("T | 1".into(), &frame(csv), 4);
+        let md = markdown(&title, &blocks);
+        assert!(md.starts_with("# T \\| 1\n"));
+        for section in [
+            "## Overview",
+            "## Summary statistics",
+            "## Normality (Jarque–Bera)",
+            "## Outliers (1.5 × IQR)",
+            "## Histograms",
+            "### x",
+            "## ECDF",
+            "## Correlations (Pearson)",
+        ] {
+            assert!(md.contains(section), "missing {section}:\n{md}");
+        }
+        assert!(md.contains("| x | 1 | 9: 100 |"), "{md}");
+        assert!(md.contains("9 rows × 3 columns"));
+    }
+
+    #[test]
+    fn html_report_escapes_and_shades() {
+        let (title, blocks) = build("<b>".into(), &frame("a,b\n1,3\n2,2\n3,1\n"), 2);
+        let page = html(&title, &blocks);
+        assert!(page.contains("<h1>&lt;b&gt;</h1>"));
+        // a and b are perfectly anti-correlated: red cells
+        assert!(page.contains("rgba(178,24,43,0.60)\">-1.00</td>"), "{page}");
+        assert!(!page.contains("<script"));
+
+        let (_, blocks) = build("t".into(), &frame("name\nx\ny\n"), 10);
+        assert!(markdown("t", &blocks).contains("No numeric columns"));
+    }
+}
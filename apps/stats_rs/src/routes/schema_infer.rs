@@ -0,0 +1,106 @@
+//! /schema/infer
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    ingest::{CsvColumn, CsvOptions, InferTypes, read_csv},
+    state::AppState,
+    types::{
+        ColumnType, CsvQuery, ErrorResponse, InferredColumn, SchemaInferOut, SchemaInferQuery,
+    },
+};
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Query, State},
+};
+use std::sync::Arc;
+
+fn infer_column(col: &CsvColumn, infer: &InferTypes, n_examples: usize) -> InferredColumn {
+    let schema = col.schema();
+    let mut examples: Vec<String> = Vec::with_capacity(n_examples);
+    for c in col.cells.iter().filter(|c| !c.is_empty()) {
+        if examples.len() == n_examples {
+            break;
+        }
+        if !examples.contains(c) {
+            examples.push(c.clone());
+        }
+    }
+    let (min, max) = match col.dtype {
+        ColumnType::Integer | ColumnType::Float => col
+            .cells
+            .iter()
+            .filter_map(|c| infer.number(c))
+            .fold((None, None), |(lo, hi): (Option<f64>, Option<f64>), x| {
+                (
+                    Some(lo.map_or(x, |l| l.min(x))),
+                    Some(hi.map_or(x, |h| h.max(x))),
+                )
+            }),
+        _ => (None, None),
+    };
+    let rows = col.cells.len();
+    InferredColumn {
+        null_rate: if rows == 0 {
+            0.0
+        } else {
+            schema.missing as f64 / rows as f64
+        },
+        schema,
+        examples,
+        min,
+        max,
+    }
+}
+
+/// Infer column types from the head of a CSV upload, before any analysis runs.
+///
+/// Only the first `sample_rows` data rows (default 1000) are inspected, so
+/// clients can send a prefix of a large file to populate column pickers.
+/// With `sample=N` the rows are instead drawn at random from the whole file
+/// (at most `min(N, sample_rows)`).
+///
+/// - **Request**: body `text/csv`; query [`CsvQuery`] plus [`SchemaInferQuery`]
+///   (`sample_rows`, `examples`)
+/// - **Response**: [`SchemaInferOut`] (`200 OK`)
+/// - **Errors**: `CsvParse`, `InvalidInput` (unknown column, bad option)
+#[utoipa::path(
+    post,
+    path = "/schema/infer",
+    tag = "schemas",
+    summary = "Infer column types, null rates, examples and numeric ranges from a CSV sample",
+    request_body(content = String, content_type = "text/csv", description = "CSV upload"),
+    params(CsvQuery, SchemaInferQuery),
+    responses(
+        (status = 200, description = "OK", body = SchemaInferOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn schema_infer(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<CsvQuery>,
+    Query(s): Query<SchemaInferQuery>,
+    body: Bytes,
+) -> Result<(NUsed, Json<SchemaInferOut>), ServiceError> {
+    let opts = CsvOptions {
+        max_rows: Some(s.sample_rows.unwrap_or(1000)),
+        ..CsvOptions::from_query(&q, state.config.seed)?
+    };
+    let table = read_csv(&body, &opts)?;
+    let n_examples = s.examples.unwrap_or(5);
+
+    Ok((
+        NUsed(table.n_rows),
+        Json(SchemaInferOut {
+            rows_sampled: table.n_rows,
+            truncated: table.truncated,
+            columns: table
+                .columns
+                .iter()
+                .map(|c| infer_column(c, &table.infer, n_examples))
+                .collect(),
+            sample: table.sample,
+        }),
+    ))
+}
@@ -0,0 +1,185 @@
+//! /stats/anomaly/score
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::{kept, resolve},
+    state::AppState,
+    stats::prelude::*,
+    types::{
+        AnomalyComponent, AnomalyDetector, AnomalyIn, AnomalyOut, AnomalyPoint, ErrorResponse,
+    },
+    validate::Valid,
+};
+use axum::{Json, extract::State};
+use rand::{SeedableRng, rngs::StdRng};
+use std::sync::Arc;
+
+const ALL_DETECTORS: [AnomalyDetector; 4] = [
+    AnomalyDetector::RobustZ,
+    AnomalyDetector::Iqr,
+    AnomalyDetector::IsolationForest,
+    AnomalyDetector::RollingResidual,
+];
+
+/// Robust z past which a point is usually called an outlier.
+const ROBUST_Z_CUTOFF: f64 = 3.5;
+
+/// Tukey's fence multiplier.
+const IQR_CUTOFF: f64 = 1.5;
+
+/// Map a non-negative distance onto `[0, 1)` so that `cutoff` lands on `0.5`.
+fn squash(d: f64, cutoff: f64) -> f64 {
+    match d.is_infinite() {
+        true => 1.0,
+        false => d / (d + cutoff),
+    }
+}
+
+/// Normalized score of every value under one detector.
+fn detector_scores(
+    detector: AnomalyDetector,
+    xs: &[f64],
+    inp: &AnomalyIn,
+    seed: u64,
+    cancel: &CancelFlag,
+) -> Vec<f64> {
+    match detector {
+        AnomalyDetector::RobustZ => robust_distances(xs)
+            .into_iter()
+            .map(|z| squash(z, ROBUST_Z_CUTOFF))
+            .collect(),
+        AnomalyDetector::Iqr => iqr_distances(xs)
+            .into_iter()
+            .map(|d| squash(d, IQR_CUTOFF))
+            .collect(),
+        // 0.5 is the forest's "no clear anomaly" level
+        AnomalyDetector::IsolationForest => isolation_forest_scores(
+            xs,
+            inp.trees.unwrap_or(100),
+            inp.sample_size.unwrap_or(256),
+            &mut StdRng::seed_from_u64(seed),
+            cancel.guard(|_| {}),
+        )
+        .into_iter()
+        .map(|s| (2.0 * s - 1.0).max(0.0))
+        .collect(),
+        AnomalyDetector::RollingResidual => {
+            let residuals = rolling_residuals(xs, inp.window.unwrap_or(7) / 2);
+            robust_distances(&residuals)
+                .into_iter()
+                .map(|z| squash(z, ROBUST_Z_CUTOFF))
+                .collect()
+        }
+    }
+}
+
+/// One composite anomaly score per point from several detectors.
+///
+/// - Each detector's score is scaled to `[0, 1]` with `0.5` at its usual
+///   cutoff: robust z `3.5`, `1.5` IQRs past the quartiles, an isolation
+///   score of `0.75`, a rolling-median residual of robust z `3.5`
+/// - The composite `score` is their mean; `contributions` split it by
+///   detector, and points at `0.5` or above count as `flagged`
+/// - `detectors` defaults to all four; `rolling_residual` compares each value
+///   with the median of its `window` (default 7) neighbourhood, so the values
+///   should be in order
+/// - The isolation forest grows `trees` (default 100) trees of `sample_size`
+///   (default 256) values from `seed` (default `STATS_SEED`): equal requests score alike
+/// - `null`s are settled by `missing` (default `drop`); indices and `scores`
+///   follow the input positions
+#[utoipa::path(
+    post,
+    path = "/stats/anomaly/score",
+    tag = "stats",
+    summary = "Composite anomaly score from several detectors",
+    request_body = AnomalyIn,
+    responses(
+        (status = 200, description = "OK", body = AnomalyOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_anomaly_score(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<AnomalyIn>,
+) -> Result<(NUsed, Json<AnomalyOut>), ServiceError> {
+    let n = kept(&inp.values, inp.missing.unwrap_or_default());
+    let size = inp.values.len().saturating_mul(inp.trees.unwrap_or(100));
+    let seed = state.config.seed_for(inp.seed);
+    let out = state
+        .compute
+        .run(size, move |cancel| anomaly(inp, seed, cancel))
+        .await?;
+    Ok((NUsed(n), Json(out)))
+}
+
+/// Body of [`stats_anomaly_score`] for an already validated request.
+fn anomaly(inp: AnomalyIn, seed: u64, cancel: &CancelFlag) -> Result<AnomalyOut, ServiceError> {
+    let r = resolve(inp.values.clone(), inp.missing.unwrap_or_default())?;
+    let xs = &r.values;
+    let detectors = inp.detectors.clone().unwrap_or(ALL_DETECTORS.to_vec());
+
+    let mut per_detector = Vec::with_capacity(detectors.len());
+    for &d in &detectors {
+        per_detector.push(detector_scores(d, xs, &inp, seed, cancel));
+        if cancel.is_cancelled() {
+            return Err(ServiceError::Cancelled);
+        }
+    }
+    let weight = 1.0 / detectors.len() as f64;
+    let composite: Vec<f64> = (0..xs.len())
+        .map(|i| per_detector.iter().map(|s| s[i]).sum::<f64>() * weight)
+        .collect();
+
+    // spread values over the input positions, `null` where they were dropped
+    let by_position = |scores: &[f64]| {
+        let mut out = vec![None; inp.values.len()];
+        for (&at, &s) in r.origin.iter().zip(scores) {
+            out[at] = Some(s);
+        }
+        out
+    };
+    let top = top_k(
+        composite.iter().copied().enumerate(),
+        inp.top_k.unwrap_or(10),
+    );
+    let ranked = top
+        .into_iter()
+        .map(|(i, score)| AnomalyPoint {
+            index: r.origin[i],
+            value: xs[i],
+            score,
+            contributions: per_detector.iter().map(|s| s[i] * weight).collect(),
+        })
+        .collect();
+
+    Ok(AnomalyOut {
+        flagged: composite.iter().filter(|&&s| s >= 0.5).count(),
+        scores: by_position(&composite),
+        components: detectors
+            .iter()
+            .zip(&per_detector)
+            .map(|(&detector, s)| AnomalyComponent {
+                detector,
+                scores: by_position(s),
+            })
+            .collect(),
+        detectors,
+        ranked,
+        seed,
+        missing: Some(r.report),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cutoffs_land_on_one_half() {
+        assert_eq!(squash(ROBUST_Z_CUTOFF, ROBUST_Z_CUTOFF), 0.5);
+        assert_eq!(squash(0.0, IQR_CUTOFF), 0.0);
+        assert_eq!(squash(f64::INFINITY, IQR_CUTOFF), 1.0);
+    }
+}
@@ -0,0 +1,191 @@
+//! /stats/compare
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::resolve,
+    state::AppState,
+    stats::prelude::*,
+    types::{
+        CompareDelta, CompareEcdf, CompareEffect, CompareHistogram, CompareIn, CompareOut,
+        ErrorResponse, MissingReport, SampleSummary, SampleTest,
+    },
+    validate::Valid,
+};
+use axum::{Json, extract::State};
+use std::sync::Arc;
+
+#[inline]
+fn o(x: f64) -> Option<f64> {
+    if x.is_finite() { Some(x) } else { None }
+}
+
+/// Summary of an ascending sample.
+fn summary(s: &[f64]) -> SampleSummary {
+    let m = mean(s);
+    let (q1, median, q3) = match s.is_empty() {
+        true => (f64::NAN, f64::NAN, f64::NAN),
+        false => quartiles_sorted(s),
+    };
+    SampleSummary {
+        count: s.len(),
+        mean: o(m),
+        median: o(median),
+        std_dev: o(sample_std_dev(s, m)),
+        min: s.first().copied(),
+        q1: o(q1),
+        q3: o(q3),
+        max: s.last().copied(),
+    }
+}
+
+fn test(statistic: f64, df: f64, p: f64) -> SampleTest {
+    SampleTest {
+        statistic: o(statistic),
+        df: o(df),
+        df2: None,
+        p_value: o(p),
+    }
+}
+
+/// Share of the ascending `sorted` values at or below `v`.
+fn ecdf_at(sorted: &[f64], v: f64) -> f64 {
+    sorted.partition_point(|&x| x <= v) as f64 / sorted.len() as f64
+}
+
+/// Compare two independent samples in one call.
+///
+/// - `x` and `y` may differ in length; every difference and effect size is
+///   `x` relative to `y`
+/// - Runs Student's and Welch's t-tests, Mann–Whitney U (tie-corrected normal
+///   approximation), the two-sample Kolmogorov–Smirnov test, and the F and
+///   Brown–Forsythe tests for equal variances; all p-values are two-sided
+/// - Effect sizes: Cohen's d, Hedges' g, Cliff's delta and the
+///   common-language effect size `P(x > y)`
+/// - `histogram` bins both samples on shared edges (`bins`, default 20) and
+///   `ecdf` evaluates both CDFs on one grid of at most `max_points` (default
+///   200) pooled values, ready to overlay
+/// - Statistics a sample is too small for are `null`
+/// - `null`s are settled by `missing` (default `drop`) in each sample
+#[utoipa::path(
+    post,
+    path = "/stats/compare",
+    tag = "stats",
+    summary = "Two-sample comparison: tests, effect sizes and overlay data",
+    request_body = CompareIn,
+    responses(
+        (status = 200, description = "OK", body = CompareOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_compare(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<CompareIn>,
+) -> Result<(NUsed, Json<CompareOut>), ServiceError> {
+    let size = inp.x.len() + inp.y.len();
+    let out = state.compute.run(size, move |_| compare(inp)).await?;
+    Ok((NUsed(out.x.count + out.y.count), Json(out)))
+}
+
+/// Body of [`stats_compare`] for an already validated request.
+fn compare(inp: CompareIn) -> Result<CompareOut, ServiceError> {
+    let policy = inp.missing.unwrap_or_default();
+    let rx = resolve(inp.x, policy)?;
+    let ry = resolve(inp.y, policy)?;
+    let missing = MissingReport {
+        policy,
+        count: rx.report.count + ry.report.count,
+    };
+    let (x, y) = (sorted(&rx.values), sorted(&ry.values));
+    let (sx, sy) = (summary(&x), summary(&y));
+
+    let diff = |a: Option<f64>, b: Option<f64>| Some(a? - b?);
+    let delta = CompareDelta {
+        mean: diff(sx.mean, sy.mean),
+        median: diff(sx.median, sy.median),
+        std_dev_ratio: sx.std_dev.zip(sy.std_dev).and_then(|(a, b)| o(a / b)),
+    };
+
+    let (t, df, p) = two_sample_t_test(&x, &y, false);
+    let t_test = test(t, df, p);
+    let (t, df, p) = two_sample_t_test(&x, &y, true);
+    let welch = test(t, df, p);
+    let (u, _, p) = mann_whitney_u(&x, &y);
+    let mann_whitney = test(u, f64::NAN, p);
+    let (d, p) = ks_two_sample(&x, &y);
+    let ks = test(d, f64::NAN, p);
+    let (f, p) = variance_ratio_test(&x, &y);
+    let variance_f = SampleTest {
+        df2: Some(y.len().saturating_sub(1) as f64),
+        ..test(f, x.len().saturating_sub(1) as f64, p)
+    };
+    let (w, df1, df2, p) = brown_forsythe(&[&x, &y]);
+    let brown_forsythe = SampleTest {
+        df2: o(df2),
+        ..test(w, df1, p)
+    };
+
+    let (cohens_d, hedges_g) = cohens_d(&x, &y);
+    let pairs = x.len() as f64 * y.len() as f64;
+    let effect = CompareEffect {
+        cohens_d: o(cohens_d),
+        hedges_g: o(hedges_g),
+        cliffs_delta: o(2.0 * u / pairs - 1.0),
+        common_language: o(u / pairs),
+    };
+
+    let pooled: Vec<f64> = x.iter().chain(&y).copied().collect();
+    let (_, edges) = histogram(&pooled, inp.bins.unwrap_or(20));
+    let histogram = CompareHistogram {
+        x: histogram_with_edges(&x, &edges).0,
+        y: histogram_with_edges(&y, &edges).0,
+        edges,
+    };
+
+    let mut grid = sorted(&pooled);
+    grid.dedup();
+    let grid: Vec<f64> = uniform_indices(grid.len(), inp.max_points.unwrap_or(200))
+        .into_iter()
+        .map(|i| grid[i])
+        .collect();
+    // an emptied sample has no CDF to draw
+    let cdf = |s: &[f64]| match s.is_empty() {
+        true => vec![],
+        false => grid.iter().map(|&v| ecdf_at(s, v)).collect(),
+    };
+    let ecdf = CompareEcdf {
+        x: cdf(&x),
+        y: cdf(&y),
+        grid,
+    };
+
+    Ok(CompareOut {
+        x: sx,
+        y: sy,
+        delta,
+        t_test,
+        welch,
+        mann_whitney,
+        ks,
+        variance_f,
+        brown_forsythe,
+        effect,
+        histogram,
+        ecdf,
+        missing: Some(missing),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ecdf_counts_ties_at_the_value() {
+        let xs = [1.0, 2.0, 2.0, 4.0];
+        assert_eq!(ecdf_at(&xs, 0.5), 0.0);
+        assert_eq!(ecdf_at(&xs, 2.0), 0.75);
+        assert_eq!(ecdf_at(&xs, 9.0), 1.0);
+    }
+}
@@ -0,0 +1,149 @@
+//! /stats/contingency
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    stats::prelude::*,
+    types::{ContingencyIn, ContingencyOut, ErrorResponse, MissingPolicy, MissingReport},
+    validate::{MAX_LEVELS, Valid, invalid},
+};
+use axum::Json;
+use std::{cmp::Ordering, collections::HashMap};
+
+/// Label order for table rows and columns: numbers first, numerically, then
+/// the other labels by their text.
+fn label_order(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.total_cmp(&y).then_with(|| a.cmp(b)),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// Distinct `labels` in [`label_order`] and the index of each label in them;
+/// `422` at `field` past [`MAX_LEVELS`].
+fn levels<'a>(
+    field: &str,
+    labels: &[&'a str],
+) -> Result<(Vec<String>, HashMap<&'a str, usize>), ServiceError> {
+    let mut distinct: Vec<&str> = labels.to_vec();
+    distinct.sort_unstable_by(|a, b| label_order(a, b));
+    distinct.dedup();
+    if distinct.len() > MAX_LEVELS {
+        return Err(invalid(
+            field,
+            format!(
+                "has {} distinct labels; at most {MAX_LEVELS} allowed",
+                distinct.len()
+            ),
+        ));
+    }
+    let index = distinct.iter().enumerate().map(|(i, &l)| (l, i)).collect();
+    Ok((distinct.into_iter().map(str::to_owned).collect(), index))
+}
+
+/// Cross-tabulate two aligned label arrays and test them for independence.
+///
+/// - Labels may be strings, numbers or booleans; rows and columns are the
+///   distinct labels, numbers first in numeric order, then the rest by text
+/// - A pair with a `null` on either side is dropped and counted in `missing`
+/// - Returns observed and expected counts, margins, Pearson's χ² with its
+///   degrees of freedom and p-value, and Cramér's V
+/// - `yates: true` applies the continuity correction to 2×2 tables
+/// - Statistics are `null` when the table has a single row or column
+/// - Empty or unequal-length arrays, or more than 1000 distinct labels on a
+///   side, are rejected (`422`)
+#[utoipa::path(
+    post,
+    path = "/stats/contingency",
+    tag = "stats",
+    summary = "Contingency table and χ² test of two label arrays",
+    request_body = ContingencyIn,
+    responses(
+        (status = 200, description = "OK", body = ContingencyOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_contingency(
+    Valid(inp): Valid<ContingencyIn>,
+) -> Result<(NUsed, Json<ContingencyOut>), ServiceError> {
+    let (xs, ys): (Vec<&str>, Vec<&str>) = inp
+        .x
+        .iter()
+        .zip(&inp.y)
+        .filter_map(|(x, y)| Some((x.as_deref()?, y.as_deref()?)))
+        .unzip();
+    let n = xs.len();
+    let missing = MissingReport {
+        policy: MissingPolicy::Drop,
+        count: inp.x.iter().chain(&inp.y).filter(|l| l.is_none()).count(),
+    };
+    let (rows, row_index) = levels("/x", &xs)?;
+    let (cols, col_index) = levels("/y", &ys)?;
+
+    let mut counts = vec![vec![0usize; cols.len()]; rows.len()];
+    for (x, y) in xs.iter().zip(&ys) {
+        counts[row_index[x]][col_index[y]] += 1;
+    }
+    let row_totals: Vec<usize> = counts.iter().map(|r| r.iter().sum()).collect();
+    let col_totals: Vec<usize> = (0..cols.len())
+        .map(|j| counts.iter().map(|r| r[j]).sum())
+        .collect();
+    let expected = row_totals
+        .iter()
+        .map(|&r| {
+            col_totals
+                .iter()
+                .map(|&c| (r * c) as f64 / n as f64)
+                .collect()
+        })
+        .collect();
+
+    let yates = inp.yates.unwrap_or(false) && rows.len() == 2 && cols.len() == 2;
+    let (plain, df, plain_p) = chi_square_independence(&counts, false);
+    let (chi2, p) = match yates {
+        true => {
+            let (chi2, _, p) = chi_square_independence(&counts, true);
+            (chi2, p)
+        }
+        false => (plain, plain_p),
+    };
+
+    #[inline]
+    fn o(x: f64) -> Option<f64> {
+        if x.is_finite() { Some(x) } else { None }
+    }
+
+    Ok((
+        NUsed(n),
+        Json(ContingencyOut {
+            counts,
+            expected,
+            row_totals,
+            col_totals,
+            n,
+            chi_square: o(chi2),
+            df,
+            p_value: o(p),
+            cramers_v: o(cramers_v(plain, n, rows.len(), cols.len())),
+            yates,
+            rows,
+            cols,
+            missing: Some(missing),
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_labels_sort_first_and_by_value() {
+        let mut labels = vec!["10", "9", "b", "1a", "2.5", "a"];
+        labels.sort_by(|a, b| label_order(a, b));
+        assert_eq!(labels, ["2.5", "9", "10", "1a", "a", "b"]);
+    }
+}
@@ -1,44 +1,634 @@
 //! /stats/corr-matrix
 
 use crate::{
+    cache::CacheKey,
+    envelope::NUsed,
+    error::ServiceError,
+    frame::{ColumnData, Frame},
+    ingest::{CsvOptions, read_csv},
+    missing::{pairwise_report, resolve_series, rows_kept},
+    routes::{
+        datasets,
+        export::{FormatQuery, OutputFormat, Tabular},
+    },
+    state::AppState,
     stats::prelude::*,
-    types::{CorrMatrixIn, CorrMatrixOut, CorrMethod},
+    tenant::Tenant,
+    types::{
+        CorrCsvQuery, CorrLinkage, CorrMatrixIn, CorrMatrixOut, CorrMethod, CsvQuery,
+        ErrorResponse, LinkageStep, MissingPolicy,
+    },
+    validate::{Valid, Validate},
 };
-use axum::Json;
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+};
+use rayon::prelude::*;
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+/// A series prepared once for all the pairs it takes part in.
+enum Prepared {
+    /// Centred values (or ranks, for Spearman) and their sum of squares
+    Centred(Vec<f64>, f64),
+    /// Average ranks, for Kendall
+    Ranks(Vec<f64>),
+    /// Constant (or non-finite) series: every correlation with it is undefined
+    Undefined,
+}
+
+fn centred(xs: &[f64]) -> Prepared {
+    let m = mean(xs);
+    let c: Vec<f64> = xs.iter().map(|x| x - m).collect();
+    let ss = dot(&c, &c);
+    if ss > 0.0 && ss.is_finite() {
+        Prepared::Centred(c, ss)
+    } else {
+        Prepared::Undefined
+    }
+}
+
+/// Pearson/Spearman correlations from one Gram matrix of the centred series
+/// (a single GEMM, see [`crate::stats::linalg`]).
+#[cfg(feature = "ndarray")]
+fn centred_gram_matrix(prepared: &[Prepared], n: usize, progress: impl Checkpoint) -> Vec<f64> {
+    let m = prepared.len();
+    if progress.cancelled() {
+        return vec![0.0; m * m];
+    }
+    let rows: Vec<Vec<f64>> = prepared
+        .iter()
+        .map(|p| match p {
+            Prepared::Centred(c, _) => c.clone(),
+            _ => vec![0.0; n],
+        })
+        .collect();
+    let mut mat = crate::stats::linalg::gram(&rows);
+    for i in 0..m {
+        for j in 0..m {
+            mat[i * m + j] = match (&prepared[i], &prepared[j]) {
+                _ if i == j => 1.0,
+                (Prepared::Centred(_, sa), Prepared::Centred(_, sb)) => {
+                    (mat[i * m + j] / (sa * sb).sqrt()).clamp(-1.0, 1.0)
+                }
+                _ => f64::NAN,
+            };
+        }
+        progress.reached(i + 1);
+    }
+    mat
+}
+
+/// Row-major `m×m` matrix of `method` correlations between `series`, with
+/// undefined pairs as `NaN`. `progress` is called with the rows finished;
+/// once it reports [`Checkpoint::cancelled`] the remaining rows are skipped
+/// and the returned matrix is incomplete.
+///
+/// Each series is prepared once (centred and scaled for Pearson, ranked and
+/// then scaled for Spearman, ranked for Kendall) and the upper triangle is
+/// filled row by row on the rayon pool. With the `ndarray` feature Pearson
+/// and Spearman come from one Gram matrix instead.
+pub(crate) fn correlation_matrix(
+    series: &[Vec<f64>],
+    method: CorrMethod,
+    progress: impl Checkpoint + Sync,
+) -> Vec<f64> {
+    let m = series.len();
+    let prepared: Vec<Prepared> = series
+        .par_iter()
+        .map(|xs| match method {
+            CorrMethod::Pearson => centred(xs),
+            CorrMethod::Spearman => centred(&average_ranks(xs)),
+            CorrMethod::Kendall => Prepared::Ranks(average_ranks(xs)),
+        })
+        .collect();
+    #[cfg(feature = "ndarray")]
+    if !matches!(method, CorrMethod::Kendall) {
+        let n = series.first().map_or(0, Vec::len);
+        return centred_gram_matrix(&prepared, n, progress);
+    }
+    let pair = |a: &Prepared, b: &Prepared| match (a, b) {
+        (Prepared::Centred(a, sa), Prepared::Centred(b, sb)) => {
+            (dot(a, b) / (sa * sb).sqrt()).clamp(-1.0, 1.0)
+        }
+        (Prepared::Ranks(a), Prepared::Ranks(b)) => kendall_tau_b_ranked(a, b),
+        _ => f64::NAN,
+    };
+
+    let done = AtomicUsize::new(0);
+    let upper: Vec<Vec<f64>> = (0..m)
+        .into_par_iter()
+        .map(|i| {
+            if progress.cancelled() {
+                return vec![];
+            }
+            let row = ((i + 1)..m)
+                .map(|j| pair(&prepared[i], &prepared[j]))
+                .collect();
+            progress.reached(done.fetch_add(1, Ordering::Relaxed) + 1);
+            row
+        })
+        .collect();
+
+    let mut mat = vec![0.0f64; m * m];
+    for (i, row) in upper.into_iter().enumerate() {
+        mat[i * m + i] = 1.0;
+        for (j, v) in ((i + 1)..m).zip(row) {
+            mat[i * m + j] = v;
+            mat[j * m + i] = v;
+        }
+    }
+    mat
+}
+
+/// [`correlation_matrix`] under pairwise-complete deletion: each pair of
+/// `series` (missing values as `NaN`) keeps the positions both observe.
+/// Returns the matrix and the pairs behind each entry (the observed count on
+/// the diagonal).
+///
+/// Nothing is shared between pairs, so this costs a pair kernel per entry
+/// rather than one preparation per series.
+pub(crate) fn pairwise_complete_matrix(
+    series: &[Vec<f64>],
+    method: CorrMethod,
+    progress: impl Checkpoint + Sync,
+) -> (Vec<f64>, Vec<usize>) {
+    let m = series.len();
+    let kernel = match method {
+        CorrMethod::Pearson => pearson_correlation,
+        CorrMethod::Spearman => spearman_rho,
+        CorrMethod::Kendall => kendall_tau_b,
+    };
+    let pair = |a: &[f64], b: &[f64]| {
+        let (xs, ys): (Vec<f64>, Vec<f64>) = a
+            .iter()
+            .zip(b)
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .map(|(&x, &y)| (x, y))
+            .unzip();
+        (kernel(&xs, &ys), xs.len())
+    };
+
+    let done = AtomicUsize::new(0);
+    let upper: Vec<Vec<(f64, usize)>> = (0..m)
+        .into_par_iter()
+        .map(|i| {
+            if progress.cancelled() {
+                return vec![];
+            }
+            let row = ((i + 1)..m).map(|j| pair(&series[i], &series[j])).collect();
+            progress.reached(done.fetch_add(1, Ordering::Relaxed) + 1);
+            row
+        })
+        .collect();
+
+    let mut mat = vec![f64::NAN; m * m];
+    let mut n_used = vec![0; m * m];
+    for (i, row) in upper.into_iter().enumerate() {
+        mat[i * m + i] = 1.0;
+        n_used[i * m + i] = series[i].iter().filter(|x| x.is_finite()).count();
+        for (j, (r, n)) in ((i + 1)..m).zip(row) {
+            (mat[i * m + j], mat[j * m + i]) = (r, r);
+            (n_used[i * m + j], n_used[j * m + i]) = (n, n);
+        }
+    }
+    (mat, n_used)
+}
+
+/// Row-major sample covariance matrix of `series`, each pair taken over the
+/// rows where both are finite (every row once missing values were dropped);
+/// `NaN` for pairs with fewer than 2 such rows.
+pub(crate) fn covariance_matrix_pairwise(series: &[Vec<f64>]) -> Vec<f64> {
+    let m = series.len();
+    let pair = |a: &[f64], b: &[f64]| {
+        let (xs, ys): (Vec<f64>, Vec<f64>) = a
+            .iter()
+            .zip(b)
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .map(|(&x, &y)| (x, y))
+            .unzip();
+        covariance(&xs, &ys)
+    };
+    let upper: Vec<Vec<f64>> = (0..m)
+        .into_par_iter()
+        .map(|i| (i..m).map(|j| pair(&series[i], &series[j])).collect())
+        .collect();
+    let mut mat = vec![f64::NAN; m * m];
+    for (i, row) in upper.into_iter().enumerate() {
+        for (j, c) in (i..m).zip(row) {
+            (mat[i * m + j], mat[j * m + i]) = (c, c);
+        }
+    }
+    mat
+}
+
+/// Two-sided p-value of each coefficient in `matrix` over the pairs in
+/// `n_used` (`t` test for Pearson and Spearman, normal approximation for
+/// Kendall); `NaN` where the coefficient is undefined.
+pub(crate) fn p_value_matrix(method: CorrMethod, matrix: &[f64], n_used: &[usize]) -> Vec<f64> {
+    let p = match method {
+        CorrMethod::Pearson | CorrMethod::Spearman => correlation_p_value,
+        CorrMethod::Kendall => kendall_p_value,
+    };
+    matrix.iter().zip(n_used).map(|(&r, &n)| p(r, n)).collect()
+}
+
+/// Heatmap order and merge tree of the `m` variables of a correlation
+/// `matrix`, clustered with `linkage` on the distance `1 − r`. Undefined
+/// coefficients count as uncorrelated (distance 1).
+pub(crate) fn cluster_variables(
+    matrix: &[f64],
+    m: usize,
+    linkage: CorrLinkage,
+) -> (Vec<usize>, Vec<LinkageStep>) {
+    let dist: Vec<f64> = matrix
+        .iter()
+        .map(|r| if r.is_finite() { 1.0 - r } else { 1.0 })
+        .collect();
+    let linkage = match linkage {
+        CorrLinkage::Single => Linkage::Single,
+        CorrLinkage::Complete => Linkage::Complete,
+        CorrLinkage::Average => Linkage::Average,
+    };
+    let (merges, order) = hierarchical_clustering(&dist, m, linkage);
+    let steps = merges
+        .into_iter()
+        .map(|s| LinkageStep {
+            left: s.left,
+            right: s.right,
+            distance: s.distance,
+            size: s.size,
+        })
+        .collect();
+    (order, steps)
+}
 
 /// Compute an `m×m` correlation matrix across multiple series.
 ///
 /// - `method` defaults to Pearson
-/// - Returns a flattened row-major matrix in [`CorrMatrixOut::matrix`]
-pub async fn stats_corr_matrix(Json(inp): Json<CorrMatrixIn>) -> Json<CorrMatrixOut> {
-    let m = inp.series.len();
+/// - Series must be non-empty and equally long, with one entry in `names`
+///   per series when given (`422` naming `/series/i` or `/names` otherwise)
+/// - `missing` defaults to `drop`: rows with a `null` in any series are removed;
+///   `pairwise` removes a row only from the pairs it has a `null` in, and
+///   reports each pair's count in `n_used`
+/// - `p_values: true` adds two-sided p-values (and `n_used`)
+/// - `cluster` adds the variables' hierarchical-clustering order and merges
+///   (`order`, `linkage`); the matrix itself stays in input order
+/// - `covariance: true` adds the sample covariance matrix of the values, and
+///   `partial: true` the partial correlations from the inverse of `matrix`
+///   (for Gaussian graphical-model views: a zero means conditionally
+///   independent). Neither is part of the CSV/TSV output
+/// - Undefined coefficients (a constant series, too few pairs) are `null`,
+///   never `0`
+/// - Returns a flattened row-major matrix in [`CorrMatrixOut::matrix`], or the
+///   labelled square matrix for `?format=csv|tsv` / `Accept: text/csv`
+/// - The matrix is cached per (method, series), so re-requesting it in another
+///   format or with other `names` does not recompute it
+/// - A large matrix stops computing if the client disconnects first, and is
+///   then not cached
+#[utoipa::path(
+    post,
+    path = "/stats/corr-matrix",
+    tag = "stats",
+    summary = "Correlation matrix for multiple series",
+    request_body = CorrMatrixIn,
+    params(FormatQuery),
+    responses(
+        (status = 200, description = "OK", content(
+            (CorrMatrixOut = "application/json"),
+            (String = "text/csv"),
+            (String = "text/tab-separated-values")
+        )),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_corr_matrix(
+    State(state): State<Arc<AppState>>,
+    fmt: OutputFormat,
+    Valid(inp): Valid<CorrMatrixIn>,
+) -> Result<(NUsed, Tabular<CorrMatrixOut>), ServiceError> {
+    let n = rows_kept(&inp.series, inp.missing.unwrap_or_default());
+    let size = inp.series.iter().map(Vec::len).sum();
+    let st = state.clone();
+    let out = state
+        .compute
+        .run(size, move |cancel| corr_matrix(&st, inp, cancel))
+        .await?;
+    Ok((NUsed(n), Tabular(fmt, out)))
+}
+
+/// Correlation matrix of the numeric columns of a CSV or a registered
+/// dataset, named by their headers.
+///
+/// Spares clients from transposing a table into `series` JSON: every integer
+/// or float column becomes a variable, text columns are skipped, and the
+/// matrix is then computed as `/stats/corr-matrix` computes it (same cache,
+/// p-values, clustering and CSV/TSV output).
+///
+/// - **Request**: body `text/csv` with [`CsvQuery`] options (`columns`
+///   narrows the candidates), or `?dataset=<id>` for a registered dataset
+///   (the body is then ignored); [`CorrCsvQuery`] sets `method`, `missing`,
+///   `p_values`, `cluster`, `covariance` and `partial`
+/// - **Missing**: empty cells default to `missing=pairwise`, so one sparse
+///   column does not discard rows for every other pair; `drop` keeps only
+///   complete rows
+/// - **Response**: [`CorrMatrixOut`] with `names`, or the labelled square
+///   matrix for `?format=csv|tsv` / `Accept: text/csv`
+/// - **Errors**: `CsvParse`, `NoNumeric` (`400`) without a numeric column,
+///   `404` for an unknown dataset, `422` for tables breaking the
+///   `/stats/corr-matrix` limits (`details.field` points into `/series`)
+#[utoipa::path(
+    post,
+    path = "/stats/corr-matrix-csv",
+    tag = "stats",
+    summary = "Correlation matrix of the numeric columns of a CSV or dataset",
+    request_body(content = String, content_type = "text/csv", description = "CSV upload (omit with ?dataset=)"),
+    params(CsvQuery, CorrCsvQuery, FormatQuery),
+    responses(
+        (status = 200, description = "OK", content(
+            (CorrMatrixOut = "application/json"),
+            (String = "text/csv"),
+            (String = "text/tab-separated-values")
+        )),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 404, description = "Not Found", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_corr_matrix_csv(
+    State(state): State<Arc<AppState>>,
+    tenant: Tenant,
+    fmt: OutputFormat,
+    Query(q): Query<CsvQuery>,
+    Query(c): Query<CorrCsvQuery>,
+    body: Bytes,
+) -> Result<(NUsed, Tabular<CorrMatrixOut>), ServiceError> {
+    let frame = match &c.dataset {
+        Some(id) => Frame::from_table(&datasets::dataset(&state, &tenant, id)?.table),
+        None => {
+            let content = (&body[..], &q);
+            let table = state
+                .cache
+                .get_or_try_insert_with(CacheKey::new("csv_table", &content), || {
+                    read_csv(&body, &CsvOptions::from_query(&q, state.config.seed)?)
+                })?;
+            Frame::from_table(&table)
+        }
+    };
+    let (names, series): (Vec<String>, Vec<Vec<f64>>) = frame
+        .columns()
+        .iter()
+        .filter_map(|col| match &col.data {
+            ColumnData::Numeric(cells) => Some((
+                col.name.clone(),
+                cells.iter().map(|x| x.unwrap_or(f64::NAN)).collect(),
+            )),
+            ColumnData::Text(_) => None,
+        })
+        .unzip();
+    if series.is_empty() {
+        return Err(ServiceError::NoNumeric);
+    }
+    let inp = CorrMatrixIn {
+        series,
+        names: Some(names),
+        method: c.method,
+        missing: Some(c.missing.unwrap_or(MissingPolicy::Pairwise)),
+        p_values: c.p_values,
+        cluster: c.cluster,
+        covariance: c.covariance,
+        partial: c.partial,
+    };
+    inp.validate(&state.config)?;
+    let n = rows_kept(&inp.series, inp.missing.unwrap_or_default());
+    let size = frame.n_rows() * inp.series.len();
+    let st = state.clone();
+    let out = state
+        .compute
+        .run(size, move |cancel| corr_matrix(&st, inp, cancel))
+        .await?;
+    Ok((NUsed(n), Tabular(fmt, out)))
+}
+
+/// Body of [`stats_corr_matrix`] for an already validated request, giving up
+/// with [`ServiceError::Cancelled`] once `cancel` is set.
+pub(crate) fn corr_matrix(
+    state: &AppState,
+    inp: CorrMatrixIn,
+    cancel: &CancelFlag,
+) -> Result<CorrMatrixOut, ServiceError> {
+    let policy = inp.missing.unwrap_or_default();
+    let pairwise = policy == MissingPolicy::Pairwise;
+    let (series, report) = match pairwise {
+        true => {
+            let report = pairwise_report(&inp.series);
+            (inp.series, report)
+        }
+        false => resolve_series(inp.series, policy)?,
+    };
+    let m = series.len();
     if m == 0 {
-        return Json(CorrMatrixOut {
+        return Ok(CorrMatrixOut {
             size: 0,
             names: None,
             matrix: vec![],
+            p_values: None,
+            n_used: None,
+            covariance: None,
+            partial: None,
+            order: None,
+            linkage: None,
+            missing: Some(report),
         });
     }
     let method = inp.method.unwrap_or(CorrMethod::Pearson);
-    let mut mat = vec![0.0f64; m * m];
-
-    for i in 0..m {
-        mat[i * m + i] = 1.0;
-        for j in (i + 1)..m {
-            let v = match method {
-                CorrMethod::Pearson => pearson_correlation(&inp.series[i], &inp.series[j]),
-                CorrMethod::Spearman => spearman_rho(&inp.series[i], &inp.series[j]),
-                CorrMethod::Kendall => kendall_tau_b(&inp.series[i], &inp.series[j]),
-            };
-            let v = if v.is_nan() { 0.0 } else { v };
-            mat[i * m + j] = v;
-            mat[j * m + i] = v;
+    let kind = match (method, pairwise) {
+        (CorrMethod::Pearson, false) => "corr_matrix:pearson",
+        (CorrMethod::Spearman, false) => "corr_matrix:spearman",
+        (CorrMethod::Kendall, false) => "corr_matrix:kendall",
+        (CorrMethod::Pearson, true) => "corr_matrix:pearson:pairwise",
+        (CorrMethod::Spearman, true) => "corr_matrix:spearman:pairwise",
+        (CorrMethod::Kendall, true) => "corr_matrix:kendall:pairwise",
+    };
+    let key = CacheKey::of_series(kind, series.iter().map(Vec::as_slice));
+    fn done<T>(cancel: &CancelFlag, out: T) -> Result<T, ServiceError> {
+        match cancel.is_cancelled() {
+            true => Err(ServiceError::Cancelled),
+            false => Ok(out),
         }
     }
-
-    Json(CorrMatrixOut {
+    let (matrix, n_used) = match pairwise {
+        true => {
+            let out = state.cache.get_or_try_insert_with(key, || {
+                done(
+                    cancel,
+                    pairwise_complete_matrix(&series, method, cancel.guard(|_| {})),
+                )
+            })?;
+            (out.0.clone(), Some(out.1.clone()))
+        }
+        false => {
+            let mat = state.cache.get_or_try_insert_with(key, || {
+                done(
+                    cancel,
+                    correlation_matrix(&series, method, cancel.guard(|_| {})),
+                )
+            })?;
+            (mat.to_vec(), None)
+        }
+    };
+    let p_values = inp.p_values.unwrap_or(false);
+    let n_used = n_used.or_else(|| p_values.then(|| vec![series[0].len(); m * m]));
+    let (order, linkage) = match inp.cluster {
+        Some(linkage) => {
+            let (order, steps) = cluster_variables(&matrix, m, linkage);
+            (Some(order), Some(steps))
+        }
+        None => (None, None),
+    };
+    Ok(CorrMatrixOut {
         size: m,
         names: inp.names,
-        matrix: mat,
+        p_values: n_used
+            .as_deref()
+            .filter(|_| p_values)
+            .map(|n| p_value_matrix(method, &matrix, n)),
+        covariance: inp
+            .covariance
+            .unwrap_or(false)
+            .then(|| covariance_matrix_pairwise(&series)),
+        partial: inp
+            .partial
+            .unwrap_or(false)
+            .then(|| partial_correlation_matrix(&matrix, m)),
+        matrix,
+        n_used,
+        order,
+        linkage,
+        missing: Some(report),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx;
+
+    #[test]
+    fn matches_the_pairwise_kernels() {
+        let series = vec![
+            vec![1.0, 2.0, 3.0, 4.0, 5.5, 2.0],
+            vec![2.0, 1.0, 4.0, 3.0, 6.0, 2.0],
+            vec![9.0, 7.0, 7.0, 3.0, 1.0, 0.5],
+            vec![4.0; 6],
+        ];
+        type Kernel = fn(&[f64], &[f64]) -> f64;
+        let kernels: [(CorrMethod, Kernel); 3] = [
+            (CorrMethod::Pearson, pearson_correlation),
+            (CorrMethod::Spearman, spearman_rho),
+            (CorrMethod::Kendall, kendall_tau_b),
+        ];
+        for (method, kernel) in kernels {
+            let rows = AtomicUsize::new(0);
+            let mat = correlation_matrix(&series, method, |_| {
+                rows.fetch_add(1, Ordering::Relaxed);
+            });
+            assert_eq!(rows.into_inner(), 4);
+            for i in 0..4 {
+                assert_eq!(mat[i * 4 + i], 1.0);
+                for j in (i + 1)..4 {
+                    let want = kernel(&series[i], &series[j]);
+                    let got = mat[i * 4 + j];
+                    assert!(
+                        (got - want).abs() < 1e-12 || (got.is_nan() && want.is_nan()),
+                        "{method:?} ({i},{j}): {got} vs {want}"
+                    );
+                    assert_eq!(got.to_bits(), mat[j * 4 + i].to_bits());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pairwise_deletion_skips_missing_values_per_pair() {
+        let nan = f64::NAN;
+        let series = vec![
+            vec![1.0, 2.0, nan, 4.0, 5.0],
+            vec![2.0, 1.0, 4.0, nan, 6.0],
+            vec![5.0, 3.0, 4.0, 1.0, 0.0],
+        ];
+        let (mat, n) = pairwise_complete_matrix(&series, CorrMethod::Pearson, |_| {});
+        assert_eq!(n, [4, 3, 4, 3, 4, 4, 4, 4, 5]);
+        let pair = [1.0, 2.0, 5.0];
+        approx!(mat[1], pearson_correlation(&pair, &[2.0, 1.0, 6.0]), 1e-12);
+        approx!(
+            mat[2 * 3],
+            pearson_correlation(&[1.0, 2.0, 4.0, 5.0], &[5.0, 3.0, 1.0, 0.0]),
+            1e-12
+        );
+        assert_eq!(mat[1].to_bits(), mat[3].to_bits());
+
+        let p = p_value_matrix(CorrMethod::Pearson, &mat, &n);
+        approx!(p[5], correlation_p_value(mat[5], 4), 1e-15);
+        // a pair of two leaves nothing to test
+        let (mat, n) = pairwise_complete_matrix(
+            &[vec![1.0, 2.0, nan], vec![nan, 3.0, 4.0]],
+            CorrMethod::Spearman,
+            |_| {},
+        );
+        assert_eq!(n[1], 1);
+        assert!(mat[1].is_nan());
+        assert!(p_value_matrix(CorrMethod::Spearman, &mat, &n)[1].is_nan());
+    }
+
+    #[test]
+    fn clustering_groups_correlated_variables() {
+        // 0 and 2 move together, as do 1 and 3; 4 is constant
+        let nan = f64::NAN;
+        #[rustfmt::skip]
+        let matrix = [
+            1.0, 0.1, 0.9, 0.0, nan,
+            0.1, 1.0, 0.2, 0.8, nan,
+            0.9, 0.2, 1.0, 0.1, nan,
+            0.0, 0.8, 0.1, 1.0, nan,
+            nan, nan, nan, nan, 1.0,
+        ];
+        let (order, steps) = cluster_variables(&matrix, 5, CorrLinkage::Average);
+        assert_eq!(order, [4, 0, 2, 1, 3]);
+        assert_eq!((steps[0].left, steps[0].right), (0, 2));
+        approx!(steps[0].distance, 0.1, 1e-12);
+        assert_eq!((steps[1].left, steps[1].right), (1, 3));
+        assert_eq!((steps[2].left, steps[2].right, steps[2].size), (5, 6, 4));
+        approx!(steps[2].distance, 0.9, 1e-12);
+        assert_eq!((steps[3].left, steps[3].right, steps[3].size), (4, 7, 5));
+        approx!(steps[3].distance, 1.0, 1e-12);
+    }
+
+    #[test]
+    fn cancelled_matrices_are_abandoned_and_not_cached() {
+        let state = AppState::default();
+        let inp = || CorrMatrixIn {
+            series: vec![vec![1.0, 2.0, 4.0], vec![3.0, 1.0, 0.0]],
+            names: None,
+            method: None,
+            missing: None,
+            p_values: None,
+            cluster: None,
+            covariance: None,
+            partial: None,
+        };
+        let cancel = CancelFlag::default();
+        cancel.cancel();
+        let err = corr_matrix(&state, inp(), &cancel).unwrap_err();
+        assert!(matches!(err, ServiceError::Cancelled));
+        assert_eq!(state.cache.stats().entries, 0);
+
+        let out = corr_matrix(&state, inp(), &CancelFlag::default()).unwrap();
+        assert_eq!(out.size, 2);
+        assert_eq!(state.cache.stats().entries, 1);
+    }
+}
@@ -1,73 +1,332 @@
 //! /stats/distribution
 
 use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::{kept, resolve},
+    routes::export::{FormatQuery, OutputFormat, Tabular},
+    state::AppState,
     stats::prelude::*,
-    types::{DistIn, DistOut},
+    types::{ApproxOut, BinScale, DistIn, DistOut, ErrorResponse, KdeOut, ShapeSe},
+    validate::{Valid, positive},
 };
-use axum::Json;
+use axum::extract::State;
+use rand::{SeedableRng, rngs::StdRng};
+use std::sync::Arc;
 
 /// Derive histogram, quantiles, and shape statistics (skew, kurtosis, entropy).
 ///
-/// - **Bins**: defaults to 10, min 2
-/// - **Quantiles**: defaults to `[0.25, 0.5, 0.75]`
+/// - **Bins**: defaults to 10; must be in `2..=10000`. `scale: "log"` spaces
+///   the edges geometrically (every value must be positive), and explicit
+///   `edges` replace both; values outside them are counted in `outside`
+/// - **Quantiles**: defaults to `[0.25, 0.5, 0.75]`; each must be in `[0, 1]`.
+///   `quantile_method` picks the definition (`r1`–`r9` or `nearest_rank`,
+///   default `r7`)
+/// - **Validation**: `422` naming the field (`/bins`, `/quantiles/i`, `/values`)
 /// - **Edge cases**: when range is degenerate, all mass in first bin
-pub async fn stats_distribution(Json(inp): Json<DistIn>) -> Json<DistOut> {
-    let values = inp.values;
-    let n = values.len();
-    if n == 0 {
-        return Json(DistOut {
-            counts: vec![],
-            edges: vec![],
-            quantiles: vec![],
-            skewness: None,
-            excess_kurtosis: None,
-            entropy_bits: None,
+/// - **Missing**: `null`s are settled by `missing` (default `drop`)
+/// - **Approx**: `approx: true` reads quantiles, skewness and kurtosis off
+///   a uniform sample of [`SKETCH_SIZE`] values when the input is larger
+///   (the histogram and entropy stay exact); see `approx` in the response
+/// - **Density**: `density: true` adds per-bin `count / (n · width)`, so
+///   the bars integrate to 1; `kde: true` adds a Gaussian KDE at the bin
+///   centers (`bandwidth` or Silverman's rule), drawn from the same values
+///   as the quantiles. Both are omitted when the range has no spread
+/// - **Weights**: `weights` counts each value that many times (e.g. the
+///   counts of pre-aggregated `(value, count)` pairs); `counts` still
+///   counts entries, `weights` sums them per bin, and quantiles, moments,
+///   entropy, `density` and `kde` are weighted. Not with `approx`, and
+///   quantiles are then `r7`
+/// - **Standard errors**: `shape_se` adds `skewness_se` and
+///   `excess_kurtosis_se`, either `analytic` (exact for normal data of the
+///   same size; with `weights`, the total weight is the size) or
+///   `bootstrap` over `resamples` resamples (default 1000, at most 10000,
+///   drawn from `seed`)
+/// - **Export**: `?format=csv|tsv` (or `Accept: text/csv`) returns the
+///   histogram as `lower,upper,count` rows
+#[utoipa::path(
+    post,
+    path = "/stats/distribution",
+    tag = "stats",
+    summary = "Histogram, quantiles, skew/kurtosis, entropy",
+    request_body = DistIn,
+    params(FormatQuery),
+    responses(
+        (status = 200, description = "OK", content(
+            (DistOut = "application/json"),
+            (String = "text/csv"),
+            (String = "text/tab-separated-values")
+        )),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_distribution(
+    State(state): State<Arc<AppState>>,
+    fmt: OutputFormat,
+    Valid(inp): Valid<DistIn>,
+) -> Result<(NUsed, Tabular<DistOut>), ServiceError> {
+    let n = kept(&inp.values, inp.missing.unwrap_or_default());
+    let size = match inp.shape_se {
+        Some(ShapeSe::Bootstrap) => inp.values.len() * inp.resamples.unwrap_or(1000),
+        _ => inp.values.len(),
+    };
+    let seed = state.config.seed_for(inp.seed);
+    let out = state
+        .compute
+        .run(size, move |cancel| distribution(inp, seed, cancel))
+        .await?;
+    Ok((NUsed(n), Tabular(fmt, out)))
+}
+
+/// Body of [`stats_distribution`] for an already validated request; `seed`
+/// draws the `approx` sample and the bootstrap resamples, which stop early
+/// once `cancel` is set.
+pub(crate) fn distribution(
+    mut inp: DistIn,
+    seed: u64,
+    cancel: &CancelFlag,
+) -> Result<DistOut, ServiceError> {
+    let r = resolve(
+        std::mem::take(&mut inp.values),
+        inp.missing.unwrap_or_default(),
+    )?;
+    let values = r.values;
+    if values.is_empty() {
+        return Ok(DistOut {
+            missing: Some(r.report),
+            ..empty()
+        });
+    }
+    if let Some(ws) = inp.weights.take() {
+        // weights follow their values through `drop`
+        let ws: Vec<f64> = r.origin.iter().map(|&i| ws[i]).collect();
+        return Ok(DistOut {
+            missing: Some(r.report),
+            ..weighted(inp, &values, &ws)?
         });
     }
 
     let bins = inp.bins.unwrap_or(10).max(2);
-    let lo = min(&values);
-    let hi = max(&values);
-    let width = (hi - lo) / bins as f64;
 
-    let mut counts = vec![0usize; bins];
-    if width == 0.0 {
-        counts[0] = n;
-    } else {
-        for &x in &values {
-            let mut b = ((x - lo) / width).floor() as usize;
-            if b >= bins {
-                b = bins - 1;
+    let qs = inp.quantiles.unwrap_or_else(|| vec![0.25, 0.5, 0.75]);
+    let sketch = inp
+        .approx
+        .unwrap_or(false)
+        .then(|| QuantileSketch::new(&values, SKETCH_SIZE, seed))
+        .filter(|s| !s.is_exact());
+    let exact;
+    let ascending = match &sketch {
+        Some(s) => s.sorted(),
+        None => {
+            exact = sorted(&values);
+            &exact
+        }
+    };
+    // The sample is uniform, so its moments estimate the population's
+    let shape = if sketch.is_some() { ascending } else { &values };
+    let (hist, outside) = match (inp.edges, inp.scale.unwrap_or_default()) {
+        (Some(edges), _) => {
+            let (counts, outside) = histogram_with_edges(&values, &edges);
+            ((counts, edges), Some(outside))
+        }
+        (None, BinScale::Log) => {
+            // imputed fills may be non-positive even when the input was not
+            positive("/values", &values)?;
+            (log_histogram(&values, bins), None)
+        }
+        (None, BinScale::Linear) => (histogram(&values, bins), None),
+    };
+    let method = inp.quantile_method.unwrap_or_default().into();
+    let out = assemble(shape, ascending, hist, qs, method);
+    let density = inp
+        .density
+        .unwrap_or(false)
+        .then(|| bin_densities(&out.counts, &out.edges))
+        .filter(|d| d.iter().all(|h| h.is_finite()));
+    let kde = inp
+        .kde
+        .unwrap_or(false)
+        .then(|| {
+            inp.bandwidth
+                .unwrap_or_else(|| silverman_bandwidth_sorted(ascending))
+        })
+        .filter(|&h| h > 0.0 && out.edges.first() < out.edges.last())
+        .map(|bandwidth| {
+            let centers = bin_centers(&out.edges);
+            KdeOut {
+                bandwidth,
+                density: gaussian_kde_sorted(ascending, &centers, bandwidth),
+                centers,
             }
-            counts[b] += 1;
+        });
+    let (skewness_se, excess_kurtosis_se) = match inp.shape_se {
+        None => (None, None),
+        Some(ShapeSe::Analytic) => analytic_se(shape.len() as f64),
+        Some(ShapeSe::Bootstrap) => {
+            let resamples = inp.resamples.unwrap_or(1000);
+            // same seed for both, so they share their resamples
+            let se = |stat: fn(&[f64]) -> f64| {
+                let mut rng = StdRng::seed_from_u64(seed);
+                o(bootstrap_se(
+                    shape,
+                    stat,
+                    resamples,
+                    &mut rng,
+                    cancel.guard(|_| {}),
+                ))
+            };
+            let ses = (se(skewness), se(excess_kurtosis));
+            if cancel.is_cancelled() {
+                return Err(ServiceError::Cancelled);
+            }
+            ses
         }
-    }
+    };
+    Ok(DistOut {
+        missing: Some(r.report),
+        approx: sketch.as_ref().map(ApproxOut::of),
+        skewness_se,
+        excess_kurtosis_se,
+        density,
+        kde,
+        outside,
+        ..out
+    })
+}
 
-    let mut edges = Vec::with_capacity(bins + 1);
-    for i in 0..=bins {
-        edges.push(lo + i as f64 * width);
+/// [`distribution`] of `values` counted by their frequency weights `ws`.
+fn weighted(inp: DistIn, values: &[f64], ws: &[f64]) -> Result<DistOut, ServiceError> {
+    if total_weight(ws) <= 0.0 {
+        return Ok(empty());
     }
+    let bins = inp.bins.unwrap_or(10).max(2);
+    let (counts, edges, sums, outside) = match (inp.edges, inp.scale.unwrap_or_default()) {
+        (Some(edges), _) => {
+            let (counts, outside) = histogram_with_edges(values, &edges);
+            let (sums, outside_weight) = weighted_histogram_with_edges(values, ws, &edges);
+            (counts, edges, sums, Some((outside, outside_weight)))
+        }
+        (None, BinScale::Log) => {
+            positive("/values", values)?;
+            let (counts, edges) = log_histogram(values, bins);
+            (
+                counts,
+                edges,
+                weighted_log_histogram(values, ws, bins).0,
+                None,
+            )
+        }
+        (None, BinScale::Linear) => {
+            let (counts, edges) = histogram(values, bins);
+            (counts, edges, weighted_histogram(values, ws, bins).0, None)
+        }
+    };
+    let pairs = sorted_pairs(values, ws);
+    let total = total_weight(&sums);
+    let probs: Vec<f64> = sums.iter().map(|&s| s / total).collect();
+    let density = inp
+        .density
+        .unwrap_or(false)
+        .then(|| weighted_bin_densities(&sums, &edges))
+        .filter(|d| d.iter().all(|h| h.is_finite()));
+    let kde = inp
+        .kde
+        .unwrap_or(false)
+        .then(|| {
+            inp.bandwidth
+                .unwrap_or_else(|| weighted_silverman_bandwidth_sorted(&pairs))
+        })
+        .filter(|&h| h > 0.0 && edges.first() < edges.last())
+        .map(|bandwidth| {
+            let centers = bin_centers(&edges);
+            KdeOut {
+                bandwidth,
+                density: weighted_gaussian_kde_sorted(&pairs, &centers, bandwidth),
+                centers,
+            }
+        });
+    let (skewness_se, excess_kurtosis_se) = match inp.shape_se {
+        Some(_) => analytic_se(total_weight(ws)),
+        None => (None, None),
+    };
+    Ok(DistOut {
+        quantiles: inp
+            .quantiles
+            .unwrap_or_else(|| vec![0.25, 0.5, 0.75])
+            .into_iter()
+            .map(|p| (p, weighted_quantile_sorted(&pairs, p)))
+            .collect(),
+        skewness: o(weighted_skewness(values, ws)),
+        excess_kurtosis: o(weighted_excess_kurtosis(values, ws)),
+        skewness_se,
+        excess_kurtosis_se,
+        entropy_bits: o(entropy_bits(&probs)),
+        counts,
+        edges,
+        weights: Some(sums),
+        density,
+        kde,
+        outside: outside.map(|(n, _)| n),
+        outside_weight: outside.map(|(_, w)| w),
+        ..empty()
+    })
+}
 
-    let qs = inp.quantiles.unwrap_or_else(|| vec![0.25, 0.5, 0.75]);
-    let quantiles = qs.into_iter().map(|p| (p, quantile(&values, p))).collect();
+/// Normal-theory standard errors of skewness and excess kurtosis at sample
+/// size `n`.
+fn analytic_se(n: f64) -> (Option<f64>, Option<f64>) {
+    (o(skewness_se(n)), o(excess_kurtosis_se(n)))
+}
 
-    let sk = skewness(&values);
-    let ek = excess_kurtosis(&values);
-    let total = n as f64;
-    let probs: Vec<f64> = counts.iter().map(|&c| c as f64 / total).collect();
-    let h = entropy_bits(&probs);
+#[inline]
+fn o(x: f64) -> Option<f64> {
+    if x.is_nan() { None } else { Some(x) }
+}
 
-    #[inline]
-    fn o(x: f64) -> Option<f64> {
-        if x.is_nan() { None } else { Some(x) }
+/// Response for input with no values left.
+pub(crate) fn empty() -> DistOut {
+    DistOut {
+        counts: vec![],
+        weights: None,
+        edges: vec![],
+        quantiles: vec![],
+        skewness: None,
+        excess_kurtosis: None,
+        skewness_se: None,
+        excess_kurtosis_se: None,
+        entropy_bits: None,
+        missing: None,
+        approx: None,
+        density: None,
+        kde: None,
+        outside: None,
+        outside_weight: None,
     }
+}
 
-    Json(DistOut {
+/// Response for non-empty input from its histogram, an ascending copy (for
+/// the quantiles `qs`, under `method`) and the values the moments are taken
+/// over.
+pub(crate) fn assemble(
+    shape: &[f64],
+    ascending: &[f64],
+    (counts, edges): (Vec<usize>, Vec<f64>),
+    qs: Vec<f64>,
+    method: QuantileMethod,
+) -> DistOut {
+    let total = counts.iter().sum::<usize>() as f64;
+    let probs: Vec<f64> = counts.iter().map(|&c| c as f64 / total).collect();
+    DistOut {
+        quantiles: qs
+            .into_iter()
+            .map(|p| (p, quantile_sorted_by(ascending, p, method)))
+            .collect(),
+        skewness: o(skewness(shape)),
+        excess_kurtosis: o(excess_kurtosis(shape)),
+        entropy_bits: o(entropy_bits(&probs)),
         counts,
         edges,
-        quantiles,
-        skewness: o(sk),
-        excess_kurtosis: o(ek),
-        entropy_bits: o(h),
-    })
+        ..empty()
+    }
 }
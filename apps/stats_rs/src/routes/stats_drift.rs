@@ -0,0 +1,109 @@
+//! /stats/drift
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::{kept, resolve},
+    state::AppState,
+    stats::prelude::*,
+    types::{DriftIn, DriftOut, DriftReference, DriftWindow, ErrorResponse},
+    validate::Valid,
+};
+use axum::{Json, extract::State};
+use std::sync::Arc;
+
+#[inline]
+fn o(x: f64) -> Option<f64> {
+    if x.is_nan() { None } else { Some(x) }
+}
+
+/// Profile how the distribution of a time-ordered series drifts over time.
+///
+/// - The series is cut into windows of `window` values starting every `step`
+///   values (default `window`, so consecutive windows do not overlap); a
+///   trailing remainder shorter than `window` is left out
+/// - Each window is compared with the `reference` window, the `first`
+///   (default) or the `previous` one: `psi` over `bins` (default 10) bins cut
+///   at the reference's quantiles, the two-sample KS statistic `ks` with its
+///   p-value, and the first Wasserstein distance `wasserstein`
+/// - The first window has no reference, so its metrics are `null`; `start`
+///   and `end` give every window's input positions for the x-axis
+/// - `null`s are settled by `missing` (default `drop`) before windowing
+#[utoipa::path(
+    post,
+    path = "/stats/drift",
+    tag = "stats",
+    summary = "Sliding-window drift profile (PSI, KS, Wasserstein)",
+    request_body = DriftIn,
+    responses(
+        (status = 200, description = "OK", body = DriftOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_drift(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<DriftIn>,
+) -> Result<(NUsed, Json<DriftOut>), ServiceError> {
+    let n = kept(&inp.values, inp.missing.unwrap_or_default());
+    let step = inp.step.unwrap_or(inp.window);
+    let windows = (inp.values.len() - inp.window) / step + 1;
+    let size = windows * inp.window;
+    let out = state
+        .compute
+        .run(size, move |cancel| drift(inp, cancel))
+        .await?;
+    Ok((NUsed(n), Json(out)))
+}
+
+/// Body of [`stats_drift`] for an already validated request.
+fn drift(inp: DriftIn, cancel: &CancelFlag) -> Result<DriftOut, ServiceError> {
+    let r = resolve(inp.values, inp.missing.unwrap_or_default())?;
+    let xs = r.values;
+    let (width, step) = (inp.window, inp.step.unwrap_or(inp.window));
+    let bins = inp.bins.unwrap_or(10);
+    let reference = inp.reference.unwrap_or_default();
+
+    let starts: Vec<usize> = match xs.len() >= width {
+        true => (0..=xs.len() - width).step_by(step).collect(),
+        false => vec![],
+    };
+    let mut windows = Vec::with_capacity(starts.len());
+    for (k, &at) in starts.iter().enumerate() {
+        if cancel.is_cancelled() {
+            return Err(ServiceError::Cancelled);
+        }
+        let current = &xs[at..at + width];
+        let base = match (k, reference) {
+            (0, _) => None,
+            (_, DriftReference::First) => Some(starts[0]),
+            (_, DriftReference::Previous) => Some(starts[k - 1]),
+        };
+        let (psi, ks, ks_p, w) = match base {
+            None => (f64::NAN, f64::NAN, f64::NAN, f64::NAN),
+            Some(b) => {
+                let base = &xs[b..b + width];
+                let (d, p) = ks_two_sample(base, current);
+                (
+                    psi_quantile_bins(base, current, bins),
+                    d,
+                    p,
+                    wasserstein_1(base, current),
+                )
+            }
+        };
+        windows.push(DriftWindow {
+            start: r.origin[at],
+            end: r.origin[at + width - 1] + 1,
+            psi: o(psi),
+            ks: o(ks),
+            ks_p_value: o(ks_p),
+            wasserstein: o(w),
+        });
+    }
+    Ok(DriftOut {
+        reference,
+        windows,
+        missing: Some(r.report),
+    })
+}
@@ -1,24 +1,84 @@
 //! /stats/ecdf
 
-use crate::types::{EcdfIn, EcdfOut};
-use axum::Json;
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::{kept, resolve},
+    routes::export::{FormatQuery, OutputFormat, Tabular},
+    state::AppState,
+    stats::{QuantileSketch, SKETCH_SIZE},
+    types::{ApproxOut, EcdfIn, EcdfOut, ErrorResponse},
+    validate::Valid,
+    window::{pick, select},
+};
+use axum::extract::State;
+use std::sync::Arc;
 
 /// Compute empirical CDF (ECDF), with optional downsampling for large outputs.
 ///
-/// - Input NaN/Inf are filtered out.
+/// - Input `null`s are settled by `missing` (default `drop`).
 /// - Output `(xs, ps)` are unique sorted values and their cumulative probabilities.
-/// - If `max_points` is set, the output is downsampled uniformly (end point preserved).
-pub async fn stats_ecdf(Json(inp): Json<EcdfIn>) -> Json<EcdfOut> {
-    let mut xs = inp
-        .values
-        .into_iter()
-        .filter(|v| v.is_finite())
-        .collect::<Vec<_>>();
-    xs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+/// - `window` pages the points (`offset`, `limit`) and downsamples the page
+///   to `max_points` (≥ 2), evenly spaced or with `lttb`; the top-level
+///   `max_points` is shorthand for `window.max_points`. The response's
+///   `window` reports which points were returned.
+/// - `?format=csv|tsv` (or `Accept: text/csv`) returns `x,p` rows.
+/// - The sorted copy comes from the shared cache.
+/// - `approx: true` builds the ECDF from a uniform sample of
+///   [`SKETCH_SIZE`] values when the input is larger; every `p` is then
+///   within `approx.rank_error` of the exact ECDF at that `x`.
+#[utoipa::path(
+    post,
+    path = "/stats/ecdf",
+    tag = "stats",
+    summary = "Empirical CDF (optionally downsampled)",
+    request_body = EcdfIn,
+    params(FormatQuery),
+    responses(
+        (status = 200, description = "OK", content(
+            (EcdfOut = "application/json"),
+            (String = "text/csv"),
+            (String = "text/tab-separated-values")
+        )),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_ecdf(
+    State(state): State<Arc<AppState>>,
+    fmt: OutputFormat,
+    Valid(inp): Valid<EcdfIn>,
+) -> Result<(NUsed, Tabular<EcdfOut>), ServiceError> {
+    let n = kept(&inp.values, inp.missing.unwrap_or_default());
+    Ok((NUsed(n), Tabular(fmt, ecdf(&state, inp)?)))
+}
+
+/// Body of [`stats_ecdf`] for an already validated request.
+pub(crate) fn ecdf(state: &AppState, inp: EcdfIn) -> Result<EcdfOut, ServiceError> {
+    let seed = state.config.seed_for(inp.seed);
+    let r = resolve(inp.values, inp.missing.unwrap_or_default())?;
+    let missing = Some(r.report);
+    let sketch = inp
+        .approx
+        .unwrap_or(false)
+        .then(|| QuantileSketch::new(&r.values, SKETCH_SIZE, seed))
+        .filter(|s| !s.is_exact());
+    let approx = sketch.as_ref().map(ApproxOut::of);
+    let cached;
+    let xs: &[f64] = match &sketch {
+        Some(s) => s.sorted(),
+        None => {
+            cached = state.cache.sorted(&r.values);
+            &cached
+        }
+    };
     if xs.is_empty() {
-        return Json(EcdfOut {
+        return Ok(EcdfOut {
             xs: vec![],
             ps: vec![],
+            window: None,
+            missing,
+            approx,
         });
     }
 
@@ -37,22 +97,23 @@ pub async fn stats_ecdf(Json(inp): Json<EcdfIn>) -> Json<EcdfOut> {
         i = j;
     }
 
-    if let Some(max_pts) = inp.max_points.filter(|&m| m > 1 && uniq_x.len() > m) {
-        let step = (uniq_x.len() as f64 / max_pts as f64).ceil() as usize;
-        let mut dx = Vec::with_capacity(max_pts);
-        let mut dp = Vec::with_capacity(max_pts);
-        let mut k = 0usize;
-        while k < uniq_x.len() {
-            dx.push(uniq_x[k]);
-            dp.push(ps[k]);
-            k = k.saturating_add(step);
-        }
-        if *dx.last().unwrap() != *uniq_x.last().unwrap() {
-            dx.push(*uniq_x.last().unwrap());
-            dp.push(*ps.last().unwrap());
-        }
-        return Json(EcdfOut { xs: dx, ps: dp });
+    let mut window = inp.window.unwrap_or_default();
+    window.max_points = window.max_points.or(inp.max_points);
+    if let Some((idx, out)) = select(&window, &uniq_x, &ps) {
+        return Ok(EcdfOut {
+            xs: pick(&uniq_x, &idx),
+            ps: pick(&ps, &idx),
+            window: Some(out),
+            missing,
+            approx,
+        });
     }
 
-    Json(EcdfOut { xs: uniq_x, ps })
+    Ok(EcdfOut {
+        xs: uniq_x,
+        ps,
+        window: None,
+        missing,
+        approx,
+    })
 }
@@ -0,0 +1,83 @@
+//! /stats/entropy
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::resolve,
+    stats::prelude::*,
+    types::{EntropyIn, EntropyMethod, EntropyOut, ErrorResponse},
+    validate::{Valid, invalid},
+};
+use axum::Json;
+use std::f64::consts::LN_2;
+
+/// Estimate the Shannon entropy of a raw numeric series, in bits and nats.
+///
+/// - `histogram` (default) bins the values into `bins` equal-width bins
+///   (default `10`) and returns the entropy of the bin frequencies, at most
+///   `log2(bins)` bits
+/// - `knn` returns the Kozachenko–Leonenko differential entropy from each
+///   value's distance to its `k`-th nearest neighbour (default `3`); it can
+///   be negative, and is `null` when a value repeats more than `k` times
+/// - `k` must be in `1..=100` and below the number of values (`422` at `/k`)
+/// - Returns `null` entropies when `missing=drop` leaves nothing
+/// - `null`s are settled by `missing` (default `drop`)
+#[utoipa::path(
+    post,
+    path = "/stats/entropy",
+    tag = "stats",
+    summary = "Entropy of a numeric series (histogram or k-NN)",
+    request_body = EntropyIn,
+    responses(
+        (status = 200, description = "OK", body = EntropyOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_entropy(
+    Valid(inp): Valid<EntropyIn>,
+) -> Result<(NUsed, Json<EntropyOut>), ServiceError> {
+    let r = resolve(inp.values, inp.missing.unwrap_or_default())?;
+    let missing = Some(r.report);
+    let xs = r.values;
+    let n = xs.len();
+    let method = inp.method.unwrap_or_default();
+
+    let (bits, nats, bins, k) = match method {
+        EntropyMethod::Histogram => {
+            let bins = inp.bins.unwrap_or(10);
+            let (counts, _) = histogram(&xs, bins);
+            let bits = histogram_entropy_bits(&counts);
+            (bits, bits * LN_2, Some(bins), None)
+        }
+        EntropyMethod::Knn => {
+            let k = inp.k.unwrap_or(3);
+            if n > 0 && k >= n {
+                return Err(invalid(
+                    "/k",
+                    format!("must be below the number of values ({n}), got {k}"),
+                ));
+            }
+            let nats = knn_entropy_nats(&xs, k);
+            (nats / LN_2, nats, None, Some(k))
+        }
+    };
+
+    #[inline]
+    fn o(x: f64) -> Option<f64> {
+        if x.is_finite() { Some(x) } else { None }
+    }
+
+    Ok((
+        NUsed(n),
+        Json(EntropyOut {
+            method,
+            bits: o(bits),
+            nats: o(nats),
+            n,
+            bins,
+            k,
+            missing,
+        }),
+    ))
+}
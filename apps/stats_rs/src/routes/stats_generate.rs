@@ -0,0 +1,119 @@
+//! /stats/generate
+
+use crate::{
+    error::ServiceError,
+    state::AppState,
+    stats::prelude::*,
+    types::{ErrorResponse, GenerateIn, GenerateOut, SampleDistribution},
+    validate::Valid,
+};
+use axum::{Json, extract::State};
+use rand::{SeedableRng, rngs::StdRng};
+use std::sync::Arc;
+
+/// Values drawn between cancellation checks.
+const CHUNK: usize = 1 << 16;
+
+impl SampleDistribution {
+    /// The kernel sampler for this distribution, defaults filled in.
+    pub fn sampler(self) -> Sampler {
+        match self {
+            SampleDistribution::Normal { mean, std_dev } => Sampler::Normal {
+                mean: mean.unwrap_or(0.0),
+                std_dev: std_dev.unwrap_or(1.0),
+            },
+            SampleDistribution::Uniform { low, high } => Sampler::Uniform {
+                low: low.unwrap_or(0.0),
+                high: high.unwrap_or(1.0),
+            },
+            SampleDistribution::Exponential { rate } => Sampler::Exponential {
+                rate: rate.unwrap_or(1.0),
+            },
+            SampleDistribution::Gamma { shape, scale } => Sampler::Gamma {
+                shape,
+                scale: scale.unwrap_or(1.0),
+            },
+            SampleDistribution::Binomial { trials, p } => Sampler::Binomial { trials, p },
+            SampleDistribution::Poisson { lambda } => Sampler::Poisson { lambda },
+        }
+    }
+}
+
+impl From<Sampler> for SampleDistribution {
+    fn from(s: Sampler) -> Self {
+        match s {
+            Sampler::Normal { mean, std_dev } => SampleDistribution::Normal {
+                mean: Some(mean),
+                std_dev: Some(std_dev),
+            },
+            Sampler::Uniform { low, high } => SampleDistribution::Uniform {
+                low: Some(low),
+                high: Some(high),
+            },
+            Sampler::Exponential { rate } => SampleDistribution::Exponential { rate: Some(rate) },
+            Sampler::Gamma { shape, scale } => SampleDistribution::Gamma {
+                shape,
+                scale: Some(scale),
+            },
+            Sampler::Binomial { trials, p } => SampleDistribution::Binomial { trials, p },
+            Sampler::Poisson { lambda } => SampleDistribution::Poisson { lambda },
+        }
+    }
+}
+
+/// Draw a reproducible sample from a standard distribution.
+///
+/// - `distribution.name` is `normal` (`mean`, `std_dev`), `uniform` (`low`,
+///   `high`), `exponential` (`rate`), `gamma` (`shape`, `scale`), `binomial`
+///   (`trials`, `p`) or `poisson` (`lambda`)
+/// - The same `seed` (default `STATS_SEED`) and parameters always give the
+///   same values; normal draws invert the service's own normal quantile
+/// - `expected_mean` and `expected_variance` are the distribution's, for
+///   checking the sample against
+/// - Out-of-range parameters are rejected (`422` at
+///   `/distribution/<parameter>`), as is an `n` of 0 or past the value limit
+#[utoipa::path(
+    post,
+    path = "/stats/generate",
+    tag = "stats",
+    summary = "Seeded random sample from a standard distribution",
+    request_body = GenerateIn,
+    responses(
+        (status = 200, description = "OK", body = GenerateOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_generate(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<GenerateIn>,
+) -> Result<Json<GenerateOut>, ServiceError> {
+    let seed = state.config.seed_for(inp.seed);
+    let out = state
+        .compute
+        .run(inp.n, move |cancel| generate(inp, seed, cancel))
+        .await?;
+    Ok(Json(out))
+}
+
+/// Body of [`stats_generate`] for an already validated request.
+fn generate(inp: GenerateIn, seed: u64, cancel: &CancelFlag) -> Result<GenerateOut, ServiceError> {
+    let sampler = inp.distribution.sampler();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut values = Vec::with_capacity(inp.n);
+    while values.len() < inp.n {
+        if cancel.is_cancelled() {
+            return Err(ServiceError::Cancelled);
+        }
+        let take = (inp.n - values.len()).min(CHUNK);
+        values.extend((0..take).map(|_| sampler.sample(&mut rng)));
+    }
+    let (expected_mean, expected_variance) = sampler.moments();
+    Ok(GenerateOut {
+        distribution: sampler.into(),
+        seed,
+        values,
+        expected_mean,
+        expected_variance,
+    })
+}
@@ -1,42 +1,251 @@
 //! /stats/normalize
 
 use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::{kept, resolve},
+    state::AppState,
     stats::prelude::*,
-    types::{NormMethod, NormalizeIn, NormalizeOut},
+    types::{
+        ErrorResponse, NormApplyIn, NormGroup, NormMethod, NormParams, NormalizeIn, NormalizeOut,
+    },
+    validate::Valid,
+    window::{pick, select},
 };
-use axum::Json;
+use axum::{Json, extract::State};
+use std::{collections::HashMap, sync::Arc};
 
 /// Normalize a numeric vector using Z-score or min–max scaling.
 ///
 /// - Defaults to `Zscore`
-/// - Min–max range defaults to `(0.0, 1.0)`
-/// - Non-finite inputs are filtered out before normalization
-pub async fn stats_normalize(Json(inp): Json<NormalizeIn>) -> Json<NormalizeOut> {
-    let xs = inp
-        .values
-        .into_iter()
-        .filter(|v| v.is_finite())
-        .collect::<Vec<_>>();
+/// - Min–max range defaults to `(0.0, 1.0)`; must satisfy lower < upper
+/// - `null`s are settled by `missing` before normalization (default `drop`,
+///   which shortens the output; the imputing policies keep positions)
+/// - `window` pages and downsamples the output (see [`crate::window`]);
+///   `indices` then gives each returned value's input position
+/// - `params` holds the fitted transform (`mean`/`std` or `min`/`max`/`range`)
+///   for [`stats_normalize_apply`] and [`stats_normalize_inverse`]
+/// - `groups` labels each value; every group is scaled with parameters fitted
+///   to its own values, listed per group in `groups` instead of `params`.
+///   `null`s are settled over the whole series first
+#[utoipa::path(
+    post,
+    path = "/stats/normalize",
+    tag = "stats",
+    summary = "Normalize vector (z-score or min–max range)",
+    request_body = NormalizeIn,
+    responses(
+        (status = 200, description = "OK", body = NormalizeOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_normalize(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<NormalizeIn>,
+) -> Result<(NUsed, Json<NormalizeOut>), ServiceError> {
+    let n = kept(&inp.values, inp.missing.unwrap_or_default());
+    let size = inp.values.len();
+    Ok((
+        NUsed(n),
+        Json(state.compute.run(size, move |_| normalize(inp)).await?),
+    ))
+}
+
+/// Body of [`stats_normalize`] for an already validated request.
+pub(crate) fn normalize(inp: NormalizeIn) -> Result<NormalizeOut, ServiceError> {
+    let r = resolve(inp.values, inp.missing.unwrap_or_default())?;
+    let missing = Some(r.report);
+    let xs = r.values;
     if xs.is_empty() {
-        return Json(NormalizeOut { values: vec![] });
+        return Ok(NormalizeOut {
+            values: vec![],
+            params: None,
+            groups: inp.groups.map(|_| vec![]),
+            indices: None,
+            window: None,
+            missing,
+        });
     }
     let method = inp.method.unwrap_or(NormMethod::Zscore);
-
-    let out = match method {
-        NormMethod::Zscore => {
-            let mu = mean(&xs);
-            let sd = sample_std_dev(&xs, mu).max(1e-12);
-            xs.iter().map(|&x| (x - mu) / sd).collect::<Vec<_>>()
+    let range = inp.range.unwrap_or((0.0, 1.0));
+    let (out, params, groups): (Vec<f64>, _, _) = match &inp.groups {
+        None => {
+            let params = fit(&method, range, &xs);
+            (
+                xs.iter().map(|&x| params.apply(x)).collect(),
+                Some(params),
+                None,
+            )
         }
-        NormMethod::Minmax => {
-            let a = inp.range.unwrap_or((0.0, 1.0));
-            let (lo, hi) = (min(&xs), max(&xs));
-            let denom = (hi - lo).max(1e-12);
-            xs.iter()
-                .map(|&x| a.0 + (x - lo) * (a.1 - a.0) / denom)
-                .collect::<Vec<_>>()
+        Some(labels) => {
+            let (names, member) = group_index(labels, &r.origin);
+            let mut parts = vec![vec![]; names.len()];
+            for (&x, &g) in xs.iter().zip(&member) {
+                parts[g].push(x);
+            }
+            let fitted: Vec<NormParams> = parts.iter().map(|p| fit(&method, range, p)).collect();
+            let out = xs
+                .iter()
+                .zip(&member)
+                .map(|(&x, &g)| fitted[g].apply(x))
+                .collect();
+            let groups = names
+                .into_iter()
+                .zip(&parts)
+                .zip(fitted)
+                .map(|((label, part), params)| NormGroup {
+                    label,
+                    count: part.len(),
+                    params,
+                })
+                .collect();
+            (out, None, Some(groups))
         }
     };
 
-    Json(NormalizeOut { values: out })
+    let positions: Vec<f64> = r.origin.iter().map(|&i| i as f64).collect();
+    if let Some((idx, window)) = select(&inp.window.unwrap_or_default(), &positions, &out) {
+        return Ok(NormalizeOut {
+            values: pick(&out, &idx),
+            params,
+            groups,
+            indices: Some(pick(&r.origin, &idx)),
+            window: Some(window),
+            missing,
+        });
+    }
+
+    Ok(NormalizeOut {
+        values: out,
+        params,
+        groups,
+        indices: None,
+        window: None,
+        missing,
+    })
+}
+
+/// `method`'s transform fitted to `xs`.
+fn fit(method: &NormMethod, range: (f64, f64), xs: &[f64]) -> NormParams {
+    match method {
+        NormMethod::Zscore => {
+            let mu = mean(xs);
+            NormParams::Zscore {
+                mean: mu,
+                std: sample_std_dev(xs, mu),
+            }
+        }
+        NormMethod::Minmax => NormParams::Minmax {
+            min: min(xs),
+            max: max(xs),
+            range,
+        },
+    }
+}
+
+/// Distinct `labels` of the values left after missing-value handling, in
+/// order of first appearance, and the index of each value's label in them;
+/// `origin` maps those values back to their `labels` position.
+pub(crate) fn group_index(
+    labels: &[Option<String>],
+    origin: &[usize],
+) -> (Vec<String>, Vec<usize>) {
+    let mut names = vec![];
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut member = Vec::with_capacity(origin.len());
+    for &i in origin {
+        let label = labels[i].as_deref().unwrap_or_default();
+        let g = *seen.entry(label).or_insert_with(|| {
+            names.push(label.to_owned());
+            names.len() - 1
+        });
+        member.push(g);
+    }
+    (names, member)
+}
+
+/// Apply a transform fitted by [`stats_normalize`] to new data.
+///
+/// - `params` is the `params` object `/stats/normalize` returned, so test
+///   data is scaled with the training set's statistics
+/// - `null`s are settled by `missing` (default `drop`)
+#[utoipa::path(
+    post,
+    path = "/stats/normalize/apply",
+    tag = "stats",
+    summary = "Apply fitted normalization parameters to new values",
+    request_body = NormApplyIn,
+    responses(
+        (status = 200, description = "OK", body = NormalizeOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_normalize_apply(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<NormApplyIn>,
+) -> Result<(NUsed, Json<NormalizeOut>), ServiceError> {
+    let n = kept(&inp.values, inp.missing.unwrap_or_default());
+    let size = inp.values.len();
+    Ok((
+        NUsed(n),
+        Json(
+            state
+                .compute
+                .run(size, move |_| transform(inp, NormParams::apply))
+                .await?,
+        ),
+    ))
+}
+
+/// Map normalized values back to the original scale.
+///
+/// - Inverts `params` as returned by [`stats_normalize`], e.g. to report
+///   model predictions in the units of the training data
+/// - `null`s are settled by `missing` (default `drop`)
+#[utoipa::path(
+    post,
+    path = "/stats/normalize/inverse",
+    tag = "stats",
+    summary = "Undo fitted normalization parameters",
+    request_body = NormApplyIn,
+    responses(
+        (status = 200, description = "OK", body = NormalizeOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_normalize_inverse(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<NormApplyIn>,
+) -> Result<(NUsed, Json<NormalizeOut>), ServiceError> {
+    let n = kept(&inp.values, inp.missing.unwrap_or_default());
+    let size = inp.values.len();
+    Ok((
+        NUsed(n),
+        Json(
+            state
+                .compute
+                .run(size, move |_| transform(inp, NormParams::invert))
+                .await?,
+        ),
+    ))
+}
+
+/// Body of [`stats_normalize_apply`] and [`stats_normalize_inverse`], mapping
+/// each value through `f`.
+pub(crate) fn transform(
+    inp: NormApplyIn,
+    f: fn(&NormParams, f64) -> f64,
+) -> Result<NormalizeOut, ServiceError> {
+    let r = resolve(inp.values, inp.missing.unwrap_or_default())?;
+    Ok(NormalizeOut {
+        values: r.values.iter().map(|&x| f(&inp.params, x)).collect(),
+        params: Some(inp.params),
+        groups: None,
+        indices: None,
+        window: None,
+        missing: Some(r.report),
+    })
 }
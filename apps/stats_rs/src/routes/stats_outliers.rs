@@ -1,64 +1,203 @@
 //! /stats/outliers
 
 use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::{kept, resolve},
+    routes::stats_normalize::group_index,
     stats::prelude::*,
-    types::{OutlierMethod, OutliersIn, OutliersOut},
+    types::{
+        ErrorResponse, OutlierBounds, OutlierClean, OutlierGroup, OutlierMethod, OutliersIn,
+        OutliersOut,
+    },
+    validate::Valid,
 };
 use axum::Json;
 
 /// Detect outliers via Z-score or IQR rules.
 ///
 /// - `method` defaults to IQR
-/// - `threshold` (Z-score) defaults to `3.0`
-/// - Non-finite inputs are filtered out
-pub async fn stats_outliers(Json(inp): Json<OutliersIn>) -> Json<OutliersOut> {
-    let xs = inp
-        .values
-        .into_iter()
-        .filter(|v| v.is_finite())
-        .collect::<Vec<_>>();
+/// - `threshold` (Z-score) defaults to `3.0`; must be positive
+/// - `null`s are settled by `missing` (default `drop`); indices always refer
+///   to positions in the input array
+/// - `top_k` keeps only the `k` most extreme outliers, most extreme first,
+///   with their `scores` and the `total` detected; they are picked with a
+///   bounded heap, so the fliers are never sorted or returned in full
+/// - `bounds` gives the region outside which points are flagged (the Tukey
+///   fences, or `mean ± threshold · sd`), for shading rejection regions
+/// - `point_scores: true` scores every input position; `clean: "remove"` or
+///   `"winsorize"` returns the series without, or clipped at, its outliers
+/// - `groups` labels each value; the rule is then fitted within each group
+///   (its bounds listed per group in `groups` instead of `bounds`), while
+///   `top_k` still ranks the scores across groups. `null`s are settled over
+///   the whole series first
+#[utoipa::path(
+    post,
+    path = "/stats/outliers",
+    tag = "stats",
+    summary = "Outlier detection (IQR, z-score, etc.)",
+    request_body = OutliersIn,
+    responses(
+        (status = 200, description = "OK", body = OutliersOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_outliers(
+    Valid(inp): Valid<OutliersIn>,
+) -> Result<(NUsed, Json<OutliersOut>), ServiceError> {
+    let n = kept(&inp.values, inp.missing.unwrap_or_default());
+    Ok((NUsed(n), Json(outliers(inp)?)))
+}
+
+/// A point's score, and whether it makes the point an outlier.
+type Scorer = Box<dyn Fn(f64) -> (f64, bool)>;
+
+/// Body of [`stats_outliers`] for an already validated request.
+pub(crate) fn outliers(inp: OutliersIn) -> Result<OutliersOut, ServiceError> {
+    let len = inp.values.len();
+    let r = resolve(inp.values, inp.missing.unwrap_or_default())?;
+    let xs = r.values;
+    let point_scores = inp.point_scores.unwrap_or(false);
     if xs.is_empty() {
-        return Json(OutliersOut {
+        return Ok(OutliersOut {
             indices: vec![],
             values: vec![],
+            scores: inp.top_k.map(|_| vec![]),
+            total: inp.top_k.map(|_| 0),
+            bounds: None,
+            groups: inp.groups.map(|_| vec![]),
+            point_scores: point_scores.then(|| vec![None; len]),
+            cleaned: inp.clean.map(|_| vec![]),
+            missing: Some(r.report),
         });
     }
 
     let method = inp.method.unwrap_or(OutlierMethod::Iqr);
     let thr = inp.threshold.unwrap_or(3.0);
 
-    let mut idx = Vec::<usize>::new();
-    let mut vals = Vec::<f64>::new();
+    let (names, member) = match &inp.groups {
+        Some(labels) => {
+            let (names, member) = group_index(labels, &r.origin);
+            (Some(names), member)
+        }
+        None => (None, vec![0; xs.len()]),
+    };
+    let mut parts = vec![vec![]; names.as_ref().map_or(1, Vec::len)];
+    for (&x, &g) in xs.iter().zip(&member) {
+        parts[g].push(x);
+    }
+    let rules: Vec<(OutlierBounds, Scorer)> = parts.iter().map(|p| rule(&method, thr, p)).collect();
+    let score = |i: usize| rules[member[i]].1(xs[i]);
+    let flagged = (0..xs.len()).filter_map(|i| match score(i) {
+        (s, true) => Some((i, s)),
+        (_, false) => None,
+    });
+
+    let mut out = match inp.top_k {
+        None => {
+            let (idx, vals) = flagged.map(|(i, _)| (r.origin[i], xs[i])).unzip();
+            OutliersOut {
+                indices: idx,
+                values: vals,
+                scores: None,
+                total: None,
+                bounds: None,
+                groups: None,
+                point_scores: None,
+                cleaned: None,
+                missing: Some(r.report),
+            }
+        }
+        Some(k) => {
+            let mut total = 0;
+            let top = top_k(flagged.inspect(|_| total += 1), k);
+            OutliersOut {
+                indices: top.iter().map(|&(i, _)| r.origin[i]).collect(),
+                values: top.iter().map(|&(i, _)| xs[i]).collect(),
+                scores: Some(top.iter().map(|&(_, s)| s).collect()),
+                total: Some(total),
+                bounds: None,
+                groups: None,
+                point_scores: None,
+                cleaned: None,
+                missing: Some(r.report),
+            }
+        }
+    };
+    if point_scores {
+        let mut all = vec![None; len];
+        for (i, &at) in r.origin.iter().enumerate() {
+            all[at] = Some(score(i).0);
+        }
+        out.point_scores = Some(all);
+    }
+    out.cleaned = inp.clean.map(|clean| {
+        let kept = (0..xs.len()).filter_map(|i| match (score(i).1, clean) {
+            (false, _) => Some(xs[i]),
+            (true, OutlierClean::Remove) => None,
+            (true, OutlierClean::Winsorize) => {
+                let bounds = &rules[member[i]].0;
+                Some(xs[i].clamp(bounds.lower, bounds.upper))
+            }
+        });
+        kept.collect()
+    });
+    let mut bounds = rules.into_iter().map(|(b, _)| b);
+    match names {
+        None => out.bounds = bounds.next(),
+        Some(names) => {
+            let groups = names.into_iter().zip(&parts).zip(bounds);
+            out.groups = Some(
+                groups
+                    .map(|((label, part), bounds)| OutlierGroup {
+                        label,
+                        count: part.len(),
+                        bounds,
+                    })
+                    .collect(),
+            );
+        }
+    }
+    Ok(out)
+}
 
+/// `method`'s decision region over `xs` and the scorer behind it.
+fn rule(method: &OutlierMethod, thr: f64, xs: &[f64]) -> (OutlierBounds, Scorer) {
     match method {
         OutlierMethod::Zscore => {
-            let mu = mean(&xs);
-            let sd = sample_std_dev(&xs, mu).max(1e-12);
-            for (i, &x) in xs.iter().enumerate() {
-                let z = (x - mu) / sd;
-                if z.abs() >= thr {
-                    idx.push(i);
-                    vals.push(x);
-                }
-            }
+            let mu = mean(xs);
+            let sd = sample_std_dev(xs, mu).max(1e-12);
+            let bounds = OutlierBounds {
+                lower: mu - thr * sd,
+                upper: mu + thr * sd,
+                threshold: thr,
+            };
+            (
+                bounds,
+                Box::new(move |x| {
+                    let z = ((x - mu) / sd).abs();
+                    (z, z >= thr)
+                }),
+            )
         }
         OutlierMethod::Iqr => {
-            let q1 = quantile(&xs, 0.25);
-            let q3 = quantile(&xs, 0.75);
+            let (q1, _, q3) = quartiles(xs);
             let iqr_v = q3 - q1;
             let lo = q1 - 1.5 * iqr_v;
             let hi = q3 + 1.5 * iqr_v;
-            for (i, &x) in xs.iter().enumerate() {
-                if x < lo || x > hi {
-                    idx.push(i);
-                    vals.push(x);
-                }
-            }
+            let bounds = OutlierBounds {
+                lower: lo,
+                upper: hi,
+                threshold: 1.5,
+            };
+            (
+                bounds,
+                Box::new(move |x| {
+                    let s = ((q1 - x).max(x - q3) / iqr_v.max(1e-12)).max(0.0);
+                    (s, x < lo || x > hi)
+                }),
+            )
         }
     }
-
-    Json(OutliersOut {
-        indices: idx,
-        values: vals,
-    })
 }
@@ -1,37 +1,108 @@
 //! /stats/pairwise
 
 use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    fields::{Fields, FieldsQuery, Selected},
+    missing::resolve_series,
+    state::AppState,
     stats::prelude::*,
-    types::{PairIn, PairOut},
+    types::{CorrMethod, CorrTest, ErrorResponse, PairIn, PairOut},
+    validate::Valid,
 };
-use axum::Json;
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use std::sync::Arc;
+
+/// Metrics selectable with `?fields=`.
+pub const PAIR_FIELDS: &[&str] = &["covariance", "pearson", "spearman", "kendall"];
 
 /// Compute covariance and correlations (Pearson, Spearman, Kendall) for two vectors.
 ///
-/// Returns `None` metrics if lengths mismatch or vectors are empty.
-pub async fn stats_pairwise(Json(inp): Json<PairIn>) -> Json<PairOut> {
-    if inp.x.len() != inp.y.len() || inp.x.is_empty() {
-        return Json(PairOut {
+/// Empty or unequal-length vectors are rejected (`422`). With the default
+/// `missing=drop`, a position where either value is `null` is removed from
+/// both; metrics are `None` when nothing is left. Spearman's rho is computed from rank vectors held in the shared
+/// cache. `?fields=pearson,spearman` computes and returns only those metrics;
+/// leaving out `kendall` skips its O(n²) pass.
+///
+/// Each returned coefficient comes with a `*_test`: its two-sided p-value and
+/// a Fisher-z interval at `confidence` (default 0.95) over the `n` pairs used.
+#[utoipa::path(
+    post,
+    path = "/stats/pairwise",
+    tag = "stats",
+    summary = "Covariance and rank/linear correlations for two vectors",
+    request_body = PairIn,
+    params(FieldsQuery),
+    responses(
+        (status = 200, description = "OK", body = PairOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_pairwise(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<FieldsQuery>,
+    Valid(inp): Valid<PairIn>,
+) -> Result<(NUsed, Json<Selected<PairOut>>), ServiceError> {
+    let fields = Fields::parse(q.fields.as_deref(), PAIR_FIELDS)?;
+    let (st, f) = (state.clone(), fields.clone());
+    let out = state
+        .compute
+        .run(inp.x.len() + inp.y.len(), move |_| pairwise(&st, inp, &f))
+        .await?;
+    Ok((NUsed(out.n), Json(Selected(fields, out))))
+}
+
+/// Body of [`stats_pairwise`] for an already validated request; metrics
+/// outside `fields` are skipped and left `None`.
+pub(crate) fn pairwise(
+    state: &AppState,
+    inp: PairIn,
+    fields: &Fields,
+) -> Result<PairOut, ServiceError> {
+    let confidence = inp.confidence.unwrap_or(0.95);
+    let (xy, report) = resolve_series(vec![inp.x, inp.y], inp.missing.unwrap_or_default())?;
+    let (x, y) = (&xy[0], &xy[1]);
+    if x.len() != y.len() || x.is_empty() {
+        return Ok(PairOut {
             covariance: None,
             pearson: None,
             spearman: None,
             kendall: None,
+            n: 0,
+            confidence,
+            pearson_test: None,
+            spearman_test: None,
+            kendall_test: None,
+            missing: Some(report),
         });
     }
-    let cov = covariance(&inp.x, &inp.y);
-    let p = pearson_correlation(&inp.x, &inp.y);
-    let s = spearman_rho(&inp.x, &inp.y);
-    let k = kendall_tau_b(&inp.x, &inp.y);
-
     #[inline]
     fn o(x: f64) -> Option<f64> {
         if x.is_nan() { None } else { Some(x) }
     }
+    let metric = |name: &str, f: &dyn Fn() -> f64| fields.wants(name).then(f).and_then(o);
+    let n = x.len();
+    let test = |method, r: Option<f64>| r.map(|r| CorrTest::new(method, r, n, confidence));
 
-    Json(PairOut {
-        covariance: o(cov),
-        pearson: o(p),
-        spearman: o(s),
-        kendall: o(k),
+    let pearson = metric("pearson", &|| pearson_correlation(x, y));
+    let spearman = metric("spearman", &|| {
+        pearson_correlation(&state.cache.ranks(x), &state.cache.ranks(y))
+    });
+    let kendall = metric("kendall", &|| kendall_tau_b(x, y));
+    Ok(PairOut {
+        covariance: metric("covariance", &|| covariance(x, y)),
+        pearson,
+        spearman,
+        kendall,
+        n,
+        confidence,
+        pearson_test: test(CorrMethod::Pearson, pearson),
+        spearman_test: test(CorrMethod::Spearman, spearman),
+        kendall_test: test(CorrMethod::Kendall, kendall),
+        missing: Some(report),
     })
 }
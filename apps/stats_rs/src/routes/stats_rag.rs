@@ -0,0 +1,282 @@
+//! /stats/rag/* (feature `rag`)
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    routes::stats_vector::VectorBatch,
+    stats::{Prf, prelude::*},
+    types::{
+        Aggregation, ErrorResponse, GroundednessIn, GroundednessOut, MmrIn, MmrOut, PrfScore,
+        RagMetricsIn, RagMetricsOut, RagPerQuery, SentenceSupportOut, TextMetricsIn,
+        TextMetricsOut, TextPairMetrics,
+    },
+};
+use axum::Json;
+use std::collections::HashSet;
+
+/// Mean over the defined (non-NaN) values; `None` if there are none.
+fn mean_defined(xs: &[f64]) -> Option<f64> {
+    let v: Vec<f64> = xs.iter().copied().filter(|x| !x.is_nan()).collect();
+    if v.is_empty() { None } else { Some(mean(&v)) }
+}
+
+/// Compute retrieval metrics (P@k, R@k, MRR, nDCG@k, MAP) per query and aggregated.
+///
+/// - `k` defaults to 10
+/// - `aggregation` defaults to `mean`; `percentile` requires `percentile` in `[0, 1]`
+/// - Queries with no relevant ids are skipped for recall and MAP (undefined there)
+/// - `per_query` keeps the individual values so badly-served queries stay visible
+#[utoipa::path(
+    post,
+    path = "/stats/rag/metrics",
+    tag = "rag",
+    summary = "Retrieval metrics (P@k, R@k, MRR, nDCG@k, MAP) over queries",
+    responses(
+        (status = 200, description = "OK", body = RagMetricsOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn stats_rag_metrics(
+    Json(inp): Json<RagMetricsIn>,
+) -> Result<(NUsed, Json<RagMetricsOut>), ServiceError> {
+    let n = inp.queries.len();
+    Ok((NUsed(n), Json(rag_metrics(inp)?)))
+}
+
+/// Body of [`stats_rag_metrics`].
+pub(crate) fn rag_metrics(inp: RagMetricsIn) -> Result<RagMetricsOut, ServiceError> {
+    let k = inp.k.unwrap_or(10);
+    let aggregation = inp.aggregation.unwrap_or(Aggregation::Mean);
+    let p_agg = match aggregation {
+        Aggregation::Mean => None,
+        Aggregation::Median => Some(0.5),
+        Aggregation::Percentile => match inp.percentile {
+            Some(p) if (0.0..=1.0).contains(&p) => Some(p),
+            _ => {
+                return Err(ServiceError::InvalidInput(
+                    "percentile in [0, 1] is required for aggregation = percentile".into(),
+                ));
+            }
+        },
+    };
+    let agg = |xs: &[f64]| -> Option<f64> {
+        let v: Vec<f64> = xs.iter().copied().filter(|x| !x.is_nan()).collect();
+        match p_agg {
+            _ if v.is_empty() => None,
+            None => Some(mean(&v)),
+            Some(p) => Some(quantile(&v, p)),
+        }
+    };
+    let opt = |xs: &[f64]| -> Vec<Option<f64>> {
+        xs.iter().map(|&x| (!x.is_nan()).then_some(x)).collect()
+    };
+    let mut p = Vec::with_capacity(inp.queries.len());
+    let mut r = Vec::with_capacity(inp.queries.len());
+    let mut rr = Vec::with_capacity(inp.queries.len());
+    let mut nd = Vec::with_capacity(inp.queries.len());
+    let mut ap = Vec::with_capacity(inp.queries.len());
+
+    for q in &inp.queries {
+        let rel: HashSet<usize> = q.relevant.iter().copied().collect();
+        p.push(precision_at_k(&q.retrieved, &q.relevant, k));
+        r.push(recall_at_k(&q.retrieved, &q.relevant, k));
+        rr.push(mrr(&q.retrieved, &q.relevant));
+        nd.push(ndcg_at_k(&q.retrieved, &q.relevant, k));
+        ap.push(average_precision(&q.retrieved, &rel));
+    }
+
+    Ok(RagMetricsOut {
+        n_queries: inp.queries.len(),
+        k,
+        aggregation,
+        precision_at_k: agg(&p),
+        recall_at_k: agg(&r),
+        mrr: agg(&rr),
+        ndcg_at_k: agg(&nd),
+        map: agg(&ap),
+        per_query: RagPerQuery {
+            precision_at_k: opt(&p),
+            recall_at_k: opt(&r),
+            reciprocal_rank: opt(&rr),
+            ndcg_at_k: opt(&nd),
+            average_precision: opt(&ap),
+        },
+    })
+}
+
+/// Re-rank candidates with greedy Maximal Marginal Relevance (cosine similarity).
+///
+/// - `lambda` defaults to `0.5` and must lie in `[0, 1]`
+/// - Query and candidates are all dense (sharing one dimension) or all sparse
+/// - Returns at most `min(k, candidates.len())` picks in selection order
+#[utoipa::path(
+    post,
+    path = "/stats/rag/mmr",
+    tag = "rag",
+    summary = "MMR re-ranking of dense or sparse candidate vectors",
+    responses(
+        (status = 200, description = "OK", body = MmrOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn stats_rag_mmr(Json(inp): Json<MmrIn>) -> Result<(NUsed, Json<MmrOut>), ServiceError> {
+    let lambda = inp.lambda.unwrap_or(0.5);
+    if !(0.0..=1.0).contains(&lambda) {
+        return Err(ServiceError::InvalidInput(
+            "lambda must be in [0, 1]".into(),
+        ));
+    }
+
+    let (n, picks) = match VectorBatch::resolve(inp.query, inp.candidates)? {
+        VectorBatch::Dense { query, candidates } => (
+            candidates.len(),
+            mmr_select_scored(&candidates, &query, lambda, inp.k),
+        ),
+        VectorBatch::Sparse { query, candidates } => {
+            let sim = |a, b| {
+                let s = sparse_cosine_similarity(a, b);
+                if s.is_nan() { 0.0 } else { s }
+            };
+            let sim_q: Vec<f64> = candidates.iter().map(|c| sim(c, &query)).collect();
+            let picks = mmr_select_with(
+                &sim_q,
+                |i, j| sim(&candidates[i], &candidates[j]),
+                lambda,
+                inp.k,
+            );
+            (candidates.len(), picks)
+        }
+    };
+    Ok((
+        NUsed(n),
+        Json(MmrOut {
+            indices: picks.iter().map(|p| p.index).collect(),
+            relevance: picks.iter().map(|p| p.relevance).collect(),
+            diversity: picks.iter().map(|p| 1.0 - p.redundancy).collect(),
+            scores: picks.iter().map(|p| p.score).collect(),
+        }),
+    ))
+}
+
+impl From<Prf> for PrfScore {
+    fn from(p: Prf) -> Self {
+        PrfScore {
+            precision: p.precision,
+            recall: p.recall,
+            f1: p.f1,
+        }
+    }
+}
+
+/// Text-overlap metrics (ROUGE-1/2/L, BLEU-4, token F1) for candidate/reference pairs.
+///
+/// Strings are lowercased and split on non-alphanumeric characters before scoring.
+#[utoipa::path(
+    post,
+    path = "/stats/rag/text-metrics",
+    tag = "rag",
+    summary = "ROUGE-1/2/L, BLEU and token F1 between candidate and reference strings",
+    responses(
+        (status = 200, description = "OK", body = TextMetricsOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn stats_rag_text_metrics(
+    Json(inp): Json<TextMetricsIn>,
+) -> Result<(NUsed, Json<TextMetricsOut>), ServiceError> {
+    if inp.candidates.len() != inp.references.len() {
+        return Err(ServiceError::InvalidInput(format!(
+            "candidates ({}) and references ({}) must have the same length",
+            inp.candidates.len(),
+            inp.references.len()
+        )));
+    }
+
+    let pairs: Vec<TextPairMetrics> = inp
+        .candidates
+        .iter()
+        .zip(&inp.references)
+        .map(|(c, r)| {
+            let (c, r) = (tokenize(c), tokenize(r));
+            TextPairMetrics {
+                rouge1: rouge_n(&c, &r, 1).into(),
+                rouge2: rouge_n(&c, &r, 2).into(),
+                rouge_l: rouge_l(&c, &r).into(),
+                bleu: bleu(&c, &r, 4),
+                token_f1: token_f1(&c, &r),
+            }
+        })
+        .collect();
+
+    let avg =
+        |f: fn(&TextPairMetrics) -> f64| mean_defined(&pairs.iter().map(f).collect::<Vec<_>>());
+    Ok((
+        NUsed(pairs.len()),
+        Json(TextMetricsOut {
+            mean_rouge1_f1: avg(|p| p.rouge1.f1),
+            mean_rouge2_f1: avg(|p| p.rouge2.f1),
+            mean_rouge_l_f1: avg(|p| p.rouge_l.f1),
+            mean_bleu: avg(|p| p.bleu),
+            mean_token_f1: avg(|p| p.token_f1),
+            pairs,
+        }),
+    ))
+}
+
+/// Groundedness proxy: how well each answer sentence is supported by retrieved context.
+///
+/// Each sentence gets its max/mean cosine support; `groundedness` is the mean max
+/// support and `supported_fraction` the share of sentences at or above `threshold`
+/// (default `0.7`). Embedding-based, so it flags unsupported sentences rather than
+/// proving entailment.
+#[utoipa::path(
+    post,
+    path = "/stats/rag/groundedness",
+    tag = "rag",
+    summary = "Per-sentence context support and overall groundedness score",
+    responses(
+        (status = 200, description = "OK", body = GroundednessOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn stats_rag_groundedness(
+    Json(inp): Json<GroundednessIn>,
+) -> Result<(NUsed, Json<GroundednessOut>), ServiceError> {
+    let d = inp
+        .answer
+        .first()
+        .or(inp.context.first())
+        .map_or(0, |v| v.len());
+    for (field, vs) in [("answer", &inp.answer), ("context", &inp.context)] {
+        if let Some(i) = vs.iter().position(|v| v.len() != d) {
+            return Err(ServiceError::InvalidInput(format!(
+                "{field}[{i}] has dimension {}, expected {d}",
+                vs[i].len()
+            )));
+        }
+    }
+    let threshold = inp.threshold.unwrap_or(0.7);
+
+    let support = sentence_support(&inp.answer, &inp.context);
+    let maxes: Vec<f64> = support.iter().map(|s| s.max).collect();
+    let n_supported = maxes.iter().filter(|&&m| m >= threshold).count();
+
+    Ok((
+        NUsed(support.len()),
+        Json(GroundednessOut {
+            groundedness: mean_defined(&maxes),
+            supported_fraction: (!support.is_empty())
+                .then(|| n_supported as f64 / support.len() as f64),
+            sentences: support
+                .iter()
+                .map(|s| SentenceSupportOut {
+                    max_support: s.max,
+                    mean_support: s.mean,
+                    best_context: s.best_context,
+                    supported: s.max >= threshold,
+                })
+                .collect(),
+            threshold,
+        }),
+    ))
+}
@@ -0,0 +1,189 @@
+//! /stats/resample
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    ingest::{
+        ColumnRef, CsvColumn, CsvOptions, CsvTable,
+        datetime::{DayFirst, Period, parse_datetime, timestamp_text},
+        read_csv,
+    },
+    state::AppState,
+    stats::prelude::*,
+    types::{
+        ColumnType, CsvQuery, ErrorResponse, ResampleAgg, ResampleBucket, ResampleFreq,
+        ResampleOut, ResampleQuery,
+    },
+};
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Query, State},
+};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Upper bound on emitted buckets (e.g. ~11 years of hours).
+const MAX_BUCKETS: i64 = 100_000;
+
+impl From<ResampleFreq> for Period {
+    fn from(f: ResampleFreq) -> Self {
+        match f {
+            ResampleFreq::Hour => Period::Hour,
+            ResampleFreq::Day => Period::Day,
+            ResampleFreq::Week => Period::Week,
+            ResampleFreq::Month => Period::Month,
+        }
+    }
+}
+
+fn find_column<'a>(table: &'a CsvTable, r: &ColumnRef) -> Result<&'a CsvColumn, ServiceError> {
+    table
+        .columns
+        .iter()
+        .find(|c| match r {
+            ColumnRef::Name(n) => c.name == *n,
+            ColumnRef::Index(i) => c.index == *i,
+        })
+        .ok_or_else(|| {
+            ServiceError::InvalidInput(match r {
+                ColumnRef::Name(n) => format!("unknown column '{n}'"),
+                ColumnRef::Index(i) => format!("column index {i} out of range"),
+            })
+        })
+}
+
+fn aggregate(agg: ResampleAgg, xs: &[f64]) -> Option<f64> {
+    match agg {
+        ResampleAgg::Count => Some(xs.len() as f64),
+        ResampleAgg::Sum => Some(sum(xs)),
+        _ if xs.is_empty() => None,
+        ResampleAgg::Mean => Some(mean(xs)),
+        ResampleAgg::Min => Some(min(xs)),
+        ResampleAgg::Max => Some(max(xs)),
+        ResampleAgg::Median => Some(median(xs)),
+    }
+}
+
+/// Aggregate CSV value columns into hourly/daily/weekly/monthly buckets of a
+/// datetime column.
+///
+/// - Value columns come from `columns` (default: every integer/float column
+///   other than the time index); non-numeric cells are ignored
+/// - Times without an offset are read as UTC; rows with an unparseable time
+///   are skipped and counted in `skipped_rows`
+/// - Gaps are filled with empty buckets unless `fill=false`
+///
+/// - **Request**: body `text/csv`; query [`CsvQuery`] plus [`ResampleQuery`]
+/// - **Response**: [`ResampleOut`] (`200 OK`)
+/// - **Errors**: `InvalidInput` (unknown column, no parseable times, too many buckets)
+#[utoipa::path(
+    post,
+    path = "/stats/resample",
+    tag = "tables",
+    summary = "Aggregate CSV columns into hour/day/week/month buckets of a datetime column",
+    request_body(content = String, content_type = "text/csv", description = "CSV upload"),
+    params(CsvQuery, ResampleQuery),
+    responses(
+        (status = 200, description = "OK", body = ResampleOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn stats_resample(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<CsvQuery>,
+    Query(r): Query<ResampleQuery>,
+    body: Bytes,
+) -> Result<(NUsed, Json<ResampleOut>), ServiceError> {
+    let mut opts = CsvOptions::from_query(&q, state.config.seed)?;
+    let value_refs = opts.columns.take();
+    let table = read_csv(&body, &opts)?;
+
+    let time_ref = match r.time.trim().parse::<usize>() {
+        Ok(i) => ColumnRef::Index(i),
+        Err(_) => ColumnRef::Name(r.time.trim().to_string()),
+    };
+    let time = find_column(&table, &time_ref)?;
+    let values: Vec<&CsvColumn> = match &value_refs {
+        Some(refs) => refs
+            .iter()
+            .map(|r| find_column(&table, r))
+            .collect::<Result<_, _>>()?,
+        None => table
+            .columns
+            .iter()
+            .filter(|c| {
+                c.index != time.index && matches!(c.dtype, ColumnType::Integer | ColumnType::Float)
+            })
+            .collect(),
+    };
+
+    let freq = r.freq.unwrap_or(ResampleFreq::Day);
+    let agg = r.agg.unwrap_or(ResampleAgg::Mean);
+    let period = Period::from(freq);
+    let day_first = match r.dayfirst {
+        None => DayFirst::Auto,
+        Some(true) => DayFirst::Yes,
+        Some(false) => DayFirst::No,
+    };
+
+    // bucket -> (rows, per-column values)
+    let mut groups: BTreeMap<i64, (usize, Vec<Vec<f64>>)> = BTreeMap::new();
+    let mut skipped_rows = 0;
+    for row in 0..table.n_rows {
+        let Some(ts) = parse_datetime(&time.cells[row], day_first) else {
+            skipped_rows += 1;
+            continue;
+        };
+        let (rows, cols) = groups
+            .entry(period.bucket(ts))
+            .or_insert_with(|| (0, vec![Vec::new(); values.len()]));
+        *rows += 1;
+        for (acc, col) in cols.iter_mut().zip(&values) {
+            acc.extend(table.infer.number(&col.cells[row]));
+        }
+    }
+
+    let (Some(&first), Some(&last)) = (groups.keys().next(), groups.keys().next_back()) else {
+        return Err(ServiceError::InvalidInput(format!(
+            "no parseable datetimes in column '{}'",
+            time.name
+        )));
+    };
+    let keys: Vec<i64> = if r.fill.unwrap_or(true) {
+        if last - first >= MAX_BUCKETS {
+            return Err(ServiceError::InvalidInput(format!(
+                "resampling spans {} buckets (limit {MAX_BUCKETS}); use a coarser freq",
+                last - first + 1
+            )));
+        }
+        (first..=last).collect()
+    } else {
+        groups.keys().copied().collect()
+    };
+
+    let empty = vec![Vec::new(); values.len()];
+    let buckets = keys
+        .into_iter()
+        .map(|b| {
+            let (rows, cols) = groups.get(&b).map_or((0, &empty), |(n, c)| (*n, c));
+            ResampleBucket {
+                start: timestamp_text(period.start(b)),
+                rows,
+                values: cols.iter().map(|xs| aggregate(agg, xs)).collect(),
+            }
+        })
+        .collect();
+
+    Ok((
+        NUsed(table.n_rows - skipped_rows),
+        Json(ResampleOut {
+            time_column: time.name.clone(),
+            freq,
+            agg,
+            columns: values.iter().map(|c| c.name.clone()).collect(),
+            buckets,
+            skipped_rows,
+            sample: table.sample,
+        }),
+    ))
+}
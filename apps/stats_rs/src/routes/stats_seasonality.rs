@@ -0,0 +1,186 @@
+//! /stats/seasonality
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    ingest::datetime::{DayFirst, civil_from_days, parse_datetime, weekday},
+    missing::resolve,
+    state::AppState,
+    stats::prelude::*,
+    types::{ErrorResponse, SeasonKey, SeasonStats, SeasonalityIn, SeasonalityOut},
+    validate::Valid,
+};
+use axum::{Json, extract::State};
+use std::sync::Arc;
+
+const MS_PER_HOUR: i64 = 3_600_000;
+const MS_PER_DAY: i64 = 24 * MS_PER_HOUR;
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+impl SeasonKey {
+    /// Labels of the calendar slots, in order.
+    fn labels(self) -> Vec<String> {
+        match self {
+            SeasonKey::HourOfDay => (0..24).map(|h| h.to_string()).collect(),
+            SeasonKey::DayOfWeek => WEEKDAYS.map(str::to_owned).to_vec(),
+            SeasonKey::MonthOfYear => MONTHS.map(str::to_owned).to_vec(),
+        }
+    }
+
+    /// Slot of a UTC epoch-millisecond timestamp.
+    fn slot(self, ms: i64) -> usize {
+        match self {
+            SeasonKey::HourOfDay => (ms.rem_euclid(MS_PER_DAY) / MS_PER_HOUR) as usize,
+            SeasonKey::DayOfWeek => weekday(ms) as usize,
+            SeasonKey::MonthOfYear => civil_from_days(ms.div_euclid(MS_PER_DAY)).1 as usize - 1,
+        }
+    }
+}
+
+#[inline]
+fn o(x: f64) -> Option<f64> {
+    if x.is_finite() { Some(x) } else { None }
+}
+
+fn season_stats(season: String, xs: &[f64]) -> SeasonStats {
+    let m = mean(xs);
+    SeasonStats {
+        season,
+        count: xs.len(),
+        mean: o(m),
+        median: o(median(xs)),
+        std_dev: o(sample_std_dev(xs, m)),
+        min: o(min(xs)),
+        max: o(max(xs)),
+    }
+}
+
+/// Detect the dominant period of a series and summarize it season by season.
+///
+/// - The series is taken as evenly spaced and in time order; `acf` holds its
+///   autocorrelation up to `max_lag` (default `min(n / 2, 400)`)
+/// - `acf_period` is the highest autocorrelation peak above the white-noise
+///   band, `periodogram_period` the integer period with the most spectral power
+/// - With `timestamps`, seasons are calendar slots (`season`, default
+///   `day_of_week`, in UTC) and values with an unparseable time are counted in
+///   `skipped`; otherwise they are positions modulo `period` (default: the
+///   detected period), and `seasons` is empty when none was found
+/// - `strength` is the share of variance explained by the season means
+/// - `null`s are settled by `missing` (default `drop`); imputing keeps the
+///   spacing of the series intact
+#[utoipa::path(
+    post,
+    path = "/stats/seasonality",
+    tag = "stats",
+    summary = "Dominant period and per-season summaries",
+    request_body = SeasonalityIn,
+    responses(
+        (status = 200, description = "OK", body = SeasonalityOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_seasonality(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<SeasonalityIn>,
+) -> Result<(NUsed, Json<SeasonalityOut>), ServiceError> {
+    let n = inp.values.len();
+    let size = n.saturating_mul(inp.max_lag.unwrap_or((n / 2).min(400)).max(1));
+    let out = state
+        .compute
+        .run(size, move |cancel| seasonality(inp, cancel))
+        .await?;
+    Ok((NUsed(out.n), Json(out)))
+}
+
+/// Body of [`stats_seasonality`] for an already validated request.
+fn seasonality(inp: SeasonalityIn, cancel: &CancelFlag) -> Result<SeasonalityOut, ServiceError> {
+    let missing_at: Vec<bool> = inp.values.iter().map(|x| !x.is_finite()).collect();
+    let r = resolve(inp.values, inp.missing.unwrap_or_default())?;
+    let xs = r.values;
+    // keep the timestamps of the values `drop` kept
+    let stamps = inp.timestamps.map(|ts| match ts.len() == xs.len() {
+        true => ts,
+        false => ts
+            .into_iter()
+            .zip(&missing_at)
+            .filter(|(_, m)| !**m)
+            .map(|(t, _)| t)
+            .collect(),
+    });
+
+    let n = xs.len();
+    let max_lag = inp.max_lag.unwrap_or((n / 2).min(400));
+    let acf = autocorrelation(&xs, max_lag);
+    let acf_p = acf_period(&acf, n);
+    if cancel.is_cancelled() {
+        return Err(ServiceError::Cancelled);
+    }
+    let periodogram_p = periodogram_period(&xs, max_lag);
+
+    let (groups, labels, period, season, skipped) = match stamps {
+        Some(ts) => {
+            let key = inp.season.unwrap_or_default();
+            let day_first = match inp.dayfirst {
+                None => DayFirst::Auto,
+                Some(true) => DayFirst::Yes,
+                Some(false) => DayFirst::No,
+            };
+            let labels = key.labels();
+            let mut groups = vec![Vec::new(); labels.len()];
+            let mut skipped = 0;
+            for (&x, t) in xs.iter().zip(&ts) {
+                match parse_datetime(t, day_first) {
+                    Some(ms) => groups[key.slot(ms)].push(x),
+                    None => skipped += 1,
+                }
+            }
+            (groups, labels, None, Some(key), Some(skipped))
+        }
+        None => {
+            let period = inp.period.or(acf_p).or(periodogram_p);
+            let p = period.unwrap_or(0);
+            let mut groups = vec![Vec::new(); p];
+            for (i, &x) in xs.iter().enumerate().filter(|_| p > 0) {
+                groups[i % p].push(x);
+            }
+            let labels = (0..p).map(|i| i.to_string()).collect();
+            (groups, labels, period, None, None)
+        }
+    };
+
+    Ok(SeasonalityOut {
+        n,
+        acf: acf.into_iter().map(o).collect(),
+        acf_period: acf_p,
+        periodogram_period: periodogram_p,
+        period,
+        season,
+        seasons: labels
+            .into_iter()
+            .zip(&groups)
+            .map(|(label, xs)| season_stats(label, xs))
+            .collect(),
+        strength: o(eta_squared(&groups)),
+        skipped,
+        missing: Some(r.report),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calendar_slots() {
+        // 2024-03-05 (a Tuesday) 14:30 UTC
+        let ms = 1_709_649_000_000;
+        assert_eq!(SeasonKey::HourOfDay.slot(ms), 14);
+        assert_eq!(SeasonKey::DayOfWeek.slot(ms), 1);
+        assert_eq!(SeasonKey::MonthOfYear.slot(ms), 2);
+        assert_eq!(SeasonKey::DayOfWeek.labels()[1], "Tue");
+    }
+}
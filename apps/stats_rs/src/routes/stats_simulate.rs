@@ -0,0 +1,139 @@
+//! /stats/simulate
+
+use crate::{
+    error::ServiceError,
+    state::AppState,
+    stats::prelude::*,
+    types::{ErrorResponse, SimPipeline, SimulateIn, SimulateOut},
+    validate::{Valid, invalid},
+};
+use axum::{Json, extract::State};
+use rand::{SeedableRng, rngs::StdRng};
+use std::sync::Arc;
+
+/// Probabilities at which the simulated distribution is summarized.
+const PROBS: [f64; 7] = [0.025, 0.05, 0.25, 0.5, 0.75, 0.95, 0.975];
+
+#[inline]
+fn o(x: f64) -> Option<f64> {
+    if x.is_finite() { Some(x) } else { None }
+}
+
+/// Run a seeded Monte Carlo simulation and summarize its distribution.
+///
+/// - `pipeline.kind` is `difference` (a statistic of a sample from `x` minus
+///   that of a sample from `y`; `y` defaults to `x`, the null of no
+///   difference) or `formula` (an expression over independently drawn
+///   variables, for propagating uncertainty)
+/// - Distributions are the `/stats/generate` ones; the same `seed` (default
+///   `STATS_SEED`) always gives the same result
+/// - `quantiles` and the histogram (`bins`, default 20) describe the finite
+///   results; `non_finite` counts the rest
+/// - With `observed`, `p_value` is its two-sided Monte Carlo p-value
+/// - `replications` defaults to 10000; formula errors are reported at
+///   `/pipeline/expression` with their position
+#[utoipa::path(
+    post,
+    path = "/stats/simulate",
+    tag = "stats",
+    summary = "Monte Carlo simulation of a difference or formula",
+    request_body = SimulateIn,
+    responses(
+        (status = 200, description = "OK", body = SimulateOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_simulate(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<SimulateIn>,
+) -> Result<Json<SimulateOut>, ServiceError> {
+    let replications = inp.replications.unwrap_or(10_000);
+    let per_replication = match &inp.pipeline {
+        SimPipeline::Difference { n_x, n_y, .. } => n_x + n_y.unwrap_or(*n_x),
+        SimPipeline::Formula { variables, .. } => variables.len().max(1),
+    };
+    let seed = state.config.seed_for(inp.seed);
+    let out = state
+        .compute
+        .run(replications * per_replication, move |cancel| {
+            simulate(inp, seed, cancel)
+        })
+        .await?;
+    Ok(Json(out))
+}
+
+/// Body of [`stats_simulate`] for an already validated request.
+fn simulate(inp: SimulateIn, seed: u64, cancel: &CancelFlag) -> Result<SimulateOut, ServiceError> {
+    let replications = inp.replications.unwrap_or(10_000);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let progress = cancel.guard(|_| {});
+    let draws = match inp.pipeline {
+        SimPipeline::Difference {
+            x,
+            y,
+            n_x,
+            n_y,
+            statistic,
+        } => {
+            let (sx, sy) = (x.sampler(), y.unwrap_or(x).sampler());
+            let n_y = n_y.unwrap_or(n_x);
+            let stat = statistic.unwrap_or_default().kernel();
+            let (mut a, mut b) = (vec![0.0; n_x], vec![0.0; n_y]);
+            replicate(replications, &mut rng, progress, |rng| {
+                a.iter_mut().for_each(|v| *v = sx.sample(rng));
+                b.iter_mut().for_each(|v| *v = sy.sample(rng));
+                stat(&a) - stat(&b)
+            })
+        }
+        SimPipeline::Formula {
+            expression,
+            variables,
+        } => {
+            let names: Vec<&str> = variables.keys().map(String::as_str).collect();
+            let formula = Formula::parse(&expression, &names)
+                .map_err(|e| invalid("/pipeline/expression", e))?;
+            let samplers: Vec<Sampler> = variables.values().map(|d| d.sampler()).collect();
+            let mut values = vec![0.0; samplers.len()];
+            replicate(replications, &mut rng, progress, |rng| {
+                for (v, s) in values.iter_mut().zip(&samplers) {
+                    *v = s.sample(rng);
+                }
+                formula.eval(&values)
+            })
+        }
+    };
+    if cancel.is_cancelled() {
+        return Err(ServiceError::Cancelled);
+    }
+
+    let finite: Vec<f64> = draws.iter().copied().filter(|d| d.is_finite()).collect();
+    let s = sorted(&finite);
+    let m = mean(&s);
+    let (counts, edges) = histogram(&s, inp.bins.unwrap_or(20));
+    let method = inp.quantile_method.unwrap_or_default().into();
+    Ok(SimulateOut {
+        replications,
+        seed,
+        mean: o(m),
+        std_dev: o(sample_std_dev(&s, m)),
+        min: s.first().copied(),
+        max: s.last().copied(),
+        quantiles: PROBS
+            .iter()
+            .map(|&p| {
+                (
+                    p,
+                    (!s.is_empty()).then(|| quantile_sorted_by(&s, p, method)),
+                )
+            })
+            .collect(),
+        counts,
+        edges,
+        non_finite: draws.len() - finite.len(),
+        observed: inp.observed,
+        p_value: inp
+            .observed
+            .and_then(|v| o(monte_carlo_p_value(&finite, v))),
+    })
+}
@@ -1,21 +1,121 @@
 //! /stats/summary
 
 use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    fields::{Fields, FieldsQuery, Selected},
+    missing::resolve,
+    routes::export::{FormatQuery, OutputFormat, Tabular},
+    state::AppState,
     stats::prelude::*,
-    types::{SummaryIn, SummaryOut},
+    types::{ApproxOut, ErrorResponse, SummaryIn, SummaryOut},
+    validate::Valid,
 };
-use axum::Json;
+use axum::extract::{Query, State};
+use std::sync::Arc;
+
+/// Metrics selectable with `?fields=`.
+pub const SUMMARY_FIELDS: &[&str] = &[
+    "mean",
+    "median",
+    "std",
+    "min",
+    "max",
+    "iqr",
+    "mad",
+    "cv",
+    "sem",
+    "skewness",
+    "excess_kurtosis",
+];
 
 /// Compute core univariate summary statistics.
 ///
 /// Returns `None` for undefined metrics (e.g., std with `n < 2`).
 ///
 /// - **Request**: [`SummaryIn`]
-/// - **Response**: [`SummaryOut`]
-pub async fn stats_summary(Json(inp): Json<SummaryIn>) -> Json<SummaryOut> {
-    let n = inp.values.len();
+/// - **Response**: [`SummaryOut`] with `missing`, or a `stat,value` table
+///   for `?format=csv|tsv` / `Accept: text/csv`
+/// - **Fields**: `?fields=mean,std` computes and returns only those metrics
+/// - **Percentiles**: `percentiles: [5, 95]` adds those percentiles (R-7
+///   interpolation, as `/stats/distribution` quantiles, unless
+///   `quantile_method` names another definition); `median` and `iqr` stay
+///   R-7
+/// - **Approx**: `approx: true` reads `median`, `iqr`, `mad` and percentiles off a
+///   uniform sample of [`SKETCH_SIZE`] values when the input is larger;
+///   `approx` in the response gives the sample and its rank-error bound
+/// - **Errors**: `NaN` when `missing=error` and the input has `null`s;
+///   `Validation` (`422`) for empty or oversized `values`
+#[utoipa::path(
+    post,
+    path = "/stats/summary",
+    tag = "stats",
+    summary = "Summary statistics",
+    request_body = SummaryIn,
+    params(FormatQuery, FieldsQuery),
+    responses(
+        (status = 200, description = "OK", content(
+            (SummaryOut = "application/json"),
+            (String = "text/csv"),
+            (String = "text/tab-separated-values")
+        )),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_summary(
+    State(state): State<Arc<AppState>>,
+    fmt: OutputFormat,
+    Query(q): Query<FieldsQuery>,
+    Valid(inp): Valid<SummaryIn>,
+) -> Result<(NUsed, Tabular<Selected<SummaryOut>>), ServiceError> {
+    let fields = Fields::parse(q.fields.as_deref(), SUMMARY_FIELDS)?;
+    let f = fields.clone();
+    let seed = state.config.seed_for(inp.seed);
+    let out = state
+        .compute
+        .run(inp.values.len(), move |_| {
+            let r = resolve(inp.values, inp.missing.unwrap_or_default())?;
+            let sketch = inp
+                .approx
+                .unwrap_or(false)
+                .then(|| QuantileSketch::new(&r.values, SKETCH_SIZE, seed))
+                .filter(|s| !s.is_exact());
+            let ps = inp.percentiles.as_deref();
+            let method = inp.quantile_method.unwrap_or_default().into();
+            let mut out = summarize_sketched(&r.values, &f, ps, method, sketch.as_ref());
+            out.missing = Some(r.report);
+            out.approx = sketch.as_ref().map(ApproxOut::of);
+            Ok(out)
+        })
+        .await?;
+    Ok((NUsed(out.count), Tabular(fmt, Selected(fields, out))))
+}
+
+/// Shared body of the summary endpoints; metrics outside `fields` are
+/// skipped and left `None`, and `percentiles` (in `[0, 100]`) are reported
+/// when given.
+pub(crate) fn summarize(
+    values: &[f64],
+    fields: &Fields,
+    percentiles: Option<&[f64]>,
+) -> SummaryOut {
+    summarize_sketched(values, fields, percentiles, QuantileMethod::R7, None)
+}
+
+/// [`summarize`] with `percentiles` under `method`, and `median`, `iqr`,
+/// `mad` and `percentiles` read off `sketch` when given; `min`, `max` and
+/// the moments still come from a scan of `values`.
+fn summarize_sketched(
+    values: &[f64],
+    fields: &Fields,
+    percentiles: Option<&[f64]>,
+    method: QuantileMethod,
+    sketch: Option<&QuantileSketch>,
+) -> SummaryOut {
+    let n = values.len();
     if n == 0 {
-        return Json(SummaryOut {
+        return SummaryOut {
             count: 0,
             mean: None,
             median: None,
@@ -24,29 +124,70 @@ pub async fn stats_summary(Json(inp): Json<SummaryIn>) -> Json<SummaryOut> {
             max: None,
             iqr: None,
             mad: None,
-        });
+            cv: None,
+            sem: None,
+            skewness: None,
+            excess_kurtosis: None,
+            percentiles: percentiles.map(|_| vec![]),
+            schema: None,
+            missing: None,
+            sample: None,
+            approx: None,
+        };
     }
-    let m = mean(&inp.values);
-    let med = median(&inp.values);
-    let stdv = sample_std_dev(&inp.values, m);
-    let mn = min(&inp.values);
-    let mx = max(&inp.values);
-    let i = iqr(&inp.values);
-    let md = mad(&inp.values);
-
     #[inline]
     fn o(x: f64) -> Option<f64> {
         if x.is_nan() { None } else { Some(x) }
     }
+    let metric = |name: &str, f: &dyn Fn() -> f64| fields.wants(name).then(f).and_then(o);
+    let m = mean(values);
+    let sd = if ["std", "cv", "sem"].iter().any(|f| fields.wants(f)) {
+        sample_std_dev(values, m)
+    } else {
+        f64::NAN
+    };
+    // One sort serves every order statistic
+    let exact;
+    let ascending: &[f64] = match sketch {
+        Some(s) => s.sorted(),
+        None if percentiles.is_some()
+            || ["median", "min", "max", "iqr", "mad"]
+                .iter()
+                .any(|f| fields.wants(f)) =>
+        {
+            exact = sorted(values);
+            &exact
+        }
+        None => &[],
+    };
 
-    Json(SummaryOut {
+    SummaryOut {
         count: n,
-        mean: o(m),
-        median: o(med),
-        std: o(stdv),
-        min: o(mn),
-        max: o(mx),
-        iqr: o(i),
-        mad: o(md),
-    })
+        mean: metric("mean", &|| m),
+        median: metric("median", &|| median_sorted(ascending)),
+        std: metric("std", &|| sd),
+        min: metric("min", &|| match sketch {
+            Some(_) => min(values),
+            None => ascending[0],
+        }),
+        max: metric("max", &|| match sketch {
+            Some(_) => max(values),
+            None => ascending[n - 1],
+        }),
+        iqr: metric("iqr", &|| iqr_sorted(ascending)),
+        mad: metric("mad", &|| mad_sorted(ascending)),
+        cv: metric("cv", &|| if m == 0.0 { f64::NAN } else { sd / m }),
+        sem: metric("sem", &|| sd / (n as f64).sqrt()),
+        skewness: metric("skewness", &|| skewness(values)),
+        excess_kurtosis: metric("excess_kurtosis", &|| excess_kurtosis(values)),
+        percentiles: percentiles.map(|ps| {
+            ps.iter()
+                .map(|&p| (p, quantile_sorted_by(ascending, p / 100.0, method)))
+                .collect()
+        }),
+        schema: None,
+        missing: None,
+        sample: None,
+        approx: None,
+    }
 }
@@ -0,0 +1,449 @@
+//! /stats/tests and /stats/tests/recommend
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::resolve,
+    state::AppState,
+    stats::prelude::*,
+    types::{
+        ErrorResponse, GroupCheck, MissingReport, RecommendIn, RecommendOut, SampleTest, StatTest,
+        TestCatalogOut, TestInfo,
+    },
+    validate::Valid,
+};
+use axum::{Json, extract::State};
+use std::sync::Arc;
+
+/// Fewest values a group needs for its normality check.
+const MIN_NORMALITY_N: usize = 5;
+
+/// Values per group past which means are treated as normal whatever the data.
+const CLT_N: usize = 30;
+
+#[inline]
+fn o(x: f64) -> Option<f64> {
+    if x.is_finite() { Some(x) } else { None }
+}
+
+fn info(
+    id: StatTest,
+    name: &str,
+    question: &str,
+    input: &str,
+    assumptions: &[&str],
+    endpoint: &str,
+    field: &str,
+) -> TestInfo {
+    TestInfo {
+        id,
+        name: name.into(),
+        question: question.into(),
+        input: input.into(),
+        assumptions: assumptions.iter().map(|&a| a.into()).collect(),
+        endpoint: endpoint.into(),
+        field: field.into(),
+    }
+}
+
+/// The tests behind [`stats_tests`].
+fn catalog() -> Vec<TestInfo> {
+    const INDEPENDENT: &str = "independent observations";
+    vec![
+        info(
+            StatTest::StudentT,
+            "Student's t-test",
+            "Do two groups have the same mean?",
+            "2 numeric samples",
+            &[
+                INDEPENDENT,
+                "normal data or large samples",
+                "equal variances",
+            ],
+            "POST /api/v1/stats/compare",
+            "t_test",
+        ),
+        info(
+            StatTest::WelchT,
+            "Welch's t-test",
+            "Do two groups have the same mean?",
+            "2 numeric samples",
+            &[INDEPENDENT, "normal data or large samples"],
+            "POST /api/v1/stats/compare",
+            "welch",
+        ),
+        info(
+            StatTest::OneSampleT,
+            "One-sample t-test",
+            "Does a sample's mean differ from a given value?",
+            "1 numeric sample",
+            &[INDEPENDENT, "normal data or large samples"],
+            "POST /api/v1/stats/ttest (test one_sample)",
+            "p_value",
+        ),
+        info(
+            StatTest::PairedT,
+            "Paired t-test",
+            "Do paired measurements differ on average?",
+            "2 numeric samples of equal length, paired by position",
+            &["independent pairs", "normal differences or many pairs"],
+            "POST /api/v1/stats/ttest (test paired)",
+            "p_value",
+        ),
+        info(
+            StatTest::MannWhitney,
+            "Mann–Whitney U test",
+            "Does one group tend to have larger values than the other?",
+            "2 numeric samples",
+            &[
+                INDEPENDENT,
+                "similarly shaped distributions to read it as a shift in medians",
+            ],
+            "POST /api/v1/stats/compare",
+            "mann_whitney",
+        ),
+        info(
+            StatTest::KsTwoSample,
+            "Two-sample Kolmogorov–Smirnov test",
+            "Do two groups come from the same distribution?",
+            "2 numeric samples",
+            &[INDEPENDENT, "continuous data"],
+            "POST /api/v1/stats/compare",
+            "ks",
+        ),
+        info(
+            StatTest::VarianceF,
+            "F test for equal variances",
+            "Do two groups have the same variance?",
+            "2 numeric samples",
+            &[INDEPENDENT, "normal data"],
+            "POST /api/v1/stats/compare",
+            "variance_f",
+        ),
+        info(
+            StatTest::BrownForsythe,
+            "Brown–Forsythe test",
+            "Do the groups have the same spread?",
+            "2 or more numeric samples",
+            &[INDEPENDENT],
+            "POST /api/v1/stats/compare",
+            "brown_forsythe",
+        ),
+        info(
+            StatTest::OneWayAnova,
+            "One-way ANOVA",
+            "Do all groups have the same mean?",
+            "2 or more numeric samples",
+            &[
+                INDEPENDENT,
+                "normal data or large samples",
+                "equal variances",
+            ],
+            "POST /api/v1/stats/tests/recommend",
+            "result",
+        ),
+        info(
+            StatTest::WelchAnova,
+            "Welch's ANOVA",
+            "Do all groups have the same mean?",
+            "2 or more numeric samples of at least 2 values",
+            &[INDEPENDENT, "normal data or large samples"],
+            "POST /api/v1/stats/tests/recommend",
+            "result",
+        ),
+        info(
+            StatTest::KruskalWallis,
+            "Kruskal–Wallis test",
+            "Does any group tend to have larger values than the others?",
+            "2 or more numeric samples",
+            &[
+                INDEPENDENT,
+                "similarly shaped distributions to read it as a shift in medians",
+            ],
+            "POST /api/v1/stats/tests/recommend",
+            "result",
+        ),
+        info(
+            StatTest::ChiSquareIndependence,
+            "Pearson's χ² test of independence",
+            "Are two categorical variables related?",
+            "2 label arrays of equal length",
+            &[INDEPENDENT, "expected counts of at least 5 in most cells"],
+            "POST /api/v1/stats/contingency",
+            "p_value",
+        ),
+        info(
+            StatTest::Permutation,
+            "Permutation test",
+            "Do two groups have the same mean?",
+            "2 numeric samples",
+            &[INDEPENDENT, "exchangeable groups under the null"],
+            "POST /api/v1/jobs (kind permutation)",
+            "p_value",
+        ),
+        info(
+            StatTest::Correlation,
+            "Correlation test",
+            "Are two numeric variables associated?",
+            "2 or more numeric columns of equal length",
+            &[
+                INDEPENDENT,
+                "Pearson: a linear relation, roughly normal data; Spearman and Kendall: a monotone one",
+            ],
+            "POST /api/v1/stats/corr-matrix (p_values: true)",
+            "p_values",
+        ),
+        info(
+            StatTest::NormalPpcc,
+            "Normal probability-plot correlation test",
+            "Is a sample normally distributed?",
+            "1 numeric sample of 5 to 5000 values",
+            &[INDEPENDENT],
+            "POST /api/v1/stats/qq-normal",
+            "ppcc",
+        ),
+        info(
+            StatTest::JarqueBera,
+            "Jarque–Bera test",
+            "Is a sample normally distributed?",
+            "1 numeric column",
+            &[INDEPENDENT, "a few dozen values or more"],
+            "POST /api/v1/report",
+            "Normality (Jarque–Bera) table",
+        ),
+    ]
+}
+
+/// List the hypothesis tests the service runs.
+///
+/// Each entry gives the question the test answers, the input it takes, its
+/// assumptions, and the endpoint and response field that carry its outcome;
+/// `/stats/tests/recommend` picks among the group comparisons.
+#[utoipa::path(
+    get,
+    path = "/stats/tests",
+    tag = "stats",
+    summary = "Catalog of available hypothesis tests",
+    responses(
+        (status = 200, description = "OK", body = TestCatalogOut)
+    )
+)]
+pub async fn stats_tests() -> Json<TestCatalogOut> {
+    Json(TestCatalogOut { tests: catalog() })
+}
+
+/// Whether `xs` passes a normality check at `alpha`: the probability-plot
+/// correlation test up to 5000 values, Jarque–Bera beyond. `None` below
+/// [`MIN_NORMALITY_N`] values.
+fn looks_normal(xs: &[f64], alpha: f64) -> Option<bool> {
+    let n = xs.len();
+    if n < MIN_NORMALITY_N {
+        return None;
+    }
+    let s = sorted(xs);
+    // a constant sample has no shape to judge
+    if s[0] == s[n - 1] {
+        return Some(false);
+    }
+    match normal_ppcc_critical(n, alpha) {
+        c if c.is_nan() => Some(jarque_bera(&s).1 >= alpha),
+        c => Some(normal_ppcc_sorted(&s) >= c),
+    }
+}
+
+/// Suggest a test for comparing groups, from what the data shows.
+///
+/// - Checks each group for normality (probability-plot correlation test, or
+///   Jarque–Bera past 5000 values) and the groups for equal variances
+///   (Brown–Forsythe), all at `alpha` (default 0.05)
+/// - Means count as normal when every group passes, or when every group
+///   has at least 30 values; then Student's t or one-way ANOVA for equal
+///   variances and Welch's t or Welch's ANOVA otherwise
+/// - Otherwise Mann–Whitney U for two groups and Kruskal–Wallis for more;
+///   groups below 5 values cannot be checked and also lead here
+/// - `result` is the recommended test run on the data
+/// - `null`s are settled by `missing` (default `drop`) in each group
+#[utoipa::path(
+    post,
+    path = "/stats/tests/recommend",
+    tag = "stats",
+    summary = "Recommend a test for comparing groups, and run it",
+    request_body = RecommendIn,
+    responses(
+        (status = 200, description = "OK", body = RecommendOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_tests_recommend(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<RecommendIn>,
+) -> Result<(NUsed, Json<RecommendOut>), ServiceError> {
+    let size = inp.groups.iter().map(Vec::len).sum();
+    let out = state.compute.run(size, move |_| recommend(inp)).await?;
+    let n = out.groups.iter().map(|g| g.count).sum();
+    Ok((NUsed(n), Json(out)))
+}
+
+/// Body of [`stats_tests_recommend`] for an already validated request.
+fn recommend(inp: RecommendIn) -> Result<RecommendOut, ServiceError> {
+    let policy = inp.missing.unwrap_or_default();
+    let alpha = inp.alpha.unwrap_or(0.05);
+    let mut missing = MissingReport { policy, count: 0 };
+    let mut groups = Vec::with_capacity(inp.groups.len());
+    for g in inp.groups {
+        let r = resolve(g, policy)?;
+        missing.count += r.report.count;
+        groups.push(r.values);
+    }
+    let refs: Vec<&[f64]> = groups.iter().map(Vec::as_slice).collect();
+
+    let checks: Vec<GroupCheck> = groups
+        .iter()
+        .map(|g| {
+            let m = mean(g);
+            GroupCheck {
+                count: g.len(),
+                mean: o(m),
+                std_dev: o(sample_std_dev(g, m)),
+                normal: looks_normal(g, alpha),
+            }
+        })
+        .collect();
+    let variance_p = brown_forsythe(&refs).3;
+
+    let mut reasons = Vec::new();
+    let all_normal = checks.iter().all(|c| c.normal == Some(true));
+    let large = checks.iter().all(|c| c.count >= CLT_N);
+    if all_normal {
+        reasons.push(format!("every group passes the normality check at {alpha}"));
+    } else {
+        for (i, c) in checks.iter().enumerate() {
+            match c.normal {
+                Some(true) => {}
+                Some(false) => reasons.push(format!("group {i} does not look normal")),
+                None => reasons.push(format!(
+                    "group {i} has {} values, too few to check normality",
+                    c.count
+                )),
+            }
+        }
+        if large {
+            reasons.push(format!(
+                "every group has at least {CLT_N} values, so their means are close to normal anyway"
+            ));
+        }
+    }
+    let parametric = all_normal || large;
+    // an undecidable variance check falls back to the tests that don't need it
+    let equal_variances = variance_p >= alpha;
+    if parametric {
+        reasons.push(match o(variance_p) {
+            Some(p) if equal_variances => {
+                format!("the variances look equal (Brown–Forsythe p = {p:.3})")
+            }
+            Some(p) => format!("the variances differ (Brown–Forsythe p = {p:.3})"),
+            None => "the variances could not be compared".into(),
+        });
+    } else {
+        reasons.push("a rank-based test does not rely on normality".into());
+        if !equal_variances {
+            reasons.push(
+                "the spreads differ, so a significant result need not mean shifted medians".into(),
+            );
+        }
+    }
+
+    let two = refs.len() == 2;
+    let (recommended, alternatives) = match (two, parametric, equal_variances) {
+        (true, true, true) => (
+            StatTest::StudentT,
+            vec![
+                StatTest::WelchT,
+                StatTest::MannWhitney,
+                StatTest::Permutation,
+            ],
+        ),
+        (true, true, false) => (
+            StatTest::WelchT,
+            vec![StatTest::MannWhitney, StatTest::Permutation],
+        ),
+        (true, false, _) => (
+            StatTest::MannWhitney,
+            vec![
+                StatTest::KsTwoSample,
+                StatTest::Permutation,
+                StatTest::WelchT,
+            ],
+        ),
+        (false, true, true) => (
+            StatTest::OneWayAnova,
+            vec![StatTest::WelchAnova, StatTest::KruskalWallis],
+        ),
+        (false, true, false) => (StatTest::WelchAnova, vec![StatTest::KruskalWallis]),
+        (false, false, _) => (StatTest::KruskalWallis, vec![StatTest::WelchAnova]),
+    };
+
+    let test = |statistic: f64, df: f64, df2: f64, p: f64| SampleTest {
+        statistic: o(statistic),
+        df: o(df),
+        df2: o(df2),
+        p_value: o(p),
+    };
+    let result = match recommended {
+        StatTest::StudentT | StatTest::WelchT => {
+            let (t, df, p) = two_sample_t_test(refs[0], refs[1], recommended == StatTest::WelchT);
+            test(t, df, f64::NAN, p)
+        }
+        StatTest::MannWhitney => {
+            let (u, _, p) = mann_whitney_u(refs[0], refs[1]);
+            test(u, f64::NAN, f64::NAN, p)
+        }
+        StatTest::OneWayAnova => {
+            let (f, df1, df2, p) = one_way_anova(&refs);
+            test(f, df1, df2, p)
+        }
+        StatTest::WelchAnova => {
+            let (f, df1, df2, p) = welch_anova(&refs);
+            test(f, df1, df2, p)
+        }
+        _ => {
+            let (h, df, p) = kruskal_wallis(&refs);
+            test(h, df, f64::NAN, p)
+        }
+    };
+
+    Ok(RecommendOut {
+        recommended,
+        reasons,
+        alternatives,
+        groups: checks,
+        variance_p: o(variance_p),
+        alpha,
+        result,
+        missing: Some(missing),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normality_check_needs_enough_values() {
+        assert_eq!(looks_normal(&[1.0, 2.0, 3.0, 4.0], 0.05), None);
+        let line: Vec<f64> = filliben_medians(40).iter().map(|z| 5.0 + z).collect();
+        assert_eq!(looks_normal(&line, 0.05), Some(true));
+        let skewed: Vec<f64> = (1..=40).map(|i| 1.5f64.powi(i)).collect();
+        assert_eq!(looks_normal(&skewed, 0.05), Some(false));
+        assert_eq!(looks_normal(&[2.0; 10], 0.05), Some(false));
+    }
+
+    #[test]
+    fn catalog_ids_are_unique() {
+        let tests = catalog();
+        for (i, t) in tests.iter().enumerate() {
+            assert!(tests[i + 1..].iter().all(|u| u.id != t.id), "{:?}", t.id);
+        }
+    }
+}
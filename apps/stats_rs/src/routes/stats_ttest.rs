@@ -0,0 +1,102 @@
+//! /stats/ttest
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    missing::{resolve, resolve_series},
+    state::AppState,
+    stats::prelude::*,
+    types::{ErrorResponse, MissingReport, TTestIn, TTestKind, TTestOut},
+    validate::Valid,
+};
+use axum::{Json, extract::State};
+use std::sync::Arc;
+
+#[inline]
+fn o(x: f64) -> Option<f64> {
+    if x.is_finite() { Some(x) } else { None }
+}
+
+/// Run a one-sample, paired or Welch two-sample t-test.
+///
+/// - `test` defaults to `one_sample` without `y` and `welch` with it;
+///   `paired` tests the differences `x − y` of equal-length samples
+/// - `mu` is the mean (or mean difference) under the null hypothesis
+///   (default 0) and `alternative` is `two_sided` (default), `less` or
+///   `greater`
+/// - Returns the estimate, its standard error, `t`, `df` (Welch–Satterthwaite
+///   for `welch`), the p-value and a `confidence` (default 0.95) interval
+///   matching `alternative`, open on one side for one-sided tests
+/// - Statistics a sample is too small or too constant for are `null`
+/// - `null`s are settled by `missing` (default `drop`); `paired` drops the
+///   whole pair
+#[utoipa::path(
+    post,
+    path = "/stats/ttest",
+    tag = "stats",
+    summary = "One-sample, paired or Welch t-test with a confidence interval",
+    request_body = TTestIn,
+    responses(
+        (status = 200, description = "OK", body = TTestOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse),
+        (status = 422, description = "Validation failed; details.field points at the field", body = ErrorResponse)
+    )
+)]
+pub async fn stats_ttest(
+    State(state): State<Arc<AppState>>,
+    Valid(inp): Valid<TTestIn>,
+) -> Result<(NUsed, Json<TTestOut>), ServiceError> {
+    let size = inp.x.len() + inp.y.as_ref().map_or(0, Vec::len);
+    let out = state.compute.run(size, move |_| ttest(inp)).await?;
+    Ok((NUsed(out.n_x + out.n_y.unwrap_or(0)), Json(out)))
+}
+
+/// Body of [`stats_ttest`] for an already validated request.
+fn ttest(inp: TTestIn) -> Result<TTestOut, ServiceError> {
+    let test = inp.kind();
+    let policy = inp.missing.unwrap_or_default();
+    let ((estimate, se, df), n_x, n_y, missing) = match (test, inp.y) {
+        (TTestKind::Paired, Some(y)) => {
+            let (xy, report) = resolve_series(vec![inp.x, y], policy)?;
+            let d: Vec<f64> = xy[0].iter().zip(&xy[1]).map(|(a, b)| a - b).collect();
+            (one_sample_t(&d), d.len(), None, report)
+        }
+        (TTestKind::Welch, Some(y)) => {
+            let (rx, ry) = (resolve(inp.x, policy)?, resolve(y, policy)?);
+            let missing = MissingReport {
+                policy,
+                count: rx.report.count + ry.report.count,
+            };
+            let parts = welch_t(&rx.values, &ry.values);
+            (parts, rx.values.len(), Some(ry.values.len()), missing)
+        }
+        _ => {
+            let r = resolve(inp.x, policy)?;
+            (one_sample_t(&r.values), r.values.len(), None, r.report)
+        }
+    };
+    let alternative = inp.alternative.unwrap_or_default();
+    let tail = alternative.into();
+    let confidence = inp.confidence.unwrap_or(0.95);
+    // no variation leaves t undefined
+    let t = match se > 0.0 {
+        true => (estimate - inp.mu.unwrap_or(0.0)) / se,
+        false => f64::NAN,
+    };
+    let (lower, upper) = t_interval(estimate, se, df, confidence, tail);
+    Ok(TTestOut {
+        test,
+        alternative,
+        estimate: o(estimate),
+        std_error: o(se),
+        t: o(t),
+        df: o(df),
+        p_value: o(t_p_value(t, df, tail)),
+        confidence,
+        lower: o(lower),
+        upper: o(upper),
+        n_x,
+        n_y,
+        missing: Some(missing),
+    })
+}
@@ -0,0 +1,356 @@
+//! /stats/vector/*
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    state::AppState,
+    stats::prelude::*,
+    types::{
+        EmbeddingDtype, ErrorResponse, IntrinsicDimIn, IntrinsicDimMethod, IntrinsicDimOut,
+        KnnDistIn, KnnDistOut, NearDupIn, NearDupOut, NearDupPair, SimilarityIn, SimilarityKernel,
+        SimilarityOut, SparseVectorIn, VectorIn, VectorMetric,
+    },
+};
+use axum::{Json, extract::State};
+use std::sync::Arc;
+
+/// Reject ragged point sets; returns the shared dimension.
+pub(crate) fn check_dims<T>(points: &[Vec<T>]) -> Result<usize, ServiceError> {
+    let d = points.first().map_or(0, |p| p.len());
+    if let Some(i) = points.iter().position(|p| p.len() != d) {
+        return Err(ServiceError::InvalidInput(format!(
+            "points[{i}] has dimension {}, expected {d}",
+            points[i].len()
+        )));
+    }
+    Ok(d)
+}
+
+/// Pairwise distance for a [`VectorMetric`].
+pub(crate) fn metric_fn<T: Element>(metric: VectorMetric) -> fn(&[T], &[T]) -> f64 {
+    match metric {
+        VectorMetric::Euclidean => T::euclidean,
+        VectorMetric::Cosine => |a, b| 1.0 - T::cosine(a, b),
+    }
+}
+
+/// Run `f` over the points at the requested precision.
+macro_rules! with_dtype {
+    ($points:expr, $dtype:expr, |$p:ident| $body:expr) => {
+        match $dtype.unwrap_or_default() {
+            EmbeddingDtype::F64 => {
+                let $p = $points.into_f64();
+                $body
+            }
+            EmbeddingDtype::F32 => {
+                let $p = $points.into_f32();
+                $body
+            }
+        }
+    };
+}
+
+/// Query and candidates resolved to a single representation.
+pub(crate) enum VectorBatch {
+    Dense {
+        query: Vec<f64>,
+        candidates: Vec<Vec<f64>>,
+    },
+    Sparse {
+        query: SparseVector,
+        candidates: Vec<SparseVector>,
+    },
+}
+
+fn to_sparse(v: SparseVectorIn, field: &str) -> Result<SparseVector, ServiceError> {
+    if v.indices.len() != v.values.len() {
+        return Err(ServiceError::InvalidInput(format!(
+            "{field} has {} indices but {} values",
+            v.indices.len(),
+            v.values.len()
+        )));
+    }
+    Ok(SparseVector::new(v.indices, v.values))
+}
+
+impl VectorBatch {
+    /// Reject mixed dense/sparse inputs, dense dimension mismatches and
+    /// sparse vectors whose index/value lists differ in length.
+    pub(crate) fn resolve(
+        query: VectorIn,
+        candidates: Vec<VectorIn>,
+    ) -> Result<Self, ServiceError> {
+        match query {
+            VectorIn::Dense(query) => {
+                let d = query.len();
+                let candidates = candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, c)| match c {
+                        VectorIn::Dense(v) if v.len() == d => Ok(v),
+                        VectorIn::Dense(v) => Err(ServiceError::InvalidInput(format!(
+                            "candidates[{i}] has dimension {}, expected {d}",
+                            v.len()
+                        ))),
+                        VectorIn::Sparse(_) => Err(ServiceError::InvalidInput(format!(
+                            "candidates[{i}] is sparse but query is dense"
+                        ))),
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(Self::Dense { query, candidates })
+            }
+            VectorIn::Sparse(query) => {
+                let query = to_sparse(query, "query")?;
+                let candidates = candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, c)| match c {
+                        VectorIn::Sparse(v) => to_sparse(v, &format!("candidates[{i}]")),
+                        VectorIn::Dense(_) => Err(ServiceError::InvalidInput(format!(
+                            "candidates[{i}] is dense but query is sparse"
+                        ))),
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(Self::Sparse { query, candidates })
+            }
+        }
+    }
+}
+
+/// Distance to the k-th nearest neighbor for every point, plus its distribution.
+///
+/// Sorting these distances gives the classic "k-distance graph" used to pick
+/// DBSCAN `eps`; a mass of near-zero distances flags degenerate embeddings.
+///
+/// - `k` defaults to 4 and must be `< points.len()`
+/// - `metric` defaults to Euclidean; `bins` defaults to 20 (min 2)
+/// - `dtype: "f32"` computes in single precision
+#[utoipa::path(
+    post,
+    path = "/stats/vector/knn-distances",
+    tag = "vector",
+    summary = "Distance to k-th nearest neighbor per point, with distribution summary",
+    responses(
+        (status = 200, description = "OK", body = KnnDistOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn stats_knn_distances(
+    State(state): State<Arc<AppState>>,
+    Json(inp): Json<KnnDistIn>,
+) -> Result<(NUsed, Json<KnnDistOut>), ServiceError> {
+    let k = inp.k.unwrap_or(4);
+    if k == 0 || k >= inp.points.len() {
+        return Err(ServiceError::InvalidInput(format!(
+            "k must be in [1, {}) for {} points",
+            inp.points.len(),
+            inp.points.len()
+        )));
+    }
+    let metric = inp.metric.unwrap_or(VectorMetric::Euclidean);
+    let n = inp.points.len();
+    let size = inp.points.values();
+    let distances = state
+        .compute
+        .run(size, move |_| {
+            Ok(with_dtype!(inp.points, inp.dtype, |points| {
+                check_dims(&points)?;
+                kth_nn_distances(&points, k, metric_fn(metric))
+            }))
+        })
+        .await?;
+
+    // Zero vectors have undefined cosine distance; summarize the defined ones.
+    let ds: Vec<f64> = distances
+        .iter()
+        .copied()
+        .filter(|d| d.is_finite())
+        .collect();
+    let (counts, edges) = histogram(&ds, inp.bins.unwrap_or(20).max(2));
+    let quantiles = if ds.is_empty() {
+        vec![]
+    } else {
+        let ds = sorted(&ds);
+        [0.05, 0.25, 0.5, 0.75, 0.95]
+            .into_iter()
+            .map(|p| (p, quantile_sorted(&ds, p)))
+            .collect()
+    };
+
+    #[inline]
+    fn o(x: f64) -> Option<f64> {
+        if x.is_nan() { None } else { Some(x) }
+    }
+
+    let m = mean(&ds);
+    Ok((
+        NUsed(n),
+        Json(KnnDistOut {
+            k,
+            mean: o(m),
+            std: o(sample_std_dev(&ds, m)),
+            min: o(min(&ds)),
+            max: o(max(&ds)),
+            quantiles,
+            counts,
+            edges,
+            distances,
+        }),
+    ))
+}
+
+/// Estimate the intrinsic dimension of a point set (Euclidean geometry).
+///
+/// A value far below the ambient dimension means embeddings can be compressed
+/// (PCA, quantization) with little loss.
+///
+/// - `method` defaults to `two_nn`; `mle` uses `k` neighbors (default 10)
+/// - `dimension` is `None` when undefined (fewer than 3 points, all duplicates, `k` out of range)
+/// - `dtype: "f32"` computes in single precision
+#[utoipa::path(
+    post,
+    path = "/stats/vector/intrinsic-dim",
+    tag = "vector",
+    summary = "Intrinsic dimension estimate (TwoNN or MLE) of an embedding set",
+    responses(
+        (status = 200, description = "OK", body = IntrinsicDimOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn stats_intrinsic_dim(
+    State(state): State<Arc<AppState>>,
+    Json(inp): Json<IntrinsicDimIn>,
+) -> Result<(NUsed, Json<IntrinsicDimOut>), ServiceError> {
+    let method = inp.method.unwrap_or(IntrinsicDimMethod::TwoNn);
+    let n = inp.points.len();
+    let size = inp.points.values();
+    let (ambient_dim, d) = state
+        .compute
+        .run(size, move |_| {
+            Ok(with_dtype!(inp.points, inp.dtype, |points| {
+                let ambient_dim = check_dims(&points)?;
+                let d = match method {
+                    IntrinsicDimMethod::TwoNn => two_nn_dimension(&points),
+                    IntrinsicDimMethod::Mle => mle_dimension(&points, inp.k.unwrap_or(10)),
+                };
+                (ambient_dim, d)
+            }))
+        })
+        .await?;
+    Ok((
+        NUsed(n),
+        Json(IntrinsicDimOut {
+            method,
+            dimension: d.is_finite().then_some(d),
+            ambient_dim,
+            n,
+        }),
+    ))
+}
+
+/// Flag embedding pairs above a cosine-similarity threshold and group them
+/// transitively (union-find), e.g. to dedupe a RAG chunk store.
+///
+/// - `threshold` defaults to `0.95` and must lie in `[-1, 1]`
+/// - `redundant` lists every group member except the first (lowest index)
+/// - `dtype: "f32"` computes in single precision
+#[utoipa::path(
+    post,
+    path = "/stats/vector/near-duplicates",
+    tag = "vector",
+    summary = "Near-duplicate embedding pairs and union-find groups above a cosine threshold",
+    responses(
+        (status = 200, description = "OK", body = NearDupOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn stats_near_duplicates(
+    State(state): State<Arc<AppState>>,
+    Json(inp): Json<NearDupIn>,
+) -> Result<(NUsed, Json<NearDupOut>), ServiceError> {
+    let threshold = inp.threshold.unwrap_or(0.95);
+    if !(-1.0..=1.0).contains(&threshold) {
+        return Err(ServiceError::InvalidInput(
+            "threshold must be in [-1, 1]".into(),
+        ));
+    }
+
+    let n = inp.points.len();
+    let size = inp.points.values();
+    let pairs = state
+        .compute
+        .run(size, move |_| {
+            Ok(with_dtype!(inp.points, inp.dtype, |points| {
+                check_dims(&points)?;
+                near_duplicate_pairs(&points, threshold)
+            }))
+        })
+        .await?;
+    let edges: Vec<(usize, usize)> = pairs.iter().map(|&(i, j, _)| (i, j)).collect();
+    let groups = connected_components(n, &edges);
+    let mut redundant: Vec<usize> = groups.iter().flat_map(|g| g[1..].iter().copied()).collect();
+    redundant.sort_unstable();
+
+    Ok((
+        NUsed(n),
+        Json(NearDupOut {
+            threshold,
+            pairs: pairs
+                .into_iter()
+                .map(|(i, j, similarity)| NearDupPair { i, j, similarity })
+                .collect(),
+            groups,
+            redundant,
+        }),
+    ))
+}
+
+/// Score candidates against a query with a dot-product or cosine kernel.
+///
+/// Vectors may be dense (arrays or base64 `f32`) or sparse `{indices, values}`,
+/// but not mixed within one request; sparse kernels use a merge join, so
+/// BM25/SPLADE-style vectors over large vocabularies stay cheap.
+///
+/// - `kernel` defaults to cosine; zero vectors score None under cosine
+#[utoipa::path(
+    post,
+    path = "/stats/vector/similarity",
+    tag = "vector",
+    summary = "Dot/cosine similarity of dense or sparse candidates to a query",
+    responses(
+        (status = 200, description = "OK", body = SimilarityOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn stats_similarity(
+    Json(inp): Json<SimilarityIn>,
+) -> Result<(NUsed, Json<SimilarityOut>), ServiceError> {
+    let kernel = inp.kernel.unwrap_or(SimilarityKernel::Cosine);
+    let scores: Vec<f64> = match VectorBatch::resolve(inp.query, inp.candidates)? {
+        VectorBatch::Dense { query, candidates } => candidates
+            .iter()
+            .map(|c| match kernel {
+                SimilarityKernel::Cosine => cosine_similarity(c, &query),
+                SimilarityKernel::Dot => dot(c, &query),
+            })
+            .collect(),
+        VectorBatch::Sparse { query, candidates } => candidates
+            .iter()
+            .map(|c| match kernel {
+                SimilarityKernel::Cosine => sparse_cosine_similarity(c, &query),
+                SimilarityKernel::Dot => sparse_dot(c, &query),
+            })
+            .collect(),
+    };
+
+    Ok((
+        NUsed(scores.len()),
+        Json(SimilarityOut {
+            kernel,
+            scores: scores
+                .into_iter()
+                .map(|s| s.is_finite().then_some(s))
+                .collect(),
+        }),
+    ))
+}
@@ -0,0 +1,385 @@
+//! /ws/stats (feature `ws`)
+//!
+//! A live-dashboard channel: the client pushes numeric chunks as text frames
+//! and receives a [`RunningStatsOut`] at most every `interval_ms` while new
+//! values arrive. State lives in the connection only, in online
+//! accumulators, so memory stays constant however long the stream runs.
+//! With `detector`, each incoming value is also scored against the stream
+//! so far and outliers are listed in the next update.
+
+use crate::{
+    error::ServiceError,
+    state::AppState,
+    stats::prelude::*,
+    types::{
+        ErrorResponse, RunningStatsOut, StreamChunkIn, StreamDetector, StreamOutlier, WsStatsQuery,
+    },
+    validate::invalid,
+};
+use axum::{
+    extract::{
+        Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::{IntoResponse, Response},
+};
+use std::{sync::Arc, time::Duration};
+use tokio::time::{MissedTickBehavior, interval};
+
+/// Quantiles tracked when the client names none.
+const DEFAULT_QUANTILES: [f64; 5] = [0.05, 0.25, 0.5, 0.75, 0.95];
+
+/// Most quantiles one connection may track.
+const MAX_QUANTILES: usize = 32;
+
+/// Window sizes `robust_z` accepts.
+const WINDOW: std::ops::RangeInclusive<usize> = 10..=10_000;
+
+/// Most outliers listed in one update; `flagged` still counts the rest.
+const MAX_LISTED: usize = 1_000;
+
+fn nan_none(x: f64) -> Option<f64> {
+    (!x.is_nan()).then_some(x)
+}
+
+fn parse_quantiles(s: Option<&str>) -> Result<Vec<f64>, ServiceError> {
+    let Some(s) = s.filter(|s| !s.trim().is_empty()) else {
+        return Ok(DEFAULT_QUANTILES.to_vec());
+    };
+    let ps = s
+        .split(',')
+        .map(|p| match p.trim().parse::<f64>() {
+            Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+            _ => Err(ServiceError::InvalidInput(format!(
+                "quantiles: '{}' is not a probability in [0, 1]",
+                p.trim()
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if ps.len() > MAX_QUANTILES {
+        return Err(ServiceError::InvalidInput(format!(
+            "quantiles: at most {MAX_QUANTILES} allowed, got {}",
+            ps.len()
+        )));
+    }
+    Ok(ps)
+}
+
+/// An online outlier detector of one connection.
+enum Detector {
+    RobustZ(RollingRobustZ),
+    Ewma(EwmaResidual),
+}
+
+impl Detector {
+    fn push(&mut self, x: f64) -> f64 {
+        match self {
+            Detector::RobustZ(d) => d.push(x),
+            Detector::Ewma(d) => d.push(x),
+        }
+    }
+}
+
+/// Outlier detection of one connection: the detector, its threshold, and
+/// what it flagged.
+struct Flagging {
+    detector: Detector,
+    threshold: f64,
+    flagged: u64,
+    pending: Vec<StreamOutlier>,
+}
+
+/// The detector `q` asks for, if any.
+fn parse_detector(q: &WsStatsQuery) -> Result<Option<Flagging>, ServiceError> {
+    let Some(kind) = q.detector else {
+        return Ok(None);
+    };
+    let detector = match kind {
+        StreamDetector::RobustZ => {
+            let window = q.window.unwrap_or(100);
+            if !WINDOW.contains(&window) {
+                return Err(ServiceError::InvalidInput(format!(
+                    "window: must be in 10..=10000, got {window}"
+                )));
+            }
+            Detector::RobustZ(RollingRobustZ::new(window))
+        }
+        StreamDetector::Ewma => {
+            let alpha = q.alpha.unwrap_or(0.1);
+            if !(alpha > 0.0 && alpha <= 1.0) {
+                return Err(ServiceError::InvalidInput(format!(
+                    "alpha: must be in (0, 1], got {alpha}"
+                )));
+            }
+            Detector::Ewma(EwmaResidual::new(alpha))
+        }
+    };
+    let threshold = q.threshold.unwrap_or(3.5);
+    if !(threshold.is_finite() && threshold > 0.0) {
+        return Err(ServiceError::InvalidInput(format!(
+            "threshold: must be positive, got {threshold}"
+        )));
+    }
+    Ok(Some(Flagging {
+        detector,
+        threshold,
+        flagged: 0,
+        pending: Vec::new(),
+    }))
+}
+
+/// Accumulated statistics of one connection.
+struct RunningStats {
+    moments: OnlineMeanVar,
+    missing: u64,
+    min: f64,
+    max: f64,
+    sketches: Vec<P2Quantile>,
+    flagging: Option<Flagging>,
+}
+
+impl RunningStats {
+    fn new(quantiles: &[f64], flagging: Option<Flagging>) -> Self {
+        Self {
+            moments: OnlineMeanVar::new(),
+            missing: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            sketches: quantiles.iter().map(|&p| P2Quantile::new(p)).collect(),
+            flagging,
+        }
+    }
+
+    /// Entries received so far, `null`s included.
+    fn position(&self) -> u64 {
+        self.moments.count() + self.missing
+    }
+
+    fn push(&mut self, values: &[Option<f64>]) {
+        for v in values {
+            match v {
+                Some(x) => {
+                    let index = self.position();
+                    if let Some(f) = &mut self.flagging {
+                        let score = f.detector.push(*x);
+                        if score > f.threshold {
+                            f.flagged += 1;
+                            if f.pending.len() < MAX_LISTED {
+                                f.pending.push(StreamOutlier {
+                                    index,
+                                    value: *x,
+                                    score: score.is_finite().then_some(score),
+                                });
+                            }
+                        }
+                    }
+                    self.moments.push(*x);
+                    self.min = self.min.min(*x);
+                    self.max = self.max.max(*x);
+                    self.sketches.iter_mut().for_each(|q| q.push(*x));
+                }
+                None => self.missing += 1,
+            }
+        }
+    }
+
+    /// Statistics so far; hands over the outliers flagged since the last
+    /// snapshot.
+    fn snapshot(&mut self) -> RunningStatsOut {
+        let count = self.moments.count();
+        let seen = count > 0;
+        RunningStatsOut {
+            count,
+            missing: self.missing,
+            mean: seen.then(|| self.moments.mean()),
+            std: (count > 1).then(|| self.moments.sample_std()),
+            min: seen.then_some(self.min),
+            max: seen.then_some(self.max),
+            quantiles: self
+                .sketches
+                .iter()
+                .map(|q| (q.p(), nan_none(q.value())))
+                .collect(),
+            flagged: self.flagging.as_ref().map(|f| f.flagged),
+            outliers: self
+                .flagging
+                .as_mut()
+                .map(|f| std::mem::take(&mut f.pending)),
+        }
+    }
+}
+
+/// Parse a text frame into values, enforcing the per-chunk limit.
+fn chunk(text: &str, max_values: usize) -> Result<Vec<Option<f64>>, ServiceError> {
+    let values = match serde_json::from_str(text) {
+        Ok(StreamChunkIn::Values(v) | StreamChunkIn::Object { values: v }) => v,
+        Err(e) => {
+            return Err(ServiceError::InvalidInput(format!(
+                "expected an array of numbers or {{\"values\": [...]}}: {e}"
+            )));
+        }
+    };
+    if values.len() > max_values {
+        return Err(invalid(
+            "/values",
+            format!("has {} values; at most {max_values} allowed", values.len()),
+        ));
+    }
+    Ok(values)
+}
+
+/// Frame for a value that always serializes.
+fn json<T: serde::Serialize>(v: &T) -> Message {
+    Message::Text(serde_json::to_string(v).unwrap_or_default().into())
+}
+
+async fn run(mut socket: WebSocket, mut stats: RunningStats, every: Duration, max_values: usize) {
+    let mut tick = interval(every);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut changed = false;
+    loop {
+        tokio::select! {
+            msg = socket.recv() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(t))) => t,
+                    Some(Ok(Message::Binary(_))) => {
+                        let e = ServiceError::InvalidInput("send chunks as JSON text frames".into());
+                        if socket.send(json(&e.to_response())).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    // Pings are answered by axum
+                    Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                };
+                match chunk(&text, max_values) {
+                    Ok(values) => {
+                        stats.push(&values);
+                        changed = true;
+                    }
+                    Err(e) => {
+                        if socket.send(json(&e.to_response())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            _ = tick.tick(), if changed => {
+                changed = false;
+                if socket.send(json(&stats.snapshot())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Live running statistics over a WebSocket.
+///
+/// - **Client → server**: text frames holding a [`StreamChunkIn`] — a JSON
+///   array of numbers (`null` = missing) or `{"values": [...]}`, each within
+///   [`ServiceConfig::max_values`](crate::config::ServiceConfig::max_values)
+/// - **Server → client**: a [`RunningStatsOut`] at most every `interval_ms`
+///   after new values arrive; quantiles are P² estimates ([`P2Quantile`])
+/// - **Outliers**: with `detector=robust_z` (median/MAD of the last `window`
+///   values, [`RollingRobustZ`]) or `detector=ewma` ([`EwmaResidual`]), each
+///   value scoring above `threshold` against the values before it is listed
+///   in the next update's `outliers`; scoring starts after
+///   [`OUTLIER_WARMUP`] values
+/// - **Errors**: `400` before the upgrade for bad `quantiles`, `window`,
+///   `alpha` or `threshold`; a malformed
+///   chunk is answered with an [`ErrorResponse`] frame and the socket stays
+///   open
+#[utoipa::path(
+    get,
+    path = "/ws/stats",
+    tag = "stats",
+    summary = "Live running statistics over a WebSocket",
+    params(WsStatsQuery),
+    responses(
+        (status = 101, description = "Switching to WebSocket; frames carry RunningStatsOut", body = RunningStatsOut),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn ws_stats(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<WsStatsQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ServiceError> {
+    let stats = RunningStats::new(
+        &parse_quantiles(q.quantiles.as_deref())?,
+        parse_detector(&q)?,
+    );
+    let every = Duration::from_millis(q.interval_ms.unwrap_or(500).max(50));
+    let max_values = state.config.max_values;
+    Ok(ws
+        .max_message_size(state.config.max_body_bytes)
+        .on_upgrade(move |socket| run(socket, stats, every, max_values))
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_default_and_validate() {
+        assert_eq!(parse_quantiles(None).unwrap(), DEFAULT_QUANTILES);
+        assert_eq!(parse_quantiles(Some(" 0.1, 0.9")).unwrap(), [0.1, 0.9]);
+        assert!(parse_quantiles(Some("0.5,1.5")).is_err());
+        assert!(parse_quantiles(Some("median")).is_err());
+    }
+
+    #[test]
+    fn chunks_accumulate() {
+        let mut s = RunningStats::new(&[0.5], None);
+        assert_eq!(s.snapshot().mean, None);
+        s.push(&chunk("[1, 2, null]", 10).unwrap());
+        s.push(&chunk(r#"{"values": [3]}"#, 10).unwrap());
+        let out = s.snapshot();
+        assert_eq!((out.count, out.missing), (3, 1));
+        assert_eq!(out.mean, Some(2.0));
+        assert_eq!(out.std, Some(1.0));
+        assert_eq!((out.min, out.max), (Some(1.0), Some(3.0)));
+        assert_eq!(out.quantiles, [(0.5, Some(2.0))]);
+        assert_eq!((out.flagged, out.outliers), (None, None));
+
+        assert!(chunk("[1, 2, 3]", 2).is_err());
+        assert!(chunk(r#"{"x": 1}"#, 10).is_err());
+    }
+
+    #[test]
+    fn detector_flags_incoming_outliers() {
+        let q = |detector, window, alpha, threshold| WsStatsQuery {
+            detector,
+            window,
+            alpha,
+            threshold,
+            ..Default::default()
+        };
+        let (robust, ewma) = (Some(StreamDetector::RobustZ), Some(StreamDetector::Ewma));
+        assert!(
+            parse_detector(&q(None, None, None, None))
+                .unwrap()
+                .is_none()
+        );
+        assert!(parse_detector(&q(robust, Some(5), None, None)).is_err());
+        assert!(parse_detector(&q(ewma, None, Some(0.0), None)).is_err());
+        assert!(parse_detector(&q(ewma, None, None, Some(-1.0))).is_err());
+
+        let flagging = parse_detector(&q(robust, Some(20), None, None)).unwrap();
+        let mut s = RunningStats::new(&[], flagging);
+        let calm: Vec<Option<f64>> = (0..20).map(|i| Some(5.0 + (i % 3) as f64 * 0.1)).collect();
+        s.push(&calm);
+        s.push(&[None, Some(50.0), Some(5.1)]);
+        let out = s.snapshot();
+        assert_eq!(out.flagged, Some(1));
+        let outliers = out.outliers.unwrap();
+        assert_eq!((outliers[0].index, outliers[0].value), (21, 50.0));
+        assert!(outliers[0].score.unwrap() > 3.5);
+        // listed once, counted for good
+        let out = s.snapshot();
+        assert_eq!((out.flagged, out.outliers), (Some(1), Some(vec![])));
+    }
+}
@@ -0,0 +1,90 @@
+//! /describe-xlsx and /stats/summary-xlsx (feature `xlsx`)
+
+use crate::{
+    envelope::NUsed,
+    error::ServiceError,
+    fields::Fields,
+    ingest::{CsvOptions, CsvTable, read_xlsx},
+    routes::{
+        describe::describe_table,
+        export::{FormatQuery, OutputFormat, Tabular},
+        stats_summary::summarize,
+    },
+    state::AppState,
+    types::{CsvQuery, DescribeOutput, ErrorResponse, SummaryOut},
+};
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Query, State},
+};
+use std::sync::Arc;
+
+fn load(state: &AppState, q: &CsvQuery, body: &Bytes) -> Result<CsvTable, ServiceError> {
+    let opts = CsvOptions::from_query(q, state.config.seed)?;
+    let (_, table) = read_xlsx(body, q.sheet.as_deref(), &opts)?;
+    Ok(table)
+}
+
+/// Compute descriptive stats from an uploaded `.xlsx` workbook.
+///
+/// Same column options and schema echo as `/describe-csv`, plus `sheet`
+/// (defaults to the first worksheet).
+///
+/// - **Request**: body `application/vnd.openxmlformats-officedocument.spreadsheetml.sheet`
+/// - **Response**: [`DescribeOutput`] with `schema` (`200 OK`)
+/// - **Errors**: `SpreadsheetParse`, `NoNumeric`, `InvalidInput` (unknown sheet/column)
+#[utoipa::path(
+    post,
+    path = "/describe-xlsx",
+    tag = "spreadsheets",
+    summary = "Compute stats for an uploaded .xlsx worksheet",
+    request_body(content = String, content_type = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", description = "Workbook upload"),
+    params(CsvQuery),
+    responses(
+        (status = 200, description = "OK", body = DescribeOutput),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn describe_xlsx(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<CsvQuery>,
+    body: Bytes,
+) -> Result<(NUsed, Json<DescribeOutput>), ServiceError> {
+    let out = describe_table(&load(&state, &q, &body)?)?;
+    Ok((NUsed(out.count), Json(out)))
+}
+
+/// Core univariate summary over the numeric cells of an `.xlsx` worksheet.
+///
+/// - **Request**: workbook body; query [`CsvQuery`] incl. `sheet`
+/// - **Response**: [`SummaryOut`] with `schema` (`200 OK`), or CSV/TSV as for
+///   `/stats/summary`
+#[utoipa::path(
+    post,
+    path = "/stats/summary-xlsx",
+    tag = "spreadsheets",
+    summary = "Univariate summary of an uploaded .xlsx worksheet",
+    request_body(content = String, content_type = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", description = "Workbook upload"),
+    params(CsvQuery, FormatQuery),
+    responses(
+        (status = 200, description = "OK", content(
+            (SummaryOut = "application/json"),
+            (String = "text/csv"),
+            (String = "text/tab-separated-values")
+        )),
+        (status = 400, description = "Bad Request", body = ErrorResponse)
+    )
+)]
+pub async fn stats_summary_xlsx(
+    State(state): State<Arc<AppState>>,
+    fmt: OutputFormat,
+    Query(q): Query<CsvQuery>,
+    body: Bytes,
+) -> Result<(NUsed, Tabular<SummaryOut>), ServiceError> {
+    let table = load(&state, &q, &body)?;
+    let mut out = summarize(&table.numeric_cells(), &Fields::all(), None);
+    out.schema = Some(table.schema());
+    out.sample = table.sample;
+    Ok((NUsed(out.count), Tabular(fmt, out)))
+}
@@ -0,0 +1,281 @@
+//! # Shared response cache (feature `redis`)
+//!
+//! Lets replicas share computed responses through Redis. A request's key is a
+//! SHA-256 over its method, path and query, tenant, `Accept` and
+//! `Content-Type` headers and body, so identical requests from one tenant to
+//! any replica are answered from the first one's result. Only `POST`s to the
+//! analysis endpoints are cached (not ingestion or jobs, which have side
+//! effects), and only `200 OK` responses up to
+//! [`RedisConfig::max_entry_bytes`] are stored, for [`RedisConfig::ttl_secs`].
+//!
+//! Requests reading a registered dataset (`?dataset=`) are not cached:
+//! dataset ids are assigned per replica, so the same id names different
+//! tables on different replicas.
+//!
+//! Each cacheable response carries an [`X_CACHE`] header: `hit`, `miss`, or
+//! `bypass` when Redis could not be reached — the request is then computed
+//! as usual.
+
+use crate::{config::RedisConfig, datasets, envelope::NUsed, error::ServiceError, tenant::Tenant};
+use axum::{
+    body::{Body, Bytes, HttpBody, to_bytes},
+    extract::{OriginalUri, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use redis::{AsyncCommands, aio::ConnectionManager};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Response header reporting the cache outcome.
+pub const X_CACHE: HeaderName = HeaderName::from_static("x-cache");
+
+/// Route prefixes (below `/api/v1`) whose `POST` responses are cached.
+const CACHED_PREFIXES: [&str; 4] = ["/stats/", "/describe", "/profile", "/schema/infer"];
+
+/// Handle to the Redis-backed response cache.
+#[derive(Clone)]
+pub struct SharedCache {
+    conn: ConnectionManager,
+    ttl_secs: u64,
+    max_entry_bytes: usize,
+    /// Request bodies larger than this are rejected (`413`), as by the
+    /// buffered routes' own limit
+    max_body_bytes: usize,
+}
+
+impl fmt::Debug for SharedCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedCache")
+            .field("ttl_secs", &self.ttl_secs)
+            .field("max_entry_bytes", &self.max_entry_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SharedCache {
+    /// Connect when `cfg.url` is set; `Ok(None)` means caching is disabled.
+    pub async fn connect(
+        cfg: &RedisConfig,
+        max_body_bytes: usize,
+    ) -> Result<Option<Self>, redis::RedisError> {
+        let Some(url) = &cfg.url else {
+            return Ok(None);
+        };
+        let conn = ConnectionManager::new(redis::Client::open(url.as_str())?).await?;
+        Ok(Some(Self {
+            conn,
+            ttl_secs: cfg.ttl_secs.max(1),
+            max_entry_bytes: cfg.max_entry_bytes,
+            max_body_bytes,
+        }))
+    }
+}
+
+/// Redis key for a request; the crate version keeps deployments with
+/// different output formats apart.
+pub fn cache_key(
+    method: &Method,
+    uri: &str,
+    tenant: &Tenant,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> String {
+    let mut h = Sha256::new();
+    for part in [
+        method.as_str().as_bytes(),
+        uri.as_bytes(),
+        tenant.as_str().as_bytes(),
+        headers
+            .get(header::ACCEPT)
+            .map_or(&b""[..], HeaderValue::as_bytes),
+        headers
+            .get(header::CONTENT_TYPE)
+            .map_or(&b""[..], HeaderValue::as_bytes),
+        body,
+    ] {
+        h.update((part.len() as u64).to_le_bytes());
+        h.update(part);
+    }
+    let digest: String = h.finalize().iter().map(|b| format!("{b:02x}")).collect();
+    format!("stats_rs:{}:{digest}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Stored form: the content type, a newline, the handler's [`NUsed`] (empty
+/// when it reported none), a newline, then the body.
+fn encode(content_type: &[u8], n_used: Option<NUsed>, body: &[u8]) -> Vec<u8> {
+    let n = n_used.map(|n| n.0.to_string()).unwrap_or_default();
+    [content_type, b"\n", n.as_bytes(), b"\n", body].concat()
+}
+
+fn decode(entry: &[u8]) -> Option<(&[u8], Option<NUsed>, &[u8])> {
+    let (ct, rest) = entry.split_at(entry.iter().position(|&b| b == b'\n')?);
+    let (n, body) = rest[1..].split_at(rest[1..].iter().position(|&b| b == b'\n')?);
+    let n_used = match n {
+        b"" => None,
+        n => Some(NUsed(std::str::from_utf8(n).ok()?.parse().ok()?)),
+    };
+    Some((ct, n_used, &body[1..]))
+}
+
+fn with_status(mut res: Response, status: &'static str) -> Response {
+    res.headers_mut()
+        .insert(X_CACHE, HeaderValue::from_static(status));
+    res
+}
+
+/// Axum middleware serving and filling the shared cache.
+pub async fn middleware(
+    State(cache): State<SharedCache>,
+    OriginalUri(uri): OriginalUri,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path();
+    if req.method() != Method::POST
+        || !CACHED_PREFIXES.iter().any(|p| path.starts_with(p))
+        || datasets::named_in_query(req.uri().query())
+    {
+        return next.run(req).await;
+    }
+
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes(body, cache.max_body_bytes).await {
+        Ok(b) => b,
+        Err(_) => {
+            return ServiceError::TooLarge("request body exceeds the size limit".into())
+                .into_response();
+        }
+    };
+    let tenant = parts
+        .extensions
+        .get::<Tenant>()
+        .cloned()
+        .unwrap_or_default();
+    let key = cache_key(
+        &parts.method,
+        &uri.to_string(),
+        &tenant,
+        &parts.headers,
+        &body,
+    );
+    let mut conn = cache.conn.clone();
+
+    let cached: Result<Option<Vec<u8>>, _> = conn.get(&key).await;
+    let reachable = match cached {
+        Ok(Some(entry)) => {
+            if let Some((ct, n_used, bytes)) = decode(&entry) {
+                let mut res = Bytes::copy_from_slice(bytes).into_response();
+                if let Ok(ct) = HeaderValue::from_bytes(ct) {
+                    res.headers_mut().insert(header::CONTENT_TYPE, ct);
+                }
+                if let Some(n) = n_used {
+                    res.extensions_mut().insert(n);
+                }
+                return with_status(res, "hit");
+            }
+            true
+        }
+        Ok(None) => true,
+        Err(e) => {
+            tracing::warn!("shared cache lookup failed: {e}");
+            false
+        }
+    };
+
+    let res = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if !reachable {
+        return with_status(res, "bypass");
+    }
+    // Only buffer bodies of known, cacheable size; anything else streams through
+    let storable = res
+        .body()
+        .size_hint()
+        .exact()
+        .is_some_and(|n| n <= cache.max_entry_bytes as u64);
+    if res.status() != StatusCode::OK || !storable {
+        return with_status(res, "miss");
+    }
+
+    let (parts, body) = res.into_parts();
+    let bytes = match to_bytes(body, cache.max_entry_bytes).await {
+        Ok(b) => b,
+        Err(e) => return ServiceError::InvalidInput(format!("body: {e}")).into_response(),
+    };
+    let ct = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .map_or(&b""[..], HeaderValue::as_bytes);
+    let n_used = parts.extensions.get::<NUsed>().copied();
+    let stored: Result<(), _> = conn
+        .set_ex(&key, encode(ct, n_used, &bytes), cache.ttl_secs)
+        .await;
+    if let Err(e) = stored {
+        tracing::warn!("shared cache store failed: {e}");
+    }
+    with_status(Response::from_parts(parts, Body::from(bytes)), "miss")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_covers_route_tenant_format_and_body() {
+        let json = HeaderMap::new();
+        let mut csv = HeaderMap::new();
+        csv.insert(header::ACCEPT, HeaderValue::from_static("text/csv"));
+        let shared = Tenant::default();
+        let k = |uri, h: &HeaderMap, body: &[u8]| cache_key(&Method::POST, uri, &shared, h, body);
+
+        let base = k("/api/v1/stats/summary", &json, b"[1,2]");
+        assert!(base.starts_with("stats_rs:"));
+        assert_eq!(base, k("/api/v1/stats/summary", &json, b"[1,2]"));
+        assert_ne!(base, k("/api/v1/stats/ecdf", &json, b"[1,2]"));
+        assert_ne!(base, k("/api/v1/stats/summary", &csv, b"[1,2]"));
+        assert_ne!(base, k("/api/v1/stats/summary?format=csv", &json, b"[1,2]"));
+        assert_ne!(base, k("/api/v1/stats/summary", &json, b"[1,3]"));
+
+        let (a, b) = (
+            Tenant::new("team-a").unwrap(),
+            Tenant::new("team-b").unwrap(),
+        );
+        let uri = "/api/v1/stats/corr-matrix-csv";
+        assert_ne!(
+            cache_key(&Method::POST, uri, &a, &json, b"x,y\n1,2\n"),
+            cache_key(&Method::POST, uri, &b, &json, b"x,y\n1,2\n")
+        );
+    }
+
+    #[test]
+    fn dataset_requests_are_recognized() {
+        assert!(datasets::named_in_query(Some("dataset=ds_1")));
+        assert!(datasets::named_in_query(Some(
+            "method=spearman&dataset=ds_1"
+        )));
+        assert!(!datasets::named_in_query(Some("datasets=1&x=dataset")));
+        assert!(!datasets::named_in_query(None));
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let e = encode(b"text/csv; charset=utf-8", None, b"x,p\n1,0.5\n");
+        assert_eq!(
+            decode(&e),
+            Some((&b"text/csv; charset=utf-8"[..], None, &b"x,p\n1,0.5\n"[..]))
+        );
+        let e = encode(b"application/json", Some(NUsed(4)), b"{\"count\":4}");
+        assert_eq!(
+            decode(&e),
+            Some((
+                &b"application/json"[..],
+                Some(NUsed(4)),
+                &b"{\"count\":4}"[..]
+            ))
+        );
+        assert_eq!(decode(b"no separator"), None);
+        // entries in the old `content type\nbody` form are misses
+        assert_eq!(decode(b"application/json\n{\"count\":4}"), None);
+    }
+}
@@ -0,0 +1,153 @@
+//! # Graceful shutdown
+//!
+//! `main.rs` hands its signal future to [`Shutdown::on`]. When it fires:
+//!
+//! 1. `/ready` starts failing ([`AppState::draining`]) and queued jobs are
+//!    cancelled;
+//! 2. after `shutdown.ready_grace_secs`, listeners stop accepting
+//!    ([`Shutdown::triggered`]);
+//! 3. [`Shutdown::drain`] waits for open requests, then running jobs, for at
+//!    most `shutdown.drain_timeout_secs` from that point.
+//!
+//! A grace period lets load balancers see the failing probe and move traffic
+//! before connections are refused, which keeps rolling deploys clean.
+
+use crate::state::AppState;
+use std::{
+    future::Future,
+    sync::{Arc, atomic::Ordering},
+};
+use tokio::sync::watch;
+
+/// A shutdown sequence waiting on its signal; cheap to clone.
+#[derive(Clone, Debug)]
+pub struct Shutdown {
+    state: Arc<AppState>,
+    stop: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Start the sequence once `signal` completes. Must be called from
+    /// within a Tokio runtime.
+    pub fn on(signal: impl Future<Output = ()> + Send + 'static, state: Arc<AppState>) -> Self {
+        let (tx, stop) = watch::channel(false);
+        let draining = state.clone();
+        tokio::spawn(async move {
+            signal.await;
+            draining.draining.store(true, Ordering::Relaxed);
+            let cancelled = draining.jobs.drain();
+            let grace = draining.config.shutdown.ready_grace();
+            tracing::info!(cancelled, ?grace, "draining: /ready now fails");
+            tokio::time::sleep(grace).await;
+            let _ = tx.send(true);
+        });
+        Self { state, stop }
+    }
+
+    /// Resolves when listeners should stop accepting connections.
+    pub async fn triggered(&self) {
+        let mut stop = self.stop.clone();
+        let _ = stop.wait_for(|s| *s).await;
+    }
+
+    /// Await `server` (which should stop on [`triggered`](Self::triggered)),
+    /// then the running jobs, giving up `shutdown.drain_timeout_secs` after
+    /// the trigger.
+    pub async fn drain<E>(&self, server: impl Future<Output = Result<(), E>>) -> Result<(), E> {
+        let timeout = self.state.config.shutdown.drain_timeout();
+        let finish = async {
+            server.await?;
+            self.state.jobs.idle().await;
+            Ok(())
+        };
+        tokio::select! {
+            done = finish => done,
+            _ = async { self.triggered().await; tokio::time::sleep(timeout).await } => {
+                tracing::warn!(?timeout, "drain timed out; exiting with work in flight");
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{ServiceConfig, ShutdownConfig},
+        tenant::Tenant,
+        types::JobKind,
+    };
+    use std::{convert::Infallible, time::Duration};
+    use tokio::sync::oneshot;
+
+    fn state(drain_timeout_secs: u64, ready_grace_secs: u64) -> Arc<AppState> {
+        Arc::new(AppState {
+            config: ServiceConfig {
+                shutdown: ShutdownConfig {
+                    drain_timeout_secs,
+                    ready_grace_secs,
+                },
+                ..Default::default()
+            },
+            jobs: crate::jobs::JobRegistry::new(1),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn fails_ready_and_waits_for_running_jobs() {
+        let state = state(30, 0);
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let running = state
+            .jobs
+            .submit(&Tenant::default(), JobKind::CorrMatrix, move |_| {
+                rx.recv().ok();
+                Ok(serde_json::Value::Null)
+            });
+        let queued = state
+            .jobs
+            .submit(&Tenant::default(), JobKind::CorrMatrix, |_| {
+                Ok(serde_json::Value::Null)
+            });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let (signal, fired) = oneshot::channel::<()>();
+        let shutdown = Shutdown::on(
+            async {
+                let _ = fired.await;
+            },
+            state.clone(),
+        );
+        assert!(!state.draining.load(Ordering::Relaxed));
+        signal.send(()).unwrap();
+
+        let stopped = shutdown.clone();
+        let drained = tokio::spawn(async move {
+            stopped
+                .drain(async {
+                    stopped.triggered().await;
+                    Ok::<_, Infallible>(())
+                })
+                .await
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(state.draining.load(Ordering::Relaxed));
+        assert_eq!(queued.status().status, crate::types::JobStatus::Cancelled);
+        assert!(!drained.is_finished(), "waits for the running job");
+
+        tx.send(()).unwrap();
+        drained.await.unwrap().unwrap();
+        assert_eq!(running.status().status, crate::types::JobStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_drain_timeout() {
+        let shutdown = Shutdown::on(async {}, state(0, 0));
+        let hung = std::future::pending::<Result<(), Infallible>>();
+        tokio::time::timeout(Duration::from_secs(5), shutdown.drain(hung))
+            .await
+            .expect("drain returns at the timeout")
+            .unwrap();
+    }
+}
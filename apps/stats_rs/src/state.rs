@@ -8,11 +8,13 @@
 //! The state is wrapped in an [`Arc`](std::sync::Arc) and cloned into
 //! each request handler via Axum’s `.with_state()` mechanism.
 //!
-//! While currently empty, this struct serves as a foundation for adding
-//! shared resources such as:
+//! It currently holds the [`DatasetRegistry`], the [`JobRegistry`], the
+//! [`ArtifactStore`], the [`ResultCache`], the [`ComputePool`], the
+//! [`ServiceConfig`] that [`build_app`](crate::build_app) reads its limits and
+//! toggles (and ingestion settings) from, the [`LogControl`] behind
+//! `/admin/log-level` and the [`AuditLog`](crate::audit::AuditLog); further shared resources
+//! can be added such as:
 //!
-//! - Cached models or compiled statistical kernels
-//! - Configuration data or feature flags
 //! - Global rate limiter or metrics handles
 //!
 //! Example usage from [`lib.rs`](crate::build_app):
@@ -30,6 +32,12 @@
 //! }
 //! ```
 
+use crate::{
+    artifacts::ArtifactStore, cache::ResultCache, compute::ComputePool, config::ServiceConfig,
+    datasets::DatasetRegistry, jobs::JobRegistry, logging::LogControl,
+};
+use std::sync::{Arc, atomic::AtomicBool};
+
 /// Global shared state for the `stats_rs` service.
 ///
 /// Cloned and shared across all request handlers.
@@ -39,13 +47,35 @@
 /// # Example
 ///
 /// ```rust,ignore
-/// #[derive(Clone, Default)]
-/// pub struct AppState {
-///     pub cache: Arc<Mutex<HashMap<String, f64>>>,
-/// }
+/// let config = ServiceConfig::load()?;
+/// let state = AppState {
+///     cache: ResultCache::new(&config.cache),
+///     config,
+///     ..Default::default()
+/// };
 /// ```
-///
-/// The current implementation is an empty struct, ready for extension
-/// as the microservice evolves.
-#[derive(Clone, Default)]
-pub struct AppState;
+#[derive(Clone, Debug, Default)]
+pub struct AppState {
+    /// Datasets registered by the ingestion endpoints
+    pub datasets: DatasetRegistry,
+    /// Submitted background jobs and their results
+    pub jobs: JobRegistry,
+    /// Large results kept for `/artifacts/{id}`
+    pub artifacts: ArtifactStore,
+    /// Parsed uploads and intermediate results, keyed by content hash
+    pub cache: ResultCache,
+    /// Blocking-pool gate for large computations
+    pub compute: ComputePool,
+    /// Body limits, timeout, CORS, route toggles, cache sizes and
+    /// URL-ingestion allowlist and limits
+    pub config: ServiceConfig,
+    /// Redis response cache, when `config.redis.url` is set
+    #[cfg(feature = "redis")]
+    pub shared_cache: Option<crate::shared_cache::SharedCache>,
+    /// Reloadable log filter, when `main.rs` installed one
+    pub log: Option<LogControl>,
+    /// Audit trail of API requests, when `config.audit` names a store
+    pub audit: Option<Arc<crate::audit::AuditLog>>,
+    /// Set once shutdown begins; `/ready` then fails
+    pub draining: Arc<AtomicBool>,
+}
@@ -0,0 +1,216 @@
+use crate::stats::prelude::*;
+use rand::{Rng, seq::index};
+
+const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+/// Robust distance of each value from the median: `|x − median| / s` with
+/// `s = 1.4826 · MAD`, or `1.2533 · mean |x − median|` when more than half
+/// the values tie at the median. All zeros for a constant series.
+pub fn robust_distances(xs: &[f64]) -> Vec<f64> {
+    if xs.is_empty() {
+        return vec![];
+    }
+    let med = median(xs);
+    let devs: Vec<f64> = xs.iter().map(|&x| (x - med).abs()).collect();
+    let scale = match 1.4826 * median(&devs) {
+        0.0 => 1.253_314 * mean(&devs),
+        s => s,
+    };
+    devs.iter()
+        .map(|&d| if scale > 0.0 { d / scale } else { 0.0 })
+        .collect()
+}
+
+/// How far each value lies outside the interquartile range, in IQRs (0
+/// inside `[Q1, Q3]`). With a zero IQR, values off the quartiles are `∞`.
+pub fn iqr_distances(xs: &[f64]) -> Vec<f64> {
+    if xs.is_empty() {
+        return vec![];
+    }
+    let (q1, _, q3) = quartiles(xs);
+    let iqr = q3 - q1;
+    xs.iter()
+        .map(|&x| match (q1 - x).max(x - q3).max(0.0) {
+            0.0 => 0.0,
+            d if iqr > 0.0 => d / iqr,
+            _ => f64::INFINITY,
+        })
+        .collect()
+}
+
+/// Residual of each value from the median of its `half_window` neighbours on
+/// either side (itself excluded; fewer at the ends).
+pub fn rolling_residuals(xs: &[f64], half_window: usize) -> Vec<f64> {
+    let n = xs.len();
+    (0..n)
+        .map(|i| {
+            let lo = i.saturating_sub(half_window);
+            let hi = (i + half_window + 1).min(n);
+            let around: Vec<f64> = (lo..hi).filter(|&j| j != i).map(|j| xs[j]).collect();
+            match around.is_empty() {
+                true => 0.0,
+                false => xs[i] - median(&around),
+            }
+        })
+        .collect()
+}
+
+/// Average path length of an unsuccessful binary-search-tree lookup among
+/// `n` points, `c(n) = 2H(n − 1) − 2(n − 1)/n`: the isolation-forest
+/// normalizer.
+fn average_path(n: usize) -> f64 {
+    match n {
+        0 | 1 => 0.0,
+        2 => 1.0,
+        _ => {
+            let n = n as f64;
+            2.0 * ((n - 1.0).ln() + EULER_GAMMA) - 2.0 * (n - 1.0) / n
+        }
+    }
+}
+
+enum Node {
+    /// Points left in the leaf
+    Leaf(usize),
+    /// Split value and the children below (`< split`) and above it
+    Split(f64, usize, usize),
+}
+
+fn grow(
+    sample: &mut [f64],
+    depth: usize,
+    limit: usize,
+    rng: &mut impl Rng,
+    nodes: &mut Vec<Node>,
+) -> usize {
+    let at = nodes.len();
+    let (lo, hi) = (min(sample), max(sample));
+    if depth >= limit || sample.len() <= 1 || lo >= hi {
+        nodes.push(Node::Leaf(sample.len()));
+        return at;
+    }
+    let split = rng.random_range(lo..hi);
+    nodes.push(Node::Leaf(0));
+    let mut k = 0;
+    for i in 0..sample.len() {
+        if sample[i] < split {
+            sample.swap(i, k);
+            k += 1;
+        }
+    }
+    let (below, above) = sample.split_at_mut(k);
+    let left = grow(below, depth + 1, limit, rng, nodes);
+    let right = grow(above, depth + 1, limit, rng, nodes);
+    nodes[at] = Node::Split(split, left, right);
+    at
+}
+
+fn path_length(nodes: &[Node], x: f64) -> f64 {
+    let (mut at, mut depth) = (0, 0.0);
+    loop {
+        match nodes[at] {
+            Node::Leaf(size) => return depth + average_path(size),
+            Node::Split(split, left, right) => {
+                at = if x < split { left } else { right };
+                depth += 1.0;
+            }
+        }
+    }
+}
+
+/// Isolation-forest anomaly score of each value, `2^(−E[h(x)] / c(ψ))`:
+/// about 0.5 or below for ordinary values and towards 1 for values that
+/// random splits isolate quickly.
+///
+/// Grows `trees` trees on subsamples of `sample_size` values (at most all of
+/// them) drawn without replacement, each to depth `⌈log₂ ψ⌉`. `progress` is
+/// called with the trees grown and can stop early, leaving the scores of the
+/// trees grown so far. Fewer than two values score 0.5.
+pub fn isolation_forest_scores(
+    xs: &[f64],
+    trees: usize,
+    sample_size: usize,
+    rng: &mut impl Rng,
+    progress: impl Checkpoint,
+) -> Vec<f64> {
+    let n = xs.len();
+    let psi = sample_size.min(n);
+    if psi < 2 || trees == 0 {
+        return vec![0.5; n];
+    }
+    let limit = (psi as f64).log2().ceil() as usize;
+    let mut total = vec![0.0; n];
+    let mut grown = 0;
+    let mut nodes = Vec::new();
+    for t in 0..trees {
+        if progress.cancelled() {
+            break;
+        }
+        let mut sample: Vec<f64> = index::sample(rng, n, psi).iter().map(|i| xs[i]).collect();
+        nodes.clear();
+        grow(&mut sample, 0, limit, rng, &mut nodes);
+        for (h, &x) in total.iter_mut().zip(xs) {
+            *h += path_length(&nodes, x);
+        }
+        grown += 1;
+        progress.reached(t + 1);
+    }
+    let c = average_path(psi);
+    total
+        .into_iter()
+        .map(|h| match grown {
+            0 => 0.5,
+            _ => 2f64.powf(-(h / grown as f64) / c),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn robust_and_iqr_distances() {
+        let xs = [1.0, 2.0, 3.0, 4.0, 100.0];
+        let d = robust_distances(&xs);
+        // median 3, MAD 1
+        approx!(d[0], 2.0 / 1.4826, 1e-12);
+        approx!(d[4], 97.0 / 1.4826, 1e-12);
+        // quartiles 2 and 4
+        let d = iqr_distances(&xs);
+        assert_eq!(&d[..4], &[0.5, 0.0, 0.0, 0.0]);
+        approx!(d[4], 48.0, 1e-12);
+
+        // mostly tied: MAD is 0, the mean deviation takes over
+        let d = robust_distances(&[5.0, 5.0, 5.0, 9.0]);
+        approx!(d[3], 4.0 / (1.253_314 * 1.0), 1e-12);
+        assert_eq!(robust_distances(&[2.0; 3]), [0.0; 3]);
+        assert_eq!(iqr_distances(&[5.0, 5.0, 5.0, 5.0, 9.0])[4], f64::INFINITY);
+    }
+
+    #[test]
+    fn rolling_residuals_skip_the_point_itself() {
+        let r = rolling_residuals(&[1.0, 2.0, 30.0, 4.0, 5.0], 1);
+        assert_eq!(r, [-1.0, -13.5, 27.0, -13.5, 1.0]);
+        assert_eq!(rolling_residuals(&[7.0], 3), [0.0]);
+    }
+
+    #[test]
+    fn isolation_forest_singles_out_the_far_value() {
+        let mut xs: Vec<f64> = (0..200).map(|i| (i % 20) as f64 * 0.1).collect();
+        xs.push(25.0);
+        let scores = isolation_forest_scores(&xs, 100, 64, &mut StdRng::seed_from_u64(1), |_| {});
+        let far = scores[200];
+        assert!(far > 0.6, "{far}");
+        assert!(scores[..200].iter().all(|&s| s < far));
+        let again = isolation_forest_scores(&xs, 100, 64, &mut StdRng::seed_from_u64(1), |_| {});
+        assert_eq!(scores, again);
+        approx!(average_path(256), 10.244_770_920_119_917, 1e-9);
+        assert_eq!(
+            isolation_forest_scores(&[1.0], 10, 8, &mut StdRng::seed_from_u64(1), |_| {}),
+            [0.5]
+        );
+    }
+}
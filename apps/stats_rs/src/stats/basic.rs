@@ -1,29 +1,77 @@
+/// Neumaier (improved Kahan) summation: carries the low-order bits each
+/// addition drops, so long series with a large offset don't lose their
+/// small differences, and `[1, 1e100, 1, -1e100]` sums to 2.
+pub fn compensated_sum(xs: impl IntoIterator<Item = f64>) -> f64 {
+    let (mut s, mut c) = (0.0_f64, 0.0_f64);
+    for x in xs {
+        let t = s + x;
+        c += if s.abs() >= x.abs() {
+            (s - t) + x
+        } else {
+            (x - t) + s
+        };
+        s = t;
+    }
+    s + c
+}
+
 pub fn sum(xs: &[f64]) -> f64 {
-    xs.iter().copied().sum()
+    compensated_sum(xs.iter().copied())
 }
 
+/// Arithmetic mean, refined by one pass over the residuals so the result
+/// is exact to rounding even when the values share a large offset.
 pub fn mean(xs: &[f64]) -> f64 {
     if xs.is_empty() {
-        f64::NAN
-    } else {
-        sum(xs) / xs.len() as f64
+        return f64::NAN;
+    }
+    let n = xs.len() as f64;
+    let m = sum(xs) / n;
+    if !m.is_finite() {
+        return m;
     }
+    m + compensated_sum(xs.iter().map(|&x| x - m)) / n
 }
 
+/// Median by selection (`O(n)`); use [`median_sorted`] on data that is
+/// already sorted.
 pub fn median(xs: &[f64]) -> f64 {
     if xs.is_empty() {
         return f64::NAN;
     }
     let mut v = xs.to_vec();
-    v.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
     let n = v.len();
+    let (below, &mut upper, _) = v.select_nth_unstable_by(n / 2, f64::total_cmp);
     if n % 2 == 1 {
-        v[n / 2]
+        upper
     } else {
-        (v[n / 2 - 1] + v[n / 2]) / 2.0
+        let lower = below
+            .iter()
+            .copied()
+            .max_by(f64::total_cmp)
+            .unwrap_or(upper);
+        (lower + upper) / 2.0
+    }
+}
+
+/// Median of an ascending slice.
+pub fn median_sorted(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    match n {
+        0 => f64::NAN,
+        _ if n % 2 == 1 => sorted[n / 2],
+        _ => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
     }
 }
 
+/// An ascending copy of `xs` (`NaN`s last) for the `*_sorted` functions, so
+/// several order statistics of one series cost a single sort.
+pub fn sorted(xs: &[f64]) -> Vec<f64> {
+    let mut v = xs.to_vec();
+    v.sort_unstable_by(f64::total_cmp);
+    v
+}
+
 /// Returns all modes (handles multimodal data).
 pub fn mode(xs: &[f64]) -> Vec<f64> {
     use std::collections::HashMap;
@@ -67,33 +115,29 @@ pub fn range(xs: &[f64]) -> f64 {
     }
 }
 
+/// Sum of squared deviations from `mean` by the corrected two-pass
+/// algorithm: the compensated sum of the deviations cancels any error in
+/// `mean` itself, and the result is never negative.
+pub(crate) fn sum_sq_dev(xs: &[f64], mean: f64) -> f64 {
+    let ss = compensated_sum(xs.iter().map(|&x| (x - mean) * (x - mean)));
+    let d = compensated_sum(xs.iter().map(|&x| x - mean));
+    let s = ss - d * d / xs.len() as f64;
+    if s < 0.0 { 0.0 } else { s }
+}
+
 pub fn sample_variance(xs: &[f64], mean: f64) -> f64 {
     let n = xs.len();
     if n < 2 {
         return f64::NAN;
     }
-    let s: f64 = xs
-        .iter()
-        .map(|&x| {
-            let d = x - mean;
-            d * d
-        })
-        .sum();
-    s / (n as f64 - 1.0)
+    sum_sq_dev(xs, mean) / (n as f64 - 1.0)
 }
 pub fn population_variance(xs: &[f64], mean: f64) -> f64 {
     let n = xs.len();
     if n == 0 {
         return f64::NAN;
     }
-    let s: f64 = xs
-        .iter()
-        .map(|&x| {
-            let d = x - mean;
-            d * d
-        })
-        .sum();
-    s / n as f64
+    sum_sq_dev(xs, mean) / n as f64
 }
 pub fn sample_std_dev(xs: &[f64], mean: f64) -> f64 {
     sample_variance(xs, mean).sqrt()
@@ -102,18 +146,26 @@ pub fn population_std_dev(xs: &[f64], mean: f64) -> f64 {
     population_variance(xs, mean).sqrt()
 }
 
-// R-7 quantile
+/// R-7 quantile. Sorts a copy of `xs` on every call; for several quantiles
+/// of the same data, [`sorted`] once and use [`quantile_sorted`].
 pub fn quantile(xs: &[f64], p: f64) -> f64 {
     assert!((0.0..=1.0).contains(&p), "p must be in [0,1]");
-    let n = xs.len();
+    if xs.len() < 2 {
+        return quantile_sorted(xs, p);
+    }
+    quantile_sorted(&sorted(xs), p)
+}
+
+/// R-7 quantile of an ascending slice (see [`sorted`]).
+pub fn quantile_sorted(v: &[f64], p: f64) -> f64 {
+    assert!((0.0..=1.0).contains(&p), "p must be in [0,1]");
+    let n = v.len();
     if n == 0 {
         return f64::NAN;
     }
     if n == 1 {
-        return xs[0];
+        return v[0];
     }
-    let mut v = xs.to_vec();
-    v.sort_by(|a, b| a.partial_cmp(b).unwrap());
     let h = (n - 1) as f64 * p;
     let i = h.floor() as usize;
     let j = h.ceil() as usize;
@@ -123,12 +175,189 @@ pub fn quantile(xs: &[f64], p: f64) -> f64 {
         v[i] + (h - i as f64) * (v[j] - v[i])
     }
 }
+/// Sample quantile definition, numbered as in Hyndman & Fan (1996) and R's
+/// `quantile(type = …)`.
+///
+/// `R1`–`R3` are discontinuous (they return an observation); `R4`–`R9`
+/// interpolate linearly between neighbouring order statistics. `R7` is the
+/// default of R, NumPy and this crate; `R6` is Minitab/SPSS and SAS
+/// `PCTLDEF=4`, `R2` is SAS `PCTLDEF=5`, `R3` is SAS `PCTLDEF=2`, and `R8`
+/// is the median-unbiased choice Hyndman & Fan recommend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuantileMethod {
+    /// Inverse of the empirical CDF
+    R1,
+    /// Inverse of the empirical CDF, averaging at discontinuities
+    R2,
+    /// Nearest order statistic, ties to the even one
+    R3,
+    /// Linear interpolation of the empirical CDF
+    R4,
+    /// Piecewise linear through the midpoints of the ECDF steps
+    R5,
+    /// `p(k) = k / (n + 1)`
+    R6,
+    /// `p(k) = (k − 1) / (n − 1)`
+    #[default]
+    R7,
+    /// `p(k) = (k − 1/3) / (n + 1/3)`, approximately median-unbiased
+    R8,
+    /// `p(k) = (k − 3/8) / (n + 1/4)`, approximately unbiased for normal data
+    R9,
+    /// The value at rank `⌈p · n⌉` (at least 1): the textbook nearest-rank
+    /// method, which is the same definition as [`R1`](Self::R1)
+    NearestRank,
+}
+
+/// Quantile `p` of an ascending slice (see [`sorted`]) under `method`.
+///
+/// Follows R's `quantile.default`: positions within `4ε` of an order
+/// statistic snap to it, and positions outside `1..=n` clamp to the
+/// extremes. `R7` is exactly [`quantile_sorted`].
+pub fn quantile_sorted_by(v: &[f64], p: f64, method: QuantileMethod) -> f64 {
+    use QuantileMethod::*;
+    assert!((0.0..=1.0).contains(&p), "p must be in [0,1]");
+    let n = v.len();
+    if n == 0 {
+        return f64::NAN;
+    }
+    if method == R7 {
+        return quantile_sorted(v, p);
+    }
+    const FUZZ: f64 = 4.0 * f64::EPSILON;
+    let nf = n as f64;
+    // `x[j]` for a 1-based `j`, clamped to the sample
+    let x = |j: f64| v[(j.max(1.0) as usize).min(n) - 1];
+    let (j, h) = match method {
+        R1 | R2 | R3 | NearestRank => {
+            let nppm = if method == R3 { nf * p - 0.5 } else { nf * p };
+            let j = (nppm + FUZZ).floor();
+            let h = match method {
+                R2 => f64::from(u8::from(nppm > j) + 1) / 2.0,
+                R3 => f64::from(u8::from(nppm != j || j % 2.0 == 1.0)),
+                _ => f64::from(u8::from(nppm > j)),
+            };
+            (j, h)
+        }
+        R4 | R5 | R6 | R7 | R8 | R9 => {
+            let (a, b) = match method {
+                R4 => (0.0, 1.0),
+                R5 => (0.5, 0.5),
+                R6 => (0.0, 0.0),
+                R8 => (1.0 / 3.0, 1.0 / 3.0),
+                R9 => (3.0 / 8.0, 3.0 / 8.0),
+                _ => (1.0, 1.0),
+            };
+            let nppm = a + p * (nf + 1.0 - a - b);
+            let j = (nppm + FUZZ).floor();
+            let h = nppm - j;
+            (j, if h.abs() < FUZZ { 0.0 } else { h })
+        }
+    };
+    let (lo, hi) = (x(j), x(j + 1.0));
+    if h == 0.0 || lo == hi {
+        lo
+    } else if h == 1.0 {
+        hi
+    } else {
+        (1.0 - h) * lo + h * hi
+    }
+}
+
+/// Equal-width histogram over `[min, max]` with `bins` bins (clamped to ≥ 1).
+///
+/// Returns `(counts, edges)` with `edges.len() == counts.len() + 1`; the last bin
+/// is right-inclusive. A degenerate range puts all mass in the first bin.
+/// Empty input yields empty vectors.
+pub fn histogram(xs: &[f64], bins: usize) -> (Vec<usize>, Vec<f64>) {
+    if xs.is_empty() {
+        return (vec![], vec![]);
+    }
+    let bins = bins.max(1);
+    let lo = min(xs);
+    let hi = max(xs);
+    let width = (hi - lo) / bins as f64;
+
+    let mut counts = vec![0usize; bins];
+    if width == 0.0 {
+        counts[0] = xs.len();
+    } else {
+        for &x in xs {
+            let b = (((x - lo) / width).floor() as usize).min(bins - 1);
+            counts[b] += 1;
+        }
+    }
+    let edges = (0..=bins).map(|i| lo + i as f64 * width).collect();
+    (counts, edges)
+}
+
+/// Histogram over caller-supplied ascending `edges` (≥ 2 of them).
+///
+/// Returns `(counts, outside)`: bins are half-open except the last, which is
+/// right-inclusive, and `outside` counts values below the first edge or
+/// above the last, which no bin holds.
+pub fn histogram_with_edges(xs: &[f64], edges: &[f64]) -> (Vec<usize>, usize) {
+    let bins = edges.len().saturating_sub(1);
+    let mut counts = vec![0usize; bins];
+    let mut outside = 0;
+    for &x in xs {
+        match edges.partition_point(|&e| e <= x) {
+            0 => outside += 1,
+            i if i <= bins => counts[i - 1] += 1,
+            _ if bins > 0 && x == edges[bins] => counts[bins - 1] += 1,
+            _ => outside += 1,
+        }
+    }
+    (counts, outside)
+}
+
+/// Log-spaced histogram over `[min, max]` of positive `xs`, with edges
+/// `min · (max / min)^(i / bins)`; heavy tails spread over several bins
+/// instead of one. Degenerate ranges and empty input behave as in
+/// [`histogram`]; callers must reject non-positive values.
+pub fn log_histogram(xs: &[f64], bins: usize) -> (Vec<usize>, Vec<f64>) {
+    if xs.is_empty() {
+        return (vec![], vec![]);
+    }
+    let bins = bins.max(1);
+    let lo = min(xs);
+    let hi = max(xs);
+    if lo == hi {
+        let mut counts = vec![0usize; bins];
+        counts[0] = xs.len();
+        return (counts, vec![lo; bins + 1]);
+    }
+    let ratio = hi / lo;
+    let mut edges: Vec<f64> = (0..=bins)
+        .map(|i| lo * ratio.powf(i as f64 / bins as f64))
+        .collect();
+    // pin the ends so `min` and `max` land inside despite rounding
+    edges[0] = lo;
+    edges[bins] = hi;
+    let (counts, _) = histogram_with_edges(xs, &edges);
+    (counts, edges)
+}
+
 pub fn quartiles(xs: &[f64]) -> (f64, f64, f64) {
-    (quantile(xs, 0.25), quantile(xs, 0.5), quantile(xs, 0.75))
+    quartiles_sorted(&sorted(xs))
+}
+
+/// R-7 quartiles of an ascending slice.
+pub fn quartiles_sorted(sorted: &[f64]) -> (f64, f64, f64) {
+    (
+        quantile_sorted(sorted, 0.25),
+        quantile_sorted(sorted, 0.5),
+        quantile_sorted(sorted, 0.75),
+    )
 }
+
 pub fn iqr(xs: &[f64]) -> f64 {
-    let (q1, _, q3) = quartiles(xs);
-    q3 - q1
+    iqr_sorted(&sorted(xs))
+}
+
+/// Interquartile range of an ascending slice.
+pub fn iqr_sorted(sorted: &[f64]) -> f64 {
+    quantile_sorted(sorted, 0.75) - quantile_sorted(sorted, 0.25)
 }
 
 #[cfg(test)]
@@ -274,6 +503,80 @@ mod more_tests {
         approx!(quantile(&xs, 0.75), 3.25, EPS_TIGHT);
     }
 
+    #[test]
+    fn quantile_methods_match_r() {
+        use QuantileMethod::*;
+        // quantile(1:4, 0.25, type = 1:9) in R
+        let xs = [1.0, 2.0, 3.0, 4.0];
+        let want = [
+            (R1, 1.0),
+            (R2, 1.5),
+            (R3, 1.0),
+            (R4, 1.0),
+            (R5, 1.5),
+            (R6, 1.25),
+            (R7, 1.75),
+            (R8, 1.0 + 5.0 / 12.0),
+            (R9, 1.4375),
+            (NearestRank, 1.0),
+        ];
+        for (m, q) in want {
+            approx!(quantile_sorted_by(&xs, 0.25, m), q, EPS_TIGHT);
+        }
+        // quantile(c(10, 20, 30, 40, 50), c(0.3, 0.5), type = 3) → 20, 20:
+        // np − 1/2 = 2 is a tie, which goes to the even order statistic
+        let ys = [10.0, 20.0, 30.0, 40.0, 50.0];
+        approx!(quantile_sorted_by(&ys, 0.3, R3), 20.0, EPS_TIGHT);
+        approx!(quantile_sorted_by(&ys, 0.5, R3), 20.0, EPS_TIGHT);
+        approx!(quantile_sorted_by(&ys, 0.5, R2), 30.0, EPS_TIGHT);
+        approx!(quantile_sorted_by(&ys, 0.4, R2), 25.0, EPS_TIGHT);
+        for m in [R1, R2, R3, R4, R5, R6, R7, R8, R9, NearestRank] {
+            approx!(quantile_sorted_by(&ys, 0.0, m), 10.0, EPS_TIGHT);
+            approx!(quantile_sorted_by(&ys, 1.0, m), 50.0, EPS_TIGHT);
+            approx!(quantile_sorted_by(&[7.0], 0.3, m), 7.0, EPS_TIGHT);
+            assert!(quantile_sorted_by(&[], 0.3, m).is_nan());
+        }
+    }
+
+    #[test]
+    fn histogram_counts_edges_and_degenerate() {
+        let (counts, edges) = histogram(&[1.0, 2.0, 3.0, 4.0, 5.0], 4);
+        assert_eq!(counts, vec![1, 1, 1, 2]); // max lands in the last bin
+        assert_eq!(edges, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        let (counts, edges) = histogram(&[7.0, 7.0, 7.0], 3);
+        assert_eq!(counts, vec![3, 0, 0]);
+        assert_eq!(edges.len(), 4);
+
+        let (counts, edges) = histogram(&[], 5);
+        assert!(counts.is_empty() && edges.is_empty());
+    }
+
+    #[test]
+    fn histogram_with_edges_counts_outside_values() {
+        let edges = [0.0, 1.0, 10.0, 100.0];
+        let (counts, outside) =
+            histogram_with_edges(&[-1.0, 0.0, 0.5, 1.0, 50.0, 100.0, 101.0], &edges);
+        assert_eq!(counts, vec![2, 1, 2]); // 100 lands in the last bin
+        assert_eq!(outside, 2);
+        assert_eq!(histogram_with_edges(&[1.0], &[0.0]), (vec![], 1));
+    }
+
+    #[test]
+    fn log_histogram_spreads_heavy_tails() {
+        let xs = [1.0, 2.0, 9.0, 11.0, 99.0, 1000.0];
+        let (counts, edges) = log_histogram(&xs, 3);
+        assert_eq!(counts, vec![3, 2, 1]);
+        approx!(edges[1], 10.0, 1e-9);
+        approx!(edges[2], 100.0, 1e-9);
+        assert_eq!((edges[0], edges[3]), (1.0, 1000.0));
+
+        let (counts, edges) = log_histogram(&[5.0, 5.0], 2);
+        assert_eq!(counts, vec![2, 0]);
+        assert_eq!(edges, vec![5.0; 3]);
+        assert!(log_histogram(&[], 2).0.is_empty());
+    }
+
     #[test]
     #[should_panic(expected = "p must be in [0,1]")]
     fn quantile_p_below_zero_panics() {
@@ -287,4 +590,42 @@ mod more_tests {
         let xs = vec![1.0, 2.0, 3.0];
         let _ = quantile(&xs, 1.01);
     }
+
+    #[test]
+    fn sorted_variants_match_the_unsorted_ones() {
+        use crate::stats::robust::{mad, mad_sorted};
+        let odd = [5.0, -1.0, 3.0, 9.0, 0.5];
+        let even = [4.0, 1.0, 3.0, 2.0, 8.0, -6.0];
+        for xs in [&odd[..], &even[..]] {
+            let s = sorted(xs);
+            assert!(s.windows(2).all(|w| w[0] <= w[1]));
+            assert_eq!(median_sorted(&s), median(xs));
+            assert_eq!(quartiles_sorted(&s), quartiles(xs));
+            assert_eq!(iqr_sorted(&s), iqr(xs));
+            assert_eq!(mad_sorted(&s), mad(xs));
+            for p in [0.0, 0.1, 0.5, 0.95, 1.0] {
+                assert_eq!(quantile_sorted(&s, p), quantile(xs, p));
+            }
+        }
+        assert_eq!(median(&odd), 3.0);
+        assert_eq!(median(&even), 2.5);
+        assert!(median(&[]).is_nan() && quantile_sorted(&[], 0.5).is_nan());
+    }
+
+    #[test]
+    fn sums_and_variances_survive_large_offsets() {
+        assert_eq!(compensated_sum([1.0, 1e100, 1.0, -1e100]), 2.0);
+        assert_eq!(sum(&[0.1; 10]), 1.0);
+
+        // Sensor readings around 1e9 with a spread of a few units
+        let xs: Vec<f64> = (0..10_000).map(|i| 1e9 + (i % 7) as f64 * 0.5).collect();
+        let shifted: Vec<f64> = xs.iter().map(|x| x - 1e9).collect();
+        let m = mean(&xs);
+        approx!(m, 1e9 + mean(&shifted), 1e-6); // ulp at 1e9 is ~1.2e-7
+        let v = sample_variance(&xs, m);
+        assert!(v > 0.0);
+        approx!(v, sample_variance(&shifted, mean(&shifted)), 1e-9);
+        assert_eq!(population_variance(&[1e9; 5], 1e9), 0.0);
+        assert!(sample_variance(&[1.0, f64::NAN], 1.0).is_nan());
+    }
 }
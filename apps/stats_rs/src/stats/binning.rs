@@ -0,0 +1,103 @@
+//! Histogram bin-count rules that need more than `n`.
+
+use crate::stats::prelude::*;
+
+/// Doane's rule `1 + log2(n) + log2(1 + |g1| / σ_g1)`, Sturges corrected
+/// for skewness `g1`, rounded up and at least 2. Falls back to Sturges for
+/// fewer than three values, where `σ_g1` is undefined.
+pub fn doane_bins(xs: &[f64]) -> usize {
+    let n = xs.len() as f64;
+    let sturges = 1.0 + n.max(1.0).log2();
+    if xs.len() < 3 {
+        return (sturges.ceil() as usize).max(2);
+    }
+    let sigma = (6.0 * (n - 2.0) / ((n + 1.0) * (n + 3.0))).sqrt();
+    let g1 = skewness(xs).abs();
+    ((sturges + (1.0 + g1 / sigma).log2()).ceil() as usize).max(2)
+}
+
+/// Shimazaki–Shinomoto bin count for an ascending slice: the `k` in
+/// `2..=max_bins` minimising the cost `(2·mean − var) / width²` of the
+/// equal-width histogram's counts (biased variance).
+///
+/// Each candidate costs O(k log n) via binary search, so the whole scan is
+/// O(max_bins² log n); callers should keep `max_bins` modest. A degenerate
+/// range or fewer than two values yields 2.
+pub fn shimazaki_shinomoto_bins(sorted: &[f64], max_bins: usize) -> usize {
+    let n = sorted.len();
+    if n < 2 || sorted[0] == sorted[n - 1] {
+        return 2;
+    }
+    let (lo, hi) = (sorted[0], sorted[n - 1]);
+    let mut best = (f64::INFINITY, 2);
+    for k in 2..=max_bins.max(2) {
+        let width = (hi - lo) / k as f64;
+        let mut prev = 0;
+        let (mut s1, mut s2) = (0.0, 0.0);
+        for i in 1..=k {
+            // the last bin is right-inclusive, as in `histogram`
+            let upto = if i == k {
+                n
+            } else {
+                let edge = lo + i as f64 * width;
+                sorted.partition_point(|&x| x < edge)
+            };
+            let c = (upto - prev) as f64;
+            s1 += c;
+            s2 += c * c;
+            prev = upto;
+        }
+        let m = s1 / k as f64;
+        let v = s2 / k as f64 - m * m;
+        let cost = (2.0 * m - v) / (width * width);
+        if cost < best.0 {
+            best = (cost, k);
+        }
+    }
+    best.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doane_adds_bins_for_skewed_data() {
+        let symmetric: Vec<f64> = (1..=100).map(f64::from).collect();
+        let skewed: Vec<f64> = symmetric.iter().map(|x| x.powi(4)).collect();
+        // symmetric: |g1| = 0, so Doane is Sturges rounded up
+        assert_eq!(doane_bins(&symmetric), 8);
+        assert!(doane_bins(&skewed) > doane_bins(&symmetric));
+        assert_eq!(doane_bins(&[1.0, 2.0]), 2);
+    }
+
+    #[test]
+    fn shimazaki_shinomoto_resolves_separated_clusters() {
+        // two tight clusters far apart: a handful of bins separates them,
+        // and the cost keeps rising once bins start splitting each cluster
+        let mut xs: Vec<f64> = (0..50).map(|i| i as f64 * 0.01).collect();
+        xs.extend((0..50).map(|i| 10.0 + i as f64 * 0.01));
+        let k = shimazaki_shinomoto_bins(&xs, 100);
+        assert!((2..=100).contains(&k));
+        let (counts, _) = histogram(&xs, k);
+        assert!(counts.iter().filter(|&&c| c > 0).count() >= 2);
+        assert_eq!(shimazaki_shinomoto_bins(&[3.0, 3.0, 3.0], 50), 2);
+        assert_eq!(shimazaki_shinomoto_bins(&[1.0], 50), 2);
+    }
+
+    #[test]
+    fn shimazaki_shinomoto_matches_a_brute_force_cost() {
+        let xs = sorted(&[1.0, 1.5, 2.0, 2.2, 3.1, 4.0, 4.1, 4.2, 7.5, 9.0, 9.1]);
+        let cost = |k: usize| {
+            let (counts, edges) = histogram(&xs, k);
+            let m = xs.len() as f64 / k as f64;
+            let v = counts.iter().map(|&c| (c as f64 - m).powi(2)).sum::<f64>() / k as f64;
+            let w = edges[1] - edges[0];
+            (2.0 * m - v) / (w * w)
+        };
+        let expected = (2..=20)
+            .min_by(|&a, &b| cost(a).partial_cmp(&cost(b)).unwrap())
+            .unwrap();
+        assert_eq!(shimazaki_shinomoto_bins(&xs, 20), expected);
+    }
+}
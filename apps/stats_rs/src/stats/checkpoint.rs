@@ -0,0 +1,71 @@
+//! Progress reports and cooperative cancellation for long-running kernels.
+//!
+//! Loop-heavy kernels ([`bootstrap_ci`](super::bootstrap_ci),
+//! [`permutation_test_mean_diff`](super::permutation_test_mean_diff), the
+//! service's correlation matrix) take a [`Checkpoint`] they call after each
+//! unit of work. A plain `Fn(usize)` closure just receives the count done so
+//! far; wrapping one with [`CancelFlag::guard`] also lets the caller stop the
+//! kernel at its next checkpoint, e.g. once the client waiting for the result
+//! has gone away. A stopped kernel returns early with a partial result the
+//! caller is expected to discard.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// Receives a kernel's progress and may ask it to stop.
+pub trait Checkpoint {
+    /// `done` units of work are finished.
+    fn reached(&self, done: usize);
+
+    /// Whether the kernel should stop before its next unit of work.
+    fn cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl<F: Fn(usize)> Checkpoint for F {
+    fn reached(&self, done: usize) {
+        self(done)
+    }
+}
+
+/// Shared, cheaply clonable stop signal.
+#[derive(Clone, Debug, Default)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `progress` that also stops its kernel once this flag is set.
+    pub fn guard<P: Checkpoint>(&self, progress: P) -> Guarded<'_, P> {
+        Guarded {
+            flag: self,
+            progress,
+        }
+    }
+}
+
+/// A [`Checkpoint`] tied to a [`CancelFlag`].
+#[derive(Debug)]
+pub struct Guarded<'a, P> {
+    flag: &'a CancelFlag,
+    progress: P,
+}
+
+impl<P: Checkpoint> Checkpoint for Guarded<'_, P> {
+    fn reached(&self, done: usize) {
+        self.progress.reached(done)
+    }
+
+    fn cancelled(&self) -> bool {
+        self.flag.is_cancelled() || self.progress.cancelled()
+    }
+}
@@ -95,6 +95,141 @@ pub fn hubness_k_occurrence(knn_indices: &[Vec<usize>], n_points: usize) -> (Vec
     (counts, gini)
 }
 
+/// Connected components of `n` nodes under the given edges (union-find with path
+/// halving and union by size).
+///
+/// Returns only components with at least two members; each is sorted ascending and
+/// the list is ordered by smallest member.
+pub fn connected_components(n: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..n).collect();
+    let mut size = vec![1usize; n];
+
+    fn find(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
+    }
+
+    for &(a, b) in edges {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        if ra == rb {
+            continue;
+        }
+        let (big, small) = if size[ra] >= size[rb] {
+            (ra, rb)
+        } else {
+            (rb, ra)
+        };
+        parent[small] = big;
+        size[big] += size[small];
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..n {
+        let r = find(&mut parent, i);
+        groups.entry(r).or_default().push(i);
+    }
+    let mut out: Vec<Vec<usize>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    out.sort_by_key(|g| g[0]);
+    out
+}
+
+/// Linkage criterion of [`hierarchical_clustering`]: how far apart two
+/// clusters are, given the distances between their points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Linkage {
+    /// Closest pair of points
+    Single,
+    /// Farthest pair of points
+    Complete,
+    /// Mean over all pairs of points (UPGMA)
+    Average,
+}
+
+/// One step of [`hierarchical_clustering`]: clusters `left < right` joined at
+/// `distance` into a cluster of `size` points. Ids below `m` are the points;
+/// the cluster formed at step `s` has id `m + s`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Merge {
+    pub left: usize,
+    pub right: usize,
+    pub distance: f64,
+    pub size: usize,
+}
+
+/// Agglomerative clustering of `m` points from their row-major `m×m` distance
+/// matrix `dist` (finite and symmetric). Returns the `m − 1` merges in order
+/// and the dendrogram's leaf order (`left` before `right` at every merge),
+/// which puts each cluster's points next to each other.
+///
+/// Distances are updated with the Lance–Williams formula; each step scans the
+/// remaining pairs, so this is O(m³). Ties go to the lowest pair of ids.
+pub fn hierarchical_clustering(
+    dist: &[f64],
+    m: usize,
+    linkage: Linkage,
+) -> (Vec<Merge>, Vec<usize>) {
+    assert_eq!(dist.len(), m * m);
+    let mut d = dist.to_vec();
+    let mut id: Vec<usize> = (0..m).collect();
+    let mut size = vec![1usize; m];
+    let mut active: Vec<usize> = (0..m).collect();
+    let mut merges = Vec::with_capacity(m.saturating_sub(1));
+
+    while active.len() > 1 {
+        let (mut a, mut b, mut best) = (0, 1, f64::INFINITY);
+        for (x, &i) in active.iter().enumerate() {
+            for (y, &j) in active.iter().enumerate().skip(x + 1) {
+                if d[i * m + j] < best || (x, y) == (0, 1) {
+                    (a, b, best) = (x, y, d[i * m + j]);
+                }
+            }
+        }
+        let (i, j) = (active[a], active[b]);
+        let (si, sj) = (size[i] as f64, size[j] as f64);
+        for &k in &active {
+            if k == i || k == j {
+                continue;
+            }
+            let (dik, djk) = (d[i * m + k], d[j * m + k]);
+            let v = match linkage {
+                Linkage::Single => dik.min(djk),
+                Linkage::Complete => dik.max(djk),
+                Linkage::Average => (si * dik + sj * djk) / (si + sj),
+            };
+            (d[i * m + k], d[k * m + i]) = (v, v);
+        }
+        let (left, right) = (id[i].min(id[j]), id[i].max(id[j]));
+        size[i] += size[j];
+        merges.push(Merge {
+            left,
+            right,
+            distance: best,
+            size: size[i],
+        });
+        id[i] = m + merges.len() - 1;
+        active.remove(b);
+    }
+
+    let mut order = Vec::with_capacity(m);
+    let mut stack: Vec<usize> = match m {
+        0 => vec![],
+        _ => vec![2 * m - 2],
+    };
+    while let Some(c) = stack.pop() {
+        if c < m {
+            order.push(c);
+        } else {
+            let step = merges[c - m];
+            stack.push(step.right);
+            stack.push(step.left);
+        }
+    }
+    (merges, order)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,6 +288,16 @@ mod tests {
         approx!(gini, 0.0, EPS_TIGHT);
     }
 
+    // --- connected_components ---
+
+    #[test]
+    fn connected_components_groups_transitively() {
+        // 0-3-5 chain and 1-4 pair; 2 and 6 are singletons (omitted)
+        let groups = connected_components(7, &[(3, 5), (1, 4), (0, 3)]);
+        assert_eq!(groups, vec![vec![0, 3, 5], vec![1, 4]]);
+        assert!(connected_components(3, &[]).is_empty());
+    }
+
     #[test]
     fn hubness_empty_is_zeroed() {
         let knn: Vec<Vec<usize>> = vec![];
@@ -160,4 +305,42 @@ mod tests {
         assert!(counts.is_empty());
         approx!(gini, 0.0, EPS_TIGHT);
     }
+
+    // --- hierarchical_clustering ---
+
+    #[test]
+    fn hierarchical_clustering_merges_nearest_first() {
+        // points 0, 10, 1, 12 on a line
+        let xs = [0.0f64, 10.0, 1.0, 12.0];
+        let dist: Vec<f64> = xs
+            .iter()
+            .flat_map(|a| xs.iter().map(move |b| (a - b).abs()))
+            .collect();
+        let (merges, order) = hierarchical_clustering(&dist, 4, Linkage::Average);
+        let steps: Vec<(usize, usize, usize)> =
+            merges.iter().map(|s| (s.left, s.right, s.size)).collect();
+        assert_eq!(steps, vec![(0, 2, 2), (1, 3, 2), (4, 5, 4)]);
+        approx!(merges[0].distance, 1.0, EPS_TIGHT);
+        approx!(merges[1].distance, 2.0, EPS_TIGHT);
+        // mean of |0−10|, |0−12|, |1−10|, |1−12|
+        approx!(merges[2].distance, 10.5, EPS_TIGHT);
+        assert_eq!(order, vec![0, 2, 1, 3]);
+
+        let (single, _) = hierarchical_clustering(&dist, 4, Linkage::Single);
+        approx!(single[2].distance, 9.0, EPS_TIGHT);
+        let (complete, _) = hierarchical_clustering(&dist, 4, Linkage::Complete);
+        approx!(complete[2].distance, 12.0, EPS_TIGHT);
+    }
+
+    #[test]
+    fn hierarchical_clustering_small_inputs() {
+        assert_eq!(
+            hierarchical_clustering(&[], 0, Linkage::Single),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            hierarchical_clustering(&[0.0], 1, Linkage::Single),
+            (vec![], vec![0])
+        );
+    }
 }
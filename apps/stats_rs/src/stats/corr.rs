@@ -9,10 +9,7 @@ pub fn covariance(xs: &[f64], ys: &[f64]) -> f64 {
     }
     let mx = super::mean(xs);
     let my = super::mean(ys);
-    let mut s = 0.0;
-    for i in 0..n {
-        s += (xs[i] - mx) * (ys[i] - my);
-    }
+    let s = super::compensated_sum(xs.iter().zip(ys).map(|(&x, &y)| (x - mx) * (y - my)));
     s / (n as f64 - 1.0)
 }
 
@@ -40,9 +37,17 @@ pub fn kendall_tau_b(xs: &[f64], ys: &[f64]) -> f64 {
         return f64::NAN;
     }
 
-    // Rank with average ties
-    let rx = average_ranks(xs);
-    let ry = average_ranks(ys);
+    kendall_tau_b_ranked(&average_ranks(xs), &average_ranks(ys))
+}
+
+/// [`kendall_tau_b`] of series already replaced by their [`average_ranks`],
+/// so a series compared with many others is ranked only once.
+pub fn kendall_tau_b_ranked(rx: &[f64], ry: &[f64]) -> f64 {
+    let n = rx.len();
+    assert_eq!(n, ry.len());
+    if n < 2 {
+        return f64::NAN;
+    }
 
     // Count concordant/discordant; O(n^2) but fine for evals.
     let mut c = 0_i64;
@@ -72,6 +77,147 @@ pub fn kendall_tau_b(xs: &[f64], ys: &[f64]) -> f64 {
     if den == 0.0 { f64::NAN } else { num / den }
 }
 
+/// Two-sided p-value of a Pearson (or Spearman) correlation `r` over `n`
+/// pairs, from `t = r·√((n − 2) / (1 − r²))` on `n − 2` degrees of freedom.
+/// `NaN` for an undefined `r` or fewer than 3 pairs.
+pub fn correlation_p_value(r: f64, n: usize) -> f64 {
+    if n < 3 || r.is_nan() {
+        return f64::NAN;
+    }
+    let df = n as f64 - 2.0;
+    let r2 = r * r;
+    if r2 >= 1.0 {
+        return 0.0;
+    }
+    2.0 * student_t_sf(r.abs() * (df / (1.0 - r2)).sqrt(), df)
+}
+
+/// Two-sided p-value of Kendall's `tau` over `n` pairs from the normal
+/// approximation `z = 3τ·√(n(n − 1)) / √(2(2n + 5))` (no tie correction).
+/// `NaN` for an undefined `tau` or fewer than 2 pairs.
+pub fn kendall_p_value(tau: f64, n: usize) -> f64 {
+    if n < 2 || tau.is_nan() {
+        return f64::NAN;
+    }
+    let n = n as f64;
+    let z = 3.0 * tau.abs() * (n * (n - 1.0)).sqrt() / (2.0 * (2.0 * n + 5.0)).sqrt();
+    (2.0 * normal_sf(z)).min(1.0)
+}
+
+/// `tanh(atanh(r) ± z·se)`: a Fisher-z interval at `confidence` for a
+/// coefficient whose `atanh` has standard error `se`.
+fn fisher_z_interval(r: f64, se: f64, confidence: f64) -> (f64, f64) {
+    if r.is_nan() || se.is_nan() {
+        return (f64::NAN, f64::NAN);
+    }
+    let z = r.clamp(-1.0, 1.0).atanh();
+    let half = normal_quantile(0.5 + 0.5 * confidence) * se;
+    ((z - half).tanh(), (z + half).tanh())
+}
+
+/// Fisher-z confidence interval of a Pearson `r` over `n` pairs
+/// (`se = 1/√(n − 3)`); `NaN`s for fewer than 4 pairs.
+pub fn pearson_ci(r: f64, n: usize, confidence: f64) -> (f64, f64) {
+    let se = if n > 3 {
+        1.0 / (n as f64 - 3.0).sqrt()
+    } else {
+        f64::NAN
+    };
+    fisher_z_interval(r, se, confidence)
+}
+
+/// Fisher-z confidence interval of a Spearman `rho` over `n` pairs, with
+/// the Bonett–Wright standard error `√((1 + ρ²/2) / (n − 3))`; `NaN`s for
+/// fewer than 4 pairs.
+pub fn spearman_ci(rho: f64, n: usize, confidence: f64) -> (f64, f64) {
+    let se = if n > 3 {
+        ((1.0 + rho * rho / 2.0) / (n as f64 - 3.0)).sqrt()
+    } else {
+        f64::NAN
+    };
+    fisher_z_interval(rho, se, confidence)
+}
+
+/// Fisher-z confidence interval of Kendall's `tau` over `n` pairs, with the
+/// Fieller–Hartley–Pearson standard error `√(0.437 / (n − 4))`; `NaN`s for
+/// fewer than 5 pairs.
+pub fn kendall_ci(tau: f64, n: usize, confidence: f64) -> (f64, f64) {
+    let se = if n > 4 {
+        (0.437 / (n as f64 - 4.0)).sqrt()
+    } else {
+        f64::NAN
+    };
+    fisher_z_interval(tau, se, confidence)
+}
+
+/// Inverse of the row-major `m×m` matrix `a` by Gauss–Jordan elimination
+/// with partial pivoting; `None` when it is singular to working precision
+/// or has non-finite entries.
+fn invert(a: &[f64], m: usize) -> Option<Vec<f64>> {
+    if a.iter().any(|x| !x.is_finite()) {
+        return None;
+    }
+    let scale = a.iter().fold(0.0_f64, |s, x| s.max(x.abs()));
+    let tol = scale * m as f64 * f64::EPSILON;
+    let mut a = a.to_vec();
+    let mut inv = vec![0.0; m * m];
+    for i in 0..m {
+        inv[i * m + i] = 1.0;
+    }
+    for col in 0..m {
+        let pivot =
+            (col..m).max_by(|&r, &s| a[r * m + col].abs().total_cmp(&a[s * m + col].abs()))?;
+        if a[pivot * m + col].abs() <= tol {
+            return None;
+        }
+        for k in 0..m {
+            a.swap(col * m + k, pivot * m + k);
+            inv.swap(col * m + k, pivot * m + k);
+        }
+        let d = a[col * m + col];
+        for k in 0..m {
+            a[col * m + k] /= d;
+            inv[col * m + k] /= d;
+        }
+        for r in (0..m).filter(|&r| r != col) {
+            let f = a[r * m + col];
+            if f != 0.0 {
+                for k in 0..m {
+                    a[r * m + k] -= f * a[col * m + k];
+                    inv[r * m + k] -= f * inv[col * m + k];
+                }
+            }
+        }
+    }
+    Some(inv)
+}
+
+/// Partial correlations of `m` variables from their row-major correlation
+/// (or covariance) matrix: with `P` its inverse (the precision matrix),
+/// the correlation of `i` and `j` given all other variables is
+/// `−P[i][j] / √(P[i][i] · P[j][j])`, with `1` on the diagonal.
+///
+/// Zero entries mark conditionally independent pairs of a Gaussian
+/// graphical model. All `NaN` when the matrix has undefined entries or is
+/// singular (e.g. a variable that is a linear combination of others).
+pub fn partial_correlation_matrix(corr: &[f64], m: usize) -> Vec<f64> {
+    let Some(p) = invert(corr, m) else {
+        return vec![f64::NAN; m * m];
+    };
+    let mut out = vec![f64::NAN; m * m];
+    for i in 0..m {
+        for j in 0..m {
+            let d = p[i * m + i] * p[j * m + j];
+            out[i * m + j] = match i == j {
+                true => 1.0,
+                false if d > 0.0 => (-p[i * m + j] / d.sqrt()).clamp(-1.0, 1.0),
+                false => f64::NAN,
+            };
+        }
+    }
+    out
+}
+
 /// Sample skewness (Fisher–Pearson adjusted).
 pub fn skewness(xs: &[f64]) -> f64 {
     let n = xs.len();
@@ -87,7 +233,9 @@ pub fn skewness(xs: &[f64]) -> f64 {
     (n as f64) * m3 / ((n as f64 - 1.0) * (n as f64 - 2.0))
 }
 
-/// Excess kurtosis (Fisher, 0 for normal). Uses sample correction.
+/// Sample excess kurtosis (0 for normal), as [`g2`] of the values
+/// standardized by the sample standard deviation. `NaN` below 4 values or
+/// without spread.
 pub fn excess_kurtosis(xs: &[f64]) -> f64 {
     let n = xs.len();
     if n < 4 {
@@ -98,11 +246,35 @@ pub fn excess_kurtosis(xs: &[f64]) -> f64 {
     if s == 0.0 {
         return f64::NAN;
     }
-    let m4: f64 = xs.iter().map(|&x| ((x - m) / s).powi(4)).sum::<f64>() / n as f64;
-    // unbiased-ish estimator (Fisher) correction
-    let num = (n as f64) * (n as f64 + 1.0) * (m4 - 3.0) + 6.0;
-    let den = (n as f64 - 1.0) * (n as f64 - 2.0) * (n as f64 - 3.0);
-    num / den
+    let z4: f64 = xs.iter().map(|&x| ((x - m) / s).powi(4)).sum();
+    g2(n as f64, z4)
+}
+
+/// Sample excess kurtosis G2 (SAS/SPSS, R `e1071` type 2) of `n` values
+/// whose fourth powers, standardized by the sample standard deviation, sum to
+/// `z4`: `n(n+1)/((n−1)(n−2)(n−3)) · z4 − 3(n−1)²/((n−2)(n−3))`.
+pub(crate) fn g2(n: f64, z4: f64) -> f64 {
+    n * (n + 1.0) / ((n - 1.0) * (n - 2.0) * (n - 3.0)) * z4
+        - 3.0 * (n - 1.0).powi(2) / ((n - 2.0) * (n - 3.0))
+}
+
+/// Standard error of [`skewness`] for a normal sample of size `n`:
+/// `√(6n(n−1) / ((n−2)(n+1)(n+3)))`. `NaN` below 3.
+pub fn skewness_se(n: f64) -> f64 {
+    if n < 3.0 {
+        return f64::NAN;
+    }
+    (6.0 * n * (n - 1.0) / ((n - 2.0) * (n + 1.0) * (n + 3.0))).sqrt()
+}
+
+/// Standard error of [`excess_kurtosis`] for a normal sample of size `n`:
+/// `2 · SES · √((n²−1) / ((n−3)(n+5)))` with SES the [`skewness_se`].
+/// `NaN` below 4.
+pub fn excess_kurtosis_se(n: f64) -> f64 {
+    if n < 4.0 {
+        return f64::NAN;
+    }
+    2.0 * skewness_se(n) * ((n * n - 1.0) / ((n - 3.0) * (n + 5.0))).sqrt()
 }
 
 /// Average ranks (handles ties). Returns ranks aligned with xs.
@@ -132,6 +304,31 @@ mod tests {
     use crate::approx; // macro from utils.rs via #[macro_export]
     use crate::stats::utils::{EPS, EPS_TIGHT};
 
+    #[test]
+    fn excess_kurtosis_matches_g2() {
+        // R: e1071::kurtosis(x, type = 2)
+        approx!(excess_kurtosis(&[1.0, 2.0, 3.0, 4.0]), -1.2, EPS_TIGHT);
+        approx!(excess_kurtosis(&[1.0, 2.0, 3.0, 4.0, 5.0]), -1.2, EPS_TIGHT);
+        let xs: Vec<f64> = (1..=20).map(|i| (i * i % 17) as f64).collect();
+        approx!(excess_kurtosis(&xs), -1.443_590, 1e-6);
+        approx!(
+            excess_kurtosis(&[1.0, 2.0, 2.0, 3.0, 3.0, 3.0, 4.0, 10.0]),
+            5.669_136,
+            1e-6
+        );
+        assert!(excess_kurtosis(&[1.0, 2.0, 3.0]).is_nan());
+        assert!(excess_kurtosis(&[2.0; 5]).is_nan());
+    }
+
+    #[test]
+    fn shape_standard_errors() {
+        // SPSS-style values for n = 20
+        approx!(skewness_se(20.0), 0.5122, 1e-4);
+        approx!(excess_kurtosis_se(20.0), 0.9924, 1e-4);
+        assert!(skewness_se(2.0).is_nan());
+        assert!(excess_kurtosis_se(3.0).is_nan());
+    }
+
     #[test]
     fn ranks_and_correlations() {
         // average ranks with ties
@@ -165,6 +362,70 @@ mod tests {
         // symmetric data → skewness ≈ 0
         assert!(skewness(&xs).abs() < EPS_TIGHT);
     }
+
+    #[test]
+    fn correlation_p_values() {
+        // r = 0.5 over 20 pairs: t = 0.5·√(18 / 0.75) ≈ 2.4495 on 18 df
+        approx!(correlation_p_value(0.5, 20), 0.024_769_558_804_109_69, 1e-9);
+        approx!(
+            correlation_p_value(-0.5, 20),
+            correlation_p_value(0.5, 20),
+            EPS_TIGHT
+        );
+        assert_eq!(correlation_p_value(0.0, 10), 1.0);
+        assert_eq!(correlation_p_value(1.0, 10), 0.0);
+        assert!(correlation_p_value(0.9, 2).is_nan());
+        assert!(correlation_p_value(f64::NAN, 10).is_nan());
+
+        // tau = 0.4 over 15 pairs: z = 1.2·√210 / √70 ≈ 2.0785
+        approx!(kendall_p_value(0.4, 15), 0.037_666_922_228_628_69, 1e-9);
+        assert_eq!(kendall_p_value(0.0, 15), 1.0);
+        assert!(kendall_p_value(0.5, 1).is_nan());
+    }
+
+    #[test]
+    fn fisher_z_intervals() {
+        // r = 0.5 over 28 pairs: atanh(0.5) ± 1.96/5
+        let (lo, hi) = pearson_ci(0.5, 28, 0.95);
+        let z = 0.5f64.atanh();
+        let half = 1.959_963_984_540_054 / 5.0;
+        approx!(lo, (z - half).tanh(), 1e-12);
+        approx!(hi, (z + half).tanh(), 1e-12);
+        // wider standard errors for the rank coefficients
+        let (slo, shi) = spearman_ci(0.5, 28, 0.95);
+        assert!(slo < lo && shi > hi);
+        let (klo, khi) = kendall_ci(0.5, 28, 0.95);
+        approx!(
+            khi - klo,
+            {
+                let h = 1.959_963_984_540_054 * (0.437f64 / 24.0).sqrt();
+                (z + h).tanh() - (z - h).tanh()
+            },
+            1e-12
+        );
+        assert_eq!(pearson_ci(1.0, 10, 0.95), (1.0, 1.0));
+        assert!(pearson_ci(0.5, 3, 0.95).0.is_nan());
+        assert!(kendall_ci(0.5, 4, 0.95).1.is_nan());
+        assert!(spearman_ci(f64::NAN, 30, 0.95).0.is_nan());
+    }
+
+    #[test]
+    fn partial_correlations_condition_on_the_rest() {
+        let (r12, r13, r23) = (0.5, 0.4, 0.3);
+        let corr = [1.0, r12, r13, r12, 1.0, r23, r13, r23, 1.0];
+        let p = partial_correlation_matrix(&corr, 3);
+        // first-order partial correlation formula
+        let r12_3 = (r12 - r13 * r23) / ((1.0 - r13 * r13) * (1.0 - r23 * r23)).sqrt();
+        approx!(p[1], r12_3, EPS_TIGHT);
+        approx!(p[3], r12_3, EPS_TIGHT);
+        approx!(p[4], 1.0, EPS_TIGHT);
+        // two variables: nothing to condition on
+        let two = partial_correlation_matrix(&[1.0, -0.7, -0.7, 1.0], 2);
+        approx!(two[1], -0.7, EPS_TIGHT);
+        // perfectly collinear or undefined: no precision matrix
+        assert!(partial_correlation_matrix(&[1.0, 1.0, 1.0, 1.0], 2)[1].is_nan());
+        assert!(partial_correlation_matrix(&[1.0, f64::NAN, f64::NAN, 1.0], 2)[0].is_nan());
+    }
 }
 
 #[cfg(test)]
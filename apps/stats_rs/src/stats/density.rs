@@ -0,0 +1,134 @@
+//! Histogram densities and Gaussian kernel density estimates.
+
+use crate::stats::prelude::*;
+
+/// Kernel mass beyond this many bandwidths is below `1e-14` and skipped.
+pub(crate) const KDE_CUTOFF: f64 = 8.0;
+
+/// Area-normalized histogram heights: `count / (n · width)` per bin, so the
+/// bars integrate to 1 over `edges`.
+///
+/// Zero-width bins (a degenerate range) get `NaN`; an empty histogram
+/// yields an empty vector.
+pub fn bin_densities(counts: &[usize], edges: &[f64]) -> Vec<f64> {
+    let total = counts.iter().sum::<usize>() as f64;
+    counts
+        .iter()
+        .zip(edges.windows(2))
+        .map(|(&c, e)| {
+            let width = e[1] - e[0];
+            if width > 0.0 {
+                c as f64 / (total * width)
+            } else {
+                f64::NAN
+            }
+        })
+        .collect()
+}
+
+/// Midpoints of consecutive histogram `edges`.
+pub fn bin_centers(edges: &[f64]) -> Vec<f64> {
+    edges.windows(2).map(|e| 0.5 * (e[0] + e[1])).collect()
+}
+
+pub fn silverman_bandwidth(xs: &[f64]) -> f64 {
+    silverman_bandwidth_sorted(&sorted(xs))
+}
+
+/// Silverman's rule of thumb `0.9 · min(sd, IQR / 1.34) · n^(-1/5)` for an
+/// ascending slice, falling back to `sd` when the IQR is zero.
+///
+/// `NaN` for fewer than two values or zero spread.
+pub fn silverman_bandwidth_sorted(sorted: &[f64]) -> f64 {
+    if sorted.len() < 2 {
+        return f64::NAN;
+    }
+    let sd = sample_std_dev(sorted, mean(sorted));
+    let iqr = iqr_sorted(sorted) / 1.34;
+    let spread = if iqr > 0.0 { sd.min(iqr) } else { sd };
+    if spread > 0.0 {
+        0.9 * spread * (sorted.len() as f64).powf(-0.2)
+    } else {
+        f64::NAN
+    }
+}
+
+pub fn gaussian_kde(xs: &[f64], at: &[f64], bandwidth: f64) -> Vec<f64> {
+    gaussian_kde_sorted(&sorted(xs), at, bandwidth)
+}
+
+/// Gaussian kernel density estimate of an ascending slice, evaluated at
+/// each point of `at`.
+///
+/// Only values within [`KDE_CUTOFF`] bandwidths of a point are summed, so a
+/// point costs O(log n + window) rather than O(n). Empty input or a
+/// non-positive bandwidth yields `NaN`s.
+pub fn gaussian_kde_sorted(sorted: &[f64], at: &[f64], bandwidth: f64) -> Vec<f64> {
+    if sorted.is_empty() || bandwidth.is_nan() || bandwidth <= 0.0 {
+        return vec![f64::NAN; at.len()];
+    }
+    let norm = 1.0 / (sorted.len() as f64 * bandwidth * (2.0 * std::f64::consts::PI).sqrt());
+    at.iter()
+        .map(|&x| {
+            let lo = sorted.partition_point(|&v| v < x - KDE_CUTOFF * bandwidth);
+            let hi = sorted.partition_point(|&v| v <= x + KDE_CUTOFF * bandwidth);
+            let sum: f64 = sorted[lo..hi]
+                .iter()
+                .map(|&v| {
+                    let u = (x - v) / bandwidth;
+                    (-0.5 * u * u).exp()
+                })
+                .sum();
+            sum * norm
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn densities_integrate_to_one() {
+        let xs = [0.0, 1.0, 1.5, 2.0, 3.5, 4.0];
+        let (counts, edges) = histogram(&xs, 4);
+        let d = bin_densities(&counts, &edges);
+        let area: f64 = d
+            .iter()
+            .zip(edges.windows(2))
+            .map(|(h, e)| h * (e[1] - e[0]))
+            .sum();
+        assert!((area - 1.0).abs() < 1e-12);
+        assert!(bin_densities(&[3], &[1.0, 1.0])[0].is_nan());
+        assert_eq!(bin_centers(&[0.0, 1.0, 3.0]), vec![0.5, 2.0]);
+    }
+
+    #[test]
+    fn silverman_matches_the_closed_form() {
+        let xs: Vec<f64> = (1..=100).map(f64::from).collect();
+        let sd = sample_std_dev(&xs, mean(&xs));
+        let expected = 0.9 * sd.min(iqr(&xs) / 1.34) * 100f64.powf(-0.2);
+        assert!((silverman_bandwidth(&xs) - expected).abs() < 1e-12);
+        assert!(silverman_bandwidth(&[2.0, 2.0, 2.0]).is_nan());
+        assert!(silverman_bandwidth(&[1.0]).is_nan());
+    }
+
+    #[test]
+    fn kde_of_one_point_is_the_kernel() {
+        let d = gaussian_kde(&[0.0], &[0.0, 1.0], 1.0);
+        let phi = |u: f64| (-0.5 * u * u).exp() / (2.0 * std::f64::consts::PI).sqrt();
+        assert!((d[0] - phi(0.0)).abs() < 1e-15);
+        assert!((d[1] - phi(1.0)).abs() < 1e-15);
+        assert!(gaussian_kde(&[], &[0.0], 1.0)[0].is_nan());
+        assert!(gaussian_kde(&[0.0], &[0.0], 0.0)[0].is_nan());
+    }
+
+    #[test]
+    fn kde_integrates_to_about_one() {
+        let xs = [-1.0, 0.0, 0.5, 2.0, 2.5];
+        let grid: Vec<f64> = (0..=2000).map(|i| -10.0 + i as f64 * 0.01).collect();
+        let d = gaussian_kde(&xs, &grid, 0.7);
+        let area: f64 = d.iter().sum::<f64>() * 0.01;
+        assert!((area - 1.0).abs() < 1e-6);
+    }
+}
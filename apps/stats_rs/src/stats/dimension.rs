@@ -0,0 +1,88 @@
+use crate::stats::prelude::*;
+
+/// TwoNN intrinsic-dimension estimate (Facco et al., 2017), Euclidean distances.
+///
+/// Uses the ratio μ = r₂/r₁ of second- to first-neighbor distances; under local
+/// uniformity μ is Pareto(d), giving the MLE `d = n / Σ ln μᵢ`. Points with a
+/// duplicate neighbor (r₁ = 0) are skipped. Returns NaN with fewer than 3 points
+/// or when no usable ratios remain.
+pub fn two_nn_dimension<T: Element>(points: &[Vec<T>]) -> f64 {
+    if points.len() < 3 {
+        return f64::NAN;
+    }
+    let logs: Vec<f64> = nearest_distances(points, 2, T::euclidean)
+        .into_iter()
+        .filter(|r| r[0] > 0.0)
+        .map(|r| (r[1] / r[0]).ln())
+        .collect();
+    let s = sum(&logs);
+    if logs.is_empty() || s <= 0.0 {
+        return f64::NAN;
+    }
+    logs.len() as f64 / s
+}
+
+/// Levina–Bickel MLE of intrinsic dimension with `k` neighbors, Euclidean distances.
+///
+/// Per point, `m̂ = [ (1/(k-1)) Σⱼ<k ln(T_k / T_j) ]⁻¹`; the per-point inverses are
+/// averaged before inverting (MacKay–Ghahramani correction). Points whose
+/// neighbor distances include zeros are skipped. Requires `2 ≤ k < n`; otherwise NaN.
+pub fn mle_dimension<T: Element>(points: &[Vec<T>], k: usize) -> f64 {
+    let n = points.len();
+    if k < 2 || k >= n {
+        return f64::NAN;
+    }
+    let inv: Vec<f64> = nearest_distances(points, k, T::euclidean)
+        .into_iter()
+        .filter(|r| r[0] > 0.0)
+        .map(|r| {
+            let tk = r[k - 1];
+            r[..k - 1].iter().map(|&tj| (tk / tj).ln()).sum::<f64>() / (k as f64 - 1.0)
+        })
+        .collect();
+    let m = mean(&inv);
+    if inv.is_empty() || m <= 0.0 {
+        return f64::NAN;
+    }
+    1.0 / m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-uniform points on a 2-D plane embedded in 4-D.
+    fn plane_in_4d(n: usize) -> Vec<Vec<f64>> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        (0..n)
+            .map(|_| {
+                let (u, v) = (next(), next());
+                vec![u, v, 0.5 * u - v, 2.0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn estimators_recover_plane_dimension() {
+        let pts = plane_in_4d(600);
+        let d2 = two_nn_dimension(&pts);
+        let dm = mle_dimension(&pts, 10);
+        assert!((d2 - 2.0).abs() < 0.35, "two_nn = {d2}");
+        assert!((dm - 2.0).abs() < 0.35, "mle = {dm}");
+    }
+
+    #[test]
+    fn degenerate_inputs_are_nan() {
+        assert!(two_nn_dimension(&[vec![0.0], vec![1.0]]).is_nan());
+        // all duplicates → no usable ratios
+        assert!(two_nn_dimension(&vec![vec![1.0, 1.0]; 5]).is_nan());
+        assert!(mle_dimension(&plane_in_4d(5), 1).is_nan());
+        assert!(mle_dimension(&plane_in_4d(5), 5).is_nan());
+    }
+}
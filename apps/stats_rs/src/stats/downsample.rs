@@ -0,0 +1,78 @@
+/// Positions of `m` evenly spaced points among `n`, first and last included.
+/// Returns every position when `m >= n`.
+pub fn uniform_indices(n: usize, m: usize) -> Vec<usize> {
+    if m >= n {
+        return (0..n).collect();
+    }
+    match m {
+        0 => vec![],
+        1 => vec![0],
+        // Round k·(n-1)/(m-1); strictly increasing because n > m
+        _ => (0..m)
+            .map(|k| (k * (n - 1) + (m - 1) / 2) / (m - 1))
+            .collect(),
+    }
+}
+
+/// Largest-Triangle-Three-Buckets: positions of `threshold` points of the
+/// curve `(xs[i], ys[i])` that keep its visual shape. The first and last
+/// points are always kept; each bucket in between contributes the point
+/// spanning the largest triangle with the previous pick and the next
+/// bucket's centroid. `xs` should be increasing.
+pub fn lttb_indices(xs: &[f64], ys: &[f64], threshold: usize) -> Vec<usize> {
+    let n = xs.len().min(ys.len());
+    if threshold >= n || threshold < 3 {
+        return uniform_indices(n, threshold);
+    }
+
+    let every = (n - 2) as f64 / (threshold - 2) as f64;
+    let bound = |i: usize| ((i as f64 * every) as usize + 1).min(n);
+    let mut out = Vec::with_capacity(threshold);
+    let mut a = 0usize;
+    out.push(a);
+    for b in 0..threshold - 2 {
+        let (start, end) = (bound(b), bound(b + 1));
+        let next = end..bound(b + 2).max(end + 1).min(n);
+        let len = next.len() as f64;
+        let cx = next.clone().map(|i| xs[i]).sum::<f64>() / len;
+        let cy = next.map(|i| ys[i]).sum::<f64>() / len;
+
+        let (ax, ay) = (xs[a], ys[a]);
+        let area = |j: usize| ((ax - cx) * (ys[j] - ay) - (ax - xs[j]) * (cy - ay)).abs();
+        a = (start..end)
+            .max_by(|&i, &j| area(i).total_cmp(&area(j)))
+            .unwrap_or(start);
+        out.push(a);
+    }
+    out.push(n - 1);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_keeps_ends_and_count() {
+        assert_eq!(uniform_indices(11, 4), [0, 3, 7, 10]);
+        assert_eq!(uniform_indices(5, 2), [0, 4]);
+        assert_eq!(uniform_indices(3, 10), [0, 1, 2]);
+        assert_eq!(uniform_indices(4, 1), [0]);
+    }
+
+    #[test]
+    fn lttb_keeps_the_spike() {
+        let xs: Vec<f64> = (0..100).map(f64::from).collect();
+        let mut ys = vec![0.0; 100];
+        ys[37] = 50.0;
+        let idx = lttb_indices(&xs, &ys, 10);
+        assert_eq!(idx.len(), 10);
+        assert_eq!((idx[0], idx[9]), (0, 99));
+        assert!(idx.contains(&37));
+        assert!(idx.windows(2).all(|w| w[0] < w[1]));
+
+        // Uniform sampling misses it
+        assert!(!uniform_indices(100, 10).contains(&37));
+        assert_eq!(lttb_indices(&xs, &ys, 2), [0, 99]);
+    }
+}
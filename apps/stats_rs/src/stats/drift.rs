@@ -10,10 +10,11 @@ pub fn psi_quantile_bins(expected: &[f64], actual: &[f64], bins: usize) -> f64 {
     }
 
     // Build bin edges from expected quantiles
+    let sorted_expected = sorted(expected);
     let mut edges = Vec::with_capacity(bins + 1);
     for i in 0..=bins {
         let p = i as f64 / bins as f64;
-        edges.push(quantile(expected, p));
+        edges.push(quantile_sorted(&sorted_expected, p));
     }
 
     // Count into bins
@@ -61,6 +62,37 @@ pub fn psi_quantile_bins(expected: &[f64], actual: &[f64], bins: usize) -> f64 {
     psi
 }
 
+/// First Wasserstein (earth mover's) distance between the empirical
+/// distributions of `x` and `y`, `∫ |Fₓ(t) − Fᵧ(t)| dt`: how far, in the
+/// units of the values, mass has to move to turn one into the other. `NaN`
+/// when either sample is empty.
+pub fn wasserstein_1(x: &[f64], y: &[f64]) -> f64 {
+    if x.is_empty() || y.is_empty() {
+        return f64::NAN;
+    }
+    let (a, b) = (sorted(x), sorted(y));
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    let (mut i, mut j, mut d) = (0, 0, 0.0);
+    let mut prev = a[0].min(b[0]);
+    while i < a.len() || j < b.len() {
+        let v = a
+            .get(i)
+            .into_iter()
+            .chain(b.get(j))
+            .fold(f64::INFINITY, |m, &x| m.min(x));
+        // the CDFs are flat between consecutive distinct values
+        d += (i as f64 / n1 - j as f64 / n2).abs() * (v - prev);
+        while i < a.len() && a[i] == v {
+            i += 1;
+        }
+        while j < b.len() && b[j] == v {
+            j += 1;
+        }
+        prev = v;
+    }
+    d
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,6 +111,16 @@ mod tests {
         let psi = psi_quantile_bins(&expected, &actual_shift, 5);
         assert!(psi > 0.0);
     }
+
+    #[test]
+    fn wasserstein_distance() {
+        // scipy.stats.wasserstein_distance docs example
+        assert!((wasserstein_1(&[0.0, 1.0, 3.0], &[5.0, 6.0, 8.0]) - 5.0).abs() < EPS_TIGHT);
+        // CDF gaps of 0.1 over [0, 1) and 0.2 over [1, 2)
+        assert!((wasserstein_1(&[0.0, 1.0], &[0.0, 0.0, 1.0, 1.0, 2.0]) - 0.3).abs() < EPS_TIGHT);
+        assert_eq!(wasserstein_1(&[2.0, 1.0], &[1.0, 2.0]), 0.0);
+        assert!(wasserstein_1(&[], &[1.0]).is_nan());
+    }
 }
 
 #[cfg(test)]
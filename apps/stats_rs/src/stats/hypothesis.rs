@@ -0,0 +1,516 @@
+use crate::stats::prelude::*;
+
+/// Jarque–Bera normality test: `(JB, p)`.
+///
+/// `JB = n/6 · (S² + (K - 3)²/4)` with the moment estimates of skewness `S`
+/// and kurtosis `K`; under normality it is asymptotically χ² with two degrees
+/// of freedom, whose survival function is `exp(-JB/2)`. The approximation is
+/// rough below a few dozen values. Fewer than 3 values or a constant sample
+/// give `(NaN, NaN)`.
+pub fn jarque_bera(xs: &[f64]) -> (f64, f64) {
+    let n = xs.len();
+    if n < 3 {
+        return (f64::NAN, f64::NAN);
+    }
+    let m = mean(xs);
+    let (m2, m3, m4) = xs.iter().fold((0.0, 0.0, 0.0), |(m2, m3, m4), &x| {
+        let d = x - m;
+        let d2 = d * d;
+        (m2 + d2, m3 + d2 * d, m4 + d2 * d2)
+    });
+    let n = n as f64;
+    let (m2, m3, m4) = (m2 / n, m3 / n, m4 / n);
+    if m2 <= 0.0 {
+        return (f64::NAN, f64::NAN);
+    }
+    let s = m3 / m2.powf(1.5);
+    let k = m4 / (m2 * m2);
+    let jb = n / 6.0 * (s * s + (k - 3.0).powi(2) / 4.0);
+    (jb, (-jb / 2.0).exp())
+}
+
+/// Filliben's estimates of the medians of the `n` standard normal order
+/// statistics: `Φ⁻¹(m_i)` with `m_n = 0.5^(1/n)`, `m_1 = 1 − m_n` and
+/// `m_i = (i − 0.3175) / (n + 0.365)` in between.
+pub fn filliben_medians(n: usize) -> Vec<f64> {
+    let last = 0.5f64.powf(1.0 / n as f64);
+    (1..=n)
+        .map(|i| {
+            let m = match i {
+                1 => 1.0 - last,
+                i if i == n => last,
+                i => (i as f64 - 0.3175) / (n as f64 + 0.365),
+            };
+            normal_quantile(m)
+        })
+        .collect()
+}
+
+/// Filliben's normal probability-plot correlation coefficient of an
+/// ascending slice: the Pearson correlation between the values and
+/// [`filliben_medians`]. Close to 1 for normal data; `NaN` for fewer than 3
+/// values or a constant sample.
+pub fn normal_ppcc_sorted(sorted: &[f64]) -> f64 {
+    if sorted.len() < 3 {
+        return f64::NAN;
+    }
+    pearson_correlation(sorted, &filliben_medians(sorted.len()))
+}
+
+/// Critical value of [`normal_ppcc_sorted`] at level `alpha` for `n`
+/// values: normality is rejected below it.
+///
+/// Uses Royston's (1993) approximation for the Shapiro–Francia statistic
+/// `W′ = r²`, `ln(1 − W′) ~ N(μ, σ)` with `μ = −1.2725 + 1.0521(u − v)`,
+/// `σ = 1.0308 − 0.26758(u + 2/v)`, `u = ln ln n` and `v = ln n`; it is
+/// within about 0.005 of Filliben's tables. `NaN` outside `5 ≤ n ≤ 5000`,
+/// where the approximation was fitted.
+pub fn normal_ppcc_critical(n: usize, alpha: f64) -> f64 {
+    if !(5..=5000).contains(&n) {
+        return f64::NAN;
+    }
+    let v = (n as f64).ln();
+    let u = v.ln();
+    let mu = -1.2725 + 1.0521 * (u - v);
+    let sigma = 1.0308 - 0.26758 * (u + 2.0 / v);
+    (1.0 - (mu + sigma * normal_quantile(1.0 - alpha)).exp()).sqrt()
+}
+
+/// Pearson's χ² test of independence on an `r×c` table of counts:
+/// `(χ², df, p)` with `df = (r − 1)(c − 1)` and `p` from the χ² survival
+/// function. `yates` applies the continuity correction
+/// `Σ (|O − E| − ½)⁺² / E` to 2×2 tables (other shapes are unaffected).
+///
+/// `NaN` statistics when `df` is 0 or a row or column sums to zero.
+pub fn chi_square_independence(table: &[Vec<usize>], yates: bool) -> (f64, usize, f64) {
+    let r = table.len();
+    let c = table.first().map_or(0, Vec::len);
+    let df = r.saturating_sub(1) * c.saturating_sub(1);
+    let rows: Vec<f64> = table
+        .iter()
+        .map(|row| row.iter().sum::<usize>() as f64)
+        .collect();
+    let cols: Vec<f64> = (0..c)
+        .map(|j| table.iter().map(|row| row[j]).sum::<usize>() as f64)
+        .collect();
+    let n: f64 = rows.iter().sum();
+    if df == 0 || rows.iter().chain(&cols).any(|&t| t == 0.0) {
+        return (f64::NAN, df, f64::NAN);
+    }
+    let correct = yates && r == 2 && c == 2;
+    let mut chi2 = 0.0;
+    for (i, row) in table.iter().enumerate() {
+        for (j, &o) in row.iter().enumerate() {
+            let e = rows[i] * cols[j] / n;
+            let d = (o as f64 - e).abs();
+            let d = if correct { (d - 0.5).max(0.0) } else { d };
+            chi2 += d * d / e;
+        }
+    }
+    (chi2, df, chi_square_sf(chi2, df as f64))
+}
+
+/// Cramér's V of a `r×c` table of `n` observations with Pearson statistic
+/// `chi2`: `√(χ² / (n · (min(r, c) − 1)))`, in `[0, 1]`. `NaN` for a table
+/// with a single row or column.
+pub fn cramers_v(chi2: f64, n: usize, r: usize, c: usize) -> f64 {
+    let k = r.min(c);
+    if k < 2 || n == 0 {
+        return f64::NAN;
+    }
+    (chi2 / (n as f64 * (k - 1) as f64)).sqrt().min(1.0)
+}
+
+/// Two-sided two-sample t-test, `(t, df, p)`, for `mean(x) − mean(y)`.
+///
+/// `welch: false` pools the variances (Student, `df = n₁ + n₂ − 2`);
+/// `welch: true` keeps them apart and uses the Welch–Satterthwaite `df`.
+/// `NaN` statistics below two values per sample or with no variation.
+pub fn two_sample_t_test(x: &[f64], y: &[f64], welch: bool) -> (f64, f64, f64) {
+    let (diff, se, df) = match welch {
+        true => welch_t(x, y),
+        false => pooled_t(x, y),
+    };
+    if se.is_nan() || se <= 0.0 {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let t = diff / se;
+    (t, df, t_p_value(t, df, Tail::TwoSided))
+}
+
+/// `mean(x) − mean(y)` with its pooled-variance standard error and
+/// `n₁ + n₂ − 2` degrees of freedom: the parts of Student's t-test. `NaN`s
+/// below two values per sample.
+fn pooled_t(x: &[f64], y: &[f64]) -> (f64, f64, f64) {
+    if x.len() < 2 || y.len() < 2 {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let (n1, n2) = (x.len() as f64, y.len() as f64);
+    let (m1, m2) = (mean(x), mean(y));
+    let df = n1 + n2 - 2.0;
+    let pooled = ((n1 - 1.0) * sample_variance(x, m1) + (n2 - 1.0) * sample_variance(y, m2)) / df;
+    (m1 - m2, (pooled * (1.0 / n1 + 1.0 / n2)).sqrt(), df)
+}
+
+/// `Σ (t³ − t)` over the groups of `t` tied values, for rank-test variance
+/// corrections.
+fn tie_sum(xs: &[f64]) -> f64 {
+    let s = sorted(xs);
+    let mut ties = 0.0;
+    let mut i = 0;
+    while i < s.len() {
+        let j = i + s[i..].iter().take_while(|&&v| v == s[i]).count();
+        let t = (j - i) as f64;
+        ties += t * t * t - t;
+        i = j;
+    }
+    ties
+}
+
+/// Mann–Whitney U test, `(U, z, p)`: `U` counts the pairs with `x > y`
+/// (ties as ½), and the two-sided `p` comes from the normal approximation
+/// with tie and continuity corrections. `NaN` `z` and `p` when either sample
+/// is empty or every value ties.
+pub fn mann_whitney_u(x: &[f64], y: &[f64]) -> (f64, f64, f64) {
+    let (n1, n2) = (x.len() as f64, y.len() as f64);
+    if x.is_empty() || y.is_empty() {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let pooled: Vec<f64> = x.iter().chain(y).copied().collect();
+    let ranks = average_ranks(&pooled);
+    let r1: f64 = ranks[..x.len()].iter().sum();
+    let u = r1 - n1 * (n1 + 1.0) / 2.0;
+
+    let ties = tie_sum(&pooled);
+    let n = n1 + n2;
+    let var = n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    if var.is_nan() || var <= 0.0 {
+        return (u, f64::NAN, f64::NAN);
+    }
+    let d = u - n1 * n2 / 2.0;
+    let z = d.signum() * (d.abs() - 0.5).max(0.0) / var.sqrt();
+    (u, z, (2.0 * normal_sf(z.abs())).min(1.0))
+}
+
+/// Two-sample Kolmogorov–Smirnov test, `(D, p)`: the largest gap between the
+/// two empirical CDFs and its asymptotic p-value,
+/// `Q((√nₑ + 0.12 + 0.11/√nₑ) D)` with `nₑ = n₁n₂ / (n₁ + n₂)`. `NaN` when
+/// either sample is empty.
+pub fn ks_two_sample(x: &[f64], y: &[f64]) -> (f64, f64) {
+    if x.is_empty() || y.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    let (a, b) = (sorted(x), sorted(y));
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    let (mut i, mut j, mut d) = (0, 0, 0.0f64);
+    while i < a.len() && j < b.len() {
+        // step past every copy of the smaller value on both sides
+        let v = a[i].min(b[j]);
+        while i < a.len() && a[i] == v {
+            i += 1;
+        }
+        while j < b.len() && b[j] == v {
+            j += 1;
+        }
+        d = d.max((i as f64 / n1 - j as f64 / n2).abs());
+    }
+    let en = (n1 * n2 / (n1 + n2)).sqrt();
+    (d, kolmogorov_sf((en + 0.12 + 0.11 / en) * d))
+}
+
+/// F test for equal variances, `(F, p)` with `F = s²ₓ / s²ᵧ` on
+/// `(n₁ − 1, n₂ − 1)` degrees of freedom and a two-sided `p`. Sensitive to
+/// non-normality; see [`brown_forsythe`]. `NaN` below two values per sample
+/// or with a zero variance.
+pub fn variance_ratio_test(x: &[f64], y: &[f64]) -> (f64, f64) {
+    if x.len() < 2 || y.len() < 2 {
+        return (f64::NAN, f64::NAN);
+    }
+    let f = sample_variance(x, mean(x)) / sample_variance(y, mean(y));
+    if !(f.is_finite() && f > 0.0) {
+        return (f64::NAN, f64::NAN);
+    }
+    let (d1, d2) = ((x.len() - 1) as f64, (y.len() - 1) as f64);
+    let upper = f_sf(f, d1, d2);
+    (f, (2.0 * upper.min(1.0 - upper)).min(1.0))
+}
+
+/// One-way ANOVA F test for equal means across `groups`, `(F, df1, df2,
+/// p)`: between-group over within-group mean squares on `(k − 1, n − k)`
+/// degrees of freedom. Empty groups are skipped. `NaN` with fewer than two
+/// non-empty groups or no residual degrees of freedom, and `NaN` `F` and `p`
+/// when nothing varies within the groups.
+pub fn one_way_anova(groups: &[&[f64]]) -> (f64, f64, f64, f64) {
+    let groups: Vec<&[f64]> = groups.iter().copied().filter(|g| !g.is_empty()).collect();
+    let k = groups.len();
+    let n: usize = groups.iter().map(|g| g.len()).sum();
+    if k < 2 || n <= k {
+        return (f64::NAN, f64::NAN, f64::NAN, f64::NAN);
+    }
+    let grand = groups.iter().copied().flatten().sum::<f64>() / n as f64;
+    let (mut between, mut within) = (0.0, 0.0);
+    for g in &groups {
+        let m = mean(g);
+        between += g.len() as f64 * (m - grand).powi(2);
+        within += g.iter().map(|v| (v - m).powi(2)).sum::<f64>();
+    }
+    let (df1, df2) = ((k - 1) as f64, (n - k) as f64);
+    if within.is_nan() || within <= 0.0 {
+        return (f64::NAN, df1, df2, f64::NAN);
+    }
+    let f = (between / df1) / (within / df2);
+    (f, df1, df2, f_sf(f, df1, df2))
+}
+
+/// Welch's ANOVA for equal means without assuming equal variances, `(F,
+/// df1, df2, p)`: group means weighted by `nᵢ / sᵢ²`, with Welch's
+/// adjusted `F` and fractional `df2`. `NaN` when there are fewer than two
+/// groups or a group has fewer than two values or no variation.
+pub fn welch_anova(groups: &[&[f64]]) -> (f64, f64, f64, f64) {
+    let k = groups.len();
+    if k < 2 || groups.iter().any(|g| g.len() < 2) {
+        return (f64::NAN, f64::NAN, f64::NAN, f64::NAN);
+    }
+    let stats: Vec<(f64, f64, f64)> = groups
+        .iter()
+        .map(|g| {
+            let m = mean(g);
+            (g.len() as f64, m, g.len() as f64 / sample_variance(g, m))
+        })
+        .collect();
+    let w: f64 = stats.iter().map(|s| s.2).sum();
+    if !w.is_finite() {
+        return (f64::NAN, f64::NAN, f64::NAN, f64::NAN);
+    }
+    let grand = stats.iter().map(|&(_, m, wi)| wi * m).sum::<f64>() / w;
+    let kf = k as f64;
+    let a = stats
+        .iter()
+        .map(|&(_, m, wi)| wi * (m - grand).powi(2))
+        .sum::<f64>()
+        / (kf - 1.0);
+    let tmp: f64 = stats
+        .iter()
+        .map(|&(n, _, wi)| (1.0 - wi / w).powi(2) / (n - 1.0))
+        .sum();
+    let f = a / (1.0 + 2.0 * (kf - 2.0) / (kf * kf - 1.0) * tmp);
+    let (df1, df2) = (kf - 1.0, (kf * kf - 1.0) / (3.0 * tmp));
+    (f, df1, df2, f_sf(f, df1, df2))
+}
+
+/// Kruskal–Wallis rank test across `groups`, `(H, df, p)`, with the tie
+/// correction and the χ² approximation on `k − 1` degrees of freedom. Empty
+/// groups are skipped. `NaN` with fewer than two non-empty groups or when
+/// every value ties.
+pub fn kruskal_wallis(groups: &[&[f64]]) -> (f64, f64, f64) {
+    let groups: Vec<&[f64]> = groups.iter().copied().filter(|g| !g.is_empty()).collect();
+    let k = groups.len();
+    if k < 2 {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let pooled: Vec<f64> = groups.iter().copied().flatten().copied().collect();
+    let n = pooled.len() as f64;
+    let ranks = average_ranks(&pooled);
+    let mut at = 0;
+    let mut h = 0.0;
+    for g in &groups {
+        let r: f64 = ranks[at..at + g.len()].iter().sum();
+        h += r * r / g.len() as f64;
+        at += g.len();
+    }
+    h = 12.0 / (n * (n + 1.0)) * h - 3.0 * (n + 1.0);
+    let correction = 1.0 - tie_sum(&pooled) / (n * n * n - n);
+    let df = (k - 1) as f64;
+    if correction.is_nan() || correction <= 0.0 {
+        return (f64::NAN, df, f64::NAN);
+    }
+    let h = h / correction;
+    (h, df, chi_square_sf(h, df))
+}
+
+/// Brown–Forsythe test for equal variances across `groups`, `(W, df1, df2,
+/// p)`: a one-way ANOVA on the absolute deviations from each group's median,
+/// robust to non-normal data. `NaN` with fewer than two non-empty groups, no
+/// residual degrees of freedom, or no spread.
+pub fn brown_forsythe(groups: &[&[f64]]) -> (f64, f64, f64, f64) {
+    let devs: Vec<Vec<f64>> = groups
+        .iter()
+        .map(|g| {
+            let med = median(g);
+            g.iter().map(|x| (x - med).abs()).collect()
+        })
+        .collect();
+    let devs: Vec<&[f64]> = devs.iter().map(Vec::as_slice).collect();
+    one_way_anova(&devs)
+}
+
+/// Cohen's `d` for `mean(x) − mean(y)` with the pooled standard deviation,
+/// and Hedges' small-sample correction `g = d · (1 − 3 / (4(n₁ + n₂) − 9))`.
+/// `NaN` below two values per sample or with no variation.
+pub fn cohens_d(x: &[f64], y: &[f64]) -> (f64, f64) {
+    if x.len() < 2 || y.len() < 2 {
+        return (f64::NAN, f64::NAN);
+    }
+    let (n1, n2) = (x.len() as f64, y.len() as f64);
+    let (m1, m2) = (mean(x), mean(y));
+    let pooled = ((n1 - 1.0) * sample_variance(x, m1) + (n2 - 1.0) * sample_variance(y, m2))
+        / (n1 + n2 - 2.0);
+    if pooled.is_nan() || pooled <= 0.0 {
+        return (f64::NAN, f64::NAN);
+    }
+    let d = (m1 - m2) / pooled.sqrt();
+    (d, d * (1.0 - 3.0 / (4.0 * (n1 + n2) - 9.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx;
+
+    #[test]
+    fn jarque_bera_separates_symmetric_from_skewed() {
+        // Symmetric and flat: S = 0, K = 1.7758 for 1..=10
+        let xs: Vec<f64> = (1..=10).map(f64::from).collect();
+        let (jb, p) = jarque_bera(&xs);
+        approx!(
+            jb,
+            10.0 / 6.0 * (1.7757575757575756f64 - 3.0).powi(2) / 4.0,
+            1e-12
+        );
+        assert!(p > 0.05, "p = {p}");
+
+        // Exponential-looking sample: strongly right-skewed
+        let ys: Vec<f64> = (1..=200).map(|i| -(1.0 - i as f64 / 201.0).ln()).collect();
+        let (_, p) = jarque_bera(&ys);
+        assert!(p < 1e-6, "p = {p}");
+
+        assert!(jarque_bera(&[1.0, 2.0]).0.is_nan());
+        assert!(jarque_bera(&[4.0; 5]).1.is_nan());
+    }
+
+    #[test]
+    fn ppcc_and_its_critical_values() {
+        let m = filliben_medians(5);
+        approx!(m[2], 0.0, 1e-15);
+        approx!(m[0], -m[4], 1e-15);
+        approx!(
+            normal_ppcc_sorted(&[1.0, 2.0, 2.1, 2.9, 3.5]),
+            0.985_673_386_960_417_7,
+            1e-12
+        );
+        // a straight line through the medians is perfectly normal
+        let line: Vec<f64> = filliben_medians(30).iter().map(|z| 3.0 + 2.0 * z).collect();
+        approx!(normal_ppcc_sorted(&line), 1.0, 1e-12);
+        assert!(normal_ppcc_sorted(&[1.0, 2.0]).is_nan());
+
+        // Filliben's table at 5%: 0.879 (n = 5), 0.950 (20), 0.987 (100)
+        approx!(normal_ppcc_critical(5, 0.05), 0.884_642_772_102_697_6, 1e-9);
+        approx!(
+            normal_ppcc_critical(20, 0.05),
+            0.951_180_261_906_049_5,
+            1e-9
+        );
+        approx!(
+            normal_ppcc_critical(100, 0.05),
+            0.987_290_613_046_748_9,
+            1e-9
+        );
+        assert!(normal_ppcc_critical(4, 0.05).is_nan());
+    }
+
+    #[test]
+    fn chi_square_of_a_two_by_three_table() {
+        // expected counts 15 / 15 / 20 in both rows
+        let table = vec![vec![10, 20, 20], vec![20, 10, 20]];
+        let (chi2, df, p) = chi_square_independence(&table, false);
+        approx!(chi2, 2.0 * (25.0 / 15.0 + 25.0 / 15.0), 1e-12);
+        assert_eq!(df, 2);
+        // with two degrees of freedom the survival function is exp(−χ²/2)
+        approx!(p, (-chi2 / 2.0).exp(), 1e-12);
+        approx!(cramers_v(chi2, 100, 2, 3), (chi2 / 100.0).sqrt(), 1e-15);
+
+        // Yates only touches 2×2 tables
+        assert_eq!(chi_square_independence(&table, true).0, chi2);
+        let (plain, _, _) = chi_square_independence(&[vec![12, 5], vec![3, 10]], false);
+        let (yates, _, p) = chi_square_independence(&[vec![12, 5], vec![3, 10]], true);
+        approx!(plain, 6.651_583_710_407_239, 1e-12);
+        approx!(yates, 4.886_877_828_054_299, 1e-12);
+        // one degree of freedom: p = erfc(√(χ²/2))
+        approx!(p, 0.027_061_581_911_647_14, 1e-9);
+
+        let (chi2, df, _) = chi_square_independence(&[vec![4, 6]], false);
+        assert!(chi2.is_nan() && df == 0);
+        assert!(cramers_v(1.0, 10, 1, 4).is_nan());
+    }
+
+    #[test]
+    fn two_sample_tests() {
+        let x = [5.1, 4.9, 6.2, 5.8, 6.0, 5.5, 5.3];
+        let y = [4.1, 4.5, 4.8, 3.9, 5.0, 4.4];
+        let (t, df, p) = two_sample_t_test(&x, &y, false);
+        approx!(t, 4.360_654_855_549_885, 1e-12);
+        assert_eq!(df, 11.0);
+        approx!(p, 0.001_135_056_767_812_891, 1e-10);
+        let (t, df, p) = two_sample_t_test(&x, &y, true);
+        approx!(t, 4.414_400_351_382_94, 1e-12);
+        approx!(df, 10.995_144_479_195_31, 1e-10);
+        approx!(p, 0.001_039_201_915_728_614, 1e-10);
+
+        let (d, g) = cohens_d(&x, &y);
+        approx!(d, 2.426_044_399_546_602, 1e-12);
+        approx!(g, 2.256_785_487_950_328, 1e-12);
+        let (f, p) = variance_ratio_test(&x, &y);
+        approx!(f, 1.342_244_500_139_236_5, 1e-12);
+        approx!(p, 0.764_161_095_924_452_8, 1e-10);
+        let (w, df1, df2, p) = brown_forsythe(&[&x, &y]);
+        approx!(w, 0.281_493_427_458_617_44, 1e-12);
+        assert_eq!((df1, df2), (1.0, 11.0));
+        approx!(p, 0.606_269_174_423_286_5, 1e-10);
+
+        // all of y lies below x but for 4.9: D = 1 − 1/7 at 5.0
+        let (d, p) = ks_two_sample(&x, &y);
+        approx!(d, 6.0 / 7.0, 1e-15);
+        approx!(p, 0.006_348_823_824_533_73, 1e-10);
+
+        assert!(two_sample_t_test(&[1.0], &y, true).0.is_nan());
+        assert!(ks_two_sample(&[], &y).1.is_nan());
+    }
+
+    #[test]
+    fn mann_whitney_with_ties() {
+        let (u, z, p) = mann_whitney_u(&[1.0, 2.0, 2.0, 3.0, 5.0, 7.0], &[2.0, 3.0, 4.0, 4.0, 6.0]);
+        assert_eq!(u, 11.5);
+        // tie groups of 3, 2 and 2 shrink the variance; continuity pulls |d| by ½
+        approx!(z, -0.555_347_832_401_703_9, 1e-12);
+        approx!(p, 0.578_656_767_594_192, 1e-10);
+        let (u, z, _) = mann_whitney_u(&[1.0, 1.0], &[1.0]);
+        assert_eq!(u, 1.0);
+        assert!(z.is_nan());
+    }
+
+    #[test]
+    fn k_sample_tests() {
+        let g1 = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let g2 = [2.0, 4.0, 6.0, 8.0, 10.0];
+        let g3 = [3.0, 3.0, 4.0, 9.0, 11.0, 12.0];
+        let groups: [&[f64]; 3] = [&g1, &g2, &g3];
+        let (f, df1, df2, p) = one_way_anova(&groups);
+        approx!(f, 2.195_542_279_411_764_5, 1e-12);
+        assert_eq!((df1, df2), (2.0, 13.0));
+        approx!(p, 0.150_837_198_035_510_7, 1e-10);
+        let (f, df1, df2, p) = welch_anova(&groups);
+        approx!(f, 3.285_095_633_691_194_7, 1e-12);
+        assert_eq!(df1, 2.0);
+        approx!(df2, 7.703_568_234_411_787, 1e-10);
+        approx!(p, 0.092_963_685_171_702_44, 1e-10);
+        // the tie between the two 3s in g3 and g1 trims H slightly
+        let (h, df, p) = kruskal_wallis(&groups);
+        approx!(h, 3.414_307_004_470_944, 1e-12);
+        assert_eq!(df, 2.0);
+        approx!(p, 0.181_381_360_124_247, 1e-10);
+
+        assert!(one_way_anova(&[&g1]).0.is_nan());
+        assert!(welch_anova(&[&g1, &[1.0]]).0.is_nan());
+        assert!(kruskal_wallis(&[&[2.0, 2.0], &[2.0]]).0.is_nan());
+    }
+}
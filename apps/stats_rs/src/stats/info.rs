@@ -1,3 +1,5 @@
+use crate::stats::prelude::*;
+
 /// Entropy in bits. p must be a prob. vector (sum≈1, all >=0).
 pub fn entropy_bits(p: &[f64]) -> f64 {
     let eps = 1e-15;
@@ -34,6 +36,54 @@ pub fn js_divergence_bits(p: &[f64], q: &[f64]) -> f64 {
     0.5 * kl_divergence_bits(p, &m) + 0.5 * kl_divergence_bits(q, &m)
 }
 
+/// Plug-in entropy in bits of the distribution a histogram's `counts`
+/// describe; at most `log2` of the number of non-empty bins. `NaN` when
+/// every count is zero.
+pub fn histogram_entropy_bits(counts: &[usize]) -> f64 {
+    let total = counts.iter().sum::<usize>() as f64;
+    if total == 0.0 {
+        return f64::NAN;
+    }
+    let p: Vec<f64> = counts.iter().map(|&c| c as f64 / total).collect();
+    entropy_bits(&p)
+}
+
+/// Kozachenko–Leonenko estimate of the differential entropy in nats of a
+/// one-dimensional sample: `ψ(n) − ψ(k) + ln 2 + mean(ln ε_i)`, with `ε_i`
+/// the distance from `x_i` to its `k`-th nearest neighbour.
+///
+/// `NaN` unless `1 <= k < n`; `−∞` when a value repeats more than `k` times,
+/// so that some `ε_i` is 0.
+pub fn knn_entropy_nats(xs: &[f64], k: usize) -> f64 {
+    let s = sorted(xs);
+    let n = s.len();
+    if k == 0 || k >= n {
+        return f64::NAN;
+    }
+    let mut sum_ln = 0.0;
+    for i in 0..n {
+        // merge the neighbours on either side until the k-th is reached
+        let (mut l, mut r, mut eps) = (i, i + 1, 0.0);
+        for _ in 0..k {
+            let left = if l > 0 {
+                s[i] - s[l - 1]
+            } else {
+                f64::INFINITY
+            };
+            let right = if r < n { s[r] - s[i] } else { f64::INFINITY };
+            if left <= right {
+                eps = left;
+                l -= 1;
+            } else {
+                eps = right;
+                r += 1;
+            }
+        }
+        sum_ln += f64::ln(eps);
+    }
+    digamma(n as f64) - digamma(k as f64) + std::f64::consts::LN_2 + sum_ln / n as f64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -55,6 +105,40 @@ mod tests {
         let q2 = vec![0.0, 1.0];
         approx!(js_divergence_bits(&p2, &q2), 1.0, EPS);
     }
+
+    #[test]
+    fn histogram_entropy_of_counts() {
+        approx!(histogram_entropy_bits(&[5, 5, 5, 5]), 2.0, 1e-12);
+        approx!(histogram_entropy_bits(&[7, 0, 0]), 0.0, EPS);
+        assert!(histogram_entropy_bits(&[0, 0]).is_nan());
+    }
+
+    #[test]
+    fn knn_entropy_approaches_the_normal_entropy() {
+        use rand::{Rng, SeedableRng, rngs::StdRng};
+
+        // a standard normal sample: H = ln(2πe) / 2 ≈ 1.4189 nats
+        let mut rng = StdRng::seed_from_u64(3);
+        let xs: Vec<f64> = (0..4_000)
+            .map(|_| normal_quantile(rng.random::<f64>()))
+            .collect();
+        let h = knn_entropy_nats(&xs, 3);
+        let exact = 0.5 * (2.0 * std::f64::consts::PI * std::f64::consts::E).ln();
+        assert!((h - exact).abs() < 0.05, "{h}");
+        // scaling by a adds ln a
+        let scaled: Vec<f64> = xs.iter().map(|x| 4.0 * x).collect();
+        approx!(knn_entropy_nats(&scaled, 3), h + 4f64.ln(), 1e-12);
+    }
+
+    #[test]
+    fn knn_entropy_edges() {
+        assert!(knn_entropy_nats(&[1.0, 2.0], 2).is_nan());
+        assert!(knn_entropy_nats(&[1.0, 2.0], 0).is_nan());
+        assert_eq!(
+            knn_entropy_nats(&[1.0, 1.0, 1.0, 2.0], 1),
+            f64::NEG_INFINITY
+        );
+    }
 }
 
 #[cfg(test)]
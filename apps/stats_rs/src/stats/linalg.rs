@@ -0,0 +1,118 @@
+//! Matrix kernels on `ndarray` (feature `ndarray`).
+//!
+//! Correlation, covariance and similarity matrices are all one product of a
+//! row matrix with its transpose; here that product is a single GEMM
+//! (`matrixmultiply`, or the system BLAS when the final binary enables
+//! `ndarray/blas` and links a `blas-src` provider) instead of `m²/2`
+//! separate dot products. Results are row-major `Vec`s so callers don't
+//! depend on `ndarray` types.
+
+use ndarray::Array2;
+
+/// Stack equal-length rows into an `n×d` matrix. Panics on ragged input.
+fn stack(rows: &[Vec<f64>]) -> Array2<f64> {
+    let d = rows.first().map_or(0, Vec::len);
+    let mut a = Array2::zeros((rows.len(), d));
+    for (mut dst, src) in a.rows_mut().into_iter().zip(rows) {
+        assert_eq!(src.len(), d, "rows must have the same length");
+        dst.assign(&ndarray::ArrayView1::from(src.as_slice()));
+    }
+    a
+}
+
+/// `A·Bᵀ` as `a.len()` rows of `b.len()` inner products.
+pub fn cross_products(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let p = stack(a).dot(&stack(b).t());
+    p.rows().into_iter().map(|r| r.to_vec()).collect()
+}
+
+/// Row-major `n×n` Gram matrix `A·Aᵀ` of the rows.
+pub fn gram(rows: &[Vec<f64>]) -> Vec<f64> {
+    let a = stack(rows);
+    a.dot(&a.t()).into_raw_vec_and_offset().0
+}
+
+/// Cosine similarity of every row of `a` to every row of `b`; zero rows give NaN.
+pub fn cosine_matrix(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let norms = |rows: &[Vec<f64>]| -> Vec<f64> {
+        rows.iter()
+            .map(|r| r.iter().map(|x| x * x).sum::<f64>().sqrt())
+            .collect()
+    };
+    let (na, nb) = (norms(a), norms(b));
+    let mut p = cross_products(a, b);
+    for (row, &x) in p.iter_mut().zip(&na) {
+        for (v, &y) in row.iter_mut().zip(&nb) {
+            *v = if x == 0.0 || y == 0.0 {
+                f64::NAN
+            } else {
+                *v / (x * y)
+            };
+        }
+    }
+    p
+}
+
+/// Row-major `m×m` sample covariance matrix (denominator `n - 1`) of
+/// equally long `series`; NaN throughout when they have fewer than 2 values.
+pub fn covariance_matrix(series: &[Vec<f64>]) -> Vec<f64> {
+    let n = series.first().map_or(0, Vec::len);
+    let centred: Vec<Vec<f64>> = series
+        .iter()
+        .map(|xs| {
+            let m = super::mean(xs);
+            xs.iter().map(|x| x - m).collect()
+        })
+        .collect();
+    let denom = n as f64 - 1.0;
+    gram(&centred)
+        .into_iter()
+        .map(|v| if n < 2 { f64::NAN } else { v / denom })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx;
+    use crate::stats::prelude::*;
+    use crate::stats::utils::EPS_TIGHT;
+
+    #[test]
+    fn products_match_the_scalar_kernels() {
+        let a = vec![
+            vec![1.0, 2.0, 3.0],
+            vec![0.0, 0.0, 0.0],
+            vec![-1.0, 0.5, 2.0],
+        ];
+        let b = vec![vec![2.0, 0.0, 1.0], vec![1.0, 1.0, 1.0]];
+
+        let p = cross_products(&a, &b);
+        assert_eq!((p.len(), p[0].len()), (3, 2));
+        approx!(p[2][0], dot(&a[2], &b[0]), EPS_TIGHT);
+
+        let g = gram(&a);
+        approx!(g[2], dot(&a[0], &a[2]), EPS_TIGHT);
+        approx!(g[2 * 3], g[2], EPS_TIGHT);
+
+        let c = cosine_matrix(&a, &b);
+        approx!(c[0][1], cosine_similarity(&a[0], &b[1]), EPS_TIGHT);
+        assert!(c[1][0].is_nan());
+    }
+
+    #[test]
+    fn covariance_matrix_matches_pairwise_covariance() {
+        let s = vec![
+            vec![1.0, 2.0, 4.0, 8.0],
+            vec![3.0, 1.0, 0.0, -2.0],
+            vec![5.0, 5.0, 6.0, 5.0],
+        ];
+        let c = covariance_matrix(&s);
+        for i in 0..3 {
+            for j in 0..3 {
+                approx!(c[i * 3 + j], covariance(&s[i], &s[j]), 1e-12);
+            }
+        }
+        assert!(covariance_matrix(&[vec![1.0], vec![2.0]])[0].is_nan());
+    }
+}
@@ -1,71 +1,255 @@
 // src/stats/mod.rs
+pub mod anomaly;
 pub mod basic;
+pub mod binning;
+pub mod checkpoint;
 pub mod cluster;
 pub mod corr;
+pub mod density;
+pub mod dimension;
+pub mod dist;
+pub mod downsample;
 pub mod drift;
+pub mod hypothesis;
 pub mod info;
+#[cfg(feature = "ndarray")]
+pub mod linalg;
 pub mod online;
 pub mod preprocess;
 #[cfg(feature = "rag")]
 pub mod rag;
+pub mod random;
+pub mod resampling;
 pub mod robust;
+pub mod seasonal;
+pub mod simulate;
+pub mod sketch;
+pub mod tests;
+#[cfg(feature = "rag")]
+pub mod text;
 pub mod vector;
+pub mod weighted;
 
+pub use anomaly::*;
 pub use basic::*;
+pub use binning::*;
+pub use checkpoint::*;
 pub use cluster::*;
 pub use corr::*;
+pub use density::*;
+pub use dimension::*;
+pub use dist::*;
+pub use downsample::*;
 pub use drift::*;
+pub use hypothesis::*;
 pub use info::*;
 pub use online::*;
 pub use preprocess::*;
 #[cfg(feature = "rag")]
 pub use rag::*;
+pub use random::*;
+pub use resampling::*;
 pub use robust::*;
+pub use seasonal::*;
+pub use simulate::*;
+pub use sketch::*;
+pub use tests::*;
+#[cfg(feature = "rag")]
+pub use text::*;
 pub use vector::*;
+pub use weighted::*;
 
 mod utils;
 
 /// Handy prelude for routes and downstream crates.
 pub mod prelude {
     pub use super::{
+        CancelFlag,
+        Checkpoint,
+        Element,
+        EwmaResidual,
+        Formula,
+        Linkage,
+        Merge,
+        OUTLIER_WARMUP,
+        OnlineCorrMatrix,
         OnlineMeanVar,
+        P2Quantile,
+        QuantileMethod,
+        QuantileSketch,
+        RollingRobustZ,
+        SKETCH_CONFIDENCE,
+        SKETCH_SIZE,
+        Sampler,
+        SparseVector,
+        Tail,
+        acf_period,
+        autocorrelation,
         average_ranks,
+        beta_cdf,
+        beta_inc,
+        beta_pdf,
+        beta_quantile,
+        bin_centers,
+        bin_densities,
+        binomial,
+        bootstrap_ci,
+        bootstrap_se,
+        brown_forsythe,
         centroid,
+        chi_square_cdf,
+        chi_square_independence,
+        chi_square_pdf,
+        chi_square_quantile,
+        chi_square_sf,
+        cohens_d,
+        compensated_sum,
+        connected_components,
+        correlation_p_value,
         cosine_similarity,
+        cosine_similarity_f32,
         // corr / shape
         covariance,
+        cramers_v,
+        digamma,
+        doane_bins,
         // vector / cluster / info / drift / online
         dot,
+        dot_f32,
         entropy_bits,
+        eta_squared,
+        euclidean_distance,
+        euclidean_distance_f32,
         excess_kurtosis,
+        excess_kurtosis_se,
+        f_cdf,
+        f_pdf,
+        f_quantile,
+        f_sf,
+        filliben_medians,
+        gamma_cdf,
+        gamma_p,
+        gamma_pdf,
+        gamma_q,
+        gamma_quantile,
+        gamma_sf,
+        gaussian_kde,
+        gaussian_kde_sorted,
+        hierarchical_clustering,
+        histogram,
+        histogram_entropy_bits,
+        histogram_with_edges,
         intra_cluster_cosine,
         iqr,
+        iqr_distances,
+        iqr_sorted,
+        isolation_forest_scores,
+        jarque_bera,
         js_divergence_bits,
+        kendall_ci,
+        kendall_p_value,
         kendall_tau_b,
+        kendall_tau_b_ranked,
         kl_divergence_bits,
+        knn_entropy_nats,
+        kolmogorov_sf,
+        kruskal_wallis,
+        ks_two_sample,
+        kth_nn_distances,
         l2_norm,
+        l2_norm_f32,
+        ln_beta,
+        ln_gamma,
+        log_histogram,
+        lttb_indices,
         mad,
+        mad_sorted,
+        mann_whitney_u,
         max,
         mean,
         median,
+        median_sorted,
         min,
         minmax_scale,
+        mle_dimension,
         mode,
+        monte_carlo_p_value,
+        near_duplicate_pairs,
+        nearest_distances,
+        normal_cdf,
+        normal_pdf,
+        normal_ppcc_critical,
+        normal_ppcc_sorted,
+        normal_quantile,
+        normal_sf,
+        one_sample_t,
+        one_way_anova,
         pairwise_cosine_stats,
+        partial_correlation_matrix,
+        pearson_ci,
         pearson_correlation,
+        periodogram_period,
+        periodogram_power,
+        permutation_test_mean_diff,
+        poisson,
         population_std_dev,
         population_variance,
         psi_quantile_bins,
         quantile,
+        quantile_sorted,
+        quantile_sorted_by,
         quartiles,
+        quartiles_sorted,
         range,
+        replicate,
+        robust_distances,
+        rolling_residuals,
         sample_std_dev,
         sample_variance,
+        shimazaki_shinomoto_bins,
         silhouette_cosine,
+        silverman_bandwidth,
+        silverman_bandwidth_sorted,
         skewness,
-        spearman_rho,
+        skewness_se,
+        sorted,
         // basic
+        sorted_pairs,
+        sparse_cosine_similarity,
+        sparse_dot,
+        sparse_l2_norm,
+        spearman_ci,
+        spearman_rho,
+        standard_exponential,
+        standard_gamma,
+        standard_normal,
+        student_t_cdf,
+        student_t_pdf,
+        student_t_quantile,
+        student_t_sf,
         sum,
+        t_interval,
+        t_p_value,
+        top_k,
+        total_weight,
+        trimmed_mean,
+        two_nn_dimension,
+        two_sample_t_test,
+        uniform_indices,
+        variance_ratio_test,
+        wasserstein_1,
+        weighted_bin_densities,
+        weighted_excess_kurtosis,
+        weighted_gaussian_kde_sorted,
+        weighted_histogram,
+        weighted_histogram_with_edges,
+        weighted_log_histogram,
+        weighted_mean,
+        weighted_quantile_sorted,
+        weighted_silverman_bandwidth_sorted,
+        weighted_skewness,
+        welch_anova,
+        welch_t,
         // preprocess
         zscores,
     };
@@ -73,7 +257,8 @@ pub mod prelude {
     // Feature-gated RAG re-exports must be a separate item:
     #[cfg(feature = "rag")]
     pub use super::{
-        average_precision, coverage_novelty_redundancy, dcg_at_k, mean_average_precision,
-        mmr_select, mrr, ndcg_at_k, precision_at_k, recall_at_k,
+        average_precision, bleu, coverage_novelty_redundancy, dcg_at_k, lcs_len,
+        mean_average_precision, mmr_select, mmr_select_scored, mmr_select_with, mrr, ndcg_at_k,
+        precision_at_k, recall_at_k, rouge_l, rouge_n, sentence_support, token_f1, tokenize,
     };
 }
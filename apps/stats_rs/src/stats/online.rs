@@ -1,3 +1,6 @@
+use crate::stats::{dot, mean, median};
+use std::collections::VecDeque;
+
 /// Welford's online algorithm.
 #[derive(Clone, Copy, Debug)]
 pub struct OnlineMeanVar {
@@ -26,6 +29,23 @@ impl OnlineMeanVar {
         let delta2 = x - self.mean;
         self.m2 += delta * delta2;
     }
+    /// Fold in another accumulator (Chan et al.), as if its values had been
+    /// pushed here; lets chunks be accumulated independently, e.g. on threads.
+    pub fn merge(&mut self, other: &Self) {
+        if other.n == 0 {
+            return;
+        }
+        if self.n == 0 {
+            *self = *other;
+            return;
+        }
+        let (na, nb) = (self.n as f64, other.n as f64);
+        let n = na + nb;
+        let delta = other.mean - self.mean;
+        self.mean += delta * nb / n;
+        self.m2 += other.m2 + delta * delta * na * nb / n;
+        self.n += other.n;
+    }
     pub fn count(&self) -> u64 {
         self.n
     }
@@ -44,11 +64,327 @@ impl OnlineMeanVar {
     }
 }
 
+/// P² streaming quantile estimate (Jain & Chlamtac, 1985).
+///
+/// Tracks one quantile `p` with five markers in constant memory; exact while
+/// fewer than five values have been seen.
+#[derive(Clone, Debug)]
+pub struct P2Quantile {
+    p: f64,
+    /// Marker heights
+    q: [f64; 5],
+    /// Marker positions (1-based ranks)
+    n: [f64; 5],
+    /// Desired marker positions
+    want: [f64; 5],
+    /// Increments of `want` per observation
+    step: [f64; 5],
+    count: u64,
+}
+
+impl P2Quantile {
+    pub fn new(p: f64) -> Self {
+        assert!((0.0..=1.0).contains(&p), "p must be in [0,1]");
+        Self {
+            p,
+            q: [0.0; 5],
+            n: [1.0, 2.0, 3.0, 4.0, 5.0],
+            want: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            step: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+            count: 0,
+        }
+    }
+
+    pub fn p(&self) -> f64 {
+        self.p
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn push(&mut self, x: f64) {
+        if self.count < 5 {
+            self.q[self.count as usize] = x;
+            self.count += 1;
+            if self.count == 5 {
+                self.q.sort_by(f64::total_cmp);
+            }
+            return;
+        }
+        self.count += 1;
+
+        let k = if x < self.q[0] {
+            self.q[0] = x;
+            0
+        } else if x >= self.q[4] {
+            self.q[4] = x;
+            3
+        } else {
+            (0..4).rfind(|&i| self.q[i] <= x).unwrap_or(0)
+        };
+        for i in k + 1..5 {
+            self.n[i] += 1.0;
+        }
+        for i in 0..5 {
+            self.want[i] += self.step[i];
+        }
+
+        for i in 1..4 {
+            let d = self.want[i] - self.n[i];
+            if (d >= 1.0 && self.n[i + 1] - self.n[i] > 1.0)
+                || (d <= -1.0 && self.n[i - 1] - self.n[i] < -1.0)
+            {
+                let d = d.signum();
+                let h = self.parabolic(i, d);
+                self.q[i] = if self.q[i - 1] < h && h < self.q[i + 1] {
+                    h
+                } else {
+                    self.linear(i, d)
+                };
+                self.n[i] += d;
+            }
+        }
+    }
+
+    fn parabolic(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.q, &self.n);
+        q[i] + d / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, d: f64) -> f64 {
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+        self.q[i] + d * (self.q[j] - self.q[i]) / (self.n[j] - self.n[i])
+    }
+
+    /// Current estimate; `NaN` before the first value.
+    pub fn value(&self) -> f64 {
+        match self.count {
+            0 => f64::NAN,
+            n if n < 5 => crate::stats::quantile(&self.q[..n as usize], self.p),
+            _ => self.q[2],
+        }
+    }
+}
+
+/// Pearson correlation matrix kept up to date as series and observations
+/// are appended.
+///
+/// Holds the series, their means and the pairwise co-moments
+/// `Σ (x_i - x̄_i)(x_j - x̄_j)`. Appending a series computes only its own row
+/// and column (`O(m·n)`); appending an observation updates the co-moments in
+/// place with Welford's rule (`O(m²)`), instead of recomputing the whole
+/// `O(m²·n)` matrix.
+#[derive(Clone, Debug, Default)]
+pub struct OnlineCorrMatrix {
+    series: Vec<Vec<f64>>,
+    means: Vec<f64>,
+    /// Row-major `m×m` co-moments
+    comoments: Vec<f64>,
+}
+
+impl OnlineCorrMatrix {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of series `m`.
+    pub fn size(&self) -> usize {
+        self.series.len()
+    }
+
+    /// Number of observations per series (0 before the first series).
+    pub fn len(&self) -> usize {
+        self.series.first().map_or(0, Vec::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    pub fn series(&self) -> &[Vec<f64>] {
+        &self.series
+    }
+
+    /// Add a series as a new last row/column. It must have [`Self::len`]
+    /// values unless it is the first.
+    pub fn push_series(&mut self, ys: Vec<f64>) {
+        assert!(
+            self.is_empty() || ys.len() == self.len(),
+            "series must have {} values",
+            self.len()
+        );
+        let m = self.size();
+        let my = if ys.is_empty() { 0.0 } else { mean(&ys) };
+        let cy: Vec<f64> = ys.iter().map(|y| y - my).collect();
+        let mut row: Vec<f64> = self
+            .series
+            .iter()
+            .zip(&self.means)
+            .map(|(xs, mx)| xs.iter().zip(&cy).map(|(x, c)| (x - mx) * c).sum())
+            .collect();
+        row.push(dot(&cy, &cy));
+
+        let mut grown = vec![0.0; (m + 1) * (m + 1)];
+        for i in 0..m {
+            grown[i * (m + 1)..i * (m + 1) + m]
+                .copy_from_slice(&self.comoments[i * m..(i + 1) * m]);
+            grown[i * (m + 1) + m] = row[i];
+        }
+        grown[m * (m + 1)..].copy_from_slice(&row);
+        self.comoments = grown;
+        self.means.push(my);
+        self.series.push(ys);
+    }
+
+    /// Add one observation to every series; `row` holds one value per series.
+    pub fn push_row(&mut self, row: &[f64]) {
+        assert_eq!(row.len(), self.size(), "row must have one value per series");
+        let m = self.size();
+        let n = (self.len() + 1) as f64;
+        let before: Vec<f64> = row.iter().zip(&self.means).map(|(x, mx)| x - mx).collect();
+        for (mx, d) in self.means.iter_mut().zip(&before) {
+            *mx += d / n;
+        }
+        for (d, cs) in before.iter().zip(self.comoments.chunks_exact_mut(m)) {
+            for ((c, x), mx) in cs.iter_mut().zip(row).zip(&self.means) {
+                *c += d * (x - mx);
+            }
+        }
+        for (xs, &x) in self.series.iter_mut().zip(row) {
+            xs.push(x);
+        }
+    }
+
+    /// Row-major `m×m` correlations, with undefined pairs (constant series,
+    /// fewer than two observations) as `NaN` and a unit diagonal.
+    pub fn matrix(&self) -> Vec<f64> {
+        let m = self.size();
+        let c = &self.comoments;
+        let mut mat = vec![0.0; m * m];
+        for i in 0..m {
+            mat[i * m + i] = 1.0;
+            for j in (i + 1)..m {
+                let r = c[i * m + j] / (c[i * m + i] * c[j * m + j]).sqrt();
+                let r = if r.is_finite() {
+                    r.clamp(-1.0, 1.0)
+                } else {
+                    f64::NAN
+                };
+                mat[i * m + j] = r;
+                mat[j * m + i] = r;
+            }
+        }
+        mat
+    }
+}
+
+/// Values an online outlier detector sees before it starts scoring.
+pub const OUTLIER_WARMUP: u64 = 10;
+
+/// Robust z-score of each new value against a sliding window of the values
+/// before it: `|x − median| / (1.4826 · MAD)`, or `1.2533 · mean |x − median|`
+/// as the scale when more than half the window ties at the median. A spike
+/// barely moves the median or MAD, so it cannot hide itself.
+#[derive(Clone, Debug)]
+pub struct RollingRobustZ {
+    window: VecDeque<f64>,
+    size: usize,
+    seen: u64,
+}
+
+impl RollingRobustZ {
+    /// Score against the last `size` values (at least 2).
+    pub fn new(size: usize) -> Self {
+        let size = size.max(2);
+        Self {
+            window: VecDeque::with_capacity(size),
+            size,
+            seen: 0,
+        }
+    }
+
+    /// Score `x`, then slide it into the window. `NaN` during the first
+    /// [`OUTLIER_WARMUP`] values; `∞` for any change from a constant window.
+    pub fn push(&mut self, x: f64) -> f64 {
+        let score = match self.seen < OUTLIER_WARMUP || self.window.len() < 2 {
+            true => f64::NAN,
+            false => {
+                let xs: Vec<f64> = self.window.iter().copied().collect();
+                let med = median(&xs);
+                let devs: Vec<f64> = xs.iter().map(|&v| (v - med).abs()).collect();
+                let scale = match 1.4826 * median(&devs) {
+                    0.0 => 1.253_314 * mean(&devs),
+                    s => s,
+                };
+                match (x - med).abs() {
+                    0.0 => 0.0,
+                    d if scale > 0.0 => d / scale,
+                    _ => f64::INFINITY,
+                }
+            }
+        };
+        if self.window.len() == self.size {
+            self.window.pop_front();
+        }
+        self.window.push_back(x);
+        self.seen += 1;
+        score
+    }
+}
+
+/// Residual of each new value from an exponentially weighted mean, in
+/// exponentially weighted standard deviations of the values before it.
+/// Adapts to drifting levels at a rate set by `alpha`; constant memory.
+#[derive(Clone, Copy, Debug)]
+pub struct EwmaResidual {
+    alpha: f64,
+    mean: f64,
+    var: f64,
+    seen: u64,
+}
+
+impl EwmaResidual {
+    /// `alpha` in `(0, 1]` is the weight of the newest value.
+    pub fn new(alpha: f64) -> Self {
+        Self {
+            alpha,
+            mean: 0.0,
+            var: 0.0,
+            seen: 0,
+        }
+    }
+
+    /// Score `x`, then fold it into the mean and variance. `NaN` during the
+    /// first [`OUTLIER_WARMUP`] values; `∞` for any change after a constant
+    /// run.
+    pub fn push(&mut self, x: f64) -> f64 {
+        if self.seen == 0 {
+            self.mean = x;
+            self.seen = 1;
+            return f64::NAN;
+        }
+        let d = x - self.mean;
+        let score = match d.abs() {
+            _ if self.seen < OUTLIER_WARMUP => f64::NAN,
+            0.0 => 0.0,
+            r if self.var > 0.0 => r / self.var.sqrt(),
+            _ => f64::INFINITY,
+        };
+        self.mean += self.alpha * d;
+        self.var = (1.0 - self.alpha) * (self.var + self.alpha * d * d);
+        self.seen += 1;
+        score
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::approx;
-    use crate::stats::utils::EPS_TIGHT;
+    use crate::stats::{pearson_correlation, utils::EPS_TIGHT};
 
     #[test]
     fn empty_state_nan_variance_and_std() {
@@ -106,6 +442,26 @@ mod tests {
         approx!(a.sample_std(), b.sample_std(), 1e-10);
     }
 
+    #[test]
+    fn merged_halves_equal_one_pass() {
+        let xs = [3.0, -1.0, 4.0, 1.0, -5.0, 9.0, 2.0, 6.0];
+        let mut all = OnlineMeanVar::new();
+        xs.iter().for_each(|&x| all.push(x));
+        let (mut a, mut b) = (OnlineMeanVar::new(), OnlineMeanVar::new());
+        xs[..3].iter().for_each(|&x| a.push(x));
+        xs[3..].iter().for_each(|&x| b.push(x));
+        a.merge(&b);
+        assert_eq!(a.count(), all.count());
+        approx!(a.mean(), all.mean(), EPS_TIGHT);
+        approx!(a.sample_variance(), all.sample_variance(), 1e-10);
+
+        let mut empty = OnlineMeanVar::new();
+        empty.merge(&all);
+        approx!(empty.mean(), all.mean(), EPS_TIGHT);
+        all.merge(&OnlineMeanVar::new());
+        assert_eq!(all.count(), 8);
+    }
+
     #[test]
     fn streaming_in_chunks_equals_all_at_once() {
         let xs = [1.0, 2.0, 3.0, 4.0, 5.0];
@@ -153,4 +509,85 @@ mod tests {
         approx!(omv.mean(), 2.5, EPS_TIGHT);
         approx!(omv.sample_variance(), 1.6666666666666667, EPS_TIGHT);
     }
+
+    #[test]
+    fn p2_tracks_quantiles_of_a_long_stream() {
+        // 0..10_000 in a scrambled order (7919 is coprime with 10_000)
+        let xs: Vec<f64> = (0..10_000u64)
+            .map(|i| ((i * 7919) % 10_000) as f64)
+            .collect();
+        for p in [0.05, 0.5, 0.95] {
+            let mut q = P2Quantile::new(p);
+            xs.iter().for_each(|&x| q.push(x));
+            assert_eq!(q.count(), 10_000);
+            let exact = p * 9_999.0;
+            assert!((q.value() - exact).abs() < 100.0, "p={p}: {}", q.value());
+        }
+
+        // Exact below five values
+        let mut q = P2Quantile::new(0.5);
+        assert!(q.value().is_nan());
+        [3.0, 1.0, 2.0].iter().for_each(|&x| q.push(x));
+        approx!(q.value(), 2.0, EPS_TIGHT);
+    }
+
+    #[test]
+    fn incremental_corr_matrix_matches_batch_correlations() {
+        let a = [1.0, 2.0, 3.0, 4.0, 5.5, 2.0, 7.0];
+        let b = [2.0, 1.0, 4.0, 3.0, 6.0, 2.0, 1.0];
+        let c = [9.0, 7.0, 7.0, 3.0, 1.0, 0.5, 2.0];
+
+        // Two series over the first four rows, the rest appended row by row,
+        // then a third series
+        let mut inc = OnlineCorrMatrix::new();
+        inc.push_series(a[..4].to_vec());
+        inc.push_series(b[..4].to_vec());
+        for t in 4..7 {
+            inc.push_row(&[a[t], b[t]]);
+        }
+        inc.push_series(c.to_vec());
+        assert_eq!((inc.size(), inc.len()), (3, 7));
+
+        let all = [&a[..], &b[..], &c[..]];
+        let mat = inc.matrix();
+        for i in 0..3 {
+            approx!(mat[i * 3 + i], 1.0, EPS_TIGHT);
+            for j in (i + 1)..3 {
+                approx!(mat[i * 3 + j], pearson_correlation(all[i], all[j]), 1e-12);
+                assert_eq!(mat[i * 3 + j], mat[j * 3 + i]);
+            }
+        }
+
+        // A constant series correlates with nothing
+        inc.push_series(vec![4.0; 7]);
+        assert!(inc.matrix()[3].is_nan());
+    }
+
+    #[test]
+    fn online_detectors_flag_a_spike_after_warmup() {
+        let calm = |i: usize| 10.0 + [0.3, -0.2, 0.1, -0.4, 0.2][i % 5];
+
+        let mut z = RollingRobustZ::new(20);
+        let mut e = EwmaResidual::new(0.2);
+        for i in 0..(OUTLIER_WARMUP as usize) {
+            assert!(z.push(calm(i)).is_nan());
+            assert!(e.push(calm(i)).is_nan());
+        }
+        for i in 0..30 {
+            assert!(z.push(calm(i)) < 3.5);
+            assert!(e.push(calm(i)) < 3.5);
+        }
+        assert!(z.push(25.0) > 10.0);
+        assert!(e.push(25.0) > 10.0);
+        // the spike stays in the robust window without masking the next one
+        z.push(calm(0));
+        assert!(z.push(25.0) > 10.0);
+
+        let mut flat = RollingRobustZ::new(5);
+        (0..OUTLIER_WARMUP).for_each(|_| {
+            flat.push(1.0);
+        });
+        assert_eq!(flat.push(1.0), 0.0);
+        assert_eq!(flat.push(2.0), f64::INFINITY);
+    }
 }
@@ -1,37 +1,84 @@
+use crate::stats::prelude::*;
+use std::collections::HashSet;
+
+/// One step of a greedy MMR selection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MmrPick {
+    /// Index of the chosen candidate.
+    pub index: usize,
+    /// Cosine similarity to the query.
+    pub relevance: f64,
+    /// Max cosine similarity to previously chosen candidates (0 for the first pick).
+    pub redundancy: f64,
+    /// MMR objective `lambda * relevance - (1 - lambda) * redundancy` at selection time.
+    pub score: f64,
+}
+
 /// Greedy MMR selection (cosine sim). Returns indices of chosen docs.
 pub fn mmr_select(cands: &[Vec<f64>], query: &[f64], lambda: f64, k: usize) -> Vec<usize> {
+    mmr_select_scored(cands, query, lambda, k)
+        .into_iter()
+        .map(|p| p.index)
+        .collect()
+}
+
+/// Greedy MMR selection returning per-pick relevance/redundancy scores.
+///
+/// Zero vectors (undefined cosine) count as similarity 0. Ties go to the lowest index,
+/// so the result is deterministic.
+pub fn mmr_select_scored(cands: &[Vec<f64>], query: &[f64], lambda: f64, k: usize) -> Vec<MmrPick> {
+    let sim = |a: &[f64], b: &[f64]| {
+        let s = cosine_similarity(a, b);
+        if s.is_nan() { 0.0 } else { s }
+    };
+    let sim_q: Vec<f64> = cands.iter().map(|v| sim(v, query)).collect();
+    mmr_select_with(&sim_q, |i, j| sim(&cands[i], &cands[j]), lambda, k)
+}
+
+/// Greedy MMR over precomputed query relevances and a candidate-candidate similarity.
+///
+/// Representation-agnostic core of [`mmr_select_scored`] (used for sparse vectors).
+pub fn mmr_select_with<F>(sim_q: &[f64], sim: F, lambda: f64, k: usize) -> Vec<MmrPick>
+where
+    F: Fn(usize, usize) -> f64,
+{
     assert!((0.0..=1.0).contains(&lambda));
-    let n = cands.len();
+    let n = sim_q.len();
     if n == 0 || k == 0 {
         return vec![];
     }
 
-    let mut selected = Vec::<usize>::new();
-    let mut remaining: HashSet<usize> = (0..n).collect();
+    // Running max similarity of each candidate to the selected set.
+    let mut max_sim_to_s = vec![f64::NEG_INFINITY; n];
+    let mut taken = vec![false; n];
+    let mut picks = Vec::<MmrPick>::with_capacity(k.min(n));
 
-    let sim_q: Vec<f64> = cands.iter().map(|v| cosine_similarity(v, query)).collect();
-
-    while selected.len() < k && !remaining.is_empty() {
-        let mut best = None::<(usize, f64)>;
-        for &i in &remaining {
-            let max_sim_to_s = if selected.is_empty() {
+    while picks.len() < k.min(n) {
+        let mut best = None::<(usize, f64, f64)>;
+        for i in (0..n).filter(|&i| !taken[i]) {
+            let red = if picks.is_empty() {
                 0.0
             } else {
-                selected
-                    .iter()
-                    .map(|&j| cosine_similarity(&cands[i], &cands[j]))
-                    .fold(f64::NEG_INFINITY, f64::max)
+                max_sim_to_s[i]
             };
-            let score = lambda * sim_q[i] - (1.0 - lambda) * max_sim_to_s;
-            if best.map_or(true, |(_, b)| score > b) {
-                best = Some((i, score));
+            let score = lambda * sim_q[i] - (1.0 - lambda) * red;
+            if best.is_none_or(|(_, b, _)| score > b) {
+                best = Some((i, score, red));
             }
         }
-        let (choice, _) = best.unwrap();
-        selected.push(choice);
-        remaining.remove(&choice);
+        let (choice, score, redundancy) = best.unwrap();
+        taken[choice] = true;
+        picks.push(MmrPick {
+            index: choice,
+            relevance: sim_q[choice],
+            redundancy,
+            score,
+        });
+        for i in (0..n).filter(|&i| !taken[i]) {
+            max_sim_to_s[i] = max_sim_to_s[i].max(sim(i, choice));
+        }
     }
-    selected
+    picks
 }
 
 /// Coverage = unique sources / total sources present in top-k;
@@ -67,6 +114,49 @@ pub fn coverage_novelty_redundancy(topk: &[Vec<f64>], source_ids: &[usize]) -> (
     (coverage, novelty, redundancy)
 }
 
+/// How well one answer sentence is supported by the retrieved context.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SentenceSupport {
+    /// Highest cosine similarity to any context chunk.
+    pub max: f64,
+    /// Mean cosine similarity over all context chunks.
+    pub mean: f64,
+    /// Index of the best-supporting context chunk.
+    pub best_context: usize,
+}
+
+/// Cosine support of each answer-sentence embedding against the context embeddings.
+///
+/// Zero vectors (undefined cosine) count as similarity 0. Returns an empty vec when
+/// there is no context.
+pub fn sentence_support(sentences: &[Vec<f64>], context: &[Vec<f64>]) -> Vec<SentenceSupport> {
+    if context.is_empty() {
+        return vec![];
+    }
+    sentences
+        .iter()
+        .map(|s| {
+            let sims: Vec<f64> = context
+                .iter()
+                .map(|c| {
+                    let v = cosine_similarity(s, c);
+                    if v.is_nan() { 0.0 } else { v }
+                })
+                .collect();
+            let (best_context, &max) = sims
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1).then(b.0.cmp(&a.0)))
+                .unwrap();
+            SentenceSupport {
+                max,
+                mean: mean(&sims),
+                best_context,
+            }
+        })
+        .collect()
+}
+
 /// Precision@k: retrieved is a list of ids in rank order; relevant is a set (or sorted vec).
 pub fn precision_at_k(retrieved: &[usize], relevant: &[usize], k: usize) -> f64 {
     if k == 0 || retrieved.is_empty() {
@@ -168,11 +258,11 @@ pub fn mean_average_precision(
 }
 
 #[cfg(test)]
-mod tests {
+mod smoke_tests {
     use super::*;
     use crate::approx;
-    use crate::stats::utils::{EPS, EPS_TIGHT};
-    use std::collections::HashSet;
+    use crate::stats::cluster::hubness_k_occurrence;
+    use crate::stats::utils::EPS;
 
     #[test]
     fn retrieval_metrics_and_mmr() {
@@ -191,8 +281,10 @@ mod tests {
         let expected_dcg = 3.0 / 1.0 + 2.0 / (3.0_f64).log2() + 1.0 / 2.0;
         approx!(dcg, expected_dcg, 1e-12);
 
+        // top-3 = [3, 1, 2] → gains [0, 1, 1] vs ideal [1, 1, 0]
         let nd = ndcg_at_k(&retrieved[..3], &[1usize, 2], 3);
-        approx!(nd, 1.0, EPS);
+        let expected_nd = (1.0 / 3.0_f64.log2() + 0.5) / (1.0 + 1.0 / 3.0_f64.log2());
+        approx!(nd, expected_nd, EPS);
 
         // AP & MAP
         let ap = {
@@ -207,12 +299,13 @@ mod tests {
             [2usize].into_iter().collect::<HashSet<_>>(),
         ];
         let map = mean_average_precision(&retrieved_lists, &relevant_sets);
-        assert!(map > 0.5 && map < 1.0);
+        // AP = 7/12 for the first list, 1/3 for the second (hit at rank 3)
+        approx!(map, (7.0 / 12.0 + 1.0 / 3.0) / 2.0, 1e-12);
 
         // MMR
         let q = vec![1.0, 0.0];
         let cands = vec![vec![1.0, 0.0], vec![0.9, 0.1], vec![0.0, 1.0]];
-        let sel = mmr_select(&cands, &q, 0.7, 2);
+        let sel = mmr_select(&cands, &q, 0.3, 2);
         assert_eq!(sel.len(), 2);
         assert!(sel.contains(&0));
         assert!(sel.contains(&2));
@@ -263,8 +356,10 @@ mod tests {
         let expected_dcg = 3.0 / 1.0 + 2.0 / (3.0_f64).log2() + 1.0 / 2.0;
         approx!(dcg, expected_dcg, 1e-12);
 
+        // top-3 = [3, 1, 2] → gains [0, 1, 1] vs ideal [1, 1, 0]
         let nd = ndcg_at_k(&retrieved[..3], &[1usize, 2], 3);
-        approx!(nd, 1.0, EPS);
+        let expected_nd = (1.0 / 3.0_f64.log2() + 0.5) / (1.0 + 1.0 / 3.0_f64.log2());
+        approx!(nd, expected_nd, EPS);
 
         // AP & MAP
         let ap = {
@@ -279,12 +374,13 @@ mod tests {
             [2usize].into_iter().collect::<HashSet<_>>(),
         ];
         let map = mean_average_precision(&retrieved_lists, &relevant_sets);
-        assert!(map > 0.5 && map < 1.0);
+        // AP = 7/12 for the first list, 1/3 for the second (hit at rank 3)
+        approx!(map, (7.0 / 12.0 + 1.0 / 3.0) / 2.0, 1e-12);
 
         // MMR greedy selection
         let q = vec![1.0, 0.0];
         let cands = vec![vec![1.0, 0.0], vec![0.9, 0.1], vec![0.0, 1.0]];
-        let sel = mmr_select(&cands, &q, 0.7, 2);
+        let sel = mmr_select(&cands, &q, 0.3, 2);
         assert_eq!(sel.len(), 2);
         assert!(sel.contains(&0)); // best to query
         assert!(sel.contains(&2)); // diversified pick
@@ -312,7 +408,7 @@ mod tests {
     fn dcg_ndcg_edges() {
         // dcg truncates at k
         let gains = vec![1.0, 0.0, 0.0];
-        approx!(dcg_at_k(&gains, 1), 1.0 / (1.0_f64).log2(), 1e-12); // 1 / log2(1+1) = 1
+        approx!(dcg_at_k(&gains, 1), 1.0 / (2.0_f64).log2(), 1e-12); // 1 / log2(1+1) = 1
         approx!(dcg_at_k(&gains, 10), dcg_at_k(&gains, 3), 1e-12); // no extra terms
 
         // ndcg: no relevant → IDCG=0 → return 0
@@ -357,6 +453,49 @@ mod tests {
         assert_eq!(sel_diverse[1], 2); // farthest from the first
     }
 
+    #[test]
+    fn mmr_scored_reports_relevance_and_redundancy() {
+        let q = vec![1.0, 0.0];
+        let cands = vec![vec![1.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0]];
+        let picks = mmr_select_scored(&cands, &q, 0.3, 3);
+        assert_eq!(
+            picks.iter().map(|p| p.index).collect::<Vec<_>>(),
+            vec![0, 2, 1]
+        );
+
+        // first pick: pure relevance, no redundancy
+        approx!(picks[0].relevance, 1.0, EPS_TIGHT);
+        approx!(picks[0].redundancy, 0.0, EPS_TIGHT);
+        approx!(picks[0].score, 0.3, EPS_TIGHT);
+        // orthogonal pick: irrelevant but novel
+        approx!(picks[1].relevance, 0.0, EPS_TIGHT);
+        approx!(picks[1].redundancy, 0.0, EPS_TIGHT);
+        // duplicate of the first pick comes last, fully redundant
+        approx!(picks[2].redundancy, 1.0, EPS_TIGHT);
+        approx!(picks[2].score, 0.3 - 0.7, EPS_TIGHT);
+
+        // zero vectors have undefined cosine; treated as 0 rather than poisoning the argmax
+        let with_zero = vec![vec![0.0, 0.0], vec![1.0, 0.0]];
+        assert_eq!(mmr_select(&with_zero, &q, 0.5, 2), vec![1, 0]);
+    }
+
+    #[test]
+    fn sentence_support_max_mean_and_argmax() {
+        let ctx = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.0, 0.0]];
+        let sents = vec![vec![0.0, 2.0], vec![1.0, 1.0]];
+        let s = sentence_support(&sents, &ctx);
+
+        approx!(s[0].max, 1.0, EPS_TIGHT);
+        assert_eq!(s[0].best_context, 1);
+        approx!(s[0].mean, 1.0 / 3.0, EPS_TIGHT); // zero-vector chunk counts as 0
+
+        // equidistant: ties go to the lowest context index
+        approx!(s[1].max, std::f64::consts::FRAC_1_SQRT_2, EPS_TIGHT);
+        assert_eq!(s[1].best_context, 0);
+
+        assert!(sentence_support(&sents, &[]).is_empty());
+    }
+
     #[test]
     fn coverage_novelty_redundancy_edges() {
         // empty
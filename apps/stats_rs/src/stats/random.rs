@@ -0,0 +1,224 @@
+//! Seeded draws from standard distributions.
+//!
+//! Every sampler takes the caller's generator, so a seeded `StdRng` gives the
+//! same values on every run and platform. Continuous draws invert or
+//! transform uniforms with the same special functions the tests use; the
+//! discrete ones are exact at any size.
+
+use crate::stats::prelude::*;
+use rand::Rng;
+
+/// A distribution to draw from, with its parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampler {
+    Normal {
+        mean: f64,
+        std_dev: f64,
+    },
+    /// On `[low, high)`
+    Uniform {
+        low: f64,
+        high: f64,
+    },
+    Exponential {
+        rate: f64,
+    },
+    Gamma {
+        shape: f64,
+        scale: f64,
+    },
+    Binomial {
+        trials: u64,
+        p: f64,
+    },
+    Poisson {
+        lambda: f64,
+    },
+}
+
+impl Sampler {
+    /// One draw.
+    pub fn sample(&self, rng: &mut impl Rng) -> f64 {
+        match *self {
+            Sampler::Normal { mean, std_dev } => mean + std_dev * standard_normal(rng),
+            Sampler::Uniform { low, high } => low + (high - low) * rng.random::<f64>(),
+            Sampler::Exponential { rate } => standard_exponential(rng) / rate,
+            Sampler::Gamma { shape, scale } => scale * standard_gamma(shape, rng),
+            Sampler::Binomial { trials, p } => binomial(trials, p, rng) as f64,
+            Sampler::Poisson { lambda } => poisson(lambda, rng) as f64,
+        }
+    }
+
+    /// Mean and variance of the distribution.
+    pub fn moments(&self) -> (f64, f64) {
+        match *self {
+            Sampler::Normal { mean, std_dev } => (mean, std_dev * std_dev),
+            Sampler::Uniform { low, high } => ((low + high) / 2.0, (high - low).powi(2) / 12.0),
+            Sampler::Exponential { rate } => (1.0 / rate, 1.0 / (rate * rate)),
+            Sampler::Gamma { shape, scale } => (shape * scale, shape * scale * scale),
+            Sampler::Binomial { trials, p } => {
+                let n = trials as f64;
+                (n * p, n * p * (1.0 - p))
+            }
+            Sampler::Poisson { lambda } => (lambda, lambda),
+        }
+    }
+}
+
+/// Uniform on the open interval `(0, 1)`.
+fn open_unit(rng: &mut impl Rng) -> f64 {
+    loop {
+        let u: f64 = rng.random();
+        if u > 0.0 {
+            return u;
+        }
+    }
+}
+
+/// Standard normal draw by inversion, `Φ⁻¹(U)`.
+pub fn standard_normal(rng: &mut impl Rng) -> f64 {
+    normal_quantile(open_unit(rng))
+}
+
+/// Exponential draw with rate 1, `−ln U`.
+pub fn standard_exponential(rng: &mut impl Rng) -> f64 {
+    -open_unit(rng).ln()
+}
+
+/// Gamma draw with scale 1 and `shape > 0` (Marsaglia–Tsang; shapes below 1
+/// are boosted with `Γ(k) = Γ(k + 1) · U^{1/k}`).
+pub fn standard_gamma(shape: f64, rng: &mut impl Rng) -> f64 {
+    if shape < 1.0 {
+        return standard_gamma(shape + 1.0, rng) * open_unit(rng).powf(1.0 / shape);
+    }
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let z = standard_normal(rng);
+        let v = (1.0 + c * z).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u = open_unit(rng);
+        if u.ln() < 0.5 * z * z + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+/// Trials left to the direct Bernoulli count in [`binomial`].
+const BINOMIAL_DIRECT: u64 = 64;
+
+/// Binomial draw: successes in `trials` trials of probability `p`.
+///
+/// Large counts split on a Beta-distributed order statistic of the uniforms
+/// behind the trials (Knuth, TAOCP 3.4.1), which keeps the draw exact in
+/// `O(log trials)` gamma draws; the last few trials are counted directly.
+pub fn binomial(trials: u64, p: f64, rng: &mut impl Rng) -> u64 {
+    let (mut n, mut p, mut k) = (trials, p, 0);
+    while n > BINOMIAL_DIRECT {
+        // the a-th smallest of n uniforms is Beta(a, n + 1 − a)
+        let a = 1 + n / 2;
+        let b = n + 1 - a;
+        let ga = standard_gamma(a as f64, rng);
+        let x = ga / (ga + standard_gamma(b as f64, rng));
+        if x >= p {
+            n = a - 1;
+            p /= x;
+        } else {
+            k += a;
+            n = b - 1;
+            p = (p - x) / (1.0 - x);
+        }
+    }
+    k + (0..n).filter(|_| rng.random::<f64>() < p).count() as u64
+}
+
+/// Mean below which [`poisson`] multiplies uniforms directly.
+const POISSON_DIRECT: f64 = 16.0;
+
+/// Poisson draw with mean `lambda`.
+///
+/// Large means are reduced by the arrival time of a Gamma-distributed
+/// number of events (Ahrens–Dieter, as in Knuth TAOCP 3.4.1), finishing with
+/// a binomial split when that overshoots; small ones count uniforms until
+/// their product drops below `e^{−λ}`.
+pub fn poisson(lambda: f64, rng: &mut impl Rng) -> u64 {
+    let (mut lambda, mut k) = (lambda, 0);
+    while lambda > POISSON_DIRECT {
+        let m = (0.875 * lambda).floor() as u64;
+        let x = standard_gamma(m as f64, rng);
+        if x >= lambda {
+            return k + binomial(m - 1, lambda / x, rng);
+        }
+        k += m;
+        lambda -= x;
+    }
+    let limit = (-lambda).exp();
+    let mut product = rng.random::<f64>();
+    while product > limit {
+        k += 1;
+        product *= rng.random::<f64>();
+    }
+    k
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    /// Sample mean and variance of 20 000 draws, against the exact moments
+    /// within five standard errors.
+    fn check(s: Sampler) {
+        let mut rng = StdRng::seed_from_u64(42);
+        let xs: Vec<f64> = (0..20_000).map(|_| s.sample(&mut rng)).collect();
+        let (mu, var) = s.moments();
+        let m = mean(&xs);
+        let se = (var / xs.len() as f64).sqrt();
+        assert!((m - mu).abs() < 5.0 * se, "{s:?}: mean {m} vs {mu}");
+        let v = sample_variance(&xs, m);
+        assert!((v / var - 1.0).abs() < 0.06, "{s:?}: variance {v} vs {var}");
+    }
+
+    #[test]
+    fn draws_match_the_moments() {
+        check(Sampler::Normal {
+            mean: 3.0,
+            std_dev: 2.0,
+        });
+        check(Sampler::Uniform {
+            low: -1.0,
+            high: 5.0,
+        });
+        check(Sampler::Exponential { rate: 0.5 });
+        check(Sampler::Gamma {
+            shape: 0.4,
+            scale: 3.0,
+        });
+        check(Sampler::Gamma {
+            shape: 7.5,
+            scale: 1.0,
+        });
+        check(Sampler::Binomial { trials: 20, p: 0.3 });
+        check(Sampler::Binomial {
+            trials: 1_000_000,
+            p: 0.999,
+        });
+        check(Sampler::Poisson { lambda: 3.0 });
+        check(Sampler::Poisson { lambda: 2_500.0 });
+    }
+
+    #[test]
+    fn discrete_edges_and_reproducibility() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(binomial(1_000, 0.0, &mut rng), 0);
+        assert_eq!(binomial(1_000, 1.0, &mut rng), 1_000);
+        assert_eq!(binomial(0, 0.5, &mut rng), 0);
+        let draw = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..5).map(|_| poisson(40.0, &mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(9), draw(9));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::stats::prelude::*;
+use rand::Rng;
+
+/// Percentile bootstrap confidence interval for `stat` over `xs`.
+///
+/// Draws `resamples` samples of `xs.len()` with replacement and returns the
+/// `(1 - confidence) / 2` and `(1 + confidence) / 2` quantiles of the
+/// resampled statistic. `progress` is called with the number of resamples done
+/// so far and can stop the loop early (see [`Checkpoint`]). Empty input or
+/// zero resamples give `(NaN, NaN)`.
+pub fn bootstrap_ci(
+    xs: &[f64],
+    stat: impl Fn(&[f64]) -> f64,
+    resamples: usize,
+    confidence: f64,
+    rng: &mut impl Rng,
+    progress: impl Checkpoint,
+) -> (f64, f64) {
+    assert!(
+        (0.0..1.0).contains(&confidence),
+        "confidence must be in [0,1)"
+    );
+    let n = xs.len();
+    if n == 0 || resamples == 0 {
+        return (f64::NAN, f64::NAN);
+    }
+    let mut draw = vec![0.0; n];
+    let mut stats = replicate(resamples, rng, progress, |rng| {
+        draw.iter_mut()
+            .for_each(|d| *d = xs[rng.random_range(0..n)]);
+        stat(&draw)
+    });
+    stats.retain(|s| !s.is_nan());
+    let alpha = (1.0 - confidence) / 2.0;
+    let stats = sorted(&stats);
+    (
+        quantile_sorted(&stats, alpha),
+        quantile_sorted(&stats, 1.0 - alpha),
+    )
+}
+
+/// Bootstrap standard error of `stat` over `xs`: the sample standard
+/// deviation of the statistic over `resamples` samples of `xs.len()` drawn
+/// with replacement. Resamples where `stat` is undefined are skipped;
+/// `NaN` with fewer than two left.
+pub fn bootstrap_se(
+    xs: &[f64],
+    stat: impl Fn(&[f64]) -> f64,
+    resamples: usize,
+    rng: &mut impl Rng,
+    progress: impl Checkpoint,
+) -> f64 {
+    let n = xs.len();
+    if n == 0 {
+        return f64::NAN;
+    }
+    let mut draw = vec![0.0; n];
+    let mut stats = replicate(resamples, rng, progress, |rng| {
+        draw.iter_mut()
+            .for_each(|d| *d = xs[rng.random_range(0..n)]);
+        stat(&draw)
+    });
+    stats.retain(|s| !s.is_nan());
+    if stats.len() < 2 {
+        return f64::NAN;
+    }
+    sample_std_dev(&stats, mean(&stats))
+}
+
+/// Two-sided permutation test for a difference in means.
+///
+/// Returns `(mean(x) - mean(y), p)` where `p = (k + 1) / (permutations + 1)`
+/// and `k` counts relabelings whose absolute difference is at least the
+/// observed one. `progress` is called with the permutations done so far and
+/// can stop the loop early.
+pub fn permutation_test_mean_diff(
+    x: &[f64],
+    y: &[f64],
+    permutations: usize,
+    rng: &mut impl Rng,
+    progress: impl Checkpoint,
+) -> (f64, f64) {
+    if x.is_empty() || y.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    let observed = mean(x) - mean(y);
+    let mut pooled: Vec<f64> = x.iter().chain(y).copied().collect();
+    let total = sum(&pooled);
+    let (nx, ny) = (x.len() as f64, y.len() as f64);
+    // Tolerance so relabelings tied with the observed split count as extreme
+    let threshold = observed.abs() * (1.0 - 1e-12);
+    let diffs = replicate(permutations, rng, progress, |rng| {
+        // Partial Fisher–Yates: the first `x.len()` slots become the new `x`
+        for i in 0..x.len() {
+            let j = rng.random_range(i..pooled.len());
+            pooled.swap(i, j);
+        }
+        let sx = sum(&pooled[..x.len()]);
+        sx / nx - (total - sx) / ny
+    });
+    let extreme = diffs.iter().filter(|d| d.abs() >= threshold).count();
+    (observed, (extreme + 1) as f64 / (permutations + 1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::cell::Cell;
+
+    #[test]
+    fn bootstrap_interval_covers_the_mean() {
+        let xs: Vec<f64> = (1..=50).map(f64::from).collect();
+        let mut rng = StdRng::seed_from_u64(1);
+        let done = Cell::new(0);
+        let (lo, hi) = bootstrap_ci(&xs, mean, 500, 0.95, &mut rng, |b| done.set(b));
+        assert_eq!(done.get(), 500);
+        assert!(lo < 25.5 && 25.5 < hi, "({lo}, {hi})");
+        // ~ mean ± 1.96·s/√n with s ≈ 14.6
+        assert!(hi - lo > 5.0 && hi - lo < 10.0, "({lo}, {hi})");
+
+        let again = bootstrap_ci(&xs, mean, 500, 0.95, &mut StdRng::seed_from_u64(1), |_| {});
+        assert_eq!(again, (lo, hi));
+        assert!(
+            bootstrap_ci(&[], mean, 10, 0.9, &mut rng, |_| {})
+                .0
+                .is_nan()
+        );
+    }
+
+    #[test]
+    fn bootstrap_se_tracks_the_standard_error_of_the_mean() {
+        let xs: Vec<f64> = (1..=50).map(f64::from).collect();
+        let se = bootstrap_se(&xs, mean, 2000, &mut StdRng::seed_from_u64(1), |_| {});
+        // s/√n ≈ 14.58/7.07 ≈ 2.06
+        assert!((se - 2.06).abs() < 0.2, "{se}");
+        let again = bootstrap_se(&xs, mean, 2000, &mut StdRng::seed_from_u64(1), |_| {});
+        assert_eq!(again, se);
+        assert!(bootstrap_se(&xs, mean, 1, &mut StdRng::seed_from_u64(1), |_| {}).is_nan());
+    }
+
+    #[test]
+    fn permutation_test_separates_shifted_samples() {
+        let x: Vec<f64> = (0..30).map(f64::from).collect();
+        let shifted: Vec<f64> = x.iter().map(|v| v + 40.0).collect();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let (d, p) = permutation_test_mean_diff(&x, &shifted, 999, &mut rng, |_| {});
+        assert_eq!(d, -40.0);
+        assert_eq!(p, 1.0 / 1000.0);
+
+        // Identical samples: every relabeling is at least as extreme as 0
+        let (d, p) = permutation_test_mean_diff(&x, &x, 199, &mut rng, |_| {});
+        assert_eq!(d, 0.0);
+        assert_eq!(p, 1.0);
+    }
+
+    #[test]
+    fn cancelled_flag_stops_resampling_at_the_next_checkpoint() {
+        let xs: Vec<f64> = (1..=50).map(f64::from).collect();
+        let flag = CancelFlag::default();
+        let done = Cell::new(0);
+        let stop_at_10 = |b| {
+            done.set(b);
+            if b == 10 {
+                flag.cancel();
+            }
+        };
+        let mut rng = StdRng::seed_from_u64(1);
+        bootstrap_ci(&xs, mean, 500, 0.95, &mut rng, flag.guard(stop_at_10));
+        assert_eq!(done.get(), 10);
+
+        done.set(0);
+        permutation_test_mean_diff(&xs, &xs, 99, &mut rng, flag.guard(|b| done.set(b)));
+        assert_eq!(done.get(), 0);
+    }
+}
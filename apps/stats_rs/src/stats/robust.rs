@@ -10,6 +10,16 @@ pub fn mad(xs: &[f64]) -> f64 {
     super::median(&devs)
 }
 
+/// [`mad`] of an ascending slice, reusing its order for the median.
+pub fn mad_sorted(sorted: &[f64]) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let med = super::median_sorted(sorted);
+    let devs: Vec<f64> = sorted.iter().map(|&x| (x - med).abs()).collect();
+    super::median(&devs)
+}
+
 /// Robust z-score using MAD (≈ 1.4826 * MAD to estimate sigma)
 pub fn robust_zscores_mad(xs: &[f64]) -> Vec<f64> {
     if xs.is_empty() {
@@ -50,8 +60,8 @@ pub fn winsorized_mean(xs: &[f64], q: f64) -> f64 {
     if xs.is_empty() {
         return f64::NAN;
     }
-    let lo = quantile(xs, q);
-    let hi = quantile(xs, 1.0 - q);
+    let s = super::sorted(xs);
+    let (lo, hi) = (quantile_sorted(&s, q), quantile_sorted(&s, 1.0 - q));
     let w: Vec<f64> = xs.iter().map(|&x| x.clamp(lo, hi)).collect();
     mean(&w)
 }
@@ -80,6 +90,56 @@ pub fn harmonic_mean(xs: &[f64]) -> f64 {
     xs.len() as f64 / denom
 }
 
+/// A scored position ordered by score, earlier positions first among ties.
+#[derive(Clone, Copy, Debug)]
+struct Scored(usize, f64);
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+impl Eq for Scored {}
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.1.total_cmp(&other.1).then(other.0.cmp(&self.0))
+    }
+}
+
+/// The `k` highest-scoring `(position, score)` pairs, highest first (earlier
+/// positions first among equal scores; NaN scores are skipped).
+///
+/// Keeps a min-heap of the best `k` seen so far: `O(n log k)` time and `O(k)`
+/// memory, with no sort of the whole input.
+pub fn top_k(scored: impl IntoIterator<Item = (usize, f64)>, k: usize) -> Vec<(usize, f64)> {
+    use std::{cmp::Reverse, collections::BinaryHeap};
+    if k == 0 {
+        return vec![];
+    }
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for (i, s) in scored {
+        if s.is_nan() {
+            continue;
+        }
+        let item = Reverse(Scored(i, s));
+        if heap.len() < k {
+            heap.push(item);
+        } else if heap.peek().is_some_and(|worst| item < *worst) {
+            heap.pop();
+            heap.push(item);
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(Scored(i, s))| (i, s))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,4 +265,16 @@ mod edge_tests {
         approx!(geometric_mean(&pos), 2.8284271247461903, EPS_TIGHT);
         approx!(harmonic_mean(&pos), 2.1333333333333333, EPS_TIGHT);
     }
+
+    #[test]
+    fn top_k_keeps_the_highest_scores_in_order() {
+        let scores = [0.5, 9.0, f64::NAN, 3.0, 9.0, 1.0, 7.0];
+        let scored = || scores.iter().copied().enumerate();
+        assert_eq!(top_k(scored(), 3), vec![(1, 9.0), (4, 9.0), (6, 7.0)]);
+        assert_eq!(top_k(scored(), 0), vec![]);
+        // k beyond the input returns every non-NaN score, still ordered
+        let all = top_k(scored(), 100);
+        assert_eq!(all.len(), 6);
+        assert_eq!(all.last(), Some(&(0, 0.5)));
+    }
 }
@@ -0,0 +1,112 @@
+use crate::stats::prelude::*;
+use std::f64::consts::TAU;
+
+/// Sample autocorrelation of an evenly spaced series at lags `0..=max_lag`:
+/// `r(k) = Σ (x_t − x̄)(x_{t+k} − x̄) / Σ (x_t − x̄)²`. Lags at or past the
+/// series length are left out; a constant series gives `NaN`s.
+pub fn autocorrelation(xs: &[f64], max_lag: usize) -> Vec<f64> {
+    let n = xs.len();
+    let m = mean(xs);
+    let c: Vec<f64> = xs.iter().map(|x| x - m).collect();
+    let c0 = dot(&c, &c);
+    (0..=max_lag.min(n.saturating_sub(1)))
+        .map(|k| match c0 > 0.0 {
+            true => dot(&c[..n - k], &c[k..]) / c0,
+            false => f64::NAN,
+        })
+        .collect()
+}
+
+/// The lag of the highest local peak of `acf` (as from [`autocorrelation`])
+/// that clears the white-noise band `1.96 / √n`, or `None`. Lag 1 is never a
+/// period.
+pub fn acf_period(acf: &[f64], n: usize) -> Option<usize> {
+    let band = 1.96 / (n as f64).sqrt();
+    (2..acf.len())
+        .filter(|&k| acf[k] > band && acf[k] > acf[k - 1])
+        .filter(|&k| acf.get(k + 1).is_none_or(|&next| acf[k] >= next))
+        .max_by(|&a, &b| acf[a].total_cmp(&acf[b]).then(b.cmp(&a)))
+}
+
+/// Periodogram of an evenly spaced series at the frequency of `period`
+/// samples: `|Σ (x_t − x̄) e^{−2πit/period}|² / n`.
+pub fn periodogram_power(xs: &[f64], period: f64) -> f64 {
+    let m = mean(xs);
+    let w = TAU / period;
+    let (re, im) = xs.iter().enumerate().fold((0.0, 0.0), |(re, im), (t, x)| {
+        let (s, c) = (w * t as f64).sin_cos();
+        (re + (x - m) * c, im - (x - m) * s)
+    });
+    (re * re + im * im) / xs.len() as f64
+}
+
+/// The integer period in `2..=max_period` with the most periodogram power,
+/// or `None` when there is no such period or no variation.
+pub fn periodogram_period(xs: &[f64], max_period: usize) -> Option<usize> {
+    (2..=max_period.min(xs.len() / 2))
+        .map(|p| (p, periodogram_power(xs, p as f64)))
+        .filter(|(_, power)| *power > 1e-12)
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+        .map(|(p, _)| p)
+}
+
+/// Share of the variance of the values in `groups` explained by the group
+/// means (η², in `[0, 1]`). `NaN` with fewer than two non-empty groups or no
+/// variation.
+pub fn eta_squared(groups: &[Vec<f64>]) -> f64 {
+    let all: Vec<f64> = groups.iter().flatten().copied().collect();
+    if groups.iter().filter(|g| !g.is_empty()).count() < 2 {
+        return f64::NAN;
+    }
+    let grand = mean(&all);
+    let total: f64 = all.iter().map(|x| (x - grand).powi(2)).sum();
+    let between: f64 = groups
+        .iter()
+        .filter(|g| !g.is_empty())
+        .map(|g| g.len() as f64 * (mean(g) - grand).powi(2))
+        .sum();
+    match total > 0.0 {
+        true => (between / total).min(1.0),
+        false => f64::NAN,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx;
+
+    fn wave(n: usize, period: f64) -> Vec<f64> {
+        (0..n)
+            .map(|t| (TAU * t as f64 / period).sin() + 0.1 * (t % 3) as f64)
+            .collect()
+    }
+
+    #[test]
+    fn autocorrelation_of_a_short_series() {
+        let acf = autocorrelation(&[1.0, 2.0, 3.0, 4.0], 10);
+        assert_eq!(acf.len(), 4);
+        approx!(acf[0], 1.0, 1e-15);
+        // centred [-1.5, -0.5, 0.5, 1.5]: lag 1 = (0.75 − 0.25 + 0.75) / 5
+        approx!(acf[1], 0.25, 1e-15);
+        assert!(autocorrelation(&[2.0; 5], 2)[1].is_nan());
+    }
+
+    #[test]
+    fn both_detectors_find_a_weekly_cycle() {
+        let xs = wave(24 * 7, 7.0);
+        let acf = autocorrelation(&xs, 30);
+        assert_eq!(acf_period(&acf, xs.len()), Some(7));
+        assert_eq!(periodogram_period(&xs, 30), Some(7));
+        assert_eq!(periodogram_period(&[5.0; 40], 10), None);
+        assert_eq!(acf_period(&[1.0, 0.0, -0.5], 100), None);
+    }
+
+    #[test]
+    fn eta_squared_is_one_for_constant_groups() {
+        approx!(eta_squared(&[vec![1.0, 1.0], vec![3.0, 3.0]]), 1.0, 1e-15);
+        // equal group means explain nothing
+        approx!(eta_squared(&[vec![0.0, 2.0], vec![1.0, 1.0]]), 0.0, 1e-15);
+        assert!(eta_squared(&[vec![1.0, 2.0], vec![]]).is_nan());
+    }
+}
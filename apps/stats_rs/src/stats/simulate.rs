@@ -0,0 +1,381 @@
+//! Monte Carlo replication engine and the formula language simulations
+//! evaluate.
+//!
+//! [`replicate`] runs any seeded trial a fixed number of times behind a
+//! [`Checkpoint`]; the bootstrap and permutation kernels are instances of it.
+//! [`Formula`] is a small arithmetic language over named variables for
+//! propagating uncertainty: `+ - * / ^`, parentheses, the constants `pi` and
+//! `e`, and the functions `sqrt`, `exp`, `ln`, `log10`, `abs`, `sin`, `cos`,
+//! `tan`, `min` and `max`.
+
+use crate::stats::prelude::*;
+use rand::Rng;
+
+/// Run `trial` `replications` times on `rng` and collect its results, in
+/// order. `progress` is called with the replications done so far and can stop
+/// the loop early, leaving the results gathered until then.
+pub fn replicate<R: Rng>(
+    replications: usize,
+    rng: &mut R,
+    progress: impl Checkpoint,
+    mut trial: impl FnMut(&mut R) -> f64,
+) -> Vec<f64> {
+    let mut out = Vec::with_capacity(replications);
+    for r in 0..replications {
+        if progress.cancelled() {
+            break;
+        }
+        out.push(trial(rng));
+        progress.reached(r + 1);
+    }
+    out
+}
+
+/// Two-sided Monte Carlo p-value of `observed` among `draws`:
+/// `2 · min(k₊ + 1, k₋ + 1) / (N + 1)` with `k₊` (`k₋`) the draws at or above
+/// (below) it, capped at 1. `NaN` without draws.
+pub fn monte_carlo_p_value(draws: &[f64], observed: f64) -> f64 {
+    if draws.is_empty() || observed.is_nan() {
+        return f64::NAN;
+    }
+    let above = draws.iter().filter(|&&d| d >= observed).count();
+    let below = draws.iter().filter(|&&d| d <= observed).count();
+    (2.0 * (above.min(below) + 1) as f64 / (draws.len() + 1) as f64).min(1.0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Func {
+    Sqrt,
+    Exp,
+    Ln,
+    Log10,
+    Abs,
+    Sin,
+    Cos,
+    Tan,
+    Min,
+    Max,
+}
+
+impl Func {
+    fn named(name: &str) -> Option<(Func, usize)> {
+        Some(match name {
+            "sqrt" => (Func::Sqrt, 1),
+            "exp" => (Func::Exp, 1),
+            "ln" => (Func::Ln, 1),
+            "log10" => (Func::Log10, 1),
+            "abs" => (Func::Abs, 1),
+            "sin" => (Func::Sin, 1),
+            "cos" => (Func::Cos, 1),
+            "tan" => (Func::Tan, 1),
+            "min" => (Func::Min, 2),
+            "max" => (Func::Max, 2),
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Num(f64),
+    /// Index into the formula's variables
+    Var(usize),
+    Neg(Box<Node>),
+    Bin(Op, Box<Node>, Box<Node>),
+    Call(Func, Vec<Node>),
+}
+
+/// A parsed arithmetic formula over named variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    root: Node,
+}
+
+/// Recursive-descent parser; positions are byte offsets into the formula.
+struct Parser<'a> {
+    src: &'a str,
+    at: usize,
+    vars: &'a [&'a str],
+    /// Nesting depth of the operand being parsed
+    depth: usize,
+}
+
+/// Deepest nesting of parentheses, signs and powers a formula may have.
+const MAX_DEPTH: usize = 64;
+
+impl Parser<'_> {
+    fn skip_space(&mut self) {
+        let rest = &self.src[self.at..];
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_space();
+        self.src[self.at..].chars().next()
+    }
+
+    fn error(&self, what: &str) -> String {
+        match self.src[self.at..].chars().next() {
+            Some(c) => format!("{what} at position {}, found '{c}'", self.at),
+            None => format!("{what} at the end of the formula"),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.peek() {
+            Some(found) if found == c => {
+                self.at += 1;
+                Ok(())
+            }
+            _ => Err(self.error(&format!("expected '{c}'"))),
+        }
+    }
+
+    /// `expr := term (('+' | '-') term)*`
+    fn expr(&mut self) -> Result<Node, String> {
+        let mut lhs = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.at += 1;
+            let op = if op == '+' { Op::Add } else { Op::Sub };
+            lhs = Node::Bin(op, Box::new(lhs), Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    /// `term := unary (('*' | '/') unary)*`
+    fn term(&mut self) -> Result<Node, String> {
+        let mut lhs = self.unary()?;
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.at += 1;
+            let op = if op == '*' { Op::Mul } else { Op::Div };
+            lhs = Node::Bin(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    /// `unary := '-' unary | atom ('^' unary)?`, so `-x^2` is `-(x^2)` and
+    /// `^` groups to the right
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(&format!("nested deeper than {MAX_DEPTH} levels")));
+        }
+        self.depth += 1;
+        let node = self.signed();
+        self.depth -= 1;
+        node
+    }
+
+    fn signed(&mut self) -> Result<Node, String> {
+        if self.peek() == Some('-') {
+            self.at += 1;
+            return Ok(Node::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.atom()?;
+        if self.peek() == Some('^') {
+            self.at += 1;
+            return Ok(Node::Bin(Op::Pow, Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    /// `atom := number | name | name '(' expr (',' expr)* ')' | '(' expr ')'`
+    fn atom(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            Some('(') => {
+                self.at += 1;
+                let inner = self.expr()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => self.name(),
+            _ => Err(self.error("expected a number, name or '('")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Node, String> {
+        let start = self.at;
+        let rest = &self.src[start..];
+        let mut len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        // exponent: 1e-3, 2.5E+4
+        let tail = &rest[len..];
+        if tail.starts_with(['e', 'E']) {
+            let sign = usize::from(tail[1..].starts_with(['+', '-']));
+            let digits = tail[1 + sign..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(tail.len() - 1 - sign);
+            if digits > 0 {
+                len += 1 + sign + digits;
+            }
+        }
+        self.at += len;
+        rest[..len].parse().map(Node::Num).map_err(|_| {
+            self.at = start;
+            self.error("malformed number")
+        })
+    }
+
+    fn name(&mut self) -> Result<Node, String> {
+        let start = self.at;
+        let rest = &self.src[start..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let name = &rest[..len];
+        self.at += len;
+        if self.peek() == Some('(') {
+            let Some((func, arity)) = Func::named(name) else {
+                self.at = start;
+                return Err(self.error(&format!("unknown function '{name}'")));
+            };
+            self.at += 1;
+            let mut args = vec![self.expr()?];
+            while self.peek() == Some(',') {
+                self.at += 1;
+                args.push(self.expr()?);
+            }
+            self.expect(')')?;
+            if args.len() != arity {
+                return Err(format!(
+                    "{name} takes {arity} argument(s), got {}",
+                    args.len()
+                ));
+            }
+            return Ok(Node::Call(func, args));
+        }
+        match self.vars.iter().position(|&v| v == name) {
+            Some(i) => Ok(Node::Var(i)),
+            None => match name {
+                "pi" => Ok(Node::Num(std::f64::consts::PI)),
+                "e" => Ok(Node::Num(std::f64::consts::E)),
+                _ => Err(format!("unknown variable '{name}' at position {start}")),
+            },
+        }
+    }
+}
+
+impl Formula {
+    /// Parse `src`, resolving names against `vars` (which shadow the
+    /// constants `pi` and `e`). The error names what went wrong and where.
+    pub fn parse(src: &str, vars: &[&str]) -> Result<Formula, String> {
+        let mut p = Parser {
+            src,
+            at: 0,
+            vars,
+            depth: 0,
+        };
+        let root = p.expr()?;
+        match p.peek() {
+            None => Ok(Formula { root }),
+            Some(_) => Err(p.error("unexpected input")),
+        }
+    }
+
+    /// Value with `values[i]` bound to the `i`-th parse variable.
+    pub fn eval(&self, values: &[f64]) -> f64 {
+        fn go(n: &Node, v: &[f64]) -> f64 {
+            match n {
+                Node::Num(x) => *x,
+                Node::Var(i) => v[*i],
+                Node::Neg(a) => -go(a, v),
+                Node::Bin(op, a, b) => {
+                    let (a, b) = (go(a, v), go(b, v));
+                    match op {
+                        Op::Add => a + b,
+                        Op::Sub => a - b,
+                        Op::Mul => a * b,
+                        Op::Div => a / b,
+                        Op::Pow => a.powf(b),
+                    }
+                }
+                Node::Call(f, args) => {
+                    let a = go(&args[0], v);
+                    match f {
+                        Func::Sqrt => a.sqrt(),
+                        Func::Exp => a.exp(),
+                        Func::Ln => a.ln(),
+                        Func::Log10 => a.log10(),
+                        Func::Abs => a.abs(),
+                        Func::Sin => a.sin(),
+                        Func::Cos => a.cos(),
+                        Func::Tan => a.tan(),
+                        Func::Min => a.min(go(&args[1], v)),
+                        Func::Max => a.max(go(&args[1], v)),
+                    }
+                }
+            }
+        }
+        go(&self.root, values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn formulas_follow_precedence() {
+        let f = |src: &str| Formula::parse(src, &["x", "y"]).map(|f| f.eval(&[3.0, 2.0]));
+        assert_eq!(f("1 + x * y"), Ok(7.0));
+        assert_eq!(f("(1 + x) * y"), Ok(8.0));
+        assert_eq!(f("-x^2"), Ok(-9.0));
+        assert_eq!(f("2^x^y"), Ok(512.0));
+        assert_eq!(f("x - y - 1"), Ok(0.0));
+        assert_eq!(f("max(x, y) / min(x, y)"), Ok(1.5));
+        assert_eq!(f("1.5e1 + 2E-1"), Ok(15.2));
+        approx!(f("sqrt(x^2 + y^2)").unwrap(), 13f64.sqrt(), 1e-15);
+        approx!(f("ln(e) + cos(pi)").unwrap(), 0.0, 1e-15);
+    }
+
+    #[test]
+    fn formula_errors_say_where() {
+        let err = |src: &str| Formula::parse(src, &["x"]).unwrap_err();
+        assert_eq!(
+            err("x +"),
+            "expected a number, name or '(' at the end of the formula"
+        );
+        assert_eq!(err("x * z"), "unknown variable 'z' at position 4");
+        assert_eq!(
+            err("foo(x)"),
+            "unknown function 'foo' at position 0, found 'f'"
+        );
+        assert_eq!(err("min(x)"), "min takes 2 argument(s), got 1");
+        assert_eq!(err("(x"), "expected ')' at the end of the formula");
+        assert_eq!(err("x y"), "unexpected input at position 2, found 'y'");
+        let deep = format!("{}x{}", "(".repeat(100), ")".repeat(100));
+        assert!(err(&deep).starts_with("nested deeper than 64 levels"));
+    }
+
+    #[test]
+    fn replicate_runs_every_trial_in_order() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut k = 0.0;
+        let out = replicate(
+            4,
+            &mut rng,
+            |_| {},
+            |_| {
+                k += 1.0;
+                k
+            },
+        );
+        assert_eq!(out, [1.0, 2.0, 3.0, 4.0]);
+        let draws: Vec<f64> = (1..=99).map(f64::from).collect();
+        // one draw at or above 99 on top, 99 at or below
+        approx!(monte_carlo_p_value(&draws, 99.0), 0.04, 1e-15);
+        approx!(monte_carlo_p_value(&draws, 50.0), 1.0, 1e-15);
+    }
+}
@@ -0,0 +1,123 @@
+//! Fixed-size sample sketches for approximate answers on very large inputs.
+//!
+//! A [`QuantileSketch`] keeps a seeded uniform sample (without replacement)
+//! of at most `size` values, sorted once. Quantiles and ECDF values read off
+//! the sample are within [`QuantileSketch::rank_error`] in rank of the exact
+//! ones with the requested confidence (Dvoretzky–Kiefer–Wolfowitz:
+//! `ε = sqrt(ln(2/δ) / 2s)`), whatever the input size, so the cost past
+//! parsing stays `O(s log s)`.
+
+use rand::{SeedableRng, rngs::StdRng, seq::index};
+
+/// Sample size used by the `approx` request option (`ε ≈ 0.009` at 99%).
+pub const SKETCH_SIZE: usize = 32_768;
+
+/// Confidence at which `approx` responses report their rank error.
+pub const SKETCH_CONFIDENCE: f64 = 0.99;
+
+#[derive(Clone, Debug)]
+pub struct QuantileSketch {
+    sorted: Vec<f64>,
+    n: usize,
+    seed: u64,
+}
+
+impl QuantileSketch {
+    /// Sample `size` of `xs` with `seed`; keeps all of `xs` when it is no
+    /// larger, in which case every answer is exact.
+    pub fn new(xs: &[f64], size: usize, seed: u64) -> Self {
+        let mut sample = if xs.len() <= size {
+            xs.to_vec()
+        } else {
+            let mut rng = StdRng::seed_from_u64(seed);
+            index::sample(&mut rng, xs.len(), size)
+                .into_iter()
+                .map(|i| xs[i])
+                .collect()
+        };
+        sample.sort_unstable_by(f64::total_cmp);
+        Self {
+            sorted: sample,
+            n: xs.len(),
+            seed,
+        }
+    }
+
+    /// Sketch over `sample`, already a uniform draw with `seed` from `n`
+    /// values (e.g. by a reservoir over a stream); exact when it holds all `n`.
+    pub fn from_sample(mut sample: Vec<f64>, n: usize, seed: u64) -> Self {
+        sample.sort_unstable_by(f64::total_cmp);
+        Self {
+            sorted: sample,
+            n,
+            seed,
+        }
+    }
+
+    /// The sample, ascending; feed it to the `*_sorted` kernels.
+    pub fn sorted(&self) -> &[f64] {
+        &self.sorted
+    }
+
+    /// Whether the sample is the whole input.
+    pub fn is_exact(&self) -> bool {
+        self.sorted.len() == self.n
+    }
+
+    /// Size of the input the sample was drawn from.
+    pub fn population(&self) -> usize {
+        self.n
+    }
+
+    /// Seed the sample was drawn with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Bound on the rank (CDF) error of any quantile or ECDF value that holds
+    /// with probability `confidence`; `0` when exact.
+    pub fn rank_error(&self, confidence: f64) -> f64 {
+        if self.is_exact() {
+            return 0.0;
+        }
+        dkw_epsilon(self.sorted.len(), 1.0 - confidence)
+    }
+}
+
+/// DKW band half-width for `s` samples at failure probability `delta`.
+pub fn dkw_epsilon(s: usize, delta: f64) -> f64 {
+    ((2.0 / delta).ln() / (2.0 * s as f64)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::prelude::*;
+
+    #[test]
+    fn small_inputs_are_exact() {
+        let xs = [3.0, 1.0, 2.0];
+        let s = QuantileSketch::new(&xs, 10, 0);
+        assert!(s.is_exact());
+        assert_eq!(s.sorted(), &[1.0, 2.0, 3.0]);
+        assert_eq!(s.rank_error(0.99), 0.0);
+    }
+
+    #[test]
+    fn sampled_quantiles_stay_within_the_rank_bound() {
+        let n = 200_000;
+        let xs: Vec<f64> = (0..n).map(|i| ((i * 7919) % n) as f64).collect();
+        let s = QuantileSketch::new(&xs, 4096, 1);
+        assert!(!s.is_exact());
+        assert_eq!((s.sorted().len(), s.population()), (4096, n));
+        let eps = s.rank_error(0.99);
+        assert!((eps - dkw_epsilon(4096, 0.01)).abs() < 1e-15);
+        for p in [0.01, 0.25, 0.5, 0.9, 0.99] {
+            // xs is a permutation of 0..n, so a value's rank is itself
+            let rank = quantile_sorted(s.sorted(), p) / n as f64;
+            assert!((rank - p).abs() <= eps, "p={p}: rank {rank}, eps {eps}");
+        }
+        let again = QuantileSketch::new(&xs, 4096, 1);
+        assert_eq!(again.sorted(), s.sorted());
+    }
+}
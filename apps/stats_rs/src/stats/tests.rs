@@ -0,0 +1,113 @@
+//! t-tests: one-sample and paired (over the differences) via
+//! [`one_sample_t`], Welch's two-sample test via [`welch_t`], with p-values
+//! and confidence intervals for either tail.
+
+use crate::stats::prelude::*;
+
+/// Alternative hypothesis of a one- or two-sided test.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tail {
+    /// The parameter differs from its null value
+    #[default]
+    TwoSided,
+    /// The parameter is below its null value
+    Less,
+    /// The parameter is above its null value
+    Greater,
+}
+
+/// Mean of `xs` with its standard error and degrees of freedom,
+/// `(mean, s/√n, n − 1)`: the parts of a one-sample t-test, or of a paired
+/// one over the differences. `NaN`s below two values.
+pub fn one_sample_t(xs: &[f64]) -> (f64, f64, f64) {
+    if xs.len() < 2 {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let n = xs.len() as f64;
+    let m = mean(xs);
+    (m, sample_std_dev(xs, m) / n.sqrt(), n - 1.0)
+}
+
+/// `mean(x) − mean(y)` with its unpooled standard error and the
+/// Welch–Satterthwaite degrees of freedom: the parts of Welch's t-test.
+/// `NaN`s below two values per sample.
+pub fn welch_t(x: &[f64], y: &[f64]) -> (f64, f64, f64) {
+    if x.len() < 2 || y.len() < 2 {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let (n1, n2) = (x.len() as f64, y.len() as f64);
+    let (m1, m2) = (mean(x), mean(y));
+    let (a, b) = (sample_variance(x, m1) / n1, sample_variance(y, m2) / n2);
+    let df = (a + b).powi(2) / (a * a / (n1 - 1.0) + b * b / (n2 - 1.0));
+    (m1 - m2, (a + b).sqrt(), df)
+}
+
+/// p-value of a t statistic on `df` degrees of freedom against `tail`.
+pub fn t_p_value(t: f64, df: f64, tail: Tail) -> f64 {
+    match tail {
+        Tail::TwoSided => (2.0 * student_t_sf(t.abs(), df)).min(1.0),
+        Tail::Less => student_t_cdf(t, df),
+        Tail::Greater => student_t_sf(t, df),
+    }
+}
+
+/// Confidence bounds at `confidence` for an `estimate` with standard error
+/// `se` on `df` degrees of freedom, matching `tail`: one-sided intervals are
+/// open (infinite) on the other side.
+pub fn t_interval(estimate: f64, se: f64, df: f64, confidence: f64, tail: Tail) -> (f64, f64) {
+    match tail {
+        Tail::TwoSided => {
+            let half = student_t_quantile(0.5 + 0.5 * confidence, df) * se;
+            (estimate - half, estimate + half)
+        }
+        Tail::Less => (
+            f64::NEG_INFINITY,
+            estimate + student_t_quantile(confidence, df) * se,
+        ),
+        Tail::Greater => (
+            estimate - student_t_quantile(confidence, df) * se,
+            f64::INFINITY,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod t_test_tests {
+    use super::*;
+    use crate::approx;
+
+    #[test]
+    fn t_tests_match_r_on_the_sleep_data() {
+        let g1 = [0.7, -1.6, -0.2, -1.2, -0.1, 3.4, 3.7, 0.8, 0.0, 2.0];
+        let g2 = [1.9, 0.8, 1.1, 0.1, -0.1, 4.4, 5.5, 1.6, 4.6, 3.4];
+
+        // t.test(g1, g2, paired = TRUE)
+        let d: Vec<f64> = g1.iter().zip(&g2).map(|(a, b)| a - b).collect();
+        let (m, se, df) = one_sample_t(&d);
+        approx!(m, -1.58, 1e-12);
+        assert_eq!(df, 9.0);
+        approx!(m / se, -4.062_127_683_382_037, 1e-9);
+        approx!(t_p_value(m / se, df, Tail::TwoSided), 0.002_832_890, 1e-8);
+        let (lo, hi) = t_interval(m, se, df, 0.95, Tail::TwoSided);
+        approx!(lo, -2.459_885_8, 1e-6);
+        approx!(hi, -0.700_114_2, 1e-6);
+
+        // t.test(g1, g2): Welch
+        let (m, se, df) = welch_t(&g1, &g2);
+        approx!(m / se, -1.8608, 1e-4);
+        approx!(df, 17.776, 1e-3);
+        approx!(t_p_value(m / se, df, Tail::TwoSided), 0.079_39, 1e-5);
+        let (lo, hi) = t_interval(m, se, df, 0.95, Tail::TwoSided);
+        approx!(lo, -3.365_483_2, 1e-6);
+        approx!(hi, 0.205_483_2, 1e-6);
+
+        // one-sided tails split the two-sided p-value
+        let t = m / se;
+        let p = t_p_value(t, df, Tail::TwoSided);
+        approx!(t_p_value(t, df, Tail::Less), p / 2.0, 1e-12);
+        approx!(t_p_value(t, df, Tail::Greater), 1.0 - p / 2.0, 1e-12);
+        let (lo, hi) = t_interval(m, se, df, 0.95, Tail::Less);
+        assert!(lo == f64::NEG_INFINITY && hi < 0.205_483_2);
+        assert!(one_sample_t(&[1.0]).0.is_nan());
+    }
+}
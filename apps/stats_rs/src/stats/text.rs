@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Precision / recall / F1 triple for overlap metrics.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Prf {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+impl Prf {
+    fn from_counts(overlap: usize, cand_total: usize, ref_total: usize) -> Self {
+        let precision = if cand_total == 0 {
+            0.0
+        } else {
+            overlap as f64 / cand_total as f64
+        };
+        let recall = if ref_total == 0 {
+            0.0
+        } else {
+            overlap as f64 / ref_total as f64
+        };
+        let f1 = if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        };
+        Self {
+            precision,
+            recall,
+            f1,
+        }
+    }
+}
+
+/// Lowercase and split on anything that is not alphanumeric.
+pub fn tokenize(s: &str) -> Vec<String> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Multiset of n-grams.
+fn ngram_counts<T: Eq + Hash>(toks: &[T], n: usize) -> HashMap<&[T], usize> {
+    let mut m = HashMap::new();
+    if n == 0 || toks.len() < n {
+        return m;
+    }
+    for w in toks.windows(n) {
+        *m.entry(w).or_insert(0) += 1;
+    }
+    m
+}
+
+/// Clipped n-gram overlap: Σ min(count_cand, count_ref).
+fn clipped_overlap<T: Eq + Hash>(
+    cand: &HashMap<&[T], usize>,
+    refs: &HashMap<&[T], usize>,
+) -> usize {
+    cand.iter()
+        .map(|(g, &c)| c.min(refs.get(g).copied().unwrap_or(0)))
+        .sum()
+}
+
+/// ROUGE-N (n-gram overlap) between a candidate and a reference token sequence.
+pub fn rouge_n<T: Eq + Hash>(cand: &[T], reference: &[T], n: usize) -> Prf {
+    let cc = ngram_counts(cand, n);
+    let rc = ngram_counts(reference, n);
+    let overlap = clipped_overlap(&cc, &rc);
+    Prf::from_counts(
+        overlap,
+        cand.len().saturating_sub(n - 1),
+        reference.len().saturating_sub(n - 1),
+    )
+}
+
+/// Length of the longest common subsequence (O(|a|·|b|) time, O(|b|) memory).
+pub fn lcs_len<T: Eq>(a: &[T], b: &[T]) -> usize {
+    let mut prev = vec![0usize; b.len() + 1];
+    let mut cur = vec![0usize; b.len() + 1];
+    for x in a {
+        for (j, y) in b.iter().enumerate() {
+            cur[j + 1] = if x == y {
+                prev[j] + 1
+            } else {
+                cur[j].max(prev[j + 1])
+            };
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+/// ROUGE-L (longest common subsequence) precision/recall/F1.
+pub fn rouge_l<T: Eq>(cand: &[T], reference: &[T]) -> Prf {
+    Prf::from_counts(lcs_len(cand, reference), cand.len(), reference.len())
+}
+
+/// Sentence-level BLEU with uniform weights up to `max_n`-grams and brevity penalty.
+///
+/// Uses add-one smoothing for n > 1 (Lin & Och, 2004) so short sentences with no
+/// higher-order matches don't collapse to 0. Returns 0 for an empty candidate.
+pub fn bleu<T: Eq + Hash>(cand: &[T], reference: &[T], max_n: usize) -> f64 {
+    if cand.is_empty() || max_n == 0 {
+        return 0.0;
+    }
+    let mut log_p = 0.0;
+    for n in 1..=max_n {
+        let cc = ngram_counts(cand, n);
+        let rc = ngram_counts(reference, n);
+        let total = cand.len().saturating_sub(n - 1);
+        let overlap = clipped_overlap(&cc, &rc);
+        let (num, den) = if n == 1 {
+            (overlap as f64, total as f64)
+        } else {
+            (overlap as f64 + 1.0, total as f64 + 1.0)
+        };
+        if num == 0.0 {
+            return 0.0;
+        }
+        log_p += (num / den).ln() / max_n as f64;
+    }
+    let (c, r) = (cand.len() as f64, reference.len() as f64);
+    let bp = if c > r { 1.0 } else { (1.0 - r / c).exp() };
+    bp * log_p.exp()
+}
+
+/// SQuAD-style bag-of-tokens F1. Two empty sequences score 1; one empty scores 0.
+pub fn token_f1<T: Eq + Hash>(cand: &[T], reference: &[T]) -> f64 {
+    if cand.is_empty() || reference.is_empty() {
+        return if cand.is_empty() && reference.is_empty() {
+            1.0
+        } else {
+            0.0
+        };
+    }
+    rouge_n(cand, reference, 1).f1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx;
+    use crate::stats::utils::EPS_TIGHT;
+
+    fn toks(s: &str) -> Vec<String> {
+        tokenize(s)
+    }
+
+    #[test]
+    fn tokenize_lowercases_and_strips_punctuation() {
+        assert_eq!(toks("The cat, sat!"), vec!["the", "cat", "sat"]);
+        assert!(toks("  ...  ").is_empty());
+    }
+
+    #[test]
+    fn rouge_scores_known_values() {
+        let c = toks("the cat sat on the mat");
+        let r = toks("the cat is on the mat");
+
+        // unigrams: overlap the,cat,on,the,mat = 5 of 6 each side
+        let r1 = rouge_n(&c, &r, 1);
+        approx!(r1.precision, 5.0 / 6.0, EPS_TIGHT);
+        approx!(r1.recall, 5.0 / 6.0, EPS_TIGHT);
+
+        // bigrams: "the cat", "on the", "the mat" = 3 of 5
+        let r2 = rouge_n(&c, &r, 2);
+        approx!(r2.f1, 3.0 / 5.0, EPS_TIGHT);
+
+        // LCS = the cat on the mat (5)
+        assert_eq!(lcs_len(&c, &r), 5);
+        approx!(rouge_l(&c, &r).f1, 5.0 / 6.0, EPS_TIGHT);
+    }
+
+    #[test]
+    fn bleu_identity_brevity_and_empty() {
+        let r = toks("a quick brown fox jumps");
+        approx!(bleu(&r, &r, 4), 1.0, EPS_TIGHT);
+
+        // a short but exact prefix is penalized by brevity
+        let c = toks("a quick brown");
+        let b = bleu(&c, &r, 4);
+        assert!(b > 0.0 && b < 1.0);
+
+        assert_eq!(bleu(&toks(""), &r, 4), 0.0);
+        assert_eq!(bleu(&toks("zebra"), &r, 4), 0.0);
+    }
+
+    #[test]
+    fn token_f1_conventions() {
+        approx!(token_f1(&toks("Paris"), &toks("paris")), 1.0, EPS_TIGHT);
+        approx!(
+            token_f1(&toks("in Paris France"), &toks("Paris")),
+            0.5,
+            EPS_TIGHT
+        );
+        approx!(token_f1(&toks(""), &toks("")), 1.0, EPS_TIGHT);
+        approx!(token_f1(&toks(""), &toks("x")), 0.0, EPS_TIGHT);
+    }
+}
@@ -8,6 +8,15 @@ pub fn dot(a: &[f64], b: &[f64]) -> f64 {
 pub fn l2_norm(a: &[f64]) -> f64 {
     dot(a, a).sqrt()
 }
+
+pub fn euclidean_distance(a: &[f64], b: &[f64]) -> f64 {
+    assert_eq!(a.len(), b.len());
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f64>()
+        .sqrt()
+}
 pub fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
     let na = l2_norm(a);
     let nb = l2_norm(b);
@@ -16,6 +25,74 @@ pub fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
     }
     dot(a, b) / (na * nb)
 }
+
+/// [`dot`] over `f32` embeddings, accumulated in `f32`.
+pub fn dot_f32(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len());
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+pub fn l2_norm_f32(a: &[f32]) -> f32 {
+    dot_f32(a, a).sqrt()
+}
+
+pub fn euclidean_distance_f32(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len());
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// [`cosine_similarity`] over `f32` embeddings; zero vectors give NaN.
+pub fn cosine_similarity_f32(a: &[f32], b: &[f32]) -> f32 {
+    let na = l2_norm_f32(a);
+    let nb = l2_norm_f32(b);
+    if na == 0.0 || nb == 0.0 {
+        return f32::NAN;
+    }
+    dot_f32(a, b) / (na * nb)
+}
+
+/// Element type of dense embeddings. The point-set kernels below are generic
+/// over it so `f32` embeddings run without widening to `f64` (half the
+/// memory, twice the SIMD lanes); arithmetic stays in the element type and
+/// only the reported values are `f64`.
+pub trait Element: Copy + Send + Sync {
+    fn dot(a: &[Self], b: &[Self]) -> f64;
+    fn euclidean(a: &[Self], b: &[Self]) -> f64;
+    fn norm(a: &[Self]) -> f64 {
+        Self::dot(a, a).sqrt()
+    }
+    /// Cosine similarity; NaN when either vector is zero.
+    fn cosine(a: &[Self], b: &[Self]) -> f64;
+}
+
+impl Element for f64 {
+    fn dot(a: &[f64], b: &[f64]) -> f64 {
+        dot(a, b)
+    }
+    fn euclidean(a: &[f64], b: &[f64]) -> f64 {
+        euclidean_distance(a, b)
+    }
+    fn cosine(a: &[f64], b: &[f64]) -> f64 {
+        cosine_similarity(a, b)
+    }
+}
+
+impl Element for f32 {
+    fn dot(a: &[f32], b: &[f32]) -> f64 {
+        dot_f32(a, b) as f64
+    }
+    fn euclidean(a: &[f32], b: &[f32]) -> f64 {
+        euclidean_distance_f32(a, b) as f64
+    }
+    fn cosine(a: &[f32], b: &[f32]) -> f64 {
+        cosine_similarity_f32(a, b) as f64
+    }
+}
+
 /// Mean vector (centroid) across rows; expects non-empty list of equal-length vectors.
 pub fn centroid(points: &[Vec<f64>]) -> Vec<f64> {
     let n = points.len();
@@ -35,39 +112,164 @@ pub fn centroid(points: &[Vec<f64>]) -> Vec<f64> {
     }
     c
 }
+/// Running mean/variance, min and max of pairwise cosine similarities.
+#[derive(Clone, Copy)]
+struct CosineMoments {
+    moments: OnlineMeanVar,
+    lo: f64,
+    hi: f64,
+}
+
+impl CosineMoments {
+    fn new() -> Self {
+        Self {
+            moments: OnlineMeanVar::new(),
+            lo: f64::INFINITY,
+            hi: f64::NEG_INFINITY,
+        }
+    }
+
+    fn push(&mut self, c: f64) {
+        self.moments.push(c);
+        self.lo = self.lo.min(c);
+        self.hi = self.hi.max(c);
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        self.moments.merge(&other.moments);
+        self.lo = self.lo.min(other.lo);
+        self.hi = self.hi.max(other.hi);
+        self
+    }
+}
+
+/// Streams the cosine of every unordered pair into [`CosineMoments`] without
+/// materialising the O(n²) similarities. Each row of the upper triangle is
+/// accumulated on its own (on the rayon pool with the `parallel` feature)
+/// and the partial results merged.
+fn pairwise_cosine_moments<T: Element>(points: &[Vec<T>]) -> CosineMoments {
+    let norms: Vec<f64> = points.iter().map(|p| T::norm(p)).collect();
+    let row = |i: usize| {
+        let mut acc = CosineMoments::new();
+        for j in (i + 1)..points.len() {
+            acc.push(if norms[i] == 0.0 || norms[j] == 0.0 {
+                f64::NAN
+            } else {
+                T::dot(&points[i], &points[j]) / (norms[i] * norms[j])
+            });
+        }
+        acc
+    };
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        (0..points.len())
+            .into_par_iter()
+            .map(row)
+            .reduce(CosineMoments::new, CosineMoments::merge)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        (0..points.len())
+            .map(row)
+            .fold(CosineMoments::new(), CosineMoments::merge)
+    }
+}
+
 /// Average pairwise cosine similarity inside a cluster (simple cohesion proxy).
-pub fn intra_cluster_cosine(points: &[Vec<f64>]) -> f64 {
-    let n = points.len();
-    if n < 2 {
+pub fn intra_cluster_cosine<T: Element>(points: &[Vec<T>]) -> f64 {
+    if points.len() < 2 {
         return f64::NAN;
     }
-    let mut s = 0.0;
-    let mut m = 0usize;
-    for i in 0..n {
-        for j in (i + 1)..n {
-            s += cosine_similarity(&points[i], &points[j]);
-            m += 1;
-        }
+    pairwise_cosine_moments(points).moments.mean()
+}
+
+/// Mean, min, max and sample standard deviation of the cosine similarity
+/// over all unordered pairs; NaNs when there are fewer than two points.
+pub fn pairwise_cosine_stats<T: Element>(points: &[Vec<T>]) -> (f64, f64, f64, f64) {
+    if points.len() < 2 {
+        return (f64::NAN, f64::NAN, f64::NAN, f64::NAN);
     }
-    s / m as f64
+    let acc = pairwise_cosine_moments(points);
+    (acc.moments.mean(), acc.lo, acc.hi, acc.moments.sample_std())
 }
 
-pub fn pairwise_cosine_stats(points: &[Vec<f64>]) -> (f64, f64, f64, f64) {
+/// Distance from each point to its k-th nearest neighbor (brute force, O(n²·d)).
+///
+/// `dist` is the pairwise distance (e.g. [`euclidean_distance`]); a point is never its
+/// own neighbor. Returns NaN for every point when `k == 0` or `k >= n`.
+pub fn kth_nn_distances<T, F>(points: &[Vec<T>], k: usize, dist: F) -> Vec<f64>
+where
+    F: Fn(&[T], &[T]) -> f64,
+{
     let n = points.len();
-    if n < 2 {
-        return (f64::NAN, f64::NAN, f64::NAN, f64::NAN);
+    if k == 0 || k >= n {
+        return vec![f64::NAN; n];
     }
-    let mut vals = Vec::new();
-    for i in 0..n {
-        for j in (i + 1)..n {
-            vals.push(cosine_similarity(&points[i], &points[j]));
+    let mut row = Vec::with_capacity(n - 1);
+    (0..n)
+        .map(|i| {
+            row.clear();
+            row.extend(
+                (0..n)
+                    .filter(|&j| j != i)
+                    .map(|j| dist(&points[i], &points[j])),
+            );
+            let (_, kth, _) = row.select_nth_unstable_by(k - 1, |a, b| a.total_cmp(b));
+            *kth
+        })
+        .collect()
+}
+
+/// Sorted distances from each point to its `k` nearest neighbors (brute force, O(n²·d)).
+///
+/// Row `i` holds `min(k, n - 1)` distances in ascending order, excluding point `i` itself.
+pub fn nearest_distances<T, F>(points: &[Vec<T>], k: usize, dist: F) -> Vec<Vec<f64>>
+where
+    F: Fn(&[T], &[T]) -> f64,
+{
+    let n = points.len();
+    let k = k.min(n.saturating_sub(1));
+    (0..n)
+        .map(|i| {
+            let mut row: Vec<f64> = (0..n)
+                .filter(|&j| j != i)
+                .map(|j| dist(&points[i], &points[j]))
+                .collect();
+            if k > 0 && k < row.len() {
+                row.select_nth_unstable_by(k - 1, |a, b| a.total_cmp(b));
+            }
+            row.truncate(k);
+            row.sort_by(|a, b| a.total_cmp(b));
+            row
+        })
+        .collect()
+}
+
+/// All pairs `(i, j, cos)` with `i < j` and cosine similarity `>= threshold`.
+///
+/// Zero vectors (undefined cosine) never match. O(n²·d).
+pub fn near_duplicate_pairs<T: Element>(
+    points: &[Vec<T>],
+    threshold: f64,
+) -> Vec<(usize, usize, f64)> {
+    let norms: Vec<f64> = points.iter().map(|p| T::norm(p)).collect();
+    let mut out = Vec::new();
+    for i in 0..points.len() {
+        if norms[i] == 0.0 {
+            continue;
+        }
+        for j in (i + 1)..points.len() {
+            if norms[j] == 0.0 {
+                continue;
+            }
+            let c = T::dot(&points[i], &points[j]) / (norms[i] * norms[j]);
+            if c >= threshold {
+                out.push((i, j, c));
+            }
         }
     }
-    let m = mean(&vals);
-    let s = sample_std_dev(&vals, m);
-    let lo = vals.iter().copied().fold(f64::INFINITY, f64::min);
-    let hi = vals.iter().copied().fold(f64::NEG_INFINITY, f64::max);
-    (m, lo, hi, s)
+    out
 }
 
 /// Redundancy = average pairwise cosine; Dispersion = 1 - mean cosine.
@@ -76,6 +278,78 @@ pub fn redundancy_and_dispersion(points: &[Vec<f64>]) -> (f64, f64) {
     (mean_cos, 1.0 - mean_cos)
 }
 
+/// Sparse vector stored as index-sorted, de-duplicated `(index, value)` pairs
+/// (e.g. BM25 term weights or SPLADE activations).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SparseVector {
+    indices: Vec<u32>,
+    values: Vec<f64>,
+}
+
+impl SparseVector {
+    /// Build from parallel index/value lists; duplicate indices are summed.
+    /// Panics if the lengths differ.
+    pub fn new(indices: Vec<u32>, values: Vec<f64>) -> Self {
+        assert_eq!(indices.len(), values.len());
+        let mut pairs: Vec<(u32, f64)> = indices.into_iter().zip(values).collect();
+        pairs.sort_by_key(|&(i, _)| i);
+        let mut out = Self::default();
+        for (i, v) in pairs {
+            if out.indices.last() == Some(&i) {
+                *out.values.last_mut().unwrap() += v;
+            } else {
+                out.indices.push(i);
+                out.values.push(v);
+            }
+        }
+        out
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// Number of stored entries.
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+/// Dot product of two sparse vectors (merge join, O(nnz_a + nnz_b)).
+pub fn sparse_dot(a: &SparseVector, b: &SparseVector) -> f64 {
+    let (mut i, mut j, mut s) = (0, 0, 0.0);
+    while i < a.indices.len() && j < b.indices.len() {
+        match a.indices[i].cmp(&b.indices[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                s += a.values[i] * b.values[j];
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    s
+}
+
+pub fn sparse_l2_norm(a: &SparseVector) -> f64 {
+    a.values.iter().map(|v| v * v).sum::<f64>().sqrt()
+}
+
+/// Cosine similarity of two sparse vectors; NaN if either has zero norm.
+pub fn sparse_cosine_similarity(a: &SparseVector, b: &SparseVector) -> f64 {
+    let na = sparse_l2_norm(a);
+    let nb = sparse_l2_norm(b);
+    if na == 0.0 || nb == 0.0 {
+        return f64::NAN;
+    }
+    sparse_dot(a, b) / (na * nb)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -125,6 +399,83 @@ mod tests {
         let labels = vec![0usize, 0, 1, 1];
         approx!(silhouette_cosine(&points, &labels), 1.0, EPS);
     }
+
+    #[test]
+    fn pairwise_cosine_stats_match_the_materialised_pairs() {
+        let pts: Vec<Vec<f64>> = (0..60)
+            .map(|i| {
+                let t = i as f64 * 0.37;
+                vec![t.sin(), t.cos(), (t * 0.5).sin() + 0.1]
+            })
+            .collect();
+        let mut vals = Vec::new();
+        for i in 0..pts.len() {
+            for j in (i + 1)..pts.len() {
+                vals.push(cosine_similarity(&pts[i], &pts[j]));
+            }
+        }
+        let (m, lo, hi, s) = pairwise_cosine_stats(&pts);
+        approx!(m, mean(&vals), EPS);
+        approx!(s, sample_std_dev(&vals, mean(&vals)), EPS);
+        approx!(lo, min(&vals), EPS_TIGHT);
+        approx!(hi, max(&vals), EPS_TIGHT);
+        approx!(intra_cluster_cosine(&pts), m, EPS_TIGHT);
+    }
+
+    #[test]
+    fn f32_kernels_track_f64() {
+        let a = [0.3_f64, -1.2, 2.5, 0.0];
+        let b = [1.1_f64, 0.4, -0.7, 3.0];
+        let (a32, b32) = (a.map(|x| x as f32), b.map(|x| x as f32));
+        approx!(dot_f32(&a32, &b32) as f64, dot(&a, &b), 1e-5);
+        approx!(
+            euclidean_distance_f32(&a32, &b32) as f64,
+            euclidean_distance(&a, &b),
+            1e-5
+        );
+        approx!(
+            cosine_similarity_f32(&a32, &b32) as f64,
+            cosine_similarity(&a, &b),
+            1e-6
+        );
+        assert!(cosine_similarity_f32(&a32, &[0.0; 4]).is_nan());
+
+        let pts = vec![a.to_vec(), b.to_vec(), vec![1.0, 1.0, 1.0, 1.0]];
+        let pts32: Vec<Vec<f32>> = pts
+            .iter()
+            .map(|p| p.iter().map(|&x| x as f32).collect())
+            .collect();
+        let (m, lo, hi, s) = pairwise_cosine_stats(&pts);
+        let (m32, lo32, hi32, s32) = pairwise_cosine_stats(&pts32);
+        for (x, y) in [(m, m32), (lo, lo32), (hi, hi32), (s, s32)] {
+            approx!(x, y, 1e-6);
+        }
+        assert_eq!(
+            near_duplicate_pairs(&pts32, 0.5).len(),
+            near_duplicate_pairs(&pts, 0.5).len()
+        );
+    }
+
+    #[test]
+    fn sparse_kernels_match_dense() {
+        let a = SparseVector::new(vec![5, 0, 5], vec![1.0, 2.0, 1.0]);
+        assert_eq!(a.indices(), &[0, 5]);
+        assert_eq!(a.values(), &[2.0, 2.0]);
+        assert_eq!(a.nnz(), 2);
+
+        let b = SparseVector::new(vec![3, 5], vec![4.0, 1.0]);
+        let (da, db) = (
+            [2.0, 0.0, 0.0, 0.0, 0.0, 2.0],
+            [0.0, 0.0, 0.0, 4.0, 0.0, 1.0],
+        );
+        approx!(sparse_dot(&a, &b), dot(&da, &db), EPS_TIGHT);
+        approx!(
+            sparse_cosine_similarity(&a, &b),
+            cosine_similarity(&da, &db),
+            EPS_TIGHT
+        );
+        assert!(sparse_cosine_similarity(&a, &SparseVector::default()).is_nan());
+    }
 }
 
 #[cfg(test)]
@@ -188,6 +539,50 @@ mod edge_tests {
         approx!(hi, 0.0, EPS_TIGHT);
     }
 
+    // --- k-NN distances ---
+
+    #[test]
+    fn kth_nn_distances_on_a_line() {
+        let pts = vec![vec![0.0], vec![1.0], vec![3.0], vec![7.0]];
+        approx!(euclidean_distance(&pts[0], &pts[3]), 7.0, EPS_TIGHT);
+
+        let d1 = kth_nn_distances(&pts, 1, euclidean_distance);
+        assert_eq!(d1, vec![1.0, 1.0, 2.0, 4.0]);
+        let d2 = kth_nn_distances(&pts, 2, euclidean_distance);
+        assert_eq!(d2, vec![3.0, 2.0, 3.0, 6.0]);
+
+        let nd = nearest_distances(&pts, 2, euclidean_distance);
+        assert_eq!(nd[0], vec![1.0, 3.0]);
+        assert_eq!(nd[3], vec![4.0, 6.0]);
+        assert_eq!(nearest_distances(&pts, 10, euclidean_distance)[0].len(), 3);
+
+        // k out of range → NaN per point
+        assert!(
+            kth_nn_distances(&pts, 4, euclidean_distance)
+                .iter()
+                .all(|d| d.is_nan())
+        );
+    }
+
+    // --- near duplicates ---
+
+    #[test]
+    fn near_duplicate_pairs_threshold_and_zero_vectors() {
+        let pts = vec![
+            vec![1.0, 0.0],
+            vec![2.0, 0.0], // same direction as 0
+            vec![0.0, 1.0],
+            vec![0.0, 0.0], // zero vector never matches
+        ];
+        let pairs = near_duplicate_pairs(&pts, 0.99);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].0, pairs[0].1), (0, 1));
+        approx!(pairs[0].2, 1.0, EPS_TIGHT);
+
+        // threshold below every similarity → all non-zero pairs
+        assert_eq!(near_duplicate_pairs(&pts, -1.0).len(), 3);
+    }
+
     // --- centroid empty ---
 
     #[test]
@@ -0,0 +1,315 @@
+//! Frequency-weighted statistics: each value `xs[i]` counts `ws[i]` times
+//! (weights may be fractional). With whole-number weights every function
+//! agrees with its unweighted twin on the expanded sample, so pre-binned
+//! `(value, count)` exports need not be expanded.
+//!
+//! Callers check that weights are finite and non-negative.
+
+use super::{corr::g2, density::KDE_CUTOFF};
+use crate::stats::prelude::*;
+
+/// Sum of the weights, the sample size the weighted estimators use.
+pub fn total_weight(ws: &[f64]) -> f64 {
+    compensated_sum(ws.iter().copied())
+}
+
+/// `Σ wᵢxᵢ / Σ wᵢ`; `NaN` when the weights sum to zero.
+pub fn weighted_mean(xs: &[f64], ws: &[f64]) -> f64 {
+    let w = total_weight(ws);
+    if w <= 0.0 {
+        return f64::NAN;
+    }
+    let m = compensated_sum(xs.iter().zip(ws).map(|(&x, &w)| w * x)) / w;
+    if !m.is_finite() {
+        return m;
+    }
+    m + compensated_sum(xs.iter().zip(ws).map(|(&x, &w)| w * (x - m))) / w
+}
+
+/// Weighted standardized third and fourth central moment sums
+/// `Σ wᵢ zᵢ³` and `Σ wᵢ zᵢ⁴`, with `z` scaled by the sample standard deviation
+/// over `W − 1`; `None` when there is no spread.
+fn standardized_moments(xs: &[f64], ws: &[f64], w: f64) -> Option<(f64, f64)> {
+    let m = weighted_mean(xs, ws);
+    let ss = compensated_sum(xs.iter().zip(ws).map(|(&x, &wi)| wi * (x - m) * (x - m)));
+    let s = (ss / (w - 1.0)).sqrt();
+    if s == 0.0 || !s.is_finite() {
+        return None;
+    }
+    let (mut m3, mut m4) = (0.0, 0.0);
+    for (&x, &wi) in xs.iter().zip(ws) {
+        let z = (x - m) / s;
+        m3 += wi * z.powi(3);
+        m4 += wi * z.powi(4);
+    }
+    Some((m3, m4))
+}
+
+/// [`skewness`] with frequency weights (`n` = total weight); `NaN` below a
+/// total weight of 3, `0` with no spread.
+pub fn weighted_skewness(xs: &[f64], ws: &[f64]) -> f64 {
+    let n = total_weight(ws);
+    if n < 3.0 {
+        return f64::NAN;
+    }
+    match standardized_moments(xs, ws, n) {
+        Some((m3, _)) => n * m3 / ((n - 1.0) * (n - 2.0)),
+        None => 0.0,
+    }
+}
+
+/// [`excess_kurtosis`] (G2) with frequency weights (`n` = total weight);
+/// `NaN` below a total weight of 4 or with no spread.
+pub fn weighted_excess_kurtosis(xs: &[f64], ws: &[f64]) -> f64 {
+    let n = total_weight(ws);
+    if n < 4.0 {
+        return f64::NAN;
+    }
+    match standardized_moments(xs, ws, n) {
+        Some((_, m4)) => g2(n, m4),
+        None => f64::NAN,
+    }
+}
+
+/// `(value, weight)` pairs in ascending value order, for
+/// [`weighted_quantile_sorted`] and [`weighted_gaussian_kde_sorted`].
+pub fn sorted_pairs(xs: &[f64], ws: &[f64]) -> Vec<(f64, f64)> {
+    let mut v: Vec<(f64, f64)> = xs.iter().copied().zip(ws.iter().copied()).collect();
+    v.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+    v
+}
+
+/// R-7 quantile of weighted pairs in ascending value order: position
+/// `h = (W − 1) · p` of the sample each value repeated by its weight,
+/// interpolating between the values at `⌊h⌋` and `⌊h⌋ + 1`. `NaN` when the
+/// weights sum to zero.
+pub fn weighted_quantile_sorted(pairs: &[(f64, f64)], p: f64) -> f64 {
+    assert!((0.0..=1.0).contains(&p), "p must be in [0,1]");
+    let w = compensated_sum(pairs.iter().map(|&(_, w)| w));
+    if pairs.is_empty() || w <= 0.0 {
+        return f64::NAN;
+    }
+    let h = ((w - 1.0) * p).max(0.0);
+    let k = h.floor();
+    // value covering expanded position `pos` (0-based)
+    let at = |pos: f64| {
+        let mut cum = 0.0;
+        for &(x, wi) in pairs {
+            cum += wi;
+            if cum > pos {
+                return x;
+            }
+        }
+        pairs
+            .iter()
+            .rev()
+            .find(|&&(_, wi)| wi > 0.0)
+            .map_or(f64::NAN, |&(x, _)| x)
+    };
+    let lo = at(k);
+    let frac = h - k;
+    if frac == 0.0 {
+        lo
+    } else {
+        lo + frac * (at(k + 1.0) - lo)
+    }
+}
+
+/// [`histogram`] with each value counted by its weight: the same edges, and
+/// per-bin weight sums in place of counts.
+pub fn weighted_histogram(xs: &[f64], ws: &[f64], bins: usize) -> (Vec<f64>, Vec<f64>) {
+    if xs.is_empty() {
+        return (vec![], vec![]);
+    }
+    let bins = bins.max(1);
+    let lo = min(xs);
+    let hi = max(xs);
+    let width = (hi - lo) / bins as f64;
+
+    let mut sums = vec![0.0; bins];
+    if width == 0.0 {
+        sums[0] = total_weight(ws);
+    } else {
+        for (&x, &w) in xs.iter().zip(ws) {
+            let b = (((x - lo) / width).floor() as usize).min(bins - 1);
+            sums[b] += w;
+        }
+    }
+    let edges = (0..=bins).map(|i| lo + i as f64 * width).collect();
+    (sums, edges)
+}
+
+/// [`histogram_with_edges`] summing weights: `(per-bin weights, weight
+/// outside the edges)`.
+pub fn weighted_histogram_with_edges(xs: &[f64], ws: &[f64], edges: &[f64]) -> (Vec<f64>, f64) {
+    let bins = edges.len().saturating_sub(1);
+    let mut sums = vec![0.0; bins];
+    let mut outside = 0.0;
+    for (&x, &w) in xs.iter().zip(ws) {
+        match edges.partition_point(|&e| e <= x) {
+            0 => outside += w,
+            i if i <= bins => sums[i - 1] += w,
+            _ if bins > 0 && x == edges[bins] => sums[bins - 1] += w,
+            _ => outside += w,
+        }
+    }
+    (sums, outside)
+}
+
+/// [`log_histogram`] summing weights over the same log-spaced edges.
+pub fn weighted_log_histogram(xs: &[f64], ws: &[f64], bins: usize) -> (Vec<f64>, Vec<f64>) {
+    let (counts, edges) = log_histogram(xs, bins);
+    if edges.first() == edges.last() {
+        let mut sums = vec![0.0; counts.len()];
+        if let Some(first) = sums.first_mut() {
+            *first = total_weight(ws);
+        }
+        return (sums, edges);
+    }
+    (weighted_histogram_with_edges(xs, ws, &edges).0, edges)
+}
+
+/// [`bin_densities`] of per-bin weight sums: `weight / (W · width)`.
+pub fn weighted_bin_densities(sums: &[f64], edges: &[f64]) -> Vec<f64> {
+    let total = total_weight(sums);
+    sums.iter()
+        .zip(edges.windows(2))
+        .map(|(&s, e)| {
+            let width = e[1] - e[0];
+            if width > 0.0 {
+                s / (total * width)
+            } else {
+                f64::NAN
+            }
+        })
+        .collect()
+}
+
+/// [`silverman_bandwidth_sorted`] of weighted pairs in ascending value
+/// order, with the weighted standard deviation and IQR and `n` = total
+/// weight.
+pub fn weighted_silverman_bandwidth_sorted(pairs: &[(f64, f64)]) -> f64 {
+    let (xs, ws): (Vec<f64>, Vec<f64>) = pairs.iter().copied().unzip();
+    let n = total_weight(&ws);
+    if n <= 1.0 {
+        return f64::NAN;
+    }
+    let m = weighted_mean(&xs, &ws);
+    let ss = compensated_sum(xs.iter().zip(&ws).map(|(&x, &w)| w * (x - m) * (x - m)));
+    let sd = (ss / (n - 1.0)).sqrt();
+    let iqr =
+        (weighted_quantile_sorted(pairs, 0.75) - weighted_quantile_sorted(pairs, 0.25)) / 1.34;
+    let spread = if iqr > 0.0 { sd.min(iqr) } else { sd };
+    if spread > 0.0 {
+        0.9 * spread * n.powf(-0.2)
+    } else {
+        f64::NAN
+    }
+}
+
+/// [`gaussian_kde_sorted`] with each kernel scaled by its weight over the
+/// total weight.
+pub fn weighted_gaussian_kde_sorted(pairs: &[(f64, f64)], at: &[f64], bandwidth: f64) -> Vec<f64> {
+    let w = compensated_sum(pairs.iter().map(|&(_, w)| w));
+    if w <= 0.0 || bandwidth.is_nan() || bandwidth <= 0.0 {
+        return vec![f64::NAN; at.len()];
+    }
+    let norm = 1.0 / (w * bandwidth * (2.0 * std::f64::consts::PI).sqrt());
+    at.iter()
+        .map(|&x| {
+            let lo = pairs.partition_point(|&(v, _)| v < x - KDE_CUTOFF * bandwidth);
+            let hi = pairs.partition_point(|&(v, _)| v <= x + KDE_CUTOFF * bandwidth);
+            let sum: f64 = pairs[lo..hi]
+                .iter()
+                .map(|&(v, wi)| {
+                    let u = (x - v) / bandwidth;
+                    wi * (-0.5 * u * u).exp()
+                })
+                .sum();
+            sum * norm
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx;
+
+    /// `xs` with each value repeated by its (whole) weight.
+    fn expand(xs: &[f64], ws: &[f64]) -> Vec<f64> {
+        xs.iter()
+            .zip(ws)
+            .flat_map(|(&x, &w)| std::iter::repeat_n(x, w as usize))
+            .collect()
+    }
+
+    #[test]
+    fn whole_weights_match_the_expanded_sample() {
+        let xs = [3.0, -1.0, 7.5, 2.0, 10.0];
+        let ws = [2.0, 1.0, 4.0, 0.0, 3.0];
+        let full = expand(&xs, &ws);
+
+        approx!(weighted_mean(&xs, &ws), mean(&full), 1e-12);
+        approx!(weighted_skewness(&xs, &ws), skewness(&full), 1e-12);
+        approx!(
+            weighted_excess_kurtosis(&xs, &ws),
+            excess_kurtosis(&full),
+            1e-12
+        );
+        // R: e1071::kurtosis(rep(xs, ws), type = 2)
+        approx!(weighted_excess_kurtosis(&xs, &ws), 0.353_174_603, 1e-9);
+        approx!(
+            weighted_excess_kurtosis(&[1.0, 2.0, 3.0, 4.0, 10.0], &[1.0, 2.0, 3.0, 1.0, 1.0]),
+            5.669_136,
+            1e-6
+        );
+        approx!(
+            weighted_excess_kurtosis(&[1.0, 2.0, 3.0, 4.0], &[1.0; 4]),
+            -1.2,
+            1e-12
+        );
+        let pairs = sorted_pairs(&xs, &ws);
+        let asc = sorted(&full);
+        for p in [0.0, 0.1, 0.25, 0.5, 0.6, 0.95, 1.0] {
+            approx!(
+                weighted_quantile_sorted(&pairs, p),
+                quantile_sorted(&asc, p),
+                1e-12
+            );
+        }
+        approx!(
+            weighted_silverman_bandwidth_sorted(&pairs),
+            silverman_bandwidth_sorted(&asc),
+            1e-12
+        );
+
+        let (sums, edges) = weighted_histogram(&xs, &ws, 4);
+        let (counts, full_edges) = histogram(&full, 4);
+        assert_eq!(edges, full_edges);
+        assert_eq!(sums, counts.iter().map(|&c| c as f64).collect::<Vec<_>>());
+        let at = bin_centers(&edges);
+        let kde = weighted_gaussian_kde_sorted(&pairs, &at, 1.5);
+        for (a, b) in kde.iter().zip(gaussian_kde_sorted(&asc, &at, 1.5)) {
+            approx!(*a, b, 1e-12);
+        }
+    }
+
+    #[test]
+    fn weighted_bins_follow_their_unweighted_edges() {
+        let xs = [1.0, 10.0, 100.0, 0.5];
+        let ws = [0.5, 2.0, 1.5, 4.0];
+        let (sums, outside) = weighted_histogram_with_edges(&xs, &ws, &[1.0, 10.0, 100.0]);
+        assert_eq!((sums, outside), (vec![0.5, 3.5], 4.0));
+
+        let (sums, edges) = weighted_log_histogram(&xs[..3], &ws[..3], 2);
+        assert_eq!(edges, log_histogram(&xs[..3], 2).1);
+        assert_eq!(sums, vec![0.5, 3.5]);
+        let (sums, _) = weighted_log_histogram(&[2.0, 2.0], &[1.5, 1.0], 3);
+        assert_eq!(sums, vec![2.5, 0.0, 0.0]);
+
+        let d = weighted_bin_densities(&[1.0, 3.0], &[0.0, 1.0, 3.0]);
+        approx!(d[0] * 1.0 + d[1] * 2.0, 1.0, 1e-12);
+        assert!(weighted_quantile_sorted(&[(1.0, 0.0)], 0.5).is_nan());
+    }
+}
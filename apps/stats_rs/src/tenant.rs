@@ -0,0 +1,255 @@
+//! # Tenants
+//!
+//! One deployment can serve several teams without them seeing each other's
+//! data. Every API request is assigned a [`Tenant`] by [`middleware`]:
+//!
+//! - with `tenants.api_keys` set, from the `X-Api-Key` header; a missing or
+//!   unknown key is `401`
+//! - otherwise, with `tenants.header` set (e.g. `x-tenant-id`), from that
+//!   header, falling back to [`DEFAULT_TENANT`] when it is absent
+//! - otherwise every request belongs to [`DEFAULT_TENANT`]
+//!
+//! Datasets, jobs (and their idempotency keys) and artifacts are filed under
+//! their tenant; ids of another tenant's resources answer `404` as if they
+//! did not exist. Quotas in [`TenantConfig`](crate::config::TenantConfig)
+//! cap what each tenant holds (`429 quota_exceeded` beyond).
+//!
+//! Stateless analyses, the result caches (keyed by request content) and the
+//! `/admin` routes, which see every tenant, are shared. `/health` and
+//! `/ready` answer without an API key so probes need none. gRPC calls go
+//! through the same rules in the service's interceptor (see `grpc`).
+//!
+//! API keys are compared in constant time, against every configured key, so
+//! response timing does not reveal how much of a guess was right.
+
+use crate::{error::ServiceError, routes::admin::same, state::AppState};
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{HeaderMap, HeaderName, request::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{convert::Infallible, fmt, sync::Arc};
+
+/// Tenant of requests that name none.
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Request header carrying a tenant API key.
+pub const X_API_KEY: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Longest tenant name.
+const MAX_NAME_LEN: usize = 64;
+
+/// Whether `name` can name a tenant: 1 to 64 ASCII letters, digits, `-`,
+/// `_` or `.`, starting with a letter or digit (so it is also a safe path
+/// segment).
+pub fn valid_name(name: &str) -> bool {
+    (1..=MAX_NAME_LEN).contains(&name.len())
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+/// The tenant a request acts for. Extracting it never fails: requests that
+/// bypassed [`middleware`] (tests) belong to [`DEFAULT_TENANT`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tenant(String);
+
+impl Tenant {
+    /// `name`, if it is a [`valid_name`].
+    pub fn new(name: &str) -> Option<Self> {
+        valid_name(name).then(|| Self(name.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for Tenant {
+    fn default() -> Self {
+        Self(DEFAULT_TENANT.into())
+    }
+}
+
+impl fmt::Display for Tenant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Tenant {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Infallible> {
+        Ok(parts
+            .extensions
+            .get::<Tenant>()
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// The tenant a request with `headers` belongs to under the configured rules.
+pub fn resolve(state: &AppState, headers: &HeaderMap) -> Result<Tenant, ServiceError> {
+    let cfg = &state.config.tenants;
+    let header = |name: &HeaderName| headers.get(name).and_then(|v| v.to_str().ok());
+    if !cfg.api_keys.is_empty() {
+        return header(&X_API_KEY)
+            .and_then(|key| {
+                // Every key is compared, so the time taken does not depend on which matched
+                let key = key.trim().as_bytes();
+                cfg.api_keys.iter().fold(None, |hit, (k, t)| {
+                    if same(k.as_bytes(), key) {
+                        Some(t)
+                    } else {
+                        hit
+                    }
+                })
+            })
+            .and_then(|t| Tenant::new(t))
+            .ok_or_else(|| ServiceError::Unauthorized("a valid X-Api-Key is required".into()));
+    }
+    let Some(name) = cfg
+        .header
+        .as_deref()
+        .and_then(|h| HeaderName::try_from(h).ok())
+    else {
+        return Ok(Tenant::default());
+    };
+    match header(&name).map(str::trim) {
+        None | Some("") => Ok(Tenant::default()),
+        Some(t) => Tenant::new(t).ok_or_else(|| {
+            ServiceError::InvalidInput(format!(
+                "{name} must be 1 to {MAX_NAME_LEN} letters, digits, '-', '_' or '.'"
+            ))
+        }),
+    }
+}
+
+/// `QuotaExceeded` unless `tenant` may register one more dataset of `bytes`
+/// bytes under `tenants.max_datasets` / `tenants.max_dataset_bytes`.
+pub fn check_dataset_quota(
+    state: &AppState,
+    tenant: &Tenant,
+    bytes: usize,
+) -> Result<(), ServiceError> {
+    let cfg = &state.config.tenants;
+    let (count, held) = state.datasets.usage(tenant);
+    if cfg.max_datasets > 0 && count >= cfg.max_datasets {
+        return Err(ServiceError::QuotaExceeded(format!(
+            "tenant '{tenant}' already holds {count} datasets (limit {})",
+            cfg.max_datasets
+        )));
+    }
+    if cfg.max_dataset_bytes > 0 && held + bytes > cfg.max_dataset_bytes {
+        return Err(ServiceError::QuotaExceeded(format!(
+            "tenant '{tenant}' holds {held} dataset bytes; {bytes} more exceeds its limit of {}",
+            cfg.max_dataset_bytes
+        )));
+    }
+    Ok(())
+}
+
+/// `QuotaExceeded` if `tenant` already has `tenants.max_jobs` jobs queued or
+/// running.
+pub fn check_job_quota(state: &AppState, tenant: &Tenant) -> Result<(), ServiceError> {
+    let max = state.config.tenants.max_jobs;
+    let active = state.jobs.active(tenant);
+    if max > 0 && active >= max {
+        return Err(ServiceError::QuotaExceeded(format!(
+            "tenant '{tenant}' already has {active} jobs queued or running (limit {max})"
+        )));
+    }
+    Ok(())
+}
+
+/// Axum middleware attaching the request's [`Tenant`], or refusing it.
+pub async fn middleware(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let (mut parts, body) = req.into_parts();
+    let tenant = match resolve(&state, &parts.headers) {
+        Ok(tenant) => tenant,
+        Err(_) if matches!(parts.uri.path(), "/health" | "/ready") => Tenant::default(),
+        Err(e) => return e.into_response(),
+    };
+    parts.extensions.insert(tenant);
+    next.run(Request::from_parts(parts, body)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ServiceConfig, TenantConfig};
+
+    fn parts(headers: &[(&str, &str)]) -> HeaderMap {
+        let mut req = axum::http::Request::builder();
+        for (k, v) in headers {
+            req = req.header(*k, *v);
+        }
+        req.body(()).unwrap().into_parts().0.headers
+    }
+
+    fn state(tenants: TenantConfig) -> AppState {
+        AppState {
+            config: ServiceConfig {
+                tenants,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn names_are_path_safe() {
+        for ok in ["team-a", "A1", "ml_platform.eu"] {
+            assert!(valid_name(ok), "{ok}");
+        }
+        for bad in ["", ".", "..", "-x", "a/b", "a b", &"x".repeat(65)] {
+            assert!(!valid_name(bad), "{bad}");
+        }
+    }
+
+    #[test]
+    fn tenants_come_from_keys_or_the_header() {
+        let open = state(TenantConfig::default());
+        assert_eq!(
+            resolve(&open, &parts(&[])).unwrap().as_str(),
+            DEFAULT_TENANT
+        );
+
+        let by_header = state(TenantConfig {
+            header: Some("x-tenant-id".into()),
+            ..Default::default()
+        });
+        let t = resolve(&by_header, &parts(&[("x-tenant-id", "team-a")])).unwrap();
+        assert_eq!(t.as_str(), "team-a");
+        assert_eq!(resolve(&by_header, &parts(&[])).unwrap(), Tenant::default());
+        assert!(matches!(
+            resolve(&by_header, &parts(&[("x-tenant-id", "../etc")])),
+            Err(ServiceError::InvalidInput(_))
+        ));
+
+        let by_key = state(TenantConfig {
+            header: Some("x-tenant-id".into()),
+            api_keys: [("k-b".to_string(), "team-b".to_string())].into(),
+            ..Default::default()
+        });
+        let t = resolve(
+            &by_key,
+            &parts(&[("x-api-key", "k-b"), ("x-tenant-id", "team-a")]),
+        );
+        assert_eq!(t.unwrap().as_str(), "team-b");
+        for headers in [
+            &[][..],
+            &[("x-api-key", "nope")],
+            &[("x-api-key", "k-")],
+            &[("x-api-key", "k-bb")],
+        ] {
+            assert!(matches!(
+                resolve(&by_key, &parts(headers)),
+                Err(ServiceError::Unauthorized(_))
+            ));
+        }
+    }
+}
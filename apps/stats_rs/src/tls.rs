@@ -0,0 +1,117 @@
+//! # HTTPS serving (feature `tls`)
+//!
+//! For simple deployments without a TLS-terminating proxy, `main.rs` serves
+//! the app over HTTPS when [`TlsConfig`] names a certificate and key, and can
+//! answer a second, plain-HTTP port with redirects to the HTTPS URL
+//! ([`redirect_app`]). Certificates are read once at startup; rotating them
+//! needs a restart.
+
+use crate::config::TlsConfig;
+use axum::{
+    Router,
+    extract::Request,
+    http::{StatusCode, Uri, header},
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::{
+    ServerConfig,
+    crypto::ring,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+};
+use std::{io, sync::Arc};
+
+/// rustls settings for the configured certificate and key, offering HTTP/2
+/// and HTTP/1.1.
+pub fn rustls_config(cfg: &TlsConfig) -> io::Result<RustlsConfig> {
+    let (Some(cert), Some(key)) = (&cfg.cert_path, &cfg.key_path) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "TLS needs both a certificate and a key",
+        ));
+    };
+    let pem = |path: &str, e: rustls::pki_types::pem::Error| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{path}: {e}"))
+    };
+    let chain = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| pem(cert, e))?;
+    let key_der = PrivateKeyDer::from_pem_file(key).map_err(|e| pem(key, e))?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|b| b.with_no_client_auth().with_single_cert(chain, key_der))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(RustlsConfig::from_config(Arc::new(config)))
+}
+
+/// A router sending every request to the same path on `https://<host>:<https_port>`
+/// with `308 Permanent Redirect`, which keeps the method and body.
+pub fn redirect_app(https_port: u16) -> Router {
+    Router::new().fallback(move |req: Request| async move { redirect(&req, https_port) })
+}
+
+fn redirect(req: &Request, https_port: u16) -> Response {
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .or_else(|| req.uri().host());
+    match host.and_then(|h| https_url(h, https_port, req.uri())) {
+        Some(url) => Redirect::permanent(&url).into_response(),
+        None => (StatusCode::BAD_REQUEST, "missing or invalid Host header").into_response(),
+    }
+}
+
+/// `https://` URL of `uri` on `host` (any port dropped) at `https_port`.
+pub fn https_url(host: &str, https_port: u16, uri: &Uri) -> Option<String> {
+    let name = match host.strip_prefix('[') {
+        Some(v6) => &host[..v6.find(']')? + 2],
+        None => host.split(':').next()?,
+    };
+    if name.is_empty() || name.contains(['/', '?', '#', '@']) {
+        return None;
+    }
+    let port = match https_port {
+        443 => String::new(),
+        p => format!(":{p}"),
+    };
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+    Some(format!("https://{name}{port}{path}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirects_keep_host_path_and_query() {
+        let uri: Uri = "/api/v1/health?x=1".parse().unwrap();
+        assert_eq!(
+            https_url("stats.example.com:80", 443, &uri).as_deref(),
+            Some("https://stats.example.com/api/v1/health?x=1")
+        );
+        assert_eq!(
+            https_url("[::1]:8080", 9443, &uri).as_deref(),
+            Some("https://[::1]:9443/api/v1/health?x=1")
+        );
+        assert_eq!(
+            https_url("localhost", 9443, &Uri::from_static("/")).as_deref(),
+            Some("https://localhost:9443/")
+        );
+        assert!(https_url("", 443, &uri).is_none());
+        assert!(https_url("evil.com/x", 443, &uri).is_none());
+    }
+
+    #[test]
+    fn missing_files_are_reported() {
+        let cfg = TlsConfig {
+            cert_path: Some("/nonexistent/cert.pem".into()),
+            key_path: Some("/nonexistent/key.pem".into()),
+            redirect_port: None,
+        };
+        let err = rustls_config(&cfg).unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/cert.pem"), "{err}");
+    }
+}
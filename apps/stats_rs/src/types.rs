@@ -3,12 +3,23 @@
 //! This module defines all request and response payloads exchanged
 //! between clients and the `stats_rs` microservice.
 //!
-//! Each struct derives [`Serialize`], [`Deserialize`], and [`JsonSchema`],
-//! allowing automatic JSON (de)serialization and OpenAPI schema generation.
+//! Each struct derives [`Serialize`], [`Deserialize`], [`JsonSchema`] (for
+//! the `/schema/*` endpoints) and [`ToSchema`] (for `/openapi.json`), allowing
+//! automatic JSON (de)serialization and schema generation. Query-string
+//! options derive [`IntoParams`] instead, so each field is documented as a
+//! query parameter.
 //!
 //! The models are grouped by their corresponding endpoints:
-//! - `/describe` and `/describe-csv` → [`DescribeInput`], [`DescribeOutput`]
-//! - `/stats/summary` → [`SummaryIn`], [`SummaryOut`]
+//! - `/describe` and `/describe-csv` → [`DescribeInput`], [`DescribeOutput`],
+//!   [`CsvQuery`], [`ColumnSchema`]
+//! - `/ingest/ndjson` → [`NdjsonIngestOut`], [`NdjsonFieldOut`]
+//! - `/schema/infer` → [`CsvQuery`], [`SchemaInferQuery`], [`SchemaInferOut`]
+//! - `/profile` → [`CsvQuery`], [`ProfileQuery`], [`ProfileOut`]
+//! - `/ingest/url` → [`IngestUrlIn`], [`DatasetOut`] (feature `fetch`)
+//! - `/datasets`, `/datasets/{id}` → [`DatasetOut`]
+//! - `/datasets/{id}/columns/{column}/distribution` → [`ColumnDistQuery`], [`DistOut`]
+//! - `/describe-xlsx` → [`CsvQuery`], [`DescribeOutput`] (feature `xlsx`)
+//! - `/stats/summary` and `/stats/summary-xlsx` → [`SummaryIn`], [`SummaryOut`]
 //! - `/stats/distribution` → [`DistIn`], [`DistOut`]
 //! - `/stats/pairwise` → [`PairIn`], [`PairOut`]
 //! - `/stats/ecdf` → [`EcdfIn`], [`EcdfOut`]
@@ -16,23 +27,159 @@
 //! - `/stats/corr-matrix` → [`CorrMatrixIn`], [`CorrMatrixOut`]
 //! - `/stats/outliers` → [`OutliersIn`], [`OutliersOut`]
 //! - `/stats/normalize` → [`NormalizeIn`], [`NormalizeOut`]
+//! - `/stats/normalize/apply`, `/stats/normalize/inverse` → [`NormApplyIn`], [`NormalizeOut`]
 //! - `/stats/binrule` → [`BinRuleIn`], [`BinRuleOut`]
+//! - `/stats/entropy` → [`EntropyIn`], [`EntropyOut`]
+//! - `/stats/contingency` → [`ContingencyIn`], [`ContingencyOut`]
+//! - `/stats/seasonality` → [`SeasonalityIn`], [`SeasonalityOut`]
+//! - `/stats/anomaly/score` → [`AnomalyIn`], [`AnomalyOut`]
+//! - `/stats/compare` → [`CompareIn`], [`CompareOut`]
+//! - `/stats/drift` → [`DriftIn`], [`DriftOut`]
+//! - `/stats/generate` → [`GenerateIn`], [`GenerateOut`]
+//! - `/stats/simulate` → [`SimulateIn`], [`SimPipeline`], [`SimulateOut`]
+//! - `/stats/ttest` → [`TTestIn`], [`TTestOut`]
+//! - `/stats/tests`, `/stats/tests/recommend` → [`TestCatalogOut`], [`RecommendIn`],
+//!   [`RecommendOut`]
+//! - `/stats/resample` → [`CsvQuery`], [`ResampleQuery`], [`ResampleOut`]
+//! - `/stats/vector/knn-distances` → [`KnnDistIn`], [`KnnDistOut`]
+//! - `/stats/vector/intrinsic-dim` → [`IntrinsicDimIn`], [`IntrinsicDimOut`]
+//! - `/stats/vector/near-duplicates` → [`NearDupIn`], [`NearDupOut`]
+//! - `/stats/vector/similarity` → [`SimilarityIn`], [`SimilarityOut`]
+//! - `/jobs`, `/jobs/{id}`, `/jobs/{id}/result` → [`JobIn`], [`JobOut`], and
+//!   [`BootstrapOut`], [`PermutationOut`] or [`CorrMatrixOut`] as results
+//! - `/artifacts/{id}` → [`ArtifactOut`] (stored large results)
+//! - `/stats/rag/metrics` → [`RagMetricsIn`], [`RagMetricsOut`] (feature `rag`)
+//! - `/stats/rag/mmr` → [`MmrIn`], [`MmrOut`] (feature `rag`)
+//! - `/stats/rag/text-metrics` → [`TextMetricsIn`], [`TextMetricsOut`] (feature `rag`)
+//! - `/stats/rag/groundedness` → [`GroundednessIn`], [`GroundednessOut`] (feature `rag`)
+//! - `/admin/*` → [`CacheFlushOut`], [`DatasetsClearedOut`], [`LogLevelIo`],
+//!   [`JobsDrainedOut`] (when an admin token is configured)
+//!
+//! Embedding fields on the vector/RAG inputs also accept base64 little-endian
+//! `f32` strings in place of number arrays (see [`crate::embedding`]); the
+//! point-set vector inputs take an [`EmbeddingDtype`] to compute in `f32`.
+//!
+//! Number arrays on `/describe` and the non-vector `/stats/*` inputs may hold
+//! `null` for missing values, settled by a [`MissingPolicy`] and reported back
+//! as a [`MissingReport`] (see [`crate::missing`]).
 //!
 //! These definitions are used by both the backend (Axum routes) and
 //! the frontend contracts (e.g., via `@your-scope/contracts`).
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
 
 /// ---- `/api/v1/describe` and `/api/v1/describe-csv` ----
 /// Request body for basic descriptive statistics.
 ///
-/// Accepts a vector of numeric values (from JSON or parsed CSV column).
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
-pub struct DescribeInput(#[schemars(description = "Array of numbers to summarize")] pub Vec<f64>);
+/// Accepts a vector of numeric values (from JSON or parsed CSV column);
+/// `null` entries are missing values.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
+#[schema(value_type = Vec<Option<f64>>)]
+pub struct DescribeInput(
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(
+        with = "Vec<Option<f64>>",
+        description = "Array of numbers to summarize (`null` = missing)"
+    )]
+    pub Vec<f64>,
+);
+
+/// Query options for `/describe`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DescribeQuery {
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+    /// Add `min`, `max`, `quartiles`, `modes`, `mad` and `trimmed_mean`
+    #[serde(default)]
+    pub robust: Option<bool>,
+    /// Proportion trimmed from each tail for `trimmed_mean`, in `[0, 0.5)`
+    /// (default 0.1)
+    #[serde(default)]
+    pub trim: Option<f64>,
+}
+
+/// How missing (`null`) entries in a numeric array are handled.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingPolicy {
+    /// Reject the request (`400`, code `nan`)
+    Error,
+    /// Remove missing entries; paired series drop the whole position
+    #[default]
+    Drop,
+    /// Replace with the mean of the observed values
+    ImputeMean,
+    /// Replace with the median of the observed values
+    ImputeMedian,
+    /// Replace with `0`
+    ImputeZero,
+    /// Like `drop`, but a correlation matrix keeps for each pair of series
+    /// every position both observe (pairwise-complete deletion)
+    Pairwise,
+}
+
+/// Missing values found in the request and the policy applied to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct MissingReport {
+    pub policy: MissingPolicy,
+    /// Number of missing entries that were dropped or imputed
+    pub count: usize,
+}
+
+/// How `max_points` thins an output array.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Downsample {
+    /// Evenly spaced points, first and last included
+    #[default]
+    Uniform,
+    /// Largest-triangle-three-buckets: keeps peaks and bends of the curve
+    Lttb,
+}
+
+/// Paging and downsampling of an endpoint's per-point output arrays.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct WindowIn {
+    /// First output point to return (default 0)
+    #[serde(default)]
+    pub offset: Option<usize>,
+    /// Most points to return from `offset` (default: all)
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Downsample the selected points to at most this many (≥ 2)
+    #[serde(default)]
+    pub max_points: Option<usize>,
+    /// Downsampling method (default `uniform`)
+    #[serde(default)]
+    pub downsample: Option<Downsample>,
+}
+
+/// Which part of the full output a windowed response holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct WindowOut {
+    /// Points in the full output
+    pub total: usize,
+    /// Position of the page in the full output
+    pub offset: usize,
+    /// Points in the page before downsampling
+    pub selected: usize,
+    /// Points returned
+    pub returned: usize,
+    /// Method that thinned the page, if `max_points` did
+    pub downsample: Option<Downsample>,
+}
 
 /// Response body containing common summary statistics.
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
 pub struct DescribeOutput {
     /// Number of observations (`n`)
     pub count: usize,
@@ -42,18 +189,520 @@ pub struct DescribeOutput {
     pub median: f64,
     /// Sample standard deviation (n−1). Returns 0.0 if `count < 2`
     pub std_dev: f64,
+    /// Smallest value (`robust=true` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Largest value (`robust=true` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// `[Q1, Q2, Q3]`, R-7 interpolated (`robust=true` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quartiles: Option<[f64; 3]>,
+    /// Most frequent values, ascending and at most ten; empty when no value
+    /// repeats (`robust=true` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modes: Option<Vec<f64>>,
+    /// Median absolute deviation (`robust=true` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mad: Option<f64>,
+    /// Mean after trimming `trim` of the values from each tail (`robust=true` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trimmed_mean: Option<f64>,
+    /// Inferred CSV schema of the selected columns (`/describe-csv` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Vec<ColumnSchema>>,
+    /// Missing-value handling (`/describe` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+    /// Row sample that was analyzed (`?sample=N` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SampleOut>,
+    /// Set when `median` was read from a value sample (`/describe-csv` with
+    /// more than 1,048,576 numeric cells)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approx: Option<ApproxOut>,
+}
+
+/// Query options for CSV ingestion (e.g. `?columns=price,qty&skip_rows=2&infer=int,float`).
+#[derive(Debug, Clone, Default, Hash, Deserialize, Serialize, JsonSchema, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CsvQuery {
+    /// Comma-separated column names or 0-based indices to keep (defaults to all)
+    #[serde(default)]
+    pub columns: Option<String>,
+    /// Number of leading rows to skip before the header/data
+    #[serde(default)]
+    pub skip_rows: Option<usize>,
+    /// Whether the first (non-skipped) row is a header; auto-detected when omitted
+    #[serde(default)]
+    pub has_header: Option<bool>,
+    /// Comma-separated types to infer: `int`, `float`, `bool`, `date` (defaults to all;
+    /// `none` reads every column as string)
+    #[serde(default)]
+    pub infer: Option<String>,
+    /// Field separator: `,` (default), `;`, `|`, `tab`, or another punctuation character
+    #[serde(default)]
+    pub delimiter: Option<String>,
+    /// Quote character (default `"`); `none` disables quoting
+    #[serde(default)]
+    pub quote: Option<String>,
+    /// Decimal mark: `.` (default) or `,` (then `.` is read as a thousands separator)
+    #[serde(default)]
+    pub decimal: Option<String>,
+    /// Worksheet name for spreadsheet uploads (defaults to the first sheet; ignored for CSV)
+    #[serde(default)]
+    pub sheet: Option<String>,
+    /// Analyze a uniform random sample of at most this many data rows
+    #[serde(default)]
+    pub sample: Option<usize>,
+    /// Seed for `sample` (default `STATS_SEED`); the same seed draws the same rows
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Row sample drawn for a `?sample=N` request.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SampleOut {
+    pub seed: u64,
+    /// Data rows read from the payload
+    pub rows_seen: usize,
+    /// Data rows analyzed
+    pub rows_sampled: usize,
+    /// `rows_sampled / rows_seen` (1 when nothing was dropped)
+    pub fraction: f64,
+}
+
+/// Inferred column type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    Integer,
+    Float,
+    Boolean,
+    /// Date or datetime (ISO-8601 and common formats, see `ingest::datetime`)
+    Date,
+    String,
+    /// Every cell is empty
+    Empty,
+}
+
+/// Echoed schema entry for one ingested column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ColumnSchema {
+    /// Header name (or `column_<i>` without a header)
+    pub name: String,
+    /// 0-based position in the source file
+    pub index: usize,
+    pub dtype: ColumnType,
+    /// Number of non-empty cells
+    pub non_empty: usize,
+    /// Number of empty cells
+    pub missing: usize,
+}
+
+/// ---- `/api/v1/ingest/ndjson` ----
+/// Streaming summary of one NDJSON record field.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct NdjsonFieldOut {
+    /// Field name
+    pub name: String,
+    /// Inferred type (`string` when values of several kinds were seen)
+    pub dtype: ColumnType,
+    /// Records containing the field (including explicit `null`)
+    pub present: u64,
+    /// Records where the field is absent or `null`
+    pub missing: u64,
+    /// Number of numeric values
+    pub numeric_count: u64,
+    pub mean: Option<f64>,
+    /// Sample standard deviation (None if fewer than 2 numeric values)
+    pub std_dev: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// Result of ingesting an NDJSON stream.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct NdjsonIngestOut {
+    /// Number of records (non-blank lines)
+    pub records: u64,
+    /// Per-field summaries, sorted by name
+    pub fields: Vec<NdjsonFieldOut>,
+}
+
+/// ---- `/api/v1/schema/infer` ----
+/// Sampling options for schema inference (alongside the [`CsvQuery`] options).
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SchemaInferQuery {
+    /// Data rows to inspect (default 1000)
+    #[serde(default)]
+    pub sample_rows: Option<usize>,
+    /// Distinct example values to return per column (default 5)
+    #[serde(default)]
+    pub examples: Option<usize>,
+}
+
+/// Inferred type, missingness, examples and range of one column.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct InferredColumn {
+    #[serde(flatten)]
+    pub schema: ColumnSchema,
+    /// Share of sampled rows with an empty cell
+    pub null_rate: f64,
+    /// First distinct non-empty values, in file order
+    pub examples: Vec<String>,
+    /// Smallest value (integer/float columns only)
+    pub min: Option<f64>,
+    /// Largest value (integer/float columns only)
+    pub max: Option<f64>,
+}
+
+/// Result of `/schema/infer`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SchemaInferOut {
+    /// Data rows inspected
+    pub rows_sampled: usize,
+    /// More rows followed the sample
+    pub truncated: bool,
+    pub columns: Vec<InferredColumn>,
+    /// Row sample that was analyzed (`?sample=N` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SampleOut>,
+}
+
+/// ---- `/api/v1/profile` ----
+/// Report options for `/profile` (alongside the [`CsvQuery`] options).
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProfileQuery {
+    /// Histogram bins for numeric columns (default 10, min 2)
+    #[serde(default)]
+    pub bins: Option<usize>,
+    /// Most frequent values to list per column (default 5)
+    #[serde(default)]
+    pub top: Option<usize>,
+}
+
+/// ---- `/api/v1/report` ----
+/// Document format of `/report`.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    /// Self-contained HTML page (inline styles, no scripts)
+    #[default]
+    Html,
+    /// GitHub-flavoured Markdown
+    Markdown,
+}
+
+/// Report options for `/report` (alongside the [`CsvQuery`] options).
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportQuery {
+    /// `html` (default) or `markdown`
+    #[serde(default)]
+    #[param(inline)]
+    pub format: Option<ReportFormat>,
+    /// Report on a registered dataset instead of the CSV body
+    #[serde(default)]
+    pub dataset: Option<String>,
+    /// Histogram bins for numeric columns (default 10; must be in `2..=10000`)
+    #[serde(default)]
+    pub bins: Option<usize>,
+    /// Document title (defaults to the dataset name or "Dataset report")
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Store the document as an artifact and answer `201` with its
+    /// [`ArtifactOut`] instead
+    #[serde(default)]
+    pub store: Option<bool>,
+}
+
+/// A value and how often it occurs.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Summary and histogram of an integer/float column.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct NumericProfile {
+    pub mean: f64,
+    /// Sample standard deviation (`null` for a single value)
+    pub std_dev: Option<f64>,
+    pub min: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    pub max: f64,
+    pub skewness: Option<f64>,
+    pub excess_kurtosis: Option<f64>,
+    pub zeros: usize,
+    /// Histogram bin counts
+    pub counts: Vec<usize>,
+    /// Histogram bin edges (`counts.len() + 1`)
+    pub edges: Vec<f64>,
+}
+
+/// Per-column section of a profiling report.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ColumnProfile {
+    #[serde(flatten)]
+    pub schema: ColumnSchema,
+    /// Share of rows with an empty cell
+    pub null_rate: f64,
+    /// Distinct non-empty values
+    pub distinct: usize,
+    /// Most frequent non-empty values, most common first
+    pub top: Vec<ValueCount>,
+    /// Present for integer/float columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numeric: Option<NumericProfile>,
+}
+
+/// Data-quality issue flagged by `/profile`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileWarningKind {
+    /// Every row is empty
+    AllMissing,
+    /// A single distinct value
+    Constant,
+    /// Text column whose values are mostly unique
+    HighCardinality,
+    /// `|skewness|` above 2
+    HighSkew,
+}
+
+/// One flagged issue.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ProfileWarning {
+    pub column: String,
+    pub kind: ProfileWarningKind,
+    pub message: String,
+}
+
+/// pandas-profiling-style report for an uploaded table.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ProfileOut {
+    pub n_rows: usize,
+    pub n_columns: usize,
+    /// Total share of empty cells across the selected columns
+    pub missing_rate: f64,
+    pub columns: Vec<ColumnProfile>,
+    /// Pearson correlations between numeric columns (pairwise-complete rows)
+    pub correlations: CorrMatrixOut,
+    pub warnings: Vec<ProfileWarning>,
+    /// Row sample that was analyzed (`?sample=N` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SampleOut>,
+}
+
+/// ---- `/api/v1/ingest/url` and `/api/v1/datasets` ----
+/// Storage format of a registered dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DatasetFormat {
+    Csv,
+    /// Apache Parquet (feature `parquet`)
+    Parquet,
+}
+
+/// Request to download a dataset from an allowlisted HTTP(S) URL.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct IngestUrlIn {
+    /// Source URL, `http(s)://` or `s3://bucket/key` (feature `s3`); must match
+    /// `STATS_URL_ALLOWLIST`
+    pub url: String,
+    /// Label for the dataset (defaults to the URL's file name)
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Format override (otherwise from the path extension or `Content-Type`)
+    #[serde(default)]
+    pub format: Option<DatasetFormat>,
+    /// Column selection / inference options, as for `/describe-csv`
+    #[serde(flatten)]
+    pub options: CsvQuery,
+}
+
+/// Metadata of a registered dataset.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct DatasetOut {
+    /// Server-assigned id (e.g. `ds_1`)
+    pub id: String,
+    pub name: String,
+    /// Origin of the data (e.g. the fetched URL)
+    pub source: String,
+    pub format: DatasetFormat,
+    /// Size of the original payload in bytes
+    pub bytes: usize,
+    /// Number of data rows
+    pub rows: usize,
+    /// Registration time (seconds since the Unix epoch)
+    pub created_at: u64,
+    /// Inferred column schema
+    pub columns: Vec<ColumnSchema>,
+    /// Set when only a row sample was registered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SampleOut>,
+    /// Owning tenant; only listed by `/admin/datasets`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+}
+
+/// Options of `GET /datasets/{id}/columns/{column}/distribution`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ColumnDistQuery {
+    /// Histogram bins (default 10; must be in `2..=10000`)
+    #[serde(default)]
+    pub bins: Option<usize>,
+    /// Comma-separated probabilities (default `0.25,0.5,0.75`)
+    #[serde(default)]
+    pub quantiles: Option<String>,
+    /// Definition of `quantiles` (default `r7`)
+    #[serde(default)]
+    #[param(inline)]
+    pub quantile_method: Option<QuantileType>,
+}
+
+/// A series appended to the correlation matrix stored for a dataset.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct CorrSeriesIn {
+    /// Label of the new row/column; must not name an existing series
+    pub name: String,
+    /// One value per observation already in the matrix (no `null`s)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<f64>")]
+    #[schema(value_type = Vec<f64>)]
+    pub values: Vec<f64>,
+}
+
+/// Observations appended to every series of a dataset's stored correlation
+/// matrix.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct CorrRowsIn {
+    /// New observations, each with one value per series in matrix order
+    /// (no `null`s)
+    #[serde(deserialize_with = "crate::missing::series")]
+    #[schemars(with = "Vec<Vec<f64>>")]
+    #[schema(value_type = Vec<Vec<f64>>)]
+    pub rows: Vec<Vec<f64>>,
 }
 
 /// ---- `/api/v1/stats/summary` ----
+/// Sample quantile definition (Hyndman & Fan type, as R's
+/// `quantile(type = …)`).
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum QuantileType {
+    /// Inverse of the empirical CDF
+    R1,
+    /// Inverse of the empirical CDF, averaging at discontinuities (SAS 5)
+    R2,
+    /// Nearest order statistic, ties to the even one (SAS 2)
+    R3,
+    /// Linear interpolation of the empirical CDF (SAS 1)
+    R4,
+    /// Piecewise linear through the midpoints of the ECDF steps
+    R5,
+    /// `p(k) = k / (n + 1)` (Minitab, SPSS, SAS 4)
+    R6,
+    /// `p(k) = (k − 1) / (n − 1)` (R, NumPy and Excel default)
+    #[default]
+    R7,
+    /// `p(k) = (k − 1/3) / (n + 1/3)`, approximately median-unbiased
+    R8,
+    /// `p(k) = (k − 3/8) / (n + 1/4)`, approximately unbiased for normal data
+    R9,
+    /// The value at rank `⌈p · n⌉`; the same definition as `r1`
+    NearestRank,
+}
+
+impl From<QuantileType> for crate::stats::QuantileMethod {
+    fn from(t: QuantileType) -> Self {
+        match t {
+            QuantileType::R1 => Self::R1,
+            QuantileType::R2 => Self::R2,
+            QuantileType::R3 => Self::R3,
+            QuantileType::R4 => Self::R4,
+            QuantileType::R5 => Self::R5,
+            QuantileType::R6 => Self::R6,
+            QuantileType::R7 => Self::R7,
+            QuantileType::R8 => Self::R8,
+            QuantileType::R9 => Self::R9,
+            QuantileType::NearestRank => Self::NearestRank,
+        }
+    }
+}
+
 /// Input for summary statistics endpoint.
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
 pub struct SummaryIn {
-    /// Array of numeric values (NaN/Inf ignored server-side)
+    /// Array of numeric values (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
     pub values: Vec<f64>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+    /// Estimate `median`, `iqr` and `mad` from a fixed-size sample when
+    /// `values` is larger than it (see [`ApproxOut`])
+    #[serde(default)]
+    pub approx: Option<bool>,
+    /// Seed of the `approx` sample (default `STATS_SEED`)
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Extra percentiles to report, each in `[0, 100]` (e.g. `[1, 5, 95, 99]`)
+    #[serde(default)]
+    pub percentiles: Option<Vec<f64>>,
+    /// Definition of `percentiles` (default `r7`)
+    #[serde(default)]
+    pub quantile_method: Option<QuantileType>,
+}
+
+/// How an `approx: true` response was computed; absent when every value
+/// was used.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ApproxOut {
+    /// Values in the uniform sample the estimates were read from
+    pub sample_size: usize,
+    /// Values in the input
+    pub population: usize,
+    /// Bound on the rank error of sampled quantiles/ECDF values (DKW), e.g.
+    /// `0.009` means a reported median lies between the exact 49.1st and
+    /// 50.9th percentiles
+    pub rank_error: f64,
+    /// Probability that `rank_error` holds (0.99)
+    pub confidence: f64,
+    /// Seed the sample was drawn with
+    pub seed: u64,
+}
+
+impl ApproxOut {
+    /// Describe a sampled [`QuantileSketch`](crate::stats::QuantileSketch)
+    /// at [`SKETCH_CONFIDENCE`](crate::stats::SKETCH_CONFIDENCE).
+    pub fn of(sketch: &crate::stats::QuantileSketch) -> Self {
+        let confidence = crate::stats::SKETCH_CONFIDENCE;
+        Self {
+            sample_size: sketch.sorted().len(),
+            population: sketch.population(),
+            rank_error: sketch.rank_error(confidence),
+            confidence,
+            seed: sketch.seed(),
+        }
+    }
 }
 
 /// Output containing various univariate summary metrics.
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
 pub struct SummaryOut {
     /// Number of usable observations
     pub count: usize,
@@ -71,27 +720,136 @@ pub struct SummaryOut {
     pub iqr: Option<f64>,
     /// Median absolute deviation
     pub mad: Option<f64>,
+    /// Coefficient of variation `std / mean` (None for a zero mean)
+    pub cv: Option<f64>,
+    /// Standard error of the mean `std / √n`
+    pub sem: Option<f64>,
+    /// Skewness (Fisher–Pearson adjusted)
+    pub skewness: Option<f64>,
+    /// Excess kurtosis
+    pub excess_kurtosis: Option<f64>,
+    /// Requested `percentiles` as `(p, value)` pairs, `p` in `[0, 100]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percentiles: Option<Vec<(f64, f64)>>,
+    /// Inferred schema of the selected columns (spreadsheet uploads only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Vec<ColumnSchema>>,
+    /// Row sample that was analyzed (`?sample=N` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SampleOut>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+    /// Sample behind `median`, `iqr` and `mad` (`approx: true` on large inputs only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approx: Option<ApproxOut>,
 }
 
 /// ---- `/api/v1/stats/distribution` ----
+/// Spacing of computed histogram edges.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BinScale {
+    /// Equal-width bins over `[min, max]`
+    #[default]
+    Linear,
+    /// Log-spaced bins over `[min, max]`; every value must be positive
+    Log,
+}
+
+/// How `/stats/distribution` estimates the standard errors of skewness and
+/// excess kurtosis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ShapeSe {
+    /// Closed form for a normal sample of the same size
+    Analytic,
+    /// Standard deviation over `resamples` bootstrap resamples
+    Bootstrap,
+}
+
 /// Request body for histogram, quantile, and entropy computations.
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
 pub struct DistIn {
-    /// Array of numeric values
+    /// Array of numeric values (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
     pub values: Vec<f64>,
+    /// Frequency weight of each value (finite, ≥ 0, same length as
+    /// `values`): a value counts as if it appeared that many times, so
+    /// pre-aggregated `(value, count)` pairs can be sent as `values` and
+    /// their counts. Cannot be combined with `approx`
+    #[serde(default)]
+    pub weights: Option<Vec<f64>>,
     /// Optional number of bins (≥2). If omitted, server decides.
     #[serde(default)]
     pub bins: Option<usize>,
+    /// Explicit ascending bin edges (3..=10001 of them); replaces `bins`
+    /// and `scale`, and values outside them are counted in `outside`
+    #[serde(default)]
+    pub edges: Option<Vec<f64>>,
+    /// Spacing of computed edges, `linear` (default) or `log`
+    #[serde(default)]
+    pub scale: Option<BinScale>,
     /// Optional quantiles to compute (0..1)
     #[serde(default)]
     pub quantiles: Option<Vec<f64>>,
+    /// Definition of `quantiles` (default `r7`; only `r7` with `weights`)
+    #[serde(default)]
+    pub quantile_method: Option<QuantileType>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+    /// Estimate quantiles, skewness and kurtosis from a fixed-size sample
+    /// when `values` is larger than it; the histogram stays exact
+    #[serde(default)]
+    pub approx: Option<bool>,
+    /// Seed of the `approx` sample and the `shape_se` bootstrap (default
+    /// `STATS_SEED`)
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Also return standard errors of skewness and excess kurtosis,
+    /// `analytic` or `bootstrap` (`analytic` only with `weights`)
+    #[serde(default)]
+    pub shape_se: Option<ShapeSe>,
+    /// Bootstrap resamples for `shape_se: "bootstrap"` (default 1000)
+    #[serde(default)]
+    pub resamples: Option<usize>,
+    /// Also return area-normalized per-bin densities
+    #[serde(default)]
+    pub density: Option<bool>,
+    /// Also return a Gaussian KDE evaluated at the bin centers
+    #[serde(default)]
+    pub kde: Option<bool>,
+    /// KDE bandwidth (> 0); Silverman's rule when omitted
+    #[serde(default)]
+    pub bandwidth: Option<f64>,
+}
+
+/// Gaussian kernel density estimate evaluated at histogram bin centers.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
+pub struct KdeOut {
+    /// Kernel bandwidth used
+    pub bandwidth: f64,
+    /// Bin centers the estimate is evaluated at (length *k*)
+    pub centers: Vec<f64>,
+    /// Estimated density at each center (length *k*)
+    pub density: Vec<f64>,
 }
 
 /// Response body containing histogram data and shape statistics.
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
 pub struct DistOut {
-    /// Histogram counts (length *k*)
+    /// Histogram counts (length *k*); with `weights`, the number of
+    /// entries in each bin
     pub counts: Vec<usize>,
+    /// Per-bin sums of `weights` (length *k*; `weights` only). Quantiles,
+    /// moments, entropy, `density` and `kde` are then weighted as well
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weights: Option<Vec<f64>>,
     /// Histogram bin edges (length *k + 1*)
     pub edges: Vec<f64>,
     /// Requested quantiles as `(p, value)` pairs
@@ -100,72 +858,239 @@ pub struct DistOut {
     pub skewness: Option<f64>,
     /// Excess kurtosis (None if undefined)
     pub excess_kurtosis: Option<f64>,
+    /// Standard error of `skewness` (`shape_se` only; None if undefined)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skewness_se: Option<f64>,
+    /// Standard error of `excess_kurtosis` (`shape_se` only; None if
+    /// undefined)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excess_kurtosis_se: Option<f64>,
     /// Shannon entropy in bits (None if undefined)
     pub entropy_bits: Option<f64>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+    /// Sample behind `quantiles`, `skewness` and `excess_kurtosis`
+    /// (`approx: true` on large inputs only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approx: Option<ApproxOut>,
+    /// Per-bin `count / (n · width)`, integrating to 1 (`density: true`
+    /// and a non-degenerate range only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub density: Option<Vec<f64>>,
+    /// KDE overlay at the bin centers (`kde: true` and a non-zero spread only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kde: Option<KdeOut>,
+    /// Values outside explicit `edges`, left out of `counts` (`edges` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outside: Option<usize>,
+    /// Total weight of the values outside explicit `edges` (`edges` and
+    /// `weights` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outside_weight: Option<f64>,
 }
 
 /// ---- `/api/v1/stats/pairwise` ----
 /// Input for pairwise correlation and covariance calculations.
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
 pub struct PairIn {
-    /// First numeric series
+    /// First numeric series (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
     pub x: Vec<f64>,
-    /// Second numeric series
+    /// Second numeric series (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
     pub y: Vec<f64>,
+    /// How `null` entries are handled (default `drop`, which removes the pair)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+    /// Coverage of the correlation intervals, in `(0, 1)` (default 0.95)
+    #[serde(default)]
+    pub confidence: Option<f64>,
+}
+
+/// Uncertainty of one correlation coefficient in [`PairOut`].
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
+pub struct CorrTest {
+    /// Two-sided p-value against zero correlation (`t` test for Pearson and
+    /// Spearman, normal approximation for Kendall)
+    pub p_value: Option<f64>,
+    /// Fisher-z confidence interval at [`PairOut::confidence`]; `None` with
+    /// too few pairs
+    pub lower: Option<f64>,
+    pub upper: Option<f64>,
+}
+
+impl CorrTest {
+    /// Test of a `method` coefficient `r` over `n` pairs.
+    pub fn new(method: CorrMethod, r: f64, n: usize, confidence: f64) -> Self {
+        use crate::stats::{
+            correlation_p_value, kendall_ci, kendall_p_value, pearson_ci, spearman_ci,
+        };
+        let (p_value, (lower, upper)) = match method {
+            CorrMethod::Pearson => (correlation_p_value(r, n), pearson_ci(r, n, confidence)),
+            CorrMethod::Spearman => (correlation_p_value(r, n), spearman_ci(r, n, confidence)),
+            CorrMethod::Kendall => (kendall_p_value(r, n), kendall_ci(r, n, confidence)),
+        };
+        let o = |x: f64| (!x.is_nan()).then_some(x);
+        Self {
+            p_value: o(p_value),
+            lower: o(lower),
+            upper: o(upper),
+        }
+    }
 }
 
 /// Output with covariance and correlation coefficients.
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
 pub struct PairOut {
     pub covariance: Option<f64>,
     pub pearson: Option<f64>,
     pub spearman: Option<f64>,
     pub kendall: Option<f64>,
+    /// Pairs left after missing-value handling
+    #[serde(default)]
+    pub n: usize,
+    /// Coverage of the `*_test` intervals
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pearson_test: Option<CorrTest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spearman_test: Option<CorrTest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kendall_test: Option<CorrTest>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
 }
 
 /// ---- Consistent error response ----
 /// Standardized error response body used across all endpoints.
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, ToSchema)]
 pub struct ErrorResponse {
-    /// Short error code (e.g. `"invalid_data"`)
+    /// Short error code (e.g. `"csv_parse"`, `"validation_failed"`)
     pub code: String,
     /// Human-readable error message
     pub message: String,
+    /// Where the problem is, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<ErrorDetails>,
+    /// `X-Request-Id` of the failed request, to quote when reporting it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Location attached to an [`ErrorResponse`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema, ToSchema)]
+pub struct ErrorDetails {
+    /// 1-based line of the uploaded CSV
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    /// 1-based field within that line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u64>,
+    /// JSON pointer to the offending request field (e.g. `/values`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+/// ---- `/api/v2` response envelope ----
+/// Body of every JSON (and plain-text) `/api/v2` response.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct Envelope {
+    /// The `/api/v1` result of the same route; `null` on failure
+    #[schema(value_type = Object)]
+    pub data: serde_json::Value,
+    pub meta: Meta,
+    /// Present exactly when the request failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorResponse>,
+}
+
+/// Bookkeeping attached to an [`Envelope`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct Meta {
+    /// Values or rows the result is based on (`null` when the route has no such count)
+    pub n_used: Option<u64>,
+    /// Values or rows discarded before computing (missing values, unparseable rows)
+    pub n_dropped: u64,
+    /// Server-side handling time in milliseconds
+    pub elapsed_ms: f64,
+    /// Caveats about the result (imputation, sampling, data-quality findings)
+    pub warnings: Vec<String>,
 }
 
 /// ---- `/api/v1/stats/ecdf` ----
 /// Request for empirical CDF (ECDF) calculation.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct EcdfIn {
-    /// Input numeric series
+    /// Input numeric series (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
     pub values: Vec<f64>,
-    /// Optional downsampling cap for large datasets
+    /// Optional downsampling cap for large datasets; shorthand for
+    /// `window.max_points`
     #[serde(default)]
     pub max_points: Option<usize>,
+    /// Paging and downsampling of the `(xs, ps)` points
+    #[serde(default)]
+    pub window: Option<WindowIn>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+    /// Build the ECDF from a fixed-size sample when `values` is larger than it
+    #[serde(default)]
+    pub approx: Option<bool>,
+    /// Seed of the `approx` sample (default `STATS_SEED`)
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 /// Response containing ECDF points (x, p(x)).
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct EcdfOut {
     /// Sorted sample values
     pub xs: Vec<f64>,
     /// Corresponding cumulative probabilities
     pub ps: Vec<f64>,
+    /// Part of the points returned, when the request set a window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<WindowOut>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+    /// Sample the points were read from (`approx: true` on large inputs only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approx: Option<ApproxOut>,
 }
 
 /// ---- `/api/v1/stats/qq-normal` ----
 /// Input for Q–Q plot computation against a normal distribution.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct QqIn {
-    /// Sample values to compare against normal quantiles
+    /// Sample values to compare against normal quantiles (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
     pub values: Vec<f64>,
     /// If true, use robust estimators for μ̂ and σ̂
     #[serde(default)]
     pub robust: Option<bool>,
+    /// Paging and downsampling of the quantile pairs
+    #[serde(default)]
+    pub window: Option<WindowIn>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
 }
 
 /// Output with theoretical vs. sample quantiles and fit parameters.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct QqOut {
     /// Empirical sample quantiles
     pub sample_quantiles: Vec<f64>,
@@ -175,11 +1100,28 @@ pub struct QqOut {
     pub mu_hat: f64,
     /// Estimated standard deviation (σ̂)
     pub sigma_hat: f64,
+    /// Slope of the reference line through the first- and third-quartile
+    /// pairs, in the units of the plot (`sample = slope · theoretical + intercept`)
+    pub line_slope: Option<f64>,
+    /// Intercept of the reference line
+    pub line_intercept: Option<f64>,
+    /// Filliben's probability-plot correlation coefficient (None below 3
+    /// values or for a constant sample)
+    pub ppcc: Option<f64>,
+    /// 5% critical value of `ppcc`: normality is rejected below it (None
+    /// outside 5..=5000 values)
+    pub ppcc_critical: Option<f64>,
+    /// Part of the quantile pairs returned, when the request set a window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<WindowOut>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
 }
 
 /// ---- `/api/v1/stats/corr-matrix` ----
 /// Available correlation methods for matrix computation.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum CorrMethod {
     /// Pearson correlation (linear)
@@ -190,10 +1132,25 @@ pub enum CorrMethod {
     Kendall,
 }
 
+/// Linkage for clustering the variables of a correlation matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrLinkage {
+    /// Distance between the closest pair of variables
+    Single,
+    /// Distance between the farthest pair of variables
+    Complete,
+    /// Mean distance over all pairs of variables (UPGMA)
+    Average,
+}
+
 /// Input for correlation matrix endpoint.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct CorrMatrixIn {
-    /// List of numeric series; all must be equal length
+    /// List of numeric series; all must be equal length (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::series")]
+    #[schemars(with = "Vec<Vec<Option<f64>>>")]
+    #[schema(value_type = Vec<Vec<Option<f64>>>)]
     pub series: Vec<Vec<f64>>,
     /// Optional names for each series (for labeling output)
     #[serde(default)]
@@ -201,23 +1158,207 @@ pub struct CorrMatrixIn {
     /// Correlation method (defaults to Pearson)
     #[serde(default)]
     pub method: Option<CorrMethod>,
+    /// How `null` entries are handled (default `drop`, which removes the row
+    /// from every series; `pairwise` drops it only from the pairs it breaks)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+    /// Also return two-sided p-values and the pairs behind each coefficient
+    #[serde(default)]
+    pub p_values: Option<bool>,
+    /// Hierarchically cluster the variables on `1 − r` with this linkage and
+    /// return the heatmap order and the merge tree
+    #[serde(default)]
+    pub cluster: Option<CorrLinkage>,
+    /// Also return the sample covariance matrix of the values
+    #[serde(default)]
+    pub covariance: Option<bool>,
+    /// Also return the partial-correlation matrix (each pair given all other
+    /// variables), derived from the inverse of the correlation matrix
+    #[serde(default)]
+    pub partial: Option<bool>,
+}
+
+/// Options of `/stats/corr-matrix-csv` (alongside the [`CsvQuery`] options).
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CorrCsvQuery {
+    /// Correlate a registered dataset's columns instead of the CSV body
+    #[serde(default)]
+    pub dataset: Option<String>,
+    /// Correlation method (defaults to Pearson)
+    #[serde(default)]
+    #[param(inline)]
+    pub method: Option<CorrMethod>,
+    /// How empty cells are handled (default `pairwise`: each pair of columns
+    /// uses the rows where both are present)
+    #[serde(default)]
+    #[param(inline)]
+    pub missing: Option<MissingPolicy>,
+    /// Also return two-sided p-values and the pairs behind each coefficient
+    #[serde(default)]
+    pub p_values: Option<bool>,
+    /// Hierarchically cluster the columns with this linkage
+    #[serde(default)]
+    #[param(inline)]
+    pub cluster: Option<CorrLinkage>,
+    /// Also return the covariance matrix of the columns
+    #[serde(default)]
+    pub covariance: Option<bool>,
+    /// Also return the partial-correlation matrix of the columns
+    #[serde(default)]
+    pub partial: Option<bool>,
+}
+
+/// One merge of the variable clustering behind [`CorrMatrixOut::order`].
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct LinkageStep {
+    /// Cluster ids joined (`left < right`); ids below `size` are variables,
+    /// and the cluster formed at step `s` has id `size + s`
+    pub left: usize,
+    pub right: usize,
+    /// Linkage distance on the `1 − r` scale (0..=2)
+    pub distance: f64,
+    /// Number of variables in the merged cluster
+    pub size: usize,
 }
 
 /// Output correlation matrix in flattened (row-major) format.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct CorrMatrixOut {
     /// Matrix size (n×n)
     pub size: usize,
     /// Optional variable names
     #[serde(default)]
     pub names: Option<Vec<String>>,
-    /// Flattened correlation matrix (row-major order)
+    /// Flattened correlation matrix (row-major order); `null` where a
+    /// coefficient is undefined (a constant series or too few pairs)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
     pub matrix: Vec<f64>,
+    /// With `p_values: true`: two-sided p-value of each coefficient, in the
+    /// layout of `matrix` (`null` where undefined)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::missing::optional_values"
+    )]
+    #[schemars(with = "Option<Vec<Option<f64>>>")]
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub p_values: Option<Vec<f64>>,
+    /// With `p_values: true` or `missing: pairwise`: pairs behind each
+    /// coefficient, in the layout of `matrix`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n_used: Option<Vec<usize>>,
+    /// With `covariance: true`: sample covariances (denominator `n − 1`) of
+    /// the values, in the layout of `matrix`; under `missing: pairwise` each
+    /// over the pairs in `n_used` (`null` where fewer than 2)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::missing::optional_values"
+    )]
+    #[schemars(with = "Option<Vec<Option<f64>>>")]
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub covariance: Option<Vec<f64>>,
+    /// With `partial: true`: correlation of each pair given all the other
+    /// variables, from the precision (inverse correlation) matrix, in the
+    /// layout of `matrix`; all `null` when `matrix` is singular or has
+    /// undefined coefficients
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::missing::optional_values"
+    )]
+    #[schemars(with = "Option<Vec<Option<f64>>>")]
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub partial: Option<Vec<f64>>,
+    /// With `cluster`: variable indices in dendrogram order, so that
+    /// `order[i]` is the variable shown at position `i` of the heatmap
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<Vec<usize>>,
+    /// With `cluster`: the `size − 1` merges of the clustering, in order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub linkage: Option<Vec<LinkageStep>>,
+    /// Missing-value handling (`/stats/corr-matrix` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/stats/resample` ----
+/// Calendar bucket size for resampling (UTC).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResampleFreq {
+    Hour,
+    Day,
+    /// ISO weeks starting Monday
+    Week,
+    Month,
+}
+
+/// Aggregate applied to each value column within a bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResampleAgg {
+    Mean,
+    Sum,
+    Count,
+    Min,
+    Max,
+    Median,
+}
+
+/// Resampling options (alongside [`CsvQuery`], whose `columns` picks the value columns).
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ResampleQuery {
+    /// Time index column: header name or 0-based index
+    pub time: String,
+    /// Bucket size (default `day`)
+    #[serde(default)]
+    pub freq: Option<ResampleFreq>,
+    /// Aggregate (default `mean`)
+    #[serde(default)]
+    pub agg: Option<ResampleAgg>,
+    /// Read `NN/NN/YYYY` as day-first (auto-detected when omitted)
+    #[serde(default)]
+    pub dayfirst: Option<bool>,
+    /// Emit empty buckets between the first and last timestamp (default `true`)
+    #[serde(default)]
+    pub fill: Option<bool>,
+}
+
+/// One time bucket.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ResampleBucket {
+    /// Bucket start, `YYYY-MM-DDTHH:MM:SS` (UTC)
+    pub start: String,
+    /// Rows that fell in this bucket
+    pub rows: usize,
+    /// One aggregate per value column (`null` when the bucket has no values)
+    pub values: Vec<Option<f64>>,
+}
+
+/// Result of `/stats/resample`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ResampleOut {
+    pub time_column: String,
+    pub freq: ResampleFreq,
+    pub agg: ResampleAgg,
+    /// Value column names, matching each bucket's `values`
+    pub columns: Vec<String>,
+    pub buckets: Vec<ResampleBucket>,
+    /// Rows whose time cell was empty or not a recognised datetime
+    pub skipped_rows: usize,
+    /// Row sample that was analyzed (`?sample=N` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SampleOut>,
 }
 
 /// ---- `/api/v1/stats/outliers` ----
 /// Available outlier detection methods.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum OutlierMethod {
     /// Z-score thresholding
@@ -226,10 +1367,35 @@ pub enum OutlierMethod {
     Iqr,
 }
 
+/// How `/stats/outliers` cleans the series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlierClean {
+    /// Drop the outliers
+    Remove,
+    /// Clip the outliers to the nearer bound
+    Winsorize,
+}
+
+/// Decision region of an outlier rule, in the units of the values.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct OutlierBounds {
+    /// Lower fence (IQR) or `mean − threshold · sd` (z-score)
+    pub lower: f64,
+    /// Upper fence (IQR) or `mean + threshold · sd` (z-score)
+    pub upper: f64,
+    /// Score past which a point is an outlier: the IQR multiplier (`1.5`)
+    /// or the z-score threshold
+    pub threshold: f64,
+}
+
 /// Input for outlier detection.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct OutliersIn {
-    /// Input numeric series
+    /// Input numeric series (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
     pub values: Vec<f64>,
     /// Method to use (`zscore` or `iqr`)
     #[serde(default)]
@@ -237,20 +1403,74 @@ pub struct OutliersIn {
     /// Threshold multiplier (e.g. 3 for z-score)
     #[serde(default)]
     pub threshold: Option<f64>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+    /// Return only the `k` most extreme outliers, most extreme first
+    /// (default: every outlier in input order)
+    #[serde(default)]
+    pub top_k: Option<usize>,
+    /// Also return a score for every input point
+    #[serde(default)]
+    pub point_scores: Option<bool>,
+    /// Also return the series with outliers removed or winsorized
+    #[serde(default)]
+    pub clean: Option<OutlierClean>,
+    /// Group label of each value (strings, numbers or booleans, same length
+    /// as `values`); the rule is then fitted within each group
+    #[serde(default, deserialize_with = "crate::missing::optional_labels")]
+    pub groups: Option<Vec<Option<String>>>,
+}
+
+/// Outlier rule fitted to one `groups` label by `/stats/outliers`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct OutlierGroup {
+    /// The group label
+    pub label: String,
+    /// Values left in the group after missing-value handling
+    pub count: usize,
+    /// Region outside which the group's points are outliers
+    pub bounds: OutlierBounds,
 }
 
 /// Output listing detected outliers.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct OutliersOut {
-    /// Indices of detected outliers
+    /// Indices of detected outliers (positions in the input array)
     pub indices: Vec<usize>,
     /// Values corresponding to detected outliers
     pub values: Vec<f64>,
+    /// With `top_k`: each returned outlier's score (`|z|` for z-score, the
+    /// distance past the nearer quartile in IQRs for IQR)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scores: Option<Vec<f64>>,
+    /// With `top_k`: how many outliers were detected in total
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    /// Region outside which points are outliers (None if no values remain
+    /// or with `groups`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<OutlierBounds>,
+    /// With `groups`: each group's bounds, in order of first appearance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<OutlierGroup>>,
+    /// With `point_scores: true`: each input position's score, on the same
+    /// scale as `scores` (`0` inside the quartiles for IQR; `null` where a
+    /// missing value was dropped)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub point_scores: Option<Vec<Option<f64>>>,
+    /// With `clean`: the series after missing-value handling, with every
+    /// outlier (not only the `top_k`) removed or clipped to `bounds`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cleaned: Option<Vec<f64>>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
 }
 
 /// ---- `/api/v1/stats/normalize` ----
 /// Normalization methods supported by `/normalize`.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum NormMethod {
     /// Standard score (Z-score) normalization
@@ -260,9 +1480,12 @@ pub enum NormMethod {
 }
 
 /// Input for data normalization.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct NormalizeIn {
-    /// Input numeric series
+    /// Input numeric series (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
     pub values: Vec<f64>,
     /// Method (defaults to `zscore`)
     #[serde(default)]
@@ -270,28 +1493,1728 @@ pub struct NormalizeIn {
     /// Range for min–max normalization, e.g. (0.0, 1.0)
     #[serde(default)]
     pub range: Option<(f64, f64)>,
+    /// Paging and downsampling of the normalized values
+    #[serde(default)]
+    pub window: Option<WindowIn>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+    /// Group label of each value (strings, numbers or booleans, same length
+    /// as `values`); each group is then scaled with its own parameters
+    #[serde(default, deserialize_with = "crate::missing::optional_labels")]
+    pub groups: Option<Vec<Option<String>>>,
+}
+
+/// Fitted normalization, as returned by `/stats/normalize` and replayed by
+/// `/stats/normalize/apply` and `/stats/normalize/inverse`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum NormParams {
+    /// `(x − mean) / std`
+    Zscore {
+        /// Mean of the fitted values
+        mean: f64,
+        /// Sample standard deviation of the fitted values
+        std: f64,
+    },
+    /// `range.0 + (x − min) · (range.1 − range.0) / (max − min)`
+    Minmax {
+        /// Minimum of the fitted values
+        min: f64,
+        /// Maximum of the fitted values
+        max: f64,
+        /// Target range
+        range: (f64, f64),
+    },
+}
+
+impl NormParams {
+    /// Smallest `std` or `max − min` divided by, so constant data maps to
+    /// `0` (z-score) or the lower end of `range` (min–max).
+    const MIN_SCALE: f64 = 1e-12;
+
+    /// Transform one value.
+    pub fn apply(&self, x: f64) -> f64 {
+        match *self {
+            Self::Zscore { mean, std } => (x - mean) / std.max(Self::MIN_SCALE),
+            Self::Minmax { min, max, range } => {
+                range.0 + (x - min) * (range.1 - range.0) / (max - min).max(Self::MIN_SCALE)
+            }
+        }
+    }
+
+    /// Undo [`apply`](Self::apply) for one transformed value.
+    pub fn invert(&self, y: f64) -> f64 {
+        match *self {
+            Self::Zscore { mean, std } => mean + y * std.max(Self::MIN_SCALE),
+            Self::Minmax { min, max, range } => {
+                min + (y - range.0) * (max - min).max(Self::MIN_SCALE) / (range.1 - range.0)
+            }
+        }
+    }
+}
+
+/// ---- `/api/v1/stats/normalize/apply`, `/api/v1/stats/normalize/inverse` ----
+/// Values to transform with previously fitted parameters.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct NormApplyIn {
+    /// Numeric series to transform (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub values: Vec<f64>,
+    /// Parameters returned by `/stats/normalize`
+    pub params: NormParams,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+}
+
+/// Transform fitted to one `groups` label by `/stats/normalize`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct NormGroup {
+    /// The group label
+    pub label: String,
+    /// Values left in the group after missing-value handling
+    pub count: usize,
+    /// The group's transform, for `/stats/normalize/apply` and `/inverse`
+    pub params: NormParams,
 }
 
 /// Output containing normalized values.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct NormalizeOut {
     pub values: Vec<f64>,
+    /// Transform that produced `values`, for `/stats/normalize/apply` and
+    /// `/inverse` (None if no values remain or with `groups`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<NormParams>,
+    /// With `groups`: each group's transform, in order of first appearance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<NormGroup>>,
+    /// Input position of each value, when the request set a window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indices: Option<Vec<usize>>,
+    /// Part of the values returned, when the request set a window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<WindowOut>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
 }
 
 /// ---- `/api/v1/stats/binrule` ----
 /// Input specifying a binning rule for histogram selection.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct BinRuleIn {
-    /// Numeric series to analyze
+    /// Numeric series to analyze (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
     pub values: Vec<f64>,
-    /// Optional binning rule (`sturges`, `sqrt`, `fd`, etc.)
+    /// Optional binning rule (`auto`, `sturges`, `sqrt`, `rice`, `doane`,
+    /// `scott`, `fd`, `shimazaki`); anything else is rejected
     #[serde(default)]
     pub rule: Option<String>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
 }
 
 /// Output with computed number of histogram bins.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
 pub struct BinRuleOut {
-    /// Number of bins chosen by rule
+    /// Number of bins chosen by rule (at most 10000)
     pub bins: usize,
+    /// Width of each equal-width bin (None if no values remain)
+    pub width: Option<f64>,
+    /// Bin edges over `[min, max]` (length *bins + 1*; empty if no values remain)
+    pub edges: Vec<f64>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/stats/entropy` ----
+/// Estimator behind `/stats/entropy`.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EntropyMethod {
+    /// Shannon entropy of the equal-width histogram's bin frequencies
+    #[default]
+    Histogram,
+    /// Kozachenko–Leonenko k-nearest-neighbour differential entropy
+    Knn,
+}
+
+/// Input for estimating the entropy of a raw numeric series.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct EntropyIn {
+    /// Numeric series to analyze (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub values: Vec<f64>,
+    /// Estimator (defaults to `histogram`)
+    #[serde(default)]
+    pub method: Option<EntropyMethod>,
+    /// Histogram bins for `histogram` (2..=10000, defaults to 10)
+    #[serde(default)]
+    pub bins: Option<usize>,
+    /// Neighbour rank for `knn` (1..=100 and below the number of values,
+    /// defaults to 3)
+    #[serde(default)]
+    pub k: Option<usize>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+}
+
+/// Entropy estimate in bits and nats.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct EntropyOut {
+    /// Estimator used
+    pub method: EntropyMethod,
+    /// Entropy in bits (None if no values remain, or for `knn` when a value
+    /// repeats more than `k` times)
+    pub bits: Option<f64>,
+    /// The same entropy in nats
+    pub nats: Option<f64>,
+    /// Number of values the estimate used
+    pub n: usize,
+    /// Histogram bins used (`histogram` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bins: Option<usize>,
+    /// Neighbour rank used (`knn` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub k: Option<usize>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/stats/contingency` ----
+/// Two aligned categorical arrays to cross-tabulate.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ContingencyIn {
+    /// Row labels: strings, numbers or booleans (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::labels")]
+    pub x: Vec<Option<String>>,
+    /// Column labels, aligned with `x` (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::labels")]
+    pub y: Vec<Option<String>>,
+    /// Apply Yates' continuity correction to a 2×2 table (default false)
+    #[serde(default)]
+    pub yates: Option<bool>,
+}
+
+/// Contingency table of two label arrays and its χ² test of independence.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ContingencyOut {
+    /// Distinct `x` labels, one per table row
+    pub rows: Vec<String>,
+    /// Distinct `y` labels, one per table column
+    pub cols: Vec<String>,
+    /// Observed counts, `counts[i][j]` for `rows[i]` and `cols[j]`
+    pub counts: Vec<Vec<usize>>,
+    /// Expected counts under independence, in the layout of `counts`
+    pub expected: Vec<Vec<f64>>,
+    pub row_totals: Vec<usize>,
+    pub col_totals: Vec<usize>,
+    /// Pairs tabulated
+    pub n: usize,
+    /// Pearson's χ² statistic (None for a single row or column)
+    pub chi_square: Option<f64>,
+    /// Degrees of freedom, `(rows − 1)(cols − 1)`
+    pub df: usize,
+    pub p_value: Option<f64>,
+    /// Cramér's V, from the uncorrected χ²
+    pub cramers_v: Option<f64>,
+    /// Whether Yates' correction was applied to `chi_square` and `p_value`
+    pub yates: bool,
+    /// Pairs dropped for a `null` on either side
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/stats/seasonality` ----
+/// Calendar slot that groups timestamped values into seasons.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SeasonKey {
+    /// `0` … `23` (UTC)
+    HourOfDay,
+    /// `Mon` … `Sun`
+    #[default]
+    DayOfWeek,
+    /// `Jan` … `Dec`
+    MonthOfYear,
+}
+
+/// Input for periodicity detection and seasonal subseries statistics.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SeasonalityIn {
+    /// Evenly spaced series, in time order (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub values: Vec<f64>,
+    /// Datetime of each value (same formats as CSV datetime columns); groups
+    /// the seasons by `season` instead of by position
+    #[serde(default)]
+    pub timestamps: Option<Vec<String>>,
+    /// Calendar slot for `timestamps` (default `day_of_week`)
+    #[serde(default)]
+    pub season: Option<SeasonKey>,
+    /// Read `NN/NN/YYYY` timestamps as day-first (auto-detected when omitted)
+    #[serde(default)]
+    pub dayfirst: Option<bool>,
+    /// Period in samples for positional seasons (defaults to the detected one)
+    #[serde(default)]
+    pub period: Option<usize>,
+    /// Longest lag/period searched (2..=10000, defaults to `min(n / 2, 400)`)
+    #[serde(default)]
+    pub max_lag: Option<usize>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+}
+
+/// Summary of the values falling in one season.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SeasonStats {
+    /// Season label: position in the period (`"0"`, `"1"`, …) or calendar
+    /// slot (`"Mon"`, `"13"`, `"Jan"`, …)
+    pub season: String,
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    /// Sample standard deviation (None below two values)
+    pub std_dev: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// Detected periodicity and per-season summaries.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SeasonalityOut {
+    /// Values analyzed
+    pub n: usize,
+    /// Autocorrelation at lags `0..=max_lag` (`null` for a constant series)
+    pub acf: Vec<Option<f64>>,
+    /// Lag of the highest autocorrelation peak above the white-noise band
+    /// `1.96 / √n`
+    pub acf_period: Option<usize>,
+    /// Integer period in `2..=max_lag` with the most periodogram power
+    pub periodogram_period: Option<usize>,
+    /// Period behind positional `seasons` (requested, or `acf_period`, else
+    /// `periodogram_period`); absent with `timestamps`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<usize>,
+    /// Calendar slot behind `seasons` (with `timestamps` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub season: Option<SeasonKey>,
+    /// One summary per season, in season order
+    pub seasons: Vec<SeasonStats>,
+    /// Share of the variance explained by the season means (η²)
+    pub strength: Option<f64>,
+    /// Values dropped for an unparseable timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<usize>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/stats/anomaly/score` ----
+/// Detector combined into the composite anomaly score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyDetector {
+    /// Distance from the median in robust standard deviations (MAD-based)
+    RobustZ,
+    /// Distance past the nearer quartile in IQRs
+    Iqr,
+    /// Isolation-forest score
+    IsolationForest,
+    /// Robust z of the residual from a centred rolling median
+    RollingResidual,
+}
+
+/// Input for composite anomaly scoring.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct AnomalyIn {
+    /// Input numeric series, in order for `rolling_residual` (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub values: Vec<f64>,
+    /// Detectors to combine, each at most once (default: all four)
+    #[serde(default)]
+    pub detectors: Option<Vec<AnomalyDetector>>,
+    /// Centred window length for `rolling_residual`, the point included
+    /// (3..=1001, default 7)
+    #[serde(default)]
+    pub window: Option<usize>,
+    /// Isolation-forest trees (1..=1000, default 100)
+    #[serde(default)]
+    pub trees: Option<usize>,
+    /// Values drawn for each isolation-forest tree (2..=4096, default 256)
+    #[serde(default)]
+    pub sample_size: Option<usize>,
+    /// Random seed of the isolation forest (default `STATS_SEED`)
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Length of the ranked list (default 10)
+    #[serde(default)]
+    pub top_k: Option<usize>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+}
+
+/// One detector's normalized score for every input position.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct AnomalyComponent {
+    pub detector: AnomalyDetector,
+    /// Score in `[0, 1]`, `0.5` at the detector's usual cutoff (`null` where
+    /// a missing value was dropped)
+    pub scores: Vec<Option<f64>>,
+}
+
+/// A point of the ranked anomaly list.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct AnomalyPoint {
+    /// Position in the input array
+    pub index: usize,
+    pub value: f64,
+    /// Composite score in `[0, 1]`
+    pub score: f64,
+    /// Share of `score` from each detector, in `detectors` order (sums to
+    /// `score`)
+    pub contributions: Vec<f64>,
+}
+
+/// Composite anomaly scores and the most anomalous points.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct AnomalyOut {
+    /// Detectors combined, in contribution order
+    pub detectors: Vec<AnomalyDetector>,
+    /// Composite score of every input position, the mean of the detector
+    /// scores (`null` where a missing value was dropped)
+    pub scores: Vec<Option<f64>>,
+    /// Per-detector scores, in `detectors` order
+    pub components: Vec<AnomalyComponent>,
+    /// The `top_k` highest composite scores, highest first
+    pub ranked: Vec<AnomalyPoint>,
+    /// Points scoring at least `0.5`
+    pub flagged: usize,
+    /// Seed the isolation forest used
+    pub seed: u64,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/stats/compare` ----
+/// Input for a two-sample comparison.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct CompareIn {
+    /// First sample (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub x: Vec<f64>,
+    /// Second sample, of any length (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub y: Vec<f64>,
+    /// Bins of the overlaid histogram (2..=10000, default 20)
+    #[serde(default)]
+    pub bins: Option<usize>,
+    /// Most points per overlaid ECDF (default 200)
+    #[serde(default)]
+    pub max_points: Option<usize>,
+    /// How `null` entries are handled in each sample (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+}
+
+/// Summary of one sample in [`CompareOut`].
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SampleSummary {
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    /// Sample standard deviation (None below two values)
+    pub std_dev: Option<f64>,
+    pub min: Option<f64>,
+    pub q1: Option<f64>,
+    pub q3: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// Differences between the samples, `x` relative to `y`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct CompareDelta {
+    /// `mean(x) − mean(y)`
+    pub mean: Option<f64>,
+    /// `median(x) − median(y)`
+    pub median: Option<f64>,
+    /// `sd(x) / sd(y)`
+    pub std_dev_ratio: Option<f64>,
+}
+
+/// Outcome of one hypothesis test.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SampleTest {
+    /// Test statistic (`t`, `U`, `D`, `F`, `W` or `H`)
+    pub statistic: Option<f64>,
+    /// Degrees of freedom, for tests that have them (`df1` for F tests)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub df: Option<f64>,
+    /// Denominator degrees of freedom of F tests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub df2: Option<f64>,
+    /// Two-sided p-value
+    pub p_value: Option<f64>,
+}
+
+/// Effect sizes of `x` against `y`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct CompareEffect {
+    /// Mean difference in pooled standard deviations
+    pub cohens_d: Option<f64>,
+    /// Cohen's d with the small-sample correction
+    pub hedges_g: Option<f64>,
+    /// `P(x > y) − P(x < y)` over all pairs, in `[−1, 1]`
+    pub cliffs_delta: Option<f64>,
+    /// `P(x > y)` over all pairs, ties counting half
+    pub common_language: Option<f64>,
+}
+
+/// Both samples binned on the same edges.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct CompareHistogram {
+    /// Bin edges over the pooled range (`bins + 1` of them)
+    pub edges: Vec<f64>,
+    pub x: Vec<usize>,
+    pub y: Vec<usize>,
+}
+
+/// Both empirical CDFs evaluated on one grid.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct CompareEcdf {
+    /// Ascending distinct pooled values (downsampled to `max_points`)
+    pub grid: Vec<f64>,
+    /// `F_x` at each grid value
+    pub x: Vec<f64>,
+    /// `F_y` at each grid value
+    pub y: Vec<f64>,
+}
+
+/// Consolidated comparison of two samples.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct CompareOut {
+    pub x: SampleSummary,
+    pub y: SampleSummary,
+    pub delta: CompareDelta,
+    /// Student's t-test with pooled variance
+    pub t_test: SampleTest,
+    /// Welch's t-test
+    pub welch: SampleTest,
+    /// Mann–Whitney U test (normal approximation, tie-corrected)
+    pub mann_whitney: SampleTest,
+    /// Two-sample Kolmogorov–Smirnov test (asymptotic)
+    pub ks: SampleTest,
+    /// F test for equal variances
+    pub variance_f: SampleTest,
+    /// Brown–Forsythe test for equal variances
+    pub brown_forsythe: SampleTest,
+    pub effect: CompareEffect,
+    pub histogram: CompareHistogram,
+    pub ecdf: CompareEcdf,
+    /// Missing-value handling applied to both samples
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/stats/drift` ----
+/// Window each `/stats/drift` window is compared with.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftReference {
+    /// The first window, for drift away from a baseline
+    #[default]
+    First,
+    /// The window before, for change from one window to the next
+    Previous,
+}
+
+/// Input for a sliding-window drift profile of a time-ordered series.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct DriftIn {
+    /// Numeric series in time order (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub values: Vec<f64>,
+    /// Values per window (≥ 2)
+    pub window: usize,
+    /// Values between the starts of consecutive windows (default `window`,
+    /// so windows do not overlap)
+    #[serde(default)]
+    pub step: Option<usize>,
+    /// Window each window is compared with (default `first`)
+    #[serde(default)]
+    pub reference: Option<DriftReference>,
+    /// PSI bins, cut at the reference window's quantiles (2..=10000,
+    /// default 10)
+    #[serde(default)]
+    pub bins: Option<usize>,
+    /// How `null` entries are handled (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+}
+
+/// One window of a [`DriftOut`] profile and its distance from the reference.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct DriftWindow {
+    /// Input position of the window's first value
+    pub start: usize,
+    /// Input position one past the window's last value
+    pub end: usize,
+    /// Population Stability Index against the reference (None for the
+    /// first window)
+    pub psi: Option<f64>,
+    /// Two-sample Kolmogorov–Smirnov statistic `D` against the reference
+    pub ks: Option<f64>,
+    /// Asymptotic p-value of `ks`
+    pub ks_p_value: Option<f64>,
+    /// First Wasserstein distance to the reference, in the units of the values
+    pub wasserstein: Option<f64>,
+}
+
+/// Drift of a series over time, one entry per window.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct DriftOut {
+    pub reference: DriftReference,
+    /// Windows in time order; a trailing remainder shorter than `window` is
+    /// left out
+    pub windows: Vec<DriftWindow>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/stats/generate` ----
+/// A distribution to draw from; parameters left out take the listed defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(tag = "name", rename_all = "snake_case")]
+pub enum SampleDistribution {
+    Normal {
+        /// Default 0
+        #[serde(default)]
+        mean: Option<f64>,
+        /// Default 1
+        #[serde(default)]
+        std_dev: Option<f64>,
+    },
+    /// On `[low, high)`
+    Uniform {
+        /// Default 0
+        #[serde(default)]
+        low: Option<f64>,
+        /// Default 1
+        #[serde(default)]
+        high: Option<f64>,
+    },
+    Exponential {
+        /// Events per unit time (default 1)
+        #[serde(default)]
+        rate: Option<f64>,
+    },
+    Gamma {
+        shape: f64,
+        /// Default 1
+        #[serde(default)]
+        scale: Option<f64>,
+    },
+    Binomial {
+        trials: u64,
+        /// Success probability of each trial
+        p: f64,
+    },
+    Poisson {
+        /// Mean count
+        lambda: f64,
+    },
+}
+
+/// Input for seeded sample generation.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct GenerateIn {
+    pub distribution: SampleDistribution,
+    /// Number of values to draw
+    pub n: usize,
+    /// Random seed (default `STATS_SEED`); the same seed gives the same values
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Drawn sample with the distribution's exact moments.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct GenerateOut {
+    /// Distribution drawn from, defaults filled in
+    pub distribution: SampleDistribution,
+    pub seed: u64,
+    pub values: Vec<f64>,
+    /// Mean of the distribution (not of `values`)
+    pub expected_mean: f64,
+    /// Variance of the distribution
+    pub expected_variance: f64,
+}
+
+/// ---- `/api/v1/stats/simulate` ----
+/// What one replication of a simulation computes.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SimPipeline {
+    /// Statistic of a sample from `x` minus the same statistic of a sample
+    /// from `y`
+    Difference {
+        x: SampleDistribution,
+        /// Defaults to `x`, simulating the null of no difference
+        #[serde(default)]
+        y: Option<SampleDistribution>,
+        /// Size of each `x` sample
+        n_x: usize,
+        /// Size of each `y` sample (default `n_x`)
+        #[serde(default)]
+        n_y: Option<usize>,
+        /// Defaults to `mean`
+        #[serde(default)]
+        statistic: Option<BootstrapStatistic>,
+    },
+    /// A formula over independent draws, one per variable and replication
+    Formula {
+        /// E.g. `a * b / (c + 1)`; supports `+ - * / ^`, `pi`, `e`, `sqrt`,
+        /// `exp`, `ln`, `log10`, `abs`, `sin`, `cos`, `tan`, `min` and `max`
+        expression: String,
+        /// Distribution of each variable the formula names
+        variables: BTreeMap<String, SampleDistribution>,
+    },
+}
+
+/// Input for a Monte Carlo simulation.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SimulateIn {
+    pub pipeline: SimPipeline,
+    /// Number of replications (default 10000)
+    #[serde(default)]
+    pub replications: Option<usize>,
+    /// Random seed (default `STATS_SEED`); the same seed gives the same distribution
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Observed value to locate in the simulated distribution
+    #[serde(default)]
+    pub observed: Option<f64>,
+    /// Histogram bins (default 20)
+    #[serde(default)]
+    pub bins: Option<usize>,
+    /// Definition of the reported `quantiles` (default `r7`)
+    #[serde(default)]
+    pub quantile_method: Option<QuantileType>,
+}
+
+/// Summary of the simulated distribution.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SimulateOut {
+    pub replications: usize,
+    pub seed: u64,
+    /// Summary statistics over the finite results
+    pub mean: Option<f64>,
+    pub std_dev: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// `(p, quantile)` at p = 0.025, 0.05, 0.25, 0.5, 0.75, 0.95 and 0.975
+    pub quantiles: Vec<(f64, Option<f64>)>,
+    pub counts: Vec<usize>,
+    pub edges: Vec<f64>,
+    /// Replications whose result was `NaN` or infinite (e.g. `ln` of a
+    /// negative draw), left out of the summary
+    pub non_finite: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed: Option<f64>,
+    /// Two-sided Monte Carlo p-value of `observed`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p_value: Option<f64>,
+}
+
+/// ---- `/api/v1/stats/ttest` ----
+/// Design of a `/stats/ttest` t-test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TTestKind {
+    /// Mean of `x` against `mu`
+    OneSample,
+    /// Mean of the pairwise differences `x − y` against `mu`
+    Paired,
+    /// `mean(x) − mean(y)` of independent samples against `mu`, without
+    /// assuming equal variances
+    Welch,
+}
+
+/// Alternative hypothesis of a `/stats/ttest` test.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TTestAlternative {
+    /// The mean (difference) is not `mu`
+    #[default]
+    TwoSided,
+    /// The mean (difference) is below `mu`
+    Less,
+    /// The mean (difference) is above `mu`
+    Greater,
+}
+
+impl From<TTestAlternative> for crate::stats::Tail {
+    fn from(a: TTestAlternative) -> Self {
+        match a {
+            TTestAlternative::TwoSided => Self::TwoSided,
+            TTestAlternative::Less => Self::Less,
+            TTestAlternative::Greater => Self::Greater,
+        }
+    }
+}
+
+/// Input for a one-sample, paired or Welch t-test.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct TTestIn {
+    /// Sample (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub x: Vec<f64>,
+    /// Second sample: paired with `x` (same length) or independent of it
+    /// (`null` = missing)
+    #[serde(default, deserialize_with = "crate::missing::optional_values")]
+    #[schemars(with = "Option<Vec<Option<f64>>>")]
+    #[schema(value_type = Option<Vec<Option<f64>>>)]
+    pub y: Option<Vec<f64>>,
+    /// Test to run (default `one_sample` without `y`, `welch` with it)
+    #[serde(default)]
+    pub test: Option<TTestKind>,
+    /// Mean, or mean difference, under the null hypothesis (default 0)
+    #[serde(default)]
+    pub mu: Option<f64>,
+    /// Alternative hypothesis (default `two_sided`)
+    #[serde(default)]
+    pub alternative: Option<TTestAlternative>,
+    /// Coverage of the confidence interval, in `(0, 1)` (default 0.95)
+    #[serde(default)]
+    pub confidence: Option<f64>,
+    /// How `null` entries are handled (default `drop`, which removes the
+    /// pair for `paired`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+}
+
+impl TTestIn {
+    /// The test to run: `test`, or the default for whether `y` is given.
+    pub fn kind(&self) -> TTestKind {
+        self.test.unwrap_or(match self.y {
+            Some(_) => TTestKind::Welch,
+            None => TTestKind::OneSample,
+        })
+    }
+}
+
+/// Outcome of a `/stats/ttest` t-test.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct TTestOut {
+    pub test: TTestKind,
+    pub alternative: TTestAlternative,
+    /// Mean of `x`, of the differences, or `mean(x) − mean(y)` (None below
+    /// two values)
+    pub estimate: Option<f64>,
+    /// Standard error of `estimate`
+    pub std_error: Option<f64>,
+    /// `(estimate − mu) / std_error` (None without variation)
+    pub t: Option<f64>,
+    /// Degrees of freedom (Welch–Satterthwaite for `welch`)
+    pub df: Option<f64>,
+    /// p-value against `alternative`
+    pub p_value: Option<f64>,
+    /// Coverage of the interval
+    pub confidence: f64,
+    /// Lower confidence bound for the mean (difference); None when the
+    /// interval is open below (`less`)
+    pub lower: Option<f64>,
+    /// Upper confidence bound; None when the interval is open above
+    /// (`greater`)
+    pub upper: Option<f64>,
+    /// Values of `x` (pairs, for `paired`) left after missing-value handling
+    pub n_x: usize,
+    /// Values of `y` left (`welch` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n_y: Option<usize>,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/stats/tests` ----
+/// A hypothesis test the service runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatTest {
+    StudentT,
+    WelchT,
+    OneSampleT,
+    PairedT,
+    MannWhitney,
+    KsTwoSample,
+    VarianceF,
+    BrownForsythe,
+    OneWayAnova,
+    WelchAnova,
+    KruskalWallis,
+    ChiSquareIndependence,
+    Permutation,
+    Correlation,
+    NormalPpcc,
+    JarqueBera,
+}
+
+/// Catalog entry for one test.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct TestInfo {
+    pub id: StatTest,
+    pub name: String,
+    /// The question the test answers
+    pub question: String,
+    /// What it takes: e.g. `2 numeric samples`, `a table of counts`
+    pub input: String,
+    pub assumptions: Vec<String>,
+    /// Endpoint that runs it
+    pub endpoint: String,
+    /// Where its outcome appears in that endpoint's response
+    pub field: String,
+}
+
+/// Every test the service runs.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct TestCatalogOut {
+    pub tests: Vec<TestInfo>,
+}
+
+/// Input for a test recommendation: the groups to compare.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct RecommendIn {
+    /// Two or more independent samples (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::series")]
+    #[schemars(with = "Vec<Vec<Option<f64>>>")]
+    #[schema(value_type = Vec<Vec<Option<f64>>>)]
+    pub groups: Vec<Vec<f64>>,
+    /// Significance level of the assumption checks (default 0.05)
+    #[serde(default)]
+    pub alpha: Option<f64>,
+    /// How `null` entries are handled in each group (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+}
+
+/// What the data showed about one group.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct GroupCheck {
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub std_dev: Option<f64>,
+    /// Passes the normality check; `null` below 5 values, too few to tell
+    pub normal: Option<bool>,
+}
+
+/// Suggested test, why, and its outcome on the data.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct RecommendOut {
+    pub recommended: StatTest,
+    /// The findings behind the choice, in plain words
+    pub reasons: Vec<String>,
+    /// Other tests that fit, best first
+    pub alternatives: Vec<StatTest>,
+    pub groups: Vec<GroupCheck>,
+    /// Brown–Forsythe p-value for equal variances
+    pub variance_p: Option<f64>,
+    pub alpha: f64,
+    /// The recommended test run on the groups
+    pub result: SampleTest,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/stats/vector/*` ----
+/// Distance metric for vector endpoints.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorMetric {
+    /// Euclidean (L2) distance
+    Euclidean,
+    /// Cosine distance (1 − cosine similarity)
+    Cosine,
+}
+
+/// Element precision for the point-set vector endpoints.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingDtype {
+    /// Double precision (default)
+    #[default]
+    F64,
+    /// Single precision: half the memory, and base64 embeddings are used
+    /// as decoded; results may differ from `f64` in the ~7th digit
+    F32,
+}
+
+/// Input for k-th nearest-neighbor distance statistics.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct KnnDistIn {
+    /// Points/embeddings (all the same dimension; arrays or base64 `f32`)
+    #[schemars(with = "Vec<crate::embedding::EmbeddingWire>")]
+    #[schema(value_type = Vec<crate::embedding::EmbeddingWire>)]
+    pub points: crate::embedding::Embeddings,
+    /// Precision to compute in (defaults to `f64`)
+    #[serde(default)]
+    pub dtype: Option<EmbeddingDtype>,
+    /// Neighbor rank (defaults to 4, a common DBSCAN `min_samples`)
+    #[serde(default)]
+    pub k: Option<usize>,
+    /// Distance metric (defaults to Euclidean)
+    #[serde(default)]
+    pub metric: Option<VectorMetric>,
+    /// Histogram bins (≥2, defaults to 20)
+    #[serde(default)]
+    pub bins: Option<usize>,
+}
+
+/// k-NN distances per point and a summary of their distribution.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct KnnDistOut {
+    /// Neighbor rank used
+    pub k: usize,
+    /// Distance from each point to its k-th nearest neighbor (input order)
+    pub distances: Vec<f64>,
+    pub mean: Option<f64>,
+    pub std: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Quantiles of the distances as `(p, value)` pairs (5, 25, 50, 75, 95%)
+    pub quantiles: Vec<(f64, f64)>,
+    /// Histogram counts of the distances
+    pub counts: Vec<usize>,
+    /// Histogram bin edges (length `counts.len() + 1`)
+    pub edges: Vec<f64>,
+}
+
+/// Intrinsic-dimension estimators.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IntrinsicDimMethod {
+    /// TwoNN (ratio of 2nd to 1st neighbor distances)
+    TwoNn,
+    /// Levina–Bickel maximum likelihood over k neighbors
+    Mle,
+}
+
+/// Input for intrinsic-dimension estimation of an embedding set.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct IntrinsicDimIn {
+    /// Points/embeddings (all the same dimension; arrays or base64 `f32`)
+    #[schemars(with = "Vec<crate::embedding::EmbeddingWire>")]
+    #[schema(value_type = Vec<crate::embedding::EmbeddingWire>)]
+    pub points: crate::embedding::Embeddings,
+    /// Precision to compute in (defaults to `f64`)
+    #[serde(default)]
+    pub dtype: Option<EmbeddingDtype>,
+    /// Estimator (defaults to `two_nn`)
+    #[serde(default)]
+    pub method: Option<IntrinsicDimMethod>,
+    /// Neighbors for `mle` (defaults to 10; ignored by `two_nn`)
+    #[serde(default)]
+    pub k: Option<usize>,
+}
+
+/// Estimated intrinsic dimension alongside the ambient dimension.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct IntrinsicDimOut {
+    /// Estimator used
+    pub method: IntrinsicDimMethod,
+    /// Estimated intrinsic dimension (None if undefined, e.g. too few distinct points)
+    pub dimension: Option<f64>,
+    /// Dimension of the input vectors
+    pub ambient_dim: usize,
+    /// Number of points supplied
+    pub n: usize,
+}
+
+/// Input for near-duplicate embedding detection.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct NearDupIn {
+    /// Embeddings (all the same dimension; arrays or base64 `f32`)
+    #[schemars(with = "Vec<crate::embedding::EmbeddingWire>")]
+    #[schema(value_type = Vec<crate::embedding::EmbeddingWire>)]
+    pub points: crate::embedding::Embeddings,
+    /// Precision to compute in (defaults to `f64`)
+    #[serde(default)]
+    pub dtype: Option<EmbeddingDtype>,
+    /// Cosine-similarity threshold in \[-1,1\] (defaults to 0.95)
+    #[serde(default)]
+    pub threshold: Option<f64>,
+}
+
+/// A pair of embeddings at or above the similarity threshold.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct NearDupPair {
+    pub i: usize,
+    pub j: usize,
+    /// Cosine similarity between `points[i]` and `points[j]`
+    pub similarity: f64,
+}
+
+/// Near-duplicate pairs and their transitive groups.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct NearDupOut {
+    /// Threshold used
+    pub threshold: f64,
+    /// Matching pairs (`i < j`)
+    pub pairs: Vec<NearDupPair>,
+    /// Connected groups of near-duplicates (size ≥ 2), each sorted ascending
+    pub groups: Vec<Vec<usize>>,
+    /// Indices safe to drop when keeping the first member of each group
+    pub redundant: Vec<usize>,
+}
+
+/// Sparse vector as parallel index/value lists (e.g. BM25 or SPLADE term weights).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SparseVectorIn {
+    /// Dimension indices (duplicates are summed)
+    pub indices: Vec<u32>,
+    /// Values aligned with `indices`
+    pub values: Vec<f64>,
+}
+
+/// A dense embedding (array or base64 `f32`) or a sparse `{indices, values}` vector.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(untagged)]
+pub enum VectorIn {
+    #[schema(value_type = crate::embedding::EmbeddingWire)]
+    Dense(
+        #[serde(deserialize_with = "crate::embedding::vector")]
+        #[schemars(with = "crate::embedding::EmbeddingWire")]
+        Vec<f64>,
+    ),
+    Sparse(SparseVectorIn),
+}
+
+/// Similarity kernel for query/candidate scoring.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityKernel {
+    /// Cosine similarity (None for zero vectors)
+    Cosine,
+    /// Raw inner product (BM25/SPLADE scores are dot products)
+    Dot,
+}
+
+/// Input for query-vs-candidates similarity scoring.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SimilarityIn {
+    /// Query vector
+    pub query: VectorIn,
+    /// Candidate vectors (same representation as `query`; dense ones share its dimension)
+    pub candidates: Vec<VectorIn>,
+    /// Kernel (defaults to cosine)
+    #[serde(default)]
+    pub kernel: Option<SimilarityKernel>,
+}
+
+/// Similarity of each candidate to the query.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SimilarityOut {
+    /// Kernel used
+    pub kernel: SimilarityKernel,
+    /// One score per candidate (None if undefined)
+    pub scores: Vec<Option<f64>>,
+}
+
+/// ---- `/api/v1/plots/spec` ----
+/// A chart to render, with the input of the computation behind it.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlotSpecIn {
+    /// Bars of `/stats/distribution`'s histogram
+    Histogram(DistIn),
+    /// Step line of `/stats/ecdf`
+    Ecdf(EcdfIn),
+    /// Quartile box, Tukey whiskers and the `/stats/outliers` fliers
+    Boxplot(OutliersIn),
+    /// `/stats/qq-normal` points against the identity line
+    Qq(QqIn),
+    /// `/stats/corr-matrix` as a labelled heatmap
+    CorrHeatmap(CorrMatrixIn),
+}
+
+impl PlotSpecIn {
+    pub fn kind(&self) -> PlotKind {
+        match self {
+            PlotSpecIn::Histogram(_) => PlotKind::Histogram,
+            PlotSpecIn::Ecdf(_) => PlotKind::Ecdf,
+            PlotSpecIn::Boxplot(_) => PlotKind::Boxplot,
+            PlotSpecIn::Qq(_) => PlotKind::Qq,
+            PlotSpecIn::CorrHeatmap(_) => PlotKind::CorrHeatmap,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlotKind {
+    Histogram,
+    Ecdf,
+    Boxplot,
+    Qq,
+    CorrHeatmap,
+}
+
+/// A Vega-Lite chart with its data embedded.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct PlotSpecOut {
+    pub kind: PlotKind,
+    /// Vega-Lite v5 specification; render it as is (e.g. with `vega-embed`)
+    #[schema(value_type = Object)]
+    pub spec: serde_json::Value,
+    /// Missing-value handling applied to the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+/// ---- `/api/v1/jobs` ----
+/// A long-running analysis submitted for background execution.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobIn {
+    /// Percentile bootstrap confidence interval → [`BootstrapOut`]
+    Bootstrap(BootstrapIn),
+    /// Two-sample permutation test on the difference in means → [`PermutationOut`]
+    Permutation(PermutationIn),
+    /// Correlation matrix of many series → [`CorrMatrixOut`]
+    CorrMatrix(CorrMatrixIn),
+}
+
+impl JobIn {
+    pub fn kind(&self) -> JobKind {
+        match self {
+            JobIn::Bootstrap(_) => JobKind::Bootstrap,
+            JobIn::Permutation(_) => JobKind::Permutation,
+            JobIn::CorrMatrix(_) => JobKind::CorrMatrix,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Bootstrap,
+    Permutation,
+    CorrMatrix,
+}
+
+/// Statistic resampled by a bootstrap job.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrapStatistic {
+    #[default]
+    Mean,
+    Median,
+    /// Sample standard deviation
+    Std,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct BootstrapIn {
+    /// Sample (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub values: Vec<f64>,
+    /// Defaults to `mean`
+    #[serde(default)]
+    pub statistic: Option<BootstrapStatistic>,
+    /// Number of resamples (default 1000)
+    #[serde(default)]
+    pub resamples: Option<usize>,
+    /// Interval coverage in `(0, 1)` (default 0.95)
+    #[serde(default)]
+    pub confidence: Option<f64>,
+    /// RNG seed (default `STATS_SEED`)
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// How to treat `null`s (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct BootstrapOut {
+    pub statistic: BootstrapStatistic,
+    /// Statistic of the original sample
+    pub estimate: Option<f64>,
+    pub lower: Option<f64>,
+    pub upper: Option<f64>,
+    pub confidence: f64,
+    pub resamples: usize,
+    pub seed: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct PermutationIn {
+    /// First sample (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub x: Vec<f64>,
+    /// Second sample (`null` = missing)
+    #[serde(deserialize_with = "crate::missing::values")]
+    #[schemars(with = "Vec<Option<f64>>")]
+    #[schema(value_type = Vec<Option<f64>>)]
+    pub y: Vec<f64>,
+    /// Number of random relabelings (default 1000)
+    #[serde(default)]
+    pub permutations: Option<usize>,
+    /// RNG seed (default `STATS_SEED`)
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// How to treat `null`s in each sample (default `drop`)
+    #[serde(default)]
+    pub missing: Option<MissingPolicy>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct PermutationOut {
+    /// `mean(x) - mean(y)`
+    pub mean_diff: Option<f64>,
+    /// Two-sided p-value, `(k + 1) / (permutations + 1)`
+    pub p_value: Option<f64>,
+    pub permutations: usize,
+    pub seed: u64,
+    /// `null`s settled across both samples
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReport>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a worker
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    /// Dropped from the queue before it ran
+    Cancelled,
+}
+
+/// Status of a submitted job.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct JobOut {
+    /// Server-assigned id (e.g. `job_1`)
+    pub id: String,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    /// Fraction of the work done, in `[0, 1]`
+    pub progress: f64,
+    /// Submission time (seconds since the Unix epoch)
+    pub created_at: u64,
+    /// Completion time, once succeeded or failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    /// Failure message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Artifact holding the output (`GET /artifacts/{id}`), when it was too
+    /// large to keep with the job
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<String>,
+}
+
+/// Body of a job-completion webhook: the final [`JobOut`] and, when the
+/// job succeeded, its [`JobResult`] (unless it was moved to an artifact).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct JobEventOut {
+    #[serde(flatten)]
+    pub job: JobOut,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<JobResult>")]
+    #[schema(value_type = Option<JobResult>)]
+    pub result: Option<serde_json::Value>,
+}
+
+/// Output of a succeeded job, shaped by its [`JobKind`].
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(untagged)]
+pub enum JobResult {
+    Bootstrap(BootstrapOut),
+    Permutation(PermutationOut),
+    CorrMatrix(CorrMatrixOut),
+}
+
+/// ---- `/api/v1/artifacts` ----
+/// A stored result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct ArtifactOut {
+    /// Server-assigned id (e.g. `art_3f2a…`)
+    pub id: String,
+    /// Media type the artifact is served with
+    pub content_type: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Storage time (seconds since the Unix epoch)
+    pub created_at: u64,
+}
+
+/// ---- `/api/v1/ws/stats` ----
+/// Connection options for the live statistics socket.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WsStatsQuery {
+    /// Comma-separated probabilities to track (default `0.05,0.25,0.5,0.75,0.95`)
+    #[serde(default)]
+    pub quantiles: Option<String>,
+    /// Minimum milliseconds between updates (default 500, at least 50)
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
+    /// Flag outlying values as they arrive (off when omitted)
+    #[serde(default)]
+    pub detector: Option<StreamDetector>,
+    /// Values `robust_z` judges against (default 100, 10–10000)
+    #[serde(default)]
+    pub window: Option<usize>,
+    /// Weight of the newest value for `ewma`, in `(0, 1]` (default 0.1)
+    #[serde(default)]
+    pub alpha: Option<f64>,
+    /// Score above which a value is flagged (default 3.5)
+    #[serde(default)]
+    pub threshold: Option<f64>,
+}
+
+/// Online outlier detector of a live statistics socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamDetector {
+    /// Robust z-score against the median and MAD of the last `window` values
+    RobustZ,
+    /// Residual from an exponentially weighted mean, in EW standard deviations
+    Ewma,
+}
+
+/// A value flagged by the socket's outlier detector.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct StreamOutlier {
+    /// 0-based position in the stream, `null` entries included
+    pub index: u64,
+    pub value: f64,
+    /// Detector score (`null` = infinite, after a constant run)
+    pub score: Option<f64>,
+}
+
+/// A chunk pushed by the client: a bare array or `{"values": [...]}`
+/// (`null` = missing).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(untagged)]
+pub enum StreamChunkIn {
+    Values(Vec<Option<f64>>),
+    Object { values: Vec<Option<f64>> },
+}
+
+/// Running statistics over every value received on the socket so far.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct RunningStatsOut {
+    pub count: u64,
+    /// `null` entries received (not counted)
+    pub missing: u64,
+    pub mean: Option<f64>,
+    /// Sample standard deviation (None if `count < 2`)
+    pub std: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// P² estimates of the tracked quantiles as `(p, value)` pairs
+    pub quantiles: Vec<(f64, Option<f64>)>,
+    /// Values flagged so far (with `detector` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flagged: Option<u64>,
+    /// Values flagged since the previous update, oldest first, at most
+    /// 1000 (with `detector` only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outliers: Option<Vec<StreamOutlier>>,
+}
+
+/// ---- `/api/v1/stats/rag/metrics` ----
+/// One query's ranked retrieval result and its ground-truth relevant ids.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct RagQuery {
+    /// Retrieved document ids in rank order
+    pub retrieved: Vec<usize>,
+    /// Ids judged relevant for this query
+    pub relevant: Vec<usize>,
+}
+
+/// Input for retrieval metrics over a batch of queries.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct RagMetricsIn {
+    /// Queries to evaluate
+    pub queries: Vec<RagQuery>,
+    /// Cutoff for @k metrics (defaults to 10)
+    #[serde(default)]
+    pub k: Option<usize>,
+    /// How per-query values are combined (defaults to `mean`)
+    #[serde(default)]
+    pub aggregation: Option<Aggregation>,
+    /// Percentile in \[0,1\] for `aggregation = "percentile"` (e.g. 0.1 = worst-decile view)
+    #[serde(default)]
+    pub percentile: Option<f64>,
+}
+
+/// Aggregation applied across queries.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    Mean,
+    Median,
+    /// Quantile given by `percentile`
+    Percentile,
+}
+
+/// Metric vectors aligned with the input queries (None where undefined, e.g.
+/// recall/AP for a query without relevant ids).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct RagPerQuery {
+    pub precision_at_k: Vec<Option<f64>>,
+    pub recall_at_k: Vec<Option<f64>>,
+    /// Reciprocal rank of the first relevant hit
+    pub reciprocal_rank: Vec<Option<f64>>,
+    pub ndcg_at_k: Vec<Option<f64>>,
+    pub average_precision: Vec<Option<f64>>,
+}
+
+/// Retrieval metrics aggregated across queries (None if undefined for every query),
+/// plus the per-query breakdown.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct RagMetricsOut {
+    /// Number of queries evaluated
+    pub n_queries: usize,
+    /// Cutoff used for @k metrics
+    pub k: usize,
+    /// Aggregation used for the top-level metrics
+    pub aggregation: Aggregation,
+    pub precision_at_k: Option<f64>,
+    pub recall_at_k: Option<f64>,
+    /// Aggregated reciprocal rank (MRR under `mean`)
+    pub mrr: Option<f64>,
+    pub ndcg_at_k: Option<f64>,
+    /// Aggregated average precision (MAP under `mean`)
+    pub map: Option<f64>,
+    /// Per-query metric vectors
+    pub per_query: RagPerQuery,
+}
+
+/// ---- `/api/v1/stats/rag/mmr` ----
+/// Input for Maximal Marginal Relevance re-ranking.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct MmrIn {
+    /// Candidate vectors (same representation as `query`; dense ones share its dimension)
+    pub candidates: Vec<VectorIn>,
+    /// Query vector (dense embedding or sparse `{indices, values}`)
+    pub query: VectorIn,
+    /// Relevance/diversity trade-off in \[0,1\] (defaults to 0.5; 1 = pure relevance)
+    #[serde(default)]
+    pub lambda: Option<f64>,
+    /// Number of candidates to select
+    pub k: usize,
+}
+
+/// Selected candidates in pick order, with per-pick scores.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct MmrOut {
+    /// Indices into `candidates`, in selection order
+    pub indices: Vec<usize>,
+    /// Cosine similarity of each pick to the query
+    pub relevance: Vec<f64>,
+    /// 1 − max cosine to earlier picks (1.0 for the first pick)
+    pub diversity: Vec<f64>,
+    /// MMR objective value at the time each pick was made
+    pub scores: Vec<f64>,
+}
+
+/// ---- `/api/v1/stats/rag/text-metrics` ----
+/// Candidate answers paired index-wise with reference answers.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct TextMetricsIn {
+    /// Generated/candidate strings
+    pub candidates: Vec<String>,
+    /// Reference strings (same length as `candidates`)
+    pub references: Vec<String>,
+}
+
+/// Precision, recall, and F1 of an overlap metric.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct PrfScore {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+/// Overlap metrics for one candidate/reference pair.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct TextPairMetrics {
+    pub rouge1: PrfScore,
+    pub rouge2: PrfScore,
+    pub rouge_l: PrfScore,
+    /// Sentence BLEU-4 (add-one smoothed)
+    pub bleu: f64,
+    /// SQuAD-style token F1
+    pub token_f1: f64,
+}
+
+/// Per-pair metrics and their means (None when there are no pairs).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct TextMetricsOut {
+    pub pairs: Vec<TextPairMetrics>,
+    pub mean_rouge1_f1: Option<f64>,
+    pub mean_rouge2_f1: Option<f64>,
+    pub mean_rouge_l_f1: Option<f64>,
+    pub mean_bleu: Option<f64>,
+    pub mean_token_f1: Option<f64>,
+}
+
+/// ---- `/api/v1/stats/rag/groundedness` ----
+/// Answer-sentence and retrieved-context embeddings for a groundedness check.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct GroundednessIn {
+    /// One embedding per answer sentence
+    #[serde(deserialize_with = "crate::embedding::vectors")]
+    #[schemars(with = "Vec<crate::embedding::EmbeddingWire>")]
+    #[schema(value_type = Vec<crate::embedding::EmbeddingWire>)]
+    pub answer: Vec<Vec<f64>>,
+    /// One embedding per retrieved context chunk (same dimension as `answer`)
+    #[serde(deserialize_with = "crate::embedding::vectors")]
+    #[schemars(with = "Vec<crate::embedding::EmbeddingWire>")]
+    #[schema(value_type = Vec<crate::embedding::EmbeddingWire>)]
+    pub context: Vec<Vec<f64>>,
+    /// Max-support cosine at which a sentence counts as supported (defaults to 0.7)
+    #[serde(default)]
+    pub threshold: Option<f64>,
+}
+
+/// Support of one answer sentence by the context.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct SentenceSupportOut {
+    /// Highest cosine to any context chunk
+    pub max_support: f64,
+    /// Mean cosine over all context chunks
+    pub mean_support: f64,
+    /// Index of the best-supporting context chunk
+    pub best_context: usize,
+    /// `max_support >= threshold`
+    pub supported: bool,
+}
+
+/// Per-sentence support and overall groundedness (None without sentences or context).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct GroundednessOut {
+    pub sentences: Vec<SentenceSupportOut>,
+    /// Mean of per-sentence max support
+    pub groundedness: Option<f64>,
+    /// Fraction of sentences at or above the threshold
+    pub supported_fraction: Option<f64>,
+    /// Threshold used
+    pub threshold: f64,
+}
+
+/// ---- `/admin/*` ----
+/// Entries and bytes freed by `POST /admin/cache/flush`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct CacheFlushOut {
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+/// Datasets dropped by `DELETE /admin/datasets`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct DatasetsClearedOut {
+    pub removed: usize,
+}
+
+/// The tracing filter, in `RUST_LOG` syntax (e.g. `"info,stats_rs=debug"`).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct LogLevelIo {
+    pub filter: String,
+}
+
+/// Queued jobs cancelled by `POST /admin/jobs/drain`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct JobsDrainedOut {
+    pub cancelled: usize,
+}
+
+/// One audited API request (see `audit`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct AuditRecord {
+    /// Arrival time, milliseconds since the Unix epoch
+    pub at_ms: u64,
+    pub request_id: Option<String>,
+    /// Value of the configured user header; `None` if absent
+    pub user: Option<String>,
+    pub method: String,
+    pub path: String,
+    /// Dataset id from the path or a `dataset_id` parameter
+    pub dataset: Option<String>,
+    /// Query and JSON body parameters, arrays reduced to `"[n items]"`
+    pub params: serde_json::Value,
+    pub status: u16,
+    pub duration_ms: f64,
+}
+
+/// Filters of `GET /admin/audit`; all optional and combined with AND.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AuditQuery {
+    pub user: Option<String>,
+    /// Path prefix, e.g. `/api/v1/stats/`
+    pub path: Option<String>,
+    pub dataset: Option<String>,
+    /// Inclusive lower bound on `at_ms`
+    pub since_ms: Option<u64>,
+    /// Exclusive upper bound on `at_ms`
+    pub until_ms: Option<u64>,
+    /// Most records returned (default 100, at most 1000)
+    pub limit: Option<usize>,
+}
+
+/// Matching audit records, newest first.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, ToSchema)]
+pub struct AuditOut {
+    pub records: Vec<AuditRecord>,
 }
@@ -0,0 +1,1127 @@
+//! # Request validation
+//!
+//! Stats DTOs implement [`Validate`]; handlers take them through the [`Valid`]
+//! extractor, which deserializes the JSON body like [`Json`] and then rejects
+//! broken constraints with `422 Unprocessable Entity`
+//! ([`ServiceError::Validation`]) naming the offending field by JSON pointer
+//! (`/y`, `/quantiles/2`, `/series/1`). Checks cover the request as sent;
+//! `null`s dropped later by the missing-value policy may still leave a series
+//! empty, in which case handlers return empty results as before.
+//!
+//! The body is parsed under [`with_value_limit`], so an array past
+//! [`ServiceConfig::max_values`] is rejected at the first extra number rather
+//! than after the whole payload was decoded.
+
+use crate::{
+    config::ServiceConfig,
+    error::ServiceError,
+    missing::with_value_limit,
+    state::AppState,
+    stats::Formula,
+    types::{
+        AnomalyIn, BinRuleIn, BinScale, ColumnDistQuery, CompareIn, ContingencyIn, CorrMatrixIn,
+        CorrRowsIn, CorrSeriesIn, DistIn, DriftIn, EcdfIn, EntropyIn, GenerateIn, NormApplyIn,
+        NormParams, NormalizeIn, OutliersIn, PairIn, PlotSpecIn, QqIn, QuantileType, RecommendIn,
+        ReportQuery, SampleDistribution, SeasonalityIn, ShapeSe, SimPipeline, SimulateIn,
+        SummaryIn, TTestIn, TTestKind,
+    },
+};
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{HeaderMap, header},
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+use serde_path_to_error::Segment;
+use std::sync::Arc;
+
+/// Largest `bins` accepted by `/stats/distribution`.
+pub const MAX_BINS: usize = 10_000;
+
+/// Largest neighbour rank `k` accepted by `/stats/entropy`.
+pub const MAX_ENTROPY_K: usize = 100;
+
+/// Most distinct labels per side accepted by `/stats/contingency`.
+pub const MAX_LEVELS: usize = 1_000;
+
+/// Longest lag or period searched by `/stats/seasonality`.
+pub const MAX_LAG: usize = 10_000;
+
+/// Most isolation-forest trees grown by `/stats/anomaly/score`.
+pub const MAX_TREES: usize = 1_000;
+
+/// Largest binomial `trials` or Poisson `lambda` accepted by `/stats/generate`.
+pub const MAX_COUNT_PARAM: f64 = 1e12;
+
+/// Most bootstrap resamples behind `/stats/distribution`'s `shape_se`.
+pub const MAX_SHAPE_RESAMPLES: usize = 10_000;
+
+/// Most values `/stats/drift` compares over all windows.
+pub const MAX_DRIFT_VALUES: usize = 100_000_000;
+
+/// Most replications run by `/stats/simulate`.
+pub const MAX_REPLICATIONS: usize = 1_000_000;
+
+/// Most values `/stats/simulate` draws over all replications.
+pub const MAX_SIMULATED_DRAWS: usize = 100_000_000;
+
+/// Longest `/stats/simulate` formula, in bytes.
+pub const MAX_FORMULA_LEN: usize = 1_000;
+
+/// Most groups compared by `/stats/tests/recommend`.
+pub const MAX_GROUPS: usize = 1_000;
+
+/// Binning rules understood by `/stats/binrule`.
+pub const BIN_RULES: [&str; 11] = [
+    "auto",
+    "sturges",
+    "sqrt",
+    "rice",
+    "doane",
+    "scott",
+    "fd",
+    "freedmandiaconis",
+    "freedman_diaconis",
+    "shimazaki",
+    "shimazaki_shinomoto",
+];
+
+/// Constraint checks for a request body.
+pub trait Validate {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError>;
+}
+
+/// A JSON body that passed [`Validate::validate`].
+#[derive(Debug, Clone)]
+pub struct Valid<T>(pub T);
+
+impl<T> FromRequest<Arc<AppState>> for Valid<T>
+where
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &Arc<AppState>) -> Result<Self, Response> {
+        let body = if is_json(req.headers()) {
+            let bytes = Bytes::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            match parse::<T>(&bytes, state.config.max_values)
+                .map_err(IntoResponse::into_response)?
+            {
+                Some(body) => body,
+                // malformed input only: re-parse for axum's status and message
+                None => {
+                    Json::<T>::from_bytes(&bytes)
+                        .map_err(IntoResponse::into_response)?
+                        .0
+                }
+            }
+        } else {
+            // axum's `415` for a missing or non-JSON content type
+            let Json(body) = Json::<T>::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            body
+        };
+        body.validate(&state.config)
+            .map_err(IntoResponse::into_response)?;
+        Ok(Valid(body))
+    }
+}
+
+/// `application/json` or an `application/*+json` type, as [`Json`] accepts.
+fn is_json(headers: &HeaderMap) -> bool {
+    let Some(ct) = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return false;
+    };
+    let essence = ct.split(';').next().unwrap_or_default().trim();
+    essence.split_once('/').is_some_and(|(ty, sub)| {
+        ty.eq_ignore_ascii_case("application")
+            && (sub.eq_ignore_ascii_case("json") || sub.to_ascii_lowercase().ends_with("+json"))
+    })
+}
+
+/// Decode `bytes` with number arrays capped at `limit`; a capped array is a
+/// validation error naming it, a malformed body `None`.
+fn parse<T: DeserializeOwned>(bytes: &[u8], limit: usize) -> Result<Option<T>, ServiceError> {
+    let (out, overflowed) = with_value_limit(limit, || {
+        serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_slice(bytes))
+    });
+    match out {
+        Ok(body) => Ok(Some(body)),
+        Err(e) if overflowed => Err(invalid(
+            pointer(e.path()),
+            format!("has more than {limit} values; at most {limit} allowed"),
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+/// JSON pointer (`/series/1`) of a deserialization path.
+fn pointer(path: &serde_path_to_error::Path) -> String {
+    path.iter()
+        .map(|s| match s {
+            Segment::Seq { index } => format!("/{index}"),
+            Segment::Map { key } => format!("/{key}"),
+            Segment::Enum { variant } => format!("/{variant}"),
+            Segment::Unknown => "/?".to_owned(),
+        })
+        .collect()
+}
+
+pub fn invalid(field: impl Into<String>, message: impl Into<String>) -> ServiceError {
+    ServiceError::Validation {
+        field: field.into(),
+        message: message.into(),
+    }
+}
+
+/// A non-empty series within [`ServiceConfig::max_values`].
+pub fn series(field: &str, xs: &[f64], cfg: &ServiceConfig) -> Result<(), ServiceError> {
+    if xs.is_empty() {
+        return Err(invalid(field, "must not be empty"));
+    }
+    if xs.len() > cfg.max_values {
+        return Err(invalid(
+            field,
+            format!(
+                "has {} values; at most {} allowed",
+                xs.len(),
+                cfg.max_values
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn probability(field: String, p: f64) -> Result<(), ServiceError> {
+    if (0.0..=1.0).contains(&p) {
+        Ok(())
+    } else {
+        Err(invalid(field, format!("must be in [0, 1], got {p}")))
+    }
+}
+
+/// Interval coverage, strictly inside `(0, 1)`.
+fn confidence(field: &str, c: f64) -> Result<(), ServiceError> {
+    if c > 0.0 && c < 1.0 {
+        Ok(())
+    } else {
+        Err(invalid(field, format!("must be in (0, 1), got {c}")))
+    }
+}
+
+/// Comma-separated probabilities, each in `[0, 1]`; `field/i` names a bad one.
+pub fn probability_list(field: &str, s: &str) -> Result<Vec<f64>, ServiceError> {
+    s.split(',')
+        .enumerate()
+        .map(|(i, p)| {
+            let field = format!("{field}/{i}");
+            let p = p
+                .trim()
+                .parse()
+                .map_err(|_| invalid(field.clone(), format!("'{}' is not a number", p.trim())))?;
+            probability(field, p).map(|()| p)
+        })
+        .collect()
+}
+
+fn bins(b: Option<usize>) -> Result<(), ServiceError> {
+    match b.filter(|b| !(2..=MAX_BINS).contains(b)) {
+        Some(b) => Err(invalid(
+            "/bins",
+            format!("must be in 2..={MAX_BINS}, got {b}"),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Explicit histogram edges: finite, strictly ascending, 2..=[`MAX_BINS`] bins.
+fn bin_edges(edges: &[f64]) -> Result<(), ServiceError> {
+    if !(3..=MAX_BINS + 1).contains(&edges.len()) {
+        return Err(invalid(
+            "/edges",
+            format!("must have 3..={} edges, got {}", MAX_BINS + 1, edges.len()),
+        ));
+    }
+    if let Some(i) = edges.iter().position(|e| !e.is_finite()) {
+        return Err(invalid(format!("/edges/{i}"), "must be finite"));
+    }
+    match edges.windows(2).position(|w| w[0] >= w[1]) {
+        Some(i) => Err(invalid(
+            format!("/edges/{}", i + 1),
+            "must be greater than the edge before it",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Frequency weights aligned with `n` values: finite, non-negative and not
+/// all zero.
+fn weights(ws: &[f64], n: usize) -> Result<(), ServiceError> {
+    if ws.len() != n {
+        return Err(invalid(
+            "/weights",
+            format!(
+                "has {} entries but /values has {n}; they must be the same length",
+                ws.len()
+            ),
+        ));
+    }
+    if let Some(i) = ws.iter().position(|w| !(w.is_finite() && *w >= 0.0)) {
+        return Err(invalid(
+            format!("/weights/{i}"),
+            format!("must be a finite number ≥ 0, got {}", ws[i]),
+        ));
+    }
+    if ws.iter().all(|&w| w == 0.0) {
+        return Err(invalid("/weights", "must not all be zero"));
+    }
+    Ok(())
+}
+
+/// Group labels aligned with `n` values, none of them `null`.
+fn groups(labels: &[Option<String>], n: usize) -> Result<(), ServiceError> {
+    if labels.len() != n {
+        return Err(invalid(
+            "/groups",
+            format!(
+                "has {} labels but /values has {n}; they must be the same length",
+                labels.len()
+            ),
+        ));
+    }
+    match labels.iter().position(Option::is_none) {
+        Some(i) => Err(invalid(format!("/groups/{i}"), "must be a label, not null")),
+        None => Ok(()),
+    }
+}
+
+/// Every present (non-`NaN`) value is positive; `field/i` names the first that is not.
+pub fn positive(field: &str, xs: &[f64]) -> Result<(), ServiceError> {
+    match xs.iter().position(|&x| x <= 0.0) {
+        Some(i) => Err(invalid(
+            format!("{field}/{i}"),
+            format!("must be positive for log bins, got {}", xs[i]),
+        )),
+        None => Ok(()),
+    }
+}
+
+impl Validate for ReportQuery {
+    fn validate(&self, _: &ServiceConfig) -> Result<(), ServiceError> {
+        bins(self.bins)
+    }
+}
+
+impl Validate for SummaryIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        for (i, &p) in self.percentiles.iter().flatten().enumerate() {
+            if !(0.0..=100.0).contains(&p) {
+                return Err(invalid(
+                    format!("/percentiles/{i}"),
+                    format!("must be in [0, 100], got {p}"),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Validate for DistIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        bins(self.bins)?;
+        if let Some(ws) = &self.weights {
+            weights(ws, self.values.len())?;
+            if self.approx == Some(true) {
+                return Err(invalid("/approx", "cannot be combined with weights"));
+            }
+            if self.quantile_method.is_some_and(|m| m != QuantileType::R7) {
+                return Err(invalid(
+                    "/quantile_method",
+                    "weighted quantiles are r7 only",
+                ));
+            }
+            if self.shape_se == Some(ShapeSe::Bootstrap) {
+                return Err(invalid(
+                    "/shape_se",
+                    "weighted standard errors are analytic only",
+                ));
+            }
+        }
+        if let Some(resamples) = self.resamples {
+            if self.shape_se != Some(ShapeSe::Bootstrap) {
+                return Err(invalid("/resamples", "requires shape_se: bootstrap"));
+            }
+            if !(1..=MAX_SHAPE_RESAMPLES).contains(&resamples) {
+                return Err(invalid(
+                    "/resamples",
+                    format!("must be in 1..={MAX_SHAPE_RESAMPLES}, got {resamples}"),
+                ));
+            }
+        }
+        if let Some(edges) = &self.edges {
+            if self.bins.is_some() || self.scale.is_some() {
+                return Err(invalid("/edges", "cannot be combined with bins or scale"));
+            }
+            bin_edges(edges)?;
+        }
+        if self.scale == Some(BinScale::Log) {
+            positive("/values", &self.values)?;
+        }
+        for (i, &p) in self.quantiles.iter().flatten().enumerate() {
+            probability(format!("/quantiles/{i}"), p)?;
+        }
+        match self.bandwidth {
+            Some(h) if !(h.is_finite() && h > 0.0) => Err(invalid(
+                "/bandwidth",
+                format!("must be a positive number, got {h}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Validate for ColumnDistQuery {
+    fn validate(&self, _: &ServiceConfig) -> Result<(), ServiceError> {
+        bins(self.bins)?;
+        match &self.quantiles {
+            Some(qs) => probability_list("/quantiles", qs).map(drop),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Validate for PairIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/x", &self.x, cfg)?;
+        series("/y", &self.y, cfg)?;
+        if self.x.len() != self.y.len() {
+            return Err(invalid(
+                "/y",
+                format!(
+                    "has {} values but /x has {}; the series must be the same length",
+                    self.y.len(),
+                    self.x.len()
+                ),
+            ));
+        }
+        if let Some(c) = self.confidence {
+            confidence("/confidence", c)?;
+        }
+        Ok(())
+    }
+}
+
+impl Validate for EcdfIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        if let Some(w) = &self.window {
+            w.validate("/window")?;
+        }
+        match self.max_points {
+            Some(m) if m < 2 => Err(invalid(
+                "/max_points",
+                format!("must be at least 2, got {m}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Validate for QqIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        match &self.window {
+            Some(w) => w.validate("/window"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Validate for CorrMatrixIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        let Some(first) = self.series.first() else {
+            return Err(invalid("/series", "must not be empty"));
+        };
+        for (i, s) in self.series.iter().enumerate() {
+            series(&format!("/series/{i}"), s, cfg)?;
+            if s.len() != first.len() {
+                return Err(invalid(
+                    format!("/series/{i}"),
+                    format!(
+                        "has {} values but /series/0 has {}; all series must be the same length",
+                        s.len(),
+                        first.len()
+                    ),
+                ));
+            }
+        }
+        let total = first.len().saturating_mul(self.series.len());
+        if total > cfg.max_values {
+            return Err(invalid(
+                "/series",
+                format!("has {total} values; at most {} allowed", cfg.max_values),
+            ));
+        }
+        match &self.names {
+            Some(names) if names.len() != self.series.len() => Err(invalid(
+                "/names",
+                format!("has {} names for {} series", names.len(), self.series.len()),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Appended values must all be present: a stored matrix has no missing policy.
+fn complete(field: &str, xs: &[f64]) -> Result<(), ServiceError> {
+    match xs.iter().position(|x| x.is_nan()) {
+        Some(i) => Err(invalid(format!("{field}/{i}"), "must be a number")),
+        None => Ok(()),
+    }
+}
+
+impl Validate for CorrSeriesIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        if self.name.is_empty() {
+            return Err(invalid("/name", "must not be empty"));
+        }
+        series("/values", &self.values, cfg)?;
+        complete("/values", &self.values)
+    }
+}
+
+impl Validate for CorrRowsIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        if self.rows.is_empty() {
+            return Err(invalid("/rows", "must not be empty"));
+        }
+        for (i, row) in self.rows.iter().enumerate() {
+            let field = format!("/rows/{i}");
+            series(&field, row, cfg)?;
+            complete(&field, row)?;
+        }
+        Ok(())
+    }
+}
+
+impl Validate for PlotSpecIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        match self {
+            PlotSpecIn::Histogram(inp) => inp.validate(cfg),
+            PlotSpecIn::Ecdf(inp) => inp.validate(cfg),
+            PlotSpecIn::Boxplot(inp) if inp.groups.is_some() => {
+                Err(invalid("/groups", "not supported by boxplot specs"))
+            }
+            PlotSpecIn::Boxplot(inp) => inp.validate(cfg),
+            PlotSpecIn::Qq(inp) => inp.validate(cfg),
+            PlotSpecIn::CorrHeatmap(inp) => inp.validate(cfg),
+        }
+    }
+}
+
+impl Validate for OutliersIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        if let Some(labels) = &self.groups {
+            groups(labels, self.values.len())?;
+        }
+        if self.top_k == Some(0) {
+            return Err(invalid("/top_k", "must be at least 1"));
+        }
+        match self.threshold {
+            Some(t) if !(t.is_finite() && t > 0.0) => Err(invalid(
+                "/threshold",
+                format!("must be a positive number, got {t}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Validate for NormalizeIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        if let Some(labels) = &self.groups {
+            groups(labels, self.values.len())?;
+        }
+        if let Some(w) = &self.window {
+            w.validate("/window")?;
+        }
+        match self.range {
+            Some((lo, hi)) if !(lo.is_finite() && hi.is_finite() && lo < hi) => Err(invalid(
+                "/range",
+                format!("must be finite with lower < upper, got ({lo}, {hi})"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Validate for NormApplyIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        match self.params {
+            NormParams::Zscore { mean, .. } if !mean.is_finite() => Err(invalid(
+                "/params/mean",
+                format!("must be a finite number, got {mean}"),
+            )),
+            NormParams::Zscore { std, .. } if !(std.is_finite() && std >= 0.0) => Err(invalid(
+                "/params/std",
+                format!("must be a non-negative number, got {std}"),
+            )),
+            NormParams::Minmax { min, max, .. }
+                if !(min.is_finite() && max.is_finite() && min <= max) =>
+            {
+                Err(invalid(
+                    "/params/max",
+                    format!("must be finite with min <= max, got ({min}, {max})"),
+                ))
+            }
+            NormParams::Minmax {
+                range: (lo, hi), ..
+            } if !(lo.is_finite() && hi.is_finite() && lo < hi) => Err(invalid(
+                "/params/range",
+                format!("must be finite with lower < upper, got ({lo}, {hi})"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Validate for BinRuleIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        match &self.rule {
+            Some(r) if !BIN_RULES.contains(&r.to_lowercase().as_str()) => Err(invalid(
+                "/rule",
+                format!(
+                    "unknown rule '{r}'; expected one of {}",
+                    BIN_RULES.join(", ")
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Validate for EntropyIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        bins(self.bins)?;
+        match self.k.filter(|k| !(1..=MAX_ENTROPY_K).contains(k)) {
+            Some(k) => Err(invalid(
+                "/k",
+                format!("must be in 1..={MAX_ENTROPY_K}, got {k}"),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Validate for ContingencyIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        for (field, labels) in [("/x", &self.x), ("/y", &self.y)] {
+            if labels.is_empty() {
+                return Err(invalid(field, "must not be empty"));
+            }
+            if labels.len() > cfg.max_values {
+                return Err(invalid(
+                    field,
+                    format!(
+                        "has {} labels; at most {} allowed",
+                        labels.len(),
+                        cfg.max_values
+                    ),
+                ));
+            }
+        }
+        if self.x.len() != self.y.len() {
+            return Err(invalid(
+                "/y",
+                format!(
+                    "has {} labels but /x has {}; the arrays must be the same length",
+                    self.y.len(),
+                    self.x.len()
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for SeasonalityIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        for (field, lag) in [("/period", self.period), ("/max_lag", self.max_lag)] {
+            if let Some(lag) = lag.filter(|l| !(2..=MAX_LAG).contains(l)) {
+                return Err(invalid(
+                    field,
+                    format!("must be in 2..={MAX_LAG}, got {lag}"),
+                ));
+            }
+        }
+        match &self.timestamps {
+            Some(ts) if ts.len() != self.values.len() => Err(invalid(
+                "/timestamps",
+                format!(
+                    "has {} entries but /values has {}; they must be the same length",
+                    ts.len(),
+                    self.values.len()
+                ),
+            )),
+            None if self.season.is_some() => Err(invalid("/season", "requires timestamps")),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Validate for AnomalyIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        if let Some(detectors) = &self.detectors {
+            if detectors.is_empty() {
+                return Err(invalid("/detectors", "must name at least one detector"));
+            }
+            for (i, d) in detectors.iter().enumerate() {
+                if detectors[..i].contains(d) {
+                    return Err(invalid(format!("/detectors/{i}"), "is listed twice"));
+                }
+            }
+        }
+        if self.top_k == Some(0) {
+            return Err(invalid("/top_k", "must be at least 1"));
+        }
+        let bounds = [
+            ("/window", self.window, 3, 1001),
+            ("/trees", self.trees, 1, MAX_TREES),
+            ("/sample_size", self.sample_size, 2, 4096),
+        ];
+        for (field, v, lo, hi) in bounds {
+            if let Some(v) = v.filter(|v| !(lo..=hi).contains(v)) {
+                return Err(invalid(field, format!("must be in {lo}..={hi}, got {v}")));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Validate for CompareIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/x", &self.x, cfg)?;
+        series("/y", &self.y, cfg)?;
+        bins(self.bins)?;
+        match self.max_points {
+            Some(m) if m < 2 => Err(invalid("/max_points", "must be at least 2")),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Validate for DriftIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/values", &self.values, cfg)?;
+        let n = self.values.len();
+        if !(2..=n).contains(&self.window) {
+            return Err(invalid(
+                "/window",
+                format!(
+                    "must be in 2..={n} (the number of values), got {}",
+                    self.window
+                ),
+            ));
+        }
+        let step = self.step.unwrap_or(self.window);
+        if step == 0 {
+            return Err(invalid("/step", "must be at least 1"));
+        }
+        let windows = (n - self.window) / step + 1;
+        if windows.saturating_mul(self.window) > MAX_DRIFT_VALUES {
+            return Err(invalid(
+                "/step",
+                format!(
+                    "{windows} windows of {} values exceed the limit of \
+                     {MAX_DRIFT_VALUES} values",
+                    self.window
+                ),
+            ));
+        }
+        bins(self.bins)
+    }
+}
+
+impl Validate for TTestIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        series("/x", &self.x, cfg)?;
+        let test = self.kind();
+        match (&self.y, test) {
+            (Some(_), TTestKind::OneSample) => {
+                return Err(invalid("/y", "not used by a one_sample test"));
+            }
+            (None, TTestKind::Paired | TTestKind::Welch) => {
+                return Err(invalid("/y", "required by paired and welch tests"));
+            }
+            (Some(y), _) => series("/y", y, cfg)?,
+            (None, _) => {}
+        }
+        if let (Some(y), TTestKind::Paired) = (&self.y, test)
+            && y.len() != self.x.len()
+        {
+            return Err(invalid(
+                "/y",
+                format!(
+                    "has {} values but /x has {}; paired samples must be the same length",
+                    y.len(),
+                    self.x.len()
+                ),
+            ));
+        }
+        if let Some(mu) = self.mu.filter(|m| !m.is_finite()) {
+            return Err(invalid("/mu", format!("must be finite, got {mu}")));
+        }
+        match self.confidence {
+            Some(c) => confidence("/confidence", c),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Validate for GenerateIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        if !(1..=cfg.max_values).contains(&self.n) {
+            return Err(invalid(
+                "/n",
+                format!("must be in 1..={}, got {}", cfg.max_values, self.n),
+            ));
+        }
+        self.distribution.validate("/distribution")
+    }
+}
+
+impl Validate for RecommendIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        if !(2..=MAX_GROUPS).contains(&self.groups.len()) {
+            return Err(invalid(
+                "/groups",
+                format!(
+                    "must have 2..={MAX_GROUPS} groups, got {}",
+                    self.groups.len()
+                ),
+            ));
+        }
+        for (i, g) in self.groups.iter().enumerate() {
+            series(&format!("/groups/{i}"), g, cfg)?;
+        }
+        let total: usize = self.groups.iter().map(Vec::len).sum();
+        if total > cfg.max_values {
+            return Err(invalid(
+                "/groups",
+                format!("has {total} values; at most {} allowed", cfg.max_values),
+            ));
+        }
+        match self.alpha {
+            Some(a) => confidence("/alpha", a),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Validate for SimulateIn {
+    fn validate(&self, cfg: &ServiceConfig) -> Result<(), ServiceError> {
+        let replications = self.replications.unwrap_or(10_000);
+        if !(1..=MAX_REPLICATIONS).contains(&replications) {
+            return Err(invalid(
+                "/replications",
+                format!("must be in 1..={MAX_REPLICATIONS}, got {replications}"),
+            ));
+        }
+        let per_replication = match &self.pipeline {
+            SimPipeline::Difference { x, y, n_x, n_y, .. } => {
+                for (field, n) in [("/pipeline/n_x", Some(*n_x)), ("/pipeline/n_y", *n_y)] {
+                    if let Some(n) = n.filter(|n| !(1..=cfg.max_values).contains(n)) {
+                        return Err(invalid(
+                            field,
+                            format!("must be in 1..={}, got {n}", cfg.max_values),
+                        ));
+                    }
+                }
+                x.validate("/pipeline/x")?;
+                if let Some(y) = y {
+                    y.validate("/pipeline/y")?;
+                }
+                n_x + n_y.unwrap_or(*n_x)
+            }
+            SimPipeline::Formula {
+                expression,
+                variables,
+            } => {
+                if expression.len() > MAX_FORMULA_LEN {
+                    return Err(invalid(
+                        "/pipeline/expression",
+                        format!(
+                            "must be at most {MAX_FORMULA_LEN} bytes, got {}",
+                            expression.len()
+                        ),
+                    ));
+                }
+                for (name, d) in variables {
+                    d.validate(&format!("/pipeline/variables/{name}"))?;
+                }
+                let names: Vec<&str> = variables.keys().map(String::as_str).collect();
+                Formula::parse(expression, &names)
+                    .map_err(|e| invalid("/pipeline/expression", e))?;
+                variables.len().max(1)
+            }
+        };
+        if replications.saturating_mul(per_replication) > MAX_SIMULATED_DRAWS {
+            return Err(invalid(
+                "/replications",
+                format!(
+                    "{replications} replications of {per_replication} draws exceed \
+                     the limit of {MAX_SIMULATED_DRAWS} draws"
+                ),
+            ));
+        }
+        if let Some(v) = self.observed.filter(|v| !v.is_finite()) {
+            return Err(invalid("/observed", format!("must be finite, got {v}")));
+        }
+        bins(self.bins)
+    }
+}
+
+impl SampleDistribution {
+    /// Parameters in range, with `field` the pointer to the distribution.
+    pub fn validate(&self, field: &str) -> Result<(), ServiceError> {
+        let positive = |name: &str, v: Option<f64>| match v {
+            Some(v) if !(v.is_finite() && v > 0.0) => Err(invalid(
+                format!("{field}/{name}"),
+                format!("must be a positive number, got {v}"),
+            )),
+            _ => Ok(()),
+        };
+        let finite = |name: &str, v: Option<f64>| match v {
+            Some(v) if !v.is_finite() => Err(invalid(
+                format!("{field}/{name}"),
+                format!("must be finite, got {v}"),
+            )),
+            _ => Ok(()),
+        };
+        match *self {
+            SampleDistribution::Normal { mean, std_dev } => {
+                finite("mean", mean)?;
+                positive("std_dev", std_dev)
+            }
+            SampleDistribution::Uniform { low, high } => {
+                finite("low", low)?;
+                finite("high", high)?;
+                match (low.unwrap_or(0.0), high.unwrap_or(1.0)) {
+                    (lo, hi) if lo >= hi => Err(invalid(
+                        format!("{field}/high"),
+                        format!("must be above low ({lo}), got {hi}"),
+                    )),
+                    _ => Ok(()),
+                }
+            }
+            SampleDistribution::Exponential { rate } => positive("rate", rate),
+            SampleDistribution::Gamma { shape, scale } => {
+                positive("shape", Some(shape))?;
+                positive("scale", scale)
+            }
+            SampleDistribution::Binomial { trials, p } => {
+                if trials > MAX_COUNT_PARAM as u64 {
+                    return Err(invalid(
+                        format!("{field}/trials"),
+                        format!("must be at most {MAX_COUNT_PARAM:e}, got {trials}"),
+                    ));
+                }
+                probability(format!("{field}/p"), p)
+            }
+            SampleDistribution::Poisson { lambda } => {
+                positive("lambda", Some(lambda))?;
+                match lambda > MAX_COUNT_PARAM {
+                    true => Err(invalid(
+                        format!("{field}/lambda"),
+                        format!("must be at most {MAX_COUNT_PARAM:e}, got {lambda}"),
+                    )),
+                    false => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(r: Result<(), ServiceError>) -> String {
+        match r {
+            Err(ServiceError::Validation { field, .. }) => field,
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_the_offending_field() {
+        let cfg = ServiceConfig::default();
+        let dist = |bins, quantiles| DistIn {
+            values: vec![1.0, 2.0],
+            weights: None,
+            bins,
+            edges: None,
+            scale: None,
+            quantiles,
+            quantile_method: None,
+            missing: None,
+            approx: None,
+            seed: None,
+            shape_se: None,
+            resamples: None,
+            density: None,
+            kde: None,
+            bandwidth: None,
+        };
+        assert!(dist(Some(2), Some(vec![0.0, 1.0])).validate(&cfg).is_ok());
+        assert_eq!(field(dist(Some(1), None).validate(&cfg)), "/bins");
+        assert_eq!(
+            field(dist(None, Some(vec![0.5, 1.5])).validate(&cfg)),
+            "/quantiles/1"
+        );
+        let kde = DistIn {
+            bandwidth: Some(0.0),
+            ..dist(None, None)
+        };
+        assert_eq!(field(kde.validate(&cfg)), "/bandwidth");
+        let edged = |edges: Vec<f64>| DistIn {
+            edges: Some(edges),
+            ..dist(None, None)
+        };
+        assert!(edged(vec![0.0, 1.0, 5.0]).validate(&cfg).is_ok());
+        assert_eq!(field(edged(vec![0.0, 1.0]).validate(&cfg)), "/edges");
+        assert_eq!(field(edged(vec![0.0, 2.0, 2.0]).validate(&cfg)), "/edges/2");
+        let both = DistIn {
+            bins: Some(4),
+            ..edged(vec![0.0, 1.0, 5.0])
+        };
+        assert_eq!(field(both.validate(&cfg)), "/edges");
+        let log = DistIn {
+            values: vec![3.0, f64::NAN, 0.0],
+            scale: Some(BinScale::Log),
+            ..dist(None, None)
+        };
+        assert_eq!(field(log.validate(&cfg)), "/values/2");
+        let weighted = |weights: Vec<f64>| DistIn {
+            weights: Some(weights),
+            ..dist(None, None)
+        };
+        assert!(weighted(vec![0.0, 2.5]).validate(&cfg).is_ok());
+        assert_eq!(field(weighted(vec![1.0]).validate(&cfg)), "/weights");
+        assert_eq!(
+            field(weighted(vec![1.0, -1.0]).validate(&cfg)),
+            "/weights/1"
+        );
+        assert_eq!(field(weighted(vec![0.0, 0.0]).validate(&cfg)), "/weights");
+        let approx = DistIn {
+            approx: Some(true),
+            ..weighted(vec![1.0, 1.0])
+        };
+        assert_eq!(field(approx.validate(&cfg)), "/approx");
+        let boot = |shape_se, resamples| DistIn {
+            shape_se,
+            resamples,
+            ..dist(None, None)
+        };
+        assert!(
+            boot(Some(ShapeSe::Bootstrap), Some(200))
+                .validate(&cfg)
+                .is_ok()
+        );
+        assert_eq!(
+            field(boot(Some(ShapeSe::Analytic), Some(200)).validate(&cfg)),
+            "/resamples"
+        );
+        assert_eq!(
+            field(boot(Some(ShapeSe::Bootstrap), Some(0)).validate(&cfg)),
+            "/resamples"
+        );
+        let weighted_boot = DistIn {
+            shape_se: Some(ShapeSe::Bootstrap),
+            ..weighted(vec![1.0, 1.0])
+        };
+        assert_eq!(field(weighted_boot.validate(&cfg)), "/shape_se");
+
+        let pair = PairIn {
+            x: vec![1.0, 2.0],
+            y: vec![1.0],
+            missing: None,
+            confidence: None,
+        };
+        assert_eq!(field(pair.validate(&cfg)), "/y");
+
+        let corr = CorrMatrixIn {
+            series: vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0]],
+            names: None,
+            method: None,
+            missing: None,
+            p_values: None,
+            cluster: None,
+            covariance: None,
+            partial: None,
+        };
+        assert_eq!(field(corr.validate(&cfg)), "/series/2");
+
+        let summary = SummaryIn {
+            values: vec![],
+            missing: None,
+            approx: None,
+            seed: None,
+            percentiles: None,
+            quantile_method: None,
+        };
+        assert_eq!(field(summary.validate(&cfg)), "/values");
+        let summary = SummaryIn {
+            values: vec![1.0],
+            missing: None,
+            approx: None,
+            seed: None,
+            percentiles: Some(vec![5.0, 101.0]),
+            quantile_method: None,
+        };
+        assert_eq!(field(summary.validate(&cfg)), "/percentiles/1");
+    }
+
+    #[test]
+    fn enforces_the_value_limit() {
+        let cfg = ServiceConfig {
+            max_values: 3,
+            ..Default::default()
+        };
+        let corr = CorrMatrixIn {
+            series: vec![vec![1.0, 2.0]; 2],
+            names: Some(vec!["a".into(), "b".into()]),
+            method: None,
+            missing: None,
+            p_values: None,
+            cluster: None,
+            covariance: None,
+            partial: None,
+        };
+        assert_eq!(field(corr.validate(&cfg)), "/series");
+        let q = QqIn {
+            values: vec![0.0; 4],
+            robust: None,
+            window: None,
+            missing: None,
+        };
+        assert_eq!(field(q.validate(&cfg)), "/values");
+    }
+}
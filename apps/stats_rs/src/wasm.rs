@@ -0,0 +1,169 @@
+//! # WebAssembly exports (feature `wasm`)
+//!
+//! `wasm-bindgen` bindings so the frontend can run small analyses in the
+//! browser with the same kernels, and so the same numbers, as the service.
+//! Results have the shape of the matching HTTP responses ([`SummaryOut`],
+//! [`PairOut`]); `NaN` entries count as missing and are dropped, like `null`
+//! under the API's default `missing: "drop"`.
+//!
+//! Build without the server:
+//!
+//! ```bash
+//! cargo rustc --lib --release --crate-type cdylib --target wasm32-unknown-unknown \
+//!     --no-default-features --features wasm
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/stats_rs.wasm
+//! ```
+
+use crate::{
+    error::ServiceError,
+    missing::{resolve, resolve_series},
+    stats::prelude::*,
+    types::{CorrMethod, CorrTest, MissingPolicy, PairOut, SummaryOut},
+};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Histogram counts and the `counts.len() + 1` bin edges.
+#[derive(Debug, Serialize)]
+pub struct HistogramOut {
+    pub counts: Vec<usize>,
+    pub edges: Vec<f64>,
+}
+
+fn o(x: f64) -> Option<f64> {
+    if x.is_nan() { None } else { Some(x) }
+}
+
+/// `count`, `mean`, `median`, `std`, `min`, `max`, `iqr`, `mad`, `cv`,
+/// `sem`, `skewness` and `excess_kurtosis`, as `POST /stats/summary`
+/// computes them.
+pub fn summary_of(values: &[f64]) -> Result<SummaryOut, ServiceError> {
+    let r = resolve(values.to_vec(), MissingPolicy::Drop)?;
+    let xs = &r.values;
+    let m = mean(xs);
+    let s = sorted(xs);
+    let sd = sample_std_dev(xs, m);
+    let nonempty = |f: &dyn Fn() -> f64| (!xs.is_empty()).then(f).and_then(o);
+    Ok(SummaryOut {
+        count: xs.len(),
+        mean: nonempty(&|| m),
+        median: nonempty(&|| median_sorted(&s)),
+        std: nonempty(&|| sd),
+        min: nonempty(&|| s[0]),
+        max: nonempty(&|| s[s.len() - 1]),
+        iqr: nonempty(&|| iqr_sorted(&s)),
+        mad: nonempty(&|| mad_sorted(&s)),
+        cv: nonempty(&|| if m == 0.0 { f64::NAN } else { sd / m }),
+        sem: nonempty(&|| sd / (xs.len() as f64).sqrt()),
+        skewness: nonempty(&|| skewness(xs)),
+        excess_kurtosis: nonempty(&|| excess_kurtosis(xs)),
+        percentiles: None,
+        schema: None,
+        sample: None,
+        missing: Some(r.report),
+        approx: None,
+    })
+}
+
+/// Equal-width histogram with `bins` bins (default 10, at least 2), as in
+/// `POST /stats/distribution`.
+pub fn histogram_of(values: &[f64], bins: Option<u32>) -> Result<HistogramOut, ServiceError> {
+    let r = resolve(values.to_vec(), MissingPolicy::Drop)?;
+    if r.values.is_empty() {
+        return Ok(HistogramOut {
+            counts: vec![],
+            edges: vec![],
+        });
+    }
+    let bins = bins.map_or(10, |b| b as usize).max(2);
+    let (counts, edges) = crate::stats::histogram(&r.values, bins);
+    Ok(HistogramOut { counts, edges })
+}
+
+/// Covariance and Pearson, Spearman and Kendall correlations of paired
+/// series, as in `POST /stats/pairwise`.
+pub fn correlation_of(x: &[f64], y: &[f64]) -> Result<PairOut, ServiceError> {
+    let (xy, report) = resolve_series(vec![x.to_vec(), y.to_vec()], MissingPolicy::Drop)?;
+    let (x, y) = (&xy[0], &xy[1]);
+    if x.len() != y.len() || x.is_empty() {
+        return Ok(PairOut {
+            covariance: None,
+            pearson: None,
+            spearman: None,
+            kendall: None,
+            n: 0,
+            confidence: 0.95,
+            pearson_test: None,
+            spearman_test: None,
+            kendall_test: None,
+            missing: Some(report),
+        });
+    }
+    let n = x.len();
+    let pearson = o(pearson_correlation(x, y));
+    let spearman = o(pearson_correlation(&average_ranks(x), &average_ranks(y)));
+    let kendall = o(kendall_tau_b(x, y));
+    let test = |method, r: Option<f64>| r.map(|r| CorrTest::new(method, r, n, 0.95));
+    Ok(PairOut {
+        covariance: o(covariance(x, y)),
+        pearson,
+        spearman,
+        kendall,
+        n,
+        confidence: 0.95,
+        pearson_test: test(CorrMethod::Pearson, pearson),
+        spearman_test: test(CorrMethod::Spearman, spearman),
+        kendall_test: test(CorrMethod::Kendall, kendall),
+        missing: Some(report),
+    })
+}
+
+fn to_js<T: Serialize>(out: Result<T, ServiceError>) -> Result<JsValue, JsError> {
+    let out = out.map_err(|e| JsError::new(&e.to_string()))?;
+    out.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// `summary(values: Float64Array | number[])` → `SummaryOut`
+#[wasm_bindgen]
+pub fn summary(values: &[f64]) -> Result<JsValue, JsError> {
+    to_js(summary_of(values))
+}
+
+/// `histogram(values, bins?)` → `{ counts, edges }`
+#[wasm_bindgen]
+pub fn histogram(values: &[f64], bins: Option<u32>) -> Result<JsValue, JsError> {
+    to_js(histogram_of(values, bins))
+}
+
+/// `correlation(x, y)` → `PairOut`
+#[wasm_bindgen]
+pub fn correlation(x: &[f64], y: &[f64]) -> Result<JsValue, JsError> {
+    to_js(correlation_of(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_http_shapes() {
+        let s = summary_of(&[1.0, f64::NAN, 2.0, 3.0, 4.0]).unwrap();
+        assert_eq!(s.count, 4);
+        assert_eq!((s.mean, s.median), (Some(2.5), Some(2.5)));
+        assert_eq!(s.missing.unwrap().count, 1);
+        assert_eq!(summary_of(&[]).unwrap().mean, None);
+
+        let h = histogram_of(&[0.0, 1.0, 2.0, 3.0], Some(1)).unwrap();
+        assert_eq!((h.counts.len(), h.edges.len()), (2, 3));
+
+        let p = correlation_of(&[1.0, 2.0, 3.0, f64::NAN], &[2.0, 4.0, 7.0, 1.0]).unwrap();
+        assert_eq!((p.spearman, p.kendall), (Some(1.0), Some(1.0)));
+        assert!(
+            correlation_of(&[1.0], &[1.0, 2.0])
+                .unwrap()
+                .pearson
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! Job-completion webhooks (feature `webhooks`).
+//!
+//! A job submitted with a `Webhook-Url` header is watched until it succeeds,
+//! fails or is cancelled, and its final [`JobEventOut`] is then POSTed to the
+//! URL. Each delivery is signed with `STATS_WEBHOOK_SECRET`:
+//!
+//! | Header              | Value                                              |
+//! |---------------------|----------------------------------------------------|
+//! | `Webhook-Id`        | Job id                                             |
+//! | `Webhook-Timestamp` | Delivery time, seconds since the Unix epoch        |
+//! | `Webhook-Signature` | `sha256=` + hex HMAC-SHA256 of `"{timestamp}.{body}"` |
+//!
+//! Only allowlisted hosts are called, and unless
+//! `STATS_WEBHOOK_ALLOW_PRIVATE` is set only at public addresses: the host is
+//! resolved before each delivery, loopback, private, link-local (cloud
+//! metadata) and similar addresses are refused, and the connection goes to
+//! the addresses that were checked.
+//!
+//! Redirects are not followed. Non-2xx answers and transport errors are
+//! retried up to `STATS_WEBHOOK_ATTEMPTS` times, backing off 1 s, 2 s, 4 s, ….
+
+use crate::{
+    config::WebhookConfig,
+    error::ServiceError,
+    jobs::{Job, now_secs},
+    types::{JobEventOut, JobStatus},
+};
+use axum::http::{HeaderMap, HeaderName};
+use hmac::{Hmac, Mac};
+use reqwest::{Url, header::CONTENT_TYPE, redirect};
+use sha2::Sha256;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Request header naming the callback of a job submission.
+pub const WEBHOOK_URL: HeaderName = HeaderName::from_static("webhook-url");
+
+/// Longest backoff between delivery attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The `Webhook-Url` header, if sent, checked against the scheme rules and
+/// `STATS_WEBHOOK_ALLOWLIST`; an IP-literal host must also be public.
+pub fn requested(headers: &HeaderMap, cfg: &WebhookConfig) -> Result<Option<Url>, ServiceError> {
+    let Some(v) = headers.get(WEBHOOK_URL) else {
+        return Ok(None);
+    };
+    if !cfg.enabled() {
+        return Err(ServiceError::InvalidInput(
+            "Webhook-Url needs STATS_WEBHOOK_SECRET to be configured".into(),
+        ));
+    }
+    let raw = v
+        .to_str()
+        .map_err(|_| ServiceError::InvalidInput("Webhook-Url must be ASCII".into()))?;
+    let url = Url::parse(raw.trim())
+        .map_err(|e| ServiceError::InvalidInput(format!("Webhook-Url: {e}")))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ServiceError::InvalidInput(
+            "Webhook-Url must use http or https".into(),
+        ));
+    }
+    let host = match url.host_str() {
+        Some(h) if cfg.url_allowed(url.as_str(), h) => h,
+        h => {
+            return Err(ServiceError::Forbidden(format!(
+                "{} is not in STATS_WEBHOOK_ALLOWLIST",
+                h.unwrap_or_default()
+            )));
+        }
+    };
+    // Names are only resolved at delivery; literals can be refused now
+    if let Ok(ip) = bare(host).parse::<IpAddr>()
+        && !cfg.allow_private
+        && !public_ip(ip)
+    {
+        return Err(ServiceError::Forbidden(format!(
+            "Webhook-Url may not target {ip}"
+        )));
+    }
+    Ok(Some(url))
+}
+
+/// Whether `ip` may be called back without `STATS_WEBHOOK_ALLOW_PRIVATE`:
+/// not loopback, private, link-local (`169.254.169.254` serves cloud
+/// metadata), shared (CGNAT), unspecified, broadcast or multicast.
+pub fn public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+                || (a, b, c) == (192, 0, 0))
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => public_ip(v4.into()),
+            None => {
+                !(v6.is_loopback()
+                    || v6.is_unspecified()
+                    || v6.is_multicast()
+                    || v6.is_unique_local()
+                    || v6.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// `host` without the brackets of an IPv6 literal.
+fn bare(host: &str) -> &str {
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+/// Resolve the host of `url` and check every address it names; the client
+/// then connects only to these, so a later DNS answer cannot swap in an
+/// internal address.
+async fn target(url: &Url, allow_private: bool) -> Result<Vec<SocketAddr>, String> {
+    let host = bare(url.host_str().ok_or("URL has no host")?);
+    let port = url.port_or_known_default().ok_or("URL has no port")?;
+    let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| format!("{host}: {e}"))?
+            .collect(),
+    };
+    if addrs.is_empty() {
+        return Err(format!("{host} has no addresses"));
+    }
+    match addrs.iter().find(|a| !allow_private && !public_ip(a.ip())) {
+        Some(a) => Err(format!("{host} resolves to non-public {}", a.ip())),
+        None => Ok(addrs),
+    }
+}
+
+/// Hex HMAC-SHA256 of `msg` under `secret`.
+pub fn hmac_hex(secret: &[u8], msg: &[u8]) -> String {
+    // HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("any key length");
+    mac.update(msg);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Value of `Webhook-Signature` for `body` sent at `timestamp`.
+pub fn signature(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let mut msg = format!("{timestamp}.").into_bytes();
+    msg.extend_from_slice(body);
+    format!("sha256={}", hmac_hex(secret.as_bytes(), &msg))
+}
+
+/// Deliver the final state of `job` to `url` once it finishes, in the
+/// background.
+pub fn watch(job: Arc<Job>, url: Url, cfg: WebhookConfig) {
+    tokio::spawn(async move {
+        let out = job.finished().await;
+        let event = JobEventOut {
+            result: (out.status == JobStatus::Succeeded)
+                .then(|| job.result())
+                .flatten(),
+            job: out,
+        };
+        if let Err(e) = deliver(&event, &url, &cfg).await {
+            tracing::warn!(job = %event.job.id, url = %url, "webhook not delivered: {e}");
+        }
+    });
+}
+
+async fn deliver(event: &JobEventOut, url: &Url, cfg: &WebhookConfig) -> Result<(), String> {
+    let secret = cfg.secret.as_deref().ok_or("no webhook secret")?;
+    let body = serde_json::to_vec(event).map_err(|e| e.to_string())?;
+    let addrs = target(url, cfg.allow_private).await?;
+    let client = reqwest::Client::builder()
+        .timeout(cfg.timeout())
+        .redirect(redirect::Policy::none())
+        .resolve_to_addrs(bare(url.host_str().unwrap_or_default()), &addrs)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut backoff = Duration::from_secs(1);
+    let mut last = String::new();
+    for attempt in 1..=cfg.attempts.max(1) {
+        if attempt > 1 {
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+        let ts = now_secs();
+        let sent = client
+            .post(url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header("webhook-id", &event.job.id)
+            .header("webhook-timestamp", ts.to_string())
+            .header("webhook-signature", signature(secret, ts, &body))
+            .body(body.clone())
+            .send()
+            .await;
+        match sent {
+            Ok(r) if r.status().is_success() => return Ok(()),
+            Ok(r) => last = format!("attempt {attempt} answered {}", r.status()),
+            Err(e) => last = format!("attempt {attempt}: {e}"),
+        }
+    }
+    Err(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        assert_eq!(
+            hmac_hex(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn webhook_url_is_checked() {
+        let mut cfg = WebhookConfig::default();
+        let mut headers = HeaderMap::new();
+        assert!(requested(&headers, &cfg).unwrap().is_none());
+
+        headers.insert(
+            WEBHOOK_URL,
+            HeaderValue::from_static("https://hooks.example.com/x"),
+        );
+        assert!(requested(&headers, &cfg).is_err(), "no secret configured");
+
+        cfg.secret = Some("s".into());
+        cfg.allowlist = vec!["hooks.example.com".into()];
+        assert_eq!(
+            requested(&headers, &cfg).unwrap().unwrap().as_str(),
+            "https://hooks.example.com/x"
+        );
+        headers.insert(
+            WEBHOOK_URL,
+            HeaderValue::from_static("https://evil.example.org/"),
+        );
+        assert!(matches!(
+            requested(&headers, &cfg),
+            Err(ServiceError::Forbidden(_))
+        ));
+        headers.insert(
+            WEBHOOK_URL,
+            HeaderValue::from_static("ftp://hooks.example.com/"),
+        );
+        assert!(matches!(
+            requested(&headers, &cfg),
+            Err(ServiceError::InvalidInput(_))
+        ));
+
+        // Allowlisted or not, internal addresses are refused unless enabled
+        cfg.allowlist = vec!["127.0.0.1".into(), "169.254.169.254".into(), "[::1]".into()];
+        for target in [
+            "http://127.0.0.1:9000/x",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::1]/x",
+        ] {
+            headers.insert(WEBHOOK_URL, HeaderValue::from_str(target).unwrap());
+            assert!(
+                matches!(requested(&headers, &cfg), Err(ServiceError::Forbidden(_))),
+                "{target}"
+            );
+        }
+        cfg.allow_private = true;
+        assert!(requested(&headers, &cfg).unwrap().is_some());
+        cfg.allowlist.clear();
+        assert!(matches!(
+            requested(&headers, &cfg),
+            Err(ServiceError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn only_public_addresses_are_called_back() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.100.100.200",
+            "0.0.0.0",
+            "::1",
+            "fe80::1",
+            "fd00:ec2::254",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!public_ip(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in ["93.184.216.34", "2606:2800:220:1::1"] {
+            assert!(public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn names_resolving_to_internal_addresses_are_refused() {
+        let url = Url::parse("http://localhost:9/hook").unwrap();
+        assert!(target(&url, false).await.is_err());
+        assert!(!target(&url, true).await.unwrap().is_empty());
+    }
+}
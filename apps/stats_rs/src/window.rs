@@ -0,0 +1,132 @@
+//! # Output windows for long arrays
+//!
+//! Endpoints that return one point per input value (`/stats/ecdf`,
+//! `/stats/qq-normal`, `/stats/normalize`) take an optional [`WindowIn`] to
+//! keep responses small:
+//!
+//! 1. `offset`/`limit` select a page of the full output, then
+//! 2. `max_points` thins that page, evenly spaced (`uniform`) or with LTTB
+//!    ([`lttb_indices`]), which keeps the peaks and bends a chart needs.
+//!
+//! Paging first lets a client zoom: fetch a downsampled overview, then a
+//! finer window of the part it cares about. The response echoes a
+//! [`WindowOut`] saying which part it holds.
+
+use crate::{
+    error::ServiceError,
+    stats::prelude::*,
+    types::{Downsample, WindowIn, WindowOut},
+    validate::invalid,
+};
+
+impl WindowIn {
+    /// Whether the request asked for nothing but the full output.
+    pub fn is_unset(&self) -> bool {
+        *self == WindowIn::default()
+    }
+
+    /// Constraint checks; `field` is the window's JSON pointer.
+    pub fn validate(&self, field: &str) -> Result<(), ServiceError> {
+        if let Some(0) = self.limit {
+            return Err(invalid(format!("{field}/limit"), "must be at least 1"));
+        }
+        match self.max_points {
+            Some(m) if m < 2 => Err(invalid(
+                format!("{field}/max_points"),
+                format!("must be at least 2, got {m}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Positions of the points to return out of the curve `(xs, ys)`, or `None`
+/// when `window` is unset and everything is returned.
+pub fn select(window: &WindowIn, xs: &[f64], ys: &[f64]) -> Option<(Vec<usize>, WindowOut)> {
+    if window.is_unset() {
+        return None;
+    }
+    let total = xs.len().min(ys.len());
+    let offset = window.offset.unwrap_or(0).min(total);
+    let end = window
+        .limit
+        .map_or(total, |l| offset.saturating_add(l).min(total));
+    let (px, py) = (&xs[offset..end], &ys[offset..end]);
+
+    let method = window.downsample.unwrap_or_default();
+    let (picked, downsample) = match window.max_points {
+        Some(m) if m < px.len() => {
+            let idx = match method {
+                Downsample::Uniform => uniform_indices(px.len(), m),
+                Downsample::Lttb => lttb_indices(px, py, m),
+            };
+            (idx, Some(method))
+        }
+        _ => ((0..px.len()).collect(), None),
+    };
+    let out = WindowOut {
+        total,
+        offset,
+        selected: px.len(),
+        returned: picked.len(),
+        downsample,
+    };
+    Some((picked.into_iter().map(|i| i + offset).collect(), out))
+}
+
+/// The entries of `xs` at `idx`.
+pub fn pick<T: Copy>(xs: &[T], idx: &[usize]) -> Vec<T> {
+    idx.iter().map(|&i| xs[i]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_then_downsamples() {
+        let xs: Vec<f64> = (0..20).map(f64::from).collect();
+        assert!(select(&WindowIn::default(), &xs, &xs).is_none());
+
+        let page = WindowIn {
+            offset: Some(5),
+            limit: Some(10),
+            ..Default::default()
+        };
+        let (idx, out) = select(&page, &xs, &xs).unwrap();
+        assert_eq!(idx, (5..15).collect::<Vec<_>>());
+        assert_eq!(
+            (out.total, out.offset, out.selected, out.returned),
+            (20, 5, 10, 10)
+        );
+        assert_eq!(out.downsample, None);
+
+        let thin = WindowIn {
+            max_points: Some(4),
+            ..page
+        };
+        let (idx, out) = select(&thin, &xs, &xs).unwrap();
+        assert_eq!(idx, [5, 8, 11, 14]);
+        assert_eq!(out.downsample, Some(Downsample::Uniform));
+
+        let past_end = WindowIn {
+            offset: Some(50),
+            ..Default::default()
+        };
+        let (idx, out) = select(&past_end, &xs, &xs).unwrap();
+        assert!(idx.is_empty());
+        assert_eq!((out.offset, out.returned), (20, 0));
+    }
+
+    #[test]
+    fn validates_limit_and_max_points() {
+        let w = |limit, max_points| WindowIn {
+            limit,
+            max_points,
+            ..Default::default()
+        };
+        assert!(w(Some(1), Some(2)).validate("/window").is_ok());
+        assert!(w(Some(0), None).validate("/window").is_err());
+        assert!(w(None, Some(1)).validate("/window").is_err());
+    }
+}
@@ -27,7 +27,24 @@ struct SummaryOut {
 }
 
 fn make_app() -> axum::Router {
-    build_app(Arc::new(AppState))
+    build_app(Arc::new(AppState::default()))
+}
+
+/// POST a JSON `body` to `uri` on a fresh app; the status and the parsed
+/// response body.
+async fn post_json(uri: &str, body: serde_json::Value) -> (StatusCode, serde_json::Value) {
+    let res = make_app()
+        .oneshot(
+            Request::post(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
 }
 
 #[tokio::test]
@@ -44,6 +61,23 @@ async fn health_ok() {
     assert_eq!(body, "ok");
 }
 
+#[tokio::test]
+async fn ready_fails_while_draining() {
+    let state = Arc::new(AppState::default());
+    let app = build_app(state.clone());
+    let ready = || Request::get("/api/v1/ready").body(Body::empty()).unwrap();
+
+    let res = app.clone().oneshot(ready()).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    state
+        .draining
+        .store(true, std::sync::atomic::Ordering::Relaxed);
+    let res = app.oneshot(ready()).await.unwrap();
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, "draining");
+}
+
 #[tokio::test]
 async fn describe_json_ok() {
     let app = make_app().into_service(); // <-- only change
@@ -85,6 +119,65 @@ async fn describe_json_empty_is_400() {
     assert_eq!(res.status(), StatusCode::BAD_REQUEST);
 }
 
+#[tokio::test]
+async fn describe_json_missing_policies() {
+    let values = serde_json::json!([1, null, 3, null]);
+
+    let (status, out) = post_json("/api/v1/describe", values.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["count"], 2);
+    assert_eq!(out["mean"], 2.0);
+    assert_eq!(
+        out["missing"],
+        serde_json::json!({"policy": "drop", "count": 2})
+    );
+
+    let (_, out) = post_json("/api/v1/describe?missing=impute_zero", values.clone()).await;
+    assert_eq!(out["count"], 4);
+    assert_eq!(out["mean"], 1.0);
+
+    let (status, _) = post_json("/api/v1/describe?missing=error", values).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn describe_json_robust_fields_are_opt_in() {
+    let (_, out) = post_json(
+        "/api/v1/describe",
+        serde_json::json!([1, 2, 2, 3, 4, 5, 6, 7, 8, 100]),
+    )
+    .await;
+    assert!(out.get("quartiles").is_none() && out.get("modes").is_none());
+
+    let (status, out) = post_json(
+        "/api/v1/describe?robust=true",
+        serde_json::json!([1, 2, 2, 3, 4, 5, 6, 7, 8, 100]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["min"], 1.0);
+    assert_eq!(out["max"], 100.0);
+    assert_eq!(out["quartiles"], serde_json::json!([2.25, 4.5, 6.75]));
+    assert_eq!(out["modes"], serde_json::json!([2.0]));
+    assert_eq!(out["mad"], 2.5);
+    // 10% off each tail drops 1 and 100
+    assert_eq!(out["trimmed_mean"], 37.0 / 8.0);
+
+    let (_, out) = post_json(
+        "/api/v1/describe?robust=true&trim=0",
+        serde_json::json!([1, 2, 2, 3, 4, 5, 6, 7, 8, 100]),
+    )
+    .await;
+    assert_eq!(out["trimmed_mean"], out["mean"]);
+    let (status, out) = post_json(
+        "/api/v1/describe?robust=true&trim=0.5",
+        serde_json::json!([1, 2, 2, 3, 4, 5, 6, 7, 8, 100]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(out["details"]["field"], "/trim");
+}
+
 #[tokio::test]
 async fn describe_csv_ok_with_header() {
     let app = make_app();
@@ -153,286 +246,764 @@ async fn describe_csv_no_numeric_400() {
     assert_eq!(res.status(), StatusCode::BAD_REQUEST);
 }
 
+#[derive(Deserialize)]
+struct ColumnSchemaOut {
+    name: String,
+    dtype: String,
+    missing: usize,
+}
+
+#[derive(Deserialize)]
+struct DescribeCsvOut {
+    count: usize,
+    mean: f64,
+    schema: Vec<ColumnSchemaOut>,
+}
+
 #[tokio::test]
-async fn openapi_json_exists() {
+async fn describe_csv_selects_columns_and_echoes_schema() {
     let app = make_app();
+    let csv = "generated by tool
+id,price,when
+1,10,2024-01-01
+2,,2024-01-02
+3,20,2024-01-03
+";
 
     let res = app
-        .oneshot(Request::get("/openapi.json").body(Body::empty()).unwrap())
+        .oneshot(
+            Request::post("/api/v1/describe-csv?columns=price,when&skip_rows=1")
+                .header("content-type", "text/csv")
+                .body(Body::from(csv))
+                .unwrap(),
+        )
         .await
         .unwrap();
 
     assert_eq!(res.status(), StatusCode::OK);
     let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
-    let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
-    assert_eq!(v["openapi"], "3.0.3");
+    let out: DescribeCsvOut = serde_json::from_slice(&body).unwrap();
+
+    // only `price` contributes numbers; `id` is not selected
+    assert_eq!(out.count, 2);
+    assert!((out.mean - 15.0).abs() < 1e-12);
+    assert_eq!(out.schema.len(), 2);
+    assert_eq!(
+        (out.schema[0].name.as_str(), out.schema[0].dtype.as_str()),
+        ("price", "integer")
+    );
+    assert_eq!(out.schema[0].missing, 1);
+    assert_eq!(out.schema[1].dtype, "date");
 }
 
 #[tokio::test]
-async fn stats_summary_basic() {
-    let app = make_app().into_service();
+async fn describe_csv_unknown_column_400() {
+    let app = make_app();
 
     let res = app
         .oneshot(
-            Request::post("/api/v1/stats/summary")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    serde_json::to_vec(&serde_json::json!({
-                        "values": [1,2,3,4,5]
-                    }))
-                    .unwrap(),
-                ))
+            Request::post("/api/v1/describe-csv?columns=nope")
+                .header("content-type", "text/csv")
+                .body(Body::from("a\n1\n"))
                 .unwrap(),
         )
         .await
         .unwrap();
 
-    assert_eq!(res.status(), StatusCode::OK);
-    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
-    let out: SummaryOut = serde_json::from_slice(&buf).unwrap();
-
-    assert_eq!(out.count, 5);
-    assert!((out.mean.unwrap() - 3.0).abs() < 1e-12);
-    assert!((out.median.unwrap() - 3.0).abs() < 1e-12);
-    assert!(out.std.unwrap() > 0.0);
-    assert_eq!(out.min.unwrap(), 1.0);
-    assert_eq!(out.max.unwrap(), 5.0);
-}
-
-// ========== distribution ==========
-#[derive(Deserialize)]
-struct DistOut {
-    counts: Vec<usize>,
-    edges: Vec<f64>,
-    quantiles: Vec<(f64, f64)>,
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
-async fn stats_distribution_basic() {
-    let app = make_app().into_service();
-
-    let res = app
+async fn describe_csv_semicolon_decimal_comma() {
+    let res = make_app()
         .oneshot(
-            Request::post("/api/v1/stats/distribution")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    serde_json::to_vec(&serde_json::json!({
-                        "values": [1,2,3,4,5],
-                        "bins": 4,
-                        "quantiles": [0.25, 0.5, 0.75]
-                    }))
-                    .unwrap(),
-                ))
+            Request::post("/api/v1/describe-csv?delimiter=;&decimal=,")
+                .header("content-type", "text/csv")
+                .body(Body::from("wert;anzahl\n3,5;1\n1.000,5;2\n"))
                 .unwrap(),
         )
         .await
         .unwrap();
 
     assert_eq!(res.status(), StatusCode::OK);
-    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
-    let out: DistOut = serde_json::from_slice(&buf).unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: DescribeOut = serde_json::from_slice(&body).unwrap();
+    assert_eq!(out.count, 4);
+    assert!((out.mean - 251.75).abs() < 1e-12);
+}
 
-    assert_eq!(out.edges.len(), out.counts.len() + 1);
-    assert_eq!(out.quantiles.len(), 3);
+// ========== schema/infer ==========
+#[derive(Deserialize)]
+struct InferredColumnOut {
+    name: String,
+    dtype: String,
+    null_rate: f64,
+    examples: Vec<String>,
+    min: Option<f64>,
+    max: Option<f64>,
 }
 
-// ========== pairwise ==========
 #[derive(Deserialize)]
-struct PairOut {
-    pearson: Option<f64>,
-    spearman: Option<f64>,
+struct SchemaInferOut {
+    rows_sampled: usize,
+    truncated: bool,
+    columns: Vec<InferredColumnOut>,
 }
 
 #[tokio::test]
-async fn stats_pairwise_same_series_is_one() {
-    let app = make_app().into_service();
-    let x = [1.0, 2.0, 3.0, 4.0];
-
-    let res = app
+async fn schema_infer_samples_rows() {
+    let csv = "city,temp,when\nOslo,3.5,2024-01-01\nRome,,2024-01-02\nOslo,12,2024-01-03\nLima,20,2024-01-04\n";
+    let res = make_app()
         .oneshot(
-            Request::post("/api/v1/stats/pairwise")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    serde_json::to_vec(&serde_json::json!({
-                        "x": x, "y": x
-                    }))
-                    .unwrap(),
-                ))
+            Request::post("/api/v1/schema/infer?sample_rows=3&examples=2")
+                .header("content-type", "text/csv")
+                .body(Body::from(csv))
                 .unwrap(),
         )
         .await
         .unwrap();
 
     assert_eq!(res.status(), StatusCode::OK);
-    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
-    let out: PairOut = serde_json::from_slice(&buf).unwrap();
-
-    assert!((out.pearson.unwrap() - 1.0).abs() < 1e-12);
-    assert!((out.spearman.unwrap() - 1.0).abs() < 1e-12);
-}
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: SchemaInferOut = serde_json::from_slice(&body).unwrap();
+    assert_eq!(out.rows_sampled, 3);
+    assert!(out.truncated);
 
-// ========== ecdf ==========
-#[derive(Deserialize)]
-struct EcdfOut {
-    xs: Vec<f64>,
-    ps: Vec<f64>,
+    let [city, temp, when] = &out.columns[..] else {
+        panic!("expected three columns");
+    };
+    assert_eq!(city.name, "city");
+    assert_eq!(city.dtype, "string");
+    assert_eq!(city.examples, ["Oslo", "Rome"]);
+    assert_eq!((city.min, city.max), (None, None));
+    assert_eq!(temp.dtype, "float");
+    assert!((temp.null_rate - 1.0 / 3.0).abs() < 1e-12);
+    assert_eq!((temp.min, temp.max), (Some(3.5), Some(12.0)));
+    assert_eq!(when.dtype, "date");
 }
 
+// ========== profile ==========
 #[tokio::test]
-async fn stats_ecdf_monotone_and_last_is_one() {
-    let app = make_app().into_service();
-
-    let res = app
+async fn profile_reports_columns_correlations_and_warnings() {
+    let mut csv = String::from("x,y,flag,note\n");
+    for i in 0..20 {
+        let x = if i == 19 { 1000 } else { i };
+        csv.push_str(&format!(
+            "{x},{},yes,{}\n",
+            2 * x,
+            if i % 2 == 0 { "a" } else { "" }
+        ));
+    }
+    let res = make_app()
         .oneshot(
-            Request::post("/api/v1/stats/ecdf")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    serde_json::to_vec(&serde_json::json!({
-                        "values": [3,1,2,2,4],
-                        "max_points": 100
-                    }))
-                    .unwrap(),
-                ))
+            Request::post("/api/v1/profile?bins=4&top=2")
+                .header("content-type", "text/csv")
+                .body(Body::from(csv))
                 .unwrap(),
         )
         .await
         .unwrap();
 
     assert_eq!(res.status(), StatusCode::OK);
-    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
-    let out: EcdfOut = serde_json::from_slice(&buf).unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(out["n_rows"], 20);
+    assert_eq!(out["n_columns"], 4);
+    assert!((out["missing_rate"].as_f64().unwrap() - 10.0 / 80.0).abs() < 1e-12);
 
-    assert_eq!(out.xs.len(), out.ps.len());
-    assert!((out.ps.last().copied().unwrap_or(0.0) - 1.0).abs() < 1e-12);
-    assert!(out.ps.windows(2).all(|w| w[0] <= w[1]));
-}
+    let x = &out["columns"][0];
+    assert_eq!(x["dtype"], "integer");
+    assert_eq!(x["numeric"]["counts"].as_array().unwrap().len(), 4);
+    assert_eq!(x["numeric"]["max"], 1000.0);
+    assert_eq!(out["columns"][2]["top"][0]["count"], 20);
+    assert_eq!(out["columns"][3]["null_rate"], 0.5);
+    assert!(out["columns"][2].get("numeric").is_none());
 
-// ========== qq-normal ==========
-#[derive(Deserialize)]
-struct QqOut {
-    sample_quantiles: Vec<f64>,
-    theoretical_quantiles: Vec<f64>,
-    sigma_hat: f64,
+    assert_eq!(out["correlations"]["names"], serde_json::json!(["x", "y"]));
+    assert!((out["correlations"]["matrix"][1].as_f64().unwrap() - 1.0).abs() < 1e-12);
+
+    let kinds: Vec<(&str, &str)> = out["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|w| (w["column"].as_str().unwrap(), w["kind"].as_str().unwrap()))
+        .collect();
+    assert!(kinds.contains(&("x", "high_skew")));
+    assert!(kinds.contains(&("flag", "constant")));
+    assert!(kinds.contains(&("note", "constant")));
 }
 
+// ========== stats/resample ==========
 #[tokio::test]
-async fn stats_qq_shapes_match() {
-    let app = make_app().into_service();
-
-    let res = app
+async fn resample_daily_mean_fills_gaps() {
+    let csv = "when,temp,site\n\
+               2024-03-04T08:00:00Z,10,a\n\
+               2024-03-04 20:00,20,b\n\
+               03/06/2024 09:30 AM,5,a\n\
+               not a date,99,c\n\
+               2024-03-06T23:30:00-01:00,7,b\n";
+    let res = make_app()
         .oneshot(
-            Request::post("/api/v1/stats/qq-normal")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    serde_json::to_vec(&serde_json::json!({
-                        "values": [1.0, 2.0, 2.1, 2.9, 3.5],
-                        "robust": false
-                    }))
-                    .unwrap(),
-                ))
+            Request::post("/api/v1/stats/resample?time=when&freq=day")
+                .header("content-type", "text/csv")
+                .body(Body::from(csv))
                 .unwrap(),
         )
         .await
         .unwrap();
 
     assert_eq!(res.status(), StatusCode::OK);
-    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
-    let out: QqOut = serde_json::from_slice(&buf).unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(out["columns"], serde_json::json!(["temp"]));
+    assert_eq!(out["skipped_rows"], 1);
+    assert_eq!(
+        out["buckets"],
+        serde_json::json!([
+            {"start": "2024-03-04T00:00:00", "rows": 2, "values": [15.0]},
+            {"start": "2024-03-05T00:00:00", "rows": 0, "values": [null]},
+            {"start": "2024-03-06T00:00:00", "rows": 1, "values": [5.0]},
+            {"start": "2024-03-07T00:00:00", "rows": 1, "values": [7.0]},
+        ])
+    );
 
-    assert_eq!(out.sample_quantiles.len(), out.theoretical_quantiles.len());
-    assert!(out.sigma_hat.is_finite());
+    let res = make_app()
+        .oneshot(
+            Request::post("/api/v1/stats/resample?time=missing")
+                .header("content-type", "text/csv")
+                .body(Body::from(csv))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
 }
 
-// ========== corr-matrix ==========
+// ========== xlsx ==========
+#[cfg(feature = "xlsx")]
+fn sample_workbook() -> Vec<u8> {
+    use rust_xlsxwriter::Workbook;
+
+    let mut wb = Workbook::new();
+    wb.add_worksheet().set_name("notes").unwrap();
+    let ws = wb.add_worksheet().set_name("data").unwrap();
+    ws.write_row(0, 0, ["region", "sales"]).unwrap();
+    for (i, (r, s)) in [("north", 10.0), ("south", 20.0), ("east", 60.0)]
+        .iter()
+        .enumerate()
+    {
+        ws.write_string(i as u32 + 1, 0, *r).unwrap();
+        ws.write_number(i as u32 + 1, 1, *s).unwrap();
+    }
+    wb.save_to_buffer().unwrap()
+}
+
+#[cfg(feature = "xlsx")]
 #[derive(Deserialize)]
-struct CorrMatrixOut {
-    size: usize,
-    matrix: Vec<f64>,
+struct XlsxSummaryOut {
+    count: usize,
+    median: Option<f64>,
+    schema: Vec<ColumnSchemaOut>,
 }
 
+#[cfg(feature = "xlsx")]
 #[tokio::test]
-async fn stats_corr_matrix_square_and_diag_one() {
-    let app = make_app().into_service();
+async fn summary_xlsx_selects_sheet() {
+    let app = make_app();
 
     let res = app
         .oneshot(
-            Request::post("/api/v1/stats/corr-matrix")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    serde_json::to_vec(&serde_json::json!({
-                        "series": [[1,2,3,4], [1,2,3,4]],
-                        "names": ["a","b"],
-                        "method": "pearson"
-                    }))
-                    .unwrap(),
-                ))
+            Request::post("/api/v1/stats/summary-xlsx?sheet=data")
+                .body(Body::from(sample_workbook()))
                 .unwrap(),
         )
         .await
         .unwrap();
 
     assert_eq!(res.status(), StatusCode::OK);
-    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
-    let out: CorrMatrixOut = serde_json::from_slice(&buf).unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: XlsxSummaryOut = serde_json::from_slice(&body).unwrap();
 
-    assert_eq!(out.size, 2);
-    assert_eq!(out.matrix.len(), 4);
-    assert!((out.matrix[0] - 1.0).abs() < 1e-12);
-    assert!((out.matrix[3] - 1.0).abs() < 1e-12);
+    assert_eq!(out.count, 3);
+    assert_eq!(out.median, Some(20.0));
+    assert_eq!(out.schema[0].name, "region");
+    assert_eq!(out.schema[1].dtype, "integer");
 }
 
-// ========== outliers ==========
-#[derive(Deserialize)]
-struct OutliersOut {
-    values: Vec<f64>,
+#[cfg(feature = "xlsx")]
+#[tokio::test]
+async fn describe_xlsx_unknown_sheet_and_garbage_400() {
+    for (uri, body) in [
+        ("/api/v1/describe-xlsx?sheet=missing", sample_workbook()),
+        ("/api/v1/describe-xlsx", b"not a zip".to_vec()),
+    ] {
+        let res = make_app()
+            .oneshot(Request::post(uri).body(Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{uri}");
+    }
 }
 
+// ========== compressed uploads ==========
 #[tokio::test]
-async fn stats_outliers_iqr_finds_extreme() {
-    let app = make_app().into_service();
+async fn describe_csv_accepts_gzip_body() {
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::Write;
 
-    let res = app
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    enc.write_all(b"value\n1\n2\n3\n4\n5\n").unwrap();
+    let gz = enc.finish().unwrap();
+
+    let res = make_app()
         .oneshot(
-            Request::post("/api/v1/stats/outliers")
-                .header("content-type", "application/json")
-                .body(Body::from(
-                    serde_json::to_vec(&serde_json::json!({
-                        "values": [1,2,3,4,100],
-                        "method": "iqr"
-                    }))
-                    .unwrap(),
-                ))
+            Request::post("/api/v1/describe-csv")
+                .header("content-type", "text/csv")
+                .header("content-encoding", "gzip")
+                .body(Body::from(gz))
                 .unwrap(),
         )
         .await
         .unwrap();
 
     assert_eq!(res.status(), StatusCode::OK);
-    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
-    let out: OutliersOut = serde_json::from_slice(&buf).unwrap();
-
-    assert!(out.values.contains(&100.0));
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: DescribeOut = serde_json::from_slice(&body).unwrap();
+    assert_eq!(out.count, 5);
+    assert!((out.mean - 3.0).abs() < 1e-12);
 }
 
-// ========== normalize ==========
-#[derive(Deserialize)]
-struct NormalizeOut {
-    values: Vec<f64>,
+/// `head`, then `n` one-MiB chunks of `line` repeated, without a content-length.
+fn chunked_body(head: &'static str, line: &str, n: usize) -> Body {
+    use axum::body::Bytes;
+    let chunk = Bytes::from(line.repeat(1024 * 1024 / line.len()));
+    let chunks =
+        std::iter::once(Bytes::from_static(head.as_bytes())).chain(std::iter::repeat_n(chunk, n));
+    Body::from_stream(futures_util::stream::iter(
+        chunks.map(Ok::<_, std::io::Error>),
+    ))
 }
 
 #[tokio::test]
-async fn stats_normalize_minmax_range() {
-    let app = make_app().into_service();
-
-    let res = app
+async fn describe_csv_streams_past_buffered_limit() {
+    // 30 MiB of "value" rows: above MAX_BODY_BYTES, parsed without buffering
+    let res = make_app()
         .oneshot(
-            Request::post("/api/v1/stats/normalize")
-                .header("content-type", "application/json")
+            Request::post("/api/v1/describe-csv")
+                .header("content-type", "text/csv")
+                .body(chunked_body("value\n", "2.5\n", 30))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: DescribeOut = serde_json::from_slice(&body).unwrap();
+    assert_eq!(out.count, 30 * 1024 * 1024 / 4);
+    assert!((out.median - 2.5).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn csv_sample_is_seeded_and_echoed() {
+    let csv: String = std::iter::once("id,x\n".to_string())
+        .chain((0..5000).map(|i| format!("{i},{}\n", i % 97)))
+        .collect();
+    let post = |uri: &'static str| {
+        make_app().oneshot(
+            Request::post(uri)
+                .header("content-type", "text/csv")
+                .body(Body::from(csv.clone()))
+                .unwrap(),
+        )
+    };
+    let json = |res: axum::response::Response| async move {
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    let uri = "/api/v1/describe-csv?columns=x&sample=200&seed=5";
+    let a = json(post(uri).await.unwrap()).await;
+    assert_eq!(a["count"], 200);
+    assert_eq!(
+        a["sample"],
+        serde_json::json!({"seed": 5, "rows_seen": 5000, "rows_sampled": 200, "fraction": 0.04})
+    );
+    assert_eq!(json(post(uri).await.unwrap()).await, a);
+
+    let p = json(post("/api/v1/profile?sample=200&seed=5").await.unwrap()).await;
+    assert_eq!(p["n_rows"], 200);
+    assert_eq!(p["sample"]["rows_sampled"], 200);
+    let (pm, am) = (
+        p["columns"][1]["numeric"]["mean"].as_f64(),
+        a["mean"].as_f64(),
+    );
+    assert!((pm.unwrap() - am.unwrap()).abs() < 1e-9); // same rows drawn
+
+    // Without `sample` nothing is echoed
+    let full = json(post("/api/v1/describe-csv?columns=x").await.unwrap()).await;
+    assert_eq!(full["count"], 5000);
+    assert!(full.get("sample").is_none());
+}
+
+#[tokio::test]
+async fn streamed_body_over_wire_limit_413() {
+    let res = make_app()
+        .oneshot(
+            Request::post("/api/v1/ingest/ndjson")
+                .header("content-type", "application/x-ndjson")
+                .body(chunked_body("", "{\"x\":1}\n", 26))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn unsupported_content_encoding_415() {
+    let res = make_app()
+        .oneshot(
+            Request::post("/api/v1/describe-csv")
+                .header("content-type", "text/csv")
+                .header("content-encoding", "lzma")
+                .body(Body::from("value\n1\n"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+// ========== datasets / ingest/url ==========
+#[tokio::test]
+async fn dataset_unknown_id_404() {
+    let res = make_app()
+        .oneshot(
+            Request::get("/api/v1/datasets/ds_42")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "fetch")]
+#[derive(Deserialize)]
+struct DatasetOut {
+    id: String,
+    name: String,
+    rows: usize,
+    columns: Vec<ColumnSchemaOut>,
+}
+
+/// Serve `body` at `/exports/sales.csv` on an ephemeral local port.
+#[cfg(feature = "fetch")]
+async fn serve_file(body: &'static str) -> std::net::SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let files = axum::Router::new().route(
+        "/exports/sales.csv",
+        axum::routing::get(move || async move { body }),
+    );
+    tokio::spawn(async move { axum::serve(listener, files).await.unwrap() });
+    addr
+}
+
+#[cfg(feature = "fetch")]
+#[tokio::test]
+async fn ingest_url_registers_dataset() {
+    use stats_rs::config::{IngestConfig, ServiceConfig};
+
+    let addr = serve_file("region,sales\nnorth,10\nsouth,20\n").await;
+    let app = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            ingest: IngestConfig {
+                url_allowlist: vec!["127.0.0.1".into()],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/ingest/url")
+                .header("content-type", "application/json")
                 .body(Body::from(
                     serde_json::to_vec(&serde_json::json!({
-                        "values": [10, 20],
-                        "method": "minmax",
-                        "range": [0.0, 1.0]
+                        "url": format!("http://{addr}/exports/sales.csv"),
+                        "columns": "sales"
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: DatasetOut = serde_json::from_slice(&body).unwrap();
+    assert_eq!(out.name, "sales.csv");
+    assert_eq!(out.rows, 2);
+    assert_eq!(out.columns.len(), 1);
+    assert_eq!(out.columns[0].dtype, "integer");
+
+    let res = app
+        .oneshot(
+            Request::get(format!("/api/v1/datasets/{}", out.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[cfg(feature = "fetch")]
+#[tokio::test]
+async fn ingest_url_outside_allowlist_403() {
+    let res = make_app()
+        .oneshot(
+            Request::post("/api/v1/ingest/url")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(
+                        &serde_json::json!({ "url": "http://169.254.169.254/latest" }),
+                    )
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+/// Minimal path-style S3 endpoint serving one object at `/lake/exports/sales.csv`.
+#[cfg(feature = "s3")]
+async fn serve_s3_object(body: &'static str) -> std::net::SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let object = move || async move {
+        (
+            [
+                ("last-modified", "Wed, 01 Jan 2025 00:00:00 GMT"),
+                ("etag", "\"0123\""),
+                ("content-type", "text/csv"),
+            ],
+            body,
+        )
+    };
+    let store = axum::Router::new().route(
+        "/lake/exports/sales.csv",
+        axum::routing::get(object).head(object),
+    );
+    tokio::spawn(async move { axum::serve(listener, store).await.unwrap() });
+    addr
+}
+
+#[cfg(feature = "s3")]
+#[tokio::test]
+async fn ingest_s3_uri_uses_configured_endpoint() {
+    use stats_rs::config::{IngestConfig, S3Config, ServiceConfig};
+
+    let addr = serve_s3_object("region,sales\nnorth,10\nsouth,20\n").await;
+    let app = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            ingest: IngestConfig {
+                url_allowlist: vec!["s3://lake/exports/".into()],
+                ..Default::default()
+            },
+            s3: S3Config {
+                endpoint: Some(format!("http://{addr}")),
+                access_key_id: Some("test".into()),
+                secret_access_key: Some("test".into()),
+                allow_http: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+
+    let ingest = |url: &str| {
+        Request::post("/api/v1/ingest/url")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::to_vec(&serde_json::json!({ "url": url })).unwrap(),
+            ))
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(ingest("s3://lake/exports/sales.csv"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: DatasetOut = serde_json::from_slice(&body).unwrap();
+    assert_eq!(out.name, "sales.csv");
+    assert_eq!(out.rows, 2);
+
+    let res = app
+        .oneshot(ingest("s3://other-bucket/sales.csv"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+// ========== ingest/ndjson ==========
+#[derive(Deserialize)]
+struct NdjsonFieldOut {
+    name: String,
+    missing: u64,
+    mean: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct NdjsonIngestOut {
+    records: u64,
+    fields: Vec<NdjsonFieldOut>,
+}
+
+#[tokio::test]
+async fn ingest_ndjson_streamed_chunks() {
+    let app = make_app();
+    let chunks: Vec<Result<&'static str, std::io::Error>> = vec![
+        Ok("{\"v\": 1, \"tag\": \"a\"}\n{\"v\""),
+        Ok(": 2}\n\n{\"v\": 6}"),
+    ];
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/ingest/ndjson")
+                .header("content-type", "application/x-ndjson")
+                .body(Body::from_stream(futures_util::stream::iter(chunks)))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: NdjsonIngestOut = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(out.records, 3);
+    assert_eq!(out.fields[0].name, "tag");
+    assert_eq!(out.fields[0].missing, 2);
+    assert!((out.fields[1].mean.unwrap() - 3.0).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn ingest_ndjson_bad_line_400() {
+    let app = make_app();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/ingest/ndjson")
+                .header("content-type", "application/x-ndjson")
+                .body(Body::from("{\"v\": 1}\nnot json\n"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn openapi_json_exists() {
+    let app = make_app();
+
+    let res = app
+        .oneshot(Request::get("/openapi.json").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(v["openapi"], "3.1.0");
+    assert_eq!(v["info"]["title"], "stats_rs");
+    assert!(v["components"]["schemas"]["ErrorResponse"].is_object());
+    assert_eq!(
+        v["paths"]["/api/v1/jobs/{id}"]["get"]["responses"]["404"]["content"]["application/json"]["schema"]
+            ["$ref"],
+        "#/components/schemas/ErrorResponse"
+    );
+
+    // Generated from the handlers: bodies, query parameters and statuses
+    let summary = &v["paths"]["/api/v1/stats/summary"]["post"];
+    assert_eq!(
+        summary["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/SummaryIn"
+    );
+    assert!(summary["responses"]["200"]["content"]["text/csv"].is_object());
+    assert!(summary["responses"]["422"].is_object());
+    let params = v["paths"]["/api/v1/describe-csv"]["post"]["parameters"]
+        .as_array()
+        .unwrap();
+    assert!(
+        params
+            .iter()
+            .any(|p| p["name"] == "columns" && p["in"] == "query")
+    );
+    assert!(v["components"]["schemas"]["DescribeOutput"].is_object());
+}
+
+#[tokio::test]
+async fn openapi_lists_every_mounted_route() {
+    use stats_rs::config::{FeatureToggles, ServiceConfig};
+
+    let doc = |state: AppState| async move {
+        let res = build_app(Arc::new(state))
+            .oneshot(Request::get("/openapi.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+    let v = doc(AppState::default()).await;
+    assert!(v["paths"]["/api/v1/stats/vector/similarity"].is_object());
+    assert!(v["paths"]["/api/v1/datasets/{id}"]["delete"].is_object());
+
+    // Routes switched off at runtime drop out of the document too
+    let v = doc(AppState {
+        config: ServiceConfig {
+            features: FeatureToggles {
+                vector: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .await;
+    assert!(v["paths"]["/api/v1/stats/vector/similarity"].is_null());
+}
+
+#[tokio::test]
+async fn stats_summary_basic() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/summary")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "values": [1,2,3,4,5]
                     }))
                     .unwrap(),
                 ))
@@ -443,30 +1014,76 @@ async fn stats_normalize_minmax_range() {
 
     assert_eq!(res.status(), StatusCode::OK);
     let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
-    let out: NormalizeOut = serde_json::from_slice(&buf).unwrap();
+    let out: SummaryOut = serde_json::from_slice(&buf).unwrap();
 
-    assert_eq!(out.values[0], 0.0);
-    assert_eq!(out.values[1], 1.0);
+    assert_eq!(out.count, 5);
+    assert!((out.mean.unwrap() - 3.0).abs() < 1e-12);
+    assert!((out.median.unwrap() - 3.0).abs() < 1e-12);
+    assert!(out.std.unwrap() > 0.0);
+    assert_eq!(out.min.unwrap(), 1.0);
+    assert_eq!(out.max.unwrap(), 5.0);
 }
 
-// ========== binrule ==========
+#[tokio::test]
+async fn stats_summary_percentiles_and_shape() {
+    let post = |uri: &'static str| async move {
+        let res = make_app()
+            .oneshot(
+                Request::post(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"values":[1,2,3,4,5],"percentiles":[0,10,95,100]}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        to_bytes(res.into_body(), usize::MAX).await.unwrap()
+    };
+
+    let out: serde_json::Value =
+        serde_json::from_slice(&post("/api/v1/stats/summary").await).unwrap();
+    // R-7: p10 = 1 + 0.4, p95 = 4 + 0.8
+    assert_eq!(
+        out["percentiles"],
+        serde_json::json!([[0.0, 1.0], [10.0, 1.4], [95.0, 4.8], [100.0, 5.0]])
+    );
+    let sd = 2.5f64.sqrt();
+    assert!((out["cv"].as_f64().unwrap() - sd / 3.0).abs() < 1e-12);
+    assert!((out["sem"].as_f64().unwrap() - sd / 5f64.sqrt()).abs() < 1e-12);
+    assert!(out["skewness"].as_f64().unwrap().abs() < 1e-12);
+    // G2, as R's e1071::kurtosis(1:5, type = 2)
+    assert!((out["excess_kurtosis"].as_f64().unwrap() + 1.2).abs() < 1e-12);
+
+    let csv = post("/api/v1/stats/summary?fields=sem&format=csv").await;
+    assert_eq!(
+        csv,
+        "stat,value\ncount,5\nsem,0.7071067811865476\np0,1\np10,1.4\np95,4.8\np100,5\n"
+    );
+}
+
+// ========== distribution ==========
 #[derive(Deserialize)]
-struct BinRuleOut {
-    bins: usize,
+struct DistOut {
+    counts: Vec<usize>,
+    edges: Vec<f64>,
+    quantiles: Vec<(f64, f64)>,
 }
 
 #[tokio::test]
-async fn stats_binrule_returns_positive_bins() {
+async fn stats_distribution_basic() {
     let app = make_app().into_service();
 
     let res = app
         .oneshot(
-            Request::post("/api/v1/stats/binrule")
+            Request::post("/api/v1/stats/distribution")
                 .header("content-type", "application/json")
                 .body(Body::from(
                     serde_json::to_vec(&serde_json::json!({
-                        "values": [1,2,3,4,5,6,7,8,9,10],
-                        "rule": "sturges"
+                        "values": [1,2,3,4,5],
+                        "bins": 4,
+                        "quantiles": [0.25, 0.5, 0.75]
                     }))
                     .unwrap(),
                 ))
@@ -477,7 +1094,4452 @@ async fn stats_binrule_returns_positive_bins() {
 
     assert_eq!(res.status(), StatusCode::OK);
     let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
-    let out: BinRuleOut = serde_json::from_slice(&buf).unwrap();
+    let out: DistOut = serde_json::from_slice(&buf).unwrap();
 
-    assert!(out.bins >= 2);
+    assert_eq!(out.edges.len(), out.counts.len() + 1);
+    assert_eq!(out.quantiles.len(), 3);
+}
+
+#[tokio::test]
+async fn stats_distribution_approx_samples_large_inputs() {
+    let n = 100_000;
+    // a permutation of 0..n, so a value's rank is the value itself
+    let values: Vec<usize> = (0..n).map(|i| (i * 7919) % n).collect();
+    let body = |approx| serde_json::json!({ "values": &values, "quantiles": [0.1, 0.5, 0.9], "approx": approx });
+
+    let (status, v) = post_json("/api/v1/stats/distribution", body(true)).await;
+    assert_eq!(status, StatusCode::OK);
+    let approx = &v["approx"];
+    assert_eq!(approx["population"], n);
+    assert!(approx["sample_size"].as_u64().unwrap() < n as u64);
+    let eps = approx["rank_error"].as_f64().unwrap();
+    assert!(eps > 0.0 && eps < 0.02);
+    for q in v["quantiles"].as_array().unwrap() {
+        let (p, x) = (q[0].as_f64().unwrap(), q[1].as_f64().unwrap());
+        assert!((x / n as f64 - p).abs() <= eps, "p={p}: {x}");
+    }
+    // the histogram is still exact
+    let counts: Vec<usize> = serde_json::from_value(v["counts"].clone()).unwrap();
+    assert_eq!(counts.iter().sum::<usize>(), n);
+
+    let (_, v) = post_json("/api/v1/stats/distribution", body(false)).await;
+    assert!(v.get("approx").is_none());
+}
+
+#[tokio::test]
+async fn stats_distribution_density_and_kde_share_bins() {
+    let values: Vec<f64> = (0..200).map(|i| ((i * 37) % 200) as f64 / 10.0).collect();
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({
+            "values": values, "bins": 8, "density": true, "kde": true
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let edges: Vec<f64> = serde_json::from_value(v["edges"].clone()).unwrap();
+    let density: Vec<f64> = serde_json::from_value(v["density"].clone()).unwrap();
+    assert_eq!(density.len(), 8);
+    let area: f64 = density
+        .iter()
+        .zip(edges.windows(2))
+        .map(|(h, e)| h * (e[1] - e[0]))
+        .sum();
+    assert!((area - 1.0).abs() < 1e-9);
+    let kde = &v["kde"];
+    assert!(kde["bandwidth"].as_f64().unwrap() > 0.0);
+    let centers: Vec<f64> = serde_json::from_value(kde["centers"].clone()).unwrap();
+    assert_eq!(centers.len(), 8);
+    assert!((centers[0] - (edges[0] + edges[1]) / 2.0).abs() < 1e-12);
+    // roughly uniform data: the KDE sits near the bar heights mid-range
+    let mid = kde["density"][4].as_f64().unwrap();
+    assert!((mid - density[4]).abs() < 0.2 * density[4]);
+
+    let (_, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({
+            "values": [1, 2, 3], "kde": true, "bandwidth": 0.5
+        }),
+    )
+    .await;
+    assert_eq!(v["kde"]["bandwidth"], 0.5);
+    assert!(v.get("density").is_none());
+
+    let (_, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({ "values": [4, 4, 4], "density": true, "kde": true }),
+    )
+    .await;
+    assert!(v.get("density").is_none() && v.get("kde").is_none());
+
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({ "values": [1, 2], "kde": true, "bandwidth": -1 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(v["details"]["field"], "/bandwidth");
+}
+
+#[tokio::test]
+async fn stats_distribution_custom_and_log_bins() {
+    // latency-like: most requests fast, a long tail of slow ones
+    let latencies = [3, 4, 5, 6, 8, 12, 40, 90, 400, 3000];
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({ "values": latencies, "bins": 3 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v["counts"], serde_json::json!([9, 0, 1]));
+
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({ "values": latencies, "bins": 3, "scale": "log" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v["counts"], serde_json::json!([6, 2, 2]));
+    assert_eq!(v["edges"][0], 3.0);
+    assert_eq!(v["edges"][3], 3000.0);
+
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({
+            "values": latencies, "edges": [0, 10, 100, 1000]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v["counts"], serde_json::json!([5, 3, 1]));
+    assert_eq!(v["edges"], serde_json::json!([0.0, 10.0, 100.0, 1000.0]));
+    assert_eq!(v["outside"], 1);
+
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({ "values": [1, 0, 2], "scale": "log" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(v["details"]["field"], "/values/1");
+
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({ "values": [1, 2], "edges": [0, 5, 3] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(v["details"]["field"], "/edges/2");
+}
+
+#[tokio::test]
+async fn stats_distribution_weights_stand_in_for_repeats() {
+    // pre-aggregated (value, count) pairs, with a null among the values
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({
+            "values": [1, 2, null, 5, 8],
+            "weights": [3, 1, 7, 2, 4],
+            "bins": 4,
+            "quantiles": [0.1, 0.5, 0.9]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let expanded = [1, 1, 1, 2, 5, 5, 8, 8, 8, 8];
+    let (_, full) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({
+            "values": expanded, "bins": 4, "quantiles": [0.1, 0.5, 0.9]
+        }),
+    )
+    .await;
+    assert_eq!(v["counts"], serde_json::json!([2, 0, 1, 1]));
+    assert_eq!(v["weights"], serde_json::json!([4.0, 0.0, 2.0, 4.0]));
+    assert_eq!(v["edges"], full["edges"]);
+    assert_eq!(v["quantiles"], full["quantiles"]);
+    for k in ["skewness", "excess_kurtosis", "entropy_bits"] {
+        let (a, b) = (v[k].as_f64().unwrap(), full[k].as_f64().unwrap());
+        assert!((a - b).abs() < 1e-12, "{k}: {a} vs {b}");
+    }
+    // R: e1071::kurtosis(expanded, type = 2)
+    assert!((v["excess_kurtosis"].as_f64().unwrap() + 2.068_032_552).abs() < 1e-9);
+    assert_eq!(v["missing"]["count"], 1);
+    assert!(full.get("weights").is_none());
+
+    let (_, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({
+            "values": [1, 20, 300], "weights": [0.5, 2, 1.5], "edges": [0, 10, 100]
+        }),
+    )
+    .await;
+    assert_eq!(v["outside"], 1);
+    assert_eq!(v["outside_weight"], 1.5);
+
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({ "values": [1, 2], "weights": [1] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(v["details"]["field"], "/weights");
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({
+            "values": [1, 2], "weights": [1, 1], "approx": true
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(v["details"]["field"], "/approx");
+}
+
+#[tokio::test]
+async fn stats_distribution_reports_shape_standard_errors() {
+    let values: Vec<f64> = (1..=20).map(|i| f64::from(i * i % 17)).collect();
+
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({ "values": values, "shape_se": "analytic" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!((v["skewness_se"].as_f64().unwrap() - 0.5121).abs() < 1e-4);
+    assert!((v["excess_kurtosis_se"].as_f64().unwrap() - 0.9924).abs() < 1e-4);
+
+    // Same total weight, same analytic errors
+    let (_, w) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({
+            "values": [1, 2], "weights": [10, 10], "shape_se": "analytic"
+        }),
+    )
+    .await;
+    assert_eq!(w["skewness_se"], v["skewness_se"]);
+
+    let body = serde_json::json!({
+        "values": values, "shape_se": "bootstrap", "resamples": 400, "seed": 3
+    });
+    let (status, b) = post_json("/api/v1/stats/distribution", body.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, again) = post_json("/api/v1/stats/distribution", body).await;
+    assert_eq!(b, again);
+    for k in ["skewness_se", "excess_kurtosis_se"] {
+        let se = b[k].as_f64().unwrap();
+        assert!(se > 0.0 && se < 2.0, "{k}: {se}");
+    }
+
+    let (_, plain) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({ "values": values }),
+    )
+    .await;
+    assert!(plain.get("skewness_se").is_none());
+
+    let (status, err) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({
+            "values": [1, 2], "weights": [1, 1], "shape_se": "bootstrap"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(err["details"]["field"], "/shape_se");
+}
+
+#[tokio::test]
+async fn quantile_method_selects_the_definition() {
+    // quantile(1:4, 0.25, type = …) in R
+    for (method, want) in [
+        ("r1", 1.0),
+        ("r6", 1.25),
+        ("r7", 1.75),
+        ("nearest_rank", 1.0),
+    ] {
+        let (status, v) = post_json(
+            "/api/v1/stats/distribution",
+            serde_json::json!({
+                "values": [4, 2, 1, 3], "quantiles": [0.25], "quantile_method": method
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v["quantiles"][0][1], want, "{method}");
+    }
+    let (_, v) = post_json(
+        "/api/v1/stats/summary",
+        serde_json::json!({
+            "values": [4, 2, 1, 3], "percentiles": [25], "quantile_method": "r2"
+        }),
+    )
+    .await;
+    assert_eq!(v["percentiles"][0][1], 1.5);
+    // the median is unaffected
+    assert_eq!(v["median"], 2.5);
+
+    let (status, v) = post_json(
+        "/api/v1/stats/distribution",
+        serde_json::json!({
+            "values": [1, 2], "weights": [1, 1], "quantile_method": "r6"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(v["details"]["field"], "/quantile_method");
+}
+
+// ========== pairwise ==========
+#[derive(Deserialize)]
+struct PairOut {
+    pearson: Option<f64>,
+    spearman: Option<f64>,
+}
+
+#[tokio::test]
+async fn stats_pairwise_same_series_is_one() {
+    let app = make_app().into_service();
+    let x = [1.0, 2.0, 3.0, 4.0];
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/pairwise")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "x": x, "y": x
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: PairOut = serde_json::from_slice(&buf).unwrap();
+
+    assert!((out.pearson.unwrap() - 1.0).abs() < 1e-12);
+    assert!((out.spearman.unwrap() - 1.0).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn stats_pairwise_reports_p_values_and_intervals() {
+    let res = make_app()
+        .oneshot(
+            Request::post("/api/v1/stats/pairwise")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({
+                        "x": [1, 2, 3, 4, 5, 6, 7, 8, null],
+                        "y": [2, 1, 4, 3, 7, 5, 8, 6, 9],
+                        "confidence": 0.9
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out["n"], 8);
+    assert_eq!(out["confidence"], 0.9);
+    for name in ["pearson", "spearman", "kendall"] {
+        let r = out[name].as_f64().unwrap();
+        let test = &out[format!("{name}_test")];
+        let (lo, hi) = (
+            test["lower"].as_f64().unwrap(),
+            test["upper"].as_f64().unwrap(),
+        );
+        assert!(lo < r && r < hi && hi < 1.0, "{name}: {lo} {r} {hi}");
+        let p = test["p_value"].as_f64().unwrap();
+        assert!(p > 0.0 && p < 0.05, "{name}: p = {p}");
+    }
+
+    let res = make_app()
+        .oneshot(
+            Request::post("/api/v1/stats/pairwise")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"x":[1,2],"y":[2,1],"confidence":1}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+// ========== ecdf ==========
+#[derive(Deserialize)]
+struct EcdfOut {
+    xs: Vec<f64>,
+    ps: Vec<f64>,
+}
+
+#[tokio::test]
+async fn stats_ecdf_monotone_and_last_is_one() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/ecdf")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "values": [3,1,2,2,4],
+                        "max_points": 100
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: EcdfOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.xs.len(), out.ps.len());
+    assert!((out.ps.last().copied().unwrap_or(0.0) - 1.0).abs() < 1e-12);
+    assert!(out.ps.windows(2).all(|w| w[0] <= w[1]));
+}
+
+// ========== qq-normal ==========
+#[derive(Deserialize)]
+struct QqOut {
+    sample_quantiles: Vec<f64>,
+    theoretical_quantiles: Vec<f64>,
+    sigma_hat: f64,
+}
+
+#[tokio::test]
+async fn stats_qq_shapes_match() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/qq-normal")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "values": [1.0, 2.0, 2.1, 2.9, 3.5],
+                        "robust": false
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: QqOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.sample_quantiles.len(), out.theoretical_quantiles.len());
+    assert!(out.sigma_hat.is_finite());
+}
+
+#[tokio::test]
+async fn stats_qq_reference_line_and_ppcc() {
+    let res = make_app()
+        .oneshot(
+            Request::post("/api/v1/stats/qq-normal")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"values":[3.5,1.0,2.1,2.0,2.9]}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+
+    // the line passes through (μ ± σ·0.6745, Q1/Q3) = (…, 2.0) and (…, 2.9)
+    let f = |k: &str| out[k].as_f64().unwrap();
+    let (slope, intercept) = (f("line_slope"), f("line_intercept"));
+    let half = f("sigma_hat") * 0.674_489_750_196_081_7;
+    assert!((slope * (f("mu_hat") - half) + intercept - 2.0).abs() < 1e-9);
+    assert!((slope * (f("mu_hat") + half) + intercept - 2.9).abs() < 1e-9);
+    assert!((f("ppcc") - 0.985_673_386_960_417_7).abs() < 1e-9);
+    assert!(f("ppcc") > f("ppcc_critical"));
+}
+
+// ========== corr-matrix ==========
+#[derive(Deserialize)]
+struct CorrMatrixOut {
+    size: usize,
+    matrix: Vec<f64>,
+}
+
+#[tokio::test]
+async fn stats_corr_matrix_square_and_diag_one() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/corr-matrix")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "series": [[1,2,3,4], [1,2,3,4]],
+                        "names": ["a","b"],
+                        "method": "pearson"
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: CorrMatrixOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.size, 2);
+    assert_eq!(out.matrix.len(), 4);
+    assert!((out.matrix[0] - 1.0).abs() < 1e-12);
+    assert!((out.matrix[3] - 1.0).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn stats_corr_matrix_pairwise_with_p_values() {
+    let series = serde_json::json!([[1, 2, 3, 4, 5], [1, 3, 2, 5, null], [7, 7, 7, 7, 7]]);
+
+    let (status, out) = post_json(
+        "/api/v1/stats/corr-matrix",
+        serde_json::json!({
+            "series": series, "missing": "pairwise", "p_values": true
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    // a and b share four rows; a keeps its fifth for the pair with c
+    assert_eq!(
+        out["n_used"],
+        serde_json::json!([5, 4, 5, 4, 4, 4, 5, 4, 5])
+    );
+    assert_eq!(out["missing"]["count"], 1);
+    let r = out["matrix"][1].as_f64().unwrap();
+    assert!((r - 5.5 / 43.75f64.sqrt()).abs() < 1e-12);
+    let p = out["p_values"][1].as_f64().unwrap();
+    assert!(p > 0.05 && p < 1.0);
+    assert_eq!(out["p_values"][0], 0.0);
+    // the constant series correlates with nothing, and is not reported as 0
+    assert!(out["matrix"][2].is_null());
+    assert!(out["p_values"][2].is_null());
+
+    // listwise by default, without the extras
+    let (status, out) = post_json(
+        "/api/v1/stats/corr-matrix",
+        serde_json::json!({ "series": series }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["missing"]["count"], 1);
+    assert!(out["matrix"][2].is_null());
+    assert!(out.get("p_values").is_none() && out.get("n_used").is_none());
+}
+
+#[tokio::test]
+async fn stats_corr_matrix_adds_covariance_and_partial_correlations() {
+    // x and y both follow z, and are otherwise unrelated
+    let z = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+    let x: Vec<f64> = z
+        .iter()
+        .zip([0.3, -0.2, 0.1, -0.4, 0.2, 0.0, -0.1, 0.3])
+        .map(|(a, e)| a + e)
+        .collect();
+    let y: Vec<f64> = z
+        .iter()
+        .zip([-0.1, 0.2, 0.4, -0.3, 0.0, 0.1, -0.2, 0.2])
+        .map(|(a, e)| 2.0 * a + e)
+        .collect();
+    let (status, out) = post_json(
+        "/api/v1/stats/corr-matrix",
+        serde_json::json!({
+            "series": [x, y, z], "covariance": true, "partial": true
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let at = |k: &str, i: usize| out[k][i].as_f64().unwrap();
+
+    // the diagonal holds the variances: var(1..=8) = 6
+    assert!((at("covariance", 8) - 6.0).abs() < 1e-12);
+    assert_eq!(out["covariance"][1], out["covariance"][3]);
+    let (rxy, rxz, ryz) = (at("matrix", 1), at("matrix", 2), at("matrix", 5));
+    assert!(rxy > 0.9);
+    let want = (rxy - rxz * ryz) / ((1.0 - rxz * rxz) * (1.0 - ryz * ryz)).sqrt();
+    assert!((at("partial", 1) - want).abs() < 1e-9);
+    // given z, x and y are far less related
+    assert!(at("partial", 1).abs() < rxy);
+    assert_eq!(at("partial", 0), 1.0);
+
+    // a constant series leaves the matrix without an inverse
+    let (status, out) = post_json(
+        "/api/v1/stats/corr-matrix",
+        serde_json::json!({
+            "series": [[1, 2, 3], [3, 1, 2], [5, 5, 5]], "covariance": true, "partial": true
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["covariance"][8], 0.0);
+    assert!(
+        out["partial"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|p| p.is_null())
+    );
+    let (status, out) = post_json(
+        "/api/v1/stats/corr-matrix",
+        serde_json::json!({ "series": [[1, 2, 3], [3, 1, 2]] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(out.get("covariance").is_none() && out.get("partial").is_none());
+}
+
+#[tokio::test]
+async fn stats_corr_matrix_cluster_orders_correlated_blocks_together() {
+    // b/d alternate in step, a/c trend together a little less tightly
+    let body = serde_json::json!({
+        "series": [
+            [1, 2, 3, 4, 5, 6],
+            [1, -1, 1, -1, 1, -1],
+            [1, 2, 3, 4, 6, 5],
+            [2, -2, 2, -2, 2, -1.5]
+        ],
+        "names": ["a", "b", "c", "d"],
+        "cluster": "average"
+    });
+
+    let (status, out) = post_json("/api/v1/stats/corr-matrix", body.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["order"], serde_json::json!([1, 3, 0, 2]));
+    let steps = out["linkage"].as_array().unwrap();
+    assert_eq!(steps.len(), 3);
+    assert_eq!(
+        (steps[0]["left"].as_u64(), steps[0]["right"].as_u64()),
+        (Some(1), Some(3))
+    );
+    assert_eq!(
+        (steps[1]["left"].as_u64(), steps[1]["right"].as_u64()),
+        (Some(0), Some(2))
+    );
+    assert_eq!(steps[2]["size"], 4);
+    let r13 = out["matrix"][7].as_f64().unwrap();
+    assert!((steps[0]["distance"].as_f64().unwrap() - (1.0 - r13)).abs() < 1e-12);
+    // the matrix keeps the input order
+    assert!(out["matrix"][1].as_f64().unwrap() < 0.0);
+
+    let mut plot = body;
+    plot["kind"] = "corr_heatmap".into();
+    let (status, out) = post_json("/api/v1/plots/spec", plot).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        out["spec"]["encoding"]["x"]["sort"],
+        serde_json::json!(["b", "d", "a", "c"])
+    );
+}
+#[tokio::test]
+async fn corr_matrix_csv_names_numeric_columns() {
+    use stats_rs::{
+        ingest::{CsvOptions, read_csv},
+        tenant::Tenant,
+        types::DatasetFormat,
+    };
+
+    let state = Arc::new(AppState::default());
+    let csv = "a,b,label,c\n1,2,x,4\n2,4,y,3\n3,6,z,\n4,9,w,1\n";
+    let ds = state.datasets.insert(
+        &Tenant::default(),
+        "t.csv".into(),
+        "test".into(),
+        DatasetFormat::Csv,
+        csv.len(),
+        read_csv(csv.as_bytes(), &CsvOptions::default()).unwrap(),
+    );
+    let app = build_app(state);
+    let post = |uri: String, content_type: &str, body: String| {
+        let app = app.clone();
+        let req = Request::post(uri)
+            .header("content-type", content_type)
+            .body(Body::from(body))
+            .unwrap();
+        async move {
+            let res = app.oneshot(req).await.unwrap();
+            let status = res.status();
+            let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            (
+                status,
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            )
+        }
+    };
+
+    let (status, out) = post(
+        "/api/v1/stats/corr-matrix-csv".into(),
+        "text/csv",
+        csv.into(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["names"], serde_json::json!(["a", "b", "c"]));
+    assert_eq!(out["size"], 3);
+    // the empty cell only costs the pairs with `c`
+    assert_eq!(
+        out["n_used"],
+        serde_json::json!([4, 4, 3, 4, 4, 3, 3, 3, 3])
+    );
+
+    // the same as transposing by hand
+    let (_, by_hand) = post(
+        "/api/v1/stats/corr-matrix".into(),
+        "application/json",
+        serde_json::json!({
+            "series": [[1, 2, 3, 4], [2, 4, 6, 9], [4, 3, null, 1]],
+            "names": ["a", "b", "c"],
+            "missing": "pairwise"
+        })
+        .to_string(),
+    )
+    .await;
+    assert_eq!(out["matrix"], by_hand["matrix"]);
+
+    let (status, from_ds) = post(
+        format!("/api/v1/stats/corr-matrix-csv?dataset={}", ds.id),
+        "text/csv",
+        String::new(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(from_ds["matrix"], out["matrix"]);
+
+    // No cached answer outlives the dataset
+    let res = app
+        .clone()
+        .oneshot(
+            Request::delete(format!("/api/v1/datasets/{}", ds.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let (status, _) = post(
+        format!("/api/v1/stats/corr-matrix-csv?dataset={}", ds.id),
+        "text/csv",
+        String::new(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, err) = post(
+        "/api/v1/stats/corr-matrix-csv".into(),
+        "text/csv",
+        "label\nx\ny\n".into(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(err["code"], "no_numeric_data");
+}
+
+// ========== CSV/TSV export ==========
+async fn export(uri: &str, accept: Option<&str>, body: serde_json::Value) -> (String, String) {
+    let mut req = Request::post(uri).header("content-type", "application/json");
+    if let Some(a) = accept {
+        req = req.header("accept", a);
+    }
+    let res = make_app()
+        .oneshot(req.body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let ct = res.headers()["content-type"].to_str().unwrap().to_string();
+    let text = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (ct, String::from_utf8(text.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn tabular_endpoints_export_csv_and_tsv() {
+    let (ct, text) = export(
+        "/api/v1/stats/summary",
+        Some("text/csv"),
+        serde_json::json!({"values": [1, 2, 3]}),
+    )
+    .await;
+    assert!(ct.starts_with("text/csv"));
+    assert!(text.starts_with("stat,value\ncount,3\nmean,2\n"));
+
+    let (ct, text) = export(
+        "/api/v1/stats/corr-matrix?format=tsv",
+        None,
+        serde_json::json!({"series": [[1, 2, 3], [3, 2, 1]], "names": ["a", "b"]}),
+    )
+    .await;
+    assert!(ct.starts_with("text/tab-separated-values"));
+    assert_eq!(text, "\ta\tb\na\t1\t-1\nb\t-1\t1\n");
+
+    let (_, text) = export(
+        "/api/v1/stats/distribution?format=csv",
+        None,
+        serde_json::json!({"values": [0, 1, 2, 3], "bins": 2}),
+    )
+    .await;
+    assert_eq!(text, "lower,upper,count\n0,1.5,2\n1.5,3,2\n");
+
+    let (_, text) = export(
+        "/api/v1/stats/ecdf?format=csv",
+        None,
+        serde_json::json!({"values": [2, 1, 2]}),
+    )
+    .await;
+    assert_eq!(
+        text.lines().collect::<Vec<_>>(),
+        ["x,p", "1,0.3333333333333333", "2,1"]
+    );
+
+    // JSON stays the default, and unknown formats are rejected
+    let (ct, _) = export(
+        "/api/v1/stats/ecdf",
+        None,
+        serde_json::json!({"values": [1]}),
+    )
+    .await;
+    assert!(ct.starts_with("application/json"));
+    let res = make_app()
+        .oneshot(
+            Request::post("/api/v1/stats/ecdf?format=xml")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"values": [1]}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+// ========== outliers ==========
+#[derive(Deserialize)]
+struct OutliersOut {
+    values: Vec<f64>,
+}
+
+#[tokio::test]
+async fn stats_outliers_iqr_finds_extreme() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/outliers")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "values": [1,2,3,4,100],
+                        "method": "iqr"
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: OutliersOut = serde_json::from_slice(&buf).unwrap();
+
+    assert!(out.values.contains(&100.0));
+}
+
+#[tokio::test]
+async fn stats_outliers_indices_skip_dropped_nulls() {
+    let res = make_app()
+        .oneshot(
+            Request::post("/api/v1/stats/outliers")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"values": [1, null, 2, 3, null, 4, 100], "method": "iqr"}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let out: serde_json::Value =
+        serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(out["indices"], serde_json::json!([6]));
+    assert_eq!(out["missing"]["count"], 2);
+}
+
+#[tokio::test]
+async fn stats_outliers_top_k_returns_the_most_extreme_first() {
+    let (status, out) = post_json(
+        "/api/v1/stats/outliers",
+        serde_json::json!({"values": [1, 2, 3, 4, 5, 6, 7, 8, null, -60, 100, 40], "top_k": 2}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["indices"], serde_json::json!([10, 9]));
+    assert_eq!(out["values"], serde_json::json!([100.0, -60.0]));
+    assert_eq!(out["total"], 3);
+    let scores = out["scores"].as_array().unwrap();
+    assert!(scores[0].as_f64() > scores[1].as_f64());
+
+    let (_, out) =
+        post_json("/api/v1/stats/outliers", serde_json::json!({"values": [1, 2, 3, 100], "method": "zscore", "threshold": 1, "top_k": 5})).await;
+    assert_eq!(out["indices"], serde_json::json!([3]));
+    assert_eq!(out["total"], 1);
+
+    let (status, out) = post_json(
+        "/api/v1/stats/outliers",
+        serde_json::json!({"values": [1, 2, 3], "top_k": 0}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(out["details"]["field"], "/top_k");
+}
+
+#[tokio::test]
+async fn stats_outliers_bounds_point_scores_and_cleaning() {
+    // q1 = 1.5, q3 = 4.5, so the fences are -3 and 9
+    let (status, out) = post_json(
+        "/api/v1/stats/outliers",
+        serde_json::json!({
+            "values": [1, 2, null, 3, 4, 5, 100, -20],
+            "point_scores": true,
+            "clean": "winsorize"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        out["bounds"],
+        serde_json::json!({ "lower": -3.0, "upper": 9.0, "threshold": 1.5 })
+    );
+    let scores = out["point_scores"].as_array().unwrap();
+    assert_eq!(scores.len(), 8);
+    assert!(scores[2].is_null());
+    assert_eq!(scores[3], 0.0);
+    assert!((scores[6].as_f64().unwrap() - 95.5 / 3.0).abs() < 1e-12); // (100 - q3) / iqr
+    assert_eq!(
+        out["cleaned"],
+        serde_json::json!([1.0, 2.0, 3.0, 4.0, 5.0, 9.0, -3.0])
+    );
+
+    let (status, out) = post_json(
+        "/api/v1/stats/outliers",
+        serde_json::json!({
+            "values": [1, 2, 3, 4, 5, 100, -20], "clean": "remove", "top_k": 1
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["indices"], serde_json::json!([5]));
+    // cleaning drops every outlier, not only the top one
+    assert_eq!(out["cleaned"], serde_json::json!([1.0, 2.0, 3.0, 4.0, 5.0]));
+    assert!(out.get("point_scores").is_none());
+
+    let (status, out) = post_json(
+        "/api/v1/stats/outliers",
+        serde_json::json!({
+            "values": [1, 2, 3, 4, 5], "method": "zscore", "threshold": 2
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (lo, hi) = (
+        out["bounds"]["lower"].as_f64().unwrap(),
+        out["bounds"]["upper"].as_f64().unwrap(),
+    );
+    let sd = 2.5f64.sqrt();
+    assert!((lo - (3.0 - 2.0 * sd)).abs() < 1e-12 && (hi - (3.0 + 2.0 * sd)).abs() < 1e-12);
+    assert_eq!(out["bounds"]["threshold"], 2.0);
+}
+
+#[tokio::test]
+async fn stats_outliers_fits_fences_within_groups() {
+    // `a` spans 10..13 with a 30, `b` spans 25..45; pooled, nothing stands out
+    let values = [10, 25, 11, 30, 12, 35, 13, 40, 30, 45];
+    let groups = ["a", "b", "a", "b", "a", "b", "a", "b", "a", "b"];
+    let (status, pooled) = post_json(
+        "/api/v1/stats/outliers",
+        serde_json::json!({ "values": values }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(pooled["indices"], serde_json::json!([]));
+
+    let (status, v) = post_json(
+        "/api/v1/stats/outliers",
+        serde_json::json!({
+            "values": values, "groups": groups, "clean": "winsorize"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v["indices"], serde_json::json!([8]));
+    assert!(v.get("bounds").is_none());
+    assert_eq!(v["groups"][0]["label"], "a");
+    assert_eq!(v["groups"][0]["count"], 5);
+    assert_eq!(v["groups"][0]["bounds"]["upper"], 16.0);
+    assert_eq!(v["groups"][1]["label"], "b");
+    assert_eq!(v["cleaned"][8], 16.0);
+
+    let (status, err) = post_json(
+        "/api/v1/stats/outliers",
+        serde_json::json!({
+            "values": [1, 2, 3], "groups": ["a", "b", null]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(err["details"]["field"], "/groups/2");
+    let (status, err) = post_json(
+        "/api/v1/stats/outliers",
+        serde_json::json!({ "values": [1, 2, 3], "groups": ["a"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(err["details"]["field"], "/groups");
+}
+
+// ========== normalize ==========
+#[derive(Deserialize)]
+struct NormalizeOut {
+    values: Vec<f64>,
+}
+
+#[tokio::test]
+async fn stats_normalize_minmax_range() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/normalize")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "values": [10, 20],
+                        "method": "minmax",
+                        "range": [0.0, 1.0]
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: NormalizeOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.values[0], 0.0);
+    assert_eq!(out.values[1], 1.0);
+}
+
+#[tokio::test]
+async fn stats_normalize_params_replay_on_new_data() {
+    // fit on the training set
+    let (status, fit) = post_json(
+        "/api/v1/stats/normalize",
+        serde_json::json!({ "values": [10, 20, 30], "method": "minmax", "range": [-1, 1] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let params = fit["params"].clone();
+    assert_eq!(
+        params,
+        serde_json::json!({ "method": "minmax", "min": 10.0, "max": 30.0, "range": [-1.0, 1.0] })
+    );
+
+    // test data is scaled with the training min/max, even outside them
+    let (status, out) = post_json(
+        "/api/v1/stats/normalize/apply",
+        serde_json::json!({ "values": [20, 40, null], "params": params }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["values"], serde_json::json!([0.0, 2.0]));
+    assert_eq!(out["missing"]["count"], 1);
+
+    let (_, back) = post_json(
+        "/api/v1/stats/normalize/inverse",
+        serde_json::json!({ "values": [0.0, 2.0], "params": params }),
+    )
+    .await;
+    assert_eq!(back["values"], serde_json::json!([20.0, 40.0]));
+
+    let (_, fit) = post_json(
+        "/api/v1/stats/normalize",
+        serde_json::json!({ "values": [1, 2, 3] }),
+    )
+    .await;
+    assert_eq!(fit["params"]["method"], "zscore");
+    assert_eq!(fit["params"]["mean"], 2.0);
+    assert_eq!(fit["params"]["std"], 1.0);
+    let (_, out) = post_json(
+        "/api/v1/stats/normalize/inverse",
+        serde_json::json!({ "values": fit["values"], "params": fit["params"] }),
+    )
+    .await;
+    assert_eq!(out["values"], serde_json::json!([1.0, 2.0, 3.0]));
+
+    let (status, out) = post_json("/api/v1/stats/normalize/apply",
+        serde_json::json!({ "values": [1], "params": { "method": "zscore", "mean": 0, "std": -1 } }),)
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(out["details"]["field"], "/params/std");
+}
+
+#[tokio::test]
+async fn long_outputs_page_and_downsample() {
+    // A spike LTTB keeps and even spacing would skip
+    let mut values = vec![0.0; 100];
+    values[37] = 50.0;
+    let (status, out) = post_json(
+        "/api/v1/stats/normalize",
+        serde_json::json!({
+            "values": values, "method": "minmax",
+            "window": {"max_points": 10, "downsample": "lttb"}
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["values"].as_array().unwrap().len(), 10);
+    assert!(out["indices"].as_array().unwrap().contains(&37.into()));
+    assert_eq!(
+        out["window"],
+        serde_json::json!({"total": 100, "offset": 0, "selected": 100, "returned": 10, "downsample": "lttb"})
+    );
+
+    let (_, out) = post_json(
+        "/api/v1/stats/ecdf",
+        serde_json::json!({"values": (1..=50).collect::<Vec<_>>(), "window": {"offset": 45, "limit": 10}}),
+    )
+    .await;
+    assert_eq!(out["xs"], serde_json::json!([46.0, 47.0, 48.0, 49.0, 50.0]));
+    assert_eq!(out["window"]["returned"], 5);
+
+    let (status, out) = post_json(
+        "/api/v1/stats/qq-normal",
+        serde_json::json!({"values": [1, 2, 3], "window": {"limit": 0}}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(out["details"]["field"], "/window/limit");
+}
+
+#[tokio::test]
+async fn fields_select_the_metrics_computed() {
+    let app = make_app();
+    let post = |uri: &str, body: serde_json::Value| {
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let read = |res: axum::response::Response| async move {
+        to_bytes(res.into_body(), usize::MAX).await.unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(post(
+            "/api/v1/stats/pairwise?fields=pearson",
+            serde_json::json!({"x": [1, 2, 3, null], "y": [2, 4, 6, 8]}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let out: serde_json::Value = serde_json::from_slice(&read(res).await).unwrap();
+    assert_eq!(
+        out,
+        serde_json::json!({
+            "pearson": 1.0,
+            "n": 3,
+            "confidence": 0.95,
+            "pearson_test": {"p_value": 0.0, "lower": null, "upper": null},
+            "missing": {"policy": "drop", "count": 1}
+        })
+    );
+
+    let res = app
+        .clone()
+        .oneshot(post(
+            "/api/v1/stats/summary?fields=mean,max&format=csv",
+            serde_json::json!({"values": [1, 2, 3]}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(read(res).await, "stat,value\ncount,3\nmean,2\nmax,3\n");
+
+    let res = app
+        .oneshot(post(
+            "/api/v1/stats/summary?fields=mean,kendall",
+            serde_json::json!({"values": [1, 2, 3]}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn stats_normalize_scales_each_group_on_its_own() {
+    let (status, v) = post_json(
+        "/api/v1/stats/normalize",
+        serde_json::json!({
+            "values": [0, 100, 5, 200, 10, null, 300],
+            "groups": [1, 2, 1, 2, 1, 2, 2],
+            "method": "minmax"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        v["values"],
+        serde_json::json!([0.0, 0.0, 0.5, 0.5, 1.0, 1.0])
+    );
+    assert!(v.get("params").is_none());
+    assert_eq!(
+        v["groups"],
+        serde_json::json!([
+            { "label": "1", "count": 3, "params": { "method": "minmax", "min": 0.0, "max": 10.0, "range": [0.0, 1.0] } },
+            { "label": "2", "count": 3, "params": { "method": "minmax", "min": 100.0, "max": 300.0, "range": [0.0, 1.0] } }
+        ])
+    );
+}
+
+// ========== binrule ==========
+#[derive(Deserialize)]
+struct BinRuleOut {
+    bins: usize,
+    width: Option<f64>,
+    edges: Vec<f64>,
+}
+
+#[tokio::test]
+async fn stats_binrule_returns_positive_bins() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/binrule")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "values": [1,2,3,4,5,6,7,8,9,10],
+                        "rule": "sturges"
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: BinRuleOut = serde_json::from_slice(&buf).unwrap();
+
+    assert!(out.bins >= 2);
+}
+
+#[tokio::test]
+async fn stats_binrule_returns_width_and_edges_for_every_rule() {
+    let values: Vec<f64> = (1..=200).map(|i| (i as f64).powf(1.5)).collect();
+    let post = |rule: &str| {
+        post_json(
+            "/api/v1/stats/binrule",
+            serde_json::json!({ "values": &values, "rule": rule }),
+        )
+    };
+
+    let mut sturges = 0;
+    for rule in [
+        "auto",
+        "sturges",
+        "sqrt",
+        "rice",
+        "doane",
+        "scott",
+        "fd",
+        "shimazaki",
+    ] {
+        let (status, out) = post(rule).await;
+        assert_eq!(status, StatusCode::OK, "{rule}");
+        let out: BinRuleOut = serde_json::from_value(out).unwrap();
+        assert!(out.bins >= 2, "{rule}");
+        assert_eq!(out.edges.len(), out.bins + 1, "{rule}");
+        let width = out.width.unwrap();
+        assert!((out.edges[1] - out.edges[0] - width).abs() < 1e-9, "{rule}");
+        assert_eq!(out.edges[0], 1.0);
+        match rule {
+            "sturges" => sturges = out.bins,
+            "rice" => assert_eq!(out.bins, 12), // ⌈2 · 200^(1/3)⌉
+            "sqrt" => assert_eq!(out.bins, 15),
+            // right-skewed, so Doane adds bins over Sturges
+            "doane" => assert!(out.bins > sturges),
+            _ => {}
+        }
+    }
+
+    assert_eq!(post("Doane").await.0, StatusCode::OK);
+    assert_eq!(post("auto-ish").await.0, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+// ========== entropy ==========
+#[tokio::test]
+async fn stats_entropy_estimates_bits_and_nats() {
+    // one value per bin of four: exactly 2 bits
+    let (status, out) = post_json(
+        "/api/v1/stats/entropy",
+        serde_json::json!({"values": [0, 1, 2, 3, null], "bins": 4}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["method"], "histogram");
+    assert!((out["bits"].as_f64().unwrap() - 2.0).abs() < 1e-12);
+    assert!((out["nats"].as_f64().unwrap() - 4f64.ln()).abs() < 1e-12);
+    assert_eq!(out["n"], 4);
+    assert_eq!(out["bins"], 4);
+    assert!(out.get("k").is_none());
+
+    // k-NN differential entropy shifts by ln 10 when the data scale by 10
+    let values: Vec<f64> = (1..=50).map(|i| (i as f64).sqrt()).collect();
+    let scaled: Vec<f64> = values.iter().map(|x| 10.0 * x).collect();
+    let (status, a) = post_json(
+        "/api/v1/stats/entropy",
+        serde_json::json!({"values": values, "method": "knn"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(a["k"], 3);
+    let (_, b) = post_json(
+        "/api/v1/stats/entropy",
+        serde_json::json!({"values": scaled, "method": "knn"}),
+    )
+    .await;
+    let (a, b) = (a["nats"].as_f64().unwrap(), b["nats"].as_f64().unwrap());
+    assert!((b - a - 10f64.ln()).abs() < 1e-9);
+
+    // repeated values collapse the k-NN distances
+    let (status, out) = post_json(
+        "/api/v1/stats/entropy",
+        serde_json::json!({"values": [1, 1, 1, 2], "method": "knn", "k": 1}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(out["bits"].is_null());
+
+    for (body, field) in [
+        (
+            serde_json::json!({"values": [1, 2, 3], "method": "knn", "k": 3}),
+            "/k",
+        ),
+        (serde_json::json!({"values": [1, 2, 3], "k": 0}), "/k"),
+        (serde_json::json!({"values": [1, 2, 3], "bins": 1}), "/bins"),
+    ] {
+        let (status, out) = post_json("/api/v1/stats/entropy", body).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(out["details"]["field"], field);
+    }
+}
+
+// ========== contingency ==========
+#[tokio::test]
+async fn stats_contingency_tabulates_raw_labels() {
+    let (status, out) = post_json(
+        "/api/v1/stats/contingency",
+        serde_json::json!({
+            "x": ["m", "f", "m", "f", "m", "m", null, "f"],
+            "y": [1, 2, 1, 1, 2, 1, 2, null],
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["rows"], serde_json::json!(["f", "m"]));
+    assert_eq!(out["cols"], serde_json::json!(["1", "2"]));
+    assert_eq!(out["counts"], serde_json::json!([[1, 1], [3, 1]]));
+    assert_eq!(out["row_totals"], serde_json::json!([2, 4]));
+    assert_eq!(out["col_totals"], serde_json::json!([4, 2]));
+    assert_eq!(out["n"], 6);
+    assert_eq!(out["df"], 1);
+    assert_eq!(out["missing"]["count"], 2);
+    let e = out["expected"][0][0].as_f64().unwrap();
+    assert!((e - 8.0 / 6.0).abs() < 1e-12);
+    // χ² = n (ad − bc)² / (r1 r2 c1 c2) for a 2×2 table
+    let chi2 = out["chi_square"].as_f64().unwrap();
+    assert!((chi2 - 6.0 * 4.0 / 64.0).abs() < 1e-12);
+    let v = out["cramers_v"].as_f64().unwrap();
+    assert!((v - (chi2 / 6.0).sqrt()).abs() < 1e-12);
+    assert_eq!(out["yates"], false);
+
+    let (_, corrected) = post_json(
+        "/api/v1/stats/contingency",
+        serde_json::json!({
+            "x": ["m", "f", "m", "f", "m", "m"],
+            "y": [1, 2, 1, 1, 2, 1],
+            "yates": true
+        }),
+    )
+    .await;
+    assert_eq!(corrected["yates"], true);
+    assert!(corrected["p_value"].as_f64().unwrap() > out["p_value"].as_f64().unwrap());
+    assert_eq!(corrected["cramers_v"], out["cramers_v"]);
+
+    // a single column tests nothing
+    let (status, out) = post_json(
+        "/api/v1/stats/contingency",
+        serde_json::json!({"x": ["a", "b"], "y": [true, true]}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["cols"], serde_json::json!(["true"]));
+    assert!(out["chi_square"].is_null() && out["p_value"].is_null());
+
+    let (status, out) = post_json(
+        "/api/v1/stats/contingency",
+        serde_json::json!({"x": ["a", "b"], "y": ["c"]}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(out["details"]["field"], "/y");
+}
+
+// ========== seasonality ==========
+#[tokio::test]
+async fn stats_seasonality_detects_period_and_summarizes_seasons() {
+    // a weekday profile repeated over eight weeks
+    let profile = [5.0, 6.0, 6.5, 6.0, 7.0, 2.0, 1.0];
+    let values: Vec<f64> = (0..56).map(|i| profile[i % 7]).collect();
+    let (status, out) = post_json(
+        "/api/v1/stats/seasonality",
+        serde_json::json!({ "values": values }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["n"], 56);
+    assert_eq!(out["acf"].as_array().unwrap().len(), 29);
+    assert_eq!(out["acf_period"], 7);
+    assert_eq!(out["periodogram_period"], 7);
+    assert_eq!(out["period"], 7);
+    let seasons = out["seasons"].as_array().unwrap();
+    assert_eq!(seasons.len(), 7);
+    assert_eq!(seasons[4]["season"], "4");
+    assert_eq!(seasons[4]["count"], 8);
+    assert_eq!(seasons[4]["mean"], 7.0);
+    assert_eq!(seasons[4]["std_dev"], 0.0);
+    assert!((out["strength"].as_f64().unwrap() - 1.0).abs() < 1e-12);
+
+    // the same series dated from Monday 2024-03-04, grouped by weekday
+    let mut timestamps: Vec<String> = (4..60)
+        .map(|day| match day {
+            ..=31 => format!("2024-03-{day:02} 09:00"),
+            _ => format!("2024-04-{:02} 09:00", day - 31),
+        })
+        .collect();
+    timestamps[3] = "not a date".into();
+    let (status, out) = post_json(
+        "/api/v1/stats/seasonality",
+        serde_json::json!({
+            "values": values, "timestamps": timestamps, "season": "day_of_week"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["season"], "day_of_week");
+    assert!(out.get("period").is_none());
+    assert_eq!(out["skipped"], 1);
+    let seasons = out["seasons"].as_array().unwrap();
+    assert_eq!(seasons[0]["season"], "Mon");
+    assert_eq!(seasons[0]["mean"], 5.0);
+    assert_eq!(seasons[3]["count"], 7);
+    assert_eq!(seasons[6]["mean"], 1.0);
+
+    let (status, out) = post_json(
+        "/api/v1/stats/seasonality",
+        serde_json::json!({"values": [1, 2, 3], "season": "hour_of_day"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(out["details"]["field"], "/season");
+    let (status, out) = post_json(
+        "/api/v1/stats/seasonality",
+        serde_json::json!({"values": [1, 2, 3], "timestamps": ["2024-01-01"]}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(out["details"]["field"], "/timestamps");
+}
+
+// ========== rag/mmr ==========
+#[cfg(feature = "rag")]
+#[derive(Deserialize)]
+struct MmrOut {
+    indices: Vec<usize>,
+    relevance: Vec<f64>,
+    diversity: Vec<f64>,
+}
+
+#[cfg(feature = "rag")]
+#[tokio::test]
+async fn stats_rag_mmr_diversifies() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/rag/mmr")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "candidates": [[1.0, 0.0], [0.9, 0.1], [0.0, 1.0]],
+                        "query": [1.0, 0.0],
+                        "lambda": 0.3,
+                        "k": 2
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: MmrOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.indices, vec![0, 2]);
+    assert!((out.relevance[0] - 1.0).abs() < 1e-12);
+    assert!((out.diversity[1] - 1.0).abs() < 1e-12);
+}
+
+#[cfg(feature = "rag")]
+#[tokio::test]
+async fn stats_rag_mmr_dimension_mismatch_is_400() {
+    let app = make_app();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/rag/mmr")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "candidates": [[1.0, 0.0], [1.0]],
+                        "query": [1.0, 0.0],
+                        "k": 1
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[cfg(feature = "rag")]
+#[tokio::test]
+async fn stats_rag_mmr_sparse_vectors() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/rag/mmr")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "candidates": [
+                            {"indices": [7], "values": [1.0]},
+                            {"indices": [7, 9000], "values": [0.9, 0.1]},
+                            {"indices": [9000], "values": [1.0]}
+                        ],
+                        "query": {"indices": [7], "values": [2.0]},
+                        "lambda": 0.3,
+                        "k": 2
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: MmrOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.indices, vec![0, 2]);
+}
+
+// ========== vector/knn-distances ==========
+#[derive(Deserialize)]
+struct KnnDistOut {
+    k: usize,
+    distances: Vec<f64>,
+    counts: Vec<usize>,
+}
+
+#[tokio::test]
+async fn stats_knn_distances_line() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/vector/knn-distances")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "points": [[0.0], [1.0], [3.0], [7.0]],
+                        "k": 1,
+                        "bins": 3
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: KnnDistOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.k, 1);
+    assert_eq!(out.distances, vec![1.0, 1.0, 2.0, 4.0]);
+    assert_eq!(out.counts.iter().sum::<usize>(), 4);
+}
+
+// ========== vector/intrinsic-dim ==========
+#[derive(Deserialize)]
+struct IntrinsicDimOut {
+    dimension: Option<f64>,
+    ambient_dim: usize,
+}
+
+#[tokio::test]
+async fn stats_intrinsic_dim_line_in_3d() {
+    let app = make_app().into_service();
+    // points on a line with irregular spacing, embedded in 3-D
+    let ts = [0.0, 0.7, 1.1, 2.6, 3.0, 4.9, 5.2, 7.5, 8.1, 9.9, 11.0, 13.7];
+    let points: Vec<Vec<f64>> = ts.iter().map(|&t| vec![t, 2.0 * t, -t]).collect();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/vector/intrinsic-dim")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "points": points,
+                        "method": "mle",
+                        "k": 3
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: IntrinsicDimOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.ambient_dim, 3);
+    let d = out.dimension.unwrap();
+    assert!(d > 0.0 && d < 3.0, "dimension = {d}");
+}
+
+// ========== vector/near-duplicates ==========
+#[derive(Deserialize)]
+struct NearDupOut {
+    groups: Vec<Vec<usize>>,
+    redundant: Vec<usize>,
+}
+
+#[tokio::test]
+async fn stats_near_duplicates_groups_transitively() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/vector/near-duplicates")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "points": [[1.0, 0.0], [0.0, 1.0], [1.0, 0.01], [2.0, 0.0], [0.0, 3.0]],
+                        "threshold": 0.99
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: NearDupOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.groups, vec![vec![0, 2, 3], vec![1, 4]]);
+    assert_eq!(out.redundant, vec![2, 3, 4]);
+}
+
+#[tokio::test]
+async fn stats_near_duplicates_accepts_base64_embeddings() {
+    use stats_rs::embedding::encode_f32_base64;
+
+    let app = make_app().into_service();
+    let points = serde_json::json!([
+        encode_f32_base64(&[1.0, 0.0]),
+        [0.0, 1.0],
+        encode_f32_base64(&[1.0, 0.01]),
+        [2.0, 0.0],
+        encode_f32_base64(&[0.0, 3.0])
+    ]);
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/vector/near-duplicates")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "points": points,
+                        "threshold": 0.99
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: NearDupOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.groups, vec![vec![0, 2, 3], vec![1, 4]]);
+}
+
+#[tokio::test]
+async fn stats_near_duplicates_computes_in_f32() {
+    use stats_rs::embedding::encode_f32_base64;
+
+    let app = make_app().into_service();
+    let points = serde_json::json!([
+        encode_f32_base64(&[1.0, 0.0]),
+        [0.0, 1.0],
+        encode_f32_base64(&[1.0, 0.01]),
+        [2.0, 0.0],
+        [0.0, 3.0]
+    ]);
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/vector/near-duplicates")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "points": points,
+                        "threshold": 0.99,
+                        "dtype": "f32"
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: NearDupOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.groups, vec![vec![0, 2, 3], vec![1, 4]]);
+    assert_eq!(out.redundant, vec![2, 3, 4]);
+}
+
+#[tokio::test]
+async fn stats_near_duplicates_rejects_bad_base64() {
+    let app = make_app();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/vector/near-duplicates")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({ "points": ["AAAA", "AAA"] })).unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+// ========== vector/similarity ==========
+#[derive(Deserialize)]
+struct SimilarityOut {
+    scores: Vec<Option<f64>>,
+}
+
+#[tokio::test]
+async fn stats_similarity_sparse_dot() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/vector/similarity")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "query": {"indices": [3, 10], "values": [1.0, 2.0]},
+                        "candidates": [
+                            {"indices": [10, 3], "values": [0.5, 4.0]},
+                            {"indices": [11], "values": [1.0]},
+                            {"indices": [], "values": []}
+                        ],
+                        "kernel": "dot"
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: SimilarityOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.scores, vec![Some(5.0), Some(0.0), Some(0.0)]);
+}
+
+#[tokio::test]
+async fn stats_similarity_rejects_mixed_representations() {
+    let app = make_app();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/vector/similarity")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "query": [1.0, 0.0],
+                        "candidates": [{"indices": [0], "values": [1.0]}]
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+// ========== rag/text-metrics ==========
+#[cfg(feature = "rag")]
+#[derive(Deserialize)]
+struct TextMetricsOut {
+    mean_token_f1: Option<f64>,
+    mean_bleu: Option<f64>,
+}
+
+#[cfg(feature = "rag")]
+#[tokio::test]
+async fn stats_rag_text_metrics_exact_match() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/rag/text-metrics")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "candidates": ["The capital of France is Paris."],
+                        "references": ["the capital of france is paris"]
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: TextMetricsOut = serde_json::from_slice(&buf).unwrap();
+
+    assert!((out.mean_token_f1.unwrap() - 1.0).abs() < 1e-12);
+    assert!((out.mean_bleu.unwrap() - 1.0).abs() < 1e-12);
+}
+
+// ========== rag/groundedness ==========
+#[cfg(feature = "rag")]
+#[derive(Deserialize)]
+struct GroundednessOut {
+    groundedness: Option<f64>,
+    supported_fraction: Option<f64>,
+}
+
+#[cfg(feature = "rag")]
+#[tokio::test]
+async fn stats_rag_groundedness_half_supported() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/rag/groundedness")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "answer": [[1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+                        "context": [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                        "threshold": 0.7
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: GroundednessOut = serde_json::from_slice(&buf).unwrap();
+
+    assert!((out.groundedness.unwrap() - 0.5).abs() < 1e-12);
+    assert!((out.supported_fraction.unwrap() - 0.5).abs() < 1e-12);
+}
+
+// ========== rag/metrics ==========
+#[cfg(feature = "rag")]
+#[derive(Deserialize)]
+struct RagPerQuery {
+    reciprocal_rank: Vec<Option<f64>>,
+    recall_at_k: Vec<Option<f64>>,
+}
+
+#[cfg(feature = "rag")]
+#[derive(Deserialize)]
+struct RagMetricsOut {
+    n_queries: usize,
+    mrr: Option<f64>,
+    per_query: RagPerQuery,
+}
+
+#[cfg(feature = "rag")]
+#[tokio::test]
+async fn stats_rag_metrics_per_query_and_median() {
+    let app = make_app().into_service();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/rag/metrics")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "queries": [
+                            {"retrieved": [1, 2, 3], "relevant": [1]},
+                            {"retrieved": [4, 5, 6], "relevant": [5]},
+                            {"retrieved": [7, 8, 9], "relevant": []}
+                        ],
+                        "k": 3,
+                        "aggregation": "median"
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: RagMetricsOut = serde_json::from_slice(&buf).unwrap();
+
+    assert_eq!(out.n_queries, 3);
+    assert_eq!(
+        out.per_query.reciprocal_rank,
+        vec![Some(1.0), Some(0.5), Some(0.0)]
+    );
+    assert_eq!(out.per_query.recall_at_k[2], None);
+    assert!((out.mrr.unwrap() - 0.5).abs() < 1e-12);
+}
+
+#[cfg(feature = "rag")]
+#[tokio::test]
+async fn stats_rag_metrics_percentile_requires_p() {
+    let app = make_app();
+
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/stats/rag/metrics")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::to_vec(&serde_json::json!({
+                        "queries": [{"retrieved": [1], "relevant": [1]}],
+                        "aggregation": "percentile"
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn service_config_drives_limits_cors_and_toggles() {
+    use stats_rs::config::{FeatureToggles, ServiceConfig};
+
+    let app = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            max_body_bytes: 64,
+            cors_origins: vec!["https://stats.example.com".into()],
+            features: FeatureToggles {
+                vector: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+
+    let post = |uri: &str, body: String| {
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .header("content-length", body.len())
+            .header("origin", "https://stats.example.com")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(post("/api/v1/describe", "[1,2,3]".into()))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.headers()["access-control-allow-origin"],
+        "https://stats.example.com"
+    );
+
+    let big = format!("[{}]", vec!["1"; 100].join(","));
+    let res = app
+        .clone()
+        .oneshot(post("/api/v1/describe", big))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let res = app
+        .oneshot(post(
+            "/api/v1/stats/vector/similarity",
+            r#"{"a":[1,0],"b":[0,1]}"#.into(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn timeouts_apply_per_route_group() {
+    use stats_rs::config::ServiceConfig;
+    use std::time::Duration;
+
+    let app = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            quick_timeout_secs: 1,
+            request_timeout_secs: 1,
+            heavy_timeout_secs: 60,
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+    // A body that never arrives keeps the handler waiting
+    let stalled = |uri: &str| {
+        let body = futures_util::stream::pending::<Result<axum::body::Bytes, std::io::Error>>();
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .body(Body::from_stream(body))
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(stalled("/api/v1/stats/summary"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::REQUEST_TIMEOUT);
+
+    let heavy = tokio::time::timeout(
+        Duration::from_millis(1500),
+        app.oneshot(stalled("/api/v1/profile")),
+    )
+    .await;
+    assert!(heavy.is_err(), "heavy route timed out with the quick limit");
+}
+
+#[tokio::test]
+async fn cors_allows_only_listed_origins() {
+    use stats_rs::config::ServiceConfig;
+
+    let app = |origins: &[&str]| {
+        build_app(Arc::new(AppState {
+            config: ServiceConfig {
+                cors_origins: origins.iter().map(|o| o.to_string()).collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }))
+    };
+    let allow_origin = |app: axum::Router, origin: &'static str| async move {
+        let res = app
+            .oneshot(
+                Request::builder()
+                    .method("OPTIONS")
+                    .uri("/api/v1/describe")
+                    .header("origin", origin)
+                    .header("access-control-request-method", "POST")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        res.headers()
+            .get("access-control-allow-origin")
+            .map(|v| v.to_str().unwrap().to_string())
+    };
+
+    // No origins configured: browsers get no CORS grant
+    assert_eq!(allow_origin(app(&[]), "https://evil.example").await, None);
+
+    let listed = app(&["https://stats.example.com", "https://*.corp.net"]);
+    assert_eq!(
+        allow_origin(listed.clone(), "https://app.corp.net")
+            .await
+            .as_deref(),
+        Some("https://app.corp.net")
+    );
+    assert_eq!(allow_origin(listed, "https://evil.example").await, None);
+
+    assert_eq!(
+        allow_origin(app(&["*"]), "https://evil.example")
+            .await
+            .as_deref(),
+        Some("*")
+    );
+}
+
+#[tokio::test]
+async fn cors_preflight_allows_dataset_writes() {
+    use stats_rs::config::ServiceConfig;
+
+    let app = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            cors_origins: vec!["https://stats.example.com".into()],
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+    for (method, uri) in [
+        ("PUT", "/api/v1/datasets/d1/corr-matrix"),
+        ("DELETE", "/api/v1/datasets/d1"),
+    ] {
+        let res = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("OPTIONS")
+                    .uri(uri)
+                    .header("origin", "https://stats.example.com")
+                    .header("access-control-request-method", method)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let allowed = res.headers()["access-control-allow-methods"]
+            .to_str()
+            .unwrap();
+        assert!(allowed.split(',').any(|m| m.trim() == method), "{allowed}");
+    }
+}
+
+#[tokio::test]
+async fn repeated_analyses_hit_the_result_cache() {
+    let state = Arc::new(AppState::default());
+    let app = build_app(state.clone());
+    let csv = "a,b\n1,2\n2,4\n3,7\n4,8\n";
+
+    let mut bodies = Vec::new();
+    for bins in [4, 6] {
+        let res = app
+            .clone()
+            .oneshot(
+                Request::post(format!("/api/v1/profile?bins={bins}"))
+                    .header("content-type", "text/csv")
+                    .body(Body::from(csv))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        bodies.push(v["correlations"].clone());
+    }
+    assert_eq!(bodies[0], bodies[1]);
+    let stats = state.cache.stats();
+    // table + correlations stored once, both found on the second request;
+    // each response is stored as well
+    assert_eq!((stats.entries, stats.hits), (4, 2));
+}
+
+#[tokio::test]
+async fn jobs_run_in_background_and_return_results() {
+    let app = make_app();
+    let submit = |body: serde_json::Value| {
+        Request::post("/api/v1/jobs")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+    let res = app
+        .clone()
+        .oneshot(submit(serde_json::json!({
+            "kind": "bootstrap",
+            "values": (1..=40).collect::<Vec<_>>(),
+            "resamples": 400,
+            "seed": 3
+        })))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    let location = res.headers()["location"].to_str().unwrap().to_string();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let job: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        location,
+        format!("/api/v1/jobs/{}", job["id"].as_str().unwrap())
+    );
+    assert_eq!(job["kind"], "bootstrap");
+
+    let mut status = serde_json::Value::Null;
+    for _ in 0..200 {
+        let res = app.clone().oneshot(get(&location)).await.unwrap();
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        status = serde_json::from_slice(&body).unwrap();
+        if status["status"] == "succeeded" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+    assert_eq!(status["status"], "succeeded");
+    assert_eq!(status["progress"], 1.0);
+
+    let res = app
+        .clone()
+        .oneshot(get(&format!("{location}/result")))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(out["estimate"], 20.5);
+    let (lo, hi) = (
+        out["lower"].as_f64().unwrap(),
+        out["upper"].as_f64().unwrap(),
+    );
+    assert!(lo < 20.5 && 20.5 < hi);
+
+    // Parameters are validated before queueing
+    let res = app
+        .clone()
+        .oneshot(submit(serde_json::json!({
+            "kind": "bootstrap", "values": [1, 2], "confidence": 1.5
+        })))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = app.oneshot(get("/api/v1/jobs/job_99")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn job_retries_with_an_idempotency_key_reuse_the_job() {
+    let app = make_app();
+    let submit = |key: &str, body: serde_json::Value| {
+        Request::post("/api/v1/jobs")
+            .header("content-type", "application/json")
+            .header("idempotency-key", key)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let job = serde_json::json!({"kind": "permutation", "x": [1, 2, 3], "y": [4, 5, 6], "seed": 1});
+
+    let first = app
+        .clone()
+        .oneshot(submit("retry-1", job.clone()))
+        .await
+        .unwrap();
+    assert_eq!(first.status(), StatusCode::ACCEPTED);
+    assert!(first.headers().get("idempotent-replayed").is_none());
+    let again = app
+        .clone()
+        .oneshot(submit("retry-1", job.clone()))
+        .await
+        .unwrap();
+    assert_eq!(again.status(), StatusCode::ACCEPTED);
+    assert_eq!(again.headers()["idempotent-replayed"], "true");
+    assert_eq!(again.headers()["location"], first.headers()["location"]);
+
+    let other = app
+        .clone()
+        .oneshot(submit("retry-2", job.clone()))
+        .await
+        .unwrap();
+    assert_ne!(other.headers()["location"], first.headers()["location"]);
+
+    let mut changed = job.clone();
+    changed["seed"] = 2.into();
+    let res = app
+        .clone()
+        .oneshot(submit("retry-1", changed))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    let res = app.oneshot(submit("", job)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn request_ids_are_echoed_and_quoted_in_errors() {
+    let app = make_app();
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+    let json = |res: axum::response::Response| async move {
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    let res = app.clone().oneshot(get("/api/v1/health")).await.unwrap();
+    let generated = res.headers()["x-request-id"].to_str().unwrap();
+    assert_eq!(generated.len(), 36, "a UUID: {generated}");
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::get("/api/v1/jobs/job_404")
+                .header("x-request-id", "report-me-42")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(res.headers()["x-request-id"], "report-me-42");
+    assert_eq!(json(res).await["request_id"], "report-me-42");
+
+    let res = app.oneshot(get("/api/v2/jobs/job_404")).await.unwrap();
+    let id = res.headers()["x-request-id"].to_str().unwrap().to_string();
+    assert_eq!(json(res).await["error"]["request_id"], id.as_str());
+}
+
+#[tokio::test]
+async fn errors_are_structured() {
+    let app = make_app();
+    let json = |res: axum::response::Response| async move {
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/profile")
+                .header("content-type", "text/csv")
+                .body(Body::from(&b"a,b\n1,2\n3,\xff\n"[..]))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let body = json(res).await;
+    assert_eq!(body["code"], "csv_parse");
+    assert_eq!(body["details"], serde_json::json!({"line": 3, "column": 2}));
+
+    let res = app
+        .oneshot(
+            Request::get("/api/v1/jobs/job_404")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let body = json(res).await;
+    assert_eq!(body["code"], "not_found");
+    assert_eq!(body["message"], "not found: job 'job_404'");
+}
+
+#[tokio::test]
+async fn invalid_stats_requests_get_422_with_field_paths() {
+    let app = make_app();
+    let check = |uri: &'static str, body: &'static str, field: &'static str| {
+        let app = app.clone();
+        async move {
+            let res = app
+                .oneshot(
+                    Request::post(uri)
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY, "{uri}");
+            let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(v["code"], "validation_failed");
+            assert_eq!(v["details"]["field"], field, "{uri}");
+        }
+    };
+
+    check("/api/v1/stats/pairwise", r#"{"x":[1,2,3],"y":[1,2]}"#, "/y").await;
+    check(
+        "/api/v1/stats/distribution",
+        r#"{"values":[1,2],"quantiles":[0.5,1.5]}"#,
+        "/quantiles/1",
+    )
+    .await;
+    check(
+        "/api/v1/stats/distribution",
+        r#"{"values":[1,2],"bins":1}"#,
+        "/bins",
+    )
+    .await;
+    check(
+        "/api/v1/stats/corr-matrix",
+        r#"{"series":[[1,2],[3,4]],"names":["a"]}"#,
+        "/names",
+    )
+    .await;
+    check("/api/v1/stats/summary", r#"{"values":[]}"#, "/values").await;
+    check(
+        "/api/v1/stats/binrule",
+        r#"{"values":[1,2],"rule":"magic"}"#,
+        "/rule",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn dataset_column_distributions_are_cached() {
+    use stats_rs::{
+        ingest::{CsvOptions, read_csv},
+        types::DatasetFormat,
+    };
+
+    let state = Arc::new(AppState::default());
+    let csv = "x,label\n3,a\n1,b\n,c\n4,d\n1,e\n5,f\n";
+    let table = read_csv(csv.as_bytes(), &CsvOptions::default()).unwrap();
+    let ds = state.datasets.insert(
+        &stats_rs::tenant::Tenant::default(),
+        "t.csv".into(),
+        "test".into(),
+        DatasetFormat::Csv,
+        csv.len(),
+        table,
+    );
+    let app = build_app(state.clone());
+    let get = |uri: String| {
+        let app = app.clone();
+        async move {
+            let res = app
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let status = res.status();
+            let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            (
+                status,
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            )
+        }
+    };
+    let uri = |rest: &str| format!("/api/v1/datasets/{}/{rest}", ds.id);
+
+    let (status, v) = get(uri("columns/x/distribution?bins=4&quantiles=0.5,1")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v["counts"].as_array().unwrap().len(), 4);
+    assert_eq!(v["quantiles"], serde_json::json!([[0.5, 3.0], [1.0, 5.0]]));
+    assert_eq!(v["missing"]["count"], 1);
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/stats/distribution")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"values":[3,1,4,1,5],"bins":4,"quantiles":[0.5,1]}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let posted: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(v["skewness"], posted["skewness"]);
+    assert_eq!(v["edges"], posted["edges"]);
+
+    // values, sorted copy and histogram are reused on the next request
+    let before = state.cache.stats();
+    let (_, again) = get(uri("columns/x/distribution?bins=4&quantiles=0.5,1")).await;
+    assert_eq!(again, v);
+    let after = state.cache.stats();
+    assert_eq!(
+        (after.hits - before.hits, after.entries),
+        (3, before.entries)
+    );
+
+    let (status, _) = get(uri("columns/nope/distribution")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = get(uri("columns/label/distribution")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, v) = get(uri("columns/x/distribution?quantiles=0.5,2")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(v["details"]["field"], "/quantiles/1");
+}
+
+#[tokio::test]
+async fn offloaded_requests_answer_like_inline_ones() {
+    use stats_rs::{compute::ComputePool, config::ComputeConfig};
+
+    let offloading = build_app(Arc::new(AppState {
+        compute: ComputePool::new(&ComputeConfig {
+            offload_above: 0,
+            workers: 1,
+        }),
+        ..Default::default()
+    }));
+    let post = |app: axum::Router, uri: &'static str, body: &'static str| async move {
+        let res = app
+            .oneshot(
+                Request::post(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK, "{uri}");
+        to_bytes(res.into_body(), usize::MAX).await.unwrap()
+    };
+    for (uri, body) in [
+        ("/api/v1/stats/summary", r#"{"values":[1,2,3,4]}"#),
+        (
+            "/api/v1/stats/corr-matrix",
+            r#"{"series":[[1,2,3],[3,1,2]],"method":"spearman"}"#,
+        ),
+        (
+            "/api/v1/stats/vector/knn-distances",
+            r#"{"points":[[0,0],[1,0],[0,2]],"k":1}"#,
+        ),
+    ] {
+        let want = post(make_app(), uri, body).await;
+        assert_eq!(post(offloading.clone(), uri, body).await, want);
+    }
+}
+
+#[tokio::test]
+async fn oversized_arrays_are_rejected_while_parsing() {
+    use stats_rs::config::ServiceConfig;
+
+    let app = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            max_values: 3,
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+    let post = |uri: &'static str, body: &'static str| {
+        let app = app.clone();
+        async move {
+            let res = app
+                .oneshot(
+                    Request::post(uri)
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = res.status();
+            let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice(&body).unwrap_or_default())
+        }
+    };
+
+    let (status, v): (_, serde_json::Value) =
+        post("/api/v1/stats/summary", r#"{"values":[1,2,null]}"#).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v["count"], 2);
+
+    // Rejected at the fourth number, before the broken tail is read
+    let (status, v) = post("/api/v1/stats/summary", r#"{"values":[1,2,3,4,oops"#).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(v["code"], "validation_failed");
+    assert_eq!(v["details"]["field"], "/values");
+
+    let (status, v) = post("/api/v1/stats/corr-matrix", r#"{"series":[[1,2],[3,4]]}"#).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(v["details"]["field"], "/series/1");
+
+    // Malformed bodies keep their usual rejection
+    let (status, _) = post("/api/v1/stats/summary", r#"{"values":[1,"#).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn v2_wraps_responses_in_an_envelope() {
+    let app = make_app();
+    let post = |uri: &'static str, content_type: &'static str, body: &'static str| {
+        let app = app.clone();
+        async move {
+            let res = app
+                .oneshot(
+                    Request::post(uri)
+                        .header("content-type", content_type)
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = res.status();
+            let ct = res.headers()["content-type"].to_str().unwrap().to_string();
+            let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            (status, ct, body)
+        }
+    };
+    let json = |b: &[u8]| serde_json::from_slice::<serde_json::Value>(b).unwrap();
+
+    let body = r#"{"values":[1,null,2,3,4]}"#;
+    let (status, _, v1) = post("/api/v1/stats/summary", "application/json", body).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, v2) = post("/api/v2/stats/summary", "application/json", body).await;
+    assert_eq!(status, StatusCode::OK);
+    let (v1, v2) = (json(&v1), json(&v2));
+    assert_eq!(v1["count"], 4, "v1 is not wrapped");
+    assert_eq!(v2["data"], v1);
+    assert_eq!(v2["meta"]["n_used"], 4);
+    assert_eq!(v2["meta"]["n_dropped"], 1);
+    assert!(v2["meta"]["elapsed_ms"].as_f64().unwrap() >= 0.0);
+    assert_eq!(v2["meta"]["warnings"], serde_json::json!([]));
+    assert!(v2.get("error").is_none());
+
+    // A cached response keeps the count its handler reported
+    let (_, _, again) = post("/api/v2/stats/summary", "application/json", body).await;
+    assert_eq!(json(&again)["meta"]["n_used"], 4);
+
+    // Typed errors and extractor rejections share the error shape
+    let (status, _, v) = post(
+        "/api/v2/stats/summary",
+        "application/json",
+        r#"{"values":[]}"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let v = json(&v);
+    assert!(v["data"].is_null());
+    assert_eq!(v["error"]["code"], "validation_failed");
+    assert_eq!(v["error"]["details"]["field"], "/values");
+
+    let (status, _, v) = post("/api/v2/stats/summary", "text/plain", body).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(json(&v)["error"]["code"], "unsupported_media_type");
+
+    // Tabular renderings pass through
+    let (status, ct, csv) =
+        post("/api/v2/stats/summary?format=csv", "application/json", body).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(ct.starts_with("text/csv"));
+    assert!(csv.starts_with(b"stat,value"));
+
+    let res = make_app()
+        .oneshot(Request::get("/api/v2/health").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let v = json(&to_bytes(res.into_body(), usize::MAX).await.unwrap());
+    assert_eq!(v["data"], "ok");
+    assert!(v["meta"]["n_used"].is_null());
+}
+
+#[tokio::test]
+async fn v2_reports_n_used_for_every_analysis() {
+    // 12 values once the `null` is dropped
+    let xs = serde_json::json!([1, 2, null, 4, 5, 6, 7, 8, 9, 10, 11, 12, 30]);
+    let (_, normalized) = post_json(
+        "/api/v2/stats/normalize",
+        serde_json::json!({ "values": xs }),
+    )
+    .await;
+    let params = &normalized["data"]["params"];
+    let cases = [
+        (
+            "/stats/distribution",
+            serde_json::json!({ "values": xs }),
+            12,
+        ),
+        ("/stats/ecdf", serde_json::json!({ "values": xs }), 12),
+        ("/stats/qq-normal", serde_json::json!({ "values": xs }), 12),
+        ("/stats/outliers", serde_json::json!({ "values": xs }), 12),
+        ("/stats/normalize", serde_json::json!({ "values": xs }), 12),
+        (
+            "/stats/normalize/apply",
+            serde_json::json!({ "values": xs, "params": params }),
+            12,
+        ),
+        (
+            "/stats/normalize/inverse",
+            serde_json::json!({ "values": xs, "params": params }),
+            12,
+        ),
+        ("/stats/binrule", serde_json::json!({ "values": xs }), 12),
+        (
+            "/stats/anomaly/score",
+            serde_json::json!({ "values": xs }),
+            12,
+        ),
+        (
+            "/stats/drift",
+            serde_json::json!({ "values": xs, "window": 4 }),
+            12,
+        ),
+        (
+            "/stats/compare",
+            serde_json::json!({ "x": xs, "y": [1, 2, 3, null] }),
+            15,
+        ),
+        (
+            "/stats/tests/recommend",
+            serde_json::json!({ "groups": [xs, [1, 2, 3, 4, 5]] }),
+            17,
+        ),
+        (
+            "/stats/corr-matrix",
+            serde_json::json!({ "series": [[1, 2, null, 4, 5], [2, 1, 4, 3, null], [1, 1, 2, 3, 5]] }),
+            3,
+        ),
+        (
+            "/stats/ttest",
+            serde_json::json!({ "x": xs, "y": [1, 2, 3, null] }),
+            15,
+        ),
+        (
+            "/plots/spec",
+            serde_json::json!({ "kind": "histogram", "values": xs }),
+            12,
+        ),
+    ];
+    for (route, body, n) in cases {
+        let (status, v) = post_json(&format!("/api/v2{route}"), body).await;
+        assert_eq!(status, StatusCode::OK, "{route}: {v}");
+        assert_eq!(v["meta"]["n_used"], n, "{route}");
+    }
+
+    // Under the default `pairwise`, rows observed in at least two columns
+    let res = make_app()
+        .oneshot(
+            Request::post("/api/v2/stats/corr-matrix-csv")
+                .header("content-type", "text/csv")
+                .body(Body::from("a,b,c\n1,2,\n2,,\n3,5,1\n4,4,2\n"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(v["meta"]["n_used"], 3);
+}
+
+#[cfg(feature = "ws")]
+#[tokio::test]
+async fn ws_stats_streams_running_statistics() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, make_app()).await.unwrap() });
+
+    let url = format!("ws://{addr}/api/v1/ws/stats?quantiles=0.5&interval_ms=50");
+    let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    ws.send(Message::text("[1, 2, null]")).await.unwrap();
+    ws.send(Message::text(r#"{"values": [3]}"#)).await.unwrap();
+    ws.send(Message::text("oops")).await.unwrap();
+
+    let mut error = None;
+    let stats = loop {
+        let Some(Ok(Message::Text(t))) = ws.next().await else {
+            panic!("socket closed early");
+        };
+        let v: serde_json::Value = serde_json::from_str(&t).unwrap();
+        if v.get("code").is_some() {
+            error = Some(v);
+        } else if v["count"] == 3 {
+            break v;
+        }
+    };
+    assert_eq!(stats["missing"], 1);
+    assert_eq!(stats["mean"], 2.0);
+    assert_eq!(stats["quantiles"], serde_json::json!([[0.5, 2.0]]));
+    // A malformed chunk is reported without closing the socket
+    let error = match error {
+        Some(e) => e,
+        None => {
+            let Some(Ok(Message::Text(t))) = ws.next().await else {
+                panic!("socket closed early");
+            };
+            serde_json::from_str(&t).unwrap()
+        }
+    };
+    assert_eq!(error["code"], "invalid_input");
+    ws.close(None).await.unwrap();
+
+    let bad = format!("ws://{addr}/api/v1/ws/stats?quantiles=2");
+    match tokio_tungstenite::connect_async(bad).await {
+        Err(tokio_tungstenite::tungstenite::Error::Http(res)) => {
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST)
+        }
+        other => panic!("expected a 400 before the upgrade, got {other:?}"),
+    }
+}
+
+#[cfg(feature = "ws")]
+#[tokio::test]
+async fn ws_stats_flags_outliers_as_they_arrive() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, make_app()).await.unwrap() });
+
+    let url = format!("ws://{addr}/api/v1/ws/stats?interval_ms=50&detector=ewma&alpha=0.2");
+    let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    let calm: Vec<f64> = (0..30).map(|i| 20.0 + f64::from(i % 4) * 0.5).collect();
+    ws.send(Message::text(serde_json::to_string(&calm).unwrap()))
+        .await
+        .unwrap();
+    ws.send(Message::text("[21, 90, 20.5]")).await.unwrap();
+
+    let mut outliers = vec![];
+    let stats = loop {
+        let Some(Ok(Message::Text(t))) = ws.next().await else {
+            panic!("socket closed early");
+        };
+        let v: serde_json::Value = serde_json::from_str(&t).unwrap();
+        outliers.extend(v["outliers"].as_array().unwrap().iter().cloned());
+        if v["count"] == 33 {
+            break v;
+        }
+    };
+    assert_eq!(stats["flagged"], 1);
+    assert_eq!(outliers.len(), 1);
+    assert_eq!(
+        (&outliers[0]["index"], &outliers[0]["value"]),
+        (&31.into(), &90.0.into())
+    );
+    ws.close(None).await.unwrap();
+
+    let bad = format!("ws://{addr}/api/v1/ws/stats?detector=robust_z&window=3");
+    match tokio_tungstenite::connect_async(bad).await {
+        Err(tokio_tungstenite::tungstenite::Error::Http(res)) => {
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST)
+        }
+        other => panic!("expected a 400 before the upgrade, got {other:?}"),
+    }
+}
+
+#[cfg(feature = "grpc")]
+#[tokio::test]
+async fn grpc_serves_stats_on_the_http_port() {
+    use stats_rs::grpc::proto::{self, stats_client::StatsClient};
+    use tonic::{Code, transport::Channel};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, make_app()).await.unwrap() });
+
+    let channel = Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let mut client = StatsClient::new(channel);
+
+    let out = client
+        .summary(proto::SummaryRequest {
+            values: vec![1.0, 2.0, f64::NAN, 3.0],
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(out.count, 3);
+    assert_eq!(out.mean, Some(2.0));
+    assert_eq!(out.missing.map(|m| m.count), Some(1));
+
+    let out = client
+        .corr_matrix(proto::CorrMatrixRequest {
+            series: vec![
+                proto::Series {
+                    values: vec![1.0, 2.0, 3.0],
+                },
+                proto::Series {
+                    values: vec![3.0, 2.0, 1.0],
+                },
+            ],
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(out.size, 2);
+    assert!((out.matrix[1] + 1.0).abs() < 1e-12);
+
+    // The same validation as the JSON route, as INVALID_ARGUMENT
+    let err = client
+        .pairwise(proto::PairwiseRequest {
+            x: vec![1.0, 2.0],
+            y: vec![1.0],
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+    let body: serde_json::Value = serde_json::from_slice(err.details()).unwrap();
+    assert_eq!(body["details"]["field"], "/y");
+}
+
+#[cfg(feature = "grpc")]
+#[tokio::test]
+async fn grpc_calls_need_an_api_key_when_keys_are_configured() {
+    use stats_rs::{
+        config::{ServiceConfig, TenantConfig},
+        grpc::proto::{self, stats_client::StatsClient},
+    };
+    use tonic::{Code, transport::Channel};
+
+    let app = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            tenants: TenantConfig {
+                api_keys: [("k-a".to_string(), "team-a".to_string())].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let channel = Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let mut client = StatsClient::new(channel);
+    let call = |key: Option<&str>| {
+        let mut req = tonic::Request::new(proto::SummaryRequest {
+            values: vec![1.0, 2.0, 3.0],
+            ..Default::default()
+        });
+        if let Some(k) = key {
+            req.metadata_mut().insert("x-api-key", k.parse().unwrap());
+        }
+        req
+    };
+
+    for key in [None, Some("nope")] {
+        let err = client.summary(call(key)).await.unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated, "{key:?}");
+    }
+    let out = client
+        .summary(call(Some("k-a")))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(out.count, 3);
+}
+
+#[tokio::test]
+async fn deterministic_analyses_support_if_none_match() {
+    let app = make_app();
+    let summary = |etag: Option<&str>| {
+        let mut req =
+            Request::post("/api/v1/stats/summary").header("content-type", "application/json");
+        if let Some(tag) = etag {
+            req = req.header("if-none-match", tag);
+        }
+        req.body(Body::from(r#"{"values":[1,2,3,4]}"#)).unwrap()
+    };
+
+    let res = app.clone().oneshot(summary(None)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let etag = res.headers()["etag"].to_str().unwrap().to_string();
+    assert!(etag.starts_with('"') && etag.ends_with('"'));
+    let first = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+
+    // Served from the cache, same tag and body
+    let res = app.clone().oneshot(summary(None)).await.unwrap();
+    assert_eq!(res.headers()["etag"], etag.as_str());
+    assert_eq!(res.headers()["content-type"], "application/json");
+    assert_eq!(to_bytes(res.into_body(), usize::MAX).await.unwrap(), first);
+
+    let res = app.clone().oneshot(summary(Some(&etag))).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(res.headers()["etag"], etag.as_str());
+    assert!(
+        to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let res = app
+        .clone()
+        .oneshot(summary(Some("\"stale\"")))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // A wildcard is no tag: an uncached request is still computed
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/stats/summary")
+                .header("content-type", "application/json")
+                .header("if-none-match", "*")
+                .body(Body::from(r#"{"values":[5,6,7]}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let out: serde_json::Value =
+        serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(out["mean"], 6.0);
+
+    // Side effects are never skipped
+    let res = app
+        .oneshot(
+            Request::post("/api/v1/jobs")
+                .header("content-type", "application/json")
+                .header("if-none-match", "*")
+                .body(Body::from(r#"{"kind":"bootstrap","values":[1,2,3]}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    assert!(res.headers().get("etag").is_none());
+}
+
+#[tokio::test]
+async fn admin_routes_need_the_configured_token() {
+    use stats_rs::{
+        config::{AdminConfig, ServiceConfig},
+        logging::LogControl,
+    };
+
+    let res = make_app()
+        .oneshot(Request::get("/admin/config").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(
+        res.status(),
+        StatusCode::NOT_FOUND,
+        "unmounted without a token"
+    );
+
+    let state = Arc::new(AppState {
+        config: ServiceConfig {
+            admin: AdminConfig {
+                token: Some("s3cret".into()),
+            },
+            ..Default::default()
+        },
+        log: Some(LogControl::new("info", |_| Ok(()))),
+        ..Default::default()
+    });
+    let app = build_app(state.clone());
+    let call = |method: &str, uri: &str, token: &str, body: Option<serde_json::Value>| {
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("authorization", format!("Bearer {token}"))
+            .header("content-type", "application/json");
+        req.body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+            .unwrap()
+    };
+    let json = |res: axum::response::Response| async move {
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/admin/config", "wrong", None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(res.headers()["www-authenticate"], "Bearer");
+    assert_eq!(json(res).await["code"], "unauthorized");
+
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/admin/config", "s3cret", None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(json(res).await["admin"]["token"], "***");
+
+    state
+        .cache
+        .insert(stats_rs::cache::CacheKey::new("t", &"k"), vec![1.0; 4]);
+    let res = app
+        .clone()
+        .oneshot(call("POST", "/admin/cache/flush", "s3cret", None))
+        .await
+        .unwrap();
+    assert_eq!(json(res).await["entries"], 1);
+    assert_eq!(state.cache.stats().entries, 0);
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "PUT",
+            "/admin/log-level",
+            "s3cret",
+            Some(serde_json::json!({"filter": "debug"})),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/admin/log-level", "s3cret", None))
+        .await
+        .unwrap();
+    assert_eq!(json(res).await["filter"], "debug");
+    let res = app
+        .clone()
+        .oneshot(call(
+            "PUT",
+            "/admin/log-level",
+            "s3cret",
+            Some(serde_json::json!({"filter": "stats_rs=loud"})),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = app
+        .clone()
+        .oneshot(call("DELETE", "/admin/datasets", "s3cret", None))
+        .await
+        .unwrap();
+    assert_eq!(json(res).await["removed"], 0);
+    let res = app
+        .oneshot(call("POST", "/admin/jobs/drain", "s3cret", None))
+        .await
+        .unwrap();
+    assert_eq!(json(res).await["cancelled"], 0);
+}
+
+#[tokio::test]
+async fn audit_trail_records_analyses_and_is_queryable() {
+    use axum::http::HeaderName;
+    use stats_rs::{
+        audit::AuditLog,
+        config::{AdminConfig, ServiceConfig},
+    };
+
+    let path = std::env::temp_dir().join(format!("stats_http_audit_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let log = AuditLog::file(&path, HeaderName::from_static("x-user-id"))
+        .await
+        .unwrap();
+    let app = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            admin: AdminConfig {
+                token: Some("s3cret".into()),
+            },
+            ..Default::default()
+        },
+        audit: Some(Arc::new(log)),
+        ..Default::default()
+    }));
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/stats/summary?dataset_id=sales")
+                .header("content-type", "application/json")
+                .header("x-user-id", "ana")
+                .body(Body::from(r#"{"values":[1,2,3]}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = app
+        .clone()
+        .oneshot(Request::get("/api/v1/health").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = app
+        .oneshot(
+            Request::get("/admin/audit?user=ana")
+                .header("authorization", "Bearer s3cret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let records = v["records"].as_array().unwrap();
+    assert_eq!(records.len(), 1, "GETs are not audited: {v}");
+    let r = &records[0];
+    assert_eq!(r["path"], "/api/v1/stats/summary");
+    assert_eq!(r["dataset"], "sales");
+    assert_eq!(r["status"], 200);
+    assert_eq!(r["params"]["values"], "[3 items]");
+    assert!(r["request_id"].is_string());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn dataset_corr_matrix_grows_in_place() {
+    use stats_rs::{
+        ingest::{CsvOptions, read_csv},
+        stats::pearson_correlation,
+        types::DatasetFormat,
+    };
+
+    let state = Arc::new(AppState::default());
+    let csv = "a,b,label\n1,2,x\n2,1,y\n3,4,z\n,3,w\n4,3,v\n";
+    let table = read_csv(csv.as_bytes(), &CsvOptions::default()).unwrap();
+    let ds = state.datasets.insert(
+        &stats_rs::tenant::Tenant::default(),
+        "t.csv".into(),
+        "test".into(),
+        DatasetFormat::Csv,
+        csv.len(),
+        table,
+    );
+    let app = build_app(state.clone());
+    let send = |method: &'static str, rest: &'static str, body: &'static str| {
+        let app = app.clone();
+        let uri = format!("/api/v1/datasets/{}/corr-matrix{rest}", ds.id);
+        async move {
+            let res = app
+                .oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = res.status();
+            let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            (
+                status,
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            )
+        }
+    };
+    let r = |v: &serde_json::Value, i: usize| v["matrix"][i].as_f64().unwrap();
+
+    let (status, _) = send("GET", "", "").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Row 4 has an empty `a` and is dropped
+    let (status, v) = send("PUT", "", "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v["names"], serde_json::json!(["a", "b"]));
+    assert_eq!(v["missing"]["count"], 1);
+    let a = [1.0, 2.0, 3.0, 4.0];
+    let b = [2.0, 1.0, 4.0, 3.0];
+    assert!((r(&v, 1) - pearson_correlation(&a, &b)).abs() < 1e-12);
+
+    let (status, v) = send("POST", "/series", r#"{"name":"c","values":[4,3,1,0]}"#).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v["size"], 3);
+    let c = [4.0, 3.0, 1.0, 0.0];
+    assert!((r(&v, 2) - pearson_correlation(&a, &c)).abs() < 1e-12);
+    assert!((r(&v, 5) - pearson_correlation(&b, &c)).abs() < 1e-12);
+
+    let (status, v) = send("POST", "/rows", r#"{"rows":[[5,6,-1],[0,2,5]]}"#).await;
+    assert_eq!(status, StatusCode::OK);
+    let a = [1.0, 2.0, 3.0, 4.0, 5.0, 0.0];
+    let c = [4.0, 3.0, 1.0, 0.0, -1.0, 5.0];
+    assert!((r(&v, 2) - pearson_correlation(&a, &c)).abs() < 1e-12);
+    let (_, stored) = send("GET", "", "").await;
+    assert_eq!(stored["matrix"], v["matrix"]);
+
+    let (status, v) = send("POST", "/series", r#"{"name":"a","values":[1,2,3,4,5,6]}"#).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(v["details"]["field"], "/name");
+    let (_, v) = send("POST", "/series", r#"{"name":"d","values":[1,2]}"#).await;
+    assert_eq!(v["details"]["field"], "/values");
+    let (_, v) = send("POST", "/rows", r#"{"rows":[[1,2,3],[1,2]]}"#).await;
+    assert_eq!(v["details"]["field"], "/rows/1");
+    let (_, v) = send("POST", "/rows", r#"{"rows":[[1,null,3]]}"#).await;
+    assert_eq!(v["details"]["field"], "/rows/0/1");
+
+    // The matrix goes with its dataset
+    state.datasets.evict(&ds.id);
+    assert!(state.datasets.corr(&ds.id).is_none());
+}
+
+#[tokio::test]
+async fn plot_specs_embed_the_computed_data() {
+    let (status, out) = post_json(
+        "/api/v1/plots/spec",
+        serde_json::json!({
+            "kind": "histogram", "values": [1, 2, 2, 3, null, 9], "bins": 4
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["kind"], "histogram");
+    assert_eq!(out["missing"]["count"], 1);
+    let spec = &out["spec"];
+    assert!(spec["$schema"].as_str().unwrap().contains("vega-lite/v5"));
+    assert_eq!(spec["mark"], "bar");
+    let bars = spec["data"]["values"].as_array().unwrap();
+    assert_eq!(bars.len(), 4);
+    let total: u64 = bars.iter().map(|b| b["count"].as_u64().unwrap()).sum();
+    assert_eq!(total, 5);
+
+    for kind in ["ecdf", "boxplot", "qq"] {
+        let (status, out) = post_json(
+            "/api/v1/plots/spec",
+            serde_json::json!({ "kind": kind, "values": [3, 1, 2, 8] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{kind}");
+        assert_eq!(out["kind"], kind);
+    }
+    let (_, out) = post_json(
+        "/api/v1/plots/spec",
+        serde_json::json!({
+            "kind": "corr_heatmap", "series": [[1, 2, 3], [3, 2, 1]], "names": ["up", "down"]
+        }),
+    )
+    .await;
+    assert_eq!(out["spec"]["data"]["values"][1]["r"], -1.0);
+
+    let (status, out) = post_json(
+        "/api/v1/plots/spec",
+        serde_json::json!({ "kind": "ecdf", "values": [] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(out["details"]["field"], "/values");
+}
+
+#[tokio::test]
+async fn report_renders_markdown_and_html() {
+    use stats_rs::{
+        ingest::{CsvOptions, read_csv},
+        types::DatasetFormat,
+    };
+
+    let state = Arc::new(AppState::default());
+    let csv = "x,y\n1,2\n2,4\n3,7\n4,8\n50,9\n";
+    let table = read_csv(csv.as_bytes(), &CsvOptions::default()).unwrap();
+    let ds = state.datasets.insert(
+        &stats_rs::tenant::Tenant::default(),
+        "sales.csv".into(),
+        "test".into(),
+        DatasetFormat::Csv,
+        csv.len(),
+        table,
+    );
+    let app = build_app(state);
+    let post = |uri: String, body: &'static str| {
+        let app = app.clone();
+        async move {
+            let res = app
+                .oneshot(
+                    Request::post(uri)
+                        .header("content-type", "text/csv")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = res.status();
+            let mime = res
+                .headers()
+                .get("content-type")
+                .map(|v| v.to_str().unwrap().to_owned());
+            let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            (status, mime, String::from_utf8(body.to_vec()).unwrap())
+        }
+    };
+
+    let (status, mime, md) = post("/api/v1/report?format=markdown&bins=3".into(), csv).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(mime.as_deref(), Some("text/markdown; charset=utf-8"));
+    assert!(md.starts_with("# Dataset report\n"));
+    assert!(md.contains("| x | 1 | 5: 50 |"), "{md}");
+
+    let (status, mime, page) = post(format!("/api/v1/report?dataset={}", ds.id), "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(mime.as_deref(), Some("text/html; charset=utf-8"));
+    assert!(page.contains("<h1>sales.csv</h1>"));
+    assert!(page.contains("<h2>Correlations (Pearson)</h2>"));
+
+    let (status, _, _) = post("/api/v1/report?dataset=nope".into(), "").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _, _) = post("/api/v1/report?bins=1".into(), csv).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+// ========== anomaly score ==========
+#[tokio::test]
+async fn stats_anomaly_score_ranks_points_across_detectors() {
+    // a gentle wave with one spike and a gap
+    let mut values: Vec<serde_json::Value> = (0..60)
+        .map(|i| serde_json::json!(10.0 + (i % 6) as f64 * 0.5))
+        .collect();
+    values[30] = serde_json::json!(40.0);
+    values[12] = serde_json::Value::Null;
+    let body = serde_json::json!({ "values": values, "top_k": 3, "seed": 7 });
+    let (status, out) = post_json("/api/v1/stats/anomaly/score", body.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        out["detectors"],
+        serde_json::json!(["robust_z", "iqr", "isolation_forest", "rolling_residual"])
+    );
+    assert_eq!(out["scores"].as_array().unwrap().len(), 60);
+    assert!(out["scores"][12].is_null());
+    assert_eq!(out["components"].as_array().unwrap().len(), 4);
+    let ranked = out["ranked"].as_array().unwrap();
+    assert_eq!(ranked.len(), 3);
+    assert_eq!(ranked[0]["index"], 30);
+    assert_eq!(ranked[0]["value"], 40.0);
+    let score = ranked[0]["score"].as_f64().unwrap();
+    assert!(score > 0.7, "{score}");
+    let parts: f64 = ranked[0]["contributions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c.as_f64().unwrap())
+        .sum();
+    assert!((parts - score).abs() < 1e-12);
+    assert_eq!(out["flagged"], 1);
+    assert_eq!(out["seed"], 7);
+    assert_eq!(out["missing"]["count"], 1);
+
+    // the same seed scores alike
+    let (_, again) = post_json("/api/v1/stats/anomaly/score", body).await;
+    assert_eq!(again["scores"], out["scores"]);
+
+    // one detector at a time
+    let (status, out) = post_json(
+        "/api/v1/stats/anomaly/score",
+        serde_json::json!({ "values": [1, 2, 3, 4, 100], "detectors": ["iqr"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["scores"][0], 0.25);
+    assert_eq!(out["scores"][2], 0.0);
+    assert_eq!(out["components"][0]["scores"][4], out["scores"][4]);
+
+    for (body, field) in [
+        (
+            serde_json::json!({ "values": [1, 2], "detectors": [] }),
+            "/detectors",
+        ),
+        (
+            serde_json::json!({ "values": [1, 2], "detectors": ["iqr", "iqr"] }),
+            "/detectors/1",
+        ),
+        (
+            serde_json::json!({ "values": [1, 2], "window": 2 }),
+            "/window",
+        ),
+        (
+            serde_json::json!({ "values": [1, 2], "trees": 0 }),
+            "/trees",
+        ),
+    ] {
+        let (status, err) = post_json("/api/v1/stats/anomaly/score", body).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err["details"]["field"], field);
+    }
+}
+
+// ========== compare ==========
+#[tokio::test]
+async fn stats_compare_reports_tests_effects_and_overlays() {
+    let (status, out) = post_json(
+        "/api/v1/stats/compare",
+        serde_json::json!({
+            "x": [5.1, 4.9, 6.2, 5.8, 6.0, 5.5, 5.3, null],
+            "y": [4.1, 4.5, 4.8, 3.9, 5.0, 4.4],
+            "bins": 4,
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["x"]["count"], 7);
+    assert_eq!(out["y"]["max"], 5.0);
+    assert_eq!(out["missing"]["count"], 1);
+    let close = |v: &serde_json::Value, want: f64| (v.as_f64().unwrap() - want).abs() < 1e-9;
+    assert!(close(&out["delta"]["mean"], 5.542857142857143 - 4.45));
+    assert!(close(&out["t_test"]["statistic"], 4.360654855549885));
+    assert_eq!(out["t_test"]["df"], 11.0);
+    assert!(close(&out["welch"]["p_value"], 0.001039201915728614));
+    assert!(close(&out["ks"]["statistic"], 6.0 / 7.0));
+    assert!(out["mann_whitney"].get("df").is_none());
+    assert_eq!(out["variance_f"]["df"], 6.0);
+    assert_eq!(out["variance_f"]["df2"], 5.0);
+    assert!(close(&out["effect"]["hedges_g"], 2.256785487950328));
+    // one x value (4.9) lies below the top y value (5.0)
+    assert!(close(&out["effect"]["common_language"], 41.0 / 42.0));
+    assert!(close(&out["effect"]["cliffs_delta"], 40.0 / 42.0));
+
+    let h = &out["histogram"];
+    assert_eq!(h["edges"].as_array().unwrap().len(), 5);
+    let total = |v: &serde_json::Value| {
+        v.as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c.as_u64().unwrap())
+            .sum::<u64>()
+    };
+    assert_eq!((total(&h["x"]), total(&h["y"])), (7, 6));
+    let e = &out["ecdf"];
+    assert_eq!(e["grid"].as_array().unwrap().len(), 13);
+    assert_eq!(e["x"][12], 1.0);
+    // 4.9 sits between the last two y values
+    assert_eq!(e["y"][6], 1.0);
+
+    // too small for the t-tests, but still summarized
+    let (status, out) = post_json(
+        "/api/v1/stats/compare",
+        serde_json::json!({ "x": [1.0], "y": [2.0, 3.0], "max_points": 2 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(out["t_test"]["statistic"].is_null());
+    assert_eq!(out["ecdf"]["grid"], serde_json::json!([1.0, 3.0]));
+
+    let (status, err) = post_json(
+        "/api/v1/stats/compare",
+        serde_json::json!({ "x": [1.0], "y": [] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(err["details"]["field"], "/y");
+}
+
+// ========== drift ==========
+#[tokio::test]
+async fn stats_drift_profiles_windows_against_the_reference() {
+    // two alike windows, a shifted one, and a short remainder
+    let values = serde_json::json!([1, null, 2, 3, 4, 1, 2, 3, 4, 11, 12, 13, 14, 5, 6]);
+
+    let (status, v) = post_json(
+        "/api/v1/stats/drift",
+        serde_json::json!({ "values": values, "window": 4 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v["reference"], "first");
+    let windows = v["windows"].as_array().unwrap();
+    assert_eq!(windows.len(), 3);
+    let span = |w: &serde_json::Value| (w["start"].clone(), w["end"].clone());
+    assert_eq!(span(&windows[0]), (0.into(), 5.into()));
+    assert_eq!(span(&windows[2]), (9.into(), 13.into()));
+    assert!(windows[0]["psi"].is_null() && windows[0]["ks"].is_null());
+    assert_eq!(windows[1]["ks"], 0.0);
+    assert_eq!(windows[1]["wasserstein"], 0.0);
+    assert!(windows[1]["psi"].as_f64().unwrap().abs() < 1e-9);
+    assert_eq!(windows[2]["ks"], 1.0);
+    assert_eq!(windows[2]["wasserstein"], 10.0);
+    assert!(windows[2]["psi"].as_f64().unwrap() > 1.0);
+    assert_eq!(v["missing"]["count"], 1);
+
+    let (_, prev) = post_json(
+        "/api/v1/stats/drift",
+        serde_json::json!({
+            "values": values, "window": 4, "step": 2, "reference": "previous"
+        }),
+    )
+    .await;
+    let windows = prev["windows"].as_array().unwrap();
+    assert_eq!(windows.len(), 6);
+    // [3, 4, 11, 12] follows [1, 2, 3, 4]
+    assert_eq!(windows[3]["wasserstein"], 5.0);
+
+    let (status, err) = post_json(
+        "/api/v1/stats/drift",
+        serde_json::json!({ "values": [1, 2, 3], "window": 1 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(err["details"]["field"], "/window");
+    let (status, err) = post_json(
+        "/api/v1/stats/drift",
+        serde_json::json!({ "values": [1, 2, 3], "window": 2, "step": 0 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(err["details"]["field"], "/step");
+}
+
+// ========== generate ==========
+#[tokio::test]
+async fn stats_generate_is_reproducible_per_seed() {
+    let body = serde_json::json!({
+        "distribution": { "name": "normal", "mean": 10.0 },
+        "n": 500,
+        "seed": 3,
+    });
+    let (status, out) = post_json("/api/v1/stats/generate", body.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        out["distribution"],
+        serde_json::json!({ "name": "normal", "mean": 10.0, "std_dev": 1.0 })
+    );
+    assert_eq!(out["seed"], 3);
+    assert_eq!(out["expected_variance"], 1.0);
+    let values = out["values"].as_array().unwrap();
+    assert_eq!(values.len(), 500);
+    let mean = values.iter().map(|v| v.as_f64().unwrap()).sum::<f64>() / 500.0;
+    assert!((mean - 10.0).abs() < 0.25, "{mean}");
+    let (_, again) = post_json("/api/v1/stats/generate", body).await;
+    assert_eq!(again["values"], out["values"]);
+    let (_, other) = post_json(
+        "/api/v1/stats/generate",
+        serde_json::json!({
+            "distribution": { "name": "normal", "mean": 10.0 }, "n": 500, "seed": 4
+        }),
+    )
+    .await;
+    assert_ne!(other["values"], out["values"]);
+
+    // counts are whole numbers within range
+    let (status, out) = post_json(
+        "/api/v1/stats/generate",
+        serde_json::json!({
+            "distribution": { "name": "binomial", "trials": 12, "p": 0.25 }, "n": 200
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["seed"], 0);
+    assert_eq!(out["expected_mean"], 3.0);
+    assert!(out["values"].as_array().unwrap().iter().all(|v| {
+        let k = v.as_f64().unwrap();
+        k.fract() == 0.0 && (0.0..=12.0).contains(&k)
+    }));
+
+    for (body, field) in [
+        (
+            serde_json::json!({ "distribution": { "name": "gamma", "shape": 0 }, "n": 5 }),
+            "/distribution/shape",
+        ),
+        (
+            serde_json::json!({ "distribution": { "name": "uniform", "low": 2, "high": 1 }, "n": 5 }),
+            "/distribution/high",
+        ),
+        (
+            serde_json::json!({ "distribution": { "name": "binomial", "trials": 3, "p": 1.5 }, "n": 5 }),
+            "/distribution/p",
+        ),
+        (
+            serde_json::json!({ "distribution": { "name": "poisson", "lambda": 2 }, "n": 0 }),
+            "/n",
+        ),
+    ] {
+        let (status, err) = post_json("/api/v1/stats/generate", body).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err["details"]["field"], field);
+    }
+}
+
+// ========== simulate ==========
+#[tokio::test]
+async fn stats_simulate_summarizes_seeded_replications() {
+    // difference of means under the null: centred on 0, sd √(2/30)
+    let body = serde_json::json!({
+        "pipeline": { "kind": "difference", "x": { "name": "normal" }, "n_x": 30 },
+        "replications": 2000,
+        "seed": 1,
+        "observed": 0.9,
+    });
+    let (status, out) = post_json("/api/v1/stats/simulate", body.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["replications"], 2000);
+    let mean = out["mean"].as_f64().unwrap();
+    assert!(mean.abs() < 0.03, "{mean}");
+    let sd = out["std_dev"].as_f64().unwrap();
+    assert!((sd - (2.0f64 / 30.0).sqrt()).abs() < 0.02, "{sd}");
+    assert_eq!(out["quantiles"].as_array().unwrap().len(), 7);
+    assert_eq!(out["quantiles"][3][0], 0.5);
+    assert_eq!(out["counts"].as_array().unwrap().len(), 20);
+    assert_eq!(out["non_finite"], 0);
+    // 0.9 is ~3.5 sd out: no replication reaches it
+    assert!((out["p_value"].as_f64().unwrap() - 2.0 / 2001.0).abs() < 1e-12);
+    let (_, again) = post_json("/api/v1/stats/simulate", body).await;
+    assert_eq!(again, out);
+
+    // uncertainty through a formula; ln of negative draws is counted apart
+    let (status, out) = post_json("/api/v1/stats/simulate", serde_json::json!({
+        "pipeline": {
+            "kind": "formula",
+            "expression": "a + 2 * b",
+            "variables": { "a": { "name": "uniform" }, "b": { "name": "uniform", "low": 1, "high": 2 } },
+        },
+        "replications": 4000,
+        "bins": 8,
+    }))
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["seed"], 0);
+    assert!((out["mean"].as_f64().unwrap() - 3.5).abs() < 0.05);
+    assert!(out["min"].as_f64().unwrap() >= 2.0 && out["max"].as_f64().unwrap() < 5.0);
+    assert_eq!(out["counts"].as_array().unwrap().len(), 8);
+    assert!(out.get("p_value").is_none());
+    let (_, out) = post_json("/api/v1/stats/simulate", serde_json::json!({
+        "pipeline": { "kind": "formula", "expression": "ln(z)", "variables": { "z": { "name": "normal" } } },
+        "replications": 1000,
+    }))
+    .await;
+    let non_finite = out["non_finite"].as_u64().unwrap();
+    assert!((400..600).contains(&non_finite), "{non_finite}");
+
+    for (pipeline, field) in [
+        (
+            serde_json::json!({ "kind": "formula", "expression": "a +", "variables": { "a": { "name": "normal" } } }),
+            "/pipeline/expression",
+        ),
+        (
+            serde_json::json!({ "kind": "formula", "expression": "a * q", "variables": { "a": { "name": "normal" } } }),
+            "/pipeline/expression",
+        ),
+        (
+            serde_json::json!({ "kind": "formula", "expression": "a", "variables": { "a": { "name": "normal", "std_dev": -1 } } }),
+            "/pipeline/variables/a/std_dev",
+        ),
+        (
+            serde_json::json!({ "kind": "difference", "x": { "name": "normal" }, "n_x": 0 }),
+            "/pipeline/n_x",
+        ),
+    ] {
+        let (status, err) = post_json(
+            "/api/v1/stats/simulate",
+            serde_json::json!({ "pipeline": pipeline }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err["details"]["field"], field);
+    }
+    let (status, err) = post_json(
+        "/api/v1/stats/simulate",
+        serde_json::json!({
+            "pipeline": { "kind": "difference", "x": { "name": "normal" }, "n_x": 1000 },
+            "replications": 1_000_000,
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(err["details"]["field"], "/replications");
+}
+
+// ========== tests catalog / recommend ==========
+#[tokio::test]
+async fn stats_tests_catalog_lists_runnable_tests() {
+    let res = make_app()
+        .oneshot(
+            Request::get("/api/v1/stats/tests")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+    let tests = out["tests"].as_array().unwrap();
+    let welch = tests.iter().find(|t| t["id"] == "welch_t").unwrap();
+    assert_eq!(welch["endpoint"], "POST /api/v1/stats/compare");
+    assert_eq!(welch["field"], "welch");
+    assert!(tests.iter().any(|t| t["id"] == "kruskal_wallis"));
+    assert!(
+        tests
+            .iter()
+            .all(|t| !t["assumptions"].as_array().unwrap().is_empty())
+    );
+}
+
+#[tokio::test]
+async fn stats_tests_recommend_follows_the_data() {
+    // symmetric, bell-ish groups with the same spread: Student's t
+    let a = [4.1, 4.6, 4.9, 5.0, 5.1, 5.4, 5.9, 5.0];
+    let b = [5.1, 5.6, 5.9, 6.0, 6.1, 6.4, 6.9, 6.0];
+    let (status, out) = post_json(
+        "/api/v1/stats/tests/recommend",
+        serde_json::json!({ "groups": [a, b] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(out["recommended"], "student_t");
+    assert_eq!(out["groups"][0]["normal"], true);
+    assert_eq!(out["alpha"], 0.05);
+    assert_eq!(out["result"]["df"], 14.0);
+    assert!(out["result"]["p_value"].as_f64().unwrap() < 0.01);
+
+    // a heavily skewed group and a tiny one: a rank test
+    let skewed: Vec<f64> = (1..=12).map(|i| 1.8f64.powi(i)).collect();
+    let (_, out) = post_json(
+        "/api/v1/stats/tests/recommend",
+        serde_json::json!({ "groups": [skewed, [1.0, 2.0, null, 3.0]] }),
+    )
+    .await;
+    assert_eq!(out["recommended"], "mann_whitney");
+    assert_eq!(out["groups"][0]["normal"], false);
+    assert!(out["groups"][1]["normal"].is_null());
+    assert_eq!(out["groups"][1]["count"], 3);
+    assert_eq!(out["missing"]["count"], 1);
+    assert!(out["result"].get("df").is_none());
+
+    // three normal groups: ANOVA on (2, 21) degrees of freedom
+    let c = [6.1, 6.6, 6.9, 7.0, 7.1, 7.4, 7.9, 7.0];
+    let (_, out) = post_json(
+        "/api/v1/stats/tests/recommend",
+        serde_json::json!({ "groups": [a, b, c] }),
+    )
+    .await;
+    assert_eq!(out["recommended"], "one_way_anova");
+    assert_eq!(out["result"]["df"], 2.0);
+    assert_eq!(out["result"]["df2"], 21.0);
+    assert_eq!(out["alternatives"][0], "welch_anova");
+
+    for (body, field) in [
+        (serde_json::json!({ "groups": [a] }), "/groups"),
+        (serde_json::json!({ "groups": [a, []] }), "/groups/1"),
+        (
+            serde_json::json!({ "groups": [a, b], "alpha": 1.5 }),
+            "/alpha",
+        ),
+    ] {
+        let (status, err) = post_json("/api/v1/stats/tests/recommend", body).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err["details"]["field"], field);
+    }
+}
+
+// ========== ttest ==========
+#[tokio::test]
+async fn stats_ttest_runs_one_sample_paired_and_welch_tests() {
+    let close = |v: &serde_json::Value, want: f64, tol: f64| {
+        let got = v.as_f64().unwrap();
+        assert!((got - want).abs() < tol, "{got} vs {want}");
+    };
+    // R's `sleep` data; a trailing null pair is dropped
+    let g1 = serde_json::json!([0.7, -1.6, -0.2, -1.2, -0.1, 3.4, 3.7, 0.8, 0.0, 2.0, null]);
+    let g2 = serde_json::json!([1.9, 0.8, 1.1, 0.1, -0.1, 4.4, 5.5, 1.6, 4.6, 3.4, 1.0]);
+
+    let (status, v) = post_json(
+        "/api/v1/stats/ttest",
+        serde_json::json!({ "x": g1, "y": g2, "test": "paired" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v["n_x"], 10);
+    assert_eq!(v["df"], 9.0);
+    close(&v["estimate"], -1.58, 1e-12);
+    close(&v["t"], -4.0621, 1e-4);
+    close(&v["p_value"], 0.002833, 1e-6);
+    close(&v["lower"], -2.4598858, 1e-6);
+    close(&v["upper"], -0.7001142, 1e-6);
+
+    let (_, v) = post_json(
+        "/api/v1/stats/ttest",
+        serde_json::json!({ "x": g1, "y": g2 }),
+    )
+    .await;
+    assert_eq!(v["test"], "welch");
+    assert_eq!(v["n_y"], 11);
+    assert_eq!(v["missing"]["count"], 1);
+
+    let (_, v) = post_json(
+        "/api/v1/stats/ttest",
+        serde_json::json!({
+            "x": [5.1, 4.9, 5.6, 5.8, 6.0, 5.7], "mu": 5, "alternative": "greater"
+        }),
+    )
+    .await;
+    assert_eq!(v["test"], "one_sample");
+    assert!(v["p_value"].as_f64().unwrap() < 0.05);
+    assert!(v["lower"].as_f64().unwrap() > 5.0);
+    assert!(v["upper"].is_null());
+
+    let (status, err) = post_json(
+        "/api/v1/stats/ttest",
+        serde_json::json!({ "x": [1, 2], "test": "paired" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(err["details"]["field"], "/y");
+    let (status, err) = post_json(
+        "/api/v1/stats/ttest",
+        serde_json::json!({ "x": [1, 2], "y": [1], "test": "paired" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(err["details"]["field"], "/y");
+}
+
+// ========== output precision ==========
+#[tokio::test]
+async fn precision_rounds_floats_and_blanks_non_finite_cells() {
+    let post = |uri: &'static str, body: serde_json::Value| async move {
+        make_app()
+            .oneshot(
+                Request::post(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    };
+    let text = |res: axum::response::Response| async move {
+        String::from_utf8(
+            to_bytes(res.into_body(), usize::MAX)
+                .await
+                .unwrap()
+                .to_vec(),
+        )
+        .unwrap()
+    };
+
+    let values = serde_json::json!({ "values": [1.0, 2.0, 4.0] });
+    let full: serde_json::Value =
+        serde_json::from_str(&text(post("/api/v1/stats/summary", values.clone()).await).await)
+            .unwrap();
+    assert_eq!(full["mean"], 7.0 / 3.0);
+    let res = post("/api/v1/stats/summary?precision=3", values.clone()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let out: serde_json::Value = serde_json::from_str(&text(res).await).unwrap();
+    assert_eq!(out["mean"], 2.33);
+    assert_eq!(out["count"], 3);
+    assert_eq!(out["max"], 4.0);
+
+    // a constant series has no correlation with another: empty cells and a warning
+    let res = post(
+        "/api/v1/stats/corr-matrix?format=csv&precision=2",
+        serde_json::json!({ "series": [[1.0, 2.0, 4.0], [5.0, 5.0, 5.0]] }),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.headers()["warning"],
+        "199 - \"2 non-finite value(s) written as empty cells\""
+    );
+    assert_eq!(
+        text(res).await,
+        ",series_0,series_1\nseries_0,1,\nseries_1,,1\n"
+    );
+
+    let res = post("/api/v1/stats/summary?precision=0", values).await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let err: serde_json::Value = serde_json::from_str(&text(res).await).unwrap();
+    assert_eq!(err["details"]["field"], "/precision");
+}
+
+// ========== configured seed ==========
+#[tokio::test]
+async fn configured_seed_applies_when_requests_give_none() {
+    use stats_rs::config::ServiceConfig;
+
+    let seeded = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            seed: 4,
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+    let post = |app: axum::Router, uri: &'static str, body: String| async move {
+        let res = app
+            .oneshot(
+                Request::post(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&buf).unwrap()
+    };
+
+    let gen_body = |seed: Option<u64>| {
+        let mut b = serde_json::json!({ "distribution": { "name": "uniform" }, "n": 50 });
+        if let Some(s) = seed {
+            b["seed"] = s.into();
+        }
+        b.to_string()
+    };
+    let out = post(seeded.clone(), "/api/v1/stats/generate", gen_body(None)).await;
+    assert_eq!(out["seed"], 4);
+    let explicit = post(make_app(), "/api/v1/stats/generate", gen_body(Some(4))).await;
+    assert_eq!(out["values"], explicit["values"]);
+    // a request's own seed wins
+    let out = post(seeded.clone(), "/api/v1/stats/generate", gen_body(Some(3))).await;
+    assert_eq!(out["seed"], 3);
+
+    let res = seeded
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/jobs")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"kind":"bootstrap","values":[1,2,3,4],"resamples":10}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    let location = res.headers()["location"].to_str().unwrap().to_string();
+    let get = |uri: String| Request::get(uri).body(Body::empty()).unwrap();
+    let mut status = serde_json::Value::Null;
+    for _ in 0..200 {
+        let res = seeded.clone().oneshot(get(location.clone())).await.unwrap();
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        status = serde_json::from_slice(&body).unwrap();
+        if status["status"] == "succeeded" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+    assert_eq!(status["status"], "succeeded");
+    let res = seeded
+        .clone()
+        .oneshot(get(format!("{location}/result")))
+        .await
+        .unwrap();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let result: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(result["seed"], 4);
+
+    let csv: String = std::iter::once("x\n".to_string())
+        .chain((0..500).map(|i| format!("{i}\n")))
+        .collect();
+    let res = seeded
+        .oneshot(
+            Request::post("/api/v1/describe-csv?sample=20")
+                .header("content-type", "text/csv")
+                .body(Body::from(csv))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let buf = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let out: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+    assert_eq!(out["sample"]["seed"], 4);
+}
+
+// ========== job webhooks ==========
+
+#[cfg(feature = "webhooks")]
+#[tokio::test]
+async fn finished_jobs_post_a_signed_webhook() {
+    use axum::{body::Bytes, http::HeaderMap, routing::post};
+    use stats_rs::config::{ServiceConfig, WebhookConfig};
+    use tokio::sync::mpsc;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<(HeaderMap, Bytes)>();
+    let receiver = axum::Router::new().route(
+        "/hook",
+        post(move |headers: HeaderMap, body: Bytes| async move {
+            tx.send((headers, body)).unwrap();
+            StatusCode::NO_CONTENT
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+    let app = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            webhooks: WebhookConfig {
+                secret: Some("whsec".into()),
+                allowlist: vec!["127.0.0.1".into()],
+                allow_private: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+    let submit = |url: &str| {
+        Request::post("/api/v1/jobs")
+            .header("content-type", "application/json")
+            .header("webhook-url", url)
+            .body(Body::from(
+                r#"{"kind": "permutation", "x": [1, 2, 3], "y": [4, 5, 6], "seed": 1}"#,
+            ))
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(submit(&format!("http://{addr}/hook")))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+
+    let (headers, body) = tokio::time::timeout(std::time::Duration::from_secs(10), rx.recv())
+        .await
+        .expect("webhook delivered")
+        .unwrap();
+    let ts: u64 = headers["webhook-timestamp"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(
+        headers["webhook-signature"],
+        stats_rs::webhooks::signature("whsec", ts, &body).as_str()
+    );
+    let event: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(headers["webhook-id"], event["id"].as_str().unwrap());
+    assert_eq!(event["status"], "succeeded");
+    assert_eq!(event["result"]["seed"], 1);
+
+    let res = app
+        .oneshot(submit("http://elsewhere.example.com/hook"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+// ========== artifacts ==========
+
+#[tokio::test]
+async fn large_results_are_served_as_artifacts() {
+    use stats_rs::{artifacts::ArtifactStore, jobs::JobRegistry};
+
+    let artifacts = ArtifactStore::default();
+    let app = build_app(Arc::new(AppState {
+        jobs: JobRegistry::new(1).with_artifacts(artifacts.clone(), 64),
+        artifacts,
+        ..Default::default()
+    }));
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+    let json = |res: axum::response::Response| async move {
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/jobs")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({
+                        "kind": "corr_matrix",
+                        "series": [[1, 2, 3, 4], [2, 4, 6, 9], [4, 3, 2, 1]]
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let location = res.headers()["location"].to_str().unwrap().to_string();
+    let mut status = serde_json::Value::Null;
+    for _ in 0..200 {
+        status = json(app.clone().oneshot(get(&location)).await.unwrap()).await;
+        if status["status"] == "succeeded" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+    let id = status["artifact"].as_str().expect("result over 64 bytes");
+
+    let res = app
+        .clone()
+        .oneshot(get(&format!("/api/v1/artifacts/{id}")))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["content-type"], "application/json");
+    let stored = json(res).await;
+    assert_eq!(stored["size"], 3);
+    // The job result reads the artifact back
+    let res = app
+        .clone()
+        .oneshot(get(&format!("{location}/result")))
+        .await
+        .unwrap();
+    assert_eq!(json(res).await, stored);
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/api/v1/report?format=markdown&store=true")
+                .header("content-type", "text/csv")
+                .body(Body::from("a,b\n1,2\n3,5\n4,4\n"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let location = res.headers()["location"].to_str().unwrap().to_string();
+    let meta = json(res).await;
+    assert_eq!(
+        location,
+        format!("/api/v1/artifacts/{}", meta["id"].as_str().unwrap())
+    );
+    let res = app.clone().oneshot(get(&location)).await.unwrap();
+    assert_eq!(
+        res.headers()["content-type"],
+        "text/markdown; charset=utf-8"
+    );
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body.len() as u64, meta["size"].as_u64().unwrap());
+
+    let res = app
+        .oneshot(get(
+            "/api/v1/artifacts/art_00000000000000000000000000000000",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+// ========== tenants ==========
+#[tokio::test]
+async fn tenants_see_only_their_own_jobs_within_quota() {
+    use stats_rs::{
+        config::{ServiceConfig, TenantConfig},
+        jobs::JobRegistry,
+        tenant::Tenant,
+        types::JobKind,
+    };
+
+    let state = Arc::new(AppState {
+        config: ServiceConfig {
+            tenants: TenantConfig {
+                header: Some("x-tenant-id".into()),
+                max_jobs: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        jobs: JobRegistry::new(1),
+        ..Default::default()
+    });
+    let app = build_app(state.clone());
+    let call = |method: &str, uri: &str, tenant: &str, body: Option<serde_json::Value>| {
+        let mut req = Request::builder().method(method).uri(uri);
+        if !tenant.is_empty() {
+            req = req.header("x-tenant-id", tenant);
+        }
+        match body {
+            Some(b) => req
+                .header("content-type", "application/json")
+                .body(Body::from(b.to_string())),
+            None => req.body(Body::empty()),
+        }
+        .unwrap()
+    };
+    let job = serde_json::json!({"kind": "bootstrap", "values": [1, 2, 3, 4], "resamples": 10});
+
+    let res = app
+        .clone()
+        .oneshot(call("POST", "/api/v1/jobs", "team-a", Some(job.clone())))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    let location = res.headers()["location"].to_str().unwrap().to_string();
+    for _ in 0..200 {
+        let res = app
+            .clone()
+            .oneshot(call("GET", &format!("{location}/result"), "team-a", None))
+            .await
+            .unwrap();
+        if res.status() == StatusCode::OK {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+    for other in ["team-b", ""] {
+        let res = app
+            .clone()
+            .oneshot(call("GET", &location, other, None))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND, "tenant {other:?}");
+    }
+
+    // team-a's only job slot is taken until the blocker finishes
+    let (tx, rx) = std::sync::mpsc::channel::<()>();
+    let blocker = state.jobs.submit(
+        &Tenant::new("team-a").unwrap(),
+        JobKind::CorrMatrix,
+        move |_| {
+            rx.recv().ok();
+            Ok(serde_json::Value::Null)
+        },
+    );
+    let res = app
+        .clone()
+        .oneshot(call("POST", "/api/v1/jobs", "team-a", Some(job.clone())))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let err: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(err["code"], "quota_exceeded");
+    let res = app
+        .clone()
+        .oneshot(call("POST", "/api/v1/jobs", "team-b", Some(job)))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    tx.send(()).unwrap();
+    blocker.finished().await;
+
+    let res = app
+        .oneshot(call("GET", "/api/v1/datasets", "../x", None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn tenant_api_keys_are_required() {
+    use stats_rs::config::{ServiceConfig, TenantConfig};
+
+    let app = build_app(Arc::new(AppState {
+        config: ServiceConfig {
+            tenants: TenantConfig {
+                api_keys: [("k-a".to_string(), "team-a".to_string())].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+    let get = |uri: &str, key: Option<&str>| {
+        let mut req = Request::get(uri);
+        if let Some(k) = key {
+            req = req.header("x-api-key", k);
+        }
+        req.body(Body::empty()).unwrap()
+    };
+
+    for key in [None, Some("nope")] {
+        let res = app
+            .clone()
+            .oneshot(get("/api/v1/datasets", key))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED, "{key:?}");
+    }
+    let res = app
+        .clone()
+        .oneshot(get("/api/v1/datasets", Some("k-a")))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    // Probes need no key
+    let res = app.oneshot(get("/api/v1/health", None)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+// ========== canonical edge cases ==========
+#[tokio::test]
+async fn one_value_has_no_std_on_any_path() {
+    // Buffered, streaming and summary paths share one definition: the sample
+    // standard deviation of fewer than two values is undefined (`null`), never 0
+    let app = make_app();
+    let post = |uri: &str, content_type: &str, body: &str| {
+        Request::post(uri)
+            .header("content-type", content_type)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let cases = [
+        (
+            post("/api/v1/describe", "application/json", "[5]"),
+            "std_dev",
+        ),
+        (
+            post("/api/v1/describe-csv", "text/csv", "v\n5\n"),
+            "std_dev",
+        ),
+        (
+            post(
+                "/api/v1/stats/summary",
+                "application/json",
+                r#"{"values": [5]}"#,
+            ),
+            "std",
+        ),
+    ];
+    for (req, field) in cases {
+        let uri = req.uri().to_string();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK, "{uri}");
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(v["count"], 1, "{uri}");
+        assert!(v[field].is_null(), "{uri}: {field} = {}", v[field]);
+    }
 }